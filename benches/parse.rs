@@ -0,0 +1,60 @@
+//! 比较`rdb::parse_file`与`rdb::parse_mmap`解析大RDB文件的速度
+//!
+//! `cargo bench --features mmap`，key的个数可通过环境变量`BENCH_RDB_KEYS`调整，默认生成约130MB的RDB
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use redis_event::{rdb, NoOpEventHandler};
+use tempdir::TempDir;
+
+const DEFAULT_KEYS: usize = 1_000_000;
+const VALUE_SIZE: usize = 100;
+
+// 生成只包含String的RDB，校验和为0(即rdbchecksum no)
+fn generate_rdb(path: &Path, keys: usize) -> Result<()> {
+    let mut output = BufWriter::new(File::create(path)?);
+    output.write_all(b"REDIS0009")?;
+    // SELECTDB 0
+    output.write_all(&[0xFE, 0x00])?;
+    let value = vec![b'v'; VALUE_SIZE];
+    for i in 0..keys {
+        let key = format!("key:{:010}", i);
+        // RDB_TYPE_STRING，key与value的长度均小于16384，使用14位的长度编码
+        output.write_all(&[0x00, key.len() as u8])?;
+        output.write_all(key.as_bytes())?;
+        output.write_all(&[0x40 | (VALUE_SIZE >> 8) as u8, VALUE_SIZE as u8])?;
+        output.write_all(&value)?;
+    }
+    // EOF以及为0的校验和
+    output.write_all(&[0xFF])?;
+    output.write_all(&[0; 8])?;
+    output.flush()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let keys = match env::var("BENCH_RDB_KEYS") {
+        Ok(keys) => keys.parse().expect("BENCH_RDB_KEYS"),
+        Err(_) => DEFAULT_KEYS,
+    };
+    let dir = TempDir::new("redis-event-bench").unwrap();
+    let path = dir.path().join("dump.rdb");
+    generate_rdb(&path, keys).unwrap();
+    let size = path.metadata().unwrap().len();
+
+    let mut group = c.benchmark_group("parse_rdb");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(size));
+    group.bench_function("parse_file", |b| {
+        b.iter(|| rdb::parse_file(&path, &mut NoOpEventHandler {}).unwrap())
+    });
+    group.bench_function("parse_mmap", |b| {
+        b.iter(|| rdb::parse_mmap(&path, &mut NoOpEventHandler {}).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
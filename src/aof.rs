@@ -0,0 +1,445 @@
+/*!
+本地AOF文件的解析
+
+支持以`aof-use-rdb-preamble yes`生成的AOF文件，此时文件以RDB格式的数据开头(以`REDIS`开头)，其中的每条数据对应一个`Event::RDB`事件，
+其后的每条命令与复制过程中一样，对应一个`Event::AOF`事件。
+
+Redis 7.0之后的multi-part AOF由一个base文件和若干incr文件组成，并由`appendonly.aof.manifest`描述，
+可以使用[`parse_manifest`]按顺序解析其中所有的文件。
+
+开启了`aof-timestamp-enabled`的AOF文件中的`#TS:<unix-time>`注释，将作为`AofPosition::timestamp`随其后的命令一同交给`EventHandler::handle_aof`。
+通过`Config::aof_backup_path`保存的复制流中带有`#OFFSET:<offset>`注释，解析时据此推算出每条命令的`AofPosition::offset`。
+
+与Redis部署在同一台机器上时，还可以使用[`follow_file`]像`tail -f`一样持续解析AOF文件中新追加的命令，而无需建立复制连接。
+
+反过来，[`writer::AOFWriter`]可以将`Event::AOF`事件重新写为AOF文件，用于归档复制流中的写命令。
+
+```no_run
+use redis_event::{aof, Event, EventHandler};
+
+struct CommandCounter {
+    commands: usize,
+}
+
+impl EventHandler for CommandCounter {
+    fn handle(&mut self, event: Event) {
+        if let Event::AOF(_) = event {
+            self.commands += 1;
+        }
+    }
+}
+
+let mut handler = CommandCounter { commands: 0 };
+aof::parse_file("appendonly.aof", &mut handler).unwrap();
+println!("{}条命令", handler.commands);
+```
+
+[`parse_manifest`]: fn.parse_manifest.html
+[`follow_file`]: fn.follow_file.html
+[`writer::AOFWriter`]: writer/struct.AOFWriter.html
+*/
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Error, ErrorKind, Read, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use byteorder::ReadBytesExt;
+#[cfg(not(feature = "tracing"))]
+use log::warn;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::cmd::CommandParsers;
+use crate::compress;
+use crate::io::CountReader;
+use crate::listener::{check_handler, PositionedHandler};
+use crate::rdb::{self, ParseInfo};
+use crate::resp::{Resp, RespDecode, LF, STAR};
+use crate::{cmd, AofPosition, EventHandler};
+
+pub mod writer;
+
+// RDB preamble的开头
+const RDB_MAGIC: &[u8] = b"REDIS";
+// Redis 7.0之后AOF中的注释以'#'开头，如开启`aof-timestamp-enabled`之后写入的`#TS:<unix-time>`
+const ANNOTATION: u8 = b'#';
+pub(crate) const TIMESTAMP_ANNOTATION: &str = "TS:";
+pub(crate) const OFFSET_ANNOTATION: &str = "OFFSET:";
+
+// 命令的序号、最近一次`#TS:`注释中的时间以及根据`#OFFSET:`注释推算出的offset，解析multi-part AOF时在各个文件之间延续
+#[derive(Default)]
+struct ReadState {
+    sequence: u64,
+    timestamp: Option<SystemTime>,
+    offset: Option<i64>,
+}
+
+/// 解析本地的AOF文件，返回RDB preamble的解析结果，文件不以RDB preamble开头时为None
+///
+/// 与Redis的`aof-load-truncated yes`一样，末尾不完整的命令将被忽略；开启`compression-gzip`或者`compression-zstd` feature时，
+/// 也可以直接解析压缩后的文件，见[`compress`]
+///
+/// [`compress`]: ../compress/index.html
+pub fn parse_file<P: AsRef<Path>>(path: P, event_handler: &mut dyn EventHandler) -> Result<Option<ParseInfo>> {
+    parse_reader(File::open(path)?, event_handler)
+}
+
+/// 从任意输入流中解析AOF，如标准输入、管道或者内存中的数据，见[`parse_file`]
+///
+/// 输入将被缓冲，并根据开头的magic number自动解压，无需再包装`BufReader`
+///
+/// [`parse_file`]: fn.parse_file.html
+pub fn parse_reader<R: Read>(input: R, event_handler: &mut dyn EventHandler) -> Result<Option<ParseInfo>> {
+    parse_reader_with_parsers(input, &CommandParsers::new(), event_handler)
+}
+
+/// 与[`parse_reader`]相同，其中注册了解析器的命令交给对应的`CommandParser`解析，以`Command::Module`的形式产生
+///
+/// [`parse_reader`]: fn.parse_reader.html
+pub fn parse_reader_with_parsers<R: Read>(
+    input: R, parsers: &CommandParsers, event_handler: &mut dyn EventHandler,
+) -> Result<Option<ParseInfo>> {
+    let mut input = compress::decompress(input)?;
+    let mut state = ReadState::default();
+    let (info, truncated) = read_aof(&mut input, parsers, event_handler, &mut state)?;
+    if truncated > 0 {
+        warn!("AOF末尾的命令不完整, 已忽略{}字节", truncated);
+    }
+    Ok(info)
+}
+
+/// 解析AOF文件之后继续等待并解析新追加到文件中的命令，每隔`poll_interval`检查一次文件是否有新的数据
+///
+/// 将`running`置为false之后返回，返回值与[`parse_file`]相同。AOF重写(`BGREWRITEAOF`)会以新文件替换原文件，此后原文件不会再有新的命令，
+/// 需要重新调用此方法；文件被截断时返回错误
+///
+/// [`parse_file`]: fn.parse_file.html
+pub fn follow_file<P: AsRef<Path>>(
+    path: P, event_handler: &mut dyn EventHandler, running: Arc<AtomicBool>, poll_interval: Duration,
+) -> Result<Option<ParseInfo>> {
+    let file = File::open(path)?;
+    let mut reader = FollowReader {
+        file,
+        position: 0,
+        running,
+        poll_interval,
+    };
+    // 读取到不完整的命令时FollowReader会等待其余的数据，因此只有停止时才可能出现不完整的命令
+    let (info, _) = read_aof(
+        &mut reader,
+        &CommandParsers::new(),
+        event_handler,
+        &mut ReadState::default(),
+    )?;
+    Ok(info)
+}
+
+/// 按顺序解析multi-part AOF中的base文件以及所有的incr文件，history文件将被忽略，返回base文件中RDB数据的解析结果
+///
+/// 与Redis加载AOF时一样，只有最后一个文件末尾不完整的命令会被忽略，其他文件不完整时返回错误
+pub fn parse_manifest<P: AsRef<Path>>(path: P, event_handler: &mut dyn EventHandler) -> Result<Option<ParseInfo>> {
+    let path = path.as_ref();
+    let manifest = read_manifest(path)?;
+    // manifest中的文件名相对于manifest所在的目录
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let files: Vec<&AofFileInfo> = manifest.base.iter().chain(manifest.incrs.iter()).collect();
+    let mut info = None;
+    let mut state = ReadState::default();
+    let parsers = CommandParsers::new();
+    for (i, file) in files.iter().enumerate() {
+        let mut input = BufReader::new(File::open(dir.join(&file.name))?);
+        let (file_info, truncated) = read_aof(&mut input, &parsers, event_handler, &mut state)?;
+        if file_info.is_some() {
+            info = file_info;
+        }
+        if truncated > 0 {
+            if i + 1 < files.len() {
+                let message = format!("AOF文件{}末尾的命令不完整", file.name);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+            warn!("AOF文件{}末尾的命令不完整, 已忽略{}字节", file.name, truncated);
+        }
+    }
+    Ok(info)
+}
+
+/// 读取multi-part AOF的manifest文件
+pub fn read_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest> {
+    let content = fs::read_to_string(path)?;
+    Manifest::parse(&content)
+}
+
+/// multi-part AOF中文件的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AofFileType {
+    /// base文件，即AOF重写时生成的快照，可能是RDB格式或者AOF格式
+    Base,
+    /// 重写之前的base文件和incr文件，等待删除，加载时不再使用
+    History,
+    /// 上一次重写之后追加的命令
+    Incr,
+}
+
+/// manifest中记录的一个AOF文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AofFileInfo {
+    /// 文件名，相对于manifest所在的目录
+    pub name: String,
+    pub seq: u64,
+    pub file_type: AofFileType,
+}
+
+/// multi-part AOF的manifest，即`appendonly.aof.manifest`的内容
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub base: Option<AofFileInfo>,
+    /// 按照加载顺序排列的incr文件
+    pub incrs: Vec<AofFileInfo>,
+    pub history: Vec<AofFileInfo>,
+}
+
+impl Manifest {
+    /// 解析manifest的内容，每行的格式为`file <name> seq <seq> type <b|h|i>`
+    pub fn parse(content: &str) -> Result<Manifest> {
+        let mut manifest = Manifest::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let args = split_args(line)?;
+            if args.len() < 6 || args.len() % 2 != 0 {
+                return Err(invalid_manifest(line));
+            }
+            let mut name = None;
+            let mut seq = None;
+            let mut file_type = None;
+            for pair in args.chunks(2) {
+                match pair[0].as_str() {
+                    "file" => name = Some(pair[1].clone()),
+                    "seq" => seq = pair[1].parse::<u64>().ok(),
+                    "type" => {
+                        file_type = match pair[1].as_str() {
+                            "b" => Some(AofFileType::Base),
+                            "h" => Some(AofFileType::History),
+                            "i" => Some(AofFileType::Incr),
+                            _ => None,
+                        }
+                    }
+                    // 忽略未知的字段，与Redis的行为一致
+                    _ => {}
+                }
+            }
+            let file = match (name, seq, file_type) {
+                (Some(name), Some(seq), Some(file_type)) => AofFileInfo { name, seq, file_type },
+                _ => return Err(invalid_manifest(line)),
+            };
+            match file.file_type {
+                AofFileType::Base => {
+                    if manifest.base.is_some() {
+                        return Err(Error::new(ErrorKind::InvalidData, "manifest中存在多个base文件"));
+                    }
+                    manifest.base = Some(file);
+                }
+                AofFileType::History => manifest.history.push(file),
+                AofFileType::Incr => {
+                    if let Some(last) = manifest.incrs.last() {
+                        if file.seq <= last.seq {
+                            let message = format!("manifest中incr文件的seq不是递增的: {}", line);
+                            return Err(Error::new(ErrorKind::InvalidData, message));
+                        }
+                    }
+                    manifest.incrs.push(file);
+                }
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn invalid_manifest(line: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("无效的manifest: {}", line))
+}
+
+// 按照Redis中sdssplitargs的规则切分manifest中的一行，文件名中包含空格等字符时将被双引号包围
+fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+        let quote = match chars.peek() {
+            None => return Ok(args),
+            Some(&c) if c == '"' || c == '\'' => {
+                chars.next();
+                Some(c)
+            }
+            Some(_) => None,
+        };
+        let mut arg = String::new();
+        loop {
+            match (chars.next(), quote) {
+                (None, None) => break,
+                (None, Some(_)) => return Err(invalid_manifest(line)),
+                (Some(c), None) if c.is_whitespace() => break,
+                (Some(c), Some(q)) if c == q => {
+                    // 引号之后必须是空白或者行尾
+                    if matches!(chars.peek(), Some(c) if !c.is_whitespace()) {
+                        return Err(invalid_manifest(line));
+                    }
+                    break;
+                }
+                (Some('\\'), Some('"')) => match chars.next() {
+                    Some('n') => arg.push('\n'),
+                    Some('r') => arg.push('\r'),
+                    Some('t') => arg.push('\t'),
+                    Some('b') => arg.push('\u{8}'),
+                    Some('a') => arg.push('\u{7}'),
+                    Some('x') => {
+                        let hex: String = chars.by_ref().take(2).collect();
+                        match u8::from_str_radix(&hex, 16) {
+                            Ok(b) => arg.push(b as char),
+                            Err(_) => return Err(invalid_manifest(line)),
+                        }
+                    }
+                    Some(c) => arg.push(c),
+                    None => return Err(invalid_manifest(line)),
+                },
+                (Some('\\'), Some('\'')) if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    arg.push('\'');
+                }
+                (Some(c), _) => arg.push(c),
+            }
+        }
+        args.push(arg);
+    }
+}
+
+// 读取到文件末尾时等待新的数据，直到running被置为false
+struct FollowReader {
+    file: File,
+    position: u64,
+    running: Arc<AtomicBool>,
+    poll_interval: Duration,
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.running.load(Ordering::Relaxed) {
+            let len = self.file.read(buf)?;
+            if len > 0 || buf.is_empty() {
+                self.position += len as u64;
+                return Ok(len);
+            }
+            if self.file.metadata()?.len() < self.position {
+                return Err(Error::new(ErrorKind::InvalidData, "AOF文件被截断"));
+            }
+            thread::sleep(self.poll_interval);
+        }
+        Ok(0)
+    }
+}
+
+// 解析可能以RDB preamble开头的AOF，返回RDB preamble的解析结果以及末尾不完整的命令所占的字节数
+fn read_aof(
+    input: &mut dyn Read, parsers: &CommandParsers, event_handler: &mut dyn EventHandler, state: &mut ReadState,
+) -> Result<(Option<ParseInfo>, i64)> {
+    let mut magic = Vec::with_capacity(RDB_MAGIC.len());
+    input.take(RDB_MAGIC.len() as u64).read_to_end(&mut magic)?;
+    let is_preamble = magic == RDB_MAGIC;
+    // 已读取的开头需要交还给后续的解析
+    let mut input = Cursor::new(magic).chain(input);
+    let info = if is_preamble {
+        let info = rdb::parse_unbuffered(&mut input, event_handler)?;
+        check_handler(event_handler)?;
+        Some(info)
+    } else {
+        None
+    };
+    let truncated = read_commands(&mut input, parsers, event_handler, state)?;
+    Ok((info, truncated))
+}
+
+fn read_commands(
+    input: &mut dyn Read, parsers: &CommandParsers, event_handler: &mut dyn EventHandler, state: &mut ReadState,
+) -> Result<i64> {
+    let mut reader = CountReader::new(input);
+    loop {
+        reader.mark();
+        match read_command(&mut reader, state) {
+            Ok(Some(command)) => {
+                let size = reader.reset()?;
+                state.sequence += 1;
+                if let Some(offset) = state.offset.as_mut() {
+                    *offset += size;
+                }
+                let mut handler = PositionedHandler {
+                    inner: event_handler,
+                    args: &command,
+                    position: AofPosition {
+                        offset: state.offset.unwrap_or(-1),
+                        sequence: state.sequence,
+                        timestamp: state.timestamp,
+                    },
+                };
+                cmd::parse(&command, parsers, &mut handler);
+                check_handler(event_handler)?;
+            }
+            Ok(None) => {
+                reader.reset()?;
+            }
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                return reader.reset();
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// 读取一条命令，读取到的是注释时返回None，其中`#TS:`注释的时间以及`#OFFSET:`注释的offset将被记录到state中
+fn read_command(reader: &mut CountReader, state: &mut ReadState) -> Result<Option<Vec<Vec<u8>>>> {
+    loop {
+        match reader.read_u8()? {
+            LF => continue,
+            ANNOTATION => {
+                let line = reader.decode_string()?;
+                if let Some(time) = line.strip_prefix(TIMESTAMP_ANNOTATION) {
+                    match time.parse::<u64>() {
+                        Ok(secs) => state.timestamp = Some(UNIX_EPOCH + Duration::from_secs(secs)),
+                        Err(_) => warn!("无效的AOF时间注释: #{}", line),
+                    }
+                } else if let Some(offset) = line.strip_prefix(OFFSET_ANNOTATION) {
+                    match offset.parse::<i64>() {
+                        Ok(offset) => state.offset = Some(offset),
+                        Err(_) => warn!("无效的AOF offset注释: #{}", line),
+                    }
+                }
+                return Ok(None);
+            }
+            STAR => {
+                let array = match reader.decode_array()? {
+                    Resp::Array(array) => array,
+                    _ => unreachable!(),
+                };
+                let mut command = Vec::with_capacity(array.len());
+                for arg in array {
+                    if let Resp::BulkBytes(bytes) = arg {
+                        command.push(bytes);
+                    } else {
+                        return Err(Error::new(ErrorKind::InvalidData, "AOF命令的参数不是bulk string"));
+                    }
+                }
+                return Ok(Some(command));
+            }
+            b => {
+                let message = format!("AOF中出现了无效的数据: {:#04x}", b);
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+        }
+    }
+}
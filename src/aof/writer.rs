@@ -0,0 +1,202 @@
+/*!
+将`Event::AOF`事件重新写为AOF文件
+
+[`AOFWriter`]本身是一个`EventHandler`，交给`Listener`之后，可以将复制流中的写命令持续归档为Redis可加载的AOF文件，
+配合`Config::rdb_backup_path`保存的RDB，即可在任意时刻重建数据。归档的文件可以使用`aof::parse_file`重新解析。
+
+```no_run
+use redis_event::aof::writer::AOFWriter;
+
+let mut writer = AOFWriter::new("archive.aof").unwrap();
+writer.with_timestamp_annotation(true);
+writer.with_max_size(256 * 1024 * 1024);
+// 交给Listener，或者解析已有的AOF文件
+redis_event::aof::parse_file("appendonly.aof", &mut writer).unwrap();
+writer.finish().unwrap();
+```
+
+[`AOFWriter`]: struct.AOFWriter.html
+*/
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Error, Result, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::aof::TIMESTAMP_ANNOTATION;
+use crate::cmd::Command;
+use crate::{io, resp};
+use crate::{AofPosition, Event, EventHandler};
+
+/// 将`Event::AOF`事件以RESP格式写为AOF文件的`EventHandler`，`Event::RDB`事件将被忽略
+///
+/// 通过`EventHandler::handle_aof_raw`收到的命令将原样写入；直接调用`handle`、`handle_aof`时，
+/// 命令按照解析后的结果重新生成，解析时未保留的参数无法写入，见`cmd`模块中各命令的定义。
+/// 文件以追加的方式打开，重启之后继续写入同一个文件。
+///
+/// 设置了[`with_max_size`]时，文件超过此大小之后将被重命名为`<path>.1`、`<path>.2`…(数字越大越新)，
+/// 并重新创建`<path>`继续写入；MULTI与EXEC之间不会进行切换，新文件以当前db的`SELECT`开头，因此每个文件都可以独立加载。
+///
+/// `EventHandler::handle`无法返回错误，写入过程中的第一个错误将被记录下来，之后的事件将被忽略，
+/// 可通过[`flush`]或者[`finish`]得到此错误
+///
+/// [`with_max_size`]: #method.with_max_size
+/// [`flush`]: #method.flush
+/// [`finish`]: #method.finish
+pub struct AOFWriter {
+    path: PathBuf,
+    output: BufWriter<File>,
+    // 当前文件的大小
+    size: u64,
+    max_size: Option<u64>,
+    is_timestamp_enabled: bool,
+    // 当前文件中最近一次写入的`#TS:`注释中的时间
+    timestamp: Option<u64>,
+    // 最近一次SELECT的db
+    db: Option<i32>,
+    is_in_multi: bool,
+    // 最近一次切换时所使用的序号
+    index: Option<u64>,
+    error: Option<Error>,
+}
+
+impl AOFWriter {
+    /// 以追加的方式打开或者创建`path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<AOFWriter> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(AOFWriter {
+            path,
+            output: BufWriter::new(file),
+            size,
+            max_size: None,
+            is_timestamp_enabled: false,
+            timestamp: None,
+            db: None,
+            is_in_multi: false,
+            index: None,
+            error: None,
+        })
+    }
+
+    /// 是否与开启了`aof-timestamp-enabled`的Redis一样，在命令之前写入`#TS:<unix-time>`注释，默认为false
+    ///
+    /// 时间为`AofPosition::timestamp`，没有时为写入时的系统时间，只在与上一次写入的时间不同时写入
+    pub fn with_timestamp_annotation(&mut self, enabled: bool) {
+        self.is_timestamp_enabled = enabled;
+    }
+
+    /// 文件超过此大小(字节)之后切换到新的文件，默认不切换
+    pub fn with_max_size(&mut self, max_size: u64) {
+        self.max_size = Some(max_size);
+    }
+
+    /// 返回写入过程中的错误，并将缓冲的数据写入文件
+    pub fn finish(mut self) -> Result<()> {
+        self.flush()
+    }
+
+    // `args`为原始命令，为None时根据`command`重新生成
+    fn write_command(
+        &mut self, command: &Command, args: Option<&[Vec<u8>]>, timestamp: Option<SystemTime>,
+    ) -> Result<()> {
+        match command {
+            Command::SELECT(select) => self.db = Some(select.db),
+            Command::MULTI => self.is_in_multi = true,
+            Command::EXEC => self.is_in_multi = false,
+            _ => {}
+        }
+        if self.is_timestamp_enabled {
+            let timestamp = timestamp.unwrap_or_else(SystemTime::now);
+            let timestamp = match timestamp.duration_since(UNIX_EPOCH) {
+                Ok(duration) => duration.as_secs(),
+                Err(_) => 0,
+            };
+            if self.timestamp != Some(timestamp) {
+                let annotation = format!("#{}{}\r\n", TIMESTAMP_ANNOTATION, timestamp);
+                self.write(annotation.as_bytes())?;
+                self.timestamp = Some(timestamp);
+            }
+        }
+        match args {
+            Some(args) => self.write_args(args)?,
+            None => self.write_args(&command.to_args())?,
+        }
+        match self.max_size {
+            Some(max_size) if self.size >= max_size && !self.is_in_multi => self.rotate(),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_args(&mut self, args: &[Vec<u8>]) -> Result<()> {
+        self.write(&resp::encode_command(args))
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.size += data.len() as u64;
+        self.output.write_all(data)
+    }
+
+    // 将当前文件重命名为`<path>.<n>`，n为已有的文件中最大的序号加一，之后重新创建`<path>`
+    fn rotate(&mut self) -> Result<()> {
+        self.output.flush()?;
+        let index = match self.index {
+            Some(index) => index + 1,
+            None => io::last_rotated_index(&self.path)? + 1,
+        };
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", index));
+        fs::rename(&self.path, rotated)?;
+        self.index = Some(index);
+        self.output = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        self.timestamp = None;
+        if let Some(db) = self.db {
+            self.write_args(&[b"SELECT".to_vec(), db.to_string().into_bytes()])?;
+        }
+        Ok(())
+    }
+}
+
+impl EventHandler for AOFWriter {
+    fn handle(&mut self, event: Event) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Event::AOF(command) = &event {
+            if let Err(err) = self.write_command(command, None, None) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Event::AOF(command) = &event {
+            if let Err(err) = self.write_command(command, None, position.timestamp) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Event::AOF(command) = &event {
+            if let Err(err) = self.write_command(command, Some(args), position.timestamp) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    /// 返回写入过程中的第一个错误
+    fn flush(&mut self) -> Result<()> {
+        match &self.error {
+            Some(err) => Err(Error::new(err.kind(), err.to_string())),
+            None => self.output.flush(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// 标准的base64编码，用于在JSON等文本格式中输出二进制数据
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// 以标准的base64编码(带`=`填充)
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_CHARS[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// 解码标准的base64编码，不是合法的编码时返回None
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub(crate) fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+    for (index, chunk) in encoded.chunks(4).enumerate() {
+        let is_last = index == encoded.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_CHARS.iter().position(|&b| b == c)?;
+            n = n << 6 | value as u32;
+        }
+        n <<= 6 * padding as u32;
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        bytes.extend_from_slice(&decoded[..3 - padding]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_base64, encode_base64};
+
+    #[test]
+    fn test_base64() {
+        let cases: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"\x00\xff\xfe\x01key", "AP/+AWtleQ=="),
+        ];
+        for (bytes, encoded) in cases.iter() {
+            assert_eq!(*encoded, encode_base64(bytes));
+            assert_eq!(Some(bytes.to_vec()), decode_base64(encoded));
+        }
+        assert_eq!(None, decode_base64("Zg="));
+        assert_eq!(None, decode_base64("Zg==Zg=="));
+        assert_eq!(None, decode_base64("Z==="));
+        assert_eq!(None, decode_base64("Zm9*"));
+    }
+}
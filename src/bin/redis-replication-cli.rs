@@ -0,0 +1,257 @@
+/*!
+以JSON Lines的格式输出`redis_event`解析得到的事件，用于快速查看库所看到的数据，需开启`cli` feature
+
+```text
+redis-replication-cli redis://:password@127.0.0.1:6379
+redis-replication-cli --rdb dump.rdb --key-pattern 'user:*'
+redis-cli --rdb - | redis-replication-cli --rdb -
+```
+
+每个事件输出为一行JSON，key、value等二进制数据按UTF-8输出，其中无法解码的字节输出为`\xNN`
+*/
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use redis_event::config::{Config, KeyFilter, ParseMode};
+use redis_event::export::json::JsonLinesWriter;
+use redis_event::rdb;
+use redis_event::{aof, listener, RedisListener};
+
+const USAGE: &str = "\
+用法:
+    redis-replication-cli [选项] [redis://[[user]:password@]host[:port][/db]]
+    redis-replication-cli [选项] --rdb <file>
+    redis-replication-cli [选项] --aof <file>
+
+以JSON Lines的格式输出监听到的事件，<file>为-时从标准输入读取；rediss://开头的URL将启用TLS
+
+连接选项:
+    -h, --host <host>           Redis的地址，默认为127.0.0.1
+    -p, --port <port>           Redis的端口，默认为6379
+        --user <user>           用户名
+    -a, --password <password>   密码
+        --tls                   启用TLS
+        --tls-insecure          信任无效的证书和域名
+        --repl-id <id>          从此replication id开始同步，默认为?
+        --repl-offset <offset>  从此replication offset开始同步，默认为-1
+        --no-rdb                跳过RDB，只输出之后的命令
+        --no-aof                RDB处理完毕之后退出
+
+过滤以及解析选项(仅对RDB有效):
+        --db <db>               只输出此db中的key，可指定多次
+        --key-prefix <prefix>   只输出以此为前缀的key，可指定多次
+        --key-pattern <pattern> 只输出与此glob模式匹配的key，可指定多次
+        --discard-expired       丢弃已过期的key
+        --skip-corrupt          跳过无法解析的key
+        --lenient               数据不符合规范时继续解析
+        --chunk-size <bytes>    String的值超过此长度时分块输出
+        --stats                 在RDB结尾输出统计信息
+
+        --help                  输出此帮助信息
+";
+
+// 数据来源
+enum Source {
+    Redis,
+    Rdb(String),
+    Aof(String),
+}
+
+fn main() {
+    let (source, config) = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let mut handler = JsonLinesWriter::new(Stdout::new());
+    let result = match source {
+        Source::Rdb(path) => {
+            open(&path).and_then(|input| rdb::parse_reader_with_config(input, &config, &mut handler).map(|_| ()))
+        }
+        Source::Aof(path) => open(&path).and_then(|input| aof::parse_reader(input, &mut handler).map(|_| ())),
+        Source::Redis => {
+            let mut builder = listener::Builder::new();
+            builder.with_config(config);
+            builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+            builder.with_event_handler(Box::new(handler));
+            builder.build().start()
+        }
+    };
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        Ok(Box::new(io::stdin()))
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<(Source, Config), String> {
+    let mut config = Config {
+        is_discard_rdb: false,
+        is_aof: true,
+        host: String::from("127.0.0.1"),
+        port: 6379,
+        username: String::new(),
+        password: String::new(),
+        repl_id: String::from("?"),
+        repl_offset: -1,
+        read_timeout: None,
+        write_timeout: None,
+        is_tls_enabled: false,
+        is_tls_insecure: false,
+        identity: None,
+        identity_passwd: None,
+        master_timeout: None,
+        aof_queue_size: None,
+        is_ack_after_handle: false,
+        max_full_resyncs: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        is_aggregate_collections: false,
+        db_filter: None,
+        key_filter: None,
+        rdb_backup_path: None,
+        aof_backup_path: None,
+        is_collect_stats: false,
+        max_handler_retries: None,
+    };
+    let mut source = Source::Redis;
+    let mut key_filters = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{}缺少参数", name));
+        match arg.as_str() {
+            "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            "-h" | "--host" => config.host = value(&arg)?,
+            "-p" | "--port" => config.port = parse_number(&arg, &value(&arg)?)?,
+            "--user" => config.username = value(&arg)?,
+            "-a" | "--password" => config.password = value(&arg)?,
+            "--tls" => config.is_tls_enabled = true,
+            "--tls-insecure" => config.is_tls_insecure = true,
+            "--repl-id" => config.repl_id = value(&arg)?,
+            "--repl-offset" => config.repl_offset = parse_number(&arg, &value(&arg)?)?,
+            "--no-rdb" => config.is_discard_rdb = true,
+            "--no-aof" => config.is_aof = false,
+            "--rdb" => source = Source::Rdb(value(&arg)?),
+            "--aof" => source = Source::Aof(value(&arg)?),
+            "--db" => {
+                let db = parse_number(&arg, &value(&arg)?)?;
+                config.db_filter.get_or_insert_with(Vec::new).push(db);
+            }
+            "--key-prefix" => key_filters.push(KeyFilter::Prefix(value(&arg)?.into_bytes())),
+            "--key-pattern" => key_filters.push(KeyFilter::Glob(value(&arg)?.into_bytes())),
+            "--discard-expired" => config.is_discard_expired_keys = true,
+            "--skip-corrupt" => config.is_skip_corrupt_keys = true,
+            "--lenient" => config.parse_mode = ParseMode::Lenient,
+            "--chunk-size" => config.string_chunk_size = Some(parse_number(&arg, &value(&arg)?)?),
+            "--stats" => config.is_collect_stats = true,
+            url if url.starts_with("redis://") || url.starts_with("rediss://") => parse_url(url, &mut config)?,
+            _ => return Err(format!("未知的参数: {}", arg)),
+        }
+    }
+    config.key_filter = match key_filters.len() {
+        0 => None,
+        1 => key_filters.pop(),
+        _ => Some(KeyFilter::Any(key_filters)),
+    };
+    Ok((source, config))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{}的值无效: {}", name, value))
+}
+
+// redis://[[user]:password@]host[:port][/db]，rediss://启用TLS
+fn parse_url(url: &str, config: &mut Config) -> Result<(), String> {
+    let (is_tls, rest) = match url.strip_prefix("rediss://") {
+        Some(rest) => (true, rest),
+        None => (false, &url["redis://".len()..]),
+    };
+    config.is_tls_enabled = is_tls;
+    let (authority, db) = match rest.find('/') {
+        Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+        None => (rest, None),
+    };
+    let address = match authority.rfind('@') {
+        Some(index) => {
+            let userinfo = &authority[..index];
+            match userinfo.find(':') {
+                Some(colon) => {
+                    config.username = userinfo[..colon].to_string();
+                    config.password = userinfo[colon + 1..].to_string();
+                }
+                None => config.username = userinfo.to_string(),
+            }
+            &authority[index + 1..]
+        }
+        None => authority,
+    };
+    // [::1]:6379形式的IPv6地址
+    let port_index = match address.rfind(']') {
+        Some(bracket) => address[bracket..].find(':').map(|index| bracket + index),
+        None => address.rfind(':'),
+    };
+    let host = match port_index {
+        Some(index) => {
+            config.port = parse_number("port", &address[index + 1..])?;
+            &address[..index]
+        }
+        None => address,
+    };
+    if !host.is_empty() {
+        config.host = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    }
+    if let Some(db) = db.filter(|db| !db.is_empty()) {
+        config.db_filter = Some(vec![parse_number("db", db)?]);
+    }
+    Ok(())
+}
+
+/// 标准输出，写入出错时直接退出
+struct Stdout {
+    output: BufWriter<io::Stdout>,
+}
+
+impl Stdout {
+    fn new() -> Stdout {
+        Stdout {
+            output: BufWriter::new(io::stdout()),
+        }
+    }
+
+    fn exit(err: io::Error) -> ! {
+        // 输出被关闭(如通过管道交给head)时直接退出
+        if err.kind() != ErrorKind::BrokenPipe {
+            eprintln!("error: {}", err);
+        }
+        process::exit(if err.kind() == ErrorKind::BrokenPipe { 0 } else { 1 });
+    }
+}
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf).or_else(|err| Stdout::exit(err))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush().or_else(|err| Stdout::exit(err))
+    }
+}
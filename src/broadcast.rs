@@ -0,0 +1,346 @@
+/*!
+将事件广播给多个消费者，每个消费者在有界的缓冲区上各自记录消费的位置
+
+与[`channel`]不同，[`BroadcastHandler`]产生的每个事件都会交给所有的[`Subscriber`]，缓冲区中保留最近的若干个事件:
+- 通过`Bus::subscribe`订阅的消费者落后太多时，缓冲区中最旧的事件将被覆盖，消费者随后收到`RecvError::Lagged`并从最旧的事件继续消费，
+  不会拖慢监听线程，适用于统计、分析等可以容忍丢失的消费者
+- 通过`Bus::subscribe_blocking`订阅的消费者不会丢失事件，缓冲区已满而其尚未消费最旧的事件时，`BroadcastHandler`将阻塞，
+  监听线程也随之暂停，适用于写入下游等不能丢失事件的消费者
+
+事件在消费者下一次接收时才被视为已处理完毕，开启`Config::is_ack_after_handle`时，`BroadcastHandler::flush`
+将等待所有阻塞式的消费者处理完已产生的事件，之后才确认offset；`Bus::subscribe`订阅的消费者不在等待之列。
+
+```no_run
+use std::thread;
+
+use redis_event::broadcast::{self, RecvError};
+use redis_event::config::Config;
+use redis_event::listener::Builder;
+use redis_event::{OwnedEvent, RedisListener};
+
+fn run(config: Config) -> std::io::Result<()> {
+    let (handler, bus) = broadcast::bus(4096);
+    let replica = bus.subscribe_blocking();
+    thread::spawn(move || {
+        for _event in replica {
+            // 写入下游
+        }
+    });
+    let mut analytics = bus.subscribe();
+    thread::spawn(move || loop {
+        match analytics.recv() {
+            Ok(event) => {
+                if let OwnedEvent::AOF(cmd) = &*event {
+                    println!("{}", cmd.name());
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => println!("skipped {} events", skipped),
+            Err(_) => break,
+        }
+    });
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(handler));
+    builder.build().start()
+}
+```
+
+[`channel`]: ../channel/index.html
+[`BroadcastHandler`]: struct.BroadcastHandler.html
+[`Subscriber`]: struct.Subscriber.html
+*/
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{Event, EventHandler, OwnedEvent};
+
+/// 创建一对相互关联的`BroadcastHandler`以及`Bus`
+///
+/// 方法参数:
+///
+/// * `capacity`: 缓冲区中最多保留的事件个数，至少为1
+pub fn bus(capacity: usize) -> (BroadcastHandler, Bus) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            events: VecDeque::with_capacity(capacity.max(1)),
+            head: 0,
+            capacity: capacity.max(1),
+            blocking: HashMap::new(),
+            handled: HashMap::new(),
+            next_id: 0,
+            is_closed: false,
+        }),
+        changed: Condvar::new(),
+    });
+    let handler = BroadcastHandler {
+        shared: Arc::clone(&shared),
+    };
+    (handler, Bus { shared })
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    events: VecDeque<Arc<OwnedEvent>>,
+    // events中第一个事件的序号
+    head: u64,
+    capacity: usize,
+    // 阻塞式消费者的id及其下一个要消费的事件的序号
+    blocking: HashMap<u64, u64>,
+    // 阻塞式消费者的id及其已处理完毕的事件个数，即最近一次开始接收时的位置
+    handled: HashMap<u64, u64>,
+    next_id: u64,
+    is_closed: bool,
+}
+
+impl State {
+    fn tail(&self) -> u64 {
+        self.head + self.events.len() as u64
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 将事件复制为`OwnedEvent`之后放入缓冲区，交给所有的`Subscriber`
+///
+/// 缓冲区已满时覆盖最旧的事件；若有阻塞式的消费者尚未消费最旧的事件，则阻塞直到其消费或者被drop。
+/// 被drop(如监听结束)之后，各个消费者消费完缓冲区中剩余的事件时将收到`RecvError::Closed`
+///
+/// `flush`阻塞直到每个阻塞式的消费者都处理完已产生的事件(即在接收了这些事件之后再次调用接收方法)或者被drop，
+/// 没有阻塞式的消费者时直接返回
+pub struct BroadcastHandler {
+    shared: Arc<Shared>,
+}
+
+impl BroadcastHandler {
+    /// 获取可以用于订阅事件的`Bus`
+    pub fn bus(&self) -> Bus {
+        Bus {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl EventHandler for BroadcastHandler {
+    fn handle(&mut self, event: Event) {
+        let event = Arc::new(event.to_owned());
+        let mut state = self.shared.lock();
+        while state.events.len() >= state.capacity {
+            let head = state.head;
+            if state.blocking.values().any(|&position| position <= head) {
+                state = self
+                    .shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            } else {
+                state.events.pop_front();
+                state.head += 1;
+            }
+        }
+        state.events.push_back(event);
+        self.shared.changed.notify_all();
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.shared.lock();
+        let tail = state.tail();
+        while state.handled.values().any(|&handled| handled < tail) {
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BroadcastHandler {
+    fn drop(&mut self) {
+        self.shared.lock().is_closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// 用于订阅`BroadcastHandler`所产生的事件，可以clone之后交给其他线程
+#[derive(Clone)]
+pub struct Bus {
+    shared: Arc<Shared>,
+}
+
+impl Bus {
+    /// 订阅事件，从缓冲区中最旧的事件开始消费，落后太多时将丢失事件，见`RecvError::Lagged`
+    pub fn subscribe(&self) -> Subscriber {
+        let state = self.shared.lock();
+        Subscriber {
+            shared: Arc::clone(&self.shared),
+            id: None,
+            position: state.head,
+        }
+    }
+
+    /// 订阅事件，从缓冲区中最旧的事件开始消费，不会丢失事件，但消费过慢时将阻塞`BroadcastHandler`
+    pub fn subscribe_blocking(&self) -> Subscriber {
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        let position = state.head;
+        state.next_id += 1;
+        state.blocking.insert(id, position);
+        state.handled.insert(id, position);
+        Subscriber {
+            shared: Arc::clone(&self.shared),
+            id: Some(id),
+            position,
+        }
+    }
+
+    /// 缓冲区中最新的事件之后的序号，即至今产生过的事件个数
+    pub fn tail(&self) -> u64 {
+        self.shared.lock().tail()
+    }
+}
+
+/// 接收事件时出现的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// 消费者落后太多，此数量的事件已被覆盖，之后从缓冲区中最旧的事件继续消费，仅`Bus::subscribe`订阅的消费者会出现
+    Lagged(u64),
+    /// 缓冲区中没有尚未消费的事件，仅由`try_recv`返回
+    Empty,
+    /// 超时，仅由`recv_timeout`返回
+    Timeout,
+    /// `BroadcastHandler`已被drop，并且缓冲区中的事件都已消费完毕
+    Closed,
+}
+
+/// 事件的消费者，各自记录下一个要消费的事件的位置，可以在其他线程中使用；
+/// 作为迭代器时逐个阻塞地接收事件，忽略`RecvError::Lagged`，收到`RecvError::Closed`时结束
+///
+/// drop之后不再影响`BroadcastHandler`
+pub struct Subscriber {
+    shared: Arc<Shared>,
+    // 阻塞式消费者的id
+    id: Option<u64>,
+    position: u64,
+}
+
+impl Subscriber {
+    /// 下一个要消费的事件的序号，即此消费者已经消费(或者因落后而跳过)的事件个数
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// 阻塞直到接收到一个事件
+    pub fn recv(&mut self) -> Result<Arc<OwnedEvent>, RecvError> {
+        self.receive(None)
+    }
+
+    /// 最多阻塞`timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Arc<OwnedEvent>, RecvError> {
+        self.receive(Some(Instant::now() + timeout))
+    }
+
+    /// 不阻塞，没有尚未消费的事件时返回`RecvError::Empty`
+    pub fn try_recv(&mut self) -> Result<Arc<OwnedEvent>, RecvError> {
+        let shared = Arc::clone(&self.shared);
+        let mut state = shared.lock();
+        self.handled(&mut state);
+        match self.take(&mut state) {
+            Some(result) => result,
+            None => Err(RecvError::Empty),
+        }
+    }
+
+    fn receive(&mut self, deadline: Option<Instant>) -> Result<Arc<OwnedEvent>, RecvError> {
+        let shared = Arc::clone(&self.shared);
+        let mut state = shared.lock();
+        self.handled(&mut state);
+        loop {
+            if let Some(result) = self.take(&mut state) {
+                return result;
+            }
+            state = match deadline {
+                None => shared
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(RecvError::Timeout);
+                    }
+                    match shared.changed.wait_timeout(state, deadline - now) {
+                        Ok((state, _)) => state,
+                        Err(poisoned) => poisoned.into_inner().0,
+                    }
+                }
+            };
+        }
+    }
+
+    // 此前接收到的事件都已处理完毕
+    fn handled(&self, state: &mut State) {
+        if let Some(id) = self.id {
+            if state.handled.insert(id, self.position) != Some(self.position) {
+                self.shared.changed.notify_all();
+            }
+        }
+    }
+
+    // 取出下一个事件，尚无事件可取时返回None
+    fn take(&mut self, state: &mut State) -> Option<Result<Arc<OwnedEvent>, RecvError>> {
+        if self.position < state.head {
+            let skipped = state.head - self.position;
+            self.position = state.head;
+            return Some(Err(RecvError::Lagged(skipped)));
+        }
+        if self.position < state.tail() {
+            let event = Arc::clone(&state.events[(self.position - state.head) as usize]);
+            self.position += 1;
+            if let Some(id) = self.id {
+                state.blocking.insert(id, self.position);
+                self.shared.changed.notify_all();
+            }
+            return Some(Ok(event));
+        }
+        if state.is_closed {
+            return Some(Err(RecvError::Closed));
+        }
+        None
+    }
+}
+
+impl Iterator for Subscriber {
+    type Item = Arc<OwnedEvent>;
+
+    fn next(&mut self) -> Option<Arc<OwnedEvent>> {
+        loop {
+            match self.recv() {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.shared.lock();
+            state.blocking.remove(&id);
+            state.handled.remove(&id);
+            self.shared.changed.notify_all();
+        }
+    }
+}
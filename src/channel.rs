@@ -0,0 +1,217 @@
+/*!
+将事件复制为[`OwnedEvent`]之后放入有界队列，由其他线程消费
+
+`Listener`以及其中的`EventHandler`都位于监听线程中，[`ChannelHandler`]作为其`EventHandler`，
+将事件交给可以在其他线程中使用的[`EventReceiver`]，无需在处理器中自行共享状态。
+
+事件在`EventReceiver`下一次接收时才被视为已处理完毕，开启`Config::is_ack_after_handle`时，
+`ChannelHandler::flush`将等待消费者处理完所有已放入队列的事件，之后才确认offset。
+
+[`OwnedEvent`]: ../enum.OwnedEvent.html
+[`ChannelHandler`]: struct.ChannelHandler.html
+[`EventReceiver`]: struct.EventReceiver.html
+*/
+use std::cell::Cell;
+use std::io::{self, Error, ErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{Event, EventHandler, OwnedEvent};
+
+/// 创建一对相互关联的`ChannelHandler`以及`EventReceiver`
+///
+/// 方法参数:
+///
+/// * `queue_size`: 已产生而尚未被接收的事件的最大个数，达到此数量时`ChannelHandler`将阻塞，监听线程也随之暂停，不再从master读取数据
+///
+/// ```no_run
+/// use std::thread;
+///
+/// use redis_event::config::Config;
+/// use redis_event::listener::Builder;
+/// use redis_event::{channel, OwnedEvent, RedisListener};
+///
+/// fn consume(config: Config) -> std::io::Result<()> {
+///     let (handler, receiver) = channel::channel(1024);
+///     thread::spawn(move || {
+///         for event in receiver {
+///             if let OwnedEvent::AOF(cmd) = event {
+///                 println!("{}", cmd.name());
+///             }
+///         }
+///     });
+///     let mut builder = Builder::new();
+///     builder.with_config(config);
+///     builder.with_event_handler(Box::new(handler));
+///     builder.build().start()
+/// }
+/// ```
+pub fn channel(queue_size: usize) -> (ChannelHandler, EventReceiver) {
+    let (sender, receiver) = mpsc::sync_channel(queue_size);
+    let progress = Arc::new(Progress {
+        state: Mutex::new(Handled {
+            count: 0,
+            is_closed: false,
+        }),
+        changed: Condvar::new(),
+    });
+    let handler = ChannelHandler {
+        sender,
+        running: None,
+        is_disconnected: false,
+        sent: 0,
+        progress: Arc::clone(&progress),
+    };
+    let receiver = EventReceiver {
+        receiver,
+        received: Cell::new(0),
+        progress,
+    };
+    (handler, receiver)
+}
+
+// 消费者已处理完毕的事件个数，由`EventReceiver`更新，`ChannelHandler::flush`等待其追上已放入队列的事件个数
+struct Progress {
+    state: Mutex<Handled>,
+    changed: Condvar,
+}
+
+struct Handled {
+    count: u64,
+    // EventReceiver是否已被drop
+    is_closed: bool,
+}
+
+impl Progress {
+    fn lock(&self) -> MutexGuard<'_, Handled> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// 将事件复制为`OwnedEvent`之后放入队列，队列已满时阻塞，直到`EventReceiver`接收了其中的事件
+///
+/// 对应的`EventReceiver`被drop之后，之后的事件都将被丢弃；若设置了控制变量，还会将其置为false以停止监听。
+/// `flush`阻塞直到`EventReceiver`处理完所有已放入队列的事件(即在接收了这些事件之后再次调用接收方法)，
+/// `EventReceiver`已被drop时返回`ErrorKind::BrokenPipe`错误
+pub struct ChannelHandler {
+    sender: SyncSender<OwnedEvent>,
+    running: Option<Arc<AtomicBool>>,
+    is_disconnected: bool,
+    // 已放入队列的事件个数
+    sent: u64,
+    progress: Arc<Progress>,
+}
+
+impl ChannelHandler {
+    /// 设置监听器的控制变量(即`Builder::with_control_flag`所设置的)，`EventReceiver`被drop之后将其置为false
+    pub fn with_control_flag(&mut self, running: Arc<AtomicBool>) {
+        self.running = Some(running);
+    }
+
+    /// 对应的`EventReceiver`是否已被drop
+    pub fn is_disconnected(&self) -> bool {
+        self.is_disconnected
+    }
+}
+
+impl EventHandler for ChannelHandler {
+    fn handle(&mut self, event: Event) {
+        if self.is_disconnected {
+            return;
+        }
+        if self.sender.send(event.to_owned()).is_err() {
+            self.is_disconnected = true;
+            if let Some(running) = &self.running {
+                running.store(false, Ordering::SeqCst);
+            }
+            return;
+        }
+        self.sent += 1;
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut handled = self.progress.lock();
+        loop {
+            if self.is_disconnected || (handled.is_closed && handled.count < self.sent) {
+                return Err(Error::new(ErrorKind::BrokenPipe, "EventReceiver已被drop, 事件未被处理"));
+            }
+            if handled.count >= self.sent {
+                return Ok(());
+            }
+            handled = self
+                .progress
+                .changed
+                .wait(handled)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+}
+
+/// 接收`ChannelHandler`放入队列的事件，可以在其他线程中使用，
+/// 作为迭代器时逐个阻塞地接收事件，`ChannelHandler`被drop(如监听结束)并且队列中的事件都已被接收之后结束
+///
+/// 每次调用接收方法时，此前接收到的事件都被视为已处理完毕
+pub struct EventReceiver {
+    receiver: Receiver<OwnedEvent>,
+    // 已接收的事件个数
+    received: Cell<u64>,
+    progress: Arc<Progress>,
+}
+
+impl EventReceiver {
+    /// 阻塞直到接收到一个事件，`ChannelHandler`已被drop并且队列为空时返回None
+    pub fn recv(&self) -> Option<OwnedEvent> {
+        self.handled();
+        let event = self.receiver.recv().ok();
+        self.received(event.is_some());
+        event
+    }
+
+    /// 最多阻塞`timeout`，超时或者`ChannelHandler`已被drop并且队列为空时返回对应的错误
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<OwnedEvent, RecvTimeoutError> {
+        self.handled();
+        let result = self.receiver.recv_timeout(timeout);
+        self.received(result.is_ok());
+        result
+    }
+
+    /// 不阻塞，队列为空或者`ChannelHandler`已被drop并且队列为空时返回对应的错误
+    pub fn try_recv(&self) -> Result<OwnedEvent, TryRecvError> {
+        self.handled();
+        let result = self.receiver.try_recv();
+        self.received(result.is_ok());
+        result
+    }
+
+    // 此前接收到的事件都已处理完毕
+    fn handled(&self) {
+        let mut handled = self.progress.lock();
+        if handled.count < self.received.get() {
+            handled.count = self.received.get();
+            self.progress.changed.notify_all();
+        }
+    }
+
+    fn received(&self, is_received: bool) {
+        if is_received {
+            self.received.set(self.received.get() + 1);
+        }
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        self.progress.lock().is_closed = true;
+        self.progress.changed.notify_all();
+    }
+}
+
+impl Iterator for EventReceiver {
+    type Item = OwnedEvent;
+
+    fn next(&mut self) -> Option<OwnedEvent> {
+        self.recv()
+    }
+}
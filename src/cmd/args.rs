@@ -0,0 +1,638 @@
+// 将解析后的命令还原为其各个参数
+use crate::cmd::cluster::SlotState;
+use crate::cmd::geo::{GeoFrom, GeoShape, RadiusOptions};
+use crate::cmd::hashes::ExpireCondition;
+use crate::cmd::keys::ORDER;
+use crate::cmd::lists::{DIRECTION, POSITION};
+use crate::cmd::scripting::RestorePolicy;
+use crate::cmd::sorted_sets::{CompareType, AGGREGATE, MINMAX, RANGEBY};
+use crate::cmd::streams::{Trim, TrimStrategy, XGROUP};
+use crate::cmd::strings::{ExistType, ExpireType, Op, Operation, Overflow};
+use crate::cmd::Command;
+use crate::resp;
+
+impl Command<'_> {
+    /// 还原为命令的各个参数，第一个为命令名
+    ///
+    /// 参数按照各个命令结构体中的字段重新生成，解析时未保留的信息(如无法识别的选项)无法还原；
+    /// 关键字的大小写也可能与原命令不同
+    pub fn to_args(&self) -> Vec<Vec<u8>> {
+        match self {
+            Command::APPEND(cmd) => Args::new("APPEND").arg(cmd.key).arg(cmd.value),
+            Command::BITFIELD(cmd) => {
+                let mut args = Args::new("BITFIELD").arg(cmd.key);
+                for statement in cmd.statements.iter().flatten() {
+                    args = match statement {
+                        Operation::GET(get) => args.arg("GET").arg(get._type).arg(get.offset),
+                        Operation::INCRBY(incr) => {
+                            args.arg("INCRBY").arg(incr._type).arg(incr.offset).arg(incr.increment)
+                        }
+                        Operation::SET(set) => args.arg("SET").arg(set._type).arg(set.offset).arg(set.value),
+                        Operation::OVERFLOW(overflow) => {
+                            let overflow = match overflow {
+                                Overflow::WRAP => "WRAP",
+                                Overflow::SAT => "SAT",
+                                Overflow::FAIL => "FAIL",
+                            };
+                            args.arg("OVERFLOW").arg(overflow)
+                        }
+                    };
+                }
+                args
+            }
+            Command::BITFIELD_RO(cmd) => {
+                let mut args = Args::new("BITFIELD_RO").arg(cmd.key);
+                for get in &cmd.statements {
+                    args = args.arg("GET").arg(get._type).arg(get.offset);
+                }
+                args
+            }
+            Command::BITOP(cmd) => {
+                let operation = match cmd.operation {
+                    Op::AND => "AND",
+                    Op::OR => "OR",
+                    Op::XOR => "XOR",
+                    Op::NOT => "NOT",
+                    Op::DIFF => "DIFF",
+                    Op::DIFF1 => "DIFF1",
+                    Op::ANDOR => "ANDOR",
+                    Op::ONE => "ONE",
+                };
+                Args::new("BITOP").arg(operation).arg(cmd.dest_key).args(&cmd.keys)
+            }
+            Command::BLMOVE(cmd) => Args::new("BLMOVE")
+                .arg(cmd.source)
+                .arg(cmd.destination)
+                .arg(direction(&cmd.wherefrom))
+                .arg(direction(&cmd.whereto))
+                .arg(cmd.timeout),
+            Command::BLMPOP(cmd) => {
+                Args::new("BLMPOP")
+                    .arg(cmd.timeout)
+                    .mpop(cmd.num_keys, &cmd.keys, direction(&cmd.direction), cmd.count)
+            }
+            Command::BRPOPLPUSH(cmd) => Args::new("BRPOPLPUSH")
+                .arg(cmd.source)
+                .arg(cmd.destination)
+                .arg(cmd.timeout),
+            Command::CLUSTERADDSLOTS(cmd) => Args::new("CLUSTER").arg("ADDSLOTS").args(&cmd.slots),
+            Command::CLUSTERADDSLOTSRANGE(cmd) => Args::new("CLUSTER").arg("ADDSLOTSRANGE").slot_ranges(&cmd.ranges),
+            Command::CLUSTERBUMPEPOCH => Args::new("CLUSTER").arg("BUMPEPOCH"),
+            Command::CLUSTERDELSLOTS(cmd) => Args::new("CLUSTER").arg("DELSLOTS").args(&cmd.slots),
+            Command::CLUSTERDELSLOTSRANGE(cmd) => Args::new("CLUSTER").arg("DELSLOTSRANGE").slot_ranges(&cmd.ranges),
+            Command::CLUSTERFLUSHSLOTS => Args::new("CLUSTER").arg("FLUSHSLOTS"),
+            Command::CLUSTERFORGET(cmd) => Args::new("CLUSTER").arg("FORGET").arg(cmd.node_id),
+            Command::CLUSTERSETCONFIGEPOCH(cmd) => Args::new("CLUSTER").arg("SET-CONFIG-EPOCH").arg(cmd.config_epoch),
+            Command::CLUSTERSETSLOT(cmd) => {
+                let args = Args::new("CLUSTER").arg("SETSLOT").arg(cmd.slot);
+                match cmd.state {
+                    SlotState::IMPORTING(node_id) => args.arg("IMPORTING").arg(node_id),
+                    SlotState::MIGRATING(node_id) => args.arg("MIGRATING").arg(node_id),
+                    SlotState::NODE(node_id) => args.arg("NODE").arg(node_id),
+                    SlotState::STABLE => args.arg("STABLE"),
+                }
+            }
+            Command::COPY(cmd) => Args::new("COPY")
+                .arg(cmd.source)
+                .arg(cmd.destination)
+                .option("DB", cmd.db)
+                .flag(cmd.replace, "REPLACE"),
+            Command::DECR(cmd) => Args::new("DECR").arg(cmd.key),
+            Command::DECRBY(cmd) => Args::new("DECRBY").arg(cmd.key).arg(cmd.decrement),
+            Command::DEL(cmd) => Args::new("DEL").args(&cmd.keys),
+            Command::EVAL(cmd) => Args::new("EVAL")
+                .arg(cmd.script)
+                .arg(cmd.num_keys.to_string())
+                .args(&cmd.keys)
+                .args(&cmd.args),
+            Command::EVALSHA(cmd) => Args::new("EVALSHA")
+                .arg(cmd.sha1)
+                .arg(cmd.num_keys.to_string())
+                .args(&cmd.keys)
+                .args(&cmd.args),
+            Command::EXPIRE(cmd) => Args::new("EXPIRE").arg(cmd.key).arg(cmd.seconds),
+            Command::EXPIREAT(cmd) => Args::new("EXPIREAT").arg(cmd.key).arg(cmd.timestamp),
+            Command::EXEC => Args::new("EXEC"),
+            Command::FCALL(cmd) => Args::new("FCALL")
+                .arg(cmd.function)
+                .arg(cmd.num_keys.to_string())
+                .args(&cmd.keys)
+                .args(&cmd.args),
+            Command::FLUSHALL(cmd) => Args::new("FLUSHALL").flag(cmd._async, "ASYNC").flag(cmd.sync, "SYNC"),
+            Command::FLUSHDB(cmd) => Args::new("FLUSHDB").flag(cmd._async, "ASYNC").flag(cmd.sync, "SYNC"),
+            Command::FUNCTIONDELETE(cmd) => Args::new("FUNCTION").arg("DELETE").arg(cmd.library_name),
+            Command::FUNCTIONFLUSH(cmd) => Args::new("FUNCTION")
+                .arg("FLUSH")
+                .flag(cmd._async, "ASYNC")
+                .flag(cmd.sync, "SYNC"),
+            Command::FUNCTIONLOAD(cmd) => Args::new("FUNCTION")
+                .arg("LOAD")
+                .flag(cmd.replace, "REPLACE")
+                .arg(cmd.code),
+            Command::FUNCTIONRESTORE(cmd) => {
+                let policy = cmd.policy.as_ref().map(|policy| match policy {
+                    RestorePolicy::FLUSH => "FLUSH",
+                    RestorePolicy::APPEND => "APPEND",
+                    RestorePolicy::REPLACE => "REPLACE",
+                });
+                Args::new("FUNCTION")
+                    .arg("RESTORE")
+                    .arg(cmd.payload)
+                    .option_name(policy)
+            }
+            Command::GEOADD(cmd) => {
+                let items = cmd
+                    .items
+                    .iter()
+                    .flat_map(|item| vec![item.longitude, item.latitude, item.member]);
+                Args::new("GEOADD")
+                    .arg(cmd.key)
+                    .option_name(cmd.exist_type.as_ref().map(exist_type))
+                    .flag(cmd.ch, "CH")
+                    .args(items)
+            }
+            Command::GEORADIUS(cmd) => Args::new("GEORADIUS")
+                .arg(cmd.key)
+                .arg(cmd.longitude)
+                .arg(cmd.latitude)
+                .arg(cmd.radius)
+                .arg(cmd.unit)
+                .radius_options(&cmd.options),
+            Command::GEORADIUSBYMEMBER(cmd) => Args::new("GEORADIUSBYMEMBER")
+                .arg(cmd.key)
+                .arg(cmd.member)
+                .arg(cmd.radius)
+                .arg(cmd.unit)
+                .radius_options(&cmd.options),
+            Command::GEOSEARCHSTORE(cmd) => {
+                let args = Args::new("GEOSEARCHSTORE").arg(cmd.destination).arg(cmd.source);
+                let args = match cmd.from {
+                    GeoFrom::FROMMEMBER(member) => args.arg("FROMMEMBER").arg(member),
+                    GeoFrom::FROMLONLAT(longitude, latitude) => args.arg("FROMLONLAT").arg(longitude).arg(latitude),
+                };
+                let args = match cmd.shape {
+                    GeoShape::BYRADIUS(radius, unit) => args.arg("BYRADIUS").arg(radius).arg(unit),
+                    GeoShape::BYBOX(width, height, unit) => args.arg("BYBOX").arg(width).arg(height).arg(unit),
+                };
+                args.option_name(cmd.order.as_ref().map(order))
+                    .option("COUNT", cmd.count)
+                    .flag(cmd.any, "ANY")
+                    .flag(cmd.store_dist, "STOREDIST")
+            }
+            Command::GETDEL(cmd) => Args::new("GETDEL").arg(cmd.key),
+            Command::GETEX(cmd) => Args::new("GETEX")
+                .arg(cmd.key)
+                .expire(&cmd.expire)
+                .flag(cmd.persist, "PERSIST"),
+            Command::GETSET(cmd) => Args::new("GETSET").arg(cmd.key).arg(cmd.value),
+            Command::HDEL(cmd) => Args::new("HDEL").arg(cmd.key).args(&cmd.fields),
+            Command::HEXPIRE(cmd) => Args::new("HEXPIRE")
+                .arg(cmd.key)
+                .arg(cmd.seconds)
+                .option_name(cmd.condition.as_ref().map(expire_condition))
+                .fields(&cmd.fields),
+            Command::HEXPIREAT(cmd) => Args::new("HEXPIREAT")
+                .arg(cmd.key)
+                .arg(cmd.timestamp)
+                .option_name(cmd.condition.as_ref().map(expire_condition))
+                .fields(&cmd.fields),
+            Command::HGETDEL(cmd) => Args::new("HGETDEL").arg(cmd.key).fields(&cmd.fields),
+            Command::HGETEX(cmd) => Args::new("HGETEX")
+                .arg(cmd.key)
+                .expire(&cmd.expire)
+                .flag(cmd.persist, "PERSIST")
+                .fields(&cmd.fields),
+            Command::HINCRBY(cmd) => Args::new("HINCRBY").arg(cmd.key).arg(cmd.field).arg(cmd.increment),
+            Command::HMSET(cmd) => {
+                let fields = cmd.fields.iter().flat_map(|field| vec![field.name, field.value]);
+                Args::new("HMSET").arg(cmd.key).args(fields)
+            }
+            Command::HPERSIST(cmd) => Args::new("HPERSIST").arg(cmd.key).fields(&cmd.fields),
+            Command::HPEXPIRE(cmd) => Args::new("HPEXPIRE")
+                .arg(cmd.key)
+                .arg(cmd.milliseconds)
+                .option_name(cmd.condition.as_ref().map(expire_condition))
+                .fields(&cmd.fields),
+            Command::HPEXPIREAT(cmd) => Args::new("HPEXPIREAT")
+                .arg(cmd.key)
+                .arg(cmd.mill_timestamp)
+                .option_name(cmd.condition.as_ref().map(expire_condition))
+                .fields(&cmd.fields),
+            Command::HSET(cmd) => {
+                let fields = cmd.fields.iter().flat_map(|field| vec![field.name, field.value]);
+                Args::new("HSET").arg(cmd.key).args(fields)
+            }
+            Command::HSETNX(cmd) => Args::new("HSETNX").arg(cmd.key).arg(cmd.field).arg(cmd.value),
+            Command::INCR(cmd) => Args::new("INCR").arg(cmd.key),
+            Command::INCRBY(cmd) => Args::new("INCRBY").arg(cmd.key).arg(cmd.increment),
+            Command::LINSERT(cmd) => {
+                let position = match cmd.position {
+                    POSITION::BEFORE => "BEFORE",
+                    POSITION::AFTER => "AFTER",
+                };
+                Args::new("LINSERT")
+                    .arg(cmd.key)
+                    .arg(position)
+                    .arg(cmd.pivot)
+                    .arg(cmd.element)
+            }
+            Command::LMOVE(cmd) => Args::new("LMOVE")
+                .arg(cmd.source)
+                .arg(cmd.destination)
+                .arg(direction(&cmd.wherefrom))
+                .arg(direction(&cmd.whereto)),
+            Command::LMPOP(cmd) => {
+                Args::new("LMPOP").mpop(cmd.num_keys, &cmd.keys, direction(&cmd.direction), cmd.count)
+            }
+            Command::LPOP(cmd) => Args::new("LPOP").arg(cmd.key).args(cmd.count),
+            Command::LPUSH(cmd) => Args::new("LPUSH").arg(cmd.key).args(&cmd.elements),
+            Command::LPUSHX(cmd) => Args::new("LPUSHX").arg(cmd.key).args(&cmd.elements),
+            Command::LREM(cmd) => Args::new("LREM").arg(cmd.key).arg(cmd.count).arg(cmd.element),
+            Command::LSET(cmd) => Args::new("LSET").arg(cmd.key).arg(cmd.index).arg(cmd.element),
+            Command::LTRIM(cmd) => Args::new("LTRIM").arg(cmd.key).arg(cmd.start).arg(cmd.stop),
+            Command::MIGRATE(cmd) => {
+                let mut args = Args::new("MIGRATE")
+                    .arg(cmd.host)
+                    .arg(cmd.port)
+                    .arg(cmd.key)
+                    .arg(cmd.destination_db)
+                    .arg(cmd.timeout)
+                    .flag(cmd.copy, "COPY")
+                    .flag(cmd.replace, "REPLACE")
+                    .option("AUTH", cmd.auth);
+                if let Some((username, password)) = cmd.auth2 {
+                    args = args.arg("AUTH2").arg(username).arg(password);
+                }
+                if let Some(keys) = &cmd.keys {
+                    args = args.arg("KEYS").args(keys);
+                }
+                args
+            }
+            Command::MOVE(cmd) => Args::new("MOVE").arg(cmd.key).arg(cmd.db),
+            Command::MSET(cmd) => {
+                let key_values = cmd.key_values.iter().flat_map(|kv| vec![kv.key, kv.value]);
+                Args::new("MSET").args(key_values)
+            }
+            Command::MSETNX(cmd) => {
+                let key_values = cmd.key_values.iter().flat_map(|kv| vec![kv.key, kv.value]);
+                Args::new("MSETNX").args(key_values)
+            }
+            Command::MULTI => Args::new("MULTI"),
+            Command::PERSIST(cmd) => Args::new("PERSIST").arg(cmd.key),
+            Command::PEXPIRE(cmd) => Args::new("PEXPIRE").arg(cmd.key).arg(cmd.milliseconds),
+            Command::PEXPIREAT(cmd) => Args::new("PEXPIREAT").arg(cmd.key).arg(cmd.mill_timestamp),
+            Command::PFADD(cmd) => Args::new("PFADD").arg(cmd.key).args(&cmd.elements),
+            Command::PFCOUNT(cmd) => Args::new("PFCOUNT").args(&cmd.keys),
+            Command::PFDEBUG(cmd) => Args::new("PFDEBUG").arg(cmd.subcommand).arg(cmd.key),
+            Command::PFMERGE(cmd) => Args::new("PFMERGE").arg(cmd.dest_key).args(&cmd.source_keys),
+            Command::PSETEX(cmd) => Args::new("PSETEX").arg(cmd.key).arg(cmd.milliseconds).arg(cmd.value),
+            Command::PUBLISH(cmd) => Args::new("PUBLISH").arg(cmd.channel).arg(cmd.message),
+            Command::RENAME(cmd) => Args::new("RENAME").arg(cmd.key).arg(cmd.new_key),
+            Command::RENAMENX(cmd) => Args::new("RENAMENX").arg(cmd.key).arg(cmd.new_key),
+            Command::RESTORE(cmd) => Args::new("RESTORE")
+                .arg(cmd.key)
+                .arg(cmd.ttl)
+                .arg(cmd.value)
+                .flag(cmd.replace, "REPLACE")
+                .flag(cmd.abs_ttl, "ABSTTL")
+                .option("IDLETIME", cmd.idle_time)
+                .option("FREQ", cmd.freq),
+            Command::RPOP(cmd) => Args::new("RPOP").arg(cmd.key).args(cmd.count),
+            Command::RPOPLPUSH(cmd) => Args::new("RPOPLPUSH").arg(cmd.source).arg(cmd.destination),
+            Command::RPUSH(cmd) => Args::new("RPUSH").arg(cmd.key).args(&cmd.elements),
+            Command::RPUSHX(cmd) => Args::new("RPUSHX").arg(cmd.key).args(&cmd.elements),
+            Command::SADD(cmd) => Args::new("SADD").arg(cmd.key).args(&cmd.members),
+            Command::SCRIPTFLUSH => Args::new("SCRIPT").arg("FLUSH"),
+            Command::SCRIPTLOAD(cmd) => Args::new("SCRIPT").arg("LOAD").arg(cmd.script),
+            Command::SDIFFSTORE(cmd) => Args::new("SDIFFSTORE").arg(cmd.destination).args(&cmd.keys),
+            Command::SET(cmd) => Args::new("SET")
+                .arg(cmd.key)
+                .arg(cmd.value)
+                .expire(&cmd.expire)
+                .option_name(cmd.exist_type.as_ref().map(exist_type))
+                .flag(cmd.keep_ttl, "KEEPTTL")
+                .flag(cmd.get, "GET"),
+            Command::SETBIT(cmd) => Args::new("SETBIT").arg(cmd.key).arg(cmd.offset).arg(cmd.value),
+            Command::SETEX(cmd) => Args::new("SETEX").arg(cmd.key).arg(cmd.seconds).arg(cmd.value),
+            Command::SETNX(cmd) => Args::new("SETNX").arg(cmd.key).arg(cmd.value),
+            Command::SELECT(cmd) => Args::new("SELECT").arg(cmd.db.to_string()),
+            Command::SETRANGE(cmd) => Args::new("SETRANGE").arg(cmd.key).arg(cmd.offset).arg(cmd.value),
+            Command::SINTERCARD(cmd) => Args::new("SINTERCARD")
+                .arg(cmd.num_keys.to_string())
+                .args(&cmd.keys)
+                .option("LIMIT", cmd.limit),
+            Command::SINTERSTORE(cmd) => Args::new("SINTERSTORE").arg(cmd.destination).args(&cmd.keys),
+            Command::SMOVE(cmd) => Args::new("SMOVE").arg(cmd.source).arg(cmd.destination).arg(cmd.member),
+            Command::SORT(cmd) => {
+                let mut args = Args::new("SORT").arg(cmd.key).option("BY", cmd.by_pattern);
+                if let Some(limit) = &cmd.limit {
+                    args = args.arg("LIMIT").arg(limit.offset).arg(limit.count);
+                }
+                for pattern in cmd.get_patterns.iter().flatten() {
+                    args = args.arg("GET").arg(pattern);
+                }
+                args.option_name(cmd.order.as_ref().map(order))
+                    .flag(cmd.alpha, "ALPHA")
+                    .option("STORE", cmd.destination)
+            }
+            Command::SREM(cmd) => Args::new("SREM").arg(cmd.key).args(&cmd.members),
+            Command::SUNIONSTORE(cmd) => Args::new("SUNIONSTORE").arg(cmd.destination).args(&cmd.keys),
+            Command::SWAPDB(cmd) => Args::new("SWAPDB").arg(cmd.index1).arg(cmd.index2),
+            Command::UNLINK(cmd) => Args::new("UNLINK").args(&cmd.keys),
+            Command::ZADD(cmd) => {
+                let items = cmd.items.iter().flat_map(|item| vec![item.score, item.member]);
+                Args::new("ZADD")
+                    .arg(cmd.key)
+                    .option_name(cmd.exist_type.as_ref().map(exist_type))
+                    .option_name(cmd.compare_type.as_ref().map(compare_type))
+                    .flag(cmd.ch, "CH")
+                    .flag(cmd.incr, "INCR")
+                    .args(items)
+            }
+            Command::ZDIFFSTORE(cmd) => Args::new("ZDIFFSTORE")
+                .arg(cmd.destination)
+                .arg(cmd.num_keys.to_string())
+                .args(&cmd.keys),
+            Command::ZINCRBY(cmd) => Args::new("ZINCRBY").arg(cmd.key).arg(cmd.increment).arg(cmd.member),
+            Command::ZINTERSTORE(cmd) => {
+                let args = Args::new("ZINTERSTORE").arg(cmd.destination);
+                args.aggregation(cmd.num_keys, &cmd.keys, &cmd.weights, &cmd.aggregate)
+            }
+            Command::ZMPOP(cmd) => {
+                let min_max = match cmd.min_max {
+                    MINMAX::MIN => "MIN",
+                    MINMAX::MAX => "MAX",
+                };
+                Args::new("ZMPOP").mpop(cmd.num_keys, &cmd.keys, min_max, cmd.count)
+            }
+            Command::ZPOPMAX(cmd) => Args::new("ZPOPMAX").arg(cmd.key).args(cmd.count),
+            Command::ZPOPMIN(cmd) => Args::new("ZPOPMIN").arg(cmd.key).args(cmd.count),
+            Command::ZRANGESTORE(cmd) => {
+                let by = cmd.by.as_ref().map(|by| match by {
+                    RANGEBY::BYSCORE => "BYSCORE",
+                    RANGEBY::BYLEX => "BYLEX",
+                });
+                let mut args = Args::new("ZRANGESTORE")
+                    .arg(cmd.destination)
+                    .arg(cmd.source)
+                    .arg(cmd.min)
+                    .arg(cmd.max)
+                    .option_name(by)
+                    .flag(cmd.rev, "REV");
+                if let Some(limit) = &cmd.limit {
+                    args = args.arg("LIMIT").arg(limit.offset).arg(limit.count);
+                }
+                args
+            }
+            Command::ZREM(cmd) => Args::new("ZREM").arg(cmd.key).args(&cmd.members),
+            Command::ZREMRANGEBYLEX(cmd) => Args::new("ZREMRANGEBYLEX").arg(cmd.key).arg(cmd.min).arg(cmd.max),
+            Command::ZREMRANGEBYRANK(cmd) => Args::new("ZREMRANGEBYRANK").arg(cmd.key).arg(cmd.start).arg(cmd.stop),
+            Command::ZREMRANGEBYSCORE(cmd) => Args::new("ZREMRANGEBYSCORE").arg(cmd.key).arg(cmd.min).arg(cmd.max),
+            Command::ZUNIONSTORE(cmd) => {
+                let args = Args::new("ZUNIONSTORE").arg(cmd.destination);
+                args.aggregation(cmd.num_keys, &cmd.keys, &cmd.weights, &cmd.aggregate)
+            }
+            Command::XACK(cmd) => Args::new("XACK").arg(cmd.key).arg(cmd.group).args(&cmd.ids),
+            Command::XADD(cmd) => {
+                let fields = cmd.fields.iter().flat_map(|field| vec![field.name, field.value]);
+                let args = Args::new("XADD").arg(cmd.key).flag(cmd.no_mkstream, "NOMKSTREAM");
+                let args = match &cmd.trim {
+                    Some(trim) => args.trim(trim),
+                    None => args,
+                };
+                args.arg(cmd.id).args(fields)
+            }
+            Command::XAUTOCLAIM(cmd) => Args::new("XAUTOCLAIM")
+                .arg(cmd.key)
+                .arg(cmd.group)
+                .arg(cmd.consumer)
+                .arg(cmd.min_idle_time)
+                .arg(cmd.start)
+                .option("COUNT", cmd.count)
+                .flag(cmd.just_id, "JUSTID"),
+            Command::XCLAIM(cmd) => Args::new("XCLAIM")
+                .arg(cmd.key)
+                .arg(cmd.group)
+                .arg(cmd.consumer)
+                .arg(cmd.min_idle_time)
+                .args(&cmd.ids)
+                .option("IDLE", cmd.idle)
+                .option("TIME", cmd.time)
+                .option("RETRYCOUNT", cmd.retry_count)
+                .flag(cmd.force, "FORCE")
+                .flag(cmd.just_id, "JUSTID")
+                .option("LASTID", cmd.last_id),
+            Command::XDEL(cmd) => Args::new("XDEL").arg(cmd.key).args(&cmd.ids),
+            Command::XGROUP(cmd) => xgroup(cmd),
+            Command::XSETID(cmd) => Args::new("XSETID")
+                .arg(cmd.key)
+                .arg(cmd.last_id)
+                .option("ENTRIESADDED", cmd.entries_added)
+                .option("MAXDELETEDID", cmd.max_deleted_id),
+            Command::XTRIM(cmd) => Args::new("XTRIM").arg(cmd.key).trim(&cmd.trim),
+            Command::Module(cmd) => Args::new(cmd.name).args(cmd.args),
+            Command::Other(cmd) => Args::new(&cmd.name).args(&cmd.args),
+        }
+        .0
+    }
+
+    /// 还原为RESP格式的命令，即将`to_args`的结果编码为RESP array，同样无法还原解析时未保留的信息
+    ///
+    /// 需要原样转发命令时，可以将`EventHandler::handle_aof_raw`中的原始命令交给`resp::encode_command`
+    pub fn to_resp(&self) -> Vec<u8> {
+        resp::encode_command(&self.to_args())
+    }
+}
+
+fn order(order: &ORDER) -> &'static str {
+    match order {
+        ORDER::ASC => "ASC",
+        ORDER::DESC => "DESC",
+    }
+}
+
+fn exist_type(exist_type: &ExistType) -> &'static str {
+    match exist_type {
+        ExistType::NX => "NX",
+        ExistType::XX => "XX",
+    }
+}
+
+fn compare_type(compare_type: &CompareType) -> &'static str {
+    match compare_type {
+        CompareType::GT => "GT",
+        CompareType::LT => "LT",
+    }
+}
+
+fn expire_condition(condition: &ExpireCondition) -> &'static str {
+    match condition {
+        ExpireCondition::NX => "NX",
+        ExpireCondition::XX => "XX",
+        ExpireCondition::GT => "GT",
+        ExpireCondition::LT => "LT",
+    }
+}
+
+fn direction(direction: &DIRECTION) -> &'static str {
+    match direction {
+        DIRECTION::LEFT => "LEFT",
+        DIRECTION::RIGHT => "RIGHT",
+    }
+}
+
+fn xgroup(cmd: &XGROUP) -> Args {
+    let args = Args::new("XGROUP");
+    if let Some(create) = &cmd.create {
+        args.arg("CREATE")
+            .arg(create.key)
+            .arg(create.group_name)
+            .arg(create.id)
+            .flag(create.mkstream, "MKSTREAM")
+            .option("ENTRIESREAD", create.entries_read)
+    } else if let Some(set_id) = &cmd.set_id {
+        args.arg("SETID")
+            .arg(set_id.key)
+            .arg(set_id.group_name)
+            .arg(set_id.id)
+            .option("ENTRIESREAD", set_id.entries_read)
+    } else if let Some(destroy) = &cmd.destroy {
+        args.arg("DESTROY").arg(destroy.key).arg(destroy.group_name)
+    } else if let Some(create_consumer) = &cmd.create_consumer {
+        args.arg("CREATECONSUMER")
+            .arg(create_consumer.key)
+            .arg(create_consumer.group_name)
+            .arg(create_consumer.consumer_name)
+    } else if let Some(del_consumer) = &cmd.del_consumer {
+        args.arg("DELCONSUMER")
+            .arg(del_consumer.key)
+            .arg(del_consumer.group_name)
+            .arg(del_consumer.consumer_name)
+    } else {
+        args
+    }
+}
+
+struct Args(Vec<Vec<u8>>);
+
+impl Args {
+    fn new(name: &str) -> Args {
+        Args(vec![name.as_bytes().to_vec()])
+    }
+
+    fn arg<T: AsRef<[u8]>>(mut self, arg: T) -> Args {
+        self.0.push(arg.as_ref().to_vec());
+        self
+    }
+
+    fn args<I: IntoIterator>(mut self, args: I) -> Args
+    where
+        I::Item: AsRef<[u8]>,
+    {
+        self.0.extend(args.into_iter().map(|arg| arg.as_ref().to_vec()));
+        self
+    }
+
+    // 值为Some(true)时添加此标志
+    fn flag(self, value: Option<bool>, name: &str) -> Args {
+        match value {
+            Some(true) => self.arg(name),
+            _ => self,
+        }
+    }
+
+    // 值为Some时添加选项名及其值
+    fn option<T: AsRef<[u8]>>(self, name: &str, value: Option<T>) -> Args {
+        match value {
+            Some(value) => self.arg(name).arg(value),
+            None => self,
+        }
+    }
+
+    // 值为Some时只添加选项名，如NX、ASC
+    fn option_name(self, name: Option<&str>) -> Args {
+        match name {
+            Some(name) => self.arg(name),
+            None => self,
+        }
+    }
+
+    // SET以及GETEX的过期时间
+    fn expire<T: AsRef<[u8]>>(self, expire: &Option<(ExpireType, T)>) -> Args {
+        match expire {
+            Some((expire_type, expire)) => {
+                let expire_type = match expire_type {
+                    ExpireType::EX => "EX",
+                    ExpireType::PX => "PX",
+                    ExpireType::EXAT => "EXAT",
+                    ExpireType::PXAT => "PXAT",
+                };
+                self.arg(expire_type).arg(expire)
+            }
+            None => self,
+        }
+    }
+
+    // XADD以及XTRIM的裁剪条件
+    // FIELDS numfields field [field ...]
+    fn fields(self, fields: &[&[u8]]) -> Args {
+        self.arg("FIELDS").arg(fields.len().to_string()).args(fields)
+    }
+
+    // 各个起始slot与结束slot
+    fn slot_ranges(mut self, ranges: &[(&[u8], &[u8])]) -> Args {
+        for (start, end) in ranges {
+            self = self.arg(start).arg(end);
+        }
+        self
+    }
+
+    // GEORADIUS与GEORADIUSBYMEMBER共有的选项
+    fn radius_options(self, options: &RadiusOptions) -> Args {
+        self.flag(options.with_coord, "WITHCOORD")
+            .flag(options.with_dist, "WITHDIST")
+            .flag(options.with_hash, "WITHHASH")
+            .option("COUNT", options.count)
+            .flag(options.any, "ANY")
+            .option_name(options.order.as_ref().map(order))
+            .option("STORE", options.store)
+            .option("STOREDIST", options.store_dist)
+    }
+
+    fn trim(self, trim: &Trim) -> Args {
+        let strategy = match trim.strategy {
+            TrimStrategy::MAXLEN => "MAXLEN",
+            TrimStrategy::MINID => "MINID",
+        };
+        let args = self.arg(strategy);
+        let args = if trim.approximation { args.arg("~") } else { args };
+        args.arg(trim.threshold).option("LIMIT", trim.limit)
+    }
+
+    // LMPOP、BLMPOP以及ZMPOP的公共部分
+    fn mpop(self, num_keys: i32, keys: &[&[u8]], direction: &str, count: Option<&[u8]>) -> Args {
+        self.arg(num_keys.to_string())
+            .args(keys)
+            .arg(direction)
+            .option("COUNT", count)
+    }
+
+    // ZINTERSTORE以及ZUNIONSTORE的公共部分
+    fn aggregation(
+        self, num_keys: i32, keys: &[&[u8]], weights: &Option<Vec<&[u8]>>, aggregate: &Option<AGGREGATE>,
+    ) -> Args {
+        let mut args = self.arg(num_keys.to_string()).args(keys);
+        if let Some(weights) = weights {
+            args = args.arg("WEIGHTS").args(weights);
+        }
+        let aggregate = aggregate.as_ref().map(|aggregate| match aggregate {
+            AGGREGATE::SUM => "SUM",
+            AGGREGATE::MIN => "MIN",
+            AGGREGATE::MAX => "MAX",
+        });
+        match aggregate {
+            Some(aggregate) => args.arg("AGGREGATE").arg(aggregate),
+            None => args,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// 命令所涉及的key及其分类
+use crate::cmd::streams::XGROUP;
+use crate::cmd::{Command, DataType};
+
+impl<'a> Command<'a> {
+    /// 命令所涉及的所有key(包括只读取的key，如`SUNIONSTORE`中的源key)，按其在命令中出现的顺序排列
+    ///
+    /// 不涉及key的命令(如`SELECT`、`FLUSHALL`、`PUBLISH`)返回空；`Other`与`Module`无法得知其中哪些参数是key，同样返回空
+    pub fn keys(&self) -> Vec<&'a [u8]> {
+        match self {
+            Command::APPEND(cmd) => vec![cmd.key],
+            Command::BITFIELD(cmd) => vec![cmd.key],
+            Command::BITFIELD_RO(cmd) => vec![cmd.key],
+            Command::BITOP(cmd) => {
+                let mut keys = vec![cmd.dest_key];
+                keys.extend(cmd.keys.iter().map(|key| key.as_slice()));
+                keys
+            }
+            Command::BLMOVE(cmd) => vec![cmd.source, cmd.destination],
+            Command::BLMPOP(cmd) => cmd.keys.clone(),
+            Command::BRPOPLPUSH(cmd) => vec![cmd.source, cmd.destination],
+            Command::COPY(cmd) => vec![cmd.source, cmd.destination],
+            Command::DECR(cmd) => vec![cmd.key],
+            Command::DECRBY(cmd) => vec![cmd.key],
+            Command::DEL(cmd) => cmd.keys.iter().map(|key| key.as_slice()).collect(),
+            Command::EVAL(cmd) => cmd.keys.clone(),
+            Command::EVALSHA(cmd) => cmd.keys.clone(),
+            Command::EXPIRE(cmd) => vec![cmd.key],
+            Command::EXPIREAT(cmd) => vec![cmd.key],
+            Command::FCALL(cmd) => cmd.keys.clone(),
+            Command::GEOADD(cmd) => vec![cmd.key],
+            Command::GEORADIUS(cmd) => {
+                let mut keys = vec![cmd.key];
+                keys.extend(cmd.options.store);
+                keys.extend(cmd.options.store_dist);
+                keys
+            }
+            Command::GEORADIUSBYMEMBER(cmd) => {
+                let mut keys = vec![cmd.key];
+                keys.extend(cmd.options.store);
+                keys.extend(cmd.options.store_dist);
+                keys
+            }
+            Command::GEOSEARCHSTORE(cmd) => vec![cmd.destination, cmd.source],
+            Command::GETDEL(cmd) => vec![cmd.key],
+            Command::GETEX(cmd) => vec![cmd.key],
+            Command::GETSET(cmd) => vec![cmd.key],
+            Command::HDEL(cmd) => vec![cmd.key],
+            Command::HEXPIRE(cmd) => vec![cmd.key],
+            Command::HEXPIREAT(cmd) => vec![cmd.key],
+            Command::HGETDEL(cmd) => vec![cmd.key],
+            Command::HGETEX(cmd) => vec![cmd.key],
+            Command::HINCRBY(cmd) => vec![cmd.key],
+            Command::HMSET(cmd) => vec![cmd.key],
+            Command::HPERSIST(cmd) => vec![cmd.key],
+            Command::HPEXPIRE(cmd) => vec![cmd.key],
+            Command::HPEXPIREAT(cmd) => vec![cmd.key],
+            Command::HSET(cmd) => vec![cmd.key],
+            Command::HSETNX(cmd) => vec![cmd.key],
+            Command::INCR(cmd) => vec![cmd.key],
+            Command::INCRBY(cmd) => vec![cmd.key],
+            Command::LINSERT(cmd) => vec![cmd.key],
+            Command::LMOVE(cmd) => vec![cmd.source, cmd.destination],
+            Command::LMPOP(cmd) => cmd.keys.clone(),
+            Command::LPOP(cmd) => vec![cmd.key],
+            Command::LPUSH(cmd) => vec![cmd.key],
+            Command::LPUSHX(cmd) => vec![cmd.key],
+            Command::LREM(cmd) => vec![cmd.key],
+            Command::LSET(cmd) => vec![cmd.key],
+            Command::LTRIM(cmd) => vec![cmd.key],
+            // 使用KEYS选项时，key参数为空字符串
+            Command::MIGRATE(cmd) => match &cmd.keys {
+                Some(keys) => keys.clone(),
+                None => vec![cmd.key],
+            },
+            Command::MOVE(cmd) => vec![cmd.key],
+            Command::MSET(cmd) => cmd.key_values.iter().map(|kv| kv.key).collect(),
+            Command::MSETNX(cmd) => cmd.key_values.iter().map(|kv| kv.key).collect(),
+            Command::PERSIST(cmd) => vec![cmd.key],
+            Command::PEXPIRE(cmd) => vec![cmd.key],
+            Command::PEXPIREAT(cmd) => vec![cmd.key],
+            Command::PFADD(cmd) => vec![cmd.key],
+            Command::PFCOUNT(cmd) => cmd.keys.clone(),
+            Command::PFDEBUG(cmd) => vec![cmd.key],
+            Command::PFMERGE(cmd) => {
+                let mut keys = vec![cmd.dest_key];
+                keys.extend(&cmd.source_keys);
+                keys
+            }
+            Command::PSETEX(cmd) => vec![cmd.key],
+            Command::RENAME(cmd) => vec![cmd.key, cmd.new_key],
+            Command::RENAMENX(cmd) => vec![cmd.key, cmd.new_key],
+            Command::RESTORE(cmd) => vec![cmd.key],
+            Command::RPOP(cmd) => vec![cmd.key],
+            Command::RPOPLPUSH(cmd) => vec![cmd.source, cmd.destination],
+            Command::RPUSH(cmd) => vec![cmd.key],
+            Command::RPUSHX(cmd) => vec![cmd.key],
+            Command::SADD(cmd) => vec![cmd.key],
+            Command::SDIFFSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::SET(cmd) => vec![cmd.key],
+            Command::SETBIT(cmd) => vec![cmd.key],
+            Command::SETEX(cmd) => vec![cmd.key],
+            Command::SETNX(cmd) => vec![cmd.key],
+            Command::SETRANGE(cmd) => vec![cmd.key],
+            Command::SINTERCARD(cmd) => cmd.keys.clone(),
+            Command::SINTERSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::SMOVE(cmd) => vec![cmd.source, cmd.destination],
+            Command::SORT(cmd) => {
+                let mut keys = vec![cmd.key];
+                keys.extend(cmd.destination);
+                keys
+            }
+            Command::SREM(cmd) => vec![cmd.key],
+            Command::SUNIONSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::UNLINK(cmd) => cmd.keys.clone(),
+            Command::ZADD(cmd) => vec![cmd.key],
+            Command::ZDIFFSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::ZINCRBY(cmd) => vec![cmd.key],
+            Command::ZINTERSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::ZMPOP(cmd) => cmd.keys.clone(),
+            Command::ZPOPMAX(cmd) => vec![cmd.key],
+            Command::ZPOPMIN(cmd) => vec![cmd.key],
+            Command::ZRANGESTORE(cmd) => vec![cmd.destination, cmd.source],
+            Command::ZREM(cmd) => vec![cmd.key],
+            Command::ZREMRANGEBYLEX(cmd) => vec![cmd.key],
+            Command::ZREMRANGEBYRANK(cmd) => vec![cmd.key],
+            Command::ZREMRANGEBYSCORE(cmd) => vec![cmd.key],
+            Command::ZUNIONSTORE(cmd) => {
+                let mut keys = vec![cmd.destination];
+                keys.extend(&cmd.keys);
+                keys
+            }
+            Command::XACK(cmd) => vec![cmd.key],
+            Command::XADD(cmd) => vec![cmd.key],
+            Command::XAUTOCLAIM(cmd) => vec![cmd.key],
+            Command::XCLAIM(cmd) => vec![cmd.key],
+            Command::XDEL(cmd) => vec![cmd.key],
+            Command::XGROUP(cmd) => xgroup_key(cmd).into_iter().collect(),
+            Command::XSETID(cmd) => vec![cmd.key],
+            Command::XTRIM(cmd) => vec![cmd.key],
+            _ => Vec::new(),
+        }
+    }
+
+    /// 命令所操作的数据类型，不操作数据的命令(如`SELECT`、`PUBLISH`、`SCRIPT LOAD`)以及`Other`、`Module`返回None
+    ///
+    /// 脚本与Function可以操作任意类型的数据，同样返回None
+    pub fn data_type(&self) -> Option<DataType> {
+        let data_type = match self {
+            Command::APPEND(_)
+            | Command::BITFIELD(_)
+            | Command::BITFIELD_RO(_)
+            | Command::BITOP(_)
+            | Command::DECR(_)
+            | Command::DECRBY(_)
+            | Command::GETDEL(_)
+            | Command::GETEX(_)
+            | Command::GETSET(_)
+            | Command::INCR(_)
+            | Command::INCRBY(_)
+            | Command::MSET(_)
+            | Command::MSETNX(_)
+            | Command::PSETEX(_)
+            | Command::SET(_)
+            | Command::SETBIT(_)
+            | Command::SETEX(_)
+            | Command::SETNX(_)
+            | Command::SETRANGE(_) => DataType::String,
+            Command::BLMOVE(_)
+            | Command::BLMPOP(_)
+            | Command::BRPOPLPUSH(_)
+            | Command::LINSERT(_)
+            | Command::LMOVE(_)
+            | Command::LMPOP(_)
+            | Command::LPOP(_)
+            | Command::LPUSH(_)
+            | Command::LPUSHX(_)
+            | Command::LREM(_)
+            | Command::LSET(_)
+            | Command::LTRIM(_)
+            | Command::RPOP(_)
+            | Command::RPOPLPUSH(_)
+            | Command::RPUSH(_)
+            | Command::RPUSHX(_) => DataType::List,
+            Command::SADD(_)
+            | Command::SDIFFSTORE(_)
+            | Command::SINTERCARD(_)
+            | Command::SINTERSTORE(_)
+            | Command::SMOVE(_)
+            | Command::SREM(_)
+            | Command::SUNIONSTORE(_) => DataType::Set,
+            // Geo的数据以有序集合的形式保存
+            Command::GEOADD(_)
+            | Command::GEORADIUS(_)
+            | Command::GEORADIUSBYMEMBER(_)
+            | Command::GEOSEARCHSTORE(_)
+            | Command::ZADD(_)
+            | Command::ZDIFFSTORE(_)
+            | Command::ZINCRBY(_)
+            | Command::ZINTERSTORE(_)
+            | Command::ZMPOP(_)
+            | Command::ZPOPMAX(_)
+            | Command::ZPOPMIN(_)
+            | Command::ZRANGESTORE(_)
+            | Command::ZREM(_)
+            | Command::ZREMRANGEBYLEX(_)
+            | Command::ZREMRANGEBYRANK(_)
+            | Command::ZREMRANGEBYSCORE(_)
+            | Command::ZUNIONSTORE(_) => DataType::SortedSet,
+            Command::HDEL(_)
+            | Command::HEXPIRE(_)
+            | Command::HEXPIREAT(_)
+            | Command::HGETDEL(_)
+            | Command::HGETEX(_)
+            | Command::HINCRBY(_)
+            | Command::HMSET(_)
+            | Command::HPERSIST(_)
+            | Command::HPEXPIRE(_)
+            | Command::HPEXPIREAT(_)
+            | Command::HSET(_)
+            | Command::HSETNX(_) => DataType::Hash,
+            Command::XACK(_)
+            | Command::XADD(_)
+            | Command::XAUTOCLAIM(_)
+            | Command::XCLAIM(_)
+            | Command::XDEL(_)
+            | Command::XGROUP(_)
+            | Command::XSETID(_)
+            | Command::XTRIM(_) => DataType::Stream,
+            Command::PFADD(_) | Command::PFCOUNT(_) | Command::PFDEBUG(_) | Command::PFMERGE(_) => {
+                DataType::HyperLogLog
+            }
+            Command::COPY(_)
+            | Command::DEL(_)
+            | Command::EXPIRE(_)
+            | Command::EXPIREAT(_)
+            | Command::MIGRATE(_)
+            | Command::MOVE(_)
+            | Command::PERSIST(_)
+            | Command::PEXPIRE(_)
+            | Command::PEXPIREAT(_)
+            | Command::RENAME(_)
+            | Command::RENAMENX(_)
+            | Command::RESTORE(_)
+            | Command::SORT(_)
+            | Command::UNLINK(_) => DataType::Generic,
+            _ => return None,
+        };
+        Some(data_type)
+    }
+
+    /// 命令是否会修改数据，包括删除key以及修改过期时间
+    ///
+    /// 不修改数据的命令(如`SELECT`、`MULTI`、`PUBLISH`、`SCRIPT LOAD`、`CLUSTER`相关的命令)以及`Other`、`Module`返回false；
+    /// 脚本与Function所产生的修改在Redis 7.0之后以其中的各个写命令的形式传播，在此之前则无法得知是否修改了数据，均视为修改
+    pub fn is_write(&self) -> bool {
+        match self {
+            Command::BITFIELD_RO(_) | Command::PFCOUNT(_) => false,
+            // 未指定STORE时只是读取
+            Command::GEORADIUS(cmd) => cmd.options.store.is_some() || cmd.options.store_dist.is_some(),
+            Command::GEORADIUSBYMEMBER(cmd) => cmd.options.store.is_some() || cmd.options.store_dist.is_some(),
+            Command::SORT(cmd) => cmd.destination.is_some(),
+            Command::EVAL(_) | Command::EVALSHA(_) | Command::FCALL(_) => true,
+            Command::FLUSHALL(_) | Command::FLUSHDB(_) | Command::SWAPDB(_) => true,
+            _ => self.data_type().is_some(),
+        }
+    }
+
+    /// 命令是否会删除整个key(而不只是其中的元素)，包括`FLUSHDB`、`FLUSHALL`以及将key移出当前db的`MOVE`、`MIGRATE`
+    pub fn is_delete(&self) -> bool {
+        match self {
+            Command::DEL(_) | Command::UNLINK(_) | Command::GETDEL(_) => true,
+            Command::FLUSHALL(_) | Command::FLUSHDB(_) => true,
+            Command::MOVE(_) => true,
+            Command::MIGRATE(cmd) => cmd.copy != Some(true),
+            _ => false,
+        }
+    }
+
+    /// 命令是否会设置或者移除过期时间，包括Hash中field的过期时间
+    pub fn is_expire(&self) -> bool {
+        match self {
+            Command::EXPIRE(_)
+            | Command::EXPIREAT(_)
+            | Command::PEXPIRE(_)
+            | Command::PEXPIREAT(_)
+            | Command::PERSIST(_)
+            | Command::SETEX(_)
+            | Command::PSETEX(_) => true,
+            Command::SET(cmd) => cmd.expire.is_some(),
+            Command::GETEX(cmd) => cmd.expire.is_some() || cmd.persist == Some(true),
+            Command::HEXPIRE(_)
+            | Command::HEXPIREAT(_)
+            | Command::HPEXPIRE(_)
+            | Command::HPEXPIREAT(_)
+            | Command::HPERSIST(_) => true,
+            Command::HGETEX(cmd) => cmd.expire.is_some() || cmd.persist == Some(true),
+            _ => false,
+        }
+    }
+}
+
+fn xgroup_key<'a>(cmd: &XGROUP<'a>) -> Option<&'a [u8]> {
+    if let Some(create) = &cmd.create {
+        Some(create.key)
+    } else if let Some(set_id) = &cmd.set_id {
+        Some(set_id.key)
+    } else if let Some(destroy) = &cmd.destroy {
+        Some(destroy.key)
+    } else if let Some(create_consumer) = &cmd.create_consumer {
+        Some(create_consumer.key)
+    } else {
+        cmd.del_consumer.as_ref().map(|del_consumer| del_consumer.key)
+    }
+}
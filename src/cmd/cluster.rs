@@ -0,0 +1,116 @@
+/*!
+Cluster相关的命令定义、解析
+
+只包含会影响slot归属的管理命令，所有涉及到的命令参考[Redis Command Reference]
+
+[Redis Command Reference]: https://redis.io/commands#cluster
+*/
+
+use std::slice::Iter;
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERADDSLOTS<'a> {
+    pub slots: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_cluster_addslots(iter: Iter<Vec<u8>>) -> CLUSTERADDSLOTS {
+    let slots = iter.map(|slot| slot.as_slice()).collect();
+    CLUSTERADDSLOTS { slots }
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERADDSLOTSRANGE<'a> {
+    /// 左为起始slot，右为结束slot(包含)
+    pub ranges: Vec<(&'a [u8], &'a [u8])>,
+}
+
+pub(crate) fn parse_cluster_addslotsrange(iter: Iter<Vec<u8>>) -> CLUSTERADDSLOTSRANGE {
+    let ranges = parse_slot_ranges(iter);
+    CLUSTERADDSLOTSRANGE { ranges }
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERDELSLOTS<'a> {
+    pub slots: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_cluster_delslots(iter: Iter<Vec<u8>>) -> CLUSTERDELSLOTS {
+    let slots = iter.map(|slot| slot.as_slice()).collect();
+    CLUSTERDELSLOTS { slots }
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERDELSLOTSRANGE<'a> {
+    /// 左为起始slot，右为结束slot(包含)
+    pub ranges: Vec<(&'a [u8], &'a [u8])>,
+}
+
+pub(crate) fn parse_cluster_delslotsrange(iter: Iter<Vec<u8>>) -> CLUSTERDELSLOTSRANGE {
+    let ranges = parse_slot_ranges(iter);
+    CLUSTERDELSLOTSRANGE { ranges }
+}
+
+fn parse_slot_ranges<'a>(mut iter: Iter<'a, Vec<u8>>) -> Vec<(&'a [u8], &'a [u8])> {
+    let mut ranges = Vec::new();
+    while let Some(start) = iter.next() {
+        let end = iter.next().expect("CLUSTER SLOTSRANGE缺失end slot");
+        ranges.push((start.as_slice(), end.as_slice()));
+    }
+    ranges
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERFORGET<'a> {
+    pub node_id: &'a [u8],
+}
+
+pub(crate) fn parse_cluster_forget(mut iter: Iter<Vec<u8>>) -> CLUSTERFORGET {
+    let node_id = iter.next().unwrap();
+    CLUSTERFORGET { node_id }
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERSETCONFIGEPOCH<'a> {
+    pub config_epoch: &'a [u8],
+}
+
+pub(crate) fn parse_cluster_set_config_epoch(mut iter: Iter<Vec<u8>>) -> CLUSTERSETCONFIGEPOCH {
+    let config_epoch = iter.next().unwrap();
+    CLUSTERSETCONFIGEPOCH { config_epoch }
+}
+
+#[derive(Debug, Clone)]
+pub struct CLUSTERSETSLOT<'a> {
+    pub slot: &'a [u8],
+    pub state: SlotState<'a>,
+}
+
+/// slot的迁移状态，除STABLE外均带有对应节点的id
+#[derive(Debug, Clone)]
+pub enum SlotState<'a> {
+    // Set a hash slot in importing state.
+    IMPORTING(&'a [u8]),
+    // Set a hash slot in migrating state.
+    MIGRATING(&'a [u8]),
+    // Bind the hash slot to a different node.
+    NODE(&'a [u8]),
+    // Clear any importing / migrating state from hash slot.
+    STABLE,
+}
+
+pub(crate) fn parse_cluster_setslot(mut iter: Iter<Vec<u8>>) -> CLUSTERSETSLOT {
+    let slot = iter.next().unwrap();
+    let state = String::from_utf8_lossy(iter.next().unwrap()).to_uppercase();
+    let state = if &state == "IMPORTING" {
+        SlotState::IMPORTING(iter.next().unwrap())
+    } else if &state == "MIGRATING" {
+        SlotState::MIGRATING(iter.next().unwrap())
+    } else if &state == "NODE" {
+        SlotState::NODE(iter.next().unwrap())
+    } else if &state == "STABLE" {
+        SlotState::STABLE
+    } else {
+        panic!("Invalid argument")
+    };
+    CLUSTERSETSLOT { slot, state }
+}
@@ -8,7 +8,7 @@ Connection相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SELECT {
     pub db: i32,
 }
@@ -19,7 +19,7 @@ pub(crate) fn parse_select(mut iter: Iter<Vec<u8>>) -> SELECT {
     SELECT { db }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SWAPDB<'a> {
     pub index1: &'a [u8],
     pub index2: &'a [u8],
@@ -0,0 +1,243 @@
+/*!
+Geo相关的命令定义、解析
+
+所有涉及到的命令参考[Redis Command Reference]
+
+[Redis Command Reference]: https://redis.io/commands#geo
+*/
+
+use std::slice::Iter;
+
+use crate::cmd::keys::ORDER;
+use crate::cmd::strings::ExistType;
+
+#[derive(Debug, Clone)]
+pub struct GEOADD<'a> {
+    pub key: &'a [u8],
+    /// XX: 只更新现有的元素，不添加新的元素.
+    /// NX: 只添加新的元素，不更新现有的元素.
+    pub exist_type: Option<ExistType>,
+    pub ch: Option<bool>,
+    pub items: Vec<GeoItem<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeoItem<'a> {
+    pub longitude: &'a [u8],
+    pub latitude: &'a [u8],
+    pub member: &'a [u8],
+}
+
+pub(crate) fn parse_geoadd(mut iter: Iter<Vec<u8>>) -> GEOADD {
+    let key = iter.next().unwrap();
+    let mut exist_type = None;
+    let mut ch = None;
+    let mut items = Vec::new();
+    while let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "NX" {
+            exist_type = Some(ExistType::NX);
+        } else if &arg_upper == "XX" {
+            exist_type = Some(ExistType::XX);
+        } else if &arg_upper == "CH" {
+            ch = Some(true);
+        } else {
+            // 依次为经度、纬度、member
+            let latitude = iter.next().unwrap();
+            let member = iter.next().unwrap();
+            items.push(GeoItem {
+                longitude: next_arg,
+                latitude,
+                member,
+            });
+        }
+    }
+    GEOADD {
+        key,
+        exist_type,
+        ch,
+        items,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GEOSEARCHSTORE<'a> {
+    pub destination: &'a [u8],
+    pub source: &'a [u8],
+    pub from: GeoFrom<'a>,
+    pub shape: GeoShape<'a>,
+    pub order: Option<ORDER>,
+    pub count: Option<&'a [u8]>,
+    pub any: Option<bool>,
+    /// 保存的是元素与中心点的距离，而不是其geohash
+    pub store_dist: Option<bool>,
+}
+
+/// 搜索的中心点
+#[derive(Debug, Clone)]
+pub enum GeoFrom<'a> {
+    FROMMEMBER(&'a [u8]),
+    /// 左为经度，右为纬度
+    FROMLONLAT(&'a [u8], &'a [u8]),
+}
+
+/// 搜索的范围
+#[derive(Debug, Clone)]
+pub enum GeoShape<'a> {
+    /// 半径以及单位
+    BYRADIUS(&'a [u8], &'a [u8]),
+    /// 宽、高以及单位
+    BYBOX(&'a [u8], &'a [u8], &'a [u8]),
+}
+
+pub(crate) fn parse_geosearchstore(mut iter: Iter<Vec<u8>>) -> GEOSEARCHSTORE {
+    let destination = iter.next().unwrap();
+    let source = iter.next().unwrap();
+    let mut from = None;
+    let mut shape = None;
+    let mut order = None;
+    let mut count = None;
+    let mut any = None;
+    let mut store_dist = None;
+    while let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "FROMMEMBER" {
+            from = Some(GeoFrom::FROMMEMBER(iter.next().unwrap()));
+        } else if &arg_upper == "FROMLONLAT" {
+            let longitude = iter.next().unwrap();
+            let latitude = iter.next().unwrap();
+            from = Some(GeoFrom::FROMLONLAT(longitude, latitude));
+        } else if &arg_upper == "BYRADIUS" {
+            let radius = iter.next().unwrap();
+            let unit = iter.next().unwrap();
+            shape = Some(GeoShape::BYRADIUS(radius, unit));
+        } else if &arg_upper == "BYBOX" {
+            let width = iter.next().unwrap();
+            let height = iter.next().unwrap();
+            let unit = iter.next().unwrap();
+            shape = Some(GeoShape::BYBOX(width, height, unit));
+        } else if &arg_upper == "ASC" {
+            order = Some(ORDER::ASC);
+        } else if &arg_upper == "DESC" {
+            order = Some(ORDER::DESC);
+        } else if &arg_upper == "COUNT" {
+            count = Some(iter.next().unwrap().as_slice());
+        } else if &arg_upper == "ANY" {
+            any = Some(true);
+        } else if &arg_upper == "STOREDIST" {
+            store_dist = Some(true);
+        }
+    }
+    GEOSEARCHSTORE {
+        destination,
+        source,
+        from: from.expect("缺失FROMMEMBER或FROMLONLAT参数"),
+        shape: shape.expect("缺失BYRADIUS或BYBOX参数"),
+        order,
+        count,
+        any,
+        store_dist,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GEORADIUS<'a> {
+    pub key: &'a [u8],
+    pub longitude: &'a [u8],
+    pub latitude: &'a [u8],
+    pub radius: &'a [u8],
+    pub unit: &'a [u8],
+    pub options: RadiusOptions<'a>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GEORADIUSBYMEMBER<'a> {
+    pub key: &'a [u8],
+    pub member: &'a [u8],
+    pub radius: &'a [u8],
+    pub unit: &'a [u8],
+    pub options: RadiusOptions<'a>,
+}
+
+/// GEORADIUS与GEORADIUSBYMEMBER共有的选项
+#[derive(Debug, Clone)]
+pub struct RadiusOptions<'a> {
+    pub with_coord: Option<bool>,
+    pub with_dist: Option<bool>,
+    pub with_hash: Option<bool>,
+    pub count: Option<&'a [u8]>,
+    pub any: Option<bool>,
+    pub order: Option<ORDER>,
+    /// 保存元素及其geohash的key
+    pub store: Option<&'a [u8]>,
+    /// 保存元素及其与中心点距离的key
+    pub store_dist: Option<&'a [u8]>,
+}
+
+pub(crate) fn parse_georadius(mut iter: Iter<Vec<u8>>) -> GEORADIUS {
+    let key = iter.next().unwrap();
+    let longitude = iter.next().unwrap();
+    let latitude = iter.next().unwrap();
+    let radius = iter.next().unwrap();
+    let unit = iter.next().unwrap();
+    let options = parse_radius_options(iter);
+    GEORADIUS {
+        key,
+        longitude,
+        latitude,
+        radius,
+        unit,
+        options,
+    }
+}
+
+pub(crate) fn parse_georadiusbymember(mut iter: Iter<Vec<u8>>) -> GEORADIUSBYMEMBER {
+    let key = iter.next().unwrap();
+    let member = iter.next().unwrap();
+    let radius = iter.next().unwrap();
+    let unit = iter.next().unwrap();
+    let options = parse_radius_options(iter);
+    GEORADIUSBYMEMBER {
+        key,
+        member,
+        radius,
+        unit,
+        options,
+    }
+}
+
+fn parse_radius_options<'a>(mut iter: Iter<'a, Vec<u8>>) -> RadiusOptions<'a> {
+    let mut options = RadiusOptions {
+        with_coord: None,
+        with_dist: None,
+        with_hash: None,
+        count: None,
+        any: None,
+        order: None,
+        store: None,
+        store_dist: None,
+    };
+    while let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "WITHCOORD" {
+            options.with_coord = Some(true);
+        } else if &arg_upper == "WITHDIST" {
+            options.with_dist = Some(true);
+        } else if &arg_upper == "WITHHASH" {
+            options.with_hash = Some(true);
+        } else if &arg_upper == "COUNT" {
+            options.count = Some(iter.next().unwrap().as_slice());
+        } else if &arg_upper == "ANY" {
+            options.any = Some(true);
+        } else if &arg_upper == "ASC" {
+            options.order = Some(ORDER::ASC);
+        } else if &arg_upper == "DESC" {
+            options.order = Some(ORDER::DESC);
+        } else if &arg_upper == "STORE" {
+            options.store = Some(iter.next().unwrap().as_slice());
+        } else if &arg_upper == "STOREDIST" {
+            options.store_dist = Some(iter.next().unwrap().as_slice());
+        }
+    }
+    options
+}
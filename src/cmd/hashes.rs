@@ -8,7 +8,9 @@ Hashes相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+use crate::cmd::strings::ExpireType;
+
+#[derive(Debug, Clone)]
 pub struct HDEL<'a> {
     pub key: &'a [u8],
     pub fields: Vec<&'a [u8]>,
@@ -23,7 +25,147 @@ pub(crate) fn parse_hdel(mut iter: Iter<Vec<u8>>) -> HDEL {
     HDEL { key, fields }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct HEXPIRE<'a> {
+    pub key: &'a [u8],
+    pub seconds: &'a [u8],
+    pub condition: Option<ExpireCondition>,
+    pub fields: Vec<&'a [u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HEXPIREAT<'a> {
+    pub key: &'a [u8],
+    pub timestamp: &'a [u8],
+    pub condition: Option<ExpireCondition>,
+    pub fields: Vec<&'a [u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpireCondition {
+    // Set expiration only when the field has no expiration.
+    NX,
+    // Set expiration only when the field has an existing expiration.
+    XX,
+    // Set expiration only when the new expiration is greater than current one.
+    GT,
+    // Set expiration only when the new expiration is less than current one.
+    LT,
+}
+
+pub(crate) fn parse_hexpire(mut iter: Iter<Vec<u8>>) -> HEXPIRE {
+    let key = iter.next().unwrap();
+    let seconds = iter.next().unwrap();
+    let (condition, fields) = parse_condition_fields(iter);
+    HEXPIRE {
+        key,
+        seconds,
+        condition,
+        fields,
+    }
+}
+
+pub(crate) fn parse_hexpireat(mut iter: Iter<Vec<u8>>) -> HEXPIREAT {
+    let key = iter.next().unwrap();
+    let timestamp = iter.next().unwrap();
+    let (condition, fields) = parse_condition_fields(iter);
+    HEXPIREAT {
+        key,
+        timestamp,
+        condition,
+        fields,
+    }
+}
+
+// 解析HEXPIRE等命令中可选的NX|XX|GT|LT以及其后的FIELDS
+fn parse_condition_fields<'a>(mut iter: Iter<'a, Vec<u8>>) -> (Option<ExpireCondition>, Vec<&'a [u8]>) {
+    let mut condition = None;
+    while let Some(arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(arg).to_uppercase();
+        if &arg_upper == "NX" {
+            condition = Some(ExpireCondition::NX);
+        } else if &arg_upper == "XX" {
+            condition = Some(ExpireCondition::XX);
+        } else if &arg_upper == "GT" {
+            condition = Some(ExpireCondition::GT);
+        } else if &arg_upper == "LT" {
+            condition = Some(ExpireCondition::LT);
+        } else if &arg_upper == "FIELDS" {
+            return (condition, parse_fields(iter));
+        }
+    }
+    panic!("缺失FIELDS参数")
+}
+
+// 解析FIELDS之后的numfields以及各个field
+fn parse_fields<'a>(mut iter: Iter<'a, Vec<u8>>) -> Vec<&'a [u8]> {
+    let num_fields = String::from_utf8_lossy(iter.next().unwrap());
+    let num_fields = num_fields.parse::<usize>().unwrap();
+    let mut fields = Vec::with_capacity(num_fields);
+    for _ in 0..num_fields {
+        fields.push(iter.next().unwrap().as_slice());
+    }
+    fields
+}
+
+#[derive(Debug, Clone)]
+pub struct HGETDEL<'a> {
+    pub key: &'a [u8],
+    pub fields: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_hgetdel(mut iter: Iter<Vec<u8>>) -> HGETDEL {
+    let key = iter.next().unwrap();
+    let arg = String::from_utf8_lossy(iter.next().unwrap()).to_uppercase();
+    if &arg != "FIELDS" {
+        panic!("缺失FIELDS参数");
+    }
+    let fields = parse_fields(iter);
+    HGETDEL { key, fields }
+}
+
+#[derive(Debug, Clone)]
+pub struct HGETEX<'a> {
+    pub key: &'a [u8],
+    pub expire: Option<(ExpireType, &'a Vec<u8>)>,
+    pub persist: Option<bool>,
+    pub fields: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_hgetex(mut iter: Iter<Vec<u8>>) -> HGETEX {
+    let key = iter.next().unwrap();
+    let mut expire = None;
+    let mut persist = None;
+    while let Some(arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(arg).to_uppercase();
+        let expire_type = if &arg_upper == "EX" {
+            ExpireType::EX
+        } else if &arg_upper == "PX" {
+            ExpireType::PX
+        } else if &arg_upper == "EXAT" {
+            ExpireType::EXAT
+        } else if &arg_upper == "PXAT" {
+            ExpireType::PXAT
+        } else if &arg_upper == "PERSIST" {
+            persist = Some(true);
+            continue;
+        } else if &arg_upper == "FIELDS" {
+            let fields = parse_fields(iter);
+            return HGETEX {
+                key,
+                expire,
+                persist,
+                fields,
+            };
+        } else {
+            continue;
+        };
+        expire = Some((expire_type, iter.next().unwrap()));
+    }
+    panic!("缺失FIELDS参数")
+}
+
+#[derive(Debug, Clone)]
 pub struct HINCRBY<'a> {
     pub key: &'a [u8],
     pub field: &'a [u8],
@@ -37,19 +179,19 @@ pub(crate) fn parse_hincrby(mut iter: Iter<Vec<u8>>) -> HINCRBY {
     HINCRBY { key, field, increment }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HMSET<'a> {
     pub key: &'a [u8],
     pub fields: Vec<Field<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HSET<'a> {
     pub key: &'a [u8],
     pub fields: Vec<Field<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Field<'a> {
     pub name: &'a [u8],
     pub value: &'a [u8],
@@ -91,7 +233,59 @@ pub(crate) fn parse_hset(mut iter: Iter<Vec<u8>>) -> HSET {
     HSET { key, fields }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct HPERSIST<'a> {
+    pub key: &'a [u8],
+    pub fields: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_hpersist(mut iter: Iter<Vec<u8>>) -> HPERSIST {
+    let key = iter.next().unwrap();
+    let (_, fields) = parse_condition_fields(iter);
+    HPERSIST { key, fields }
+}
+
+#[derive(Debug, Clone)]
+pub struct HPEXPIRE<'a> {
+    pub key: &'a [u8],
+    pub milliseconds: &'a [u8],
+    pub condition: Option<ExpireCondition>,
+    pub fields: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_hpexpire(mut iter: Iter<Vec<u8>>) -> HPEXPIRE {
+    let key = iter.next().unwrap();
+    let milliseconds = iter.next().unwrap();
+    let (condition, fields) = parse_condition_fields(iter);
+    HPEXPIRE {
+        key,
+        milliseconds,
+        condition,
+        fields,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HPEXPIREAT<'a> {
+    pub key: &'a [u8],
+    pub mill_timestamp: &'a [u8],
+    pub condition: Option<ExpireCondition>,
+    pub fields: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_hpexpireat(mut iter: Iter<Vec<u8>>) -> HPEXPIREAT {
+    let key = iter.next().unwrap();
+    let mill_timestamp = iter.next().unwrap();
+    let (condition, fields) = parse_condition_fields(iter);
+    HPEXPIREAT {
+        key,
+        mill_timestamp,
+        condition,
+        fields,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct HSETNX<'a> {
     pub key: &'a [u8],
     pub field: &'a [u8],
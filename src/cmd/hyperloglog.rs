@@ -8,7 +8,7 @@ HyperLogLog相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PFADD<'a> {
     pub key: &'a [u8],
     pub elements: Vec<&'a [u8]>,
@@ -23,7 +23,8 @@ pub(crate) fn parse_pfadd(mut iter: Iter<Vec<u8>>) -> PFADD {
     PFADD { key, elements }
 }
 
-#[derive(Debug)]
+/// 计算基数时会更新HyperLogLog中缓存的基数，因此Redis也会将PFCOUNT传播给从节点
+#[derive(Debug, Clone)]
 pub struct PFCOUNT<'a> {
     pub keys: Vec<&'a [u8]>,
 }
@@ -36,7 +37,20 @@ pub(crate) fn parse_pfcount(mut iter: Iter<Vec<u8>>) -> PFCOUNT {
     PFCOUNT { keys }
 }
 
-#[derive(Debug)]
+/// 只有会修改数据的子命令(如TODENSE)才会被传播
+#[derive(Debug, Clone)]
+pub struct PFDEBUG<'a> {
+    pub subcommand: &'a [u8],
+    pub key: &'a [u8],
+}
+
+pub(crate) fn parse_pfdebug(mut iter: Iter<Vec<u8>>) -> PFDEBUG {
+    let subcommand = iter.next().unwrap();
+    let key = iter.next().unwrap();
+    PFDEBUG { subcommand, key }
+}
+
+#[derive(Debug, Clone)]
 pub struct PFMERGE<'a> {
     pub dest_key: &'a [u8],
     pub source_keys: Vec<&'a [u8]>,
@@ -6,11 +6,48 @@ Keys相关的命令定义、解析
 [Redis Command Reference]: https://redis.io/commands#generic
 */
 
+use std::io::{Error, ErrorKind, Result};
 use std::slice::Iter;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::cmd::keys::ORDER::{ASC, DESC};
+use crate::dump;
+use crate::rdb::{ExpireType, Meta};
+use crate::EventHandler;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct COPY<'a> {
+    pub source: &'a [u8],
+    pub destination: &'a [u8],
+    /// 目标key所在的db，None为当前db
+    pub db: Option<&'a [u8]>,
+    pub replace: Option<bool>,
+}
+
+pub(crate) fn parse_copy(mut iter: Iter<Vec<u8>>) -> COPY {
+    let source = iter.next().unwrap();
+    let destination = iter.next().unwrap();
+    let mut db = None;
+    let mut replace = None;
+    while let Some(arg) = iter.next() {
+        let arg_string = String::from_utf8_lossy(arg);
+        let p_arg = &arg_string.to_uppercase();
+        if p_arg == "DB" {
+            let next_arg = iter.next().unwrap();
+            db = Some(next_arg.as_slice());
+        } else if p_arg == "REPLACE" {
+            replace = Some(true);
+        }
+    }
+    COPY {
+        source,
+        destination,
+        db,
+        replace,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DEL<'a> {
     pub keys: Vec<&'a Vec<u8>>,
 }
@@ -23,7 +60,7 @@ pub(crate) fn parse_del(iter: Iter<Vec<u8>>) -> DEL {
     DEL { keys }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PERSIST<'a> {
     pub key: &'a [u8],
 }
@@ -33,7 +70,7 @@ pub(crate) fn parse_persist(mut iter: Iter<Vec<u8>>) -> PERSIST {
     PERSIST { key }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EXPIRE<'a> {
     pub key: &'a [u8],
     pub seconds: &'a [u8],
@@ -45,7 +82,7 @@ pub(crate) fn parse_expire(mut iter: Iter<Vec<u8>>) -> EXPIRE {
     EXPIRE { key, seconds }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PEXPIRE<'a> {
     pub key: &'a [u8],
     pub milliseconds: &'a [u8],
@@ -57,7 +94,7 @@ pub(crate) fn parse_pexpire(mut iter: Iter<Vec<u8>>) -> PEXPIRE {
     PEXPIRE { key, milliseconds }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EXPIREAT<'a> {
     pub key: &'a [u8],
     pub timestamp: &'a [u8],
@@ -69,7 +106,7 @@ pub(crate) fn parse_expireat(mut iter: Iter<Vec<u8>>) -> EXPIREAT {
     EXPIREAT { key, timestamp }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PEXPIREAT<'a> {
     pub key: &'a [u8],
     pub mill_timestamp: &'a [u8],
@@ -81,7 +118,7 @@ pub(crate) fn parse_pexpireat(mut iter: Iter<Vec<u8>>) -> PEXPIREAT {
     PEXPIREAT { key, mill_timestamp }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MOVE<'a> {
     pub key: &'a [u8],
     pub db: &'a [u8],
@@ -93,7 +130,7 @@ pub(crate) fn parse_move(mut iter: Iter<Vec<u8>>) -> MOVE {
     MOVE { key, db }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RENAME<'a> {
     pub key: &'a [u8],
     pub new_key: &'a [u8],
@@ -105,7 +142,7 @@ pub(crate) fn parse_rename(mut iter: Iter<Vec<u8>>) -> RENAME {
     RENAME { key, new_key }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RENAMENX<'a> {
     pub key: &'a [u8],
     pub new_key: &'a [u8],
@@ -117,7 +154,7 @@ pub(crate) fn parse_renamenx(mut iter: Iter<Vec<u8>>) -> RENAMENX {
     RENAMENX { key, new_key }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RESTORE<'a> {
     pub key: &'a [u8],
     pub ttl: &'a [u8],
@@ -159,7 +196,92 @@ pub(crate) fn parse_restore(mut iter: Iter<Vec<u8>>) -> RESTORE {
     }
 }
 
-#[derive(Debug)]
+impl RESTORE<'_> {
+    /// 将`value`中的DUMP格式的数据解析为`Event::RDB`事件，`db`为执行此命令时所在的db
+    ///
+    /// 事件中的过期时间由`ttl`得出，未指定`ABSTTL`时以当前时间为基准计算
+    pub fn parse_value(&self, db: isize, event_handler: &mut dyn EventHandler) -> Result<()> {
+        let ttl = String::from_utf8_lossy(self.ttl);
+        let ttl = match ttl.parse::<i64>() {
+            Ok(ttl) => ttl,
+            Err(_) => return Err(Error::new(ErrorKind::InvalidData, format!("无效的ttl: {}", ttl))),
+        };
+        let expire = if ttl <= 0 {
+            None
+        } else if self.abs_ttl == Some(true) {
+            Some((ExpireType::Millisecond, ttl))
+        } else {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+            Some((ExpireType::Millisecond, now + ttl))
+        };
+        let meta = Meta {
+            db,
+            expire,
+            evict: None,
+        };
+        dump::parse(self.key, self.value, &meta, event_handler)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MIGRATE<'a> {
+    pub host: &'a [u8],
+    pub port: &'a [u8],
+    /// 使用KEYS选项时为空
+    pub key: &'a [u8],
+    pub destination_db: &'a [u8],
+    pub timeout: &'a [u8],
+    pub copy: Option<bool>,
+    pub replace: Option<bool>,
+    pub auth: Option<&'a [u8]>,
+    /// 左为用户名，右为密码
+    pub auth2: Option<(&'a [u8], &'a [u8])>,
+    pub keys: Option<Vec<&'a [u8]>>,
+}
+
+pub(crate) fn parse_migrate(mut iter: Iter<Vec<u8>>) -> MIGRATE {
+    let host = iter.next().unwrap();
+    let port = iter.next().unwrap();
+    let key = iter.next().unwrap();
+    let destination_db = iter.next().unwrap();
+    let timeout = iter.next().unwrap();
+    let mut copy = None;
+    let mut replace = None;
+    let mut auth = None;
+    let mut auth2 = None;
+    let mut keys = None;
+    while let Some(next_arg) = iter.next() {
+        let arg = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg == "COPY" {
+            copy = Some(true);
+        } else if &arg == "REPLACE" {
+            replace = Some(true);
+        } else if &arg == "AUTH" {
+            auth = Some(iter.next().unwrap().as_slice());
+        } else if &arg == "AUTH2" {
+            let username = iter.next().unwrap().as_slice();
+            let password = iter.next().unwrap().as_slice();
+            auth2 = Some((username, password));
+        } else if &arg == "KEYS" {
+            // KEYS之后的参数都是key
+            keys = Some(iter.by_ref().map(|key| key.as_slice()).collect());
+        }
+    }
+    MIGRATE {
+        host,
+        port,
+        key,
+        destination_db,
+        timeout,
+        copy,
+        replace,
+        auth,
+        auth2,
+        keys,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct SORT<'a> {
     pub key: &'a [u8],
     pub by_pattern: Option<&'a [u8]>,
@@ -170,13 +292,13 @@ pub struct SORT<'a> {
     pub destination: Option<&'a [u8]>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LIMIT<'a> {
     pub offset: &'a [u8],
     pub count: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ORDER {
     ASC,
     DESC,
@@ -229,7 +351,7 @@ pub(crate) fn parse_sort(mut iter: Iter<Vec<u8>>) -> SORT {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UNLINK<'a> {
     pub keys: Vec<&'a [u8]>,
 }
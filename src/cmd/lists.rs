@@ -10,7 +10,7 @@ use std::slice::Iter;
 
 use crate::cmd::lists::POSITION::{AFTER, BEFORE};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BRPOPLPUSH<'a> {
     pub source: &'a [u8],
     pub destination: &'a [u8],
@@ -28,7 +28,31 @@ pub(crate) fn parse_brpoplpush(mut iter: Iter<Vec<u8>>) -> BRPOPLPUSH {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct BLMOVE<'a> {
+    pub source: &'a [u8],
+    pub destination: &'a [u8],
+    pub wherefrom: DIRECTION,
+    pub whereto: DIRECTION,
+    pub timeout: &'a [u8],
+}
+
+pub(crate) fn parse_blmove(mut iter: Iter<Vec<u8>>) -> BLMOVE {
+    let source = iter.next().unwrap();
+    let destination = iter.next().unwrap();
+    let wherefrom = parse_direction(iter.next().unwrap());
+    let whereto = parse_direction(iter.next().unwrap());
+    let timeout = iter.next().unwrap();
+    BLMOVE {
+        source,
+        destination,
+        wherefrom,
+        whereto,
+        timeout,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LINSERT<'a> {
     pub key: &'a [u8],
     pub position: POSITION,
@@ -36,7 +60,7 @@ pub struct LINSERT<'a> {
     pub element: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum POSITION {
     BEFORE,
     AFTER,
@@ -62,17 +86,40 @@ pub(crate) fn parse_linsert(mut iter: Iter<Vec<u8>>) -> LINSERT {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct LMOVE<'a> {
+    pub source: &'a [u8],
+    pub destination: &'a [u8],
+    pub wherefrom: DIRECTION,
+    pub whereto: DIRECTION,
+}
+
+pub(crate) fn parse_lmove(mut iter: Iter<Vec<u8>>) -> LMOVE {
+    let source = iter.next().unwrap();
+    let destination = iter.next().unwrap();
+    let wherefrom = parse_direction(iter.next().unwrap());
+    let whereto = parse_direction(iter.next().unwrap());
+    LMOVE {
+        source,
+        destination,
+        wherefrom,
+        whereto,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LPOP<'a> {
     pub key: &'a [u8],
+    pub count: Option<&'a [u8]>,
 }
 
 pub(crate) fn parse_lpop(mut iter: Iter<Vec<u8>>) -> LPOP {
     let key = iter.next().unwrap();
-    LPOP { key }
+    let count = iter.next().map(|count| count.as_slice());
+    LPOP { key, count }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LPUSH<'a> {
     pub key: &'a [u8],
     pub elements: Vec<&'a [u8]>,
@@ -87,7 +134,7 @@ pub(crate) fn parse_lpush(mut iter: Iter<Vec<u8>>) -> LPUSH {
     LPUSH { key, elements }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LPUSHX<'a> {
     pub key: &'a [u8],
     pub elements: Vec<&'a [u8]>,
@@ -102,7 +149,7 @@ pub(crate) fn parse_lpushx(mut iter: Iter<Vec<u8>>) -> LPUSHX {
     LPUSHX { key, elements }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LREM<'a> {
     pub key: &'a [u8],
     pub count: &'a [u8],
@@ -116,7 +163,7 @@ pub(crate) fn parse_lrem(mut iter: Iter<Vec<u8>>) -> LREM {
     LREM { key, count, element }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LSET<'a> {
     pub key: &'a [u8],
     pub index: &'a [u8],
@@ -130,7 +177,7 @@ pub(crate) fn parse_lset(mut iter: Iter<Vec<u8>>) -> LSET {
     LSET { key, index, element }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LTRIM<'a> {
     pub key: &'a [u8],
     pub start: &'a [u8],
@@ -144,17 +191,19 @@ pub(crate) fn parse_ltrim(mut iter: Iter<Vec<u8>>) -> LTRIM {
     LTRIM { key, start, stop }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RPOP<'a> {
     pub key: &'a [u8],
+    pub count: Option<&'a [u8]>,
 }
 
 pub(crate) fn parse_rpop(mut iter: Iter<Vec<u8>>) -> RPOP {
     let key = iter.next().unwrap();
-    RPOP { key }
+    let count = iter.next().map(|count| count.as_slice());
+    RPOP { key, count }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RPOPLPUSH<'a> {
     pub source: &'a [u8],
     pub destination: &'a [u8],
@@ -166,7 +215,7 @@ pub(crate) fn parse_rpoplpush(mut iter: Iter<Vec<u8>>) -> RPOPLPUSH {
     RPOPLPUSH { source, destination }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RPUSH<'a> {
     pub key: &'a [u8],
     pub elements: Vec<&'a [u8]>,
@@ -181,7 +230,7 @@ pub(crate) fn parse_rpush(mut iter: Iter<Vec<u8>>) -> RPUSH {
     RPUSH { key, elements }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RPUSHX<'a> {
     pub key: &'a [u8],
     pub elements: Vec<&'a [u8]>,
@@ -195,3 +244,78 @@ pub(crate) fn parse_rpushx(mut iter: Iter<Vec<u8>>) -> RPUSHX {
     }
     RPUSHX { key, elements }
 }
+
+#[derive(Debug, Clone)]
+pub struct LMPOP<'a> {
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+    pub direction: DIRECTION,
+    pub count: Option<&'a [u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub enum DIRECTION {
+    LEFT,
+    RIGHT,
+}
+
+pub(crate) fn parse_lmpop(mut iter: Iter<Vec<u8>>) -> LMPOP {
+    let (num_keys, keys, direction, count) = parse_mpop(&mut iter);
+    LMPOP {
+        num_keys,
+        keys,
+        direction,
+        count,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BLMPOP<'a> {
+    pub timeout: &'a [u8],
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+    pub direction: DIRECTION,
+    pub count: Option<&'a [u8]>,
+}
+
+pub(crate) fn parse_blmpop(mut iter: Iter<Vec<u8>>) -> BLMPOP {
+    let timeout = iter.next().unwrap();
+    let (num_keys, keys, direction, count) = parse_mpop(&mut iter);
+    BLMPOP {
+        timeout,
+        num_keys,
+        keys,
+        direction,
+        count,
+    }
+}
+
+// LMPOP以及BLMPOP的公共部分: numkeys key [key ...] LEFT|RIGHT [COUNT count]
+fn parse_mpop<'a>(iter: &mut Iter<'a, Vec<u8>>) -> (i32, Vec<&'a [u8]>, DIRECTION, Option<&'a [u8]>) {
+    let num_keys = String::from_utf8_lossy(iter.next().unwrap());
+    let num_keys = num_keys.parse::<i32>().unwrap();
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        let next_key = iter.next().unwrap();
+        keys.push(next_key.as_slice());
+    }
+    let direction = parse_direction(iter.next().unwrap());
+    let mut count = None;
+    while let Some(next_arg) = iter.next() {
+        if String::from_utf8_lossy(next_arg).to_uppercase() == "COUNT" {
+            count = Some(iter.next().unwrap().as_slice());
+        }
+    }
+    (num_keys, keys, direction, count)
+}
+
+fn parse_direction(arg: &[u8]) -> DIRECTION {
+    let direction = String::from_utf8_lossy(arg).to_uppercase();
+    if &direction == "LEFT" {
+        DIRECTION::LEFT
+    } else if &direction == "RIGHT" {
+        DIRECTION::RIGHT
+    } else {
+        panic!("方向只能为LEFT或RIGHT: {}", direction);
+    }
+}
@@ -4,6 +4,7 @@
 此模块包括:
 - 所有支持的Redis命令定义，见于枚举[Command]及各个子模块
 - 相关Redis命令的解析代码，见于各个子模块
+- 命令所涉及的key及其分类，见于`Command::keys`、`Command::data_type`、`Command::is_write`等方法
 
 子模块的命名与分组，按照[Redis Command Reference]中的`filter by group`进行命名与分组，各个命令所对应的结构体中的字段命名已尽可能和文档中的保持一致。
 
@@ -12,21 +13,37 @@
 [Command]: enum.Command.html
 [Redis Command Reference]: https://redis.io/commands
 */
+use std::collections::HashMap;
+use std::slice::Iter;
+use std::sync::{Arc, Mutex};
+
+use crate::cmd::cluster::{
+    CLUSTERADDSLOTS, CLUSTERADDSLOTSRANGE, CLUSTERDELSLOTS, CLUSTERDELSLOTSRANGE, CLUSTERFORGET, CLUSTERSETCONFIGEPOCH,
+    CLUSTERSETSLOT,
+};
 use crate::cmd::connection::{SELECT, SWAPDB};
+use crate::cmd::geo::{GEOADD, GEORADIUS, GEORADIUSBYMEMBER, GEOSEARCHSTORE};
 use crate::cmd::hashes::*;
-use crate::cmd::hyperloglog::{PFADD, PFCOUNT, PFMERGE};
+use crate::cmd::hyperloglog::{PFADD, PFCOUNT, PFDEBUG, PFMERGE};
 use crate::cmd::keys::*;
 use crate::cmd::lists::*;
 use crate::cmd::pub_sub::PUBLISH;
-use crate::cmd::scripting::{EVAL, EVALSHA, SCRIPTLOAD};
+use crate::cmd::scripting::{
+    EVAL, EVALSHA, FCALL, FUNCTIONDELETE, FUNCTIONFLUSH, FUNCTIONLOAD, FUNCTIONRESTORE, SCRIPTLOAD,
+};
 use crate::cmd::server::{FLUSHALL, FLUSHDB};
 use crate::cmd::sets::*;
 use crate::cmd::sorted_sets::*;
-use crate::cmd::streams::{XACK, XADD, XCLAIM, XDEL, XGROUP, XTRIM};
+use crate::cmd::streams::{XACK, XADD, XAUTOCLAIM, XCLAIM, XDEL, XGROUP, XSETID, XTRIM};
 use crate::cmd::strings::*;
-use crate::{Event, EventHandler};
+use crate::rdb::Module;
+use crate::{CommandParser, Event, EventHandler};
 
+mod args;
+mod classify;
+pub mod cluster;
 pub mod connection;
+pub mod geo;
 pub mod hashes;
 pub mod hyperloglog;
 pub mod keys;
@@ -41,13 +58,28 @@ pub mod strings;
 
 /// 所有支持的Redis命令
 ///
-/// 不在此枚举中的Redis命令均不支持
-#[derive(Debug)]
+/// 不在此枚举中的Redis命令(包括SCRIPT、FUNCTION、CLUSTER中未识别的子命令)以`Other`的形式产生，
+/// 其中保留了原始的命令名及参数，不会被丢弃；通过[CommandParsers]注册了解析器的命令则以`Module`的形式产生
+#[derive(Debug, Clone)]
 pub enum Command<'a> {
     APPEND(&'a APPEND<'a>),
     BITFIELD(&'a BITFIELD<'a>),
+    #[allow(non_camel_case_types)]
+    BITFIELD_RO(&'a BITFIELD_RO<'a>),
     BITOP(&'a BITOP<'a>),
+    BLMOVE(&'a BLMOVE<'a>),
+    BLMPOP(&'a BLMPOP<'a>),
     BRPOPLPUSH(&'a BRPOPLPUSH<'a>),
+    CLUSTERADDSLOTS(&'a CLUSTERADDSLOTS<'a>),
+    CLUSTERADDSLOTSRANGE(&'a CLUSTERADDSLOTSRANGE<'a>),
+    CLUSTERBUMPEPOCH,
+    CLUSTERDELSLOTS(&'a CLUSTERDELSLOTS<'a>),
+    CLUSTERDELSLOTSRANGE(&'a CLUSTERDELSLOTSRANGE<'a>),
+    CLUSTERFLUSHSLOTS,
+    CLUSTERFORGET(&'a CLUSTERFORGET<'a>),
+    CLUSTERSETCONFIGEPOCH(&'a CLUSTERSETCONFIGEPOCH<'a>),
+    CLUSTERSETSLOT(&'a CLUSTERSETSLOT<'a>),
+    COPY(&'a COPY<'a>),
     DECR(&'a DECR<'a>),
     DECRBY(&'a DECRBY<'a>),
     DEL(&'a DEL<'a>),
@@ -56,23 +88,44 @@ pub enum Command<'a> {
     EXPIRE(&'a EXPIRE<'a>),
     EXPIREAT(&'a EXPIREAT<'a>),
     EXEC,
+    FCALL(&'a FCALL<'a>),
     FLUSHALL(&'a FLUSHALL),
     FLUSHDB(&'a FLUSHDB),
+    FUNCTIONDELETE(&'a FUNCTIONDELETE<'a>),
+    FUNCTIONFLUSH(&'a FUNCTIONFLUSH),
+    FUNCTIONLOAD(&'a FUNCTIONLOAD<'a>),
+    FUNCTIONRESTORE(&'a FUNCTIONRESTORE<'a>),
+    GEOADD(&'a GEOADD<'a>),
+    GEORADIUS(&'a GEORADIUS<'a>),
+    GEORADIUSBYMEMBER(&'a GEORADIUSBYMEMBER<'a>),
+    GEOSEARCHSTORE(&'a GEOSEARCHSTORE<'a>),
+    GETDEL(&'a GETDEL<'a>),
+    GETEX(&'a GETEX<'a>),
     GETSET(&'a GETSET<'a>),
     HDEL(&'a HDEL<'a>),
+    HEXPIRE(&'a HEXPIRE<'a>),
+    HEXPIREAT(&'a HEXPIREAT<'a>),
+    HGETDEL(&'a HGETDEL<'a>),
+    HGETEX(&'a HGETEX<'a>),
     HINCRBY(&'a HINCRBY<'a>),
     HMSET(&'a HMSET<'a>),
+    HPERSIST(&'a HPERSIST<'a>),
+    HPEXPIRE(&'a HPEXPIRE<'a>),
+    HPEXPIREAT(&'a HPEXPIREAT<'a>),
     HSET(&'a HSET<'a>),
     HSETNX(&'a HSETNX<'a>),
     INCR(&'a INCR<'a>),
     INCRBY(&'a INCRBY<'a>),
     LINSERT(&'a LINSERT<'a>),
+    LMOVE(&'a LMOVE<'a>),
+    LMPOP(&'a LMPOP<'a>),
     LPOP(&'a LPOP<'a>),
     LPUSH(&'a LPUSH<'a>),
     LPUSHX(&'a LPUSHX<'a>),
     LREM(&'a LREM<'a>),
     LSET(&'a LSET<'a>),
     LTRIM(&'a LTRIM<'a>),
+    MIGRATE(&'a MIGRATE<'a>),
     MOVE(&'a MOVE<'a>),
     MSET(&'a MSET<'a>),
     MSETNX(&'a MSETNX<'a>),
@@ -82,6 +135,7 @@ pub enum Command<'a> {
     PEXPIREAT(&'a PEXPIREAT<'a>),
     PFADD(&'a PFADD<'a>),
     PFCOUNT(&'a PFCOUNT<'a>),
+    PFDEBUG(&'a PFDEBUG<'a>),
     PFMERGE(&'a PFMERGE<'a>),
     PSETEX(&'a PSETEX<'a>),
     PUBLISH(&'a PUBLISH<'a>),
@@ -102,6 +156,7 @@ pub enum Command<'a> {
     SETNX(&'a SETNX<'a>),
     SELECT(&'a SELECT),
     SETRANGE(&'a SETRANGE<'a>),
+    SINTERCARD(&'a SINTERCARD<'a>),
     SINTERSTORE(&'a SINTERSTORE<'a>),
     SMOVE(&'a SMOVE<'a>),
     SORT(&'a SORT<'a>),
@@ -110,10 +165,13 @@ pub enum Command<'a> {
     SWAPDB(&'a SWAPDB<'a>),
     UNLINK(&'a UNLINK<'a>),
     ZADD(&'a ZADD<'a>),
+    ZDIFFSTORE(&'a ZDIFFSTORE<'a>),
     ZINCRBY(&'a ZINCRBY<'a>),
     ZINTERSTORE(&'a ZINTERSTORE<'a>),
+    ZMPOP(&'a ZMPOP<'a>),
     ZPOPMAX(&'a ZPOPMAX<'a>),
     ZPOPMIN(&'a ZPOPMIN<'a>),
+    ZRANGESTORE(&'a ZRANGESTORE<'a>),
     ZREM(&'a ZREM<'a>),
     ZREMRANGEBYLEX(&'a ZREMRANGEBYLEX<'a>),
     ZREMRANGEBYRANK(&'a ZREMRANGEBYRANK<'a>),
@@ -121,20 +179,123 @@ pub enum Command<'a> {
     ZUNIONSTORE(&'a ZUNIONSTORE<'a>),
     XACK(&'a XACK<'a>),
     XADD(&'a XADD<'a>),
+    XAUTOCLAIM(&'a XAUTOCLAIM<'a>),
     XCLAIM(&'a XCLAIM<'a>),
     XDEL(&'a XDEL<'a>),
     XGROUP(&'a XGROUP<'a>),
+    XSETID(&'a XSETID<'a>),
     XTRIM(&'a XTRIM<'a>),
+    Module(&'a ModuleCommand<'a>),
     Other(RawCommand),
 }
 
-#[derive(Debug)]
+/// 未解析的命令
+#[derive(Debug, Clone)]
 pub struct RawCommand {
+    /// 大写的命令名
     pub name: String,
+    /// 命令名之后的各个参数
+    pub args: Vec<Vec<u8>>,
+}
+
+/// 命令所操作的数据类型，见`Command::data_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    String,
+    List,
+    Set,
+    /// 包括Geo相关的命令
+    SortedSet,
+    Hash,
+    Stream,
+    HyperLogLog,
+    /// 适用于任意类型的key，如`DEL`、`EXPIRE`、`RENAME`
+    Generic,
+}
+
+/// 由自定义的`CommandParser`解析的命令
+#[derive(Debug)]
+pub struct ModuleCommand<'a> {
+    /// 大写的命令名
+    pub name: &'a str,
+    /// 命令名之后的各个参数
+    pub args: &'a [Vec<u8>],
+    /// 解析的结果
+    pub value: Box<dyn Module>,
+}
+
+/// 按命令名注册的自定义命令解析器
+///
+/// 命令名不区分大小写，内置的命令不会交给自定义的解析器
+#[derive(Clone, Default)]
+pub struct CommandParsers {
+    parsers: HashMap<String, Arc<Mutex<dyn CommandParser + Send>>>,
+}
+
+impl CommandParsers {
+    pub fn new() -> CommandParsers {
+        CommandParsers::default()
+    }
+
+    /// 注册命令`name`的解析器，同一个命令重复注册时，以最后一次注册的为准
+    pub fn register(&mut self, name: &str, parser: Arc<Mutex<dyn CommandParser + Send>>) {
+        self.parsers.insert(name.to_uppercase(), parser);
+    }
+
+    fn get(&self, name: &str) -> Option<&Arc<Mutex<dyn CommandParser + Send>>> {
+        self.parsers.get(name)
+    }
+}
+
+/// `Command`的拥有所有权的版本，不再借用解析时的缓冲区，可以保存在队列中，或者交给其他线程处理
+///
+/// 只保存命令的各个参数，需要时通过[`replay`]重新解析为`Command`。通过`Command::to_owned`得到时，参数由`to_args`还原，
+/// 解析时未保留的信息无法还原；需要完全一致时，可以直接以`EventHandler::handle_aof_raw`中的原始命令构造
+///
+/// [`replay`]: #method.replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedCommand {
+    /// 命令的各个参数，第一个为命令名
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes_vec"))]
     pub args: Vec<Vec<u8>>,
 }
 
-pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
+impl OwnedCommand {
+    pub fn new(args: Vec<Vec<u8>>) -> OwnedCommand {
+        OwnedCommand { args }
+    }
+
+    /// 大写的命令名
+    pub fn name(&self) -> String {
+        match self.args.first() {
+            Some(name) => String::from_utf8_lossy(name).to_uppercase(),
+            None => String::new(),
+        }
+    }
+
+    /// 重新解析为`Command`，并以`Event::AOF`的形式交给`handler`
+    pub fn replay(&self, parsers: &CommandParsers, handler: &mut dyn EventHandler) {
+        parse(&self.args, parsers, handler);
+    }
+}
+
+impl Command<'_> {
+    /// 复制为拥有所有权的`OwnedCommand`
+    pub fn to_owned(&self) -> OwnedCommand {
+        OwnedCommand::new(self.to_args())
+    }
+}
+
+impl RawCommand {
+    // 子命令未识别时，将其与剩余的参数一起保留
+    fn with_sub_command(name: String, sub_cmd: &[u8], iter: Iter<Vec<u8>>) -> RawCommand {
+        let args = std::iter::once(sub_cmd.to_vec()).chain(iter.cloned()).collect();
+        RawCommand { name, args }
+    }
+}
+
+pub(crate) fn parse(data: &[Vec<u8>], parsers: &CommandParsers, cmd_handler: &mut dyn EventHandler) {
     let mut iter = data.iter();
     if let Some(cmd_name) = iter.next() {
         let cmd_name = String::from_utf8_lossy(cmd_name).to_uppercase();
@@ -147,14 +308,69 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = strings::parse_bitfield(iter);
                 cmd_handler.handle(Event::AOF(Command::BITFIELD(&cmd)));
             }
+            "BITFIELD_RO" => {
+                let cmd = strings::parse_bitfield_ro(iter);
+                cmd_handler.handle(Event::AOF(Command::BITFIELD_RO(&cmd)));
+            }
             "BITOP" => {
                 let cmd = strings::parse_bitop(iter);
                 cmd_handler.handle(Event::AOF(Command::BITOP(&cmd)));
             }
+            "BLMOVE" => {
+                let cmd = lists::parse_blmove(iter);
+                cmd_handler.handle(Event::AOF(Command::BLMOVE(&cmd)));
+            }
+            "BLMPOP" => {
+                let cmd = lists::parse_blmpop(iter);
+                cmd_handler.handle(Event::AOF(Command::BLMPOP(&cmd)));
+            }
             "BRPOPLPUSH" => {
                 let cmd = lists::parse_brpoplpush(iter);
                 cmd_handler.handle(Event::AOF(Command::BRPOPLPUSH(&cmd)));
             }
+            "CLUSTER" => {
+                let sub_cmd = iter.next().unwrap();
+                match String::from_utf8_lossy(sub_cmd).to_uppercase().as_str() {
+                    "ADDSLOTS" => {
+                        let cmd = cluster::parse_cluster_addslots(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERADDSLOTS(&cmd)));
+                    }
+                    "ADDSLOTSRANGE" => {
+                        let cmd = cluster::parse_cluster_addslotsrange(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERADDSLOTSRANGE(&cmd)));
+                    }
+                    "BUMPEPOCH" => cmd_handler.handle(Event::AOF(Command::CLUSTERBUMPEPOCH)),
+                    "DELSLOTS" => {
+                        let cmd = cluster::parse_cluster_delslots(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERDELSLOTS(&cmd)));
+                    }
+                    "DELSLOTSRANGE" => {
+                        let cmd = cluster::parse_cluster_delslotsrange(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERDELSLOTSRANGE(&cmd)));
+                    }
+                    "FLUSHSLOTS" => cmd_handler.handle(Event::AOF(Command::CLUSTERFLUSHSLOTS)),
+                    "FORGET" => {
+                        let cmd = cluster::parse_cluster_forget(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERFORGET(&cmd)));
+                    }
+                    "SET-CONFIG-EPOCH" => {
+                        let cmd = cluster::parse_cluster_set_config_epoch(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERSETCONFIGEPOCH(&cmd)));
+                    }
+                    "SETSLOT" => {
+                        let cmd = cluster::parse_cluster_setslot(iter);
+                        cmd_handler.handle(Event::AOF(Command::CLUSTERSETSLOT(&cmd)));
+                    }
+                    _ => {
+                        let cmd = RawCommand::with_sub_command(cmd_name, sub_cmd, iter);
+                        cmd_handler.handle(Event::AOF(Command::Other(cmd)))
+                    }
+                }
+            }
+            "COPY" => {
+                let cmd = keys::parse_copy(iter);
+                cmd_handler.handle(Event::AOF(Command::COPY(&cmd)));
+            }
             "DEL" => {
                 let cmd = keys::parse_del(iter);
                 cmd_handler.handle(Event::AOF(Command::DEL(&cmd)));
@@ -186,6 +402,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
             "EXEC" => {
                 cmd_handler.handle(Event::AOF(Command::EXEC));
             }
+            "FCALL" => {
+                let cmd = scripting::parse_fcall(iter);
+                cmd_handler.handle(Event::AOF(Command::FCALL(&cmd)));
+            }
             "FLUSHALL" => {
                 let cmd = server::parse_flushall(iter);
                 cmd_handler.handle(Event::AOF(Command::FLUSHALL(&cmd)));
@@ -194,6 +414,55 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = server::parse_flushdb(iter);
                 cmd_handler.handle(Event::AOF(Command::FLUSHDB(&cmd)));
             }
+            "FUNCTION" => {
+                let sub_cmd = iter.next().unwrap();
+                match String::from_utf8_lossy(sub_cmd).to_uppercase().as_str() {
+                    "DELETE" => {
+                        let cmd = scripting::parse_function_delete(iter);
+                        cmd_handler.handle(Event::AOF(Command::FUNCTIONDELETE(&cmd)));
+                    }
+                    "FLUSH" => {
+                        let cmd = scripting::parse_function_flush(iter);
+                        cmd_handler.handle(Event::AOF(Command::FUNCTIONFLUSH(&cmd)));
+                    }
+                    "LOAD" => {
+                        let cmd = scripting::parse_function_load(iter);
+                        cmd_handler.handle(Event::AOF(Command::FUNCTIONLOAD(&cmd)));
+                    }
+                    "RESTORE" => {
+                        let cmd = scripting::parse_function_restore(iter);
+                        cmd_handler.handle(Event::AOF(Command::FUNCTIONRESTORE(&cmd)));
+                    }
+                    _ => {
+                        let cmd = RawCommand::with_sub_command(cmd_name, sub_cmd, iter);
+                        cmd_handler.handle(Event::AOF(Command::Other(cmd)))
+                    }
+                }
+            }
+            "GEOADD" => {
+                let cmd = geo::parse_geoadd(iter);
+                cmd_handler.handle(Event::AOF(Command::GEOADD(&cmd)));
+            }
+            "GEORADIUS" => {
+                let cmd = geo::parse_georadius(iter);
+                cmd_handler.handle(Event::AOF(Command::GEORADIUS(&cmd)));
+            }
+            "GEORADIUSBYMEMBER" => {
+                let cmd = geo::parse_georadiusbymember(iter);
+                cmd_handler.handle(Event::AOF(Command::GEORADIUSBYMEMBER(&cmd)));
+            }
+            "GEOSEARCHSTORE" => {
+                let cmd = geo::parse_geosearchstore(iter);
+                cmd_handler.handle(Event::AOF(Command::GEOSEARCHSTORE(&cmd)));
+            }
+            "GETDEL" => {
+                let cmd = strings::parse_getdel(iter);
+                cmd_handler.handle(Event::AOF(Command::GETDEL(&cmd)));
+            }
+            "GETEX" => {
+                let cmd = strings::parse_getex(iter);
+                cmd_handler.handle(Event::AOF(Command::GETEX(&cmd)));
+            }
             "GETSET" => {
                 let cmd = strings::parse_getset(iter);
                 cmd_handler.handle(Event::AOF(Command::GETSET(&cmd)));
@@ -202,6 +471,22 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = hashes::parse_hdel(iter);
                 cmd_handler.handle(Event::AOF(Command::HDEL(&cmd)));
             }
+            "HEXPIRE" => {
+                let cmd = hashes::parse_hexpire(iter);
+                cmd_handler.handle(Event::AOF(Command::HEXPIRE(&cmd)));
+            }
+            "HEXPIREAT" => {
+                let cmd = hashes::parse_hexpireat(iter);
+                cmd_handler.handle(Event::AOF(Command::HEXPIREAT(&cmd)));
+            }
+            "HGETDEL" => {
+                let cmd = hashes::parse_hgetdel(iter);
+                cmd_handler.handle(Event::AOF(Command::HGETDEL(&cmd)));
+            }
+            "HGETEX" => {
+                let cmd = hashes::parse_hgetex(iter);
+                cmd_handler.handle(Event::AOF(Command::HGETEX(&cmd)));
+            }
             "HINCRBY" => {
                 let cmd = hashes::parse_hincrby(iter);
                 cmd_handler.handle(Event::AOF(Command::HINCRBY(&cmd)));
@@ -210,6 +495,18 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = hashes::parse_hmset(iter);
                 cmd_handler.handle(Event::AOF(Command::HMSET(&cmd)));
             }
+            "HPERSIST" => {
+                let cmd = hashes::parse_hpersist(iter);
+                cmd_handler.handle(Event::AOF(Command::HPERSIST(&cmd)));
+            }
+            "HPEXPIRE" => {
+                let cmd = hashes::parse_hpexpire(iter);
+                cmd_handler.handle(Event::AOF(Command::HPEXPIRE(&cmd)));
+            }
+            "HPEXPIREAT" => {
+                let cmd = hashes::parse_hpexpireat(iter);
+                cmd_handler.handle(Event::AOF(Command::HPEXPIREAT(&cmd)));
+            }
             "HSET" => {
                 let cmd = hashes::parse_hset(iter);
                 cmd_handler.handle(Event::AOF(Command::HSET(&cmd)));
@@ -230,6 +527,14 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = lists::parse_linsert(iter);
                 cmd_handler.handle(Event::AOF(Command::LINSERT(&cmd)));
             }
+            "LMOVE" => {
+                let cmd = lists::parse_lmove(iter);
+                cmd_handler.handle(Event::AOF(Command::LMOVE(&cmd)));
+            }
+            "LMPOP" => {
+                let cmd = lists::parse_lmpop(iter);
+                cmd_handler.handle(Event::AOF(Command::LMPOP(&cmd)));
+            }
             "LPOP" => {
                 let cmd = lists::parse_lpop(iter);
                 cmd_handler.handle(Event::AOF(Command::LPOP(&cmd)));
@@ -287,13 +592,17 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 cmd_handler.handle(Event::AOF(Command::SADD(&cmd)));
             }
             "SCRIPT" => {
-                let cmd = iter.next().unwrap();
-                let cmd = String::from_utf8_lossy(cmd).to_uppercase();
-                if &cmd == "LOAD" {
-                    let cmd = scripting::parse_script_load(iter);
-                    cmd_handler.handle(Event::AOF(Command::SCRIPTLOAD(&cmd)));
-                } else if &cmd == "FLUSH" {
-                    cmd_handler.handle(Event::AOF(Command::SCRIPTFLUSH));
+                let sub_cmd = iter.next().unwrap();
+                match String::from_utf8_lossy(sub_cmd).to_uppercase().as_str() {
+                    "LOAD" => {
+                        let cmd = scripting::parse_script_load(iter);
+                        cmd_handler.handle(Event::AOF(Command::SCRIPTLOAD(&cmd)));
+                    }
+                    "FLUSH" => cmd_handler.handle(Event::AOF(Command::SCRIPTFLUSH)),
+                    _ => {
+                        let cmd = RawCommand::with_sub_command(cmd_name, sub_cmd, iter);
+                        cmd_handler.handle(Event::AOF(Command::Other(cmd)))
+                    }
                 }
             }
             "SDIFFSTORE" => {
@@ -332,6 +641,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = keys::parse_unlink(iter);
                 cmd_handler.handle(Event::AOF(Command::UNLINK(&cmd)));
             }
+            "MIGRATE" => {
+                let cmd = keys::parse_migrate(iter);
+                cmd_handler.handle(Event::AOF(Command::MIGRATE(&cmd)));
+            }
             "MOVE" => {
                 let cmd = keys::parse_move(iter);
                 cmd_handler.handle(Event::AOF(Command::MOVE(&cmd)));
@@ -355,6 +668,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = hyperloglog::parse_pfcount(iter);
                 cmd_handler.handle(Event::AOF(Command::PFCOUNT(&cmd)));
             }
+            "PFDEBUG" => {
+                let cmd = hyperloglog::parse_pfdebug(iter);
+                cmd_handler.handle(Event::AOF(Command::PFDEBUG(&cmd)));
+            }
             "PFMERGE" => {
                 let cmd = hyperloglog::parse_pfmerge(iter);
                 cmd_handler.handle(Event::AOF(Command::PFMERGE(&cmd)));
@@ -395,6 +712,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = strings::parse_setbit(iter);
                 cmd_handler.handle(Event::AOF(Command::SETBIT(&cmd)));
             }
+            "SINTERCARD" => {
+                let cmd = sets::parse_sintercard(iter);
+                cmd_handler.handle(Event::AOF(Command::SINTERCARD(&cmd)));
+            }
             "SINTERSTORE" => {
                 let cmd = sets::parse_sinterstore(iter);
                 cmd_handler.handle(Event::AOF(Command::SINTERSTORE(&cmd)));
@@ -403,6 +724,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = sorted_sets::parse_zadd(iter);
                 cmd_handler.handle(Event::AOF(Command::ZADD(&cmd)));
             }
+            "ZDIFFSTORE" => {
+                let cmd = sorted_sets::parse_zdiffstore(iter);
+                cmd_handler.handle(Event::AOF(Command::ZDIFFSTORE(&cmd)));
+            }
             "ZINCRBY" => {
                 let cmd = sorted_sets::parse_zincrby(iter);
                 cmd_handler.handle(Event::AOF(Command::ZINCRBY(&cmd)));
@@ -411,6 +736,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = sorted_sets::parse_zinterstore(iter);
                 cmd_handler.handle(Event::AOF(Command::ZINTERSTORE(&cmd)));
             }
+            "ZMPOP" => {
+                let cmd = sorted_sets::parse_zmpop(iter);
+                cmd_handler.handle(Event::AOF(Command::ZMPOP(&cmd)));
+            }
             "ZPOPMAX" => {
                 let cmd = sorted_sets::parse_zpopmax(iter);
                 cmd_handler.handle(Event::AOF(Command::ZPOPMAX(&cmd)));
@@ -419,6 +748,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = sorted_sets::parse_zpopmin(iter);
                 cmd_handler.handle(Event::AOF(Command::ZPOPMIN(&cmd)));
             }
+            "ZRANGESTORE" => {
+                let cmd = sorted_sets::parse_zrangestore(iter);
+                cmd_handler.handle(Event::AOF(Command::ZRANGESTORE(&cmd)));
+            }
             "ZREM" => {
                 let cmd = sorted_sets::parse_zrem(iter);
                 cmd_handler.handle(Event::AOF(Command::ZREM(&cmd)));
@@ -447,6 +780,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = streams::parse_xadd(iter);
                 cmd_handler.handle(Event::AOF(Command::XADD(&cmd)));
             }
+            "XAUTOCLAIM" => {
+                let cmd = streams::parse_xautoclaim(iter);
+                cmd_handler.handle(Event::AOF(Command::XAUTOCLAIM(&cmd)));
+            }
             "XCLAIM" => {
                 let cmd = streams::parse_xclaim(iter);
                 cmd_handler.handle(Event::AOF(Command::XCLAIM(&cmd)));
@@ -459,6 +796,10 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 let cmd = streams::parse_xgroup(iter);
                 cmd_handler.handle(Event::AOF(Command::XGROUP(&cmd)));
             }
+            "XSETID" => {
+                let cmd = streams::parse_xsetid(iter);
+                cmd_handler.handle(Event::AOF(Command::XSETID(&cmd)));
+            }
             "XTRIM" => {
                 let cmd = streams::parse_xtrim(iter);
                 cmd_handler.handle(Event::AOF(Command::XTRIM(&cmd)));
@@ -467,12 +808,20 @@ pub(crate) fn parse(data: Vec<Vec<u8>>, cmd_handler: &mut dyn EventHandler) {
                 // PING命令是由Redis master主动发送过来，判断下游节点是否活跃，不需要处理
             }
             _ => {
-                let mut args = Vec::new();
-                while let Some(arg) = iter.next() {
-                    args.push(arg.clone());
+                if let Some(parser) = parsers.get(&cmd_name) {
+                    let args = iter.as_slice();
+                    let value = parser.lock().unwrap().parse(&cmd_name, args);
+                    let cmd = ModuleCommand {
+                        name: &cmd_name,
+                        args,
+                        value,
+                    };
+                    cmd_handler.handle(Event::AOF(Command::Module(&cmd)));
+                } else {
+                    let args = iter.cloned().collect();
+                    let cmd = RawCommand { name: cmd_name, args };
+                    cmd_handler.handle(Event::AOF(Command::Other(cmd)))
                 }
-                let cmd = RawCommand { name: cmd_name, args };
-                cmd_handler.handle(Event::AOF(Command::Other(cmd)))
             }
         };
     }
@@ -8,7 +8,7 @@ Pub/Sub相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PUBLISH<'a> {
     pub channel: &'a [u8],
     pub message: &'a [u8],
@@ -8,7 +8,7 @@ Scripting相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EVAL<'a> {
     pub script: &'a [u8],
     pub num_keys: i32,
@@ -16,8 +16,8 @@ pub struct EVAL<'a> {
     pub args: Vec<&'a [u8]>,
 }
 
-pub(crate) fn parse_eval(mut iter: Iter<Vec<u8>>) -> EVAL {
-    let script = iter.next().unwrap();
+// 解析numkeys以及其后的各个key与参数
+fn parse_keys_args<'a>(mut iter: Iter<'a, Vec<u8>>) -> (i32, Vec<&'a [u8]>, Vec<&'a [u8]>) {
     let num_keys = iter.next().unwrap();
     let num_keys = String::from_utf8_lossy(num_keys).parse::<i32>().unwrap();
     let mut keys = Vec::with_capacity(num_keys as usize);
@@ -25,10 +25,13 @@ pub(crate) fn parse_eval(mut iter: Iter<Vec<u8>>) -> EVAL {
         let key = iter.next().unwrap();
         keys.push(key.as_slice());
     }
-    let mut args = Vec::new();
-    while let Some(arg) = iter.next() {
-        args.push(arg.as_slice());
-    }
+    let args = iter.map(|arg| arg.as_slice()).collect();
+    (num_keys, keys, args)
+}
+
+pub(crate) fn parse_eval(mut iter: Iter<Vec<u8>>) -> EVAL {
+    let script = iter.next().unwrap();
+    let (num_keys, keys, args) = parse_keys_args(iter);
     EVAL {
         script,
         num_keys,
@@ -37,7 +40,7 @@ pub(crate) fn parse_eval(mut iter: Iter<Vec<u8>>) -> EVAL {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EVALSHA<'a> {
     pub sha1: &'a [u8],
     pub num_keys: i32,
@@ -47,17 +50,7 @@ pub struct EVALSHA<'a> {
 
 pub(crate) fn parse_evalsha(mut iter: Iter<Vec<u8>>) -> EVALSHA {
     let sha1 = iter.next().unwrap();
-    let num_keys = iter.next().unwrap();
-    let num_keys = String::from_utf8_lossy(num_keys).parse::<i32>().unwrap();
-    let mut keys = Vec::with_capacity(num_keys as usize);
-    for _ in 0..num_keys {
-        let key = iter.next().unwrap();
-        keys.push(key.as_slice());
-    }
-    let mut args = Vec::new();
-    while let Some(arg) = iter.next() {
-        args.push(arg.as_slice());
-    }
+    let (num_keys, keys, args) = parse_keys_args(iter);
     EVALSHA {
         sha1,
         num_keys,
@@ -66,7 +59,110 @@ pub(crate) fn parse_evalsha(mut iter: Iter<Vec<u8>>) -> EVALSHA {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct FCALL<'a> {
+    pub function: &'a [u8],
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+    pub args: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_fcall(mut iter: Iter<Vec<u8>>) -> FCALL {
+    let function = iter.next().unwrap();
+    let (num_keys, keys, args) = parse_keys_args(iter);
+    FCALL {
+        function,
+        num_keys,
+        keys,
+        args,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FUNCTIONDELETE<'a> {
+    pub library_name: &'a [u8],
+}
+
+pub(crate) fn parse_function_delete(mut iter: Iter<Vec<u8>>) -> FUNCTIONDELETE {
+    let library_name = iter.next().unwrap();
+    FUNCTIONDELETE { library_name }
+}
+
+#[derive(Debug, Clone)]
+pub struct FUNCTIONFLUSH {
+    pub _async: Option<bool>,
+    pub sync: Option<bool>,
+}
+
+pub(crate) fn parse_function_flush(mut iter: Iter<Vec<u8>>) -> FUNCTIONFLUSH {
+    let mut _async = None;
+    let mut sync = None;
+    if let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "ASYNC" {
+            _async = Some(true);
+        } else if &arg_upper == "SYNC" {
+            sync = Some(true);
+        } else {
+            panic!("Invalid argument")
+        }
+    }
+    FUNCTIONFLUSH { _async, sync }
+}
+
+#[derive(Debug, Clone)]
+pub struct FUNCTIONLOAD<'a> {
+    pub replace: Option<bool>,
+    /// 函数库的代码，第一行为`#!<engine> name=<library name>`
+    pub code: &'a [u8],
+}
+
+pub(crate) fn parse_function_load(mut iter: Iter<Vec<u8>>) -> FUNCTIONLOAD {
+    let mut replace = None;
+    let mut code = iter.next().unwrap();
+    if String::from_utf8_lossy(code).to_uppercase() == "REPLACE" {
+        replace = Some(true);
+        code = iter.next().unwrap();
+    }
+    FUNCTIONLOAD { replace, code }
+}
+
+#[derive(Debug, Clone)]
+pub struct FUNCTIONRESTORE<'a> {
+    /// FUNCTION DUMP所返回的数据
+    pub payload: &'a [u8],
+    pub policy: Option<RestorePolicy>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RestorePolicy {
+    // Deletes all existing libraries before restoring the payload.
+    FLUSH,
+    // Appends the restored libraries to the existing libraries and aborts on collision.
+    APPEND,
+    // Appends the restored libraries to the existing libraries, replacing any existing ones in case of name collisions.
+    REPLACE,
+}
+
+pub(crate) fn parse_function_restore(mut iter: Iter<Vec<u8>>) -> FUNCTIONRESTORE {
+    let payload = iter.next().unwrap();
+    let mut policy = None;
+    if let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "FLUSH" {
+            policy = Some(RestorePolicy::FLUSH);
+        } else if &arg_upper == "APPEND" {
+            policy = Some(RestorePolicy::APPEND);
+        } else if &arg_upper == "REPLACE" {
+            policy = Some(RestorePolicy::REPLACE);
+        } else {
+            panic!("Invalid argument")
+        }
+    }
+    FUNCTIONRESTORE { payload, policy }
+}
+
+#[derive(Debug, Clone)]
 pub struct SCRIPTLOAD<'a> {
     pub script: &'a [u8],
 }
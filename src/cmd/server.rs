@@ -8,38 +8,46 @@ Server相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FLUSHDB {
     pub _async: Option<bool>,
+    pub sync: Option<bool>,
 }
 
 pub(crate) fn parse_flushdb(mut iter: Iter<Vec<u8>>) -> FLUSHDB {
     let mut _async = None;
+    let mut sync = None;
     if let Some(next_arg) = iter.next() {
         let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
         if &arg_upper == "ASYNC" {
             _async = Some(true);
+        } else if &arg_upper == "SYNC" {
+            sync = Some(true);
         } else {
             panic!("Invalid argument")
         }
     }
-    FLUSHDB { _async }
+    FLUSHDB { _async, sync }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FLUSHALL {
     pub _async: Option<bool>,
+    pub sync: Option<bool>,
 }
 
 pub(crate) fn parse_flushall(mut iter: Iter<Vec<u8>>) -> FLUSHALL {
     let mut _async = None;
+    let mut sync = None;
     if let Some(next_arg) = iter.next() {
         let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
         if &arg_upper == "ASYNC" {
             _async = Some(true);
+        } else if &arg_upper == "SYNC" {
+            sync = Some(true);
         } else {
             panic!("Invalid argument")
         }
     }
-    FLUSHALL { _async }
+    FLUSHALL { _async, sync }
 }
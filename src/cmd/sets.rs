@@ -8,7 +8,7 @@ Sets相关的命令定义、解析
 
 use std::slice::Iter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SINTERSTORE<'a> {
     pub destination: &'a [u8],
     pub keys: Vec<&'a [u8]>,
@@ -23,7 +23,7 @@ pub(crate) fn parse_sinterstore(mut iter: Iter<Vec<u8>>) -> SINTERSTORE {
     SINTERSTORE { destination, keys }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SADD<'a> {
     pub key: &'a [u8],
     pub members: Vec<&'a [u8]>,
@@ -38,7 +38,7 @@ pub(crate) fn parse_sadd(mut iter: Iter<Vec<u8>>) -> SADD {
     SADD { key, members }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SDIFFSTORE<'a> {
     pub destination: &'a [u8],
     pub keys: Vec<&'a [u8]>,
@@ -53,7 +53,7 @@ pub(crate) fn parse_sdiffstore(mut iter: Iter<Vec<u8>>) -> SDIFFSTORE {
     SDIFFSTORE { destination, keys }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SMOVE<'a> {
     pub source: &'a [u8],
     pub destination: &'a [u8],
@@ -71,7 +71,7 @@ pub(crate) fn parse_smove(mut iter: Iter<Vec<u8>>) -> SMOVE {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SREM<'a> {
     pub key: &'a [u8],
     pub members: Vec<&'a [u8]>,
@@ -86,7 +86,7 @@ pub(crate) fn parse_srem(mut iter: Iter<Vec<u8>>) -> SREM {
     SREM { key, members }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SUNIONSTORE<'a> {
     pub destination: &'a [u8],
     pub keys: Vec<&'a [u8]>,
@@ -100,3 +100,27 @@ pub(crate) fn parse_sunionstore(mut iter: Iter<Vec<u8>>) -> SUNIONSTORE {
     }
     SUNIONSTORE { destination, keys }
 }
+
+#[derive(Debug, Clone)]
+pub struct SINTERCARD<'a> {
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+    pub limit: Option<&'a [u8]>,
+}
+
+pub(crate) fn parse_sintercard(mut iter: Iter<Vec<u8>>) -> SINTERCARD {
+    let num_keys = String::from_utf8_lossy(iter.next().unwrap());
+    let num_keys = num_keys.parse::<i32>().unwrap();
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        let next_key = iter.next().unwrap();
+        keys.push(next_key.as_slice());
+    }
+    let mut limit = None;
+    while let Some(next_arg) = iter.next() {
+        if String::from_utf8_lossy(next_arg).to_uppercase() == "LIMIT" {
+            limit = Some(iter.next().unwrap().as_slice());
+        }
+    }
+    SINTERCARD { num_keys, keys, limit }
+}
@@ -8,22 +8,32 @@ Sorted Sets相关的命令定义、解析
 
 use std::slice::Iter;
 
+use crate::cmd::keys::LIMIT;
 use crate::cmd::sorted_sets::AGGREGATE::{MAX, MIN, SUM};
 use crate::cmd::strings::ExistType;
 use crate::cmd::strings::ExistType::{NX, XX};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZADD<'a> {
     pub key: &'a [u8],
     /// XX: 只更新现有的元素，不添加新的元素.
     /// NX: 只添加新的元素，不更新现有的元素.
     pub exist_type: Option<ExistType>,
+    /// GT: 只在新的score大于当前score时更新.
+    /// LT: 只在新的score小于当前score时更新.
+    pub compare_type: Option<CompareType>,
     pub ch: Option<bool>,
     pub incr: Option<bool>,
     pub items: Vec<Item<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub enum CompareType {
+    GT,
+    LT,
+}
+
+#[derive(Debug, Clone)]
 pub struct Item<'a> {
     pub score: &'a [u8],
     pub member: &'a [u8],
@@ -32,6 +42,7 @@ pub struct Item<'a> {
 pub(crate) fn parse_zadd(mut iter: Iter<Vec<u8>>) -> ZADD {
     let key = iter.next().unwrap();
     let mut exist_type = None;
+    let mut compare_type = None;
     let mut ch = None;
     let mut incr = None;
     let mut items = Vec::new();
@@ -41,6 +52,10 @@ pub(crate) fn parse_zadd(mut iter: Iter<Vec<u8>>) -> ZADD {
             exist_type = Some(NX);
         } else if &arg_upper == "XX" {
             exist_type = Some(XX);
+        } else if &arg_upper == "GT" {
+            compare_type = Some(CompareType::GT);
+        } else if &arg_upper == "LT" {
+            compare_type = Some(CompareType::LT);
         } else if &arg_upper == "CH" {
             ch = Some(true);
         } else if &arg_upper == "INCR" {
@@ -57,13 +72,14 @@ pub(crate) fn parse_zadd(mut iter: Iter<Vec<u8>>) -> ZADD {
     ZADD {
         key,
         exist_type,
+        compare_type,
         ch,
         incr,
         items,
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZINCRBY<'a> {
     pub key: &'a [u8],
     pub increment: &'a [u8],
@@ -77,7 +93,7 @@ pub(crate) fn parse_zincrby(mut iter: Iter<Vec<u8>>) -> ZINCRBY {
     ZINCRBY { key, increment, member }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZINTERSTORE<'a> {
     pub destination: &'a [u8],
     pub num_keys: i32,
@@ -86,7 +102,7 @@ pub struct ZINTERSTORE<'a> {
     pub aggregate: Option<AGGREGATE>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AGGREGATE {
     SUM,
     MIN,
@@ -133,7 +149,7 @@ pub(crate) fn parse_zinterstore(mut iter: Iter<Vec<u8>>) -> ZINTERSTORE {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZPOPMAX<'a> {
     pub key: &'a [u8],
     pub count: Option<&'a [u8]>,
@@ -148,7 +164,7 @@ pub(crate) fn parse_zpopmax(mut iter: Iter<Vec<u8>>) -> ZPOPMAX {
     ZPOPMAX { key, count }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZPOPMIN<'a> {
     pub key: &'a [u8],
     pub count: Option<&'a [u8]>,
@@ -163,7 +179,7 @@ pub(crate) fn parse_zpopmin(mut iter: Iter<Vec<u8>>) -> ZPOPMIN {
     ZPOPMIN { key, count }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZREM<'a> {
     pub key: &'a [u8],
     pub members: Vec<&'a [u8]>,
@@ -178,7 +194,7 @@ pub(crate) fn parse_zrem(mut iter: Iter<Vec<u8>>) -> ZREM {
     ZREM { key, members }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZREMRANGEBYLEX<'a> {
     pub key: &'a [u8],
     pub min: &'a [u8],
@@ -192,7 +208,7 @@ pub(crate) fn parse_zremrangebylex(mut iter: Iter<Vec<u8>>) -> ZREMRANGEBYLEX {
     ZREMRANGEBYLEX { key, min, max }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZREMRANGEBYRANK<'a> {
     pub key: &'a [u8],
     pub start: &'a [u8],
@@ -206,7 +222,7 @@ pub(crate) fn parse_zremrangebyrank(mut iter: Iter<Vec<u8>>) -> ZREMRANGEBYRANK
     ZREMRANGEBYRANK { key, start, stop }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZREMRANGEBYSCORE<'a> {
     pub key: &'a [u8],
     pub min: &'a [u8],
@@ -220,7 +236,7 @@ pub(crate) fn parse_zremrangebyscore(mut iter: Iter<Vec<u8>>) -> ZREMRANGEBYSCOR
     ZREMRANGEBYSCORE { key, min, max }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZUNIONSTORE<'a> {
     pub destination: &'a [u8],
     pub num_keys: i32,
@@ -268,3 +284,122 @@ pub(crate) fn parse_zunionstore(mut iter: Iter<Vec<u8>>) -> ZUNIONSTORE {
         aggregate,
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ZDIFFSTORE<'a> {
+    pub destination: &'a [u8],
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+}
+
+pub(crate) fn parse_zdiffstore(mut iter: Iter<Vec<u8>>) -> ZDIFFSTORE {
+    let destination = iter.next().unwrap();
+    let num_keys = String::from_utf8_lossy(iter.next().unwrap());
+    let num_keys = num_keys.parse::<i32>().unwrap();
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        let next_key = iter.next().unwrap();
+        keys.push(next_key.as_slice());
+    }
+    ZDIFFSTORE {
+        destination,
+        num_keys,
+        keys,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZMPOP<'a> {
+    pub num_keys: i32,
+    pub keys: Vec<&'a [u8]>,
+    /// 弹出分数最小还是最大的元素
+    pub min_max: MINMAX,
+    pub count: Option<&'a [u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub enum MINMAX {
+    MIN,
+    MAX,
+}
+
+pub(crate) fn parse_zmpop(mut iter: Iter<Vec<u8>>) -> ZMPOP {
+    let num_keys = String::from_utf8_lossy(iter.next().unwrap());
+    let num_keys = num_keys.parse::<i32>().unwrap();
+    let mut keys = Vec::new();
+    for _ in 0..num_keys {
+        let next_key = iter.next().unwrap();
+        keys.push(next_key.as_slice());
+    }
+    let min_max = String::from_utf8_lossy(iter.next().unwrap()).to_uppercase();
+    let min_max = if &min_max == "MIN" {
+        MINMAX::MIN
+    } else if &min_max == "MAX" {
+        MINMAX::MAX
+    } else {
+        panic!("ZMPOP只能为MIN或MAX: {}", min_max);
+    };
+    let mut count = None;
+    while let Some(next_arg) = iter.next() {
+        if String::from_utf8_lossy(next_arg).to_uppercase() == "COUNT" {
+            count = Some(iter.next().unwrap().as_slice());
+        }
+    }
+    ZMPOP {
+        num_keys,
+        keys,
+        min_max,
+        count,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ZRANGESTORE<'a> {
+    pub destination: &'a [u8],
+    pub source: &'a [u8],
+    pub min: &'a [u8],
+    pub max: &'a [u8],
+    /// 按分数或者字典序，None为按排名
+    pub by: Option<RANGEBY>,
+    pub rev: Option<bool>,
+    pub limit: Option<LIMIT<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum RANGEBY {
+    BYSCORE,
+    BYLEX,
+}
+
+pub(crate) fn parse_zrangestore(mut iter: Iter<Vec<u8>>) -> ZRANGESTORE {
+    let destination = iter.next().unwrap();
+    let source = iter.next().unwrap();
+    let min = iter.next().unwrap();
+    let max = iter.next().unwrap();
+    let mut by = None;
+    let mut rev = None;
+    let mut limit = None;
+    while let Some(next_arg) = iter.next() {
+        let arg_upper = String::from_utf8_lossy(next_arg).to_uppercase();
+        if &arg_upper == "BYSCORE" {
+            by = Some(RANGEBY::BYSCORE);
+        } else if &arg_upper == "BYLEX" {
+            by = Some(RANGEBY::BYLEX);
+        } else if &arg_upper == "REV" {
+            rev = Some(true);
+        } else if &arg_upper == "LIMIT" {
+            let offset = iter.next().unwrap();
+            let count = iter.next().unwrap();
+            limit = Some(LIMIT { offset, count });
+        }
+    }
+    ZRANGESTORE {
+        destination,
+        source,
+        min,
+        max,
+        by,
+        rev,
+        limit,
+    }
+}
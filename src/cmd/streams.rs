@@ -10,7 +10,7 @@ use core::slice::Iter;
 
 use crate::cmd::hashes::Field;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XACK<'a> {
     pub key: &'a [u8],
     pub group: &'a [u8],
@@ -27,33 +27,137 @@ pub(crate) fn parse_xack(mut iter: Iter<Vec<u8>>) -> XACK {
     XACK { key, group, ids }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XADD<'a> {
     pub key: &'a [u8],
+    /// key不存在时不创建stream
+    pub no_mkstream: Option<bool>,
+    /// 添加之后对stream进行裁剪
+    pub trim: Option<Trim<'a>>,
     pub id: &'a [u8],
     pub fields: Vec<Field<'a>>,
 }
 
 pub(crate) fn parse_xadd(mut iter: Iter<Vec<u8>>) -> XADD {
     let key = iter.next().unwrap();
-    let id = iter.next().unwrap();
-    let mut fields = Vec::new();
+    let mut no_mkstream = None;
+    let mut trim = None;
+    let id;
     loop {
-        if let Some(field) = iter.next() {
-            if let Some(value) = iter.next() {
-                let field = Field { name: field, value };
-                fields.push(field);
-            } else {
-                panic!("XADD缺失field value");
+        let arg = iter.next().unwrap();
+        let arg_string = String::from_utf8_lossy(arg);
+        let p_arg = &arg_string.to_uppercase();
+        if p_arg == "NOMKSTREAM" {
+            no_mkstream = Some(true);
+        } else if p_arg == "MAXLEN" || p_arg == "MINID" {
+            trim = Some(parse_trim(p_arg, &mut iter));
+        } else if p_arg == "LIMIT" {
+            if let Some(trim) = &mut trim {
+                trim.limit = Some(iter.next().unwrap());
             }
         } else {
+            id = arg;
             break;
         }
     }
-    XADD { key, id, fields }
+    let mut fields = Vec::new();
+    while let Some(field) = iter.next() {
+        if let Some(value) = iter.next() {
+            let field = Field { name: field, value };
+            fields.push(field);
+        } else {
+            panic!("XADD缺失field value");
+        }
+    }
+    XADD {
+        key,
+        no_mkstream,
+        trim,
+        id,
+        fields,
+    }
+}
+
+/// XADD以及XTRIM的裁剪条件
+#[derive(Debug, Clone)]
+pub struct Trim<'a> {
+    pub strategy: TrimStrategy,
+    /// 是否为近似裁剪，即`~`
+    pub approximation: bool,
+    /// MAXLEN时为最大长度，MINID时为最小的ID
+    pub threshold: &'a [u8],
+    /// 近似裁剪时最多删除的元素个数
+    pub limit: Option<&'a Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimStrategy {
+    /// 按长度裁剪
+    MAXLEN,
+    /// 删除ID小于阈值的元素
+    MINID,
+}
+
+// 解析MAXLEN|MINID之后的[=|~] threshold
+fn parse_trim<'a>(strategy: &str, iter: &mut Iter<'a, Vec<u8>>) -> Trim<'a> {
+    let strategy = if strategy == "MINID" {
+        TrimStrategy::MINID
+    } else {
+        TrimStrategy::MAXLEN
+    };
+    let mut threshold = iter.next().unwrap();
+    let approximation = threshold == b"~";
+    if approximation || threshold == b"=" {
+        threshold = iter.next().unwrap();
+    }
+    Trim {
+        strategy,
+        approximation,
+        threshold,
+        limit: None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XAUTOCLAIM<'a> {
+    pub key: &'a [u8],
+    pub group: &'a [u8],
+    pub consumer: &'a [u8],
+    pub min_idle_time: &'a [u8],
+    pub start: &'a [u8],
+    pub count: Option<&'a Vec<u8>>,
+    pub just_id: Option<bool>,
 }
 
-#[derive(Debug)]
+pub(crate) fn parse_xautoclaim(mut iter: Iter<Vec<u8>>) -> XAUTOCLAIM {
+    let key = iter.next().unwrap();
+    let group = iter.next().unwrap();
+    let consumer = iter.next().unwrap();
+    let min_idle_time = iter.next().unwrap();
+    let start = iter.next().unwrap();
+    let mut count = None;
+    let mut just_id = None;
+    while let Some(arg) = iter.next() {
+        let arg_string = String::from_utf8_lossy(arg);
+        let p_arg = &arg_string.to_uppercase();
+        if p_arg == "COUNT" {
+            count = Some(iter.next().unwrap());
+        } else if p_arg == "JUSTID" {
+            just_id = Some(true);
+        }
+    }
+    XAUTOCLAIM {
+        key,
+        group,
+        consumer,
+        min_idle_time,
+        start,
+        count,
+        just_id,
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct XCLAIM<'a> {
     pub key: &'a [u8],
     pub group: &'a [u8],
@@ -65,6 +169,8 @@ pub struct XCLAIM<'a> {
     pub retry_count: Option<&'a Vec<u8>>,
     pub force: Option<bool>,
     pub just_id: Option<bool>,
+    /// 更新group的last_id，Redis传播XCLAIM时会带上此选项
+    pub last_id: Option<&'a Vec<u8>>,
 }
 
 pub(crate) fn parse_xclaim(mut iter: Iter<Vec<u8>>) -> XCLAIM {
@@ -80,7 +186,8 @@ pub(crate) fn parse_xclaim(mut iter: Iter<Vec<u8>>) -> XCLAIM {
     let mut retry_count = None;
     let mut force = None;
     let mut just_id = None;
-    for arg in iter.next() {
+    let mut last_id = None;
+    while let Some(arg) = iter.next() {
         let arg_string = String::from_utf8_lossy(arg);
         let p_arg = &arg_string.to_uppercase();
         if p_arg == "IDLE" {
@@ -96,6 +203,8 @@ pub(crate) fn parse_xclaim(mut iter: Iter<Vec<u8>>) -> XCLAIM {
             force = Some(true);
         } else if p_arg == "JUSTID" {
             just_id = Some(true);
+        } else if p_arg == "LASTID" {
+            last_id = Some(iter.next().unwrap());
         } else {
             ids.push(arg);
         }
@@ -111,10 +220,11 @@ pub(crate) fn parse_xclaim(mut iter: Iter<Vec<u8>>) -> XCLAIM {
         retry_count,
         force,
         just_id,
+        last_id,
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XDEL<'a> {
     pub key: &'a [u8],
     pub ids: Vec<&'a Vec<u8>>,
@@ -129,35 +239,48 @@ pub(crate) fn parse_xdel(mut iter: Iter<Vec<u8>>) -> XDEL {
     XDEL { key, ids }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct XGROUP<'a> {
     pub create: Option<Create<'a>>,
     pub set_id: Option<SetID<'a>>,
     pub destroy: Option<Destroy<'a>>,
+    pub create_consumer: Option<CreateConsumer<'a>>,
     pub del_consumer: Option<DelConsumer<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Create<'a> {
     pub key: &'a [u8],
     pub group_name: &'a [u8],
     pub id: &'a [u8],
+    /// key不存在时创建一个空的stream
+    pub mkstream: Option<bool>,
+    /// group已读取的元素数量，Redis 7.0之后传播XGROUP CREATE时会带上此选项
+    pub entries_read: Option<&'a Vec<u8>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SetID<'a> {
     pub key: &'a [u8],
     pub group_name: &'a [u8],
     pub id: &'a [u8],
+    pub entries_read: Option<&'a Vec<u8>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Destroy<'a> {
     pub key: &'a [u8],
     pub group_name: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct CreateConsumer<'a> {
+    pub key: &'a [u8],
+    pub group_name: &'a [u8],
+    pub consumer_name: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
 pub struct DelConsumer<'a> {
     pub key: &'a [u8],
     pub group_name: &'a [u8],
@@ -168,24 +291,62 @@ pub(crate) fn parse_xgroup(mut iter: Iter<Vec<u8>>) -> XGROUP {
     let mut create = None;
     let mut set_id = None;
     let mut destroy = None;
+    let mut create_consumer = None;
     let mut del_consumer = None;
-    for arg in iter.next() {
+    if let Some(arg) = iter.next() {
         let arg_string = String::from_utf8_lossy(arg);
         let p_arg = &arg_string.to_uppercase();
         if p_arg == "CREATE" {
             let key = iter.next().unwrap();
             let group_name = iter.next().unwrap();
             let id = iter.next().unwrap();
-            create = Some(Create { key, group_name, id })
+            let mut mkstream = None;
+            let mut entries_read = None;
+            while let Some(arg) = iter.next() {
+                let arg_string = String::from_utf8_lossy(arg);
+                let p_arg = &arg_string.to_uppercase();
+                if p_arg == "MKSTREAM" {
+                    mkstream = Some(true);
+                } else if p_arg == "ENTRIESREAD" {
+                    entries_read = Some(iter.next().unwrap());
+                }
+            }
+            create = Some(Create {
+                key,
+                group_name,
+                id,
+                mkstream,
+                entries_read,
+            })
         } else if p_arg == "SETID" {
             let key = iter.next().unwrap();
             let group_name = iter.next().unwrap();
             let id = iter.next().unwrap();
-            set_id = Some(SetID { key, group_name, id })
+            let mut entries_read = None;
+            while let Some(arg) = iter.next() {
+                if String::from_utf8_lossy(arg).to_uppercase() == "ENTRIESREAD" {
+                    entries_read = Some(iter.next().unwrap());
+                }
+            }
+            set_id = Some(SetID {
+                key,
+                group_name,
+                id,
+                entries_read,
+            })
         } else if p_arg == "DESTROY" {
             let key = iter.next().unwrap();
             let group_name = iter.next().unwrap();
             destroy = Some(Destroy { key, group_name })
+        } else if p_arg == "CREATECONSUMER" {
+            let key = iter.next().unwrap();
+            let group_name = iter.next().unwrap();
+            let consumer_name = iter.next().unwrap();
+            create_consumer = Some(CreateConsumer {
+                key,
+                group_name,
+                consumer_name,
+            })
         } else if p_arg == "DELCONSUMER" {
             let key = iter.next().unwrap();
             let group_name = iter.next().unwrap();
@@ -201,35 +362,57 @@ pub(crate) fn parse_xgroup(mut iter: Iter<Vec<u8>>) -> XGROUP {
         create,
         set_id,
         destroy,
+        create_consumer,
         del_consumer,
     }
 }
 
-#[derive(Debug)]
-pub struct XTRIM<'a> {
+#[derive(Debug, Clone)]
+pub struct XSETID<'a> {
     pub key: &'a [u8],
-    pub approximation: bool,
-    pub count: u64,
+    pub last_id: &'a [u8],
+    /// stream从创建以来添加过的元素总数，Redis 7.0新增
+    pub entries_added: Option<&'a Vec<u8>>,
+    /// 被删除的元素中最大的ID，Redis 7.0新增
+    pub max_deleted_id: Option<&'a Vec<u8>>,
 }
 
-pub(crate) fn parse_xtrim(mut iter: Iter<Vec<u8>>) -> XTRIM {
+pub(crate) fn parse_xsetid(mut iter: Iter<Vec<u8>>) -> XSETID {
     let key = iter.next().unwrap();
-    iter.next().unwrap();
-    let third = iter.next().unwrap();
-    let third = String::from_utf8_lossy(third);
-    let approximation;
-    let count;
-    if "~" == third {
-        approximation = true;
-        let arg = String::from_utf8_lossy(iter.next().unwrap());
-        count = arg.parse::<u64>().unwrap();
-    } else {
-        approximation = false;
-        count = third.parse::<u64>().unwrap();
+    let last_id = iter.next().unwrap();
+    let mut entries_added = None;
+    let mut max_deleted_id = None;
+    while let Some(arg) = iter.next() {
+        let arg_string = String::from_utf8_lossy(arg);
+        let p_arg = &arg_string.to_uppercase();
+        if p_arg == "ENTRIESADDED" {
+            entries_added = Some(iter.next().unwrap());
+        } else if p_arg == "MAXDELETEDID" {
+            max_deleted_id = Some(iter.next().unwrap());
+        }
     }
-    XTRIM {
+    XSETID {
         key,
-        approximation,
-        count,
+        last_id,
+        entries_added,
+        max_deleted_id,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XTRIM<'a> {
+    pub key: &'a [u8],
+    pub trim: Trim<'a>,
+}
+
+pub(crate) fn parse_xtrim(mut iter: Iter<Vec<u8>>) -> XTRIM {
+    let key = iter.next().unwrap();
+    let strategy = String::from_utf8_lossy(iter.next().unwrap()).to_uppercase();
+    let mut trim = parse_trim(&strategy, &mut iter);
+    while let Some(arg) = iter.next() {
+        if String::from_utf8_lossy(arg).to_uppercase() == "LIMIT" {
+            trim.limit = Some(iter.next().unwrap());
+        }
     }
+    XTRIM { key, trim }
 }
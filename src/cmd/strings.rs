@@ -8,9 +8,9 @@ Strings相关的命令定义、解析
 
 use core::slice::Iter;
 
-use crate::cmd::strings::Op::{AND, NOT, OR, XOR};
+use crate::cmd::strings::Op::{AND, ANDOR, DIFF, DIFF1, NOT, ONE, OR, XOR};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct APPEND<'a> {
     pub key: &'a [u8],
     pub value: &'a [u8],
@@ -22,41 +22,44 @@ pub(crate) fn parse_append(mut iter: Iter<Vec<u8>>) -> APPEND {
     APPEND { key, value }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BITFIELD<'a> {
     pub key: &'a [u8],
+    /// 按原命令中的顺序排列的各个子命令，OVERFLOW只对其后的SET、INCRBY生效
     pub statements: Option<Vec<Operation<'a>>>,
+    /// 所有的OVERFLOW，不包含其位置信息
     pub overflows: Option<Vec<Overflow>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Operation<'a> {
     GET(Get<'a>),
     INCRBY(IncrBy<'a>),
     SET(Set<'a>),
+    OVERFLOW(Overflow),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Get<'a> {
     pub _type: &'a [u8],
     pub offset: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IncrBy<'a> {
     pub _type: &'a [u8],
     pub offset: &'a [u8],
     pub increment: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Set<'a> {
     pub _type: &'a [u8],
     pub offset: &'a [u8],
     pub value: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Overflow {
     WRAP,
     SAT,
@@ -91,13 +94,17 @@ pub(crate) fn parse_bitfield(mut iter: Iter<Vec<u8>>) -> BITFIELD {
         } else if arg_upper == "OVERFLOW" {
             let _type = String::from_utf8_lossy(iter.next().expect("bitfield 缺失OVERFLOW type"));
             let type_upper = &_type.to_uppercase();
-            if type_upper == "FAIL" {
-                overflows.push(Overflow::FAIL);
+            let overflow = if type_upper == "FAIL" {
+                Overflow::FAIL
             } else if type_upper == "SAT" {
-                overflows.push(Overflow::SAT);
+                Overflow::SAT
             } else if type_upper == "WRAP" {
-                overflows.push(Overflow::WRAP);
-            }
+                Overflow::WRAP
+            } else {
+                continue;
+            };
+            overflows.push(overflow);
+            statements.push(Operation::OVERFLOW(overflow));
         }
     }
 
@@ -120,19 +127,48 @@ pub(crate) fn parse_bitfield(mut iter: Iter<Vec<u8>>) -> BITFIELD {
     }
 }
 
-#[derive(Debug)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+pub struct BITFIELD_RO<'a> {
+    pub key: &'a [u8],
+    pub statements: Vec<Get<'a>>,
+}
+
+pub(crate) fn parse_bitfield_ro(mut iter: Iter<Vec<u8>>) -> BITFIELD_RO {
+    let key = iter.next().unwrap();
+    let mut statements = Vec::new();
+    while let Some(next_arg) = iter.next() {
+        let arg_upper = &String::from_utf8_lossy(next_arg).to_uppercase();
+        if arg_upper == "GET" {
+            let _type = iter.next().expect("bitfield_ro 缺失get type");
+            let offset = iter.next().expect("bitfield_ro 缺失get offset");
+            statements.push(Get { _type, offset });
+        }
+    }
+    BITFIELD_RO { key, statements }
+}
+
+#[derive(Debug, Clone)]
 pub struct BITOP<'a> {
     pub operation: Op,
     pub dest_key: &'a [u8],
     pub keys: Vec<&'a Vec<u8>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Op {
     AND,
     OR,
     XOR,
     NOT,
+    // 只在第一个key中存在，而不在其他任何key中存在的位 (Redis 8.2)
+    DIFF,
+    // 在其他key中存在，而不在第一个key中存在的位 (Redis 8.2)
+    DIFF1,
+    // 在第一个key中存在，并且在其他key中至少一个中存在的位 (Redis 8.2)
+    ANDOR,
+    // 只在一个key中存在的位 (Redis 8.2)
+    ONE,
 }
 
 pub(crate) fn parse_bitop(mut iter: Iter<Vec<u8>>) -> BITOP {
@@ -146,6 +182,14 @@ pub(crate) fn parse_bitop(mut iter: Iter<Vec<u8>>) -> BITOP {
         operation = XOR;
     } else if &op == "NOT" {
         operation = NOT;
+    } else if &op == "DIFF" {
+        operation = DIFF;
+    } else if &op == "DIFF1" {
+        operation = DIFF1;
+    } else if &op == "ANDOR" {
+        operation = ANDOR;
+    } else if &op == "ONE" {
+        operation = ONE;
     } else {
         panic!("bitop命令缺失operation")
     }
@@ -165,24 +209,30 @@ pub(crate) fn parse_bitop(mut iter: Iter<Vec<u8>>) -> BITOP {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SET<'a> {
     pub key: &'a [u8],
     pub value: &'a [u8],
     pub expire: Option<(ExpireType, &'a Vec<u8>)>,
     pub exist_type: Option<ExistType>,
     pub keep_ttl: Option<bool>,
+    /// 返回key原来的值
+    pub get: Option<bool>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ExpireType {
     // seconds -- Set the specified expire time, in seconds.
     EX,
     // milliseconds -- Set the specified expire time, in milliseconds.
     PX,
+    // timestamp-seconds -- Set the specified Unix time at which the key will expire, in seconds.
+    EXAT,
+    // timestamp-milliseconds -- Set the specified Unix time at which the key will expire, in milliseconds.
+    PXAT,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ExistType {
     // Only set the key if it does not already exist.
     NX,
@@ -195,32 +245,36 @@ pub(crate) fn parse_set(mut iter: Iter<Vec<u8>>) -> SET {
 
     let value = iter.next().unwrap();
 
-    let mut expire_time = None;
-    let mut expire_type = None;
     let mut exist_type = None;
     let mut expire = None;
     let mut keep_ttl = None;
+    let mut get = None;
 
-    for arg in iter {
+    while let Some(arg) = iter.next() {
         let arg_string = String::from_utf8_lossy(arg);
         let p_arg = &arg_string.to_uppercase();
-        if p_arg == "EX" {
-            expire_type = Some(ExpireType::EX);
+        let expire_type = if p_arg == "EX" {
+            ExpireType::EX
         } else if p_arg == "PX" {
-            expire_type = Some(ExpireType::PX);
-        } else if p_arg == "NX" {
-            exist_type = Some(ExistType::NX);
-        } else if p_arg == "XX" {
-            exist_type = Some(ExistType::XX);
-        } else if p_arg == "KEEPTTL" {
-            keep_ttl = Some(true)
+            ExpireType::PX
+        } else if p_arg == "EXAT" {
+            ExpireType::EXAT
+        } else if p_arg == "PXAT" {
+            ExpireType::PXAT
         } else {
-            // 读取过期时间
-            expire_time = Some(arg);
-        }
-    }
-    if expire_type.is_some() && expire_time.is_some() {
-        expire = Some((expire_type.unwrap(), expire_time.unwrap()));
+            if p_arg == "NX" {
+                exist_type = Some(ExistType::NX);
+            } else if p_arg == "XX" {
+                exist_type = Some(ExistType::XX);
+            } else if p_arg == "KEEPTTL" {
+                keep_ttl = Some(true);
+            } else if p_arg == "GET" {
+                get = Some(true);
+            }
+            continue;
+        };
+        // 读取过期时间
+        expire = Some((expire_type, iter.next().unwrap()));
     }
     SET {
         key,
@@ -228,10 +282,11 @@ pub(crate) fn parse_set(mut iter: Iter<Vec<u8>>) -> SET {
         exist_type,
         expire,
         keep_ttl,
+        get,
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SETEX<'a> {
     pub key: &'a [u8],
     pub seconds: &'a [u8],
@@ -245,7 +300,7 @@ pub(crate) fn parse_setex(mut iter: Iter<Vec<u8>>) -> SETEX {
     SETEX { key, seconds, value }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SETNX<'a> {
     pub key: &'a [u8],
     pub value: &'a [u8],
@@ -257,7 +312,7 @@ pub(crate) fn parse_setnx(mut iter: Iter<Vec<u8>>) -> SETNX {
     SETNX { key, value }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PSETEX<'a> {
     pub key: &'a [u8],
     pub milliseconds: &'a [u8],
@@ -275,7 +330,7 @@ pub(crate) fn parse_psetex(mut iter: Iter<Vec<u8>>) -> PSETEX {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SETRANGE<'a> {
     pub key: &'a [u8],
     pub offset: &'a [u8],
@@ -289,7 +344,7 @@ pub(crate) fn parse_setrange(mut iter: Iter<Vec<u8>>) -> SETRANGE {
     SETRANGE { key, offset, value }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DECR<'a> {
     pub key: &'a [u8],
 }
@@ -299,7 +354,7 @@ pub(crate) fn parse_decr(mut iter: Iter<Vec<u8>>) -> DECR {
     DECR { key }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DECRBY<'a> {
     pub key: &'a [u8],
     pub decrement: &'a [u8],
@@ -311,7 +366,7 @@ pub(crate) fn parse_decrby(mut iter: Iter<Vec<u8>>) -> DECRBY {
     DECRBY { key, decrement }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct INCR<'a> {
     pub key: &'a [u8],
 }
@@ -321,7 +376,7 @@ pub(crate) fn parse_incr(mut iter: Iter<Vec<u8>>) -> INCR {
     INCR { key }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct INCRBY<'a> {
     pub key: &'a [u8],
     pub increment: &'a [u8],
@@ -333,13 +388,13 @@ pub(crate) fn parse_incrby(mut iter: Iter<Vec<u8>>) -> INCRBY {
     INCRBY { key, increment }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct KeyValue<'a> {
     pub key: &'a [u8],
     pub value: &'a [u8],
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MSET<'a> {
     pub key_values: Vec<KeyValue<'a>>,
 }
@@ -357,7 +412,7 @@ pub(crate) fn parse_mset(mut iter: Iter<Vec<u8>>) -> MSET {
     MSET { key_values }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MSETNX<'a> {
     pub key_values: Vec<KeyValue<'a>>,
 }
@@ -375,7 +430,7 @@ pub(crate) fn parse_msetnx(mut iter: Iter<Vec<u8>>) -> MSETNX {
     MSETNX { key_values }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SETBIT<'a> {
     pub key: &'a [u8],
     pub offset: &'a [u8],
@@ -389,7 +444,51 @@ pub(crate) fn parse_setbit(mut iter: Iter<Vec<u8>>) -> SETBIT {
     SETBIT { key, value, offset }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct GETDEL<'a> {
+    pub key: &'a [u8],
+}
+
+pub(crate) fn parse_getdel(mut iter: Iter<Vec<u8>>) -> GETDEL {
+    let key = iter.next().unwrap();
+    GETDEL { key }
+}
+
+#[derive(Debug, Clone)]
+pub struct GETEX<'a> {
+    pub key: &'a [u8],
+    pub expire: Option<(ExpireType, &'a Vec<u8>)>,
+    /// 移除key的过期时间
+    pub persist: Option<bool>,
+}
+
+pub(crate) fn parse_getex(mut iter: Iter<Vec<u8>>) -> GETEX {
+    let key = iter.next().unwrap();
+    let mut expire = None;
+    let mut persist = None;
+    while let Some(arg) = iter.next() {
+        let arg_string = String::from_utf8_lossy(arg);
+        let p_arg = &arg_string.to_uppercase();
+        let expire_type = if p_arg == "EX" {
+            ExpireType::EX
+        } else if p_arg == "PX" {
+            ExpireType::PX
+        } else if p_arg == "EXAT" {
+            ExpireType::EXAT
+        } else if p_arg == "PXAT" {
+            ExpireType::PXAT
+        } else {
+            if p_arg == "PERSIST" {
+                persist = Some(true);
+            }
+            continue;
+        };
+        expire = Some((expire_type, iter.next().unwrap()));
+    }
+    GETEX { key, expire, persist }
+}
+
+#[derive(Debug, Clone)]
 pub struct GETSET<'a> {
     pub key: &'a [u8],
     pub value: &'a [u8],
@@ -0,0 +1,245 @@
+/*!
+将每个事件按顺序交给多个`EventHandler`，使统计、归档、重放等处理器可以同时运行
+
+```no_run
+use redis_event::aof::writer::AOFWriter;
+use redis_event::channel;
+use redis_event::composite::{CompositeHandler, ErrorPolicy};
+use redis_event::config::Config;
+use redis_event::listener::Builder;
+use redis_event::RedisListener;
+
+fn run(config: Config) -> std::io::Result<()> {
+    let (replayer, _receiver) = channel::channel(1024);
+    let mut handler = CompositeHandler::new();
+    // 重放出错时停止监听，归档出错时不影响重放
+    handler.add("replay", Box::new(replayer), ErrorPolicy::Propagate);
+    handler.add("archive", Box::new(AOFWriter::new("archive.aof")?), ErrorPolicy::Detach);
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(handler));
+    builder.build().start()
+}
+```
+*/
+use std::any::Any;
+use std::io::{Error, Result};
+use std::rc::Rc;
+
+#[cfg(not(feature = "tracing"))]
+use log::warn;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::rdb::{Meta, Module, Object, SkippedKey};
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// 处理器出错(即其`take_error`返回Some，或者`flush`返回Err)时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// 将错误交给监听器，由其按照`HandlerError`的类型停止监听或者重新同步；`flush`的错误则由`CompositeHandler::flush`返回。
+    /// 出错之后，同一条命令或者同一个RDB中之后的事件不再交给任何处理器
+    Propagate,
+    /// 记录日志之后忽略此错误，此处理器继续接收之后的事件
+    Ignore,
+    /// 记录日志之后不再将任何事件交给此处理器，其余的处理器不受影响
+    Detach,
+}
+
+struct Member {
+    name: String,
+    handler: Box<dyn EventHandler + Send>,
+    policy: ErrorPolicy,
+    is_detached: bool,
+}
+
+/// 将每个事件按照添加的顺序依次交给其中的各个处理器，每个处理器出错时的处理方式由其`ErrorPolicy`决定
+///
+/// 每个处理器收到的都是同一个事件的副本，事件中借用的数据不会被复制；Module的值通过引用计数共享，
+/// 各个处理器仍可通过`as_any`取得原来的值；`Object::SkippedKey`中的错误只保留其类型以及描述。
+///
+/// 处理器的`handle_transaction`无法经由`dyn EventHandler`调用，需要按事务处理时应将`TransactionGrouper`放在各个处理器之内
+pub struct CompositeHandler {
+    members: Vec<Member>,
+    error: Option<HandlerError>,
+}
+
+impl CompositeHandler {
+    pub fn new() -> CompositeHandler {
+        CompositeHandler {
+            members: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// 在末尾添加一个处理器
+    ///
+    /// 方法参数:
+    ///
+    /// * `name`: 处理器的名字，用于日志以及`detached`
+    /// * `handler`: 事件处理器，需要在监听结束之后读取其中的状态时，可以传入`Arc<Mutex<_>>`并保留一份`Arc`
+    /// * `policy`: 此处理器出错时的处理方式
+    pub fn add(&mut self, name: &str, handler: Box<dyn EventHandler + Send>, policy: ErrorPolicy) {
+        self.members.push(Member {
+            name: name.to_string(),
+            handler,
+            policy,
+            is_detached: false,
+        });
+    }
+
+    /// 因出错而不再接收事件的处理器(即`ErrorPolicy::Detach`)的名字
+    pub fn detached(&self) -> Vec<&str> {
+        self.members
+            .iter()
+            .filter(|member| member.is_detached)
+            .map(|member| member.name.as_str())
+            .collect()
+    }
+
+    fn dispatch(&mut self, event: Event, deliver: impl Fn(&mut dyn EventHandler, Event)) {
+        if self.error.is_some() {
+            return;
+        }
+        let template = Template::new(event);
+        for member in self.members.iter_mut().filter(|member| !member.is_detached) {
+            deliver(member.handler.as_mut(), template.event());
+            if let Some(error) = member.handler.take_error() {
+                match member.policy {
+                    ErrorPolicy::Propagate => {
+                        self.error = Some(error);
+                        return;
+                    }
+                    ErrorPolicy::Ignore => warn!("处理器{}出错: {}, 已忽略", member.name, error),
+                    ErrorPolicy::Detach => {
+                        warn!("处理器{}出错: {}, 不再将事件交给此处理器", member.name, error);
+                        member.is_detached = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for CompositeHandler {
+    fn default() -> Self {
+        CompositeHandler::new()
+    }
+}
+
+impl EventHandler for CompositeHandler {
+    fn handle(&mut self, event: Event) {
+        self.dispatch(event, |handler, event| handler.handle(event));
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.dispatch(event, |handler, event| handler.handle_aof(event, position));
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        self.dispatch(event, |handler, event| handler.handle_aof_raw(event, args, position));
+    }
+
+    /// 依次flush各个处理器，返回第一个`ErrorPolicy::Propagate`的处理器的错误，其余的处理器仍会被flush
+    fn flush(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for member in self.members.iter_mut().filter(|member| !member.is_detached) {
+            if let Err(error) = member.handler.flush() {
+                match member.policy {
+                    ErrorPolicy::Propagate => {
+                        if result.is_ok() {
+                            result = Err(error);
+                        }
+                    }
+                    ErrorPolicy::Ignore => warn!("处理器{} flush出错: {}, 已忽略", member.name, error),
+                    ErrorPolicy::Detach => {
+                        warn!("处理器{} flush出错: {}, 不再将事件交给此处理器", member.name, error);
+                        member.is_detached = true;
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// 各个处理器中最早的一个
+    fn pending_offset(&self) -> Option<i64> {
+        self.members
+            .iter()
+            .filter(|member| !member.is_detached)
+            .filter_map(|member| member.handler.pending_offset())
+            .min()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.error.take()
+    }
+}
+
+/// 可以复制出多个副本的事件，无法复制的部分改为共享或者重新构造
+enum Template<'a> {
+    Module(Vec<u8>, Rc<dyn Module>, &'a Meta),
+    ModuleAux(String, Rc<dyn Module>),
+    SkippedKey(Vec<u8>, u8, Error, &'a Meta),
+    Event(Event<'a>),
+}
+
+impl<'a> Template<'a> {
+    fn new(event: Event<'a>) -> Template<'a> {
+        match event {
+            Event::RDB(Object::Module(key, module, meta)) => Template::Module(key, Rc::from(module), meta),
+            Event::RDB(Object::ModuleAux(name, module)) => Template::ModuleAux(name, Rc::from(module)),
+            Event::RDB(Object::SkippedKey(skipped)) => {
+                Template::SkippedKey(skipped.key, skipped.value_type, skipped.error, skipped.meta)
+            }
+            event => Template::Event(event),
+        }
+    }
+
+    fn event(&self) -> Event<'a> {
+        match self {
+            Template::Module(key, module, meta) => Event::RDB(Object::Module(
+                key.clone(),
+                Box::new(SharedModule(Rc::clone(module))),
+                meta,
+            )),
+            Template::ModuleAux(name, module) => Event::RDB(Object::ModuleAux(
+                name.clone(),
+                Box::new(SharedModule(Rc::clone(module))),
+            )),
+            Template::SkippedKey(key, value_type, error, meta) => Event::RDB(Object::SkippedKey(SkippedKey {
+                key: key.clone(),
+                value_type: *value_type,
+                error: Error::new(error.kind(), error.to_string()),
+                meta,
+            })),
+            Template::Event(Event::AOF(cmd)) => Event::AOF(cmd.clone()),
+            Template::Event(Event::RDB(object)) => Event::RDB(match object {
+                Object::String(kv) => Object::String(kv.clone()),
+                Object::List(list) => Object::List(list.clone()),
+                Object::Set(set) => Object::Set(set.clone()),
+                Object::SortedSet(sorted_set) => Object::SortedSet(sorted_set.clone()),
+                Object::Hash(hash) => Object::Hash(hash.clone()),
+                Object::Stream(key, stream) => Object::Stream(key.clone(), stream.clone()),
+                Object::Function(code) => Object::Function(code.clone()),
+                Object::StringChunk(chunk) => Object::StringChunk(chunk.clone()),
+                Object::ResizeDB(resize_db) => Object::ResizeDB(*resize_db),
+                Object::BOR => Object::BOR,
+                Object::Stats(stats) => Object::Stats(stats),
+                Object::EOR => Object::EOR,
+                Object::Module(..) | Object::ModuleAux(..) | Object::SkippedKey(..) => {
+                    unreachable!("converted in Template::new")
+                }
+            }),
+        }
+    }
+}
+
+/// 共享同一个Module的值，`as_any`返回原来的值
+struct SharedModule(Rc<dyn Module>);
+
+impl Module for SharedModule {
+    fn as_any(&self) -> &dyn Any {
+        self.0.as_any()
+    }
+}
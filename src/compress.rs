@@ -0,0 +1,69 @@
+/*!
+解析压缩后的RDB以及AOF文件
+
+备份的RDB通常以gzip或zstd压缩后保存，如`dump.rdb.gz`。开启`compression-gzip`或者`compression-zstd` feature之后，
+`rdb`与`aof`模块中的`parse_file`以及`parse_reader`会根据输入开头的magic number自动进行解压，无需先解压到临时文件；
+需要自行读取解压后的数据时，可以使用[`decompress`]。
+
+```no_run
+use std::fs::File;
+use std::io;
+use redis_event::compress;
+
+let file = File::open("dump.rdb.zst").unwrap();
+let mut input = compress::decompress(file).unwrap();
+io::copy(&mut input, &mut File::create("dump.rdb").unwrap()).unwrap();
+```
+
+[`decompress`]: fn.decompress.html
+*/
+use std::io::{BufRead, BufReader, Read, Result};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// 根据输入流开头的magic number判断其压缩格式，返回解压之后的输入流；未压缩的输入流原样返回
+///
+/// 输入流为gzip或zstd格式，但未开启对应的feature时，返回`ErrorKind::InvalidInput`错误
+pub fn decompress<'a, R: Read + 'a>(input: R) -> Result<Box<dyn Read + 'a>> {
+    decompress_buffered(BufReader::new(input))
+}
+
+// 与`decompress`相同，但输入已带有缓冲(如内存中的数据)，未压缩时不再包装`BufReader`
+pub(crate) fn decompress_buffered<'a, R: BufRead + 'a>(mut input: R) -> Result<Box<dyn Read + 'a>> {
+    let magic = input.fill_buf()?;
+    if magic.starts_with(GZIP_MAGIC) {
+        gzip_decoder(input)
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        zstd_decoder(input)
+    } else {
+        Ok(Box::new(input))
+    }
+}
+
+#[cfg(feature = "compression-gzip")]
+fn gzip_decoder<'a, R: BufRead + 'a>(input: R) -> Result<Box<dyn Read + 'a>> {
+    // 与gunzip一样，支持由多个gzip member拼接而成的文件
+    Ok(Box::new(flate2::bufread::MultiGzDecoder::new(input)))
+}
+
+#[cfg(not(feature = "compression-gzip"))]
+fn gzip_decoder<'a, R: BufRead + 'a>(_: R) -> Result<Box<dyn Read + 'a>> {
+    Err(unsupported("gzip", "compression-gzip"))
+}
+
+#[cfg(feature = "compression-zstd")]
+fn zstd_decoder<'a, R: BufRead + 'a>(input: R) -> Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(zstd::stream::read::Decoder::with_buffer(input)?))
+}
+
+#[cfg(not(feature = "compression-zstd"))]
+fn zstd_decoder<'a, R: BufRead + 'a>(_: R) -> Result<Box<dyn Read + 'a>> {
+    Err(unsupported("zstd", "compression-zstd"))
+}
+
+#[cfg(not(all(feature = "compression-gzip", feature = "compression-zstd")))]
+fn unsupported(format: &str, feature: &str) -> std::io::Error {
+    let message = format!("输入为{}格式的压缩数据，需开启{} feature", format, feature);
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
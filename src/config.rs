@@ -3,7 +3,8 @@
 
 [`RedisListener`]: trait.RedisListener.html
 */
-use std::time::Duration;
+use std::cmp;
+use std::time::{Duration, SystemTime};
 
 /// 配置信息结构体定义
 #[derive(Debug)]
@@ -36,6 +37,159 @@ pub struct Config {
     pub identity: Option<String>,
     /// 解密Key所需的密码
     pub identity_passwd: Option<String>,
+    /// 超过此时长未从master收到任何数据(包括PING)时，认为连接已失效并进行重连
+    pub master_timeout: Option<Duration>,
+    /// AOF阶段在读取线程与处理线程之间使用的队列大小，队列满时暂停从socket读取数据，仅对非TLS连接有效
+    pub aof_queue_size: Option<usize>,
+    /// 是否在`EventHandler::flush`成功之后才确认offset，用于保证数据至少被处理一次
+    pub is_ack_after_handle: bool,
+    /// RDB中出现无法识别的数据类型或者不符合规范的数据(即`RDBError`)时，断开连接并重新进行全量同步的最大次数，超出后返回错误；
+    /// None为不重试，直接返回错误。处理器中的panic不会被捕获，也不会触发重新同步
+    pub max_full_resyncs: Option<u32>,
+    /// 遇到不符合规范的数据(如无法解析的浮点数、module数据末尾多余的字节)时的处理方式
+    pub parse_mode: ParseMode,
+    /// 解析某个key的值出错时，是否跳过此key并产生`Object::SkippedKey`事件，继续解析之后的数据；
+    /// 仅对ziplist、listpack、intset等整体保存为一个字符串的编码有效，其余情况仍然返回错误
+    pub is_skip_corrupt_keys: bool,
+    /// 是否丢弃RDB中已过期的key，与Redis加载RDB时的行为一致，被丢弃的key不会产生任何事件
+    pub is_discard_expired_keys: bool,
+    /// 判断key是否过期时所使用的时间，为None时使用解析时的系统时间
+    pub expire_reference_time: Option<SystemTime>,
+    /// String的值超过此长度时，将分为多个`Object::StringChunk`事件产生，用于限制超大值占用的内存；None为不分块
+    pub string_chunk_size: Option<usize>,
+    /// 是否将集合类型(List、Set、SortedSet、Hash)数据的所有元素合并为一个事件产生，
+    /// 下游无需再处理分批的情况，但元素较多的key将完整地保存在内存中
+    pub is_aggregate_collections: bool,
+    /// 只处理RDB中这些db的数据，其余db中的key只读出其字节，不进行解析也不产生事件；None为处理所有db
+    ///
+    /// 仅对RDB有效，AOF中的命令不会被过滤
+    pub db_filter: Option<Vec<isize>>,
+    /// 只处理RDB中与之匹配的key，其余key的值只读出其字节，不进行解析也不产生事件；None为处理所有的key
+    ///
+    /// 仅对RDB有效，AOF中的命令不会被过滤
+    pub key_filter: Option<KeyFilter>,
+    /// 全量同步时将接收到的RDB原样保存到此路径，无论是否跳过RDB(`is_discard_rdb`)；None为不保存
+    ///
+    /// 数据先写入`<path>.tmp`，接收完整后再重命名为此路径，每次全量同步都会覆盖之前保存的文件
+    pub rdb_backup_path: Option<String>,
+    /// 将RDB之后接收到的命令原样追加到此路径的文件中，PSYNC模式下每秒写入一次`#OFFSET:<offset>`注释；None为不保存
+    ///
+    /// 保存的文件可以通过`aof::parse_file`重新解析，其中的命令将带有原本的offset；
+    /// 每次全量同步时清空此文件，与`rdb_backup_path`一同使用时，两者组成一份完整的备份
+    pub aof_backup_path: Option<String>,
+    /// 是否在解析RDB的同时统计各类型key的个数、元素个数、最大的key、过期时间的分布以及各db的汇总，
+    /// 并在`Object::EOR`之前以`Object::Stats`事件产生，见[`rdb::stats`]
+    ///
+    /// [`rdb::stats`]: ../rdb/stats/index.html
+    pub is_collect_stats: bool,
+    /// `FallibleEventHandler`返回`HandlerError::Retry`时，断开连接并重新同步的最大次数，超出后返回错误；None为不重试，直接返回错误
+    pub max_handler_retries: Option<u32>,
+}
+
+/// 数据解析模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// 返回错误
+    Strict,
+    /// 记录日志，使用默认值或者跳过不符合规范的部分，继续解析；适用于同步历史遗留的脏数据
+    Lenient,
+}
+
+/// key的过滤条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyFilter {
+    /// 以指定的前缀开头
+    Prefix(Vec<u8>),
+    /// 与Redis中KEYS、SCAN命令相同的glob模式，支持`*`、`?`、`[...]`以及`\`转义
+    Glob(Vec<u8>),
+    /// 满足其中任意一个条件即可
+    Any(Vec<KeyFilter>),
+}
+
+impl KeyFilter {
+    /// key是否满足此条件
+    pub fn matches(&self, key: &[u8]) -> bool {
+        match self {
+            KeyFilter::Prefix(prefix) => key.starts_with(prefix),
+            KeyFilter::Glob(pattern) => glob_match(pattern, key),
+            KeyFilter::Any(filters) => filters.iter().any(|filter| filter.matches(key)),
+        }
+    }
+}
+
+// 移植自Redis util.c中的stringmatchlen
+fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let mut p = 0;
+    let mut s = 0;
+    while p < pattern.len() && s < string.len() {
+        match pattern[p] {
+            b'*' => {
+                while p + 1 < pattern.len() && pattern[p + 1] == b'*' {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+                return (s..string.len()).any(|i| glob_match(&pattern[p + 1..], &string[i..]));
+            }
+            b'?' => s += 1,
+            b'[' => {
+                p += 1;
+                let is_not = p < pattern.len() && pattern[p] == b'^';
+                if is_not {
+                    p += 1;
+                }
+                let mut is_match = false;
+                loop {
+                    if p >= pattern.len() {
+                        // 缺少']'
+                        p -= 1;
+                        break;
+                    } else if pattern[p] == b'\\' && pattern.len() - p >= 2 {
+                        p += 1;
+                        if pattern[p] == string[s] {
+                            is_match = true;
+                        }
+                    } else if pattern[p] == b']' {
+                        break;
+                    } else if pattern.len() - p >= 3 && pattern[p + 1] == b'-' {
+                        let start = cmp::min(pattern[p], pattern[p + 2]);
+                        let end = cmp::max(pattern[p], pattern[p + 2]);
+                        if string[s] >= start && string[s] <= end {
+                            is_match = true;
+                        }
+                        p += 2;
+                    } else if pattern[p] == string[s] {
+                        is_match = true;
+                    }
+                    p += 1;
+                }
+                if is_match == is_not {
+                    return false;
+                }
+                s += 1;
+            }
+            c => {
+                let c = if c == b'\\' && pattern.len() - p >= 2 {
+                    p += 1;
+                    pattern[p]
+                } else {
+                    c
+                };
+                if c != string[s] {
+                    return false;
+                }
+                s += 1;
+            }
+        }
+        p += 1;
+        if s == string.len() {
+            while p < pattern.len() && pattern[p] == b'*' {
+                p += 1;
+            }
+        }
+    }
+    p == pattern.len() && s == string.len()
 }
 
 impl Clone for Config {
@@ -55,6 +209,22 @@ impl Clone for Config {
             is_tls_insecure: self.is_tls_insecure,
             identity: self.identity.clone(),
             identity_passwd: self.identity_passwd.clone(),
+            master_timeout: self.master_timeout,
+            aof_queue_size: self.aof_queue_size,
+            is_ack_after_handle: self.is_ack_after_handle,
+            max_full_resyncs: self.max_full_resyncs,
+            parse_mode: self.parse_mode,
+            is_skip_corrupt_keys: self.is_skip_corrupt_keys,
+            is_discard_expired_keys: self.is_discard_expired_keys,
+            expire_reference_time: self.expire_reference_time,
+            string_chunk_size: self.string_chunk_size,
+            is_aggregate_collections: self.is_aggregate_collections,
+            db_filter: self.db_filter.clone(),
+            key_filter: self.key_filter.clone(),
+            rdb_backup_path: self.rdb_backup_path.clone(),
+            aof_backup_path: self.aof_backup_path.clone(),
+            is_collect_stats: self.is_collect_stats,
+            max_handler_retries: self.max_handler_retries,
         }
     }
 }
@@ -0,0 +1,51 @@
+// Redis所使用的crc64算法(Jones多项式)，用于校验RDB的完整性
+use std::cell::Cell;
+use std::io::{Read, Result};
+
+// 0xad93d23594c935a9按位反转之后的值
+const POLY: u64 = 0x95ac9329ac4bc9b5;
+
+const TABLE: [u64; 256] = make_table();
+
+const fn make_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut j = 0;
+        while j < 8 {
+            if crc & 1 == 1 {
+                crc = (crc >> 1) ^ POLY;
+            } else {
+                crc >>= 1;
+            }
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub(crate) fn crc64(mut crc: u64, data: &[u8]) -> u64 {
+    for byte in data {
+        crc = TABLE[((crc ^ *byte as u64) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+/// 在读取的同时计算所读取数据的crc64，并记录已读取的字节数
+pub(crate) struct CrcReader<'a> {
+    pub(crate) input: &'a mut dyn Read,
+    pub(crate) crc: &'a Cell<u64>,
+    pub(crate) position: &'a Cell<u64>,
+}
+
+impl Read for CrcReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.input.read(buf)?;
+        self.crc.set(crc64(self.crc.get(), &buf[..len]));
+        self.position.set(self.position.get() + len as u64);
+        Ok(len)
+    }
+}
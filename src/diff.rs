@@ -0,0 +1,256 @@
+/*!
+比较两份RDB中的数据，找出新增、删除、值不同以及过期时间不同的key，用于检查主库与备份之间的数据是否一致
+
+每份RDB先被汇总为一个[`Snapshot`]，其中每个key只保存值的摘要(crc64)以及过期时间，而不保存值本身。
+`Snapshot`本身是一个`EventHandler`，除了解析本地的RDB文件，也可以将其交给`Listener`(`is_aof`为false)，从运行中的Redis获取快照。
+
+Set、SortedSet以及Hash的摘要与元素的顺序无关，两份RDB中元素的保存顺序不同(如rehash之后)不会被视为值不同。
+
+```no_run
+use redis_event::diff::{self, Difference};
+
+diff::diff_files("primary.rdb", "backup.rdb", |difference| match difference {
+    Difference::Added { db, key } => println!("+ db{} {}", db, String::from_utf8_lossy(&key)),
+    Difference::Removed { db, key } => println!("- db{} {}", db, String::from_utf8_lossy(&key)),
+    Difference::ValueChanged { db, key } => println!("~ db{} {}", db, String::from_utf8_lossy(&key)),
+    Difference::ExpireChanged { db, key, old, new } => {
+        println!("~ db{} {} ttl: {:?} -> {:?}", db, String::from_utf8_lossy(&key), old, new)
+    }
+})
+.unwrap();
+```
+
+[`Snapshot`]: struct.Snapshot.html
+*/
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io::{Read, Result};
+use std::path::Path;
+
+use crate::crc64::crc64;
+use crate::rdb::{self, ChunkPosition, Meta, Object, RawModule};
+use crate::{Event, EventHandler};
+
+/// 两份RDB中某个key的差异
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// 只存在于新的RDB中的key
+    Added { db: isize, key: Vec<u8> },
+    /// 只存在于旧的RDB中的key
+    Removed { db: isize, key: Vec<u8> },
+    /// 两份RDB中都存在，但类型或者值不同
+    ValueChanged { db: isize, key: Vec<u8> },
+    /// 两份RDB中都存在，但过期时间(unix时间戳，单位毫秒)不同，None为不过期；值同时不同时，还会产生一个`ValueChanged`
+    ExpireChanged {
+        db: isize,
+        key: Vec<u8>,
+        old: Option<i64>,
+        new: Option<i64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    String,
+    List,
+    Set,
+    SortedSet,
+    Hash,
+    Stream,
+    Module,
+    // 因无法解析而被跳过的key(`Object::SkippedKey`)，只能与同样被跳过的key比较
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Digest {
+    value_type: ValueType,
+    value: u64,
+    expire: Option<i64>,
+}
+
+/// 一份RDB中所有key的摘要
+#[derive(Default)]
+pub struct Snapshot {
+    keys: BTreeMap<(isize, Vec<u8>), Digest>,
+}
+
+impl Snapshot {
+    pub fn new() -> Snapshot {
+        Snapshot::default()
+    }
+
+    /// 解析本地的RDB文件，得到其快照
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+        rdb::parse_file(path, &mut snapshot)?;
+        Ok(snapshot)
+    }
+
+    /// 从任意输入流中解析RDB，得到其快照，见`rdb::parse_reader`
+    pub fn from_reader<R: Read>(input: R) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+        rdb::parse_reader(input, &mut snapshot)?;
+        Ok(snapshot)
+    }
+
+    /// 快照中key的个数
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// 以此快照为旧的一方，与`new`进行比较，按db以及key的顺序将差异逐个交给`on_diff`
+    pub fn diff<F: FnMut(Difference)>(&self, new: &Snapshot, mut on_diff: F) {
+        let mut old_keys = self.keys.iter().peekable();
+        let mut new_keys = new.keys.iter().peekable();
+        loop {
+            let order = match (old_keys.peek(), new_keys.peek()) {
+                (Some((old_key, _)), Some((new_key, _))) => old_key.cmp(new_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match order {
+                Ordering::Less => {
+                    let ((db, key), _) = old_keys.next().unwrap();
+                    on_diff(Difference::Removed {
+                        db: *db,
+                        key: key.clone(),
+                    });
+                }
+                Ordering::Greater => {
+                    let ((db, key), _) = new_keys.next().unwrap();
+                    on_diff(Difference::Added {
+                        db: *db,
+                        key: key.clone(),
+                    });
+                }
+                Ordering::Equal => {
+                    let ((db, key), old) = old_keys.next().unwrap();
+                    let (_, new) = new_keys.next().unwrap();
+                    if old.value_type != new.value_type || old.value != new.value {
+                        on_diff(Difference::ValueChanged {
+                            db: *db,
+                            key: key.clone(),
+                        });
+                    }
+                    if old.expire != new.expire {
+                        on_diff(Difference::ExpireChanged {
+                            db: *db,
+                            key: key.clone(),
+                            old: old.expire,
+                            new: new.expire,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // 返回key的摘要，is_first为true时重新开始计算
+    fn digest(&mut self, meta: &Meta, key: &[u8], value_type: ValueType, is_first: bool) -> &mut Digest {
+        let empty = Digest {
+            value_type,
+            value: 0,
+            expire: meta.expire_millis(),
+        };
+        let digest = self.keys.entry((meta.db, key.to_vec())).or_insert(empty);
+        if is_first {
+            *digest = empty;
+        }
+        digest
+    }
+}
+
+impl EventHandler for Snapshot {
+    fn handle(&mut self, event: Event) {
+        let object = match event {
+            Event::RDB(object) => object,
+            Event::AOF(_) => return,
+        };
+        match &object {
+            Object::String(kv) => {
+                let digest = self.digest(kv.meta, kv.key, ValueType::String, true);
+                digest.value = crc64(0, kv.value);
+            }
+            Object::StringChunk(chunk) => {
+                // crc64可以分段计算，分块与否得到的摘要相同
+                let is_first = chunk.position == ChunkPosition::Begin;
+                let digest = self.digest(chunk.meta, chunk.key, ValueType::String, is_first);
+                digest.value = crc64(digest.value, chunk.chunk);
+            }
+            Object::List(list) => {
+                let digest = self.digest(list.meta, list.key, ValueType::List, list.is_first);
+                for value in list.values {
+                    digest.value = crc64(digest.value, &(value.len() as u64).to_le_bytes());
+                    digest.value = crc64(digest.value, value);
+                }
+            }
+            // 集合类型中每个元素单独计算摘要再相加，与元素的顺序无关
+            Object::Set(set) => {
+                let digest = self.digest(set.meta, set.key, ValueType::Set, set.is_first);
+                for member in set.members {
+                    digest.value = digest.value.wrapping_add(crc64(0, member));
+                }
+            }
+            Object::SortedSet(sorted_set) => {
+                let digest = self.digest(
+                    sorted_set.meta,
+                    sorted_set.key,
+                    ValueType::SortedSet,
+                    sorted_set.is_first,
+                );
+                for item in sorted_set.items {
+                    let crc = crc64(0, &item.score.to_bits().to_le_bytes());
+                    digest.value = digest.value.wrapping_add(crc64(crc, &item.member));
+                }
+            }
+            Object::Hash(hash) => {
+                let digest = self.digest(hash.meta, hash.key, ValueType::Hash, hash.is_first);
+                for field in hash.fields {
+                    let mut crc = crc64(0, &field.expire.unwrap_or(-1).to_le_bytes());
+                    crc = crc64(crc, &(field.name.len() as u64).to_le_bytes());
+                    crc = crc64(crc, &field.name);
+                    digest.value = digest.value.wrapping_add(crc64(crc, &field.value));
+                }
+            }
+            Object::Stream(key, stream) => {
+                // 以重建此stream所需的命令计算摘要，包括其中的元素、consumer group以及PEL
+                let mut value = 0;
+                for command in object.to_commands() {
+                    for arg in command {
+                        value = crc64(value, &(arg.len() as u64).to_le_bytes());
+                        value = crc64(value, &arg);
+                    }
+                }
+                self.digest(stream.meta, key, ValueType::Stream, true).value = value;
+            }
+            Object::Module(key, module, meta) => {
+                // 注册了ModuleParser时无法得知module的内容，只比较是否存在以及过期时间
+                let value = match module.as_any().downcast_ref::<RawModule>() {
+                    Some(raw) => crc64(crc64(0, raw.name.as_bytes()), &raw.data),
+                    None => 0,
+                };
+                self.digest(meta, key, ValueType::Module, true).value = value;
+            }
+            Object::SkippedKey(skipped) => {
+                self.digest(skipped.meta, &skipped.key, ValueType::Skipped, true);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 解析两个本地的RDB文件并进行比较，见[`Snapshot::diff`]
+///
+/// [`Snapshot::diff`]: struct.Snapshot.html#method.diff
+pub fn diff_files<P: AsRef<Path>, Q: AsRef<Path>, F: FnMut(Difference)>(old: P, new: Q, on_diff: F) -> Result<()> {
+    let old = Snapshot::from_file(old)?;
+    let new = Snapshot::from_file(new)?;
+    old.diff(&new, on_diff);
+    Ok(())
+}
@@ -0,0 +1,175 @@
+/*!
+将`Event::RDB`中的数据序列化为`DUMP`命令所返回的格式
+
+序列化的结果可以直接作为`RESTORE`命令的参数写入到另一个Redis中，比逐条执行写命令更加高效。
+集合类型的数据默认会分批产生事件，而`RESTORE`需要完整的值，因此需要开启`Config::is_aggregate_collections`。
+
+反过来，[`parse`]可将`RESTORE`命令中的payload解析为`Event::RDB`事件。
+
+```
+use redis_event::dump;
+use redis_event::rdb::{KeyValue, Meta, Object};
+
+let meta = Meta { db: 0, expire: None, evict: None };
+let object = Object::String(KeyValue { key: b"foo", value: b"bar", meta: &meta });
+let payload = dump::dump(&object).unwrap();
+// RESTORE foo 0 <payload>
+assert_eq!(&[0, 3, b'b', b'a', b'r'], &payload[..5]);
+```
+*/
+use std::io::{Error, ErrorKind, Result};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::crc64::crc64;
+use crate::rdb::{
+    self, Field, Meta, Object, RDB_32BITLEN, RDB_64BITLEN, RDB_TYPE_HASH, RDB_TYPE_HASH_METADATA, RDB_TYPE_LIST,
+    RDB_TYPE_SET, RDB_TYPE_STRING, RDB_TYPE_ZSET_2,
+};
+use crate::EventHandler;
+
+// 各个类型最早出现时的RDB版本，Redis会拒绝RDB版本高于自身的payload，因此尽量使用低的版本
+const RDB_VERSION_BASE: u16 = 6;
+const RDB_VERSION_ZSET_2: u16 = 8;
+const RDB_VERSION_HASH_METADATA: u16 = 12;
+
+/// 将String、List、Set、SortedSet以及Hash类型的数据序列化为`DUMP`的格式，末尾带有RDB版本以及crc64校验和
+///
+/// 数据的过期时间不包含在结果中，需要通过`RESTORE`的ttl参数指定，见[`Meta::expire_millis`]；
+/// 分批产生的数据中的一批，以及其他类型的数据将返回`ErrorKind::InvalidInput`错误
+///
+/// [`Meta::expire_millis`]: ../rdb/struct.Meta.html#method.expire_millis
+pub fn dump(object: &Object) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    let version = write_object(&mut payload, object)?;
+    payload.write_u16::<LittleEndian>(version)?;
+    let crc = crc64(0, &payload);
+    payload.write_u64::<LittleEndian>(crc)?;
+    Ok(payload)
+}
+
+/// 解析`DUMP`格式的payload(如`RESTORE`命令中的值)，以`key`以及`meta`产生对应的`Event::RDB`事件
+///
+/// 会先校验末尾的crc64校验和，集合类型的数据在一个事件中完整产生。
+/// payload不完整或校验和不一致时返回`ErrorKind::InvalidData`错误
+pub fn parse(key: &[u8], payload: &[u8], meta: &Meta, event_handler: &mut dyn EventHandler) -> Result<()> {
+    // 至少包含1字节的类型、2字节的RDB版本以及8字节的校验和
+    if payload.len() < 11 {
+        return Err(Error::new(ErrorKind::InvalidData, "DUMP payload不完整"));
+    }
+    let (body, mut crc) = payload.split_at(payload.len() - 8);
+    if crc64(0, body) != crc.read_u64::<LittleEndian>()? {
+        return Err(Error::new(ErrorKind::InvalidData, "DUMP payload的校验和不一致"));
+    }
+    let mut value = &body[1..body.len() - 2];
+    rdb::parse_value(&mut value, body[0], key.to_vec(), meta, event_handler)
+}
+
+// 写入值的类型以及值，返回所需的最低RDB版本
+pub(crate) fn write_object(out: &mut Vec<u8>, object: &Object) -> Result<u16> {
+    match object {
+        Object::String(kv) => {
+            out.push(RDB_TYPE_STRING);
+            write_string(out, kv.value);
+            Ok(RDB_VERSION_BASE)
+        }
+        Object::List(list) => {
+            check_complete(list.key, list.is_first, list.is_last)?;
+            out.push(RDB_TYPE_LIST);
+            write_length(out, list.values.len() as u64);
+            for value in list.values {
+                write_string(out, value);
+            }
+            Ok(RDB_VERSION_BASE)
+        }
+        Object::Set(set) => {
+            check_complete(set.key, set.is_first, set.is_last)?;
+            out.push(RDB_TYPE_SET);
+            write_length(out, set.members.len() as u64);
+            for member in set.members {
+                write_string(out, member);
+            }
+            Ok(RDB_VERSION_BASE)
+        }
+        Object::SortedSet(sorted_set) => {
+            check_complete(sorted_set.key, sorted_set.is_first, sorted_set.is_last)?;
+            out.push(RDB_TYPE_ZSET_2);
+            write_length(out, sorted_set.items.len() as u64);
+            for item in sorted_set.items {
+                write_string(out, &item.member);
+                out.write_f64::<LittleEndian>(item.score)?;
+            }
+            Ok(RDB_VERSION_ZSET_2)
+        }
+        Object::Hash(hash) => {
+            check_complete(hash.key, hash.is_first, hash.is_last)?;
+            match hash.fields.iter().filter_map(|field| field.expire).min() {
+                Some(min_expire) => {
+                    write_hash_metadata(out, hash.fields, min_expire)?;
+                    Ok(RDB_VERSION_HASH_METADATA)
+                }
+                None => {
+                    out.push(RDB_TYPE_HASH);
+                    write_length(out, hash.fields.len() as u64);
+                    for field in hash.fields {
+                        write_string(out, &field.name);
+                        write_string(out, &field.value);
+                    }
+                    Ok(RDB_VERSION_BASE)
+                }
+            }
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "只支持序列化String、List、Set、SortedSet以及Hash类型的数据",
+        )),
+    }
+}
+
+// 带有字段过期时间的Hash，与Redis 7.4一致，各字段的过期时间以相对于最早过期时间的偏移量再加1保存，0代表不过期
+fn write_hash_metadata(out: &mut Vec<u8>, fields: &[Field], min_expire: i64) -> Result<()> {
+    out.push(RDB_TYPE_HASH_METADATA);
+    out.write_i64::<LittleEndian>(min_expire)?;
+    write_length(out, fields.len() as u64);
+    for field in fields {
+        match field.expire {
+            Some(expire) => write_length(out, (expire - min_expire) as u64 + 1),
+            None => write_length(out, 0),
+        }
+        write_string(out, &field.name);
+        write_string(out, &field.value);
+    }
+    Ok(())
+}
+
+fn check_complete(key: &[u8], is_first: bool, is_last: bool) -> Result<()> {
+    if is_first && is_last {
+        Ok(())
+    } else {
+        let message = format!(
+            "key {}的值分为了多批产生，需开启Config::is_aggregate_collections",
+            String::from_utf8_lossy(key)
+        );
+        Err(Error::new(ErrorKind::InvalidInput, message))
+    }
+}
+
+pub(crate) fn write_length(out: &mut Vec<u8>, length: u64) {
+    if length < 1 << 6 {
+        out.push(length as u8);
+    } else if length < 1 << 14 {
+        out.push(0x40 | (length >> 8) as u8);
+        out.push(length as u8);
+    } else if length <= u32::MAX as u64 {
+        out.push(RDB_32BITLEN);
+        out.write_u32::<BigEndian>(length as u32).unwrap();
+    } else {
+        out.push(RDB_64BITLEN);
+        out.write_u64::<BigEndian>(length).unwrap();
+    }
+}
+
+pub(crate) fn write_string(out: &mut Vec<u8>, value: &[u8]) {
+    write_length(out, value.len() as u64);
+    out.extend_from_slice(value);
+}
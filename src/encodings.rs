@@ -0,0 +1,292 @@
+/*!
+ziplist、zipmap、intset等Redis紧凑编码的解析
+
+这些编码出现在RDB中小的List、Hash、SortedSet以及Set中，解析`DUMP`命令返回的数据，或者实现自定义的[`ModuleParser`]时，
+可以使用此模块读取其中的元素。Redis 7.0之后所使用的listpack见[`listpack`]模块。
+
+```
+use redis_event::encodings::IntSet;
+
+// 以int16保存的intset，包含1和-2两个元素
+let data = [2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0xFE, 0xFF];
+let members: Vec<i64> = IntSet::new(&data).unwrap().collect();
+assert_eq!(vec![1, -2], members);
+```
+
+[`ModuleParser`]: ../trait.ModuleParser.html
+[`listpack`]: ../listpack/index.html
+*/
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+
+use crate::rdb::{ZIP_INT_16BIT, ZIP_INT_24BIT, ZIP_INT_32BIT, ZIP_INT_64BIT, ZIP_INT_8BIT};
+
+// ZL_BYTES、ZL_TAIL和ZL_LEN所占的字节数
+const ZIP_LIST_HEADER_SIZE: u64 = 10;
+// 元素个数超过65535时，ZL_LEN为此值，只能遍历整个ziplist才能得知元素个数
+const ZIP_LIST_LEN_UNKNOWN: u16 = u16::MAX;
+const ZIP_LIST_END: u8 = 255;
+const ZIP_MAP_BIGLEN: u8 = 254;
+const ZIP_MAP_END: u8 = 255;
+
+/// ziplist中元素的迭代器
+///
+/// 整数类型的元素将被转换为其字符串形式，与Redis中读取到的值一致
+pub struct ZipList<'a> {
+    cursor: Cursor<&'a [u8]>,
+    len: u16,
+    is_done: bool,
+}
+
+impl<'a> ZipList<'a> {
+    /// 校验ziplist的头部，返回其元素的迭代器
+    pub fn new(data: &'a [u8]) -> Result<ZipList<'a>> {
+        let mut cursor = Cursor::new(data);
+        let total_bytes = cursor.read_u32::<LittleEndian>()? as usize;
+        let _tail = cursor.read_u32::<LittleEndian>()?;
+        let len = cursor.read_u16::<LittleEndian>()?;
+        if total_bytes < ZIP_LIST_HEADER_SIZE as usize + 1 || total_bytes > data.len() {
+            let message = format!("ziplist的长度错误: {}, 实际长度: {}", total_bytes, data.len());
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        let mut cursor = Cursor::new(&data[..total_bytes]);
+        cursor.set_position(ZIP_LIST_HEADER_SIZE);
+        Ok(ZipList {
+            cursor,
+            len,
+            is_done: false,
+        })
+    }
+
+    /// ziplist头部记录的元素个数，元素个数不少于65535时为None
+    pub fn num_elements(&self) -> Option<usize> {
+        if self.len == ZIP_LIST_LEN_UNKNOWN {
+            None
+        } else {
+            Some(self.len as usize)
+        }
+    }
+}
+
+impl Iterator for ZipList<'_> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        let pos = self.cursor.position() as usize;
+        match self.cursor.get_ref().get(pos) {
+            Some(&ZIP_LIST_END) => {
+                self.is_done = true;
+                None
+            }
+            Some(_) => {
+                let entry = read_zip_list_entry(&mut self.cursor);
+                // 出错之后无法再确定下一个元素的位置
+                self.is_done = entry.is_err();
+                Some(entry)
+            }
+            None => {
+                self.is_done = true;
+                Some(Err(Error::new(ErrorKind::UnexpectedEof, "ziplist缺少结束标志")))
+            }
+        }
+    }
+}
+
+/// 从流中读取ziplist中的一个元素，包括其开头的prevlen
+pub fn read_zip_list_entry(input: &mut dyn Read) -> Result<Vec<u8>> {
+    if input.read_u8()? >= 254 {
+        input.read_u32::<LittleEndian>()?;
+    }
+    let flag = input.read_u8()?;
+    match flag >> 6 {
+        0 => {
+            let length = flag & 0x3F;
+            let mut buff = vec![0; length as usize];
+            input.read_exact(&mut buff)?;
+            return Ok(buff);
+        }
+        1 => {
+            let next_byte = input.read_u8()?;
+            let length = (((flag as u16) & 0x3F) << 8) | (next_byte as u16);
+            let mut buff = vec![0; length as usize];
+            input.read_exact(&mut buff)?;
+            return Ok(buff);
+        }
+        2 => {
+            let length = input.read_u32::<BigEndian>()?;
+            let mut buff = vec![0; length as usize];
+            input.read_exact(&mut buff)?;
+            return Ok(buff);
+        }
+        _ => {}
+    }
+    return match flag {
+        ZIP_INT_8BIT => {
+            let int = input.read_i8()?;
+            Ok(int.to_string().into_bytes())
+        }
+        ZIP_INT_16BIT => {
+            let int = input.read_i16::<LittleEndian>()?;
+            Ok(int.to_string().into_bytes())
+        }
+        ZIP_INT_24BIT => {
+            let int = input.read_i24::<LittleEndian>()?;
+            Ok(int.to_string().into_bytes())
+        }
+        ZIP_INT_32BIT => {
+            let int = input.read_i32::<LittleEndian>()?;
+            Ok(int.to_string().into_bytes())
+        }
+        ZIP_INT_64BIT => {
+            let int = input.read_i64::<LittleEndian>()?;
+            Ok(int.to_string().into_bytes())
+        }
+        0xF1..=0xFD => {
+            // 0到12之间的整数直接保存在flag的低4位中
+            let result = (flag - 0xF1) as isize;
+            Ok(result.to_string().into_bytes())
+        }
+        _ => {
+            let message = format!("无效的ziplist元素编码: {:#04x}", flag);
+            Err(Error::new(ErrorKind::InvalidData, message))
+        }
+    };
+}
+
+/// zipmap中字段的迭代器，每个元素为字段名和字段值
+pub struct ZipMap<'a> {
+    cursor: Cursor<&'a [u8]>,
+    len: u8,
+    is_done: bool,
+}
+
+impl<'a> ZipMap<'a> {
+    pub fn new(data: &'a [u8]) -> Result<ZipMap<'a>> {
+        let mut cursor = Cursor::new(data);
+        let len = cursor.read_u8()?;
+        Ok(ZipMap {
+            cursor,
+            len,
+            is_done: false,
+        })
+    }
+
+    /// zipmap头部记录的字段个数，字段个数不少于254时为None
+    pub fn num_fields(&self) -> Option<usize> {
+        if self.len < ZIP_MAP_BIGLEN {
+            Some(self.len as usize)
+        } else {
+            None
+        }
+    }
+
+    fn read_field(&mut self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let len = read_zip_map_len(&mut self.cursor)?;
+        if len == ZIP_MAP_END as usize {
+            return Ok(None);
+        }
+        let mut field = vec![0; len];
+        self.cursor.read_exact(&mut field)?;
+        let len = read_zip_map_len(&mut self.cursor)?;
+        if len == ZIP_MAP_END as usize {
+            self.is_done = true;
+            return Ok(Some((field, Vec::new())));
+        }
+        // 值之后预留的空闲字节
+        let free = self.cursor.read_u8()?;
+        let mut value = vec![0; len];
+        self.cursor.read_exact(&mut value)?;
+        self.cursor.set_position(self.cursor.position() + free as u64);
+        Ok(Some((field, value)))
+    }
+}
+
+impl Iterator for ZipMap<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        match self.read_field() {
+            Ok(Some(field)) => Some(Ok(field)),
+            Ok(None) => {
+                self.is_done = true;
+                None
+            }
+            Err(err) => {
+                self.is_done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+fn read_zip_map_len(input: &mut dyn Read) -> Result<usize> {
+    let len = input.read_u8()?;
+    if len == ZIP_MAP_BIGLEN {
+        let value = input.read_u32::<BigEndian>()?;
+        return Ok(value as usize);
+    }
+    Ok(len as usize)
+}
+
+/// intset中元素的迭代器
+pub struct IntSet<'a> {
+    cursor: Cursor<&'a [u8]>,
+    encoding: u32,
+    remaining: u32,
+}
+
+impl<'a> IntSet<'a> {
+    /// 校验intset的头部，返回其元素的迭代器
+    pub fn new(data: &'a [u8]) -> Result<IntSet<'a>> {
+        let mut cursor = Cursor::new(data);
+        let encoding = cursor.read_u32::<LittleEndian>()?;
+        let length = cursor.read_u32::<LittleEndian>()?;
+        if encoding != 2 && encoding != 4 && encoding != 8 {
+            let message = format!("无效的intset编码: {}", encoding);
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        if 8 + encoding as u64 * length as u64 > data.len() as u64 {
+            let message = format!("intset的长度错误: {}, 实际长度: {}", length, data.len());
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        Ok(IntSet {
+            cursor,
+            encoding,
+            remaining: length,
+        })
+    }
+
+    /// 每个元素所占的字节数，2、4或者8
+    pub fn encoding(&self) -> u32 {
+        self.encoding
+    }
+}
+
+impl Iterator for IntSet<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        // 长度已在创建时校验过，这里的读取不会失败
+        let value = match self.encoding {
+            2 => self.cursor.read_i16::<LittleEndian>().ok()? as i64,
+            4 => self.cursor.read_i32::<LittleEndian>().ok()? as i64,
+            _ => self.cursor.read_i64::<LittleEndian>().ok()?,
+        };
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+将事件导出为文本格式的`EventHandler`，用于导入数据湖、审计以及排查问题
+
+- [`json::JsonLinesWriter`](json/struct.JsonLinesWriter.html): 每个事件输出为一行JSON
+- [`csv::CsvWriter`](csv/struct.CsvWriter.html): RDB中的每个元素输出为一行CSV或者TSV
+
+二进制的key以及值按照[`Encoding`]输出，写入文件时可以使用[`RotatingFile`]按大小或者时间切换文件。
+
+[`Encoding`]: enum.Encoding.html
+[`RotatingFile`]: struct.RotatingFile.html
+*/
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Result, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::base64::encode_base64;
+use crate::io;
+
+pub mod csv;
+pub mod json;
+
+/// 二进制数据(key、值等)的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// 按UTF-8输出，无法解码的字节输出为`\xNN`，适合阅读
+    Utf8,
+    /// 标准的base64编码(带`=`填充)，可以还原出原始的字节
+    Base64,
+    /// 小写的十六进制编码，可以还原出原始的字节
+    Hex,
+}
+
+impl Encoding {
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => match std::str::from_utf8(bytes) {
+                Ok(str) => str.to_string(),
+                Err(_) => encode_utf8(bytes),
+            },
+            Encoding::Base64 => encode_base64(bytes),
+            Encoding::Hex => {
+                let mut encoded = String::with_capacity(bytes.len() * 2);
+                for byte in bytes {
+                    let _ = write!(encoded, "{:02x}", byte);
+                }
+                encoded
+            }
+        }
+    }
+}
+
+// 逐段解码，合法的UTF-8原样保留，无法解码的字节输出为\xNN
+fn encode_utf8(mut bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    while !bytes.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(bytes) {
+            Ok(valid) => (valid, 0),
+            Err(err) => {
+                let valid = std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap();
+                (valid, err.error_len().unwrap_or(bytes.len() - err.valid_up_to()))
+            }
+        };
+        encoded.push_str(valid);
+        bytes = &bytes[valid.len()..];
+        for byte in &bytes[..invalid] {
+            let _ = write!(encoded, "\\x{:02x}", byte);
+        }
+        bytes = &bytes[invalid..];
+    }
+    encoded
+}
+
+/// 可以按大小或者时间切换的文件，只在一行写完之后(即最后写入的字节为`\n`时)切换，因此每一行都完整地位于同一个文件中
+///
+/// 文件以追加的方式打开，切换时当前文件被重命名为`<path>.1`、`<path>.2`…(数字越大越新)，并重新创建`<path>`继续写入，
+/// 与`AOFWriter`的切换方式相同
+pub struct RotatingFile {
+    path: PathBuf,
+    output: BufWriter<File>,
+    // 当前文件的大小
+    size: u64,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    // 当前文件的打开时间
+    opened: Instant,
+    is_line_end: bool,
+    // 最近一次切换时所使用的序号
+    index: Option<u64>,
+}
+
+impl RotatingFile {
+    /// 以追加的方式打开或者创建`path`，默认不切换
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<RotatingFile> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            output: BufWriter::new(file),
+            size,
+            max_size: None,
+            max_age: None,
+            opened: Instant::now(),
+            is_line_end: true,
+            index: None,
+        })
+    }
+
+    /// 文件超过此大小(字节)之后切换到新的文件
+    pub fn with_max_size(&mut self, max_size: u64) {
+        self.max_size = Some(max_size);
+    }
+
+    /// 文件打开超过此时间之后切换到新的文件，空的文件不会被切换
+    pub fn with_max_age(&mut self, max_age: Duration) {
+        self.max_age = Some(max_age);
+    }
+
+    fn is_rotate_needed(&self) -> bool {
+        if !self.is_line_end || self.size == 0 {
+            return false;
+        }
+        let is_too_large = matches!(self.max_size, Some(max_size) if self.size >= max_size);
+        let is_too_old = matches!(self.max_age, Some(max_age) if self.opened.elapsed() >= max_age);
+        is_too_large || is_too_old
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.output.flush()?;
+        let index = match self.index {
+            Some(index) => index + 1,
+            None => io::last_rotated_index(&self.path)? + 1,
+        };
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", index));
+        fs::rename(&self.path, rotated)?;
+        self.index = Some(index);
+        self.output = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        self.opened = Instant::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.is_rotate_needed() {
+            self.rotate()?;
+        }
+        let written = self.output.write(buf)?;
+        if written > 0 {
+            self.size += written as u64;
+            self.is_line_end = buf[written - 1] == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.output.flush()
+    }
+}
@@ -0,0 +1,267 @@
+/*!
+将RDB中的数据输出为CSV或者TSV，每个元素一行，便于审计或者导入表格软件分析
+
+```no_run
+use redis_event::export::csv::CsvWriter;
+use redis_event::rdb;
+
+fn main() -> std::io::Result<()> {
+    let output = std::fs::File::create("dump.tsv")?;
+    let mut writer = CsvWriter::new(output);
+    writer.with_delimiter(b'\t');
+    rdb::parse_file("dump.rdb", &mut writer)?;
+    Ok(())
+}
+```
+
+输出的列依次为`db,key,type,field,value,ttl`，第一行为表头:
+
+| type | field | value |
+| ---- | ----- | ----- |
+| `string` | 空 | 值 |
+| `string_chunk` | 分块在值中的偏移量 | 分块的内容 |
+| `list` | 元素的下标 | 元素 |
+| `set` | 空 | 元素 |
+| `sorted_set` | 元素 | 分值 |
+| `hash` | 字段名 | 字段值 |
+| `stream` | `<消息ID>:<字段名>` | 字段值 |
+| `module` | Module的名称(仅`RawModule`) | 空 |
+
+`ttl`为剩余的存活时间(毫秒)，不过期时为空，已过期时为0；Hash中单独设置了过期时间的字段以字段的过期时间为准。
+命令以及RDB中的其他事件(如Function、统计信息)不会输出。
+
+包含分隔符、双引号或者换行的值按照RFC 4180加上双引号，其中的双引号写为两个双引号。
+*/
+use std::io::{Error, Result, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::export::Encoding;
+use crate::rdb::{Meta, Object, RawModule};
+use crate::{Event, EventHandler, HandlerError};
+
+const HEADER: [&str; 6] = ["db", "key", "type", "field", "value", "ttl"];
+
+/// 将RDB中的数据输出为CSV或者TSV的处理器，见[模块文档](index.html)
+///
+/// RDB结束时会调用`output`的`flush`；写入出错之后不再输出，错误通过`EventHandler::flush`返回
+pub struct CsvWriter<W: Write> {
+    output: W,
+    delimiter: u8,
+    key_encoding: Encoding,
+    value_encoding: Encoding,
+    is_header: bool,
+    reference_time: Option<SystemTime>,
+    // 分批产生的List中下一个元素的下标
+    list_index: usize,
+    error: Option<Error>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// 以`,`分隔，输出表头，key与值按`Encoding::Utf8`输出
+    pub fn new(output: W) -> CsvWriter<W> {
+        CsvWriter {
+            output,
+            delimiter: b',',
+            key_encoding: Encoding::Utf8,
+            value_encoding: Encoding::Utf8,
+            is_header: true,
+            reference_time: None,
+            list_index: 0,
+            error: None,
+        }
+    }
+
+    /// 列之间的分隔符，输出TSV时为`\t`
+    pub fn with_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    /// 是否在第一行输出表头，追加到已有的文件中时可以关闭
+    pub fn with_header(&mut self, is_header: bool) {
+        self.is_header = is_header;
+    }
+
+    /// key的编码
+    pub fn with_key_encoding(&mut self, encoding: Encoding) {
+        self.key_encoding = encoding;
+    }
+
+    /// 值、集合的元素以及字段名的编码
+    pub fn with_value_encoding(&mut self, encoding: Encoding) {
+        self.value_encoding = encoding;
+    }
+
+    /// 计算`ttl`时所使用的当前时间，None(默认)时使用写入时的系统时间
+    pub fn with_reference_time(&mut self, reference_time: Option<SystemTime>) {
+        self.reference_time = reference_time;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn now_millis(&self) -> i64 {
+        let now = self.reference_time.unwrap_or_else(SystemTime::now);
+        now.duration_since(UNIX_EPOCH).map_or(0, |now| now.as_millis() as i64)
+    }
+
+    fn ttl(&self, expire: Option<i64>) -> String {
+        match expire {
+            Some(expire) => (expire - self.now_millis()).max(0).to_string(),
+            None => String::new(),
+        }
+    }
+
+    fn write_row(&mut self, meta: &Meta, key: &[u8], kind: &str, field: &str, value: &str, expire: Option<i64>) {
+        let db = meta.db.to_string();
+        let key = self.key_encoding.encode(key);
+        let ttl = self.ttl(expire);
+        self.write_line(&[&db, &key, kind, field, value, &ttl]);
+    }
+
+    fn write_line(&mut self, columns: &[&str]) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.is_header {
+            self.is_header = false;
+            self.write_line(&HEADER);
+        }
+        let mut line = Vec::new();
+        for (index, column) in columns.iter().enumerate() {
+            if index > 0 {
+                line.push(self.delimiter);
+            }
+            quote(column, self.delimiter, &mut line);
+        }
+        line.push(b'\n');
+        if let Err(err) = self.output.write_all(&line) {
+            self.error = Some(err);
+        }
+    }
+
+    fn write_object(&mut self, object: Object) {
+        let encoding = self.value_encoding;
+        match object {
+            Object::String(kv) => {
+                let expire = kv.meta.expire_millis();
+                self.write_row(kv.meta, kv.key, "string", "", &encoding.encode(kv.value), expire);
+            }
+            Object::StringChunk(chunk) => {
+                let expire = chunk.meta.expire_millis();
+                let offset = chunk.offset.to_string();
+                let value = encoding.encode(chunk.chunk);
+                self.write_row(chunk.meta, chunk.key, "string_chunk", &offset, &value, expire);
+            }
+            Object::List(list) => {
+                if list.is_first {
+                    self.list_index = 0;
+                }
+                let expire = list.meta.expire_millis();
+                for value in list.values {
+                    let index = self.list_index.to_string();
+                    self.list_index += 1;
+                    self.write_row(list.meta, list.key, "list", &index, &encoding.encode(value), expire);
+                }
+            }
+            Object::Set(set) => {
+                let expire = set.meta.expire_millis();
+                for member in set.members {
+                    self.write_row(set.meta, set.key, "set", "", &encoding.encode(member), expire);
+                }
+            }
+            Object::SortedSet(sorted_set) => {
+                let expire = sorted_set.meta.expire_millis();
+                for item in sorted_set.items {
+                    let member = encoding.encode(&item.member);
+                    let score = item.score.to_string();
+                    self.write_row(sorted_set.meta, sorted_set.key, "sorted_set", &member, &score, expire);
+                }
+            }
+            Object::Hash(hash) => {
+                let expire = hash.meta.expire_millis();
+                for field in hash.fields {
+                    let name = encoding.encode(&field.name);
+                    let value = encoding.encode(&field.value);
+                    let expire = field.expire.or(expire);
+                    self.write_row(hash.meta, hash.key, "hash", &name, &value, expire);
+                }
+            }
+            Object::Stream(key, stream) => {
+                let expire = stream.meta.expire_millis();
+                for entry in stream.entries.values().filter(|entry| !entry.deleted) {
+                    for (name, value) in &entry.fields {
+                        let field = format!("{}:{}", entry.id.to_string(), encoding.encode(name));
+                        self.write_row(stream.meta, &key, "stream", &field, &encoding.encode(value), expire);
+                    }
+                }
+            }
+            Object::Module(key, module, meta) => {
+                let name = match module.as_any().downcast_ref::<RawModule>() {
+                    Some(raw) => raw.name.clone(),
+                    None => String::new(),
+                };
+                self.write_row(meta, &key, "module", &name, "", meta.expire_millis());
+            }
+            Object::EOR => {
+                if self.error.is_none() {
+                    if let Err(err) = self.output.flush() {
+                        self.error = Some(err);
+                    }
+                }
+            }
+            Object::BOR
+            | Object::ResizeDB(_)
+            | Object::Function(_)
+            | Object::ModuleAux(_, _)
+            | Object::SkippedKey(_)
+            | Object::Stats(_) => {}
+        }
+    }
+}
+
+// 包含分隔符、双引号或者换行时加上双引号
+fn quote(column: &str, delimiter: u8, buf: &mut Vec<u8>) {
+    let is_quote_needed = column
+        .bytes()
+        .any(|byte| byte == delimiter || byte == b'"' || byte == b'\n' || byte == b'\r');
+    if !is_quote_needed {
+        buf.extend_from_slice(column.as_bytes());
+        return;
+    }
+    buf.push(b'"');
+    for byte in column.bytes() {
+        if byte == b'"' {
+            buf.push(b'"');
+        }
+        buf.push(byte);
+    }
+    buf.push(b'"');
+}
+
+impl<W: Write> EventHandler for CsvWriter<W> {
+    fn handle(&mut self, event: Event) {
+        if let Event::RDB(object) = event {
+            self.write_object(object);
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(err) = &self.error {
+            return Err(Error::new(err.kind(), err.to_string()));
+        }
+        self.output.flush()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.error.take().map(HandlerError::Fatal)
+    }
+}
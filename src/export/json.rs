@@ -0,0 +1,410 @@
+/*!
+将每个事件输出为一行JSON(JSON Lines)
+
+```no_run
+use redis_event::config::Config;
+use redis_event::export::json::JsonLinesWriter;
+use redis_event::export::{Encoding, RotatingFile};
+use redis_event::listener::Builder;
+use redis_event::RedisListener;
+
+fn run(config: Config) -> std::io::Result<()> {
+    let mut file = RotatingFile::new("/data/events.jsonl")?;
+    // 每个文件最多1GB
+    file.with_max_size(1 << 30);
+    let mut writer = JsonLinesWriter::new(file);
+    writer.with_value_encoding(Encoding::Base64);
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(writer));
+    builder.build().start()
+}
+```
+
+每一行都有表示事件类型的`type`字段:
+
+```text
+{"type":"begin_rdb"}
+{"type":"string","db":0,"key":"a","value":"1"}
+{"type":"hash","db":0,"key":"h","expire":1700000000000,"fields":[{"name":"f","value":"v"}]}
+{"type":"end_rdb"}
+{"type":"command","offset":1024,"timestamp":1700000000,"command":"SET","args":["c","3"]}
+```
+
+分批产生的集合带有`is_first`以及`is_last`字段，整个key在一个事件中时省略；`expire`为毫秒级的unix时间戳。
+key按照key的编码输出，值、集合的元素以及命令的参数按照值的编码输出，默认都为`Encoding::Utf8`。
+*/
+use std::io::{Error, Result, Write};
+use std::time::UNIX_EPOCH;
+
+use crate::export::Encoding;
+use crate::rdb::stats::{KeySize, RDBStats, ValueType};
+use crate::rdb::{Meta, Object, RawModule};
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// 将每个事件输出为一行JSON的处理器，见[模块文档](index.html)
+///
+/// RDB结束时以及每条命令之后都会调用`output`的`flush`；写入出错之后不再输出，错误通过`EventHandler::flush`返回
+pub struct JsonLinesWriter<W: Write> {
+    output: W,
+    key_encoding: Encoding,
+    value_encoding: Encoding,
+    error: Option<Error>,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(output: W) -> JsonLinesWriter<W> {
+        JsonLinesWriter {
+            output,
+            key_encoding: Encoding::Utf8,
+            value_encoding: Encoding::Utf8,
+            error: None,
+        }
+    }
+
+    /// key的编码
+    pub fn with_key_encoding(&mut self, encoding: Encoding) {
+        self.key_encoding = encoding;
+    }
+
+    /// 值、集合的元素以及命令的参数的编码
+    pub fn with_value_encoding(&mut self, encoding: Encoding) {
+        self.value_encoding = encoding;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn write(&mut self, line: String, is_flush: bool) {
+        if self.error.is_some() {
+            return;
+        }
+        let mut result = writeln!(self.output, "{}", line);
+        if is_flush {
+            result = result.and_then(|_| self.output.flush());
+        }
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+
+    fn key_json(&self, kind: &str, key: &[u8], meta: &Meta) -> Json {
+        let mut json = Json::new(kind);
+        json.number("db", meta.db);
+        json.string("key", &self.key_encoding.encode(key));
+        if let Some(expire) = meta.expire_millis() {
+            json.number("expire", expire);
+        }
+        json
+    }
+
+    fn value(&self, value: &[u8]) -> String {
+        self.value_encoding.encode(value)
+    }
+
+    fn values<T: AsRef<[u8]>, I: IntoIterator<Item = T>>(&self, values: I) -> Vec<String> {
+        values.into_iter().map(|value| self.value(value.as_ref())).collect()
+    }
+
+    fn object_json(&self, object: Object) -> Json {
+        match object {
+            Object::String(kv) => {
+                let mut json = self.key_json("string", kv.key, kv.meta);
+                json.string("value", &self.value(kv.value));
+                json
+            }
+            Object::StringChunk(chunk) => {
+                let mut json = self.key_json("string_chunk", chunk.key, chunk.meta);
+                json.number("offset", chunk.offset);
+                json.number("total_len", chunk.total_len);
+                json.string("chunk", &self.value(chunk.chunk));
+                json
+            }
+            Object::List(list) => {
+                let mut json = self.key_json("list", list.key, list.meta);
+                json.strings("values", self.values(list.values));
+                batch(&mut json, list.is_first, list.is_last);
+                json
+            }
+            Object::Set(set) => {
+                let mut json = self.key_json("set", set.key, set.meta);
+                json.strings("members", self.values(set.members));
+                batch(&mut json, set.is_first, set.is_last);
+                json
+            }
+            Object::SortedSet(sorted_set) => {
+                let mut json = self.key_json("sorted_set", sorted_set.key, sorted_set.meta);
+                let items = sorted_set.items.iter().map(|item| {
+                    let mut json = Json::object();
+                    json.string("member", &self.value(&item.member));
+                    json.float("score", item.score);
+                    json.finish()
+                });
+                json.raw_array("items", items);
+                batch(&mut json, sorted_set.is_first, sorted_set.is_last);
+                json
+            }
+            Object::Hash(hash) => {
+                let mut json = self.key_json("hash", hash.key, hash.meta);
+                let fields = hash.fields.iter().map(|field| {
+                    let mut json = Json::object();
+                    json.string("name", &self.value(&field.name));
+                    json.string("value", &self.value(&field.value));
+                    if let Some(expire) = field.expire {
+                        json.number("expire", expire);
+                    }
+                    json.finish()
+                });
+                json.raw_array("fields", fields);
+                batch(&mut json, hash.is_first, hash.is_last);
+                json
+            }
+            Object::Stream(key, stream) => {
+                let mut json = self.key_json("stream", &key, stream.meta);
+                let entries = stream.entries.values().filter(|entry| !entry.deleted).map(|entry| {
+                    let mut json = Json::object();
+                    json.string("id", &entry.id.to_string());
+                    let fields = entry.fields.iter().flat_map(|(name, value)| vec![name, value]);
+                    json.strings("fields", self.values(fields));
+                    json.finish()
+                });
+                json.raw_array("entries", entries);
+                json.string("last_id", &stream.last_id.to_string());
+                let groups = stream.groups.iter().map(|group| group.name.as_slice());
+                json.strings("groups", self.values(groups));
+                json
+            }
+            Object::Module(key, module, meta) => {
+                let mut json = self.key_json("module", &key, meta);
+                if let Some(raw) = module.as_any().downcast_ref::<RawModule>() {
+                    json.string("module", &raw.name);
+                }
+                json
+            }
+            Object::Function(code) => {
+                let mut json = Json::new("function");
+                json.string("code", &self.value(&code));
+                json
+            }
+            Object::ModuleAux(name, _) => {
+                let mut json = Json::new("module_aux");
+                json.string("module", &name);
+                json
+            }
+            Object::SkippedKey(skipped) => {
+                let mut json = self.key_json("skipped_key", &skipped.key, skipped.meta);
+                json.number("value_type", skipped.value_type);
+                json.string("error", &skipped.error.to_string());
+                json
+            }
+            Object::ResizeDB(resize_db) => {
+                let mut json = Json::new("resize_db");
+                json.number("db", resize_db.db);
+                json.number("size", resize_db.size);
+                json.number("expires_size", resize_db.expires_size);
+                json
+            }
+            Object::Stats(stats) => self.stats_json(stats),
+            Object::BOR => Json::new("begin_rdb"),
+            Object::EOR => Json::new("end_rdb"),
+        }
+    }
+
+    fn stats_json(&self, stats: &RDBStats) -> Json {
+        let mut json = Json::new("stats");
+        json.number("keys", stats.key_count());
+        let mut types = Json::object();
+        for (value_type, type_stats) in &stats.types {
+            let name = match value_type {
+                ValueType::String => "string",
+                ValueType::List => "list",
+                ValueType::Set => "set",
+                ValueType::SortedSet => "sorted_set",
+                ValueType::Hash => "hash",
+                ValueType::Stream => "stream",
+                ValueType::Module => "module",
+            };
+            let mut type_json = Json::object();
+            type_json.number("keys", type_stats.keys);
+            type_json.number("elements", type_stats.elements);
+            type_json.number("bytes", type_stats.bytes);
+            if let Some(largest) = &type_stats.largest {
+                type_json.raw("largest", self.key_size_json(largest));
+            }
+            types.raw(name, type_json.finish());
+        }
+        json.raw("types", types.finish());
+        let dbs = stats.dbs.iter().map(|db| {
+            let mut json = Json::object();
+            json.number("db", db.db);
+            json.number("keys", db.keys);
+            json.number("expires", db.expires);
+            json.number("bytes", db.bytes);
+            json.finish()
+        });
+        json.raw_array("dbs", dbs);
+        let mut expires = Json::object();
+        expires.number("persistent", stats.expires.persistent);
+        expires.number("expired", stats.expires.expired);
+        expires.number("within_hour", stats.expires.within_hour);
+        expires.number("within_day", stats.expires.within_day);
+        expires.number("within_week", stats.expires.within_week);
+        expires.number("later", stats.expires.later);
+        json.raw("expires", expires.finish());
+        json
+    }
+
+    fn key_size_json(&self, size: &KeySize) -> String {
+        let mut json = Json::object();
+        json.number("db", size.db);
+        json.string("key", &self.key_encoding.encode(&size.key));
+        json.number("elements", size.elements);
+        json.number("bytes", size.bytes);
+        json.finish()
+    }
+
+    fn handle_command(&mut self, event: Event, position: Option<AofPosition>) {
+        if let Event::AOF(command) = event {
+            let mut args = command.to_args().into_iter();
+            let name = String::from_utf8_lossy(&args.next().unwrap_or_default()).to_uppercase();
+            let mut json = Json::new("command");
+            if let Some(position) = position {
+                if position.offset >= 0 {
+                    json.number("offset", position.offset);
+                }
+                if let Some(timestamp) = position.timestamp.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+                    json.number("timestamp", timestamp.as_secs());
+                }
+            }
+            json.string("command", &name);
+            json.strings("args", self.values(args));
+            self.write(json.finish(), true);
+        }
+    }
+}
+
+fn batch(json: &mut Json, is_first: bool, is_last: bool) {
+    // 整个key在一个事件中时省略
+    if !(is_first && is_last) {
+        json.raw("is_first", is_first.to_string());
+        json.raw("is_last", is_last.to_string());
+    }
+}
+
+impl<W: Write> EventHandler for JsonLinesWriter<W> {
+    fn handle(&mut self, event: Event) {
+        let object = match event {
+            Event::RDB(object) => object,
+            Event::AOF(_) => return self.handle_command(event, None),
+        };
+        // RDB结束之后立即输出，之后的命令每条都会立即输出
+        let is_flush = matches!(object, Object::EOR);
+        let json = self.object_json(object);
+        self.write(json.finish(), is_flush);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.handle_command(event, Some(position));
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(err) = &self.error {
+            return Err(Error::new(err.kind(), err.to_string()));
+        }
+        self.output.flush()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.error.take().map(HandlerError::Fatal)
+    }
+}
+
+/// 逐个字段拼接的JSON对象
+struct Json {
+    buf: String,
+}
+
+impl Json {
+    fn object() -> Json {
+        Json { buf: String::from("{") }
+    }
+
+    fn new(kind: &str) -> Json {
+        let mut json = Json::object();
+        json.string("type", kind);
+        json
+    }
+
+    fn raw(&mut self, name: &str, value: String) {
+        if self.buf.len() > 1 {
+            self.buf.push(',');
+        }
+        escape(name, &mut self.buf);
+        self.buf.push(':');
+        self.buf.push_str(&value);
+    }
+
+    fn raw_array<I: Iterator<Item = String>>(&mut self, name: &str, values: I) {
+        let values: Vec<String> = values.collect();
+        self.raw(name, format!("[{}]", values.join(",")));
+    }
+
+    fn string(&mut self, name: &str, value: &str) {
+        let mut buf = String::new();
+        escape(value, &mut buf);
+        self.raw(name, buf);
+    }
+
+    fn strings(&mut self, name: &str, values: Vec<String>) {
+        let values = values.iter().map(|value| {
+            let mut buf = String::new();
+            escape(value, &mut buf);
+            buf
+        });
+        self.raw_array(name, values);
+    }
+
+    fn number<T: ToString>(&mut self, name: &str, value: T) {
+        self.raw(name, value.to_string());
+    }
+
+    // JSON中没有inf以及nan，以字符串输出
+    fn float(&mut self, name: &str, value: f64) {
+        if value.is_finite() {
+            self.number(name, value);
+        } else {
+            self.string(name, &value.to_string());
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.buf.push('}');
+        self.buf
+    }
+}
+
+// 以JSON字符串的格式输出
+fn escape(value: &str, buf: &mut String) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
@@ -0,0 +1,105 @@
+/*!
+将[`FallibleEventHandler`]适配为`EventHandler`，处理事件失败时由监听器停止监听或者重新同步
+
+```no_run
+use std::io::{Error, ErrorKind};
+
+use redis_event::config::Config;
+use redis_event::fallible::Fallible;
+use redis_event::listener::Builder;
+use redis_event::{Event, FallibleEventHandler, HandlerError, RedisListener};
+
+struct Downstream {}
+
+impl Downstream {
+    fn write(&mut self, _event: &Event) -> std::io::Result<()> {
+        Err(Error::new(ErrorKind::ConnectionRefused, "downstream unavailable"))
+    }
+}
+
+impl FallibleEventHandler for Downstream {
+    fn handle(&mut self, event: Event) -> Result<(), HandlerError> {
+        // 下游暂时不可用时重新同步，其余的错误停止监听
+        self.write(&event).map_err(|error| match error.kind() {
+            ErrorKind::ConnectionRefused => HandlerError::Retry(error),
+            _ => HandlerError::Fatal(error),
+        })
+    }
+}
+
+fn run(mut config: Config) -> std::io::Result<()> {
+    config.max_handler_retries = Some(3);
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(Fallible::new(Downstream {})));
+    builder.build().start()
+}
+```
+
+[`FallibleEventHandler`]: ../trait.FallibleEventHandler.html
+*/
+use std::io::Result;
+
+use crate::{AofPosition, Event, EventHandler, FallibleEventHandler, HandlerError};
+
+/// 将事件交给内部的`FallibleEventHandler`，并保存其返回的第一个错误，直到监听器通过`take_error`将其取出；
+/// 保存有错误期间收到的事件将被丢弃
+pub struct Fallible<H: FallibleEventHandler> {
+    inner: H,
+    error: Option<HandlerError>,
+}
+
+impl<H: FallibleEventHandler> Fallible<H> {
+    pub fn new(inner: H) -> Fallible<H> {
+        Fallible { inner, error: None }
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    fn record(&mut self, result: std::result::Result<(), HandlerError>) {
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl<H: FallibleEventHandler> EventHandler for Fallible<H> {
+    fn handle(&mut self, event: Event) {
+        if self.error.is_none() {
+            let result = self.inner.handle(event);
+            self.record(result);
+        }
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        if self.error.is_none() {
+            let result = self.inner.handle_aof(event, position);
+            self.record(result);
+        }
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        if self.error.is_none() {
+            let result = self.inner.handle_aof_raw(event, args, position);
+            self.record(result);
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.error.take()
+    }
+}
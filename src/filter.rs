@@ -0,0 +1,211 @@
+/*!
+在事件交给`EventHandler`之前按key、db、命令名以及数据类型将其丢弃
+
+```no_run
+use redis_event::config::Config;
+use redis_event::filter::{Filter, ObjectType};
+use redis_event::listener::Builder;
+use redis_event::{NoOpEventHandler, RedisListener};
+
+fn run(config: Config) -> std::io::Result<()> {
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(NoOpEventHandler {}));
+    // 只处理db 0中以user:开头的key，RDB中只保留Hash
+    builder.with_filter(Filter::new().keys("user:*").dbs(&[0]).types(&[ObjectType::Hash]));
+    builder.build().start()
+}
+```
+
+[`Filter`]中的各个条件需同时满足，同一个条件设置多次时满足其中任意一个即可。事件仍需完整解析之后才能判断，
+只需要RDB中部分数据时，`Config::db_filter`与`Config::key_filter`可以在解析之前跳过不需要的值，速度更快。
+
+[`Filter`]: struct.Filter.html
+*/
+use std::io::Result;
+
+use crate::cmd::Command;
+use crate::config::KeyFilter;
+use crate::rdb::Object;
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// RDB中数据的类型，见`Filter::types`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    /// 包括`Object::StringChunk`
+    String,
+    List,
+    Set,
+    SortedSet,
+    Hash,
+    Stream,
+    Module,
+}
+
+/// 事件的过滤条件，未设置任何条件时保留所有的事件
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    keys: Vec<KeyFilter>,
+    dbs: Option<Vec<isize>>,
+    commands: Option<Vec<String>>,
+    types: Option<Vec<ObjectType>>,
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    /// 只保留key与glob模式(与Redis中的KEYS命令相同)相匹配的事件
+    ///
+    /// 涉及多个key的命令只要其中一个key匹配即保留；不涉及key的命令(如`SELECT`、`FLUSHALL`、`MULTI`)以及
+    /// 无法得知key的命令(`Command::Other`、`Command::Module`)不受此条件影响，同样不受影响的还有RDB中的`BOR`、`EOR`等事件
+    pub fn keys(mut self, pattern: &str) -> Filter {
+        self.keys.push(KeyFilter::Glob(pattern.as_bytes().to_vec()));
+        self
+    }
+
+    /// 只保留这些db中的事件，命令所属的db由此前的`SELECT`决定，`SELECT`本身属于其所选择的db
+    pub fn dbs(mut self, dbs: &[isize]) -> Filter {
+        self.dbs.get_or_insert_with(Vec::new).extend_from_slice(dbs);
+        self
+    }
+
+    /// 只保留这些命令(不区分大小写)，对RDB中的数据没有影响
+    ///
+    /// `SELECT`同样受此条件影响，下游需要知道命令所属的db时应将其包括在内
+    pub fn commands(mut self, names: &[&str]) -> Filter {
+        let commands = self.commands.get_or_insert_with(Vec::new);
+        commands.extend(names.iter().map(|name| name.to_uppercase()));
+        self
+    }
+
+    /// 只保留RDB中这些类型的数据，对命令以及RDB中不对应任何key的事件没有影响
+    pub fn types(mut self, types: &[ObjectType]) -> Filter {
+        self.types.get_or_insert_with(Vec::new).extend_from_slice(types);
+        self
+    }
+
+    fn is_key_match(&self, key: &[u8]) -> bool {
+        self.keys.is_empty() || self.keys.iter().any(|filter| filter.matches(key))
+    }
+
+    fn is_db_match(&self, db: isize) -> bool {
+        match &self.dbs {
+            Some(dbs) => dbs.contains(&db),
+            None => true,
+        }
+    }
+
+    fn is_object_match(&self, object: &Object) -> bool {
+        let (db, key, object_type) = match object {
+            Object::String(kv) => (kv.meta.db, kv.key, Some(ObjectType::String)),
+            Object::StringChunk(chunk) => (chunk.meta.db, chunk.key, Some(ObjectType::String)),
+            Object::List(list) => (list.meta.db, list.key, Some(ObjectType::List)),
+            Object::Set(set) => (set.meta.db, set.key, Some(ObjectType::Set)),
+            Object::SortedSet(sorted_set) => (sorted_set.meta.db, sorted_set.key, Some(ObjectType::SortedSet)),
+            Object::Hash(hash) => (hash.meta.db, hash.key, Some(ObjectType::Hash)),
+            Object::Stream(key, stream) => (stream.meta.db, key.as_slice(), Some(ObjectType::Stream)),
+            Object::Module(key, _, meta) => (meta.db, key.as_slice(), Some(ObjectType::Module)),
+            Object::SkippedKey(skipped) => (skipped.meta.db, skipped.key.as_slice(), None),
+            Object::ResizeDB(resize_db) => return self.is_db_match(resize_db.db),
+            Object::BOR | Object::EOR | Object::Stats(_) | Object::Function(_) | Object::ModuleAux(_, _) => {
+                return true
+            }
+        };
+        let is_type_match = match (&self.types, object_type) {
+            (Some(types), Some(object_type)) => types.contains(&object_type),
+            _ => true,
+        };
+        is_type_match && self.is_db_match(db) && self.is_key_match(key)
+    }
+
+    fn is_command_match(&self, cmd: &Command, args: Option<&[Vec<u8>]>, db: isize) -> bool {
+        if !self.is_db_match(db) {
+            return false;
+        }
+        if let Some(commands) = &self.commands {
+            let name = match args.and_then(|args| args.first()) {
+                Some(name) => String::from_utf8_lossy(name).to_uppercase(),
+                None => cmd.to_owned().name(),
+            };
+            if !commands.contains(&name) {
+                return false;
+            }
+        }
+        let keys = cmd.keys();
+        keys.is_empty() || keys.iter().any(|key| self.is_key_match(key))
+    }
+}
+
+/// 按照`Filter`丢弃事件，其余的事件交给内部的`EventHandler`
+///
+/// 通过`Builder::with_filter`设置时由监听器自动创建，解析本地的RDB、AOF文件时可以直接使用
+pub struct Filtered<H: EventHandler> {
+    inner: H,
+    filter: Filter,
+    // 当前命令所属的db
+    db: isize,
+}
+
+impl<H: EventHandler> Filtered<H> {
+    pub fn new(inner: H, filter: Filter) -> Filtered<H> {
+        Filtered { inner, filter, db: 0 }
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    fn is_match(&mut self, event: &Event, args: Option<&[Vec<u8>]>) -> bool {
+        match event {
+            Event::RDB(object) => self.filter.is_object_match(object),
+            Event::AOF(cmd) => {
+                if let Command::SELECT(select) = cmd {
+                    self.db = select.db as isize;
+                }
+                self.filter.is_command_match(cmd, args, self.db)
+            }
+        }
+    }
+}
+
+impl<H: EventHandler> EventHandler for Filtered<H> {
+    fn handle(&mut self, event: Event) {
+        if self.is_match(&event, None) {
+            self.inner.handle(event);
+        }
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        if self.is_match(&event, None) {
+            self.inner.handle_aof(event, position);
+        }
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        if self.is_match(&event, Some(args)) {
+            self.inner.handle_aof_raw(event, args, position);
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        self.inner.pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
@@ -0,0 +1,157 @@
+/*!
+同时监听多个Redis实例，并将它们的事件以来源标识区分后汇总到同一个处理器中
+
+每个Redis实例都在单独的线程中进行监听，出错退出后将按照设置的间隔重新启动
+*/
+use std::io::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::sleep;
+use std::time::Duration;
+
+#[cfg(not(feature = "tracing"))]
+use log::{error, info, warn};
+use scheduled_thread_pool::ScheduledThreadPool;
+#[cfg(feature = "tracing")]
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::listener::Builder;
+use crate::{AofPosition, Event, EventHandler, RedisListener, TaggedEventHandler};
+
+/// 用于同时监听多个Redis实例的事件
+pub struct ListenerGroup {
+    members: Vec<(String, Config)>,
+    handler: Arc<Mutex<dyn TaggedEventHandler>>,
+    running: Arc<AtomicBool>,
+    restart_interval: Duration,
+}
+
+impl ListenerGroup {
+    /// 方法参数:
+    ///
+    /// * `handler`: 所有Redis实例共用的事件处理器
+    /// * `running`: 控制变量，设置为false后所有的监听器都将有序退出
+    pub fn new(handler: Arc<Mutex<dyn TaggedEventHandler>>, running: Arc<AtomicBool>) -> ListenerGroup {
+        ListenerGroup {
+            members: Vec::new(),
+            handler,
+            running,
+            restart_interval: Duration::from_secs(5),
+        }
+    }
+
+    /// 添加一个需要监听的Redis实例，`source`将作为此实例所产生事件的来源标识
+    pub fn add(&mut self, source: &str, config: Config) {
+        self.members.push((source.to_string(), config));
+    }
+
+    /// 监听器出错退出后，等待此时长再重新启动，默认为5秒
+    pub fn with_restart_interval(&mut self, interval: Duration) {
+        self.restart_interval = interval;
+    }
+}
+
+impl RedisListener for ListenerGroup {
+    /// 为每个Redis实例启动一个线程进行监听，所有线程都退出后此方法才返回
+    fn start(&mut self) -> Result<()> {
+        let thread_pool = ScheduledThreadPool::builder()
+            .num_threads(self.members.len().max(1))
+            .thread_name_pattern("heartbeat-thread-{}")
+            .build();
+        let thread_pool = Arc::new(thread_pool);
+        let mut threads = Vec::with_capacity(self.members.len());
+        for (source, config) in &self.members {
+            let supervisor = Supervisor {
+                source: source.clone(),
+                config: config.clone(),
+                handler: Arc::clone(&self.handler),
+                running: Arc::clone(&self.running),
+                thread_pool: Arc::clone(&thread_pool),
+                restart_interval: self.restart_interval,
+            };
+            let thread = thread::Builder::new()
+                .name(format!("listener-{}", source))
+                .spawn(move || supervisor.run())?;
+            threads.push(thread);
+        }
+        for thread in threads {
+            if thread.join().is_err() {
+                error!("listener thread panicked");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 负责在单个线程中运行某个Redis实例的监听器，并在其出错退出后重新启动
+struct Supervisor {
+    source: String,
+    config: Config,
+    handler: Arc<Mutex<dyn TaggedEventHandler>>,
+    running: Arc<AtomicBool>,
+    thread_pool: Arc<ScheduledThreadPool>,
+    restart_interval: Duration,
+}
+
+impl Supervisor {
+    fn run(self) {
+        let handler = TaggedHandler {
+            source: self.source.clone(),
+            inner: self.handler,
+        };
+        let mut builder = Builder::new();
+        builder.with_config(self.config);
+        builder.with_control_flag(Arc::clone(&self.running));
+        builder.with_event_handler(Box::new(handler));
+        builder.with_thread_pool(self.thread_pool);
+        // 复用同一个监听器，重启时将从上次处理到的offset继续同步；
+        // 若失败时RDB尚未处理完毕，start会丢弃FULLRESYNC返回的offset，重启后重新进行全量同步
+        let mut listener = builder.build();
+
+        while self.running.load(Ordering::Relaxed) {
+            match listener.start() {
+                Ok(_) => {
+                    info!("[{}] listener finished", self.source);
+                    break;
+                }
+                Err(err) => {
+                    error!("[{}] listener failed: {}", self.source, err);
+                    if self.running.load(Ordering::Relaxed) {
+                        warn!("[{}] restart in {:?}", self.source, self.restart_interval);
+                        sleep(self.restart_interval);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 将单个监听器的事件附上来源标识，转发给共用的处理器
+struct TaggedHandler {
+    source: String,
+    inner: Arc<Mutex<dyn TaggedEventHandler>>,
+}
+
+impl EventHandler for TaggedHandler {
+    fn handle(&mut self, event: Event) {
+        let mut handler = self.inner.lock().unwrap();
+        handler.handle(&self.source, event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        let mut handler = self.inner.lock().unwrap();
+        handler.handle_aof(&self.source, event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        let mut handler = self.inner.lock().unwrap();
+        handler.handle_aof_raw(&self.source, event, args, position);
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let mut handler = self.inner.lock().unwrap();
+        handler.flush(&self.source)
+    }
+}
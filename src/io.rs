@@ -3,7 +3,9 @@
 */
 
 use crate::resp::*;
+use std::fs;
 use std::io::{BufReader, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
 
 pub(crate) struct CountReader<'a> {
     input: BufReader<&'a mut dyn Read>,
@@ -53,6 +55,28 @@ impl CountReader<'_> {
     }
 }
 
+// 将读取到的数据同时写入`output`，output为None时只读取
+pub(crate) struct TeeReader<'a, W: Write> {
+    input: &'a mut dyn Read,
+    output: Option<&'a mut W>,
+}
+
+impl<'a, W: Write> TeeReader<'a, W> {
+    pub(crate) fn new(input: &'a mut dyn Read, output: Option<&'a mut W>) -> TeeReader<'a, W> {
+        TeeReader { input, output }
+    }
+}
+
+impl<W: Write> Read for TeeReader<'_, W> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.input.read(buf)?;
+        if let Some(output) = self.output.as_mut() {
+            output.write_all(&buf[..len])?;
+        }
+        Ok(len)
+    }
+}
+
 pub(crate) fn send<T: Write>(output: &mut T, command: &[u8], args: &[&[u8]]) -> Result<()> {
     let mut buf = vec![];
     buf.write(&[STAR])?;
@@ -79,3 +103,24 @@ pub(crate) fn skip(input: &mut dyn Read, length: isize) -> Result<()> {
     std::io::copy(&mut input.take(length as u64), &mut std::io::sink())?;
     Ok(())
 }
+
+// 已被切换的文件(即`<path>.<n>`)中最大的序号，没有时为0；即使较早的文件已被删除，新的文件也不会复用较小的序号
+pub(crate) fn last_rotated_index(path: &Path) -> Result<u64> {
+    let name = match path.file_name() {
+        Some(name) => format!("{}.", name.to_string_lossy()),
+        None => return Ok(0),
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut last_index = 0;
+    for entry in fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        if let Some(Ok(index)) = file_name.strip_prefix(&name).map(str::parse::<u64>) {
+            last_index = last_index.max(index);
+        }
+    }
+    Ok(last_index)
+}
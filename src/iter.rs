@@ -3,7 +3,10 @@ use std::io::{Cursor, Error, ErrorKind, Read};
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
-use crate::rdb::{read_zip_list_entry, read_zm_len, Field, Item, RDBDecode};
+use crate::config::ParseMode;
+use crate::encodings::read_zip_list_entry;
+use crate::listpack::read_entry as read_list_pack_entry;
+use crate::rdb::{read_score, Field, Item, RDBDecode, LP_EOF, QUICKLIST_NODE_CONTAINER_PLAIN};
 
 /// 迭代器接口的定义（迭代器方便处理大key，减轻内存使用）
 ///
@@ -75,23 +78,62 @@ impl QuickListIter<'_> {
     }
 }
 
-// ZipList的值迭代器
-pub(crate) struct ZipListIter<'a> {
+// ListQuickList2的值迭代器，Redis 7.0开始quicklist的节点使用listpack编码
+pub(crate) struct QuickList2Iter<'a> {
     pub(crate) count: isize,
-    pub(crate) cursor: &'a mut Cursor<Vec<u8>>,
+    pub(crate) input: &'a mut dyn Read,
+    pub(crate) cursor: Option<Cursor<Vec<u8>>>,
 }
 
-impl Iter for ZipListIter<'_> {
+impl Iter for QuickList2Iter<'_> {
     fn next(&mut self) -> io::Result<Vec<u8>> {
-        if self.count > 0 {
-            let val = read_zip_list_entry(self.cursor)?;
+        loop {
+            if let Some(cursor) = self.cursor.as_mut() {
+                if has_list_pack_entry(cursor) {
+                    return read_list_pack_entry(cursor);
+                }
+                self.cursor = Option::None;
+            }
+            if self.count <= 0 {
+                return Err(Error::new(ErrorKind::NotFound, "No element left"));
+            }
             self.count -= 1;
-            return Ok(val);
+            let (container, _) = self.input.read_length()?;
+            let data = self.input.read_string()?;
+            if container == QUICKLIST_NODE_CONTAINER_PLAIN {
+                return Ok(data);
+            }
+            let mut cursor = Cursor::new(data);
+            // 跳过listpack的total bytes和num elements
+            cursor.set_position(6);
+            self.cursor = Option::Some(cursor);
+        }
+    }
+}
+
+// ListPack的值迭代器
+pub(crate) struct ListPackIter<'a> {
+    pub(crate) cursor: &'a mut Cursor<Vec<u8>>,
+}
+
+impl Iter for ListPackIter<'_> {
+    fn next(&mut self) -> io::Result<Vec<u8>> {
+        if has_list_pack_entry(self.cursor) {
+            return read_list_pack_entry(self.cursor);
         }
         Err(Error::new(ErrorKind::NotFound, "No element left"))
     }
 }
 
+// 是否还未读到listpack的结束标志
+fn has_list_pack_entry(cursor: &Cursor<Vec<u8>>) -> bool {
+    let pos = cursor.position() as usize;
+    match cursor.get_ref().get(pos) {
+        Some(&byte) => byte != LP_EOF,
+        None => false,
+    }
+}
+
 // SortedSet的值迭代器
 pub(crate) struct SortedSetIter<'a> {
     pub(crate) count: isize,
@@ -99,6 +141,7 @@ pub(crate) struct SortedSetIter<'a> {
     /// v = 2, zset2
     pub(crate) v: u8,
     pub(crate) input: &'a mut dyn Read,
+    pub(crate) parse_mode: ParseMode,
 }
 
 impl SortedSetIter<'_> {
@@ -107,7 +150,7 @@ impl SortedSetIter<'_> {
             let member = self.input.read_string()?;
             let score;
             if self.v == 1 {
-                score = self.input.read_double()?;
+                score = read_score(self.input, self.parse_mode)?;
             } else {
                 let score_u64 = self.input.read_u64::<LittleEndian>()?;
                 score = f64::from_bits(score_u64);
@@ -119,75 +162,28 @@ impl SortedSetIter<'_> {
     }
 }
 
-// HashZipMap的值迭代器
-pub(crate) struct ZipMapIter<'a> {
-    pub(crate) has_more: bool,
-    pub(crate) cursor: &'a mut Cursor<&'a Vec<u8>>,
-}
-
-impl ZipMapIter<'_> {
-    pub(crate) fn next(&mut self) -> io::Result<Field> {
-        if !self.has_more {
-            return Err(Error::new(ErrorKind::NotFound, "No element left"));
-        }
-        let zm_len = read_zm_len(self.cursor)?;
-        if zm_len == 255 {
-            self.has_more = false;
-            return Err(Error::new(ErrorKind::NotFound, "No element left"));
-        }
-        let mut field = vec![0; zm_len];
-        self.cursor.read_exact(&mut field)?;
-        let zm_len = read_zm_len(self.cursor)?;
-        if zm_len == 255 {
-            self.has_more = false;
-            return Ok(Field {
-                name: field,
-                value: Vec::new(),
-            });
-        };
-        let free = self.cursor.read_i8()?;
-        let mut val = vec![0; zm_len];
-        self.cursor.read_exact(&mut val)?;
-        self.cursor.set_position(self.cursor.position() + free as u64);
-        return Ok(Field {
-            name: field,
-            value: val,
-        });
-    }
-}
-
-// IntSet的值迭代器
-pub(crate) struct IntSetIter<'a> {
-    pub(crate) encoding: i32,
+// HashMetadata的值迭代器，每个字段前带有其过期时间
+pub(crate) struct HashMetadataIter<'a> {
     pub(crate) count: isize,
-    pub(crate) cursor: &'a mut Cursor<&'a Vec<u8>>,
+    pub(crate) min_expire: i64,
+    pub(crate) input: &'a mut dyn Read,
 }
 
-impl Iter for IntSetIter<'_> {
-    fn next(&mut self) -> io::Result<Vec<u8>> {
+impl HashMetadataIter<'_> {
+    pub(crate) fn next(&mut self) -> io::Result<Field> {
         if self.count > 0 {
-            let val;
-            match self.encoding {
-                2 => {
-                    let member = self.cursor.read_i16::<LittleEndian>()?;
-                    let member = member.to_string().into_bytes();
-                    val = member;
-                }
-                4 => {
-                    let member = self.cursor.read_i32::<LittleEndian>()?;
-                    let member = member.to_string().into_bytes();
-                    val = member;
-                }
-                8 => {
-                    let member = self.cursor.read_i64::<LittleEndian>()?;
-                    let member = member.to_string().into_bytes();
-                    val = member;
-                }
-                _ => panic!("Invalid integer size: {}", self.encoding),
-            }
+            // 0代表此字段没有设置过期时间，否则为相对于min_expire的偏移量再加1
+            let (ttl, _) = self.input.read_length()?;
+            let expire = if ttl == 0 {
+                None
+            } else {
+                Some(ttl as i64 + self.min_expire - 1)
+            };
+            let name = self.input.read_string()?;
+            let value = self.input.read_string()?;
             self.count -= 1;
-            return Ok(val);
+            return Ok(Field { name, value, expire });
         }
-        return Err(Error::new(ErrorKind::NotFound, "No element left"));
+        Err(Error::new(ErrorKind::NotFound, "No element left"))
     }
 }
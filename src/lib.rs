@@ -16,10 +16,8 @@
 * use std::sync::atomic::AtomicBool;
 * use std::sync::Arc;
 * use std::str::FromStr;
-* use std::rc::Rc;
-* use std::cell::RefCell;
 * use redis_event::listener;
-* use redis_event::config::Config;
+* use redis_event::config::{Config, ParseMode};
 * use redis_event::{NoOpEventHandler, RedisListener};
 *
 * fn main() -> std::io::Result<()> {
@@ -40,7 +38,23 @@
 *         is_tls_enabled: false,            // 不启用TLS
 *         is_tls_insecure: false,           // 未启用TLS，设置为false即可
 *         identity: None,                   // 未启用TLS，设置为None即可
-*         identity_passwd: None             // 未启用TLS，设置为None即可
+*         identity_passwd: None,            // 未启用TLS，设置为None即可
+*         master_timeout: None,             // None，即不检测master是否失联
+*         aof_queue_size: None,             // None，即读取与处理在同一线程中进行
+*         is_ack_after_handle: false,       // 处理完事件后立即确认offset
+*         max_full_resyncs: None,           // None，即解析出错时不重新进行全量同步
+*         parse_mode: ParseMode::Strict,    // 数据不符合规范时返回错误
+*         is_skip_corrupt_keys: false,      // 解析key出错时返回错误
+*         is_discard_expired_keys: false,   // 不丢弃已过期的key
+*         expire_reference_time: None,      // 以当前时间判断key是否过期
+*         string_chunk_size: None,          // String的值不分块
+*         is_aggregate_collections: false,  // 集合类型的数据分批产生
+*         db_filter: None,                  // 处理所有db的数据
+*         key_filter: None,                 // 处理所有的key
+*         rdb_backup_path: None,            // 不保存接收到的RDB
+*         aof_backup_path: None,            // 不保存接收到的命令
+*         is_collect_stats: false,          // 不统计RDB中的数据
+*         max_handler_retries: None         // None，即处理器出错时不重新同步
 *     };
 *     let running = Arc::new(AtomicBool::new(true));
 *
@@ -49,7 +63,7 @@
 *     // 设置控制变量, 通过此变量在外界中断`redis_event`内部的逻辑
 *     builder.with_control_flag(running);
 *     // 设置事件处理器
-*     builder.with_event_handler(Rc::new(RefCell::new(NoOpEventHandler{})));
+*     builder.with_event_handler(Box::new(NoOpEventHandler{}));
 *
 *     let mut redis_listener = builder.build();
 *     // 启动程序
@@ -62,20 +76,49 @@
 * [`Event`]: enum.Event.html
 */
 
-use std::io::{Read, Result};
+use std::future::Future;
+use std::io::{Error, Read, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-use crate::cmd::Command;
-use crate::rdb::{Module, Object};
+use crate::cmd::{Command, OwnedCommand};
+use crate::rdb::{Module, Object, OwnedObject, ParseInfo};
+use crate::transaction::Transaction;
 
+pub mod aof;
+mod base64;
+pub mod broadcast;
+pub mod channel;
 pub mod cmd;
+pub mod composite;
+pub mod compress;
 pub mod config;
+mod crc64;
+pub mod diff;
+pub mod dump;
+pub mod encodings;
+pub mod export;
+pub mod fallible;
+pub mod filter;
+pub mod group;
 mod io;
 mod iter;
 pub mod listener;
+pub mod listpack;
 mod lzf;
+#[cfg(feature = "metrics-prometheus")]
+pub mod metrics;
+pub mod modules;
+pub mod normalize;
 pub mod rdb;
+pub mod replay;
 pub mod resp;
+#[cfg(feature = "serde")]
+mod serialize;
 mod tests;
+pub mod transaction;
+pub mod transform;
+pub mod typed;
 
 /// Redis事件监听器的定义，所有类型的监听器都实现此接口
 pub trait RedisListener {
@@ -92,10 +135,13 @@ pub trait RDBParser {
     /// * `input`: RDB输入流
     /// * `length`: RDB的总长度
     /// * `event_handler`: Redis事件处理器
-    fn parse(&mut self, input: &mut dyn Read, length: i64, event_handler: &mut dyn EventHandler) -> Result<()>;
+    ///
+    /// 解析完毕后返回RDB的版本、AUX字段等信息，调用方可据此判断兼容性
+    fn parse(&mut self, input: &mut dyn Read, length: i64, event_handler: &mut dyn EventHandler) -> Result<ParseInfo>;
 }
 
 /// Redis事件
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Event<'a> {
     /// RDB事件
     ///
@@ -109,9 +155,99 @@ pub enum Event<'a> {
     AOF(Command<'a>),
 }
 
+impl Event<'_> {
+    /// 复制为拥有所有权的`OwnedEvent`
+    pub fn to_owned(&self) -> OwnedEvent {
+        match self {
+            Event::RDB(object) => OwnedEvent::RDB(object.to_owned()),
+            Event::AOF(cmd) => OwnedEvent::AOF(cmd.to_owned()),
+        }
+    }
+}
+
+/// `Event`的拥有所有权的版本，不再借用解析时的缓冲区，可以保存在队列中、交给其他线程处理，或者作为迭代器的元素返回
+///
+/// 通过`Event::to_owned`得到，其中的数据与命令的转换方式见`Object::to_owned`以及`Command::to_owned`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedEvent {
+    /// RDB事件
+    RDB(OwnedObject),
+    /// AOF事件
+    AOF(OwnedCommand),
+}
+
+/// `Event::AOF`事件在复制流中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AofPosition {
+    /// 处理完此事件之后的replication offset，与此时`Listener.config.repl_offset`的值一致；
+    /// 使用SYNC同步，或者解析不带`#OFFSET:`注释的本地AOF文件时无法得知offset，此值为-1
+    pub offset: i64,
+    /// 事件的序号，从1开始单调递增，重连之后继续递增，但不会跨进程保留
+    pub sequence: u64,
+    /// AOF文件中此命令之前最近的一个`#TS:<unix-time>`注释所记录的时间(秒级)，
+    /// 仅在解析开启了`aof-timestamp-enabled`的AOF文件时才有，复制流中不包含此注释，始终为None
+    pub timestamp: Option<SystemTime>,
+}
+
 /// Redis事件处理器的定义，所有类型的处理器都必须实现此接口
 pub trait EventHandler {
     fn handle(&mut self, event: Event);
+
+    /// 处理`Event::AOF`事件，并附带此事件在复制流中的位置，默认直接交给`handle`处理
+    ///
+    /// 支持幂等写入的下游可以记录`position.offset`，重启之后据此跳过已经写入过的事件
+    fn handle_aof(&mut self, event: Event, _position: AofPosition) {
+        self.handle(event);
+    }
+
+    /// 处理`Event::AOF`事件，并附带产生此事件的原始命令(第一个为命令名)，默认交给`handle_aof`处理
+    ///
+    /// 需要原样转发命令的下游可以实现此方法，直接写出`args`中的字节，同时仍可根据`event`中解析后的命令进行过滤
+    fn handle_aof_raw(&mut self, event: Event, _args: &[Vec<u8>], position: AofPosition) {
+        self.handle_aof(event, position);
+    }
+
+    /// 处理MULTI与EXEC之间的一组命令，仅在使用[`TransactionGrouper`]时调用，默认依次解析其中的命令并交给`handle_aof_raw`处理
+    ///
+    /// 需要原子地写入事务的下游可以实现此方法
+    ///
+    /// [`TransactionGrouper`]: transaction/struct.TransactionGrouper.html
+    fn handle_transaction(&mut self, transaction: &Transaction)
+    where
+        Self: Sized,
+    {
+        transaction.replay(self);
+    }
+
+    /// 确认此前收到的所有事件都已处理完毕(如已写入下游)，仅在`Config::is_ack_after_handle`为true时调用
+    ///
+    /// 返回Ok之后，对应的offset才会被确认给master；返回Err时offset不会前进，监听器将以此错误退出，
+    /// 之后重新同步时将从最后一次确认的offset开始，保证数据至少被处理一次
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 已经收到但尚未交给下游的事件(如[`TransactionGrouper`]中暂存的事务)在复制流中的起始offset，仅在`Config::is_ack_after_handle`为true时使用
+    ///
+    /// `flush`成功之后确认给master的offset不会超过此值，重新同步时将从此处开始再次接收这些事件；
+    /// 默认返回None，即收到的事件都已交给下游。包装其他`EventHandler`的处理器应将此方法转交给内部的处理器
+    ///
+    /// [`TransactionGrouper`]: transaction/struct.TransactionGrouper.html
+    fn pending_offset(&self) -> Option<i64> {
+        None
+    }
+
+    /// 取出处理事件时出现的错误，监听器在每条命令以及整个RDB处理完毕之后调用，返回Some时按照[`HandlerError`]的类型停止监听或者重新同步
+    ///
+    /// 默认返回None，[`FallibleEventHandler`]经[`Fallible`]适配之后由其实现；包装其他`EventHandler`的处理器应将此方法转交给内部的处理器
+    ///
+    /// [`HandlerError`]: enum.HandlerError.html
+    /// [`FallibleEventHandler`]: trait.FallibleEventHandler.html
+    /// [`Fallible`]: fallible/struct.Fallible.html
+    fn take_error(&mut self) -> Option<HandlerError> {
+        None
+    }
 }
 
 /// 对于接收到的Redis事件不做任何处理
@@ -121,6 +257,289 @@ impl EventHandler for NoOpEventHandler {
     fn handle(&mut self, _: Event) {}
 }
 
+/// 需要在监听结束之后(或者在其他线程中)访问处理器时，可以将处理器放在`Arc<Mutex<_>>`中，
+/// 保留一份`Arc`之后，将另一份交给`Builder::with_event_handler`，每个事件都在持有锁时处理
+impl<H: EventHandler + ?Sized> EventHandler for Arc<Mutex<H>> {
+    fn handle(&mut self, event: Event) {
+        self.lock().unwrap().handle(event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.lock().unwrap().handle_aof(event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        self.lock().unwrap().handle_aof_raw(event, args, position);
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.lock().unwrap().flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        self.lock().unwrap().pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.lock().unwrap().take_error()
+    }
+}
+
+/// 使`Box<dyn EventHandler + Send>`等可以再交给`Normalizer`、`Filtered`等包装器
+impl<H: EventHandler + ?Sized> EventHandler for Box<H> {
+    fn handle(&mut self, event: Event) {
+        (**self).handle(event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        (**self).handle_aof(event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        (**self).handle_aof_raw(event, args, position);
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        (**self).pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        (**self).take_error()
+    }
+}
+
+/// 可以返回错误的Redis事件处理器，处理失败时无需panic或者自行设置控制变量，通过[`Fallible`]适配为`EventHandler`之后使用
+///
+/// 各个方法与`EventHandler`中的同名方法一一对应，返回错误之后，同一条命令或者同一个RDB中之后的事件将不再交给此处理器，
+/// 监听器随后按照错误的类型停止监听或者重新同步，见[`HandlerError`]
+///
+/// [`Fallible`]: fallible/struct.Fallible.html
+/// [`HandlerError`]: enum.HandlerError.html
+pub trait FallibleEventHandler {
+    fn handle(&mut self, event: Event) -> std::result::Result<(), HandlerError>;
+
+    /// 参见[`EventHandler::handle_aof`]
+    ///
+    /// [`EventHandler::handle_aof`]: trait.EventHandler.html#method.handle_aof
+    fn handle_aof(&mut self, event: Event, _position: AofPosition) -> std::result::Result<(), HandlerError> {
+        self.handle(event)
+    }
+
+    /// 参见[`EventHandler::handle_aof_raw`]
+    ///
+    /// [`EventHandler::handle_aof_raw`]: trait.EventHandler.html#method.handle_aof_raw
+    fn handle_aof_raw(
+        &mut self, event: Event, _args: &[Vec<u8>], position: AofPosition,
+    ) -> std::result::Result<(), HandlerError> {
+        self.handle_aof(event, position)
+    }
+
+    /// 参见[`EventHandler::flush`]
+    ///
+    /// [`EventHandler::flush`]: trait.EventHandler.html#method.flush
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `FallibleEventHandler`处理事件失败时返回的错误，决定监听器之后的行为
+///
+/// 解析本地的AOF或RDB文件时两者没有区别，都将以其中的错误结束解析
+#[derive(Debug)]
+pub enum HandlerError {
+    /// 暂时性的错误(如下游暂时不可用)，断开连接之后重新同步，最多重试`Config::max_handler_retries`次，超出后`start`返回此错误
+    ///
+    /// 增量同步阶段从出错的命令开始继续同步，开启`Config::is_ack_after_handle`时从最后一次确认的offset开始，
+    /// 出错的命令将再次交给处理器；RDB阶段出错时重新进行全量同步。重连之前不会等待，需要等待下游恢复时可在返回错误之前自行等待
+    Retry(Error),
+    /// 无法恢复的错误，停止监听，`start`返回其中的错误
+    Fatal(Error),
+}
+
+impl HandlerError {
+    /// 获取其中的错误
+    pub fn get_ref(&self) -> &Error {
+        match self {
+            HandlerError::Retry(error) | HandlerError::Fatal(error) => error,
+        }
+    }
+
+    /// 是否为可以重试的错误
+    pub fn is_retry(&self) -> bool {
+        matches!(self, HandlerError::Retry(_))
+    }
+}
+
+impl std::fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.get_ref().fmt(f)
+    }
+}
+
+impl std::error::Error for HandlerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.get_ref())
+    }
+}
+
+/// 未指明类型的错误均视为无法恢复的错误
+impl From<Error> for HandlerError {
+    fn from(error: Error) -> Self {
+        HandlerError::Fatal(error)
+    }
+}
+
+/// `Fatal`转换为其中的错误；`Retry`则保留在返回的错误之中，监听器据此判断是否需要重试
+impl From<HandlerError> for Error {
+    fn from(error: HandlerError) -> Self {
+        match error {
+            HandlerError::Fatal(error) => error,
+            HandlerError::Retry(inner) => Error::new(inner.kind(), HandlerError::Retry(inner)),
+        }
+    }
+}
+
+/// 异步的Redis事件处理器，适用于需要通过网络写入下游(如Kafka、另一个Redis、数据库)的处理器，等待写入时不会阻塞复制线程
+///
+/// 事件以`OwnedEvent`的形式传入，返回的`Future`不借用解析时的缓冲区，可以跨越`.await`持有事件；
+/// 返回的`Future`必须是`Send`的，以便监听器可以在多线程的运行时中运行，实现时可以直接使用`async fn`
+pub trait AsyncEventHandler {
+    fn handle(&mut self, event: OwnedEvent) -> impl Future<Output = ()> + Send;
+
+    /// 处理`OwnedEvent::AOF`事件，并附带此事件在复制流中的位置，默认直接交给`handle`处理，参见[`EventHandler::handle_aof`]
+    ///
+    /// [`EventHandler::handle_aof`]: trait.EventHandler.html#method.handle_aof
+    fn handle_aof(&mut self, event: OwnedEvent, _position: AofPosition) -> impl Future<Output = ()> + Send {
+        self.handle(event)
+    }
+
+    /// 确认此前收到的所有事件都已处理完毕，参见[`EventHandler::flush`]
+    ///
+    /// [`EventHandler::flush`]: trait.EventHandler.html#method.flush
+    fn flush(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl AsyncEventHandler for NoOpEventHandler {
+    async fn handle(&mut self, _: OwnedEvent) {}
+}
+
+/// 带有来源标识的Redis事件处理器，[`ListenerGroup`]将多个Redis的事件汇总到此处理器中
+///
+/// 各个Redis的事件在各自的线程中产生，因此实现者必须是`Send`的
+///
+/// [`ListenerGroup`]: group/struct.ListenerGroup.html
+pub trait TaggedEventHandler: Send {
+    /// 方法参数:
+    ///
+    /// * `source`: 事件的来源，即调用`ListenerGroup::add`时所指定的名字
+    /// * `event`: Redis事件
+    fn handle(&mut self, source: &str, event: Event);
+
+    /// 处理`Event::AOF`事件，并附带此事件在`source`的复制流中的位置，参见[`EventHandler::handle_aof`]
+    ///
+    /// [`EventHandler::handle_aof`]: trait.EventHandler.html#method.handle_aof
+    fn handle_aof(&mut self, source: &str, event: Event, _position: AofPosition) {
+        self.handle(source, event);
+    }
+
+    /// 处理`Event::AOF`事件，并附带产生此事件的原始命令，参见[`EventHandler::handle_aof_raw`]
+    ///
+    /// [`EventHandler::handle_aof_raw`]: trait.EventHandler.html#method.handle_aof_raw
+    fn handle_aof_raw(&mut self, source: &str, event: Event, _args: &[Vec<u8>], position: AofPosition) {
+        self.handle_aof(source, event, position);
+    }
+
+    /// 确认`source`此前的所有事件都已处理完毕，参见[`EventHandler::flush`]
+    ///
+    /// [`EventHandler::flush`]: trait.EventHandler.html#method.flush
+    fn flush(&mut self, _source: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 连接生命周期的回调接口，所有方法均有默认的空实现，按需实现即可
+///
+/// 可用于在连接建立、断开或者出错时发出告警，而不必在外部包裹整个监听逻辑
+pub trait LifecycleHandler {
+    /// 与Redis成功建立连接之后调用
+    ///
+    /// 方法参数:
+    ///
+    /// * `addr`: Redis的地址，格式为`host:port`
+    fn on_connect(&mut self, _addr: &str) {}
+
+    /// 认证以及REPLCONF握手完成之后调用
+    fn on_handshake(&mut self) {}
+
+    /// 与Redis的连接断开之后调用
+    fn on_disconnect(&mut self) {}
+
+    /// 运行过程中出现错误时调用
+    fn on_error(&mut self, _error: &Error) {}
+
+    /// 超过`Config::master_timeout`未收到master的任何数据时调用，之后将断开连接并重连
+    fn on_master_timeout(&mut self, _timeout: Duration) {}
+
+    /// RDB中出现无法识别或者不符合规范的数据(即`RDBError`)，即将断开连接并重新进行全量同步时调用，仅在设置了`Config::max_full_resyncs`时生效
+    ///
+    /// 之后将再次收到完整的RDB数据，下游可在此清理已经写入的数据
+    ///
+    /// 方法参数:
+    ///
+    /// * `error`: 解析时出现的错误
+    /// * `attempt`: 第几次重新进行全量同步，从1开始
+    fn on_full_resync(&mut self, _error: &Error, _attempt: u32) {}
+
+    /// RDB解析完毕之后调用，跳过RDB时不会调用
+    ///
+    /// 方法参数:
+    ///
+    /// * `info`: RDB的版本、AUX字段以及校验和等信息
+    fn on_rdb_parsed(&mut self, _info: &ParseInfo) {}
+}
+
+/// 对于连接的生命周期事件不做任何处理
+pub struct NoOpLifecycleHandler {}
+
+impl LifecycleHandler for NoOpLifecycleHandler {}
+
+/// 与`EventHandler`相同，需要在监听结束之后访问时，可以将`Arc<Mutex<_>>`交给`Builder::with_lifecycle_handler`
+impl<H: LifecycleHandler + ?Sized> LifecycleHandler for Arc<Mutex<H>> {
+    fn on_connect(&mut self, addr: &str) {
+        self.lock().unwrap().on_connect(addr);
+    }
+
+    fn on_handshake(&mut self) {
+        self.lock().unwrap().on_handshake();
+    }
+
+    fn on_disconnect(&mut self) {
+        self.lock().unwrap().on_disconnect();
+    }
+
+    fn on_error(&mut self, error: &Error) {
+        self.lock().unwrap().on_error(error);
+    }
+
+    fn on_master_timeout(&mut self, timeout: Duration) {
+        self.lock().unwrap().on_master_timeout(timeout);
+    }
+
+    fn on_full_resync(&mut self, error: &Error, attempt: u32) {
+        self.lock().unwrap().on_full_resync(error, attempt);
+    }
+
+    fn on_rdb_parsed(&mut self, info: &ParseInfo) {
+        self.lock().unwrap().on_rdb_parsed(info);
+    }
+}
+
 /// Module Parser
 pub trait ModuleParser {
     /// 解析Module的具体实现
@@ -131,6 +550,64 @@ pub trait ModuleParser {
     /// * `module_name`: Module的名字
     /// * `module_version`: Module的版本
     fn parse(&mut self, input: &mut dyn Read, module_name: &str, module_version: usize) -> Box<dyn Module>;
+
+    /// 解析MODULE_2类型的值，默认交给`parse`处理，此时`module_version`为2
+    ///
+    /// 需要根据Module自身的编码版本来决定读取哪些字段的实现可覆盖此方法，无法解码时返回的错误将包装在`RDBError`中返回
+    ///
+    /// 方法参数:
+    ///
+    /// * `input`: RDB输入流
+    /// * `module_name`: Module的名字
+    /// * `encver`: Module的编码版本，即Module在保存数据时所使用的版本
+    fn parse_module2(&mut self, input: &mut dyn Read, module_name: &str, _encver: usize) -> Result<Box<dyn Module>> {
+        Ok(self.parse(input, module_name, 2))
+    }
+
+    /// 是否能够解析此Module的值，默认返回true，仅解析特定Module的parser可以覆盖此方法
+    ///
+    /// 返回false时与没有设置parser时的处理方式相同: `RDB_TYPE_MODULE_2`类型的值以[`RawModule`]的形式产生，
+    /// 旧版本的Module类型的值无法跳过，将返回错误
+    ///
+    /// 方法参数:
+    ///
+    /// * `module_name`: Module的名字
+    /// * `is_module2`: 值是否为`RDB_TYPE_MODULE_2`类型，即其中的每个字段都带有module opcode
+    ///
+    /// [`RawModule`]: rdb/struct.RawModule.html
+    fn supports(&self, _module_name: &str, _is_module2: bool) -> bool {
+        true
+    }
+
+    /// 解析Module的AUX数据，即Module的全局数据(如RediSearch的索引定义)，默认返回None，即跳过此数据
+    ///
+    /// 返回Some时将产生一个`Object::ModuleAux`事件
+    ///
+    /// 方法参数:
+    ///
+    /// * `module_name`: Module的名字
+    /// * `encver`: Module的编码版本
+    /// * `when`: 数据保存在key之前(REDISMODULE_AUX_BEFORE_RDB，即1)还是之后(REDISMODULE_AUX_AFTER_RDB，即2)
+    /// * `data`: 完整的AUX数据，由一系列module opcode及其对应的值组成，包括末尾的EOF
+    fn parse_aux(&mut self, _module_name: &str, _encver: usize, _when: u64, _data: &[u8]) -> Option<Box<dyn Module>> {
+        None
+    }
+}
+
+/// 自定义命令的解析器，用于解析Redis Module所提供的命令，如`JSON.SET`、`TS.ADD`等
+///
+/// 通过[`CommandParsers`]按命令名注册，内置的命令不会交给自定义的解析器，解析的结果以`Command::Module`的形式产生，
+/// 可通过`command.value.as_any().downcast_ref::<T>()`获取
+///
+/// [`CommandParsers`]: cmd/struct.CommandParsers.html
+pub trait CommandParser {
+    /// 解析命令的具体实现
+    ///
+    /// 方法参数:
+    ///
+    /// * `name`: 大写的命令名
+    /// * `args`: 命令名之后的各个参数
+    fn parse(&mut self, name: &str, args: &[Vec<u8>]) -> Box<dyn Module>;
 }
 
 /// 转换为utf-8字符串，不验证正确性
@@ -3,40 +3,67 @@
 
 [`RedisListener`]: trait.RedisListener.html
 */
-use std::cell::RefCell;
-use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Write};
-use std::net::TcpStream;
-use std::ops::DerefMut;
-use std::rc::Rc;
+use std::any::Any;
+use std::cmp;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::net::{Shutdown, TcpStream};
+use std::panic;
 use std::result::Result::Ok;
-use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
-use std::sync::Arc;
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicU8, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+#[cfg(not(feature = "tracing"))]
 use log::{error, info, warn};
 use native_tls::{Identity, TlsConnector, TlsStream};
+#[cfg(feature = "tracing")]
+use tracing::{error, info, warn};
 
+use crate::channel::{self, EventReceiver};
+use crate::cmd::CommandParsers;
 use crate::config::Config;
-use crate::io::send;
-use crate::rdb::DefaultRDBParser;
+use crate::filter::{Filter, Filtered};
+use crate::io::{send, TeeReader};
+use crate::rdb::{DefaultRDBParser, Object, ParseError, RDBError, UnknownTypeError};
 use crate::resp::{Resp, RespDecode, Type};
-use crate::{cmd, io, EventHandler, ModuleParser, NoOpEventHandler, RDBParser, RedisListener};
+use crate::transform::{Transform, Transformer};
+use crate::{
+    aof, cmd, io, AofPosition, CommandParser, Event, EventHandler, HandlerError, LifecycleHandler, ModuleParser,
+    NoOpEventHandler, NoOpLifecycleHandler, OwnedEvent, RDBParser, RedisListener,
+};
 use scheduled_thread_pool::{JobHandle, ScheduledThreadPool};
-use std::fs::File;
+use std::fs::{self, File, OpenOptions};
+
+#[cfg(feature = "async-std")]
+pub mod async_std;
+#[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+pub mod runtime;
+#[cfg(feature = "smol")]
+pub mod smol;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 
 /// 用于监听单个Redis实例的事件
 pub struct Listener {
     pub config: Config,
     conn: Option<Stream>,
-    rdb_parser: Rc<RefCell<dyn RDBParser>>,
-    event_handler: Rc<RefCell<dyn EventHandler>>,
+    rdb_parser: Box<dyn RDBParser + Send>,
+    event_handler: Box<dyn EventHandler + Send>,
+    command_parsers: CommandParsers,
+    lifecycle_handler: Box<dyn LifecycleHandler + Send>,
     heartbeat_thread: HeartbeatWorker,
     running: Arc<AtomicBool>,
     local_ip: Option<String>,
     local_port: Option<u16>,
     thread_pool: Arc<ScheduledThreadPool>,
     repl_offset: Arc<AtomicI64>,
+    state: Arc<State>,
+    socket: Arc<Mutex<Option<TcpStream>>>,
+    is_resuming: bool,
+    // 已收到FULLRESYNC，但RDB中的数据尚未全部处理完毕
+    is_full_syncing: bool,
+    aof_sequence: u64,
 }
 
 impl Listener {
@@ -44,9 +71,12 @@ impl Listener {
     fn connect(&mut self) -> Result<()> {
         let addr = format!("{}:{}", &self.config.host, self.config.port);
         let stream = TcpStream::connect(&addr)?;
-        stream
-            .set_read_timeout(self.config.read_timeout)
-            .expect("read timeout set failed");
+        // 读取超时取两者中较小的一个，超时之后再根据最近一次收到数据的时间判断是否属于master_timeout
+        let read_timeout = match (self.config.read_timeout, self.config.master_timeout) {
+            (Some(read_timeout), Some(master_timeout)) => Some(cmp::min(read_timeout, master_timeout)),
+            (read_timeout, master_timeout) => read_timeout.or(master_timeout),
+        };
+        stream.set_read_timeout(read_timeout).expect("read timeout set failed");
         stream
             .set_write_timeout(self.config.write_timeout)
             .expect("write timeout set failed");
@@ -58,6 +88,15 @@ impl Listener {
         let local_port = socket_addr.port();
         self.local_port = Some(local_port);
 
+        // 保留一份socket的引用，stop时通过shutdown唤醒阻塞中的读取
+        {
+            let mut socket = self.socket.lock().unwrap();
+            *socket = Some(stream.try_clone()?);
+            if !self.is_running() {
+                stream.shutdown(Shutdown::Both)?;
+            }
+        }
+
         if self.config.is_tls_enabled {
             let mut builder = TlsConnector::builder();
             builder.danger_accept_invalid_hostnames(self.config.is_tls_insecure);
@@ -84,6 +123,11 @@ impl Listener {
             self.conn = Option::Some(Stream::Tcp(stream));
         }
         info!("Connected to server {}", &addr);
+        self.state.connected.store(true, Ordering::SeqCst);
+        if self.state.connections.fetch_add(1, Ordering::Relaxed) > 0 {
+            self.state.reconnects.fetch_add(1, Ordering::Relaxed);
+        }
+        self.lifecycle_handler.on_connect(&addr);
         Ok(())
     }
 
@@ -161,7 +205,7 @@ impl Listener {
                     return Err(Error::new(ErrorKind::InvalidData, err));
                 }
             }
-            _ => panic!("Unexpected response type"),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Unexpected response type")),
         }
         Ok(())
     }
@@ -191,19 +235,59 @@ impl Listener {
                     Stream::Tcp(tcp_stream) => tcp_stream,
                     Stream::Tls(tls_stream) => tls_stream,
                 };
-                let mut reader = BufReader::new(conn);
+                let mut conn = TrackingReader {
+                    inner: conn,
+                    state: &self.state,
+                };
+                let received = self.state.bytes_received.load(Ordering::Relaxed);
+                let mut reader = BufReader::new(&mut conn);
                 reader.fill_buf()?;
+                let mut backup = match &self.config.rdb_backup_path {
+                    Some(path) => Some(BufWriter::new(File::create(format!("{}.tmp", path))?)),
+                    None => None,
+                };
                 if length != -1 && self.config.is_discard_rdb {
                     info!("跳过RDB不进行处理");
-                    io::skip(&mut reader, length as isize)?;
+                    io::skip(&mut TeeReader::new(&mut reader, backup.as_mut()), length as isize)?;
+                    self.state.rdb_size.store(length as u64, Ordering::Relaxed);
                 } else {
-                    let mut event_handler = self.event_handler.borrow_mut();
-                    let mut rdb_parser = self.rdb_parser.borrow_mut();
-                    rdb_parser.parse(&mut reader, length, event_handler.deref_mut())?;
+                    let mut event_handler = TrackingHandler {
+                        inner: self.event_handler.as_mut(),
+                        state: &self.state,
+                    };
+                    let rdb_parser = &mut self.rdb_parser;
+                    #[cfg(feature = "tracing")]
+                    let _span = tracing::info_span!("rdb", length).entered();
+                    let mut input = TeeReader::new(&mut reader, backup.as_mut());
+                    let parse_info = rdb_parser.parse(&mut input, length, &mut event_handler)?;
+                    check_handler(&mut event_handler)?;
+                    info!("RDB版本: {}, 校验和: {:?}", parse_info.rdb_version, parse_info.checksum);
+                    self.lifecycle_handler.on_rdb_parsed(&parse_info);
+                    if let Some(version) = parse_info.aux_fields.get("redis-ver") {
+                        self.state
+                            .detect_script_replication(ScriptReplication::of_redis_version(version));
+                    }
+                    let rdb_size = if length == -1 {
+                        let received = self.state.bytes_received.load(Ordering::Relaxed) - received;
+                        received - reader.buffer().len() as u64
+                    } else {
+                        length as u64
+                    };
+                    self.state.rdb_size.store(rdb_size, Ordering::Relaxed);
                     if length == -1 {
                         io::skip(&mut reader, 40)?;
                     }
                 }
+                if let Some(mut backup) = backup {
+                    backup.flush()?;
+                    let path = self.config.rdb_backup_path.as_ref().unwrap();
+                    fs::rename(format!("{}.tmp", path), path)?;
+                    info!("RDB已保存至: {}", path);
+                }
+                if let Some(path) = &self.config.aof_backup_path {
+                    // 之前保存的命令与新的RDB已不再连续
+                    File::create(path)?;
+                }
                 Ok(mode)
             }
             NextStep::PartialResync => {
@@ -215,7 +299,13 @@ impl Listener {
     }
 
     fn psync(&mut self) -> Result<(NextStep, i64)> {
-        let offset = self.config.repl_offset.to_string();
+        // 重连时所请求的是下一个未处理的字节
+        let offset = if self.is_resuming {
+            self.config.repl_offset + 1
+        } else {
+            self.config.repl_offset
+        };
+        let offset = offset.to_string();
         let repl_offset = offset.as_bytes();
         let repl_id = self.config.repl_id.as_bytes();
 
@@ -241,12 +331,19 @@ impl Listener {
                         if let Some(repl_id) = iter.nth(1) {
                             self.config.repl_id = repl_id.to_owned();
                         } else {
-                            panic!("Expect replication id, but got None");
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                "Expect replication id, but got None",
+                            ));
                         }
                         if let Some(repl_offset) = iter.next() {
                             self.config.repl_offset = repl_offset.parse::<i64>().unwrap();
+                            self.is_full_syncing = true;
                         } else {
-                            panic!("Expect replication offset, but got None");
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                "Expect replication offset, but got None",
+                            ));
                         }
                         info!("等待Redis dump完成...");
                         if let Type::BulkString = conn.decode_type()? {
@@ -258,7 +355,7 @@ impl Listener {
                                 return Ok((NextStep::FullSync, length));
                             }
                         } else {
-                            panic!("Expect BulkString response");
+                            return Err(Error::new(ErrorKind::InvalidData, "Expect BulkString response"));
                         }
                     } else if resp.starts_with("CONTINUE") {
                         let mut iter = resp.split_whitespace();
@@ -274,7 +371,10 @@ impl Listener {
                         return Ok((NextStep::Wait, -1));
                     }
                 }
-                panic!("Unexpected Response: {:?}", response);
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unexpected Response: {:?}", response),
+                ))
             }
             Err(error) => {
                 if error.to_string().eq("ERR unknown command 'PSYNC'") {
@@ -302,10 +402,10 @@ impl Listener {
             if let Resp::Int(length) = conn.decode_int()? {
                 return Ok(length);
             } else {
-                panic!("Expect int response")
+                Err(Error::new(ErrorKind::InvalidData, "Expect int response"))
             }
         } else {
-            panic!("Expect BulkString response");
+            Err(Error::new(ErrorKind::InvalidData, "Expect BulkString response"))
         }
     }
 
@@ -342,34 +442,118 @@ impl Listener {
     }
 
     fn receive_aof(&mut self, mode: &Mode) -> Result<()> {
-        let mut handler = self.event_handler.as_ref().borrow_mut();
+        let mut result = self.receive_commands(mode);
+        if let Mode::PSync = mode {
+            if self.config.is_ack_after_handle {
+                // 退出前尝试确认已处理的事件，未能确认的部分在下次同步时将被重新接收
+                let flushed = self.event_handler.flush();
+                match flushed {
+                    Ok(_) => {
+                        let offset = durable_offset(self.config.repl_offset, &*self.event_handler);
+                        self.repl_offset.store(offset, Ordering::SeqCst)
+                    }
+                    Err(error) => {
+                        if result.is_ok() {
+                            result = Err(error);
+                        }
+                    }
+                }
+                self.config.repl_offset = self.repl_offset.load(Ordering::SeqCst);
+            }
+        }
+        result
+    }
+
+    fn receive_commands(&mut self, mode: &Mode) -> Result<()> {
+        let mut dispatcher = CommandDispatcher {
+            handler: TrackingHandler {
+                inner: self.event_handler.as_mut(),
+                state: &self.state,
+            },
+            acker: Acker {
+                is_ack_after_handle: self.config.is_ack_after_handle,
+                timer: Instant::now(),
+            },
+            command_log: match &self.config.aof_backup_path {
+                Some(path) => Some(CommandLog::open(path)?),
+                None => None,
+            },
+            parsers: &self.command_parsers,
+            state: &self.state,
+            mode,
+            repl_offset: &mut self.config.repl_offset,
+            acked_offset: &self.repl_offset,
+            aof_sequence: &mut self.aof_sequence,
+        };
+        if let Mode::PSync = mode {
+            self.state.mark_aof_start(*dispatcher.repl_offset);
+            // RDB中的数据同样需要在handler确认之后，才能确认FULLRESYNC返回的offset
+            let offset = *dispatcher.repl_offset;
+            dispatcher
+                .acker
+                .ack(offset, &mut dispatcher.handler, dispatcher.acked_offset)?;
+            self.is_full_syncing = false;
+        }
 
         let __conn = self.conn.as_mut().unwrap();
         match __conn {
-            Stream::Tcp(tcp_stream) => {
-                let mut reader = io::CountReader::new(tcp_stream);
+            Stream::Tcp(tcp_stream) if self.config.aof_queue_size.is_some() => {
+                let capacity = self.config.aof_queue_size.unwrap();
+                let (sender, receiver) = mpsc::sync_channel(capacity);
+                let mut input = tcp_stream.try_clone()?;
+                let running = Arc::clone(&self.running);
+                let state = Arc::clone(&self.state);
+                let reader_thread = thread::spawn(move || {
+                    let mut input = TrackingReader {
+                        inner: &mut input,
+                        state: &state,
+                    };
+                    let mut reader = io::CountReader::new(&mut input);
+                    while running.load(Ordering::Relaxed) {
+                        let command = read_command(&mut reader);
+                        let is_err = command.is_err();
+                        // 队列已满时阻塞在此处，不再从socket读取，由TCP的流量控制来限制master的发送速度
+                        if sender.send(command).is_err() || is_err {
+                            break;
+                        }
+                    }
+                });
 
+                let mut result = Ok(());
                 while self.running.load(Ordering::Relaxed) {
-                    reader.mark();
-                    if let Resp::Array(array) = reader.decode_resp()? {
-                        let size = reader.reset()?;
-                        let mut vec = Vec::with_capacity(array.len());
-                        for x in array {
-                            if let Resp::BulkBytes(bytes) = x {
-                                vec.push(bytes);
-                            } else {
-                                panic!("Expected BulkString response");
+                    match receiver.recv() {
+                        Ok(Ok((vec, size))) => {
+                            if let Err(error) = dispatcher.on_command(vec, size) {
+                                result = Err(error);
+                                break;
                             }
                         }
-                        cmd::parse(vec, handler.deref_mut());
-                        if let Mode::PSync = mode {
-                            self.config.repl_offset += size;
-                            self.repl_offset.store(self.config.repl_offset, Ordering::SeqCst);
+                        Ok(Err(error)) => {
+                            result = Err(error);
+                            break;
                         }
-                    } else {
-                        panic!("Expected array response");
+                        Err(_) => break,
                     }
                 }
+                // 唤醒可能阻塞在读取或发送上的读取线程，并等待其退出
+                drop(receiver);
+                tcp_stream.shutdown(Shutdown::Both).ok();
+                if let Err(panic) = reader_thread.join() {
+                    panic::resume_unwind(panic);
+                }
+                return result;
+            }
+            Stream::Tcp(tcp_stream) => {
+                let mut tcp_stream = TrackingReader {
+                    inner: tcp_stream,
+                    state: &self.state,
+                };
+                let mut reader = io::CountReader::new(&mut tcp_stream);
+
+                while self.running.load(Ordering::Relaxed) {
+                    let (vec, size) = read_command(&mut reader)?;
+                    dispatcher.on_command(vec, size)?;
+                }
             }
             Stream::Tls(tls_stream) => {
                 let mut timer = Instant::now();
@@ -377,31 +561,22 @@ impl Listener {
 
                 while self.running.load(Ordering::Relaxed) {
                     {
-                        let mut reader = io::CountReader::new(tls_stream);
-                        reader.mark();
-                        if let Resp::Array(array) = reader.decode_resp()? {
-                            let size = reader.reset()?;
-                            let mut vec = Vec::with_capacity(array.len());
-                            for x in array {
-                                if let Resp::BulkBytes(bytes) = x {
-                                    vec.push(bytes);
-                                } else {
-                                    panic!("Expected BulkString response");
-                                }
-                            }
-                            cmd::parse(vec, handler.deref_mut());
-                            self.config.repl_offset += size;
-                        } else {
-                            panic!("Expected array response");
-                        }
+                        let mut tls_stream = TrackingReader {
+                            inner: tls_stream,
+                            state: &self.state,
+                        };
+                        let mut reader = io::CountReader::new(&mut tls_stream);
+                        let (vec, size) = read_command(&mut reader)?;
+                        dispatcher.on_command(vec, size)?;
                     }
 
                     let elapsed = timer.elapsed();
                     if elapsed.ge(&one_sec) {
-                        let offset_str = self.config.repl_offset.to_string();
+                        let offset_str = self.repl_offset.load(Ordering::SeqCst).to_string();
                         let offset_bytes = offset_str.as_bytes();
                         if let Err(error) = send(tls_stream, b"REPLCONF", &[b"ACK", offset_bytes]) {
                             error!("heartbeat error: {}", error);
+                            self.lifecycle_handler.on_error(&error);
                             break;
                         }
                         timer = Instant::now();
@@ -416,16 +591,100 @@ impl Listener {
     fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
     }
-}
 
-impl RedisListener for Listener {
-    /// 程序运行的整体逻辑都在这个方法里面实现
+    /// 获取当前的健康状态，可用于liveness/readiness探针
+    pub fn health(&self) -> Health {
+        self.health_check().health()
+    }
+
+    /// 获取当前的运行统计信息
+    pub fn stats(&self) -> Stats {
+        self.health_check().stats()
+    }
+
+    /// 获取当前的复制延迟，未处于PSYNC模式的增量同步阶段时返回None
+    pub fn lag(&self) -> Option<Lag> {
+        self.health_check().lag()
+    }
+
+    /// 获取master复制Lua脚本的方式，见[`ScriptReplication`]
     ///
-    /// 具体的细节体现在各个方法内
-    fn start(&mut self) -> Result<()> {
-        self.connect()?;
-        self.auth()?;
-        self.send_replica_info()?;
+    /// [`ScriptReplication`]: enum.ScriptReplication.html
+    pub fn script_replication(&self) -> ScriptReplication {
+        self.health_check().script_replication()
+    }
+
+    /// 获取一个可在其他线程中查询健康状态以及统计信息的句柄
+    pub fn health_check(&self) -> HealthCheck {
+        HealthCheck {
+            state: Arc::clone(&self.state),
+            repl_offset: Arc::clone(&self.repl_offset),
+        }
+    }
+
+    /// 停止监听，即使当前正阻塞在读取数据上也会立即返回
+    pub fn stop(&self) {
+        self.stop_handle().stop();
+    }
+
+    /// 获取一个可在其他线程中停止监听的句柄
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            running: Arc::clone(&self.running),
+            socket: Arc::clone(&self.socket),
+        }
+    }
+
+    /// 判断错误是否由于超过`master_timeout`未收到数据所导致
+    ///
+    /// `read_timeout`小于`master_timeout`时，读取超时并不代表master已失效，因此还需要检查距离最近一次收到数据的时间
+    fn is_master_timeout(&self, error: &Error) -> bool {
+        let master_timeout = match self.config.master_timeout {
+            Some(master_timeout) => master_timeout,
+            None => return false,
+        };
+        if !matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+            return false;
+        }
+        let last_byte_at = self.state.last_byte_at.load(Ordering::Relaxed);
+        now_millis().saturating_sub(last_byte_at) >= master_timeout.as_millis() as u64
+    }
+
+    /// 丢弃当前的replication id及offset，下次同步时将进行全量同步
+    fn reset_replication(&mut self) {
+        self.config.repl_id = String::from("?");
+        self.config.repl_offset = -1;
+        self.repl_offset.store(-1, Ordering::SeqCst);
+        self.is_resuming = false;
+        self.is_full_syncing = false;
+    }
+
+    /// 关闭连接，并通知`LifecycleHandler`
+    fn disconnect(&mut self) {
+        if let Some(handle) = self.heartbeat_thread.handle.take() {
+            info!("Cancel heartbeat");
+            handle.cancel();
+        }
+        self.socket.lock().unwrap().take();
+        self.state.connected.store(false, Ordering::SeqCst);
+        self.state.aof_started_at.store(0, Ordering::SeqCst);
+        if self.conn.take().is_some() {
+            info!("Disconnected from server {}:{}", &self.config.host, self.config.port);
+            self.lifecycle_handler.on_disconnect();
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("redis_listener", host = %self.config.host, port = self.config.port).entered();
+        {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("handshake").entered();
+            self.connect()?;
+            self.auth()?;
+            self.send_replica_info()?;
+        }
+        self.lifecycle_handler.on_handshake();
         let mut mode;
         loop {
             mode = self.start_sync()?;
@@ -444,12 +703,69 @@ impl RedisListener for Listener {
             Ok(())
         } else {
             self.start_heartbeat(&mode);
+            if let Mode::PSync = mode {
+                // 已进入增量同步阶段，之后再次调用start时将从当前offset继续同步
+                self.is_resuming = true;
+            }
             self.receive_aof(&mode)?;
             Ok(())
         }
     }
 }
 
+impl RedisListener for Listener {
+    /// 程序运行的整体逻辑都在这个方法里面实现
+    ///
+    /// 具体的细节体现在各个方法内
+    fn start(&mut self) -> Result<()> {
+        let mut resyncs = 0;
+        let mut retries = 0;
+        loop {
+            let mut result = self.run();
+            let is_parse_error = match &result {
+                Err(error) => self.config.max_full_resyncs.is_some() && is_rdb_error(error),
+                Ok(_) => false,
+            };
+            if result.is_err() && self.is_full_syncing {
+                // RDB中的数据尚未全部处理完毕，不能从FULLRESYNC返回的offset继续同步，
+                // 无论是重新连接还是再次调用start，都需要重新进行全量同步
+                self.reset_replication();
+            }
+            if result.is_err() && !self.is_running() {
+                // 通过stop中断了阻塞的读取，属于正常退出
+                result = Ok(());
+            }
+            if let Err(error) = &result {
+                if is_parse_error && resyncs < self.config.max_full_resyncs.unwrap() {
+                    resyncs += 1;
+                    warn!("解析数据出错: {}, 重新进行全量同步(第{}次)", error, resyncs);
+                    self.lifecycle_handler.on_full_resync(error, resyncs);
+                    self.disconnect();
+                    self.reset_replication();
+                    continue;
+                }
+                if self.is_master_timeout(error) {
+                    let timeout = self.config.master_timeout.unwrap();
+                    warn!("{:?}内未收到master的任何数据, 重新连接", timeout);
+                    self.lifecycle_handler.on_master_timeout(timeout);
+                    self.disconnect();
+                    continue;
+                }
+                if is_handler_retry(error) && retries < self.config.max_handler_retries.unwrap_or(0) {
+                    retries += 1;
+                    warn!("处理事件出错: {}, 重新同步(第{}次)", error, retries);
+                    self.lifecycle_handler.on_error(error);
+                    self.disconnect();
+                    continue;
+                }
+                self.lifecycle_handler.on_error(error);
+            }
+            self.disconnect();
+            return result;
+        }
+    }
+}
+
 impl Drop for Listener {
     fn drop(&mut self) {
         if let Some(handle) = &self.heartbeat_thread.handle {
@@ -459,6 +775,674 @@ impl Drop for Listener {
     }
 }
 
+/// 获取panic时携带的信息
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown parse error")
+    }
+}
+
+/// 是否为RDB中出现了无法识别的数据类型，或者数据不符合规范所导致的错误
+fn is_rdb_error(error: &Error) -> bool {
+    match error.get_ref().and_then(|inner| inner.downcast_ref::<RDBError>()) {
+        Some(error) => error.cause::<UnknownTypeError>().is_some() || error.cause::<ParseError>().is_some(),
+        None => false,
+    }
+}
+
+/// 是否为`FallibleEventHandler`返回的`HandlerError::Retry`
+fn is_handler_retry(error: &Error) -> bool {
+    match error.get_ref().and_then(|inner| inner.downcast_ref::<HandlerError>()) {
+        Some(error) => error.is_retry(),
+        None => false,
+    }
+}
+
+/// 取出handler处理事件时出现的错误，见`EventHandler::take_error`
+pub(crate) fn check_handler(handler: &mut dyn EventHandler) -> Result<()> {
+    match handler.take_error() {
+        Some(error) => Err(error.into()),
+        None => Ok(()),
+    }
+}
+
+/// 计算处理完此命令之后的复制offset，非PSYNC模式下无法得知offset，返回-1
+fn aof_offset(mode: &Mode, repl_offset: i64, size: i64) -> i64 {
+    match mode {
+        Mode::PSync => repl_offset + size,
+        _ => -1,
+    }
+}
+
+/// 增量同步阶段逐条处理复制流中的命令，各种读取方式(TCP、TLS以及开启`aof_queue_size`时的队列)共用
+struct CommandDispatcher<'a> {
+    handler: TrackingHandler<'a>,
+    acker: Acker,
+    command_log: Option<CommandLog>,
+    parsers: &'a CommandParsers,
+    state: &'a State,
+    mode: &'a Mode,
+    // 即`Listener.config.repl_offset`
+    repl_offset: &'a mut i64,
+    // 已确认给master的offset，即`Listener.repl_offset`
+    acked_offset: &'a AtomicI64,
+    aof_sequence: &'a mut u64,
+}
+
+impl CommandDispatcher<'_> {
+    /// 将命令写入备份文件，更新统计信息，解析之后连同其在复制流中的位置交给handler，PSYNC模式下再推进offset
+    fn on_command(&mut self, vec: Vec<Vec<u8>>, size: i64) -> Result<()> {
+        if let Some(command_log) = self.command_log.as_mut() {
+            command_log.append(&vec, aof_offset(self.mode, *self.repl_offset, 0))?;
+        }
+        self.state.touch_ping(&vec);
+        self.state
+            .detect_script_replication(ScriptReplication::of_command(&vec));
+        self.state.commands.fetch_add(1, Ordering::Relaxed);
+        *self.aof_sequence += 1;
+        let position = AofPosition {
+            offset: aof_offset(self.mode, *self.repl_offset, size),
+            sequence: *self.aof_sequence,
+            timestamp: None,
+        };
+        self.dispatch(vec, position);
+        check_handler(&mut self.handler)?;
+        if let Mode::PSync = self.mode {
+            *self.repl_offset += size;
+            self.acker
+                .handled(*self.repl_offset, &mut self.handler, self.acked_offset)?;
+        }
+        Ok(())
+    }
+
+    /// 解析命令，并将其在复制流中的位置随`Event::AOF`一同交给handler
+    fn dispatch(&mut self, vec: Vec<Vec<u8>>, position: AofPosition) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "command",
+            name = %vec.first().map(|name| String::from_utf8_lossy(name)).unwrap_or_default(),
+            offset = position.offset,
+            sequence = position.sequence
+        )
+        .entered();
+        let mut handler = PositionedHandler {
+            inner: &mut self.handler,
+            args: &vec,
+            position,
+        };
+        cmd::parse(&vec, self.parsers, &mut handler);
+    }
+}
+
+/// 从AOF流中读取一条命令，返回命令的各个参数及其所占用的字节数
+fn read_command(reader: &mut io::CountReader) -> Result<(Vec<Vec<u8>>, i64)> {
+    reader.mark();
+    if let Resp::Array(array) = reader.decode_resp()? {
+        let size = reader.reset()?;
+        let mut vec = Vec::with_capacity(array.len());
+        for x in array {
+            if let Resp::BulkBytes(bytes) = x {
+                vec.push(bytes);
+            } else {
+                return Err(Error::new(ErrorKind::InvalidData, "Expected BulkString response"));
+            }
+        }
+        Ok((vec, size))
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, "Expected array response"))
+    }
+}
+
+/// 在单独的线程中运行`Listener`，并以迭代器的形式逐个拉取事件，作为`EventHandler`之外的另一种消费方式
+///
+/// 方法参数:
+///
+/// * `queue_size`: 已产生而尚未被拉取的事件的最大个数，达到此数量时监听线程将暂停，不再从master读取数据
+/// * `configure`: 在监听线程中配置`Builder`，至少需要通过`with_config`设置`Config`；
+///   其中设置的`EventHandler`以及控制变量将被替换，可通过`Events::stop_handle`停止监听
+///
+/// 监听出错时迭代器将产生一个`Err`，之后结束；`Events`被drop时监听将停止。
+/// 事件在迭代器下一次拉取时才被视为已处理完毕，开启`Config::is_ack_after_handle`时，
+/// `ChannelHandler::flush`将等待队列中的事件都被拉取并处理之后，才确认offset
+///
+/// ```no_run
+/// use redis_event::config::Config;
+/// use redis_event::{listener, OwnedEvent};
+///
+/// fn consume(config: Config) -> std::io::Result<()> {
+///     let events = listener::events(1024, move |builder| builder.with_config(config))?;
+///     for event in events {
+///         match event? {
+///             OwnedEvent::RDB(object) => println!("{:?}", object),
+///             OwnedEvent::AOF(cmd) => println!("{}", cmd.name()),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn events<F>(queue_size: usize, configure: F) -> Result<Events>
+where
+    F: FnOnce(&mut Builder) + Send + 'static,
+{
+    let (mut handler, receiver) = channel::channel(queue_size);
+    let (handle_sender, handle_receiver) = mpsc::channel();
+    let thread = thread::Builder::new().name("redis-events".to_string()).spawn(move || {
+        let running = Arc::new(AtomicBool::new(true));
+        handler.with_control_flag(Arc::clone(&running));
+        let mut builder = Builder::new();
+        configure(&mut builder);
+        builder.with_control_flag(running);
+        builder.with_event_handler(Box::new(handler));
+        let mut listener = builder.build();
+        if handle_sender
+            .send((listener.stop_handle(), listener.health_check()))
+            .is_err()
+        {
+            return Ok(());
+        }
+        listener.start()
+    })?;
+    match handle_receiver.recv() {
+        Ok((stop_handle, health_check)) => Ok(Events {
+            receiver,
+            stop_handle,
+            health_check,
+            thread: Some(thread),
+        }),
+        // 配置Builder时出错，如未设置Config
+        Err(_) => match thread.join() {
+            Err(panic) => Err(Error::other(panic_message(panic))),
+            Ok(_) => Err(Error::other("listener thread exited")),
+        },
+    }
+}
+
+/// 由`listener::events`返回的事件迭代器
+pub struct Events {
+    receiver: EventReceiver,
+    stop_handle: StopHandle,
+    health_check: HealthCheck,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl Events {
+    /// 获取一个可在其他线程中停止监听的句柄，停止之后迭代器在拉取完队列中剩余的事件后结束
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// 获取一个可在其他线程中查询健康状态以及统计信息的句柄
+    pub fn health_check(&self) -> HealthCheck {
+        self.health_check.clone()
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<OwnedEvent>;
+
+    fn next(&mut self) -> Option<Result<OwnedEvent>> {
+        match self.receiver.recv() {
+            Some(event) => Some(Ok(event)),
+            // 监听线程已退出，将其返回的错误或者panic转换为最后一个元素
+            None => match self.thread.take()?.join() {
+                Ok(Ok(_)) => None,
+                Ok(Err(error)) => Some(Err(error)),
+                Err(panic) => Some(Err(Error::other(panic_message(panic)))),
+            },
+        }
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.stop_handle.stop();
+    }
+}
+
+/// 用于停止监听的句柄，可以在其他线程中使用
+#[derive(Clone)]
+pub struct StopHandle {
+    running: Arc<AtomicBool>,
+    socket: Arc<Mutex<Option<TcpStream>>>,
+}
+
+impl StopHandle {
+    /// 将控制变量置为false，并关闭socket以唤醒阻塞中的读取
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(socket) = self.socket.lock().unwrap().as_ref() {
+            if let Err(error) = socket.shutdown(Shutdown::Both) {
+                warn!("shutdown socket error: {}", error);
+            }
+        }
+    }
+}
+
+/// 监听器的健康状态
+#[derive(Debug, Clone)]
+pub struct Health {
+    /// 当前是否已连接到Redis
+    pub connected: bool,
+    /// 最近一次从Redis接收到数据的时间
+    pub last_byte_at: Option<SystemTime>,
+    /// 最近一次将事件交给`EventHandler`的时间
+    pub last_event_at: Option<SystemTime>,
+    /// 当前的replication offset
+    pub offset: i64,
+    /// 距离最近一次收到master发送的PING所经过的时长
+    pub since_last_ping: Option<Duration>,
+}
+
+/// 监听器的运行统计信息
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// 从Redis接收到的总字节数
+    pub bytes_received: u64,
+    /// 最近一次全量同步的RDB大小(字节)
+    pub rdb_size: u64,
+    /// 各类型事件的数量
+    pub events: EventStats,
+    /// 已解析的命令数量，包括master发送的PING等不会产生事件的命令
+    pub commands_parsed: u64,
+    /// 重连的次数
+    pub reconnects: u64,
+}
+
+/// 按类型统计的事件数量
+///
+/// 对于大key，RDB中同一个key的数据可能会被拆分为多个事件，每个事件都会计数
+#[derive(Debug, Clone, Default)]
+pub struct EventStats {
+    pub strings: u64,
+    pub lists: u64,
+    pub sets: u64,
+    pub sorted_sets: u64,
+    pub hashes: u64,
+    pub modules: u64,
+    pub streams: u64,
+    /// AOF事件，即Redis命令
+    pub commands: u64,
+}
+
+/// 复制延迟，即master已经发送过来的数据与已交给`EventHandler`处理的数据之间的差距
+#[derive(Debug, Clone)]
+pub struct Lag {
+    /// master的replication offset，根据已接收到的字节数推算
+    pub master_offset: i64,
+    /// 最后一个已交给`EventHandler`处理完毕的命令所对应的offset
+    pub handled_offset: i64,
+    /// 延迟的字节数
+    pub bytes: i64,
+    /// 根据处理速度估算的延迟秒数
+    pub seconds: f64,
+}
+
+/// master复制Lua脚本的方式，决定了下游能否直接重放复制流中的命令
+///
+/// `Verbatim`时复制流中为`EVAL`、`EVALSHA`，下游需要以同样的脚本执行才能得到相同的结果，`EVALSHA`所引用的脚本
+/// 来自之前的`SCRIPT LOAD`事件(包括全量同步时RDB中所带的脚本)；`Effects`时脚本所产生的写命令包装在MULTI与EXEC之间复制，
+/// 与普通的事务相同，可配合`transaction::TransactionGrouper`使用。
+///
+/// 根据全量同步时RDB中的`redis-ver`(Redis 7.0之后只支持`Effects`)以及复制流中出现的`EVAL`、`EVALSHA`判断，
+/// Redis 7.0之前可以通过`redis.replicate_commands()`为单个脚本选择`Effects`，因此`Verbatim`表示至少有部分脚本被原样复制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptReplication {
+    /// 尚未能判断，如Redis 7.0之前的master还未执行过任何脚本
+    Unknown,
+    /// 原样复制脚本，即`lua-replicate-commands no`，Redis 5.0之前的默认方式
+    Verbatim,
+    /// 复制脚本所产生的写命令
+    Effects,
+}
+
+impl ScriptReplication {
+    /// 根据master的版本判断，Redis 7.0之前无法仅凭版本得知
+    pub(crate) fn of_redis_version(version: &str) -> ScriptReplication {
+        let major = version.split('.').next().and_then(|major| major.parse::<u32>().ok());
+        match major {
+            Some(major) if major >= 7 => ScriptReplication::Effects,
+            _ => ScriptReplication::Unknown,
+        }
+    }
+
+    /// 根据复制流中的命令判断，只有以`Effects`方式复制时不会出现的命令才能作为依据
+    pub(crate) fn of_command(command: &[Vec<u8>]) -> ScriptReplication {
+        match command.first() {
+            Some(name) if name.eq_ignore_ascii_case(b"EVAL") || name.eq_ignore_ascii_case(b"EVALSHA") => {
+                ScriptReplication::Verbatim
+            }
+            _ => ScriptReplication::Unknown,
+        }
+    }
+
+    fn from_u8(value: u8) -> ScriptReplication {
+        match value {
+            1 => ScriptReplication::Verbatim,
+            2 => ScriptReplication::Effects,
+            _ => ScriptReplication::Unknown,
+        }
+    }
+}
+
+/// 健康状态以及统计信息的查询句柄，可以在其他线程中使用
+#[derive(Clone)]
+pub struct HealthCheck {
+    state: Arc<State>,
+    repl_offset: Arc<AtomicI64>,
+}
+
+impl HealthCheck {
+    /// 获取当前的健康状态
+    pub fn health(&self) -> Health {
+        let last_ping_at = to_system_time(self.state.last_ping_at.load(Ordering::Relaxed));
+        Health {
+            connected: self.state.connected.load(Ordering::Relaxed),
+            last_byte_at: to_system_time(self.state.last_byte_at.load(Ordering::Relaxed)),
+            last_event_at: to_system_time(self.state.last_event_at.load(Ordering::Relaxed)),
+            offset: self.repl_offset.load(Ordering::Relaxed),
+            since_last_ping: last_ping_at.map(|time| time.elapsed().unwrap_or_default()),
+        }
+    }
+
+    /// 获取当前的复制延迟，未处于PSYNC模式的增量同步阶段时返回None
+    pub fn lag(&self) -> Option<Lag> {
+        let state = &self.state;
+        let started_at = to_system_time(state.aof_started_at.load(Ordering::Relaxed))?;
+        let base_offset = state.aof_base_offset.load(Ordering::Relaxed);
+        let base_bytes = state.aof_base_bytes.load(Ordering::Relaxed);
+        let received = state.bytes_received.load(Ordering::Relaxed).saturating_sub(base_bytes);
+        let master_offset = base_offset + received as i64;
+        let handled_offset = self.repl_offset.load(Ordering::Relaxed);
+        let bytes = cmp::max(master_offset - handled_offset, 0);
+
+        let elapsed = started_at.elapsed().unwrap_or_default().as_secs_f64();
+        let handled = (handled_offset - base_offset) as f64;
+        let seconds = if bytes == 0 {
+            0.0
+        } else if handled > 0.0 && elapsed > 0.0 {
+            bytes as f64 / (handled / elapsed)
+        } else {
+            elapsed
+        };
+        Some(Lag {
+            master_offset,
+            handled_offset,
+            bytes,
+            seconds,
+        })
+    }
+
+    /// 获取master复制Lua脚本的方式
+    pub fn script_replication(&self) -> ScriptReplication {
+        ScriptReplication::from_u8(self.state.script_replication.load(Ordering::Relaxed))
+    }
+
+    /// 获取当前的运行统计信息
+    pub fn stats(&self) -> Stats {
+        let state = &self.state;
+        Stats {
+            bytes_received: state.bytes_received.load(Ordering::Relaxed),
+            rdb_size: state.rdb_size.load(Ordering::Relaxed),
+            events: EventStats {
+                strings: state.events[EVENT_STRING].load(Ordering::Relaxed),
+                lists: state.events[EVENT_LIST].load(Ordering::Relaxed),
+                sets: state.events[EVENT_SET].load(Ordering::Relaxed),
+                sorted_sets: state.events[EVENT_SORTED_SET].load(Ordering::Relaxed),
+                hashes: state.events[EVENT_HASH].load(Ordering::Relaxed),
+                modules: state.events[EVENT_MODULE].load(Ordering::Relaxed),
+                streams: state.events[EVENT_STREAM].load(Ordering::Relaxed),
+                commands: state.events[EVENT_COMMAND].load(Ordering::Relaxed),
+            },
+            commands_parsed: state.commands.load(Ordering::Relaxed),
+            reconnects: state.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 各时间点以距离UNIX_EPOCH的毫秒数保存，0表示还未发生过
+#[derive(Default)]
+struct State {
+    connected: AtomicBool,
+    last_byte_at: AtomicU64,
+    last_event_at: AtomicU64,
+    last_ping_at: AtomicU64,
+    bytes_received: AtomicU64,
+    rdb_size: AtomicU64,
+    events: [AtomicU64; 8],
+    commands: AtomicU64,
+    connections: AtomicU64,
+    reconnects: AtomicU64,
+    aof_started_at: AtomicU64,
+    aof_base_offset: AtomicI64,
+    aof_base_bytes: AtomicU64,
+    script_replication: AtomicU8,
+}
+
+// `State::events`中各类型事件的下标
+const EVENT_STRING: usize = 0;
+const EVENT_LIST: usize = 1;
+const EVENT_SET: usize = 2;
+const EVENT_SORTED_SET: usize = 3;
+const EVENT_HASH: usize = 4;
+const EVENT_MODULE: usize = 5;
+const EVENT_STREAM: usize = 6;
+const EVENT_COMMAND: usize = 7;
+
+impl State {
+    // 记录增量同步开始时的offset以及已接收的字节数，用于推算master的offset
+    fn mark_aof_start(&self, repl_offset: i64) {
+        self.aof_base_offset.store(repl_offset, Ordering::SeqCst);
+        self.aof_base_bytes
+            .store(self.bytes_received.load(Ordering::SeqCst), Ordering::SeqCst);
+        self.aof_started_at.store(now_millis(), Ordering::SeqCst);
+    }
+
+    fn count_event(&self, event: &Event) {
+        let index = match event {
+            Event::RDB(Object::String(_)) => EVENT_STRING,
+            Event::RDB(Object::List(_)) => EVENT_LIST,
+            Event::RDB(Object::Set(_)) => EVENT_SET,
+            Event::RDB(Object::SortedSet(_)) => EVENT_SORTED_SET,
+            Event::RDB(Object::Hash(_)) => EVENT_HASH,
+            Event::RDB(Object::Module(..)) => EVENT_MODULE,
+            Event::RDB(Object::Stream(..)) => EVENT_STREAM,
+            Event::RDB(_) => return,
+            Event::AOF(_) => EVENT_COMMAND,
+        };
+        self.events[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    // 未能判断时保留之前的结果
+    fn detect_script_replication(&self, mode: ScriptReplication) {
+        let value = match mode {
+            ScriptReplication::Unknown => return,
+            ScriptReplication::Verbatim => 1,
+            ScriptReplication::Effects => 2,
+        };
+        if self.script_replication.load(Ordering::Relaxed) != value {
+            info!("master复制Lua脚本的方式: {:?}", mode);
+            self.script_replication.store(value, Ordering::Relaxed);
+        }
+    }
+
+    fn touch_ping(&self, command: &[Vec<u8>]) {
+        if let Some(name) = command.first() {
+            if name.eq_ignore_ascii_case(b"PING") {
+                self.last_ping_at.store(now_millis(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as u64,
+        Err(_) => 0,
+    }
+}
+
+fn to_system_time(millis: u64) -> Option<SystemTime> {
+    if millis == 0 {
+        None
+    } else {
+        Some(UNIX_EPOCH + Duration::from_millis(millis))
+    }
+}
+
+// 读取数据时记录最近一次接收到数据的时间，以及接收到的字节数
+struct TrackingReader<'a> {
+    inner: &'a mut dyn Read,
+    state: &'a State,
+}
+
+impl Read for TrackingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.inner.read(buf)?;
+        if len > 0 {
+            self.state.last_byte_at.store(now_millis(), Ordering::Relaxed);
+            self.state.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+        }
+        Ok(len)
+    }
+}
+
+// 将复制流中的命令原样追加到文件中，并每秒写入一次`#OFFSET:<offset>`注释，记录之后的命令在复制流中的起始位置
+struct CommandLog {
+    output: BufWriter<File>,
+    marked_at: Option<Instant>,
+}
+
+impl CommandLog {
+    fn open(path: &str) -> Result<CommandLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(CommandLog {
+            output: BufWriter::new(file),
+            marked_at: None,
+        })
+    }
+
+    // offset为此命令之前的replication offset，为-1时不写入注释
+    fn append(&mut self, command: &[Vec<u8>], offset: i64) -> Result<()> {
+        let is_due = match self.marked_at {
+            Some(at) => at.elapsed() >= Duration::from_secs(1),
+            None => true,
+        };
+        if is_due {
+            self.output.flush()?;
+            if offset != -1 {
+                write!(self.output, "#{}{}\r\n", aof::OFFSET_ANNOTATION, offset)?;
+            }
+            self.marked_at = Some(Instant::now());
+        }
+        write!(self.output, "*{}\r\n", command.len())?;
+        for arg in command {
+            write!(self.output, "${}\r\n", arg.len())?;
+            self.output.write_all(arg)?;
+            self.output.write_all(b"\r\n")?;
+        }
+        Ok(())
+    }
+}
+
+// 将事件交给`EventHandler`时记录当前的时间，并按类型统计事件数量
+struct TrackingHandler<'a> {
+    inner: &'a mut dyn EventHandler,
+    state: &'a State,
+}
+
+impl EventHandler for TrackingHandler<'_> {
+    fn handle(&mut self, event: Event) {
+        self.state.last_event_at.store(now_millis(), Ordering::Relaxed);
+        self.state.count_event(&event);
+        self.inner.handle(event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.state.last_event_at.store(now_millis(), Ordering::Relaxed);
+        self.state.count_event(&event);
+        self.inner.handle_aof(event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        self.state.last_event_at.store(now_millis(), Ordering::Relaxed);
+        self.state.count_event(&event);
+        self.inner.handle_aof_raw(event, args, position);
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        self.inner.pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
+
+/// 将`Event::AOF`事件连同原始命令及其位置信息转交给`EventHandler::handle_aof_raw`
+pub(crate) struct PositionedHandler<'a> {
+    pub(crate) inner: &'a mut dyn EventHandler,
+    pub(crate) args: &'a [Vec<u8>],
+    pub(crate) position: AofPosition,
+}
+
+impl EventHandler for PositionedHandler<'_> {
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::AOF(_) => self.inner.handle_aof_raw(event, self.args, self.position),
+            Event::RDB(_) => self.inner.handle(event),
+        }
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
+
+/// 负责推进发送给master的(即已确认的)offset
+///
+/// ack-after-handle模式下，只有在handler的flush成功之后才会推进，以保证重新同步时不会跳过未处理完的数据
+struct Acker {
+    is_ack_after_handle: bool,
+    timer: Instant,
+}
+
+impl Acker {
+    /// 一条命令处理完毕之后调用，ack-after-handle模式下每秒最多flush一次
+    fn handled(&mut self, offset: i64, handler: &mut dyn EventHandler, acked: &AtomicI64) -> Result<()> {
+        if self.is_ack_after_handle && self.timer.elapsed() < Duration::from_secs(1) {
+            return Ok(());
+        }
+        self.ack(offset, handler, acked)
+    }
+
+    fn ack(&mut self, offset: i64, handler: &mut dyn EventHandler, acked: &AtomicI64) -> Result<()> {
+        let mut offset = offset;
+        if self.is_ack_after_handle {
+            handler.flush()?;
+            self.timer = Instant::now();
+            offset = durable_offset(offset, handler);
+        }
+        acked.store(offset, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// flush之后可以确认的offset，不超过handler中尚未交给下游的事件的起始offset
+fn durable_offset(offset: i64, handler: &dyn EventHandler) -> i64 {
+    match handler.pending_offset() {
+        Some(pending) => cmp::min(offset, pending),
+        None => offset,
+    }
+}
+
 struct HeartbeatWorker {
     handle: Option<JobHandle>,
 }
@@ -478,11 +1462,15 @@ enum Mode {
 
 pub struct Builder {
     pub config: Option<Config>,
-    pub rdb_parser: Option<Rc<RefCell<dyn RDBParser>>>,
-    pub event_handler: Option<Rc<RefCell<dyn EventHandler>>>,
-    pub module_parser: Option<Rc<RefCell<dyn ModuleParser>>>,
+    pub rdb_parser: Option<Box<dyn RDBParser + Send>>,
+    pub event_handler: Option<Box<dyn EventHandler + Send>>,
+    pub module_parser: Option<Box<dyn ModuleParser + Send>>,
+    pub command_parsers: CommandParsers,
+    pub lifecycle_handler: Option<Box<dyn LifecycleHandler + Send>>,
     pub control_flag: Option<Arc<AtomicBool>>,
     pub thread_pool: Option<Arc<ScheduledThreadPool>>,
+    pub filter: Option<Filter>,
+    pub transforms: Vec<Box<dyn Transform + Send>>,
 }
 
 impl Builder {
@@ -492,8 +1480,12 @@ impl Builder {
             rdb_parser: None,
             event_handler: None,
             module_parser: None,
+            command_parsers: CommandParsers::new(),
+            lifecycle_handler: None,
             control_flag: None,
             thread_pool: None,
+            filter: None,
+            transforms: Vec::new(),
         }
     }
 
@@ -501,18 +1493,48 @@ impl Builder {
         self.config = Some(config);
     }
 
-    pub fn with_rdb_parser(&mut self, parser: Rc<RefCell<dyn RDBParser>>) {
+    pub fn with_rdb_parser(&mut self, parser: Box<dyn RDBParser + Send>) {
         self.rdb_parser = Some(parser);
     }
 
-    pub fn with_event_handler(&mut self, handler: Rc<RefCell<dyn EventHandler>>) {
+    /// 设置事件处理器，处理器必须是`Send`的，创建好的`Listener`可以交给其他线程运行
+    ///
+    /// 监听结束之后还需要访问处理器时，可以传入`Arc<Mutex<_>>`并保留一份`Arc`
+    pub fn with_event_handler(&mut self, handler: Box<dyn EventHandler + Send>) {
         self.event_handler = Some(handler);
     }
 
-    pub fn with_module_parser(&mut self, parser: Rc<RefCell<dyn ModuleParser>>) {
+    /// 在事件交给处理器之前按照`filter`丢弃不需要的事件，见[`Filter`]
+    ///
+    /// [`Filter`]: ../filter/struct.Filter.html
+    pub fn with_filter(&mut self, filter: Filter) {
+        self.filter = Some(filter);
+    }
+
+    /// 添加一个改写阶段，各个阶段按照添加的顺序执行，见[`Transform`]
+    ///
+    /// 设置了`Filter`时，先过滤再改写，即`Filter`匹配的是改写之前的key
+    ///
+    /// [`Transform`]: ../transform/trait.Transform.html
+    pub fn with_transform(&mut self, stage: Box<dyn Transform + Send>) {
+        self.transforms.push(stage);
+    }
+
+    pub fn with_module_parser(&mut self, parser: Box<dyn ModuleParser + Send>) {
         self.module_parser = Some(parser);
     }
 
+    /// 注册命令`name`的自定义解析器，见[`CommandParser`]
+    ///
+    /// [`CommandParser`]: ../trait.CommandParser.html
+    pub fn with_command_parser(&mut self, name: &str, parser: Arc<Mutex<dyn CommandParser + Send>>) {
+        self.command_parsers.register(name, parser);
+    }
+
+    pub fn with_lifecycle_handler(&mut self, handler: Box<dyn LifecycleHandler + Send>) {
+        self.lifecycle_handler = Some(handler);
+    }
+
     pub fn with_control_flag(&mut self, flag: Arc<AtomicBool>) {
         self.control_flag = Some(flag);
     }
@@ -521,37 +1543,57 @@ impl Builder {
         self.thread_pool = Option::Some(thread_pool);
     }
 
+    /// 创建`Listener`，设置的处理器以及解析器将移交给此`Listener`，再次调用时将使用默认的实现
     pub fn build(&mut self) -> Listener {
         let config = match &self.config {
             Some(c) => c,
             None => panic!("Parameter Config is required"),
         };
 
-        let module_parser = match &self.module_parser {
-            None => None,
-            Some(parser) => Some(parser.clone()),
-        };
+        let module_parser = self.module_parser.take();
 
         let running = match &self.control_flag {
             None => panic!("Parameter Control_flag is required"),
             Some(flag) => flag.clone(),
         };
 
-        let rdb_parser = match &self.rdb_parser {
-            None => Rc::new(RefCell::new(DefaultRDBParser {
-                running: Arc::clone(&running),
+        let rdb_parser = match self.rdb_parser.take() {
+            None => Box::new(DefaultRDBParser::from_config(
+                config,
+                Arc::clone(&running),
                 module_parser,
-            })),
-            Some(parser) => parser.clone(),
+            )),
+            Some(parser) => parser,
         };
 
-        let event_handler = match &self.event_handler {
-            None => Rc::new(RefCell::new(NoOpEventHandler {})),
-            Some(handler) => handler.clone(),
+        let mut event_handler: Box<dyn EventHandler + Send> = match self.event_handler.take() {
+            None => Box::new(NoOpEventHandler {}),
+            Some(handler) => handler,
+        };
+        if !self.transforms.is_empty() {
+            let mut transformer = Transformer::new(event_handler);
+            transformer.set_command_parsers(self.command_parsers.clone());
+            for stage in self.transforms.drain(..) {
+                transformer.add(stage);
+            }
+            event_handler = Box::new(transformer);
+        }
+        if let Some(filter) = self.filter.take() {
+            event_handler = Box::new(Filtered::new(event_handler, filter));
+        }
+
+        let lifecycle_handler = match self.lifecycle_handler.take() {
+            None => Box::new(NoOpLifecycleHandler {}),
+            Some(handler) => handler,
         };
 
         let thread_pool = match &self.thread_pool {
-            None => Arc::new(ScheduledThreadPool::with_name("hearbeat-thread-{}", 1)),
+            None => Arc::new(
+                ScheduledThreadPool::builder()
+                    .num_threads(1)
+                    .thread_name_pattern("heartbeat-thread-{}")
+                    .build(),
+            ),
             Some(pool) => Arc::clone(pool),
         };
 
@@ -560,12 +1602,19 @@ impl Builder {
             conn: None,
             rdb_parser,
             event_handler,
+            command_parsers: self.command_parsers.clone(),
+            lifecycle_handler,
             heartbeat_thread: HeartbeatWorker { handle: None },
             running,
             local_ip: None,
             local_port: None,
             thread_pool,
             repl_offset: Arc::new(AtomicI64::from(config.repl_offset)),
+            state: Arc::new(State::default()),
+            socket: Arc::new(Mutex::new(None)),
+            is_resuming: false,
+            is_full_syncing: false,
+            aof_sequence: 0,
         }
     }
 }
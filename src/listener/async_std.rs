@@ -0,0 +1,99 @@
+/*!
+基于async-std的异步监听器，见[`runtime`]模块
+
+```no_run
+use redis_event::config::Config;
+use redis_event::listener::async_std::Listener;
+use redis_event::{AsyncEventHandler, OwnedEvent};
+
+struct Printer {}
+
+impl AsyncEventHandler for Printer {
+    async fn handle(&mut self, event: OwnedEvent) {
+        if let OwnedEvent::AOF(cmd) = event {
+            println!("{}", cmd.name());
+        }
+    }
+}
+
+async fn run(config: Config) -> std::io::Result<()> {
+    let mut listener = Listener::new(config, Printer {});
+    listener.start().await
+}
+```
+
+[`runtime`]: ../runtime/index.html
+*/
+use std::future::Future;
+use std::io::{Error, Result};
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+use ::async_std::channel::{self, Receiver, Sender};
+use ::async_std::io::{ReadExt, WriteExt};
+use ::async_std::net::TcpStream;
+use ::async_std::task;
+
+use super::panic_message;
+use super::runtime::{AsyncListener, Runtime};
+use crate::OwnedEvent;
+
+/// 基于async-std的异步监听器
+pub type Listener<H> = AsyncListener<AsyncStd, H>;
+
+/// async-std运行时，RDB在`task::spawn_blocking`的线程中解析，其中通过`task::block_on`读取socket
+pub struct AsyncStd;
+
+impl Runtime for AsyncStd {
+    type Reader = TcpStream;
+    type Writer = TcpStream;
+    type Handle = ();
+    type Sender = Sender<OwnedEvent>;
+    type Receiver = Receiver<OwnedEvent>;
+
+    async fn connect(addr: String) -> Result<(TcpStream, TcpStream, SocketAddr)> {
+        let stream = TcpStream::connect(addr).await?;
+        let local_addr = stream.local_addr()?;
+        Ok((stream.clone(), stream, local_addr))
+    }
+
+    async fn read(reader: &mut TcpStream, buf: &mut [u8]) -> Result<usize> {
+        reader.read(buf).await
+    }
+
+    async fn write_all(writer: &mut TcpStream, buf: &[u8]) -> Result<()> {
+        writer.write_all(buf).await
+    }
+
+    async fn sleep(duration: Duration) {
+        task::sleep(duration).await
+    }
+
+    fn handle() {}
+
+    fn block_on<F: Future>(_: &(), future: F) -> F::Output {
+        task::block_on(future)
+    }
+
+    fn spawn_blocking<F, T>(f: F) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = task::spawn_blocking(move || panic::catch_unwind(AssertUnwindSafe(f)));
+        async move { task.await.map_err(|panic| Error::other(panic_message(panic))) }
+    }
+
+    fn channel(capacity: usize) -> (Self::Sender, Self::Receiver) {
+        channel::bounded(capacity)
+    }
+
+    fn send_blocking(_: &(), sender: &Self::Sender, event: OwnedEvent) -> bool {
+        task::block_on(sender.send(event)).is_ok()
+    }
+
+    async fn recv(receiver: &mut Self::Receiver) -> Option<OwnedEvent> {
+        receiver.recv().await.ok()
+    }
+}
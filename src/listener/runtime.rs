@@ -0,0 +1,753 @@
+/*!
+与异步运行时无关的异步监听器，适用于已经运行在异步运行时之上的服务，无需为每个master单独占用一个阻塞的线程
+
+与[`Listener`]一样以replica的身份连接到Redis，区别在于:
+- 事件以`OwnedEvent`的形式交给[`AsyncEventHandler`]处理，处理器可以在其中异步地写入下游
+- 握手以及增量同步阶段的命令在调用`start`的任务中异步地读取及解析
+- RDB仍由同步的解析器解析，解析在运行时的阻塞线程池中进行，其通过`Runtime::block_on`从同一个异步的socket读取数据，
+  产生的事件经有界队列交回调用`start`的任务处理，RDB解析完毕之后该线程即被归还
+- 尚不支持TLS(开启时`start`返回错误)，`read_timeout`、`write_timeout`、`master_timeout`、`aof_queue_size`、
+  `max_full_resyncs`、`rdb_backup_path`以及`aof_backup_path`将被忽略，出错之后也不会自动重连
+- 停止监听时可以直接drop`start`返回的`Future`，或者通过`stop_handle`，后者在处理完当前的命令之后才会返回
+
+运行时通过[`Runtime`]接口抽象，开启对应的feature之后可以直接使用[`tokio::Listener`]、[`async_std::Listener`]或者[`smol::Listener`]，
+其他的运行时可以自行实现[`Runtime`]之后使用[`AsyncListener`]。
+
+[`Listener`]: ../struct.Listener.html
+[`AsyncEventHandler`]: ../../trait.AsyncEventHandler.html
+[`Runtime`]: trait.Runtime.html
+[`AsyncListener`]: struct.AsyncListener.html
+[`tokio::Listener`]: ../tokio/type.Listener.html
+[`async_std::Listener`]: ../async_std/type.Listener.html
+[`smol::Listener`]: ../smol/type.Listener.html
+*/
+use std::future::Future;
+use std::io::{BufReader, Error, ErrorKind, Read, Result};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+#[cfg(not(feature = "tracing"))]
+use log::{info, warn};
+#[cfg(feature = "tracing")]
+use tracing::{info, warn};
+
+use super::{now_millis, HealthCheck, ScriptReplication, State, StopHandle, TrackingHandler};
+use crate::cmd::{self, CommandParsers};
+use crate::config::Config;
+use crate::io;
+use crate::rdb::DefaultRDBParser;
+use crate::resp::encode_command;
+use crate::{AofPosition, AsyncEventHandler, CommandParser, Event, EventHandler, ModuleParser, OwnedEvent, RDBParser};
+
+// 解析RDB时已产生而尚未被处理的事件的最大个数，达到此数量时解析线程将暂停
+const EVENT_QUEUE_SIZE: usize = 1024;
+
+// 每次从socket读取的最大字节数
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// 异步运行时的抽象，`AsyncListener`通过此接口建立连接、读写数据以及在阻塞线程池中解析RDB
+///
+/// 返回的`Future`都必须是`Send`的，以便`AsyncListener::start`可以在多线程的运行时中运行
+pub trait Runtime: 'static {
+    /// 连接的读取端
+    type Reader: Send + Unpin + 'static;
+    /// 连接的写入端
+    type Writer: Send + Unpin;
+    /// 在阻塞线程池中执行`block_on`时所需的运行时句柄，在调用`start`的任务中通过`handle`获取
+    type Handle: Clone + Send + 'static;
+    /// 有界队列的发送端，在阻塞线程池中使用
+    type Sender: Send + 'static;
+    /// 有界队列的接收端，在调用`start`的任务中使用
+    type Receiver: Send;
+
+    /// 建立TCP连接，返回连接的读取端、写入端以及本地地址
+    fn connect(addr: String) -> impl Future<Output = Result<(Self::Reader, Self::Writer, SocketAddr)>> + Send;
+
+    /// 读取数据，返回0表示连接已关闭
+    fn read(reader: &mut Self::Reader, buf: &mut [u8]) -> impl Future<Output = Result<usize>> + Send;
+
+    fn write_all(writer: &mut Self::Writer, buf: &[u8]) -> impl Future<Output = Result<()>> + Send;
+
+    fn sleep(duration: Duration) -> impl Future<Output = ()> + Send;
+
+    fn handle() -> Self::Handle;
+
+    /// 在阻塞线程池的线程中阻塞地执行`future`
+    fn block_on<F: Future>(handle: &Self::Handle, future: F) -> F::Output;
+
+    /// 在阻塞线程池中执行`f`，`f`中panic时返回错误
+    ///
+    /// `f`须在调用时即开始执行，而不是等到返回的`Future`被poll时，`AsyncListener`在等待其结束之前需要先接收其产生的事件
+    fn spawn_blocking<F, T>(f: F) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static;
+
+    fn channel(capacity: usize) -> (Self::Sender, Self::Receiver);
+
+    /// 在阻塞线程池的线程中阻塞地发送事件，接收端已被drop时返回false
+    fn send_blocking(handle: &Self::Handle, sender: &Self::Sender, event: OwnedEvent) -> bool;
+
+    /// 接收事件，发送端已被drop并且队列为空时返回None
+    fn recv(receiver: &mut Self::Receiver) -> impl Future<Output = Option<OwnedEvent>> + Send;
+}
+
+/// 基于`Runtime`的异步监听器，见[模块文档](index.html)
+pub struct AsyncListener<R: Runtime, H: AsyncEventHandler> {
+    pub config: Config,
+    handler: H,
+    running: Arc<AtomicBool>,
+    repl_offset: Arc<AtomicI64>,
+    state: Arc<State>,
+    is_resuming: bool,
+    aof_sequence: u64,
+    module_parser: Arc<ModuleParserSlot>,
+    command_parsers: CommandParsers,
+    runtime: PhantomData<fn() -> R>,
+}
+
+impl<R: Runtime, H: AsyncEventHandler> AsyncListener<R, H> {
+    pub fn new(config: Config, handler: H) -> AsyncListener<R, H> {
+        let repl_offset = Arc::new(AtomicI64::from(config.repl_offset));
+        AsyncListener {
+            config,
+            handler,
+            running: Arc::new(AtomicBool::new(true)),
+            repl_offset,
+            state: Arc::new(State::default()),
+            is_resuming: false,
+            aof_sequence: 0,
+            module_parser: Arc::new(ModuleParserSlot(Mutex::new(None))),
+            command_parsers: CommandParsers::new(),
+            runtime: PhantomData,
+        }
+    }
+
+    /// 设置控制变量，将其置为false之后，监听将在处理完当前的命令之后结束
+    pub fn with_control_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.running = flag;
+    }
+
+    /// 设置解析RDB中Module类型的值所用的解析器，与`Builder::with_module_parser`相同
+    pub fn with_module_parser(&mut self, parser: Box<dyn ModuleParser + Send>) {
+        *self.module_parser.0.lock().unwrap() = Some(parser);
+    }
+
+    /// 注册命令`name`的自定义解析器，与`Builder::with_command_parser`相同
+    pub fn with_command_parser(&mut self, name: &str, parser: Arc<Mutex<dyn CommandParser + Send>>) {
+        self.command_parsers.register(name, parser);
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    /// 获取一个可在其他线程中停止监听的句柄，见[模块文档](index.html)
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle {
+            running: Arc::clone(&self.running),
+            socket: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 获取一个可在其他线程中查询健康状态以及统计信息的句柄
+    pub fn health_check(&self) -> HealthCheck {
+        HealthCheck {
+            state: Arc::clone(&self.state),
+            repl_offset: Arc::clone(&self.repl_offset),
+        }
+    }
+
+    /// 连接Redis并开始监听，直到出错或者被停止；进入增量同步之后再次调用时将从当前的offset继续同步
+    pub async fn start(&mut self) -> Result<()> {
+        let result = self.run().await;
+        self.state.connected.store(false, Ordering::SeqCst);
+        self.state.aof_started_at.store(0, Ordering::SeqCst);
+        info!("Disconnected from server {}:{}", &self.config.host, self.config.port);
+        match result {
+            // 通过stop中断，属于正常退出
+            Err(_) if !self.running.load(Ordering::Relaxed) => Ok(()),
+            result => result,
+        }
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        if self.config.is_tls_enabled {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "TLS is not supported by the async listener",
+            ));
+        }
+        let addr = format!("{}:{}", &self.config.host, self.config.port);
+        let (reader, mut writer, local_addr) = R::connect(addr.clone()).await?;
+        let mut input = Input::<R>::new(reader);
+        info!("Connected to server {}", &addr);
+        self.state.connected.store(true, Ordering::SeqCst);
+        if self.state.connections.fetch_add(1, Ordering::Relaxed) > 0 {
+            self.state.reconnects.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.handshake(&mut input, &mut writer, local_addr).await?;
+        let length = loop {
+            match self.psync(&mut input, &mut writer).await? {
+                NextStep::FullSync(length) => break Some(length),
+                NextStep::PartialResync => break None,
+                NextStep::Wait if self.running.load(Ordering::Relaxed) => R::sleep(Duration::from_secs(5)).await,
+                NextStep::Wait => return Ok(()),
+            }
+        };
+        match length {
+            Some(length) => input = self.receive_rdb(input, length).await?,
+            None => info!("PSYNC进度恢复"),
+        }
+        if !self.config.is_aof {
+            return Ok(());
+        }
+        // 已进入增量同步阶段，之后再次调用start时将从当前offset继续同步
+        self.is_resuming = true;
+        let mut result = self.receive_commands(&mut input, &mut writer).await;
+        if self.config.is_ack_after_handle {
+            // 退出前尝试确认已处理的事件，未能确认的部分在下次同步时将被重新接收
+            match self.handler.flush().await {
+                Ok(_) => self.repl_offset.store(self.config.repl_offset, Ordering::SeqCst),
+                Err(error) => {
+                    if result.is_ok() {
+                        result = Err(error);
+                    }
+                }
+            }
+            self.config.repl_offset = self.repl_offset.load(Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// 认证，并发送replica相关信息到redis
+    async fn handshake(&mut self, input: &mut Input<R>, writer: &mut R::Writer, local_addr: SocketAddr) -> Result<()> {
+        if !self.config.password.is_empty() {
+            let mut args = vec![b"AUTH".to_vec()];
+            if !self.config.username.is_empty() {
+                args.push(self.config.username.as_bytes().to_vec());
+            }
+            args.push(self.config.password.as_bytes().to_vec());
+            R::write_all(writer, &encode_command(&args)).await?;
+            reply(input).await?;
+        }
+        let port = local_addr.port().to_string();
+        let ip = local_addr.ip().to_string();
+        let commands: [&[&[u8]]; 5] = [
+            &[b"PING"],
+            &[b"REPLCONF", b"listening-port", port.as_bytes()],
+            &[b"REPLCONF", b"ip-address", ip.as_bytes()],
+            &[b"REPLCONF", b"capa", b"eof"],
+            &[b"REPLCONF", b"capa", b"psync2"],
+        ];
+        for command in commands.iter() {
+            let args: Vec<Vec<u8>> = command.iter().map(|arg| arg.to_vec()).collect();
+            info!("{}", String::from_utf8_lossy(&args.join(&b' ')));
+            R::write_all(writer, &encode_command(&args)).await?;
+            reply(input).await?;
+        }
+        Ok(())
+    }
+
+    async fn psync(&mut self, input: &mut Input<R>, writer: &mut R::Writer) -> Result<NextStep> {
+        // 重连时所请求的是下一个未处理的字节
+        let offset = if self.is_resuming {
+            self.config.repl_offset + 1
+        } else {
+            self.config.repl_offset
+        };
+        let args = vec![
+            b"PSYNC".to_vec(),
+            self.config.repl_id.as_bytes().to_vec(),
+            offset.to_string().into_bytes(),
+        ];
+        R::write_all(writer, &encode_command(&args)).await?;
+        let (line, _) = input.read_line().await?;
+        let resp = String::from_utf8_lossy(&line).into_owned();
+        info!("{}", resp);
+        let mut iter = resp.split_whitespace();
+        match iter.next() {
+            Some("+FULLRESYNC") => {
+                match (iter.next(), iter.next().and_then(|offset| offset.parse().ok())) {
+                    (Some(repl_id), Some(repl_offset)) => {
+                        self.config.repl_id = repl_id.to_owned();
+                        self.config.repl_offset = repl_offset;
+                    }
+                    _ => return Err(Error::new(ErrorKind::InvalidData, resp)),
+                }
+                info!("等待Redis dump完成...");
+                let (line, _) = input.read_line().await?;
+                if line.starts_with(b"$EOF:") {
+                    Ok(NextStep::FullSync(-1))
+                } else {
+                    Ok(NextStep::FullSync(parse_header(&line, b'$')? as i64))
+                }
+            }
+            Some("+CONTINUE") => {
+                if let Some(repl_id) = iter.next() {
+                    self.config.repl_id = repl_id.to_owned();
+                }
+                Ok(NextStep::PartialResync)
+            }
+            Some("+NOMASTERLINK") | Some("+LOADING") => Ok(NextStep::Wait),
+            _ => Err(Error::new(ErrorKind::InvalidData, resp)),
+        }
+    }
+
+    /// 在阻塞线程池中解析RDB，同时在当前任务中处理解析出的事件，返回放回了RDB之后多读取的数据的输入流
+    async fn receive_rdb(&mut self, input: Input<R>, length: i64) -> Result<Input<R>> {
+        if length != -1 {
+            info!("Full Sync, size: {}bytes", length);
+        } else {
+            info!("Disk-less replication.");
+        }
+        let (sender, mut receiver) = R::channel(EVENT_QUEUE_SIZE);
+        let handle = R::handle();
+        let mut input = BlockingReader {
+            input,
+            handle: handle.clone(),
+            state: Arc::clone(&self.state),
+        };
+        let config = self.config.clone();
+        let running = Arc::clone(&self.running);
+        let state = Arc::clone(&self.state);
+        let module_parser = Arc::clone(&self.module_parser);
+        let parser = R::spawn_blocking(move || {
+            let received = state.bytes_received.load(Ordering::Relaxed);
+            let mut reader = BufReader::new(&mut input);
+            let mut version = None;
+            if length != -1 && config.is_discard_rdb {
+                info!("跳过RDB不进行处理");
+                io::skip(&mut reader, length as isize)?;
+            } else {
+                let mut forwarder = Forwarder::<R> { sender, handle };
+                let mut handler = TrackingHandler {
+                    inner: &mut forwarder,
+                    state: &state,
+                };
+                let mut parser =
+                    DefaultRDBParser::from_config(&config, running, module_parser.0.lock().unwrap().take());
+                let parse_info = parser.parse(&mut reader, length, &mut handler);
+                // 无论解析是否成功都交还Module解析器，再次调用start时继续使用
+                *module_parser.0.lock().unwrap() = parser.module_parser.take();
+                let parse_info = parse_info?;
+                info!("RDB版本: {}, 校验和: {:?}", parse_info.rdb_version, parse_info.checksum);
+                version = parse_info.aux_fields.get("redis-ver").cloned();
+            }
+            let rdb_size = if length == -1 {
+                state.bytes_received.load(Ordering::Relaxed) - received - reader.buffer().len() as u64
+            } else {
+                length as u64
+            };
+            state.rdb_size.store(rdb_size, Ordering::Relaxed);
+            if length == -1 {
+                io::skip(&mut reader, 40)?;
+            }
+            let remaining = reader.buffer().to_vec();
+            drop(reader);
+            input.input.unread(remaining);
+            Ok::<_, Error>((input.input, version))
+        });
+        // 解析线程结束之后队列的发送端被drop，循环随之结束
+        while let Some(event) = R::recv(&mut receiver).await {
+            self.handler.handle(event).await;
+        }
+        let (input, version) = parser.await??;
+        if let Some(version) = version {
+            self.state
+                .detect_script_replication(ScriptReplication::of_redis_version(&version));
+        }
+        Ok(input)
+    }
+
+    async fn receive_commands(&mut self, input: &mut Input<R>, writer: &mut R::Writer) -> Result<()> {
+        self.state.mark_aof_start(self.config.repl_offset);
+        // RDB中的数据同样需要在handler确认之后，才能确认FULLRESYNC返回的offset
+        self.ack().await?;
+        let mut acked_at = Instant::now();
+        let mut heartbeat_at = Instant::now();
+        let one_sec = Duration::from_secs(1);
+        while self.running.load(Ordering::Relaxed) {
+            let (command, size) = input.read_command().await?;
+            self.state.last_byte_at.store(now_millis(), Ordering::Relaxed);
+            self.state.bytes_received.fetch_add(size as u64, Ordering::Relaxed);
+            self.state.touch_ping(&command);
+            self.state
+                .detect_script_replication(ScriptReplication::of_command(&command));
+            self.state.commands.fetch_add(1, Ordering::Relaxed);
+            self.aof_sequence += 1;
+            let position = AofPosition {
+                offset: self.config.repl_offset + size,
+                sequence: self.aof_sequence,
+                timestamp: None,
+            };
+            for event in parse_command(&command, &self.command_parsers, &self.state) {
+                self.handler.handle_aof(event, position).await;
+            }
+            self.config.repl_offset += size;
+            // ack-after-handle模式下每秒最多flush一次
+            if !self.config.is_ack_after_handle || acked_at.elapsed() >= one_sec {
+                self.ack().await?;
+                acked_at = Instant::now();
+            }
+            if heartbeat_at.elapsed() >= one_sec {
+                let offset = self.repl_offset.load(Ordering::SeqCst).to_string();
+                let args = [b"REPLCONF".to_vec(), b"ACK".to_vec(), offset.into_bytes()];
+                R::write_all(writer, &encode_command(&args)).await?;
+                heartbeat_at = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    /// 推进发送给master的offset，ack-after-handle模式下只有在handler的flush成功之后才会推进
+    async fn ack(&mut self) -> Result<()> {
+        if self.config.is_ack_after_handle {
+            self.handler.flush().await?;
+        }
+        self.repl_offset.store(self.config.repl_offset, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+enum NextStep {
+    FullSync(i64),
+    PartialResync,
+    Wait,
+}
+
+/// 以`Stream`的形式逐个拉取事件，作为`AsyncEventHandler`之外的另一种消费方式，可以与`StreamExt`的各种组合子一起使用
+///
+/// 监听在`Stream`被poll时推进，出错时`Stream`将产生一个`Err`，之后结束；`EventStream`被drop时监听即停止。
+/// 事件在下一次poll时才被视为已处理完毕，开启`Config::is_ack_after_handle`时只会确认已被拉取的事件
+///
+/// ```no_run
+/// use std::future::poll_fn;
+/// use std::pin::Pin;
+///
+/// use futures_core::Stream;
+/// use redis_event::config::Config;
+/// use redis_event::listener::runtime::{self, Runtime};
+/// use redis_event::OwnedEvent;
+///
+/// async fn consume<R: Runtime>(config: Config) -> std::io::Result<()> {
+///     let mut events = runtime::events::<R>(config);
+///     while let Some(event) = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+///         if let OwnedEvent::AOF(cmd) = event? {
+///             println!("{}", cmd.name());
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn events<R: Runtime>(config: Config) -> EventStream {
+    let slot = Arc::new(Mutex::new(None));
+    let handler = StreamHandler {
+        slot: Arc::clone(&slot),
+    };
+    let mut listener = AsyncListener::<R, _>::new(config, handler);
+    let stop_handle = listener.stop_handle();
+    let health_check = listener.health_check();
+    EventStream {
+        listener: Some(Box::pin(async move { listener.start().await })),
+        slot,
+        stop_handle,
+        health_check,
+    }
+}
+
+/// `events`返回的事件流
+pub struct EventStream {
+    listener: Option<Pin<Box<dyn Future<Output = Result<()>> + Send>>>,
+    slot: Arc<Mutex<Option<OwnedEvent>>>,
+    stop_handle: StopHandle,
+    health_check: HealthCheck,
+}
+
+impl EventStream {
+    /// 获取一个可在其他线程中停止监听的句柄，停止之后`Stream`在当前的命令被拉取之后结束
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle.clone()
+    }
+
+    /// 获取一个可在其他线程中查询健康状态以及统计信息的句柄
+    pub fn health_check(&self) -> HealthCheck {
+        self.health_check.clone()
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<OwnedEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<OwnedEvent>>> {
+        let listener = match self.listener.as_mut() {
+            Some(listener) => listener,
+            None => return Poll::Ready(None),
+        };
+        let poll = listener.as_mut().poll(cx);
+        // 监听在交出事件时暂停，直到下一次poll
+        if let Some(event) = self.slot.lock().unwrap().take() {
+            return Poll::Ready(Some(Ok(event)));
+        }
+        match poll {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => {
+                self.listener = None;
+                Poll::Ready(result.err().map(Err))
+            }
+        }
+    }
+}
+
+// 将事件放入`slot`，由`EventStream`取走之后才算处理完毕
+struct StreamHandler {
+    slot: Arc<Mutex<Option<OwnedEvent>>>,
+}
+
+impl AsyncEventHandler for StreamHandler {
+    fn handle(&mut self, event: OwnedEvent) -> impl Future<Output = ()> + Send {
+        Handoff {
+            slot: &self.slot,
+            event: Some(event),
+        }
+    }
+}
+
+// 只会在`EventStream::poll_next`中被poll，`slot`中的事件被取走之后的下一次poll即返回，因此无需注册waker
+struct Handoff<'a> {
+    slot: &'a Mutex<Option<OwnedEvent>>,
+    event: Option<OwnedEvent>,
+}
+
+impl Future for Handoff<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if let Some(event) = self.event.take() {
+            *self.slot.lock().unwrap() = Some(event);
+            return Poll::Pending;
+        }
+        if self.slot.lock().unwrap().is_some() {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
+
+// 带缓冲区的输入流，全量同步时RDB之后多读取的数据将被放回缓冲区
+struct Input<R: Runtime> {
+    reader: R::Reader,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Runtime> Input<R> {
+    fn new(reader: R::Reader) -> Input<R> {
+        Input {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    // 缓冲区为空时从socket读取，连接已关闭时返回错误
+    async fn fill_buf(&mut self) -> Result<&[u8]> {
+        if self.pos >= self.buf.len() {
+            self.buf.resize(READ_BUFFER_SIZE, 0);
+            let len = R::read(&mut self.reader, &mut self.buf).await?;
+            self.buf.truncate(len);
+            self.pos = 0;
+            if len == 0 {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "connection closed by master"));
+            }
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    // 优先返回缓冲区中的数据，缓冲区为空时直接读取到`buf`中
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pos >= self.buf.len() {
+            return R::read(&mut self.reader, buf).await;
+        }
+        let len = buf.len().min(self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let available = self.fill_buf().await?;
+            let len = available.len().min(buf.len() - filled);
+            buf[filled..filled + len].copy_from_slice(&available[..len]);
+            self.pos += len;
+            filled += len;
+        }
+        Ok(())
+    }
+
+    /// 读取一行，去掉末尾的CRLF，返回其内容以及所占用的字节数；master在准备RDB期间发送的空行将被跳过
+    async fn read_line(&mut self) -> Result<(Vec<u8>, usize)> {
+        let mut line = Vec::new();
+        let mut size = 0;
+        loop {
+            let available = self.fill_buf().await?;
+            let (len, is_end) = match available.iter().position(|&b| b == b'\n') {
+                Some(index) => (index + 1, true),
+                None => (available.len(), false),
+            };
+            line.extend_from_slice(&available[..len]);
+            self.pos += len;
+            size += len;
+            if !is_end {
+                continue;
+            }
+            while line.last() == Some(&b'\n') || line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if !line.is_empty() {
+                return Ok((line, size));
+            }
+        }
+    }
+
+    /// 从复制流中读取一条命令，返回命令的各个参数及其所占用的字节数
+    async fn read_command(&mut self) -> Result<(Vec<Vec<u8>>, i64)> {
+        let (line, mut size) = self.read_line().await?;
+        let len = parse_header(&line, b'*')?;
+        let mut command = Vec::with_capacity(len);
+        for _ in 0..len {
+            let (line, header_size) = self.read_line().await?;
+            let len = parse_header(&line, b'$')?;
+            let mut arg = vec![0; len + 2];
+            self.read_exact(&mut arg).await?;
+            size += header_size + arg.len();
+            arg.truncate(len);
+            command.push(arg);
+        }
+        Ok((command, size as i64))
+    }
+
+    // 将`data`放回缓冲区的开头，之后的读取将先返回这些数据
+    fn unread(&mut self, mut data: Vec<u8>) {
+        data.extend_from_slice(&self.buf[self.pos..]);
+        self.buf = data;
+        self.pos = 0;
+    }
+}
+
+// 在阻塞线程池的线程中，以同步的方式从异步的socket读取数据，供同步的RDB解析器使用
+struct BlockingReader<R: Runtime> {
+    input: Input<R>,
+    handle: R::Handle,
+    state: Arc<State>,
+}
+
+impl<R: Runtime> Read for BlockingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = R::block_on(&self.handle, self.input.read(buf))?;
+        if len > 0 {
+            self.state.last_byte_at.store(now_millis(), Ordering::Relaxed);
+            self.state.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+        }
+        Ok(len)
+    }
+}
+
+// 将解析线程中产生的事件交回调用`start`的任务，该任务已结束(`Future`被drop)时丢弃之后的事件
+struct Forwarder<R: Runtime> {
+    sender: R::Sender,
+    handle: R::Handle,
+}
+
+impl<R: Runtime> EventHandler for Forwarder<R> {
+    fn handle(&mut self, event: Event) {
+        R::send_blocking(&self.handle, &self.sender, event.to_owned());
+    }
+}
+
+// 收集一条命令解析出的事件
+struct Collector {
+    events: Vec<OwnedEvent>,
+}
+
+impl EventHandler for Collector {
+    fn handle(&mut self, event: Event) {
+        self.events.push(event.to_owned());
+    }
+}
+
+// 由解析RDB的线程借出并归还的Module解析器
+//
+// 直接在传给`spawn_blocking`的闭包中持有`dyn ModuleParser`时，编译器无法证明`start`返回的`Future`是`Send`的，
+// 因此包装为具体的类型
+struct ModuleParserSlot(Mutex<Option<Box<dyn ModuleParser + Send>>>);
+
+// 解析命令并转换为`OwnedEvent`，解析出的`Event`不是`Send`的，不能跨越`.await`持有
+fn parse_command(command: &[Vec<u8>], parsers: &CommandParsers, state: &State) -> Vec<OwnedEvent> {
+    let mut collector = Collector { events: Vec::new() };
+    let mut handler = TrackingHandler {
+        inner: &mut collector,
+        state,
+    };
+    cmd::parse(command, parsers, &mut handler);
+    collector.events
+}
+
+/// 读取握手阶段的响应，与同步的监听器一样，只有认证相关的错误才会中断握手
+async fn reply<R: Runtime>(input: &mut Input<R>) -> Result<()> {
+    let (line, _) = input.read_line().await?;
+    let resp = String::from_utf8_lossy(&line).into_owned();
+    match line.first() {
+        Some(b'-') => {
+            warn!("{}", &resp[1..]);
+            if (resp.contains("NOAUTH") || resp.contains("NOPERM"))
+                && !resp.contains("no password")
+                && !resp.contains("Unrecognized REPLCONF option")
+            {
+                return Err(Error::new(ErrorKind::InvalidData, resp[1..].to_string()));
+            }
+        }
+        Some(b'+') => info!("{}", &resp[1..]),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unexpected response: {}", resp),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// 解析`*<len>`、`$<len>`形式的行
+fn parse_header(line: &[u8], prefix: u8) -> Result<usize> {
+    let len = match line.split_first() {
+        Some((first, len)) if *first == prefix => std::str::from_utf8(len).ok().and_then(|len| len.parse().ok()),
+        _ => None,
+    };
+    len.ok_or_else(|| {
+        let message = format!(
+            "Expected {}<len>, got: {}",
+            prefix as char,
+            String::from_utf8_lossy(line)
+        );
+        Error::new(ErrorKind::InvalidData, message)
+    })
+}
@@ -0,0 +1,102 @@
+/*!
+基于tokio的异步监听器，见[`runtime`]模块
+
+```no_run
+use redis_event::config::Config;
+use redis_event::listener::tokio::Listener;
+use redis_event::{AsyncEventHandler, OwnedEvent};
+
+struct Printer {}
+
+impl AsyncEventHandler for Printer {
+    async fn handle(&mut self, event: OwnedEvent) {
+        if let OwnedEvent::AOF(cmd) = event {
+            println!("{}", cmd.name());
+        }
+    }
+}
+
+async fn run(config: Config) -> std::io::Result<()> {
+    let mut listener = Listener::new(config, Printer {});
+    listener.start().await
+}
+```
+
+[`runtime`]: ../runtime/index.html
+*/
+use std::future::Future;
+use std::io::{Error, Result};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use ::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use ::tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use ::tokio::net::TcpStream;
+use ::tokio::runtime::Handle;
+use ::tokio::sync::mpsc;
+use ::tokio::{task, time};
+
+use super::runtime::{AsyncListener, Runtime};
+use crate::OwnedEvent;
+
+/// 基于tokio的异步监听器
+pub type Listener<H> = AsyncListener<Tokio, H>;
+
+/// tokio运行时，RDB在`spawn_blocking`的线程中解析，其中通过`Handle::block_on`读取socket
+pub struct Tokio;
+
+impl Runtime for Tokio {
+    type Reader = OwnedReadHalf;
+    type Writer = OwnedWriteHalf;
+    type Handle = Handle;
+    type Sender = mpsc::Sender<OwnedEvent>;
+    type Receiver = mpsc::Receiver<OwnedEvent>;
+
+    async fn connect(addr: String) -> Result<(OwnedReadHalf, OwnedWriteHalf, SocketAddr)> {
+        let stream = TcpStream::connect(addr).await?;
+        let local_addr = stream.local_addr()?;
+        let (reader, writer) = stream.into_split();
+        Ok((reader, writer, local_addr))
+    }
+
+    async fn read(reader: &mut OwnedReadHalf, buf: &mut [u8]) -> Result<usize> {
+        reader.read(buf).await
+    }
+
+    async fn write_all(writer: &mut OwnedWriteHalf, buf: &[u8]) -> Result<()> {
+        writer.write_all(buf).await
+    }
+
+    async fn sleep(duration: Duration) {
+        time::sleep(duration).await
+    }
+
+    fn handle() -> Handle {
+        Handle::current()
+    }
+
+    fn block_on<F: Future>(handle: &Handle, future: F) -> F::Output {
+        handle.block_on(future)
+    }
+
+    fn spawn_blocking<F, T>(f: F) -> impl Future<Output = Result<T>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = task::spawn_blocking(f);
+        async move { task.await.map_err(Error::other) }
+    }
+
+    fn channel(capacity: usize) -> (Self::Sender, Self::Receiver) {
+        mpsc::channel(capacity)
+    }
+
+    fn send_blocking(_: &Handle, sender: &Self::Sender, event: OwnedEvent) -> bool {
+        sender.blocking_send(event).is_ok()
+    }
+
+    async fn recv(receiver: &mut Self::Receiver) -> Option<OwnedEvent> {
+        receiver.recv().await
+    }
+}
@@ -0,0 +1,169 @@
+/*!
+[listpack](https://github.com/redis/redis/blob/unstable/src/listpack.c)的解析
+
+listpack是Redis 7.0之后Hash、SortedSet、List(quicklist的节点)以及Stream所使用的紧凑编码，
+实现自定义的[`ModuleParser`]，或者解析`DUMP`命令返回的数据时，可以使用此模块读取其中的元素。
+
+```
+use redis_event::listpack::ListPack;
+
+// 包含"a"和1两个元素的listpack
+let data = [12, 0, 0, 0, 2, 0, 0x81, b'a', 2, 0x01, 1, 0xFF];
+let list_pack = ListPack::new(&data).unwrap();
+assert_eq!(Some(2), list_pack.num_elements());
+let entries: Vec<Vec<u8>> = list_pack.map(|entry| entry.unwrap()).collect();
+assert_eq!(vec![b"a".to_vec(), b"1".to_vec()], entries);
+```
+
+[`ModuleParser`]: ../trait.ModuleParser.html
+*/
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::rdb::LP_EOF;
+
+// LP_TOTAL_BYTES和LP_NUM_ELEMENTS所占的字节数
+const LP_HEADER_SIZE: usize = 6;
+// 元素个数超过65535时，LP_NUM_ELEMENTS为此值，只能遍历整个listpack才能得知元素个数
+const LP_NUM_ELEMENTS_UNKNOWN: u16 = u16::MAX;
+
+/// listpack中元素的迭代器
+///
+/// 整数类型的元素将被转换为其字符串形式，与Redis中读取到的值一致
+pub struct ListPack<'a> {
+    cursor: Cursor<&'a [u8]>,
+    num_elements: u16,
+    is_done: bool,
+}
+
+impl<'a> ListPack<'a> {
+    /// 校验listpack的头部，返回其元素的迭代器
+    pub fn new(data: &'a [u8]) -> Result<ListPack<'a>> {
+        let mut cursor = Cursor::new(data);
+        let total_bytes = cursor.read_u32::<LittleEndian>()? as usize;
+        let num_elements = cursor.read_u16::<LittleEndian>()?;
+        if total_bytes < LP_HEADER_SIZE + 1 || total_bytes > data.len() {
+            let message = format!("listpack的长度错误: {}, 实际长度: {}", total_bytes, data.len());
+            return Err(Error::new(ErrorKind::InvalidData, message));
+        }
+        let mut cursor = Cursor::new(&data[..total_bytes]);
+        cursor.set_position(LP_HEADER_SIZE as u64);
+        Ok(ListPack {
+            cursor,
+            num_elements,
+            is_done: false,
+        })
+    }
+
+    /// listpack头部记录的元素个数，元素个数不少于65535时为None
+    pub fn num_elements(&self) -> Option<usize> {
+        if self.num_elements == LP_NUM_ELEMENTS_UNKNOWN {
+            None
+        } else {
+            Some(self.num_elements as usize)
+        }
+    }
+}
+
+impl Iterator for ListPack<'_> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        let pos = self.cursor.position() as usize;
+        match self.cursor.get_ref().get(pos) {
+            Some(&LP_EOF) => {
+                self.is_done = true;
+                None
+            }
+            Some(_) => {
+                let entry = read_entry(&mut self.cursor);
+                // 出错之后无法再确定下一个元素的位置
+                self.is_done = entry.is_err();
+                Some(entry)
+            }
+            None => {
+                self.is_done = true;
+                Some(Err(Error::new(ErrorKind::UnexpectedEof, "listpack缺少结束标志")))
+            }
+        }
+    }
+}
+
+/// 从流中读取listpack中的一个元素，包括其末尾的backlen
+pub fn read_entry(input: &mut dyn Read) -> Result<Vec<u8>> {
+    let special = input.read_u8()? as i32;
+    let skip: i32;
+    let mut bytes;
+    if (special & 0x80) == 0 {
+        skip = 1;
+        let value = special & 0x7F;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xC0) == 0x80 {
+        let len = special & 0x3F;
+        skip = 1 + len as i32;
+        bytes = vec![0; len as usize];
+        input.read_exact(&mut bytes)?;
+    } else if (special & 0xE0) == 0xC0 {
+        skip = 2;
+        let next = input.read_u8()?;
+        let value = (((special & 0x1F) << 8) | next as i32) << 19 >> 19;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xFF) == 0xF1 {
+        skip = 3;
+        let value = input.read_i16::<LittleEndian>()?;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xFF) == 0xF2 {
+        skip = 4;
+        let value = input.read_i24::<LittleEndian>()?;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xFF) == 0xF3 {
+        skip = 5;
+        let value = input.read_i32::<LittleEndian>()?;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xFF) == 0xF4 {
+        skip = 9;
+        let value = input.read_i64::<LittleEndian>()?;
+        let value = value.to_string();
+        bytes = value.into_bytes();
+    } else if (special & 0xF0) == 0xE0 {
+        let next = input.read_u8()?;
+        let len = ((special & 0x0F) << 8) | next as i32;
+        skip = 2 + len as i32;
+        bytes = vec![0; len as usize];
+        input.read_exact(&mut bytes)?;
+    } else if (special & 0xFF) == 0xF0 {
+        let len = input.read_u32::<LittleEndian>()?;
+        skip = 5 + len as i32;
+        bytes = vec![0; len as usize];
+        input.read_exact(&mut bytes)?;
+    } else {
+        let message = format!("无效的listpack元素编码: {:#04x}", special);
+        return Err(Error::new(ErrorKind::InvalidData, message));
+    }
+    if skip <= 127 {
+        let mut buf = vec![0; 1];
+        input.read_exact(&mut buf)?;
+    } else if skip < 16383 {
+        let mut buf = vec![0; 2];
+        input.read_exact(&mut buf)?;
+    } else if skip < 2097151 {
+        let mut buf = vec![0; 3];
+        input.read_exact(&mut buf)?;
+    } else if skip < 268435455 {
+        let mut buf = vec![0; 4];
+        input.read_exact(&mut buf)?;
+    } else {
+        let mut buf = vec![0; 5];
+        input.read_exact(&mut buf)?;
+    }
+    Ok(bytes)
+}
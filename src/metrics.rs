@@ -0,0 +1,135 @@
+/*!
+以Prometheus指标的形式暴露监听器的健康状态以及统计信息，需要开启`metrics-prometheus` feature
+
+# 示例
+
+```ignore
+let registry = prometheus::Registry::new();
+let collector = ListenerCollector::new(redis_listener.health_check(), "redis-1")?;
+registry.register(Box::new(collector))?;
+```
+*/
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{Gauge, IntCounter, IntCounterVec, IntGauge, Opts};
+
+use crate::listener::HealthCheck;
+
+const NAMESPACE: &str = "redis_event";
+
+/// 在每次抓取时从[`HealthCheck`]读取最新的数据，所有指标均带有`source`标签，用于区分不同的Redis实例
+///
+/// 事件数量等以counter的形式暴露，每秒的事件数可在Prometheus中通过`rate()`计算
+///
+/// [`HealthCheck`]: ../listener/struct.HealthCheck.html
+pub struct ListenerCollector {
+    health_check: HealthCheck,
+    connected: IntGauge,
+    offset: IntGauge,
+    lag_bytes: IntGauge,
+    lag_seconds: Gauge,
+    rdb_bytes: IntGauge,
+    bytes_received: IntCounter,
+    commands: IntCounter,
+    reconnects: IntCounter,
+    events: IntCounterVec,
+}
+
+impl ListenerCollector {
+    /// 方法参数:
+    ///
+    /// * `health_check`: 通过`Listener::health_check`获取
+    /// * `source`: `source`标签的值
+    pub fn new(health_check: HealthCheck, source: &str) -> prometheus::Result<ListenerCollector> {
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE).const_label("source", source);
+        Ok(ListenerCollector {
+            health_check,
+            connected: IntGauge::with_opts(opts("connected", "是否已连接到Redis"))?,
+            offset: IntGauge::with_opts(opts("repl_offset", "当前的replication offset"))?,
+            lag_bytes: IntGauge::with_opts(opts("lag_bytes", "复制延迟的字节数"))?,
+            lag_seconds: Gauge::with_opts(opts("lag_seconds", "根据处理速度估算的复制延迟秒数"))?,
+            rdb_bytes: IntGauge::with_opts(opts("rdb_bytes", "最近一次全量同步的RDB大小"))?,
+            bytes_received: IntCounter::with_opts(opts("received_bytes_total", "从Redis接收到的总字节数"))?,
+            commands: IntCounter::with_opts(opts("commands_parsed_total", "已解析的命令数量"))?,
+            reconnects: IntCounter::with_opts(opts("reconnects_total", "重连的次数"))?,
+            events: IntCounterVec::new(opts("events_total", "按类型统计的事件数量"), &["type"])?,
+        })
+    }
+
+    /// 将统计信息同步到各个指标中
+    fn update(&self) {
+        let health = self.health_check.health();
+        self.connected.set(health.connected as i64);
+        self.offset.set(health.offset);
+
+        match self.health_check.lag() {
+            Some(lag) => {
+                self.lag_bytes.set(lag.bytes);
+                self.lag_seconds.set(lag.seconds);
+            }
+            None => {
+                self.lag_bytes.set(0);
+                self.lag_seconds.set(0.0);
+            }
+        }
+
+        let stats = self.health_check.stats();
+        self.rdb_bytes.set(stats.rdb_size as i64);
+        advance(&self.bytes_received, stats.bytes_received);
+        advance(&self.commands, stats.commands_parsed);
+        advance(&self.reconnects, stats.reconnects);
+
+        let events = &stats.events;
+        for (event_type, count) in &[
+            ("string", events.strings),
+            ("list", events.lists),
+            ("set", events.sets),
+            ("sorted_set", events.sorted_sets),
+            ("hash", events.hashes),
+            ("module", events.modules),
+            ("stream", events.streams),
+            ("command", events.commands),
+        ] {
+            advance(&self.events.with_label_values(&[event_type]), *count);
+        }
+    }
+}
+
+impl Collector for ListenerCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        let mut descs = Vec::new();
+        descs.extend(self.connected.desc());
+        descs.extend(self.offset.desc());
+        descs.extend(self.lag_bytes.desc());
+        descs.extend(self.lag_seconds.desc());
+        descs.extend(self.rdb_bytes.desc());
+        descs.extend(self.bytes_received.desc());
+        descs.extend(self.commands.desc());
+        descs.extend(self.reconnects.desc());
+        descs.extend(self.events.desc());
+        descs
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.update();
+        let mut families = Vec::new();
+        families.extend(self.connected.collect());
+        families.extend(self.offset.collect());
+        families.extend(self.lag_bytes.collect());
+        families.extend(self.lag_seconds.collect());
+        families.extend(self.rdb_bytes.collect());
+        families.extend(self.bytes_received.collect());
+        families.extend(self.commands.collect());
+        families.extend(self.reconnects.collect());
+        families.extend(self.events.collect());
+        families
+    }
+}
+
+/// counter只能递增，将其推进到统计信息中的值
+fn advance(counter: &IntCounter, value: u64) {
+    let current = counter.get();
+    if value > current {
+        counter.inc_by(value - current);
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+常见Redis Module的解析器，以及实现`ModuleParser`时读取MODULE_2类型数据的辅助函数
+
+MODULE_2类型的值中，每个字段之前都带有一个opcode，用来标识字段的类型，此模块中的`load_xxx`函数会先校验opcode，再读取对应的字段。
+
+各个Module的解析器需要开启对应的feature:
+- `module-timeseries`: [RedisTimeSeries](https://github.com/RedisTimeSeries/RedisTimeSeries)
+- `module-search`: [RediSearch](https://github.com/RediSearch/RediSearch)，仅解析索引定义
+*/
+use std::io::{Error, ErrorKind, Read, Result};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::rdb::{
+    RDBDecode, RDB_MODULE_OPCODE_DOUBLE, RDB_MODULE_OPCODE_FLOAT, RDB_MODULE_OPCODE_SINT, RDB_MODULE_OPCODE_STRING,
+    RDB_MODULE_OPCODE_UINT,
+};
+
+#[cfg(feature = "module-search")]
+pub mod search;
+#[cfg(feature = "module-timeseries")]
+pub mod timeseries;
+
+/// MODULE_2类型数据中的单个字段
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModuleValue {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f32),
+    Double(f64),
+    String(Vec<u8>),
+}
+
+/// 读取一个字段，字段的类型由opcode决定
+pub fn load_value(input: &mut dyn Read) -> Result<ModuleValue> {
+    let (opcode, _) = input.read_length()?;
+    let value = match opcode {
+        RDB_MODULE_OPCODE_SINT => ModuleValue::Signed(input.read_length()?.0 as i64),
+        RDB_MODULE_OPCODE_UINT => ModuleValue::Unsigned(input.read_length()?.0 as u64),
+        RDB_MODULE_OPCODE_FLOAT => ModuleValue::Float(input.read_f32::<LittleEndian>()?),
+        RDB_MODULE_OPCODE_DOUBLE => ModuleValue::Double(input.read_f64::<LittleEndian>()?),
+        RDB_MODULE_OPCODE_STRING => ModuleValue::String(input.read_string()?),
+        _ => return Err(unexpected_opcode("any", opcode)),
+    };
+    Ok(value)
+}
+
+/// 对应Redis Module API中的`RedisModule_LoadUnsigned`
+pub fn load_unsigned(input: &mut dyn Read) -> Result<u64> {
+    expect_opcode(input, RDB_MODULE_OPCODE_UINT)?;
+    let (value, _) = input.read_length()?;
+    Ok(value as u64)
+}
+
+/// 对应Redis Module API中的`RedisModule_LoadSigned`
+pub fn load_signed(input: &mut dyn Read) -> Result<i64> {
+    expect_opcode(input, RDB_MODULE_OPCODE_SINT)?;
+    let (value, _) = input.read_length()?;
+    Ok(value as i64)
+}
+
+/// 对应Redis Module API中的`RedisModule_LoadFloat`
+pub fn load_float(input: &mut dyn Read) -> Result<f32> {
+    expect_opcode(input, RDB_MODULE_OPCODE_FLOAT)?;
+    input.read_f32::<LittleEndian>()
+}
+
+/// 对应Redis Module API中的`RedisModule_LoadDouble`
+pub fn load_double(input: &mut dyn Read) -> Result<f64> {
+    expect_opcode(input, RDB_MODULE_OPCODE_DOUBLE)?;
+    input.read_f64::<LittleEndian>()
+}
+
+/// 对应Redis Module API中的`RedisModule_LoadString`以及`RedisModule_LoadStringBuffer`
+pub fn load_string(input: &mut dyn Read) -> Result<Vec<u8>> {
+    expect_opcode(input, RDB_MODULE_OPCODE_STRING)?;
+    input.read_string()
+}
+
+fn expect_opcode(input: &mut dyn Read, expected: isize) -> Result<()> {
+    let (opcode, _) = input.read_length()?;
+    if opcode != expected {
+        return Err(unexpected_opcode(&expected.to_string(), opcode));
+    }
+    Ok(())
+}
+
+fn unexpected_opcode(expected: &str, actual: isize) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("module opcode不正确, 期望: {}, 实际: {}", expected, actual),
+    )
+}
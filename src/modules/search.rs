@@ -0,0 +1,291 @@
+/*!
+[RediSearch](https://github.com/RediSearch/RediSearch)索引定义的解析器，需开启`module-search` feature
+
+RediSearch 2.x将所有索引的定义(即FT.CREATE时指定的schema)保存在Module的AUX数据中，解析之后将产生一个`Object::ModuleAux`事件，
+其值为[`SearchIndexes`]，可据此在复制过程中审计有哪些索引，以及索引了哪些key和字段。
+
+RediSearch 2.x不会将文档表以及倒排索引保存到RDB中，而是在加载RDB之后根据索引定义重新对key建立索引，因此这里只能得到索引的定义。
+
+```no_run
+use redis_event::listener;
+use redis_event::modules::search::SearchModuleParser;
+
+let mut builder = listener::Builder::new();
+builder.with_module_parser(Box::new(SearchModuleParser {}));
+```
+
+[`SearchIndexes`]: struct.SearchIndexes.html
+*/
+use std::any::Any;
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+#[cfg(not(feature = "tracing"))]
+use log::warn;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::modules::{load_double, load_signed, load_string, load_unsigned};
+use crate::rdb::Module;
+use crate::ModuleParser;
+
+/// RediSearch索引定义在RDB中的类型名
+pub const SEARCH_MODULE_NAME: &str = "ft_index0";
+
+const INDEX_HAS_CUSTOM_STOPWORDS: u64 = 0x08;
+const INDEX_HAS_SMAP: u64 = 0x100;
+
+const FIELD_FULLTEXT: u64 = 0x01;
+const FIELD_NUMERIC: u64 = 0x02;
+const FIELD_GEO: u64 = 0x04;
+const FIELD_TAG: u64 = 0x08;
+const FIELD_OPTION_DYNAMIC: u64 = 0x10;
+
+/// RDB中所有的RediSearch索引定义
+#[derive(Debug)]
+pub struct SearchIndexes {
+    pub indexes: Vec<IndexSpec>,
+    /// 是否完整解析了所有的索引定义，遇到不支持的特性(如同义词、向量字段)时将停止解析，此时为false
+    pub is_complete: bool,
+}
+
+impl Module for SearchIndexes {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// 单个索引的定义
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSpec {
+    /// 索引名
+    pub name: Vec<u8>,
+    /// 索引的选项，如NOOFFSETS、NOFREQS等
+    pub flags: u64,
+    pub fields: Vec<FieldSpec>,
+    /// 所索引的数据类型，HASH或者JSON
+    pub document_type: Vec<u8>,
+    /// 所索引的key的前缀，即FT.CREATE的PREFIX
+    pub prefixes: Vec<Vec<u8>>,
+    /// 即FT.CREATE的FILTER
+    pub filter: Option<Vec<u8>>,
+    /// 即FT.CREATE的LANGUAGE_FIELD
+    pub language_field: Option<Vec<u8>>,
+    /// 即FT.CREATE的SCORE_FIELD
+    pub score_field: Option<Vec<u8>>,
+    /// 即FT.CREATE的PAYLOAD_FIELD
+    pub payload_field: Option<Vec<u8>>,
+    /// 即FT.CREATE的SCORE
+    pub default_score: f64,
+    /// 即FT.CREATE的LANGUAGE
+    pub default_language: Vec<u8>,
+    /// 自定义的停用词，未自定义时为None
+    pub stopwords: Option<Vec<Vec<u8>>>,
+    /// 临时索引的过期时间(秒)
+    pub timeout: u64,
+    /// 通过FT.ALIASADD添加的别名
+    pub aliases: Vec<Vec<u8>>,
+}
+
+/// 索引中字段的定义
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    /// 字段名，即AS所指定的名字
+    pub name: Vec<u8>,
+    /// 字段在文档中的路径，与字段名相同时为None，JSON索引中为JSONPath
+    pub path: Option<Vec<u8>>,
+    /// 字段的类型，可能同时为多种类型，见`is_xxx`方法
+    pub types: u64,
+    /// 字段的选项，如SORTABLE、NOINDEX等
+    pub options: u64,
+    /// 在排序表中的位置，不可排序时为-1
+    pub sort_index: i64,
+    /// TEXT字段的权重
+    pub weight: Option<f64>,
+    /// TAG字段的分隔符
+    pub tag_separator: Option<u8>,
+}
+
+impl FieldSpec {
+    pub fn is_text(&self) -> bool {
+        self.types & FIELD_FULLTEXT != 0
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.types & FIELD_NUMERIC != 0
+    }
+
+    pub fn is_geo(&self) -> bool {
+        self.types & FIELD_GEO != 0
+    }
+
+    pub fn is_tag(&self) -> bool {
+        self.types & FIELD_TAG != 0
+    }
+}
+
+/// RediSearch的Module解析器，仅解析AUX数据中的索引定义
+///
+/// RediSearch 2.x并不会产生module类型的key，其他Module的值将以`RawModule`的形式产生，见`ModuleParser::supports`
+pub struct SearchModuleParser {}
+
+impl ModuleParser for SearchModuleParser {
+    fn parse(&mut self, _: &mut dyn Read, module_name: &str, module_version: usize) -> Box<dyn Module> {
+        // supports始终返回false，DefaultRDBParser不会调用此方法
+        unreachable!(
+            "SearchModuleParser无法解析MODULE {}, version {}",
+            module_name, module_version
+        );
+    }
+
+    fn supports(&self, _module_name: &str, _is_module2: bool) -> bool {
+        false
+    }
+
+    fn parse_aux(&mut self, module_name: &str, _encver: usize, _when: u64, data: &[u8]) -> Option<Box<dyn Module>> {
+        if module_name != SEARCH_MODULE_NAME {
+            return None;
+        }
+        let mut input = Cursor::new(data);
+        let mut indexes = Vec::new();
+        let mut is_complete = true;
+        match load_unsigned(&mut input) {
+            Ok(count) => {
+                for _ in 0..count {
+                    match read_index_spec(&mut input) {
+                        Ok(spec) => indexes.push(spec),
+                        Err(err) => {
+                            warn!("停止解析RediSearch索引定义, 已解析{}个: {}", indexes.len(), err);
+                            is_complete = false;
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("解析RediSearch索引定义失败: {}", err);
+                is_complete = false;
+            }
+        }
+        Some(Box::new(SearchIndexes { indexes, is_complete }))
+    }
+}
+
+fn read_index_spec(input: &mut dyn Read) -> Result<IndexSpec> {
+    let name = load_c_string(input)?;
+    let flags = load_unsigned(input)?;
+    let fields_count = load_unsigned(input)?;
+    let mut fields = Vec::with_capacity(fields_count as usize);
+    for _ in 0..fields_count {
+        fields.push(read_field_spec(input)?);
+    }
+
+    let document_type = load_c_string(input)?;
+    let prefixes_count = load_unsigned(input)?;
+    let mut prefixes = Vec::with_capacity(prefixes_count as usize);
+    for _ in 0..prefixes_count {
+        prefixes.push(load_c_string(input)?);
+    }
+    let filter = load_optional(input)?;
+    let language_field = load_optional(input)?;
+    let score_field = load_optional(input)?;
+    let payload_field = load_optional(input)?;
+    let default_score = load_double(input)?;
+    let default_language = load_c_string(input)?;
+
+    let stopwords = if flags & INDEX_HAS_CUSTOM_STOPWORDS != 0 {
+        let count = load_unsigned(input)?;
+        let mut stopwords = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            stopwords.push(load_c_string(input)?);
+        }
+        Some(stopwords)
+    } else {
+        None
+    };
+    if flags & INDEX_HAS_SMAP != 0 {
+        return Err(unsupported(&name, "synonym"));
+    }
+    let timeout = load_unsigned(input)?;
+    let aliases_count = load_unsigned(input)?;
+    let mut aliases = Vec::with_capacity(aliases_count as usize);
+    for _ in 0..aliases_count {
+        aliases.push(load_c_string(input)?);
+    }
+
+    Ok(IndexSpec {
+        name,
+        flags,
+        fields,
+        document_type,
+        prefixes,
+        filter,
+        language_field,
+        score_field,
+        payload_field,
+        default_score,
+        default_language,
+        stopwords,
+        timeout,
+        aliases,
+    })
+}
+
+fn read_field_spec(input: &mut dyn Read) -> Result<FieldSpec> {
+    let name = load_c_string(input)?;
+    let path = load_optional(input)?;
+    let types = load_unsigned(input)?;
+    if types & !(FIELD_FULLTEXT | FIELD_NUMERIC | FIELD_GEO | FIELD_TAG) != 0 {
+        // 如VECTOR、GEOSHAPE字段，其后还有各自的参数
+        return Err(unsupported(&name, &format!("field type {}", types)));
+    }
+    let options = load_unsigned(input)?;
+    let sort_index = load_signed(input)?;
+    let is_dynamic = options & FIELD_OPTION_DYNAMIC != 0;
+
+    let weight = if types & FIELD_FULLTEXT != 0 || is_dynamic {
+        let _ft_id = load_unsigned(input)?;
+        Some(load_double(input)?)
+    } else {
+        None
+    };
+    let tag_separator = if types & FIELD_TAG != 0 || is_dynamic {
+        let _tag_flags = load_unsigned(input)?;
+        load_string(input)?.first().copied()
+    } else {
+        None
+    };
+    Ok(FieldSpec {
+        name,
+        path,
+        types,
+        options,
+        sort_index,
+        weight,
+        tag_separator,
+    })
+}
+
+/// RediSearch保存字符串时会带上末尾的'\0'，这里将其去掉
+fn load_c_string(input: &mut dyn Read) -> Result<Vec<u8>> {
+    let mut value = load_string(input)?;
+    if value.last() == Some(&0) {
+        value.pop();
+    }
+    Ok(value)
+}
+
+/// 先读取一个标识，为1时再读取对应的字符串
+fn load_optional(input: &mut dyn Read) -> Result<Option<Vec<u8>>> {
+    if load_unsigned(input)? != 0 {
+        Ok(Some(load_c_string(input)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn unsupported(name: &[u8], feature: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("索引{}使用了不支持的特性: {}", String::from_utf8_lossy(name), feature),
+    )
+}
@@ -0,0 +1,438 @@
+/*!
+[RedisTimeSeries](https://github.com/RedisTimeSeries/RedisTimeSeries)的解析器，需开启`module-timeseries` feature
+
+解析出的每个key对应一个[`TimeSeries`]，包括retention、labels、compaction规则以及所有的sample，
+可据此将时序数据复制到其他的时序数据库中。
+
+```no_run
+use redis_event::listener;
+use redis_event::modules::timeseries::TimeSeriesModuleParser;
+
+let mut builder = listener::Builder::new();
+builder.with_module_parser(Box::new(TimeSeriesModuleParser {}));
+```
+
+[`TimeSeries`]: struct.TimeSeries.html
+*/
+use std::any::Any;
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::modules::{load_double, load_signed, load_string, load_unsigned, load_value, ModuleValue};
+use crate::rdb::Module;
+use crate::ModuleParser;
+
+/// RedisTimeSeries在RDB中的类型名
+pub const TIMESERIES_MODULE_NAME: &str = "TSDB-TYPE";
+
+// 各编码版本新增的字段
+const TS_SIZE_RDB_VER: usize = 1;
+const TS_UNCOMPRESSED_VER: usize = 2;
+const TS_IS_RESSETED_DUP_POLICY_RDB_VER: usize = 3;
+const TS_ALIGNMENT_TS_VER: usize = 6;
+const TS_CREATE_IGNORE_VER: usize = 7;
+
+const SERIES_OPT_UNCOMPRESSED: u64 = 0x1;
+const SAMPLE_SIZE: u64 = 16;
+
+/// RedisTimeSeries中的一条时间序列
+#[derive(Debug)]
+pub struct TimeSeries {
+    /// 时间序列所属的key
+    pub key: Vec<u8>,
+    /// 数据的保留时长(毫秒)，0代表永久保留
+    pub retention: u64,
+    /// 每个chunk的大小(字节)
+    pub chunk_size: u64,
+    /// 创建时的选项，如是否压缩
+    pub options: u64,
+    /// 写入重复时间戳时的处理策略，0代表使用全局配置
+    pub duplicate_policy: u64,
+    /// 编码版本7新增，判定为重复写入的时间差，低版本中为0
+    pub ignore_max_time_diff: u64,
+    /// 编码版本7新增，判定为重复写入的差值，低版本中为0
+    pub ignore_max_val_diff: f64,
+    /// 最后一个sample的时间戳
+    pub last_timestamp: u64,
+    /// 最后一个sample的值
+    pub last_value: f64,
+    /// sample的总数
+    pub total_samples: u64,
+    /// 作为compaction规则的目标时，其来源时间序列的key
+    pub src_key: Option<Vec<u8>>,
+    pub labels: Vec<Label>,
+    pub rules: Vec<Rule>,
+    /// 所有的sample，按时间戳升序排列
+    pub samples: Vec<Sample>,
+}
+
+impl TimeSeries {
+    /// 是否使用了压缩的chunk
+    pub fn is_compressed(&self) -> bool {
+        self.options & SERIES_OPT_UNCOMPRESSED == 0
+    }
+}
+
+impl Module for TimeSeries {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub name: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// compaction规则，即TS.CREATERULE所创建的规则
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// 目标时间序列的key
+    pub dest_key: Vec<u8>,
+    /// 聚合的时间窗口(毫秒)
+    pub bucket_duration: u64,
+    /// 时间窗口的对齐时间戳，编码版本6之前为0
+    pub timestamp_alignment: u64,
+    /// 聚合类型，见[`Aggregation`]
+    ///
+    /// [`Aggregation`]: enum.Aggregation.html
+    pub aggregation: Aggregation,
+    /// 当前时间窗口的起始时间戳
+    pub start_current_time_bucket: u64,
+    /// 当前时间窗口聚合的中间状态，按原样保留
+    pub context: Vec<ModuleValue>,
+}
+
+/// 聚合类型，与RedisTimeSeries中的定义保持一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    None,
+    Min,
+    Max,
+    Sum,
+    Avg,
+    Count,
+    First,
+    Last,
+    Range,
+    StdP,
+    StdS,
+    VarP,
+    VarS,
+    Twa,
+}
+
+impl Aggregation {
+    fn from(value: u64) -> Result<Aggregation> {
+        let aggregation = match value {
+            0 => Aggregation::None,
+            1 => Aggregation::Min,
+            2 => Aggregation::Max,
+            3 => Aggregation::Sum,
+            4 => Aggregation::Avg,
+            5 => Aggregation::Count,
+            6 => Aggregation::First,
+            7 => Aggregation::Last,
+            8 => Aggregation::Range,
+            9 => Aggregation::StdP,
+            10 => Aggregation::StdS,
+            11 => Aggregation::VarP,
+            12 => Aggregation::VarS,
+            13 => Aggregation::Twa,
+            _ => return Err(invalid_data(format!("未知的聚合类型: {}", value))),
+        };
+        Ok(aggregation)
+    }
+
+    /// 聚合的中间状态所包含的字段数
+    fn context_len(&self, encver: usize) -> Result<usize> {
+        let len = match self {
+            Aggregation::Sum | Aggregation::Count | Aggregation::First | Aggregation::Last => {
+                if encver >= TS_IS_RESSETED_DUP_POLICY_RDB_VER {
+                    2
+                } else {
+                    1
+                }
+            }
+            Aggregation::Min | Aggregation::Max | Aggregation::Range => 3,
+            Aggregation::Avg => 2,
+            Aggregation::StdP | Aggregation::StdS | Aggregation::VarP | Aggregation::VarS => 3,
+            Aggregation::None | Aggregation::Twa => {
+                return Err(invalid_data(format!("不支持的聚合类型: {:?}", self)));
+            }
+        };
+        Ok(len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub timestamp: u64,
+    pub value: f64,
+}
+
+/// RedisTimeSeries的Module解析器
+///
+/// 仅解析RedisTimeSeries(MODULE_2类型)的数据，其他Module的值将以`RawModule`的形式产生，见`ModuleParser::supports`
+pub struct TimeSeriesModuleParser {}
+
+impl ModuleParser for TimeSeriesModuleParser {
+    fn parse(&mut self, _: &mut dyn Read, module_name: &str, module_version: usize) -> Box<dyn Module> {
+        // supports对旧版本的Module类型返回false，DefaultRDBParser不会调用此方法
+        unreachable!(
+            "不支持的MODULE类型: {}, version {}, 仅支持MODULE_2",
+            module_name, module_version
+        );
+    }
+
+    fn parse_module2(&mut self, input: &mut dyn Read, module_name: &str, encver: usize) -> Result<Box<dyn Module>> {
+        if module_name != TIMESERIES_MODULE_NAME {
+            return Err(invalid_data(format!(
+                "TimeSeriesModuleParser无法解析MODULE {}",
+                module_name
+            )));
+        }
+        match read_time_series(input, encver) {
+            Ok(series) => Ok(Box::new(series)),
+            Err(err) => Err(Error::new(
+                err.kind(),
+                format!("解析TimeSeries失败, encver {}: {}", encver, err),
+            )),
+        }
+    }
+
+    fn supports(&self, module_name: &str, is_module2: bool) -> bool {
+        is_module2 && module_name == TIMESERIES_MODULE_NAME
+    }
+}
+
+fn read_time_series(input: &mut dyn Read, encver: usize) -> Result<TimeSeries> {
+    let key = load_string(input)?;
+    let retention = load_unsigned(input)?;
+    let mut chunk_size = load_unsigned(input)?;
+    if encver < TS_SIZE_RDB_VER {
+        // 早期版本中记录的是sample的个数
+        chunk_size *= SAMPLE_SIZE;
+    }
+    let options = if encver >= TS_UNCOMPRESSED_VER {
+        load_unsigned(input)?
+    } else {
+        SERIES_OPT_UNCOMPRESSED
+    };
+    let duplicate_policy = if encver >= TS_IS_RESSETED_DUP_POLICY_RDB_VER {
+        load_unsigned(input)?
+    } else {
+        0
+    };
+    let (ignore_max_time_diff, ignore_max_val_diff) = if encver >= TS_CREATE_IGNORE_VER {
+        (load_unsigned(input)?, load_double(input)?)
+    } else {
+        (0, 0.0)
+    };
+    let last_timestamp = load_unsigned(input)?;
+    let last_value = load_double(input)?;
+    let total_samples = load_unsigned(input)?;
+    let src_key = if load_unsigned(input)? != 0 {
+        Some(load_string(input)?)
+    } else {
+        None
+    };
+
+    let labels_count = load_unsigned(input)?;
+    let mut labels = Vec::with_capacity(labels_count as usize);
+    for _ in 0..labels_count {
+        let name = load_string(input)?;
+        let value = load_string(input)?;
+        labels.push(Label { name, value });
+    }
+
+    let rules_count = load_unsigned(input)?;
+    let mut rules = Vec::with_capacity(rules_count as usize);
+    for _ in 0..rules_count {
+        let dest_key = load_string(input)?;
+        let bucket_duration = load_unsigned(input)?;
+        let timestamp_alignment = if encver >= TS_ALIGNMENT_TS_VER {
+            load_unsigned(input)?
+        } else {
+            0
+        };
+        let aggregation = Aggregation::from(load_unsigned(input)?)?;
+        let start_current_time_bucket = load_unsigned(input)?;
+        let mut context = Vec::new();
+        for _ in 0..aggregation.context_len(encver)? {
+            context.push(load_value(input)?);
+        }
+        rules.push(Rule {
+            dest_key,
+            bucket_duration,
+            timestamp_alignment,
+            aggregation,
+            start_current_time_bucket,
+            context,
+        });
+    }
+
+    let chunks = load_unsigned(input)?;
+    let mut samples = Vec::with_capacity(total_samples as usize);
+    for _ in 0..chunks {
+        if options & SERIES_OPT_UNCOMPRESSED != 0 {
+            read_uncompressed_chunk(input, &mut samples)?;
+        } else {
+            read_compressed_chunk(input, &mut samples)?;
+        }
+    }
+
+    Ok(TimeSeries {
+        key,
+        retention,
+        chunk_size,
+        options,
+        duplicate_policy,
+        ignore_max_time_diff,
+        ignore_max_val_diff,
+        last_timestamp,
+        last_value,
+        total_samples,
+        src_key,
+        labels,
+        rules,
+        samples,
+    })
+}
+
+/// 未压缩的chunk，数据为连续存放的sample，每个sample由8字节的时间戳以及8字节的值组成
+fn read_uncompressed_chunk(input: &mut dyn Read, samples: &mut Vec<Sample>) -> Result<()> {
+    let _base_timestamp = load_unsigned(input)?;
+    let count = load_unsigned(input)?;
+    let _size = load_unsigned(input)?;
+    let data = load_string(input)?;
+    let mut cursor = Cursor::new(&data);
+    for _ in 0..count {
+        let timestamp = cursor.read_u64::<LittleEndian>()?;
+        let value = cursor.read_f64::<LittleEndian>()?;
+        samples.push(Sample { timestamp, value });
+    }
+    Ok(())
+}
+
+/// 使用Gorilla算法压缩的chunk
+fn read_compressed_chunk(input: &mut dyn Read, samples: &mut Vec<Sample>) -> Result<()> {
+    let _size = load_unsigned(input)?;
+    let count = load_unsigned(input)?;
+    let _idx = load_unsigned(input)?;
+    let base_value = f64::from_bits(load_unsigned(input)?);
+    let base_timestamp = load_unsigned(input)?;
+    // 以下为写入最后一个sample之后的状态，解析时用不到
+    let _prev_timestamp = load_unsigned(input)?;
+    let _prev_timestamp_delta = load_signed(input)?;
+    let _prev_value = load_unsigned(input)?;
+    let _prev_leading = load_unsigned(input)?;
+    let _prev_trailing = load_unsigned(input)?;
+    let data = load_string(input)?;
+    if count == 0 {
+        return Ok(());
+    }
+
+    let mut reader = GorillaReader::new(&data, base_timestamp, base_value);
+    samples.push(Sample {
+        timestamp: base_timestamp,
+        value: base_value,
+    });
+    for _ in 1..count {
+        samples.push(reader.next_sample()?);
+    }
+    Ok(())
+}
+
+// 时间戳的delta of delta按照所需的位数分为以下几档，前缀分别为10、110、1110、11110，11111则直接使用64位
+const DOUBLE_DELTA_BITS: [u32; 4] = [5, 8, 11, 15];
+const DOUBLE_LEADING_BITS: u32 = 5;
+const DOUBLE_BLOCK_SIZE_BITS: u32 = 6;
+
+/// 按照RedisTimeSeries的实现，数据以64位小端整数为单位，每个整数内从低位开始存放
+struct GorillaReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+    prev_timestamp: u64,
+    prev_delta: i64,
+    prev_value: u64,
+    prev_leading: u32,
+    prev_trailing: u32,
+}
+
+impl<'a> GorillaReader<'a> {
+    fn new(data: &'a [u8], base_timestamp: u64, base_value: f64) -> GorillaReader<'a> {
+        GorillaReader {
+            data,
+            bit: 0,
+            prev_timestamp: base_timestamp,
+            prev_delta: 0,
+            prev_value: base_value.to_bits(),
+            prev_leading: 32,
+            prev_trailing: 32,
+        }
+    }
+
+    fn next_sample(&mut self) -> Result<Sample> {
+        let timestamp = self.next_timestamp()?;
+        let value = self.next_value()?;
+        Ok(Sample { timestamp, value })
+    }
+
+    fn next_timestamp(&mut self) -> Result<u64> {
+        let mut ones = 0;
+        while ones <= DOUBLE_DELTA_BITS.len() && self.read_bits(1)? == 1 {
+            ones += 1;
+        }
+        let double_delta = match ones {
+            0 => 0,
+            n if n <= DOUBLE_DELTA_BITS.len() => {
+                let bits = DOUBLE_DELTA_BITS[n - 1];
+                let raw = self.read_bits(bits)?;
+                // 符号扩展
+                ((raw << (64 - bits)) as i64) >> (64 - bits)
+            }
+            _ => self.read_bits(64)? as i64,
+        };
+        self.prev_delta += double_delta;
+        self.prev_timestamp = self.prev_timestamp.wrapping_add(self.prev_delta as u64);
+        Ok(self.prev_timestamp)
+    }
+
+    fn next_value(&mut self) -> Result<f64> {
+        if self.read_bits(1)? == 1 {
+            let xor = if self.read_bits(1)? == 0 {
+                let block_size = 64 - self.prev_leading - self.prev_trailing;
+                self.read_bits(block_size)? << self.prev_trailing
+            } else {
+                let leading = self.read_bits(DOUBLE_LEADING_BITS)? as u32;
+                let block_size = self.read_bits(DOUBLE_BLOCK_SIZE_BITS)? as u32 + 1;
+                let trailing = 64 - leading - block_size;
+                self.prev_leading = leading;
+                self.prev_trailing = trailing;
+                self.read_bits(block_size)? << trailing
+            };
+            self.prev_value ^= xor;
+        }
+        Ok(f64::from_bits(self.prev_value))
+    }
+
+    fn read_bits(&mut self, len: u32) -> Result<u64> {
+        let mut value = 0;
+        for i in 0..len {
+            let byte = match self.data.get(self.bit / 8) {
+                Some(byte) => byte,
+                None => return Err(Error::new(ErrorKind::UnexpectedEof, "compressed chunk已读取完毕")),
+            };
+            value |= (((byte >> (self.bit % 8)) & 1) as u64) << i;
+            self.bit += 1;
+        }
+        Ok(value)
+    }
+}
+
+fn invalid_data(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
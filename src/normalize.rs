@@ -0,0 +1,209 @@
+/*!
+将命令改写为确定性的效果，与replica应用这些命令时的结果一致
+
+相对时间的过期设置在不同的时刻重放会得到不同的结果，[`Normalizer`]将其改写为绝对时间，使得下游(尤其是非Redis的存储)可以幂等地写入:
+- `EXPIRE`、`PEXPIRE`、`EXPIREAT`改写为`PEXPIREAT`，保留NX、XX等选项
+- `SETEX`、`PSETEX`改写为`SET`以及`PEXPIREAT`两条命令
+- 带有`EX`、`PX`、`EXAT`选项的`SET`改写为带有`PXAT`选项的`SET`
+- `GETEX`改写为`PEXPIREAT`或者`PERSIST`，不带选项时只是读取，将被丢弃；`GETDEL`改写为`DEL`
+- `HEXPIRE`、`HPEXPIRE`、`HEXPIREAT`改写为`HPEXPIREAT`；`HGETEX`改写为`HPEXPIREAT`或者`HPERSIST`，不带选项时将被丢弃
+
+其余的命令原样转交，Redis自身在传播时已经做过改写的命令(如`SPOP`以`SREM`的形式传播，`INCRBYFLOAT`以`SET KEEPTTL`的形式传播)无需再处理。
+
+相对时间以命令之前最近的`#TS:`注释所记录的时间(见`AofPosition::timestamp`)为基准，没有此注释时以处理命令时的当前时间为基准。
+*/
+use std::io::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cmd::strings::ExpireType;
+use crate::cmd::{self, Command, CommandParsers};
+use crate::listener::PositionedHandler;
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// 将命令改写为确定性的效果之后交给内部的`EventHandler`，改写后的命令同样以`Event::AOF`的形式产生，
+/// 其`handle_aof_raw`收到的是改写后的命令，同一条命令改写出的多条命令具有相同的`AofPosition`
+///
+/// 只有通过`EventHandler::handle_aof_raw`收到的命令才会被改写，其余的事件直接转交
+pub struct Normalizer<H: EventHandler> {
+    inner: H,
+}
+
+impl<H: EventHandler> Normalizer<H> {
+    pub fn new(inner: H) -> Normalizer<H> {
+        Normalizer { inner }
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+}
+
+impl<H: EventHandler> EventHandler for Normalizer<H> {
+    fn handle(&mut self, event: Event) {
+        self.inner.handle(event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.inner.handle_aof(event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        let now = position.timestamp.unwrap_or_else(SystemTime::now);
+        let effects = match &event {
+            Event::AOF(cmd) => normalize(cmd, args, now),
+            Event::RDB(_) => None,
+        };
+        match effects {
+            None => self.inner.handle_aof_raw(event, args, position),
+            Some(effects) => {
+                for effect in &effects {
+                    let mut handler = PositionedHandler {
+                        inner: &mut self.inner,
+                        args: effect,
+                        position,
+                    };
+                    // 改写后的命令均为内置的命令
+                    cmd::parse(effect, &CommandParsers::new(), &mut handler);
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        self.inner.pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
+
+/// 将命令改写为确定性的效果，返回改写后的各条命令(可能为空，即此命令没有任何效果)，无需改写时返回None
+///
+/// 方法参数:
+///
+/// * `cmd`: 解析后的命令
+/// * `args`: 原始命令，第一个为命令名
+/// * `now`: 计算相对时间时所使用的基准时间
+pub fn normalize(cmd: &Command, args: &[Vec<u8>], now: SystemTime) -> Option<Vec<Vec<Vec<u8>>>> {
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+    let effects = match cmd {
+        Command::EXPIRE(expire) => {
+            let at = expire_at(&ExpireType::EX, expire.seconds, now)?;
+            vec![rename(args, "PEXPIREAT", at)]
+        }
+        Command::PEXPIRE(pexpire) => {
+            let at = expire_at(&ExpireType::PX, pexpire.milliseconds, now)?;
+            vec![rename(args, "PEXPIREAT", at)]
+        }
+        Command::EXPIREAT(expireat) => {
+            let at = expire_at(&ExpireType::EXAT, expireat.timestamp, now)?;
+            vec![rename(args, "PEXPIREAT", at)]
+        }
+        Command::SETEX(setex) => {
+            let at = expire_at(&ExpireType::EX, setex.seconds, now)?;
+            vec![
+                vec![b"SET".to_vec(), setex.key.to_vec(), setex.value.to_vec()],
+                pexpireat(setex.key, at),
+            ]
+        }
+        Command::PSETEX(psetex) => {
+            let at = expire_at(&ExpireType::PX, psetex.milliseconds, now)?;
+            vec![
+                vec![b"SET".to_vec(), psetex.key.to_vec(), psetex.value.to_vec()],
+                pexpireat(psetex.key, at),
+            ]
+        }
+        Command::SET(set) => match &set.expire {
+            Some((ExpireType::PXAT, _)) | None => return None,
+            Some((expire_type, value)) => {
+                let at = expire_at(expire_type, value, now)?;
+                vec![to_pxat(args, at)]
+            }
+        },
+        Command::GETEX(getex) => match &getex.expire {
+            Some((expire_type, value)) => vec![pexpireat(getex.key, expire_at(expire_type, value, now)?)],
+            None if getex.persist == Some(true) => vec![vec![b"PERSIST".to_vec(), getex.key.to_vec()]],
+            None => Vec::new(),
+        },
+        Command::GETDEL(getdel) => vec![vec![b"DEL".to_vec(), getdel.key.to_vec()]],
+        Command::HEXPIRE(hexpire) => {
+            let at = expire_at(&ExpireType::EX, hexpire.seconds, now)?;
+            vec![rename(args, "HPEXPIREAT", at)]
+        }
+        Command::HPEXPIRE(hpexpire) => {
+            let at = expire_at(&ExpireType::PX, hpexpire.milliseconds, now)?;
+            vec![rename(args, "HPEXPIREAT", at)]
+        }
+        Command::HEXPIREAT(hexpireat) => {
+            let at = expire_at(&ExpireType::EXAT, hexpireat.timestamp, now)?;
+            vec![rename(args, "HPEXPIREAT", at)]
+        }
+        Command::HGETEX(hgetex) => {
+            let mut fields = vec![b"FIELDS".to_vec(), hgetex.fields.len().to_string().into_bytes()];
+            fields.extend(hgetex.fields.iter().map(|field| field.to_vec()));
+            let mut effect = match &hgetex.expire {
+                Some((expire_type, value)) => {
+                    let at = expire_at(expire_type, value, now)?;
+                    vec![b"HPEXPIREAT".to_vec(), hgetex.key.to_vec(), at.to_string().into_bytes()]
+                }
+                None if hgetex.persist == Some(true) => vec![b"HPERSIST".to_vec(), hgetex.key.to_vec()],
+                None => return Some(Vec::new()),
+            };
+            effect.append(&mut fields);
+            vec![effect]
+        }
+        _ => return None,
+    };
+    Some(effects)
+}
+
+// 计算毫秒级的过期时间戳，参数不是整数或者溢出时返回None，此时命令将原样转交
+fn expire_at(expire_type: &ExpireType, value: &[u8], now: i64) -> Option<i64> {
+    let value: i64 = std::str::from_utf8(value).ok()?.parse().ok()?;
+    match expire_type {
+        ExpireType::EX => value.checked_mul(1000)?.checked_add(now),
+        ExpireType::PX => value.checked_add(now),
+        ExpireType::EXAT => value.checked_mul(1000),
+        ExpireType::PXAT => Some(value),
+    }
+}
+
+fn pexpireat(key: &[u8], at: i64) -> Vec<Vec<u8>> {
+    vec![b"PEXPIREAT".to_vec(), key.to_vec(), at.to_string().into_bytes()]
+}
+
+// 替换命令名以及第二个参数(即过期时间)，其余的参数保持不变
+fn rename(args: &[Vec<u8>], name: &str, at: i64) -> Vec<Vec<u8>> {
+    let mut effect = args.to_vec();
+    effect[0] = name.as_bytes().to_vec();
+    effect[2] = at.to_string().into_bytes();
+    effect
+}
+
+// 将SET命令中的EX、PX、EXAT选项替换为PXAT
+fn to_pxat(args: &[Vec<u8>], at: i64) -> Vec<Vec<u8>> {
+    let mut effect = args.to_vec();
+    // 选项在key与value之后
+    let index = effect.iter().skip(3).position(|arg| {
+        let arg = String::from_utf8_lossy(arg).to_uppercase();
+        arg == "EX" || arg == "PX" || arg == "EXAT"
+    });
+    if let Some(index) = index.map(|index| index + 3) {
+        effect[index] = b"PXAT".to_vec();
+        effect[index + 1] = at.to_string().into_bytes();
+    }
+    effect
+}
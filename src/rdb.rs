@@ -4,24 +4,41 @@ RDB中各项Redis数据相关的结构体定义，以及RDB解析相关的代码
 use core::result;
 use std::any::Any;
 use std::cmp;
-use std::collections::BTreeMap;
-use std::fmt::{Debug, Error, Formatter};
-use std::io::{Cursor, Read, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Error, Formatter};
+use std::fs::File;
+use std::io::{self, Cursor, ErrorKind, Read, Result};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
-use log::info;
+#[cfg(not(feature = "tracing"))]
+use log::{info, warn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "tracing")]
+use tracing::{info, warn};
 
 use crate::cmd::connection::SELECT;
+use crate::cmd::scripting::SCRIPTLOAD;
 use crate::cmd::Command;
-use crate::iter::{IntSetIter, Iter, QuickListIter, SortedSetIter, StrValIter, ZipListIter, ZipMapIter};
-use crate::{lzf, to_string, Event, EventHandler, ModuleParser, RDBParser};
-use std::cell::RefCell;
+use crate::config::{Config, KeyFilter, ParseMode};
+use crate::crc64::CrcReader;
+use crate::encodings::{IntSet, ZipList, ZipMap};
+use crate::iter::{HashMetadataIter, Iter, ListPackIter, QuickList2Iter, QuickListIter, SortedSetIter, StrValIter};
+use crate::rdb::stats::{RDBStats, StatsHandler};
+use crate::{compress, listpack, lzf, to_string, Event, EventHandler, ModuleParser, NoOpEventHandler, RDBParser};
+use std::cell::Cell;
 use std::f64::{INFINITY, NAN, NEG_INFINITY};
 use std::iter::FromIterator;
-use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod commands;
+pub mod splitter;
+pub mod stats;
+pub mod writer;
 
 /// 一些解析RDB数据的方法
 pub trait RDBDecode: Read {
@@ -78,73 +95,356 @@ pub trait RDBDecode: Read {
     /// 从流中读取一个string
     fn read_string(&mut self) -> Result<Vec<u8>> {
         let (length, is_encoded) = self.read_length()?;
-        if is_encoded {
-            match length {
-                RDB_ENC_INT8 => {
-                    let int = self.read_i8()?;
-                    return Ok(int.to_string().into_bytes());
-                }
-                RDB_ENC_INT16 => {
-                    let int = self.read_integer(2, false)?;
-                    return Ok(int.to_string().into_bytes());
-                }
-                RDB_ENC_INT32 => {
-                    let int = self.read_integer(4, false)?;
-                    return Ok(int.to_string().into_bytes());
-                }
-                RDB_ENC_LZF => {
-                    let (compressed_len, _) = self.read_length()?;
-                    let (origin_len, _) = self.read_length()?;
-                    let mut compressed = vec![0; compressed_len as usize];
-                    self.read_exact(&mut compressed)?;
-                    let mut origin = vec![0; origin_len as usize];
-                    lzf::decompress(&mut compressed, compressed_len, &mut origin, origin_len);
-                    return Ok(origin);
-                }
-                _ => panic!("Invalid string length: {}", length),
-            };
-        };
-        let mut buff = vec![0; length as usize];
-        self.read_exact(&mut buff)?;
-        Ok(buff)
+        read_string_content(self, length, is_encoded)
     }
 
     /// 从流中读取一个double
     fn read_double(&mut self) -> Result<f64> {
-        let len = self.read_u8()?;
-        return match len {
-            255 => Ok(NEG_INFINITY),
-            254 => Ok(INFINITY),
-            253 => Ok(NAN),
+        read_score(self, ParseMode::Strict)
+    }
+}
+
+impl<R: Read + ?Sized> RDBDecode for R {}
+
+// 已读取了长度，继续读取字符串的内容
+fn read_string_content<R: Read + ?Sized>(input: &mut R, length: isize, is_encoded: bool) -> Result<Vec<u8>> {
+    if is_encoded {
+        match length {
+            RDB_ENC_INT8 => {
+                let int = input.read_i8()?;
+                return Ok(int.to_string().into_bytes());
+            }
+            RDB_ENC_INT16 => {
+                let int = input.read_integer(2, false)?;
+                return Ok(int.to_string().into_bytes());
+            }
+            RDB_ENC_INT32 => {
+                let int = input.read_integer(4, false)?;
+                return Ok(int.to_string().into_bytes());
+            }
+            RDB_ENC_LZF => {
+                let (compressed_len, _) = input.read_length()?;
+                let (origin_len, _) = input.read_length()?;
+                let mut compressed = read_bytes(input, compressed_len)?;
+                // lzf中每个回溯引用至少占2个字节，最多展开为264个字节，超出该比例的长度必然是损坏的数据
+                if origin_len < 0 || origin_len / LZF_MAX_RATIO > compressed_len {
+                    let message = format!("Invalid lzf length: {}, compressed: {}", origin_len, compressed_len);
+                    return Err(io::Error::new(ErrorKind::InvalidData, message));
+                }
+                let mut origin = vec![0; origin_len as usize];
+                lzf::decompress(&mut compressed, compressed_len, &mut origin, origin_len);
+                return Ok(origin);
+            }
             _ => {
-                let mut buff = vec![0; len as usize];
-                self.read_exact(&mut buff)?;
-                let score_str = to_string(buff);
-                let score = score_str.parse::<f64>().unwrap();
-                Ok(score)
+                let message = format!("Invalid string encoding: {}", length);
+                return Err(io::Error::new(ErrorKind::InvalidData, message));
             }
         };
+    };
+    read_bytes(input, length)
+}
+
+// 读取`length`个字节，长度来自输入本身，数据损坏时可能是负数或者远超实际的大小，
+// 因此不预先按长度分配内存，而是随读取逐步增长，输入不足时返回`UnexpectedEof`
+fn read_bytes<R: Read + ?Sized>(input: &mut R, length: isize) -> Result<Vec<u8>> {
+    if length < 0 {
+        let message = format!("Invalid length: {}", length);
+        return Err(io::Error::new(ErrorKind::InvalidData, message));
+    }
+    let mut buff = Vec::with_capacity(cmp::min(length as usize, PREALLOC_LIMIT));
+    input.take(length as u64).read_to_end(&mut buff)?;
+    if buff.len() != length as usize {
+        let message = format!("Expect {} bytes, but got {}", length, buff.len());
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, message));
     }
+    Ok(buff)
 }
 
-impl<R: Read + ?Sized> RDBDecode for R {}
+/// 解析本地的RDB文件，如通过`BGSAVE`或者`redis-cli --rdb`得到的文件，其中的每条数据同样对应一个`Event::RDB`事件
+///
+/// 使用默认的解析选项: 数据不符合规范时返回错误，不过滤任何key；没有Module解析器，
+/// `RDB_TYPE_MODULE_2`类型的值将以[`RawModule`]的形式产生，遇到旧版本的Module类型的值时返回错误
+///
+/// 开启`compression-gzip`或者`compression-zstd` feature时，也可以直接解析压缩后的文件，见[`compress`]
+///
+/// [`RawModule`]: struct.RawModule.html
+/// [`compress`]: ../compress/index.html
+pub fn parse_file<P: AsRef<Path>>(path: P, event_handler: &mut dyn EventHandler) -> Result<ParseInfo> {
+    parse_reader(File::open(path)?, event_handler)
+}
+
+/// 从任意输入流中解析RDB，如标准输入、管道、HTTP响应或者内存中的数据，解析选项与[`parse_file`]相同，
+/// 可用于直接解析从对象存储中下载的快照，而无需先保存到本地
+///
+/// 输入将被缓冲，并与`parse_file`一样根据开头的magic number自动解压，无需再包装`BufReader`；
+/// 缓冲可能会预先读取RDB之后的数据，输入流在RDB之后还有其他数据时，需要自行从RDB的结尾处继续读取
+///
+/// ```no_run
+/// use std::io;
+/// use redis_event::{rdb, NoOpEventHandler};
+///
+/// // redis-cli --rdb - | my-tool
+/// let stdin = io::stdin();
+/// let info = rdb::parse_reader(stdin.lock(), &mut NoOpEventHandler {}).unwrap();
+/// println!("RDB版本: {}", info.rdb_version);
+/// ```
+///
+/// [`parse_file`]: fn.parse_file.html
+pub fn parse_reader<R: Read>(input: R, event_handler: &mut dyn EventHandler) -> Result<ParseInfo> {
+    let mut input = compress::decompress(input)?;
+    parse_unbuffered(&mut input, event_handler)
+}
+
+/// 与[`parse_reader`]相同，但使用`config`中与RDB解析相关的选项，如`parse_mode`、`db_filter`、`key_filter`、
+/// `is_discard_expired_keys`、`string_chunk_size`、`is_collect_stats`等，与`Listener`解析全量同步的RDB时的行为一致；
+/// 连接相关的选项将被忽略
+///
+/// [`parse_reader`]: fn.parse_reader.html
+pub fn parse_reader_with_config<R: Read>(
+    input: R, config: &Config, event_handler: &mut dyn EventHandler,
+) -> Result<ParseInfo> {
+    let mut input = compress::decompress(input)?;
+    let mut parser = DefaultRDBParser::from_config(config, Arc::new(AtomicBool::new(true)), None);
+    parser.parse(&mut input, 0, event_handler)
+}
+
+/// 将本地RDB文件映射到内存中进行解析，需开启`mmap` feature，解析选项与[`parse_file`]相同
+///
+/// 数据直接从page cache中读取，省去了`read`系统调用以及`BufReader`的二次拷贝，也不再占用额外的缓冲区，
+/// 适合解析数百GB的RDB；文件以顺序访问的方式映射，内核会提前预读，已解析过的部分也可被及时回收。
+/// 解析本身的开销通常大于读取，收益取决于磁盘以及page cache的情况，可使用`benches/parse.rs`进行比较。
+/// 同样支持gzip以及zstd压缩的文件，但此时解压的开销远大于读取，使用mmap几乎没有收益。
+///
+/// 解析期间文件不能被截断或者改写(如被Redis的`BGSAVE`覆盖，Redis会先写入临时文件再重命名，因此不受影响)，
+/// 否则进程可能收到`SIGBUS`信号
+///
+/// ```no_run
+/// use redis_event::{rdb, NoOpEventHandler};
+///
+/// let info = rdb::parse_mmap("dump.rdb", &mut NoOpEventHandler {}).unwrap();
+/// println!("RDB版本: {}", info.rdb_version);
+/// ```
+///
+/// [`parse_file`]: fn.parse_file.html
+#[cfg(feature = "mmap")]
+pub fn parse_mmap<P: AsRef<Path>>(path: P, event_handler: &mut dyn EventHandler) -> Result<ParseInfo> {
+    let file = File::open(path)?;
+    // 空文件无法映射，交给parse_reader产生与parse_file相同的错误
+    if file.metadata()?.len() == 0 {
+        return parse_reader(file, event_handler);
+    }
+    // 安全性：映射期间文件不能被截断或改写，见文档
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    #[cfg(unix)]
+    mmap.advise(memmap2::Advice::Sequential)?;
+    let mut input = compress::decompress_buffered(&mmap[..])?;
+    parse_unbuffered(&mut input, event_handler)
+}
+
+// 与`parse_reader`相同，但不对输入进行缓冲，解析完毕后输入流恰好位于RDB的结尾，用于解析AOF中的RDB preamble
+pub(crate) fn parse_unbuffered(input: &mut dyn Read, event_handler: &mut dyn EventHandler) -> Result<ParseInfo> {
+    let mut parser = DefaultRDBParser {
+        running: Arc::new(AtomicBool::new(true)),
+        module_parser: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        batch_size: BATCH_SIZE,
+        db_filter: None,
+        key_filter: None,
+        is_collect_stats: false,
+    };
+    parser.parse(input, 0, event_handler)
+}
+
+/// 只校验本地RDB文件的结构以及校验和，而不解析其中的值，相当于`redis-check-rdb`，见[`verify_reader`]
+///
+/// [`verify_reader`]: fn.verify_reader.html
+pub fn verify_file<P: AsRef<Path>>(path: P) -> Result<VerifyReport> {
+    Ok(verify_reader(File::open(path)?))
+}
+
+/// 只校验RDB的结构以及crc64校验和，每个值只根据其长度信息读出字节，不进行解码，也不产生任何事件，因此比完整的解析快得多；
+/// 无法仅凭长度跳过的值(如Stream)仍需解析之后丢弃
+///
+/// 校验过程中的错误不会直接返回，而是记录在返回的[`VerifyReport`]中。
+/// 对于全量同步得到的RDB，可通过`Config::rdb_backup_path`保存之后再进行校验
+///
+/// [`VerifyReport`]: struct.VerifyReport.html
+pub fn verify_reader<R: Read>(input: R) -> VerifyReport {
+    let mut info = ParseInfo::new();
+    let mut input = match compress::decompress(input) {
+        Ok(input) => input,
+        Err(err) => return VerifyReport { info, error: Some(err) },
+    };
+    let mut parser = DefaultRDBParser {
+        running: Arc::new(AtomicBool::new(true)),
+        module_parser: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        batch_size: BATCH_SIZE,
+        // 不选择任何db，所有的值都只读出其字节
+        db_filter: Some(Vec::new()),
+        key_filter: None,
+        is_collect_stats: false,
+    };
+    let error = parser.parse_into(&mut input, &mut NoOpEventHandler {}, &mut info).err();
+    VerifyReport { info, error }
+}
+
+// 解析单个值，供`dump::parse`使用，集合类型的数据在一个事件中完整产生
+pub(crate) fn parse_value(
+    input: &mut dyn Read, value_type: u8, key: Vec<u8>, meta: &Meta, event_handler: &mut dyn EventHandler,
+) -> Result<()> {
+    let mut parser = DefaultRDBParser {
+        running: Arc::new(AtomicBool::new(true)),
+        module_parser: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        batch_size: usize::MAX,
+        db_filter: None,
+        key_filter: None,
+        is_collect_stats: false,
+    };
+    let mut is_value_read = false;
+    parser.read_value(input, value_type, key, event_handler, meta, &mut is_value_read)
+}
+
+/// RDB的校验结果
+#[derive(Debug)]
+pub struct VerifyReport {
+    /// RDB的版本、各db中key的个数以及校验和的校验结果，出错时为出错之前已读取到的部分
+    pub info: ParseInfo,
+    /// 校验过程中遇到的错误，出错之后不再继续校验；与解析时一样，其中包装了带有出错位置的[`RDBError`]
+    ///
+    /// [`RDBError`]: struct.RDBError.html
+    pub error: Option<io::Error>,
+}
+
+impl VerifyReport {
+    /// RDB的结构完整，且校验和正确或者未开启校验和
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none() && !matches!(self.info.checksum, ChecksumStatus::Mismatch { .. })
+    }
+
+    /// 数据是否在结束标志之前就已中断，如文件被截断或者传输中断
+    pub fn is_truncated(&self) -> bool {
+        matches!(&self.error, Some(error) if error.kind() == ErrorKind::UnexpectedEof)
+    }
+
+    /// 所有db中key的总数
+    pub fn key_count(&self) -> usize {
+        self.info.db_sizes.iter().map(|size| size.size).sum()
+    }
+}
 
 pub(crate) struct DefaultRDBParser {
     pub(crate) running: Arc<AtomicBool>,
-    pub(crate) module_parser: Option<Rc<RefCell<dyn ModuleParser>>>,
+    pub(crate) module_parser: Option<Box<dyn ModuleParser + Send>>,
+    pub(crate) parse_mode: ParseMode,
+    pub(crate) is_skip_corrupt_keys: bool,
+    pub(crate) is_discard_expired_keys: bool,
+    pub(crate) expire_reference_time: Option<SystemTime>,
+    pub(crate) string_chunk_size: Option<usize>,
+    /// 集合类型的数据每个事件中最多包含的元素个数
+    pub(crate) batch_size: usize,
+    pub(crate) db_filter: Option<Vec<isize>>,
+    pub(crate) key_filter: Option<KeyFilter>,
+    pub(crate) is_collect_stats: bool,
+}
+
+impl DefaultRDBParser {
+    // 使用`config`中与RDB解析相关的选项
+    pub(crate) fn from_config(
+        config: &Config, running: Arc<AtomicBool>, module_parser: Option<Box<dyn ModuleParser + Send>>,
+    ) -> DefaultRDBParser {
+        DefaultRDBParser {
+            running,
+            module_parser,
+            parse_mode: config.parse_mode,
+            is_skip_corrupt_keys: config.is_skip_corrupt_keys,
+            is_discard_expired_keys: config.is_discard_expired_keys,
+            expire_reference_time: config.expire_reference_time,
+            string_chunk_size: config.string_chunk_size,
+            batch_size: if config.is_aggregate_collections {
+                usize::MAX
+            } else {
+                BATCH_SIZE
+            },
+            db_filter: config.db_filter.clone(),
+            key_filter: config.key_filter.clone(),
+            is_collect_stats: config.is_collect_stats,
+        }
+    }
 }
 
 impl RDBParser for DefaultRDBParser {
-    fn parse(&mut self, input: &mut dyn Read, _: i64, event_handler: &mut dyn EventHandler) -> Result<()> {
+    fn parse(&mut self, input: &mut dyn Read, _: i64, event_handler: &mut dyn EventHandler) -> Result<ParseInfo> {
+        let mut info = ParseInfo::new();
+        self.parse_into(input, event_handler, &mut info)?;
+        Ok(info)
+    }
+}
+
+impl DefaultRDBParser {
+    // 解析的结果记录在info中，出错时info中为出错之前已读取到的信息
+    fn parse_into(
+        &mut self, input: &mut dyn Read, event_handler: &mut dyn EventHandler, info: &mut ParseInfo,
+    ) -> Result<()> {
+        let crc = Cell::new(0);
+        let position = Cell::new(0);
+        let opcode = Cell::new(None);
+        let mut input = CrcReader {
+            input,
+            crc: &crc,
+            position: &position,
+        };
+        let mut stats_handler;
+        let event_handler: &mut dyn EventHandler = if self.is_collect_stats {
+            stats_handler = StatsHandler::new(event_handler, self.expire_reference_time);
+            &mut stats_handler
+        } else {
+            event_handler
+        };
+        match self.read_rdb(&mut input, event_handler, info, &crc, &position, &opcode) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(RDBError::wrap(err, position.get(), opcode.get())),
+        }
+    }
+
+    // `position`为已读取的字节数，`opcode`记录当前正在处理的opcode及其位置，用于在出错时提供上下文
+    fn read_rdb(
+        &mut self, input: &mut dyn Read, event_handler: &mut dyn EventHandler, info: &mut ParseInfo, crc: &Cell<u64>,
+        position: &Cell<u64>, opcode: &Cell<Option<(u8, u64)>>,
+    ) -> Result<()> {
         event_handler.handle(Event::RDB(Object::BOR));
         let mut bytes = vec![0; 5];
         // 开头5个字节: REDIS
         input.read_exact(&mut bytes)?;
+        if bytes != b"REDIS" {
+            let message = format!("Invalid RDB magic: {:?}", String::from_utf8_lossy(&bytes));
+            return Err(io::Error::new(ErrorKind::InvalidData, message));
+        }
         // 4个字节: rdb版本
         input.read_exact(&mut bytes[..=3])?;
         let rdb_version = String::from_utf8_lossy(&bytes[..=3]);
-        let rdb_version = rdb_version.parse::<isize>().unwrap();
+        let rdb_version = match rdb_version.parse::<isize>() {
+            Ok(version) => version,
+            Err(_) => {
+                let message = format!("Invalid RDB version: {:?}", rdb_version);
+                return Err(io::Error::new(ErrorKind::InvalidData, message));
+            }
+        };
         let mut db = 0;
+        info.rdb_version = rdb_version;
 
         while self.running.load(Ordering::Relaxed) {
             let mut meta = Meta {
@@ -153,19 +453,29 @@ impl RDBParser for DefaultRDBParser {
                 evict: None,
             };
 
+            let opcode_position = position.get();
             let data_type = input.read_u8()?;
+            opcode.set(Some((data_type, opcode_position)));
             match data_type {
                 RDB_OPCODE_AUX => {
                     let field_name = input.read_string()?;
                     let field_val = input.read_string()?;
-                    let field_name = to_string(field_name);
-                    let field_val = to_string(field_val);
-                    info!("{}:{}", field_name, field_val);
+                    if field_name == b"lua" {
+                        // Redis 7.0之前，master将缓存中的Lua脚本以多个lua字段发送给replica，以便replica能够执行之后的EVALSHA
+                        let cmd = SCRIPTLOAD { script: &field_val };
+                        event_handler.handle(Event::AOF(Command::SCRIPTLOAD(&cmd)));
+                    } else {
+                        let field_name = to_string(field_name);
+                        let field_val = to_string(field_val);
+                        info!("{}:{}", field_name, field_val);
+                        info.aux_fields.insert(field_name, field_val);
+                    }
                 }
                 RDB_OPCODE_SELECTDB => {
                     let (_db, _) = input.read_length()?;
                     meta.db = _db;
                     db = _db;
+                    info.db_count += 1;
                     let cmd = SELECT { db: _db as i32 };
                     event_handler.handle(Event::AOF(Command::SELECT(&cmd)));
                 }
@@ -174,6 +484,12 @@ impl RDBParser for DefaultRDBParser {
                     info!("db[{}] total keys: {}", db, total);
                     let (expired, _) = input.read_length()?;
                     info!("db[{}] expired keys: {}", db, expired);
+                    let resize_db = ResizeDB {
+                        db,
+                        size: total as usize,
+                        expires_size: expired as usize,
+                    };
+                    event_handler.handle(Event::RDB(Object::ResizeDB(resize_db)));
                 }
                 RDB_OPCODE_EXPIRETIME | RDB_OPCODE_EXPIRETIME_MS => {
                     if data_type == RDB_OPCODE_EXPIRETIME_MS {
@@ -189,16 +505,16 @@ impl RDBParser for DefaultRDBParser {
                             let val = input.read_u8()?;
                             let value_type = input.read_u8()?;
                             meta.evict = Option::Some((EvictType::LFU, val as i64));
-                            self.read_object(input, value_type, event_handler, &meta)?;
+                            self.read_object(input, value_type, event_handler, &meta, info)?;
                         }
                         RDB_OPCODE_IDLE => {
                             let (val, _) = input.read_length()?;
                             let value_type = input.read_u8()?;
                             meta.evict = Option::Some((EvictType::LRU, val as i64));
-                            self.read_object(input, value_type, event_handler, &meta)?;
+                            self.read_object(input, value_type, event_handler, &meta, info)?;
                         }
                         _ => {
-                            self.read_object(input, value_type, event_handler, &meta)?;
+                            self.read_object(input, value_type, event_handler, &meta, info)?;
                         }
                     }
                 }
@@ -206,26 +522,59 @@ impl RDBParser for DefaultRDBParser {
                     let val = input.read_u8()?;
                     let value_type = input.read_u8()?;
                     meta.evict = Option::Some((EvictType::LFU, val as i64));
-                    self.read_object(input, value_type, event_handler, &meta)?;
+                    self.read_object(input, value_type, event_handler, &meta, info)?;
                 }
                 RDB_OPCODE_IDLE => {
                     let (val, _) = input.read_length()?;
                     meta.evict = Option::Some((EvictType::LRU, val as i64));
                     let value_type = input.read_u8()?;
-                    self.read_object(input, value_type, event_handler, &meta)?;
+                    self.read_object(input, value_type, event_handler, &meta, info)?;
                 }
                 RDB_OPCODE_MODULE_AUX => {
-                    input.read_length()?;
-                    self.rdb_load_check_module_value(input)?;
+                    let (module_id, _) = input.read_length()?;
+                    let (module_name, module_version) = decode_module_id(module_id as usize);
+                    let (when_opcode, _) = input.read_length()?;
+                    if when_opcode != RDB_MODULE_OPCODE_UINT {
+                        let message = format!("MODULE {} AUX数据的when opcode错误: {}", module_name, when_opcode);
+                        return Err(io::Error::new(ErrorKind::InvalidData, message));
+                    }
+                    let (when, _) = input.read_length()?;
+                    // AUX数据是module的全局数据，并不对应任何key，先完整读出，再交给parser
+                    let mut recorder = RecordReader {
+                        input,
+                        data: Vec::new(),
+                    };
+                    self.rdb_load_check_module_value(&mut recorder)?;
+                    let module = match &mut self.module_parser {
+                        Some(parser) => parser.parse_aux(&module_name, module_version, when as u64, &recorder.data),
+                        None => None,
+                    };
+                    match module {
+                        Some(module) => event_handler.handle(Event::RDB(Object::ModuleAux(module_name, module))),
+                        None => info!("跳过MODULE {}的AUX数据, when: {}", module_name, when),
+                    }
+                }
+                RDB_OPCODE_FUNCTION2 => {
+                    let code = input.read_string()?;
+                    event_handler.handle(Event::RDB(Object::Function(code)));
                 }
                 RDB_OPCODE_EOF => {
                     if rdb_version >= 5 {
-                        input.read_integer(8, true)?;
+                        let actual = crc.get();
+                        let expected = input.read_u64::<LittleEndian>()?;
+                        info.checksum = if expected == 0 {
+                            ChecksumStatus::Disabled
+                        } else if expected == actual {
+                            ChecksumStatus::Valid
+                        } else {
+                            warn!("RDB校验和不一致, 期望: {:x}, 实际: {:x}", expected, actual);
+                            ChecksumStatus::Mismatch { expected, actual }
+                        };
                     }
                     break;
                 }
                 _ => {
-                    self.read_object(input, data_type, event_handler, &meta)?;
+                    self.read_object(input, data_type, event_handler, &meta, info)?;
                 }
             };
         }
@@ -234,30 +583,376 @@ impl RDBParser for DefaultRDBParser {
     }
 }
 
+/// 解析RDB出错时的上下文
+///
+/// 解析RDB时产生的所有错误都会被包装为此错误，作为`io::Error`的内部错误返回，`io::Error`的`kind`与原始错误保持一致，
+/// 可通过`error.get_ref()`再`downcast_ref::<RDBError>()`获取
+#[derive(Debug)]
+pub struct RDBError {
+    /// 出错时已从RDB中读取的字节数
+    pub offset: u64,
+    /// 出错时正在处理的opcode(或数据类型)，及其在RDB中的位置；读取RDB头部时出错为None
+    pub opcode: Option<(u8, u64)>,
+    /// 出错时正在处理的key
+    pub key: Option<Vec<u8>>,
+    /// 原始的错误
+    pub source: io::Error,
+}
+
+impl RDBError {
+    /// 获取原始错误的内部错误，如`UnknownTypeError`、`ParseError`
+    pub fn cause<T: std::error::Error + 'static>(&self) -> Option<&T> {
+        self.source.get_ref().and_then(|inner| inner.downcast_ref::<T>())
+    }
+
+    fn with_key(error: io::Error, key: Vec<u8>) -> io::Error {
+        let kind = error.kind();
+        let rdb_error = RDBError {
+            offset: 0,
+            opcode: None,
+            key: Some(key),
+            source: error,
+        };
+        io::Error::new(kind, rdb_error)
+    }
+
+    fn wrap(error: io::Error, offset: u64, opcode: Option<(u8, u64)>) -> io::Error {
+        let kind = error.kind();
+        let is_rdb_error = error.get_ref().is_some_and(|inner| inner.is::<RDBError>());
+        let mut rdb_error = if is_rdb_error {
+            *error.into_inner().unwrap().downcast::<RDBError>().unwrap()
+        } else {
+            RDBError {
+                offset: 0,
+                opcode: None,
+                key: None,
+                source: error,
+            }
+        };
+        rdb_error.offset = offset;
+        rdb_error.opcode = opcode;
+        io::Error::new(kind, rdb_error)
+    }
+}
+
+impl fmt::Display for RDBError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}, offset: {}", self.source, self.offset)?;
+        if let Some((opcode, position)) = self.opcode {
+            write!(f, ", opcode: {} (offset: {})", opcode, position)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ", key: {}", String::from_utf8_lossy(key))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RDBError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// RDB中出现了无法识别的数据类型，比如更高版本的Redis新增的类型
+///
+/// 包装在[`RDBError`]中返回，可通过`RDBError::cause`获取
+///
+/// [`RDBError`]: struct.RDBError.html
+#[derive(Debug)]
+pub struct UnknownTypeError {
+    /// 数据类型
+    pub type_id: u8,
+    /// 此数据所对应的key
+    pub key: Vec<u8>,
+}
+
+impl fmt::Display for UnknownTypeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown data type: {}, key: {}",
+            self.type_id,
+            String::from_utf8_lossy(&self.key)
+        )
+    }
+}
+
+impl std::error::Error for UnknownTypeError {}
+
+/// RDB中的数据不符合规范，如无法解析的浮点数，仅在`ParseMode::Strict`下返回
+///
+/// 与`UnknownTypeError`一样包装在[`RDBError`]中返回
+///
+/// [`RDBError`]: struct.RDBError.html
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// RDB解析完毕之后得到的信息
+#[derive(Debug, Clone)]
+pub struct ParseInfo {
+    /// RDB的版本
+    pub rdb_version: isize,
+    /// RDB中的AUX字段，如redis-ver、redis-bits、ctime等，其中的Lua脚本(lua字段)以`Command::SCRIPTLOAD`事件产生，不在此列
+    pub aux_fields: HashMap<String, String>,
+    /// RDB中包含的db个数
+    pub db_count: usize,
+    /// 按db出现的顺序，各db中实际读取到的key的个数以及其中设置了过期时间的key的个数，
+    /// 包括被过滤以及已过期而未产生事件的key
+    pub db_sizes: Vec<ResizeDB>,
+    pub checksum: ChecksumStatus,
+}
+
+impl ParseInfo {
+    fn new() -> ParseInfo {
+        ParseInfo {
+            rdb_version: 0,
+            aux_fields: HashMap::new(),
+            db_count: 0,
+            db_sizes: Vec::new(),
+            checksum: ChecksumStatus::NotChecked,
+        }
+    }
+
+    fn count_key(&mut self, meta: &Meta) {
+        // 同一个db中的key是连续的
+        let is_same_db = matches!(self.db_sizes.last(), Some(size) if size.db == meta.db);
+        if !is_same_db {
+            self.db_sizes.push(ResizeDB {
+                db: meta.db,
+                size: 0,
+                expires_size: 0,
+            });
+        }
+        let size = self.db_sizes.last_mut().unwrap();
+        size.size += 1;
+        if meta.expire.is_some() {
+            size.expires_size += 1;
+        }
+    }
+}
+
+/// RDB末尾校验和的校验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// 未校验，RDB版本低于5时没有校验和，或者解析被中断
+    NotChecked,
+    /// Redis关闭了rdbchecksum，校验和为0
+    Disabled,
+    /// 校验通过
+    Valid,
+    /// 校验失败
+    Mismatch { expected: u64, actual: u64 },
+}
+
 impl DefaultRDBParser {
     // 根据传入的数据类型，从流中读取对应类型的数据
     fn read_object(
         &mut self, input: &mut dyn Read, value_type: u8, event_handler: &mut dyn EventHandler, meta: &Meta,
+        info: &mut ParseInfo,
+    ) -> Result<()> {
+        let key = input.read_string()?;
+        info.count_key(meta);
+        if !self.is_selected(&key, meta) {
+            return match self.skip_value(input, value_type, &key, meta) {
+                Ok(()) => Ok(()),
+                Err(error) => Err(RDBError::with_key(error, key)),
+            };
+        }
+        let error_key = key.clone();
+        let mut is_value_read = false;
+        // 已过期的key仍需读出其值，只是不再产生事件
+        let mut discard_handler = NoOpEventHandler {};
+        let event_handler: &mut dyn EventHandler = if self.is_expired(meta) {
+            &mut discard_handler
+        } else {
+            event_handler
+        };
+        match self.read_value(input, value_type, key, event_handler, meta, &mut is_value_read) {
+            Ok(()) => Ok(()),
+            Err(error) if is_value_read && self.is_skip_corrupt_keys => {
+                // 值已被完整读出，出错的只是解码，此时已处于下一个opcode的边界，可以跳过此key继续解析
+                warn!("跳过无法解析的key: {}, {}", String::from_utf8_lossy(&error_key), error);
+                let skipped = SkippedKey {
+                    key: error_key,
+                    value_type,
+                    error,
+                    meta,
+                };
+                event_handler.handle(Event::RDB(Object::SkippedKey(skipped)));
+                Ok(())
+            }
+            Err(error) => Err(RDBError::with_key(error, error_key)),
+        }
+    }
+
+    // 是否满足db和key的过滤条件
+    fn is_selected(&self, key: &[u8], meta: &Meta) -> bool {
+        if let Some(db_filter) = &self.db_filter {
+            if !db_filter.contains(&meta.db) {
+                return false;
+            }
+        }
+        match &self.key_filter {
+            Some(key_filter) => key_filter.matches(key),
+            None => true,
+        }
+    }
+
+    // 根据长度信息跳过一个值，不解析其中的元素，也不产生任何事件
+    fn skip_value(&mut self, input: &mut dyn Read, value_type: u8, key: &[u8], meta: &Meta) -> Result<()> {
+        match value_type {
+            RDB_TYPE_STRING
+            | RDB_TYPE_HASH_ZIPMAP
+            | RDB_TYPE_LIST_ZIPLIST
+            | RDB_TYPE_SET_INTSET
+            | RDB_TYPE_ZSET_ZIPLIST
+            | RDB_TYPE_HASH_ZIPLIST
+            | RDB_TYPE_HASH_LISTPACK
+            | RDB_TYPE_ZSET_LISTPACK => skip_string(input)?,
+            RDB_TYPE_LIST | RDB_TYPE_SET | RDB_TYPE_LIST_QUICKLIST => {
+                let (count, _) = input.read_length()?;
+                for _ in 0..count {
+                    skip_string(input)?;
+                }
+            }
+            RDB_TYPE_HASH => {
+                let (count, _) = input.read_length()?;
+                for _ in 0..count * 2 {
+                    skip_string(input)?;
+                }
+            }
+            RDB_TYPE_ZSET | RDB_TYPE_ZSET_2 => {
+                let (count, _) = input.read_length()?;
+                for _ in 0..count {
+                    skip_string(input)?;
+                    if value_type == RDB_TYPE_ZSET_2 {
+                        skip_bytes(input, 8)?;
+                    } else {
+                        // 253、254、255分别代表NaN、+inf、-inf，其后没有数据
+                        let len = input.read_u8()?;
+                        if len < 253 {
+                            skip_bytes(input, len as u64)?;
+                        }
+                    }
+                }
+            }
+            RDB_TYPE_LIST_QUICKLIST_2 => {
+                let (count, _) = input.read_length()?;
+                for _ in 0..count {
+                    // 节点的类型
+                    input.read_length()?;
+                    skip_string(input)?;
+                }
+            }
+            RDB_TYPE_HASH_LISTPACK_EX => {
+                skip_bytes(input, 8)?;
+                skip_string(input)?;
+            }
+            RDB_TYPE_HASH_METADATA => {
+                skip_bytes(input, 8)?;
+                let (count, _) = input.read_length()?;
+                for _ in 0..count {
+                    // 字段的过期时间
+                    input.read_length()?;
+                    skip_string(input)?;
+                    skip_string(input)?;
+                }
+            }
+            RDB_TYPE_MODULE_2 => {
+                input.read_length()?;
+                self.rdb_load_check_module_value(input)?;
+            }
+            _ => {
+                // 其余类型(如stream、旧版本的module)无法仅凭长度跳过，只能解析之后丢弃
+                let mut handler = NoOpEventHandler {};
+                self.read_value(input, value_type, key.to_vec(), &mut handler, meta, &mut false)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 与Redis一致，过期时间早于当前时间才算过期
+    fn is_expired(&self, meta: &Meta) -> bool {
+        if !self.is_discard_expired_keys {
+            return false;
+        }
+        let expire = match meta.expire_millis() {
+            Some(expire) => expire,
+            None => return false,
+        };
+        let now = self.expire_reference_time.unwrap_or_else(SystemTime::now);
+        let now = match now.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as i64,
+            Err(_) => 0,
+        };
+        expire < now
+    }
+
+    // `is_value_read`: 值是否已从流中被完整读出，仅对整体保存为一个字符串的编码(如ziplist、listpack)有效
+    fn read_value(
+        &mut self, input: &mut dyn Read, value_type: u8, key: Vec<u8>, event_handler: &mut dyn EventHandler,
+        meta: &Meta, is_value_read: &mut bool,
     ) -> Result<()> {
         match value_type {
             RDB_TYPE_STRING => {
-                let key = input.read_string()?;
-                let value = input.read_string()?;
-                event_handler.handle(Event::RDB(Object::String(KeyValue {
-                    key: &key,
-                    value: &value,
-                    meta,
-                })));
+                let (length, is_encoded) = input.read_length()?;
+                match self.string_chunk_size {
+                    // 压缩过的值需要完整读出才能解压，仍然一次性产生
+                    Some(chunk_size) if !is_encoded && length as usize > chunk_size => {
+                        let total_len = length as usize;
+                        let mut chunk = vec![0; chunk_size];
+                        let mut offset = 0;
+                        while offset < total_len {
+                            let len = cmp::min(chunk_size, total_len - offset);
+                            input.read_exact(&mut chunk[..len])?;
+                            let position = if offset == 0 {
+                                ChunkPosition::Begin
+                            } else if offset + len == total_len {
+                                ChunkPosition::End
+                            } else {
+                                ChunkPosition::Continue
+                            };
+                            event_handler.handle(Event::RDB(Object::StringChunk(StringChunk {
+                                key: &key,
+                                chunk: &chunk[..len],
+                                offset,
+                                total_len,
+                                position,
+                                meta,
+                            })));
+                            offset += len;
+                        }
+                    }
+                    _ => {
+                        let value = read_string_content(input, length, is_encoded)?;
+                        event_handler.handle(Event::RDB(Object::String(KeyValue {
+                            key: &key,
+                            value: &value,
+                            meta,
+                        })));
+                    }
+                }
             }
             RDB_TYPE_LIST | RDB_TYPE_SET => {
-                let key = input.read_string()?;
                 let (count, _) = input.read_length()?;
                 let mut iter = StrValIter { count, input };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
                         if let Ok(next_val) = iter.next() {
                             val.push(next_val);
                         } else {
@@ -265,57 +960,77 @@ impl DefaultRDBParser {
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         if value_type == RDB_TYPE_LIST {
                             event_handler.handle(Event::RDB(Object::List(List {
                                 key: &key,
                                 values: &val,
+                                is_first,
+                                is_last: !has_more,
                                 meta,
                             })));
                         } else {
                             event_handler.handle(Event::RDB(Object::Set(Set {
                                 key: &key,
                                 members: &val,
+                                is_first,
+                                is_last: !has_more,
                                 meta,
                             })));
                         }
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_ZSET => {
-                let key = input.read_string()?;
                 let (count, _) = input.read_length()?;
-                let mut iter = SortedSetIter { count, v: 1, input };
+                let mut iter = SortedSetIter {
+                    count,
+                    v: 1,
+                    input,
+                    parse_mode: self.parse_mode,
+                };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
-                        if let Ok(next_val) = iter.next() {
-                            val.push(next_val);
-                        } else {
-                            has_more = false;
-                            break;
+                    for _ in 0..self.batch_size {
+                        match iter.next() {
+                            Ok(next_val) => val.push(next_val),
+                            Err(ref err) if err.kind() == ErrorKind::NotFound => {
+                                has_more = false;
+                                break;
+                            }
+                            Err(err) => return Err(err),
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::SortedSet(SortedSet {
                             key: &key,
                             items: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_ZSET_2 => {
-                let key = input.read_string()?;
                 let (count, _) = input.read_length()?;
-                let mut iter = SortedSetIter { count, v: 2, input };
+                let mut iter = SortedSetIter {
+                    count,
+                    v: 2,
+                    input,
+                    parse_mode: self.parse_mode,
+                };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
                         if let Ok(next_val) = iter.next() {
                             val.push(next_val);
                         } else {
@@ -323,17 +1038,19 @@ impl DefaultRDBParser {
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::SortedSet(SortedSet {
                             key: &key,
                             items: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_HASH => {
-                let key = input.read_string()?;
                 let (count, _) = input.read_length()?;
                 let mut iter = StrValIter {
                     count: count * 2,
@@ -341,186 +1058,354 @@ impl DefaultRDBParser {
                 };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
                         let name;
                         let value;
                         if let Ok(next_val) = iter.next() {
                             name = next_val;
                             value = iter.next().expect("missing hash field value");
-                            val.push(Field { name, value });
+                            val.push(Field {
+                                name,
+                                value,
+                                expire: None,
+                            });
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::Hash(Hash {
                             key: &key,
                             fields: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_HASH_ZIPMAP => {
-                let key = input.read_string()?;
                 let bytes = input.read_string()?;
-                let cursor = &mut Cursor::new(&bytes);
-                cursor.set_position(1);
-                let mut iter = ZipMapIter { has_more: true, cursor };
+                *is_value_read = true;
+                let mut iter = ZipMap::new(&bytes)?;
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut fields = Vec::new();
-                    for _ in 0..BATCH_SIZE {
-                        if let Ok(field) = iter.next() {
-                            fields.push(field);
+                    for _ in 0..self.batch_size {
+                        if let Some(field) = iter.next() {
+                            let (name, value) = field?;
+                            fields.push(Field {
+                                name,
+                                value,
+                                expire: None,
+                            });
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !fields.is_empty() {
+                    if !fields.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::Hash(Hash {
                             key: &key,
                             fields: &fields,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_LIST_ZIPLIST => {
-                let key = input.read_string()?;
                 let bytes = input.read_string()?;
-                let cursor = &mut Cursor::new(bytes);
-                // 跳过ZL_BYTES和ZL_TAIL
-                cursor.set_position(8);
-                let count = cursor.read_u16::<LittleEndian>()? as isize;
-                let mut iter = ZipListIter { count, cursor };
+                *is_value_read = true;
+                let mut iter = ZipList::new(&bytes)?;
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
-                        if let Ok(next_val) = iter.next() {
-                            val.push(next_val);
+                    for _ in 0..self.batch_size {
+                        if let Some(next_val) = iter.next() {
+                            val.push(next_val?);
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::List(List {
                             key: &key,
                             values: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_HASH_ZIPLIST => {
-                let key = input.read_string()?;
                 let bytes = input.read_string()?;
+                *is_value_read = true;
+                let mut iter = ZipList::new(&bytes)?;
+
+                let mut has_more = true;
+                let mut is_first = true;
+                while has_more {
+                    let mut val = Vec::new();
+                    for _ in 0..self.batch_size {
+                        let name;
+                        let value;
+                        if let Some(next_val) = iter.next() {
+                            name = next_val?;
+                            value = iter.next().expect("missing hash field value")?;
+                            val.push(Field {
+                                name,
+                                value,
+                                expire: None,
+                            });
+                        } else {
+                            has_more = false;
+                            break;
+                        }
+                    }
+                    if !val.is_empty() || !is_first {
+                        event_handler.handle(Event::RDB(Object::Hash(Hash {
+                            key: &key,
+                            fields: &val,
+                            is_first,
+                            is_last: !has_more,
+                            meta,
+                        })));
+                        is_first = false;
+                    }
+                }
+            }
+            RDB_TYPE_HASH_LISTPACK => {
+                let bytes = input.read_string()?;
+                *is_value_read = true;
                 let cursor = &mut Cursor::new(bytes);
-                // 跳过ZL_BYTES和ZL_TAIL
-                cursor.set_position(8);
-                let count = cursor.read_u16::<LittleEndian>()? as isize;
-                let mut iter = ZipListIter { count, cursor };
+                // 跳过LP_TOTAL_BYTES和LP_NUM_ELEMENTS
+                cursor.set_position(6);
+                let mut iter = ListPackIter { cursor };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
                         let name;
                         let value;
                         if let Ok(next_val) = iter.next() {
                             name = next_val;
-                            value = iter.next().expect("missing hash field value");
-                            val.push(Field { name, value });
+                            value = iter.next()?;
+                            val.push(Field {
+                                name,
+                                value,
+                                expire: None,
+                            });
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::Hash(Hash {
                             key: &key,
                             fields: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_ZSET_ZIPLIST => {
-                let key = input.read_string()?;
                 let bytes = input.read_string()?;
+                *is_value_read = true;
+                let mut iter = ZipList::new(&bytes)?;
+
+                let mut has_more = true;
+                let mut is_first = true;
+                while has_more {
+                    let mut val = Vec::new();
+                    for _ in 0..self.batch_size {
+                        let member;
+                        let score: f64;
+                        if let Some(next_val) = iter.next() {
+                            member = next_val?;
+                            let score_str = iter.next().expect("missing sorted set element's score")?;
+                            score = parse_number(self.parse_mode, &score_str, 0.0)?;
+                            val.push(Item { member, score });
+                        } else {
+                            has_more = false;
+                            break;
+                        }
+                    }
+                    if !val.is_empty() || !is_first {
+                        event_handler.handle(Event::RDB(Object::SortedSet(SortedSet {
+                            key: &key,
+                            items: &val,
+                            is_first,
+                            is_last: !has_more,
+                            meta,
+                        })));
+                        is_first = false;
+                    }
+                }
+            }
+            RDB_TYPE_HASH_METADATA => {
+                // 所有字段中最早的过期时间，各字段的过期时间以相对于它的偏移量保存
+                let min_expire = input.read_integer(8, false)? as i64;
+                let (count, _) = input.read_length()?;
+                let mut iter = HashMetadataIter {
+                    count,
+                    min_expire,
+                    input,
+                };
+
+                let mut has_more = true;
+                let mut is_first = true;
+                while has_more {
+                    let mut val = Vec::new();
+                    for _ in 0..self.batch_size {
+                        if let Ok(field) = iter.next() {
+                            val.push(field);
+                        } else {
+                            has_more = false;
+                            break;
+                        }
+                    }
+                    if !val.is_empty() || !is_first {
+                        event_handler.handle(Event::RDB(Object::Hash(Hash {
+                            key: &key,
+                            fields: &val,
+                            is_first,
+                            is_last: !has_more,
+                            meta,
+                        })));
+                        is_first = false;
+                    }
+                }
+            }
+            RDB_TYPE_HASH_LISTPACK_EX => {
+                // 所有字段中最早的过期时间，listpack中保存了各字段完整的过期时间，这里用不到
+                input.read_integer(8, false)?;
+                let bytes = input.read_string()?;
+                *is_value_read = true;
                 let cursor = &mut Cursor::new(bytes);
-                // 跳过ZL_BYTES和ZL_TAIL
-                cursor.set_position(8);
-                let count = cursor.read_u16::<LittleEndian>()? as isize;
-                let mut iter = ZipListIter { count, cursor };
+                // 跳过LP_TOTAL_BYTES和LP_NUM_ELEMENTS
+                cursor.set_position(6);
+                let mut iter = ListPackIter { cursor };
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
+                        if let Ok(name) = iter.next() {
+                            let value = iter.next()?;
+                            let expire = iter.next()?;
+                            let expire = parse_number(self.parse_mode, &expire, 0)?;
+                            val.push(Field {
+                                name,
+                                value,
+                                // 0代表此字段没有设置过期时间
+                                expire: if expire == 0 { None } else { Some(expire) },
+                            });
+                        } else {
+                            has_more = false;
+                            break;
+                        }
+                    }
+                    if !val.is_empty() || !is_first {
+                        event_handler.handle(Event::RDB(Object::Hash(Hash {
+                            key: &key,
+                            fields: &val,
+                            is_first,
+                            is_last: !has_more,
+                            meta,
+                        })));
+                        is_first = false;
+                    }
+                }
+            }
+            RDB_TYPE_ZSET_LISTPACK => {
+                let bytes = input.read_string()?;
+                *is_value_read = true;
+                let cursor = &mut Cursor::new(bytes);
+                // 跳过LP_TOTAL_BYTES和LP_NUM_ELEMENTS
+                cursor.set_position(6);
+                let mut iter = ListPackIter { cursor };
+
+                let mut has_more = true;
+                let mut is_first = true;
+                while has_more {
+                    let mut val = Vec::new();
+                    for _ in 0..self.batch_size {
                         let member;
                         let score: f64;
                         if let Ok(next_val) = iter.next() {
                             member = next_val;
-                            let score_str = to_string(iter.next().expect("missing sorted set element's score"));
-                            score = score_str.parse::<f64>().unwrap();
+                            let score_str = iter.next()?;
+                            score = parse_number(self.parse_mode, &score_str, 0.0)?;
                             val.push(Item { member, score });
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::SortedSet(SortedSet {
                             key: &key,
                             items: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_SET_INTSET => {
-                let key = input.read_string()?;
                 let bytes = input.read_string()?;
-                let mut cursor = Cursor::new(&bytes);
-                let encoding = cursor.read_i32::<LittleEndian>()?;
-                let length = cursor.read_u32::<LittleEndian>()?;
-                let mut iter = IntSetIter {
-                    encoding,
-                    count: length as isize,
-                    cursor: &mut cursor,
-                };
+                *is_value_read = true;
+                let mut iter = IntSet::new(&bytes)?;
 
                 let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
-                        if let Ok(next_val) = iter.next() {
-                            val.push(next_val);
+                    for _ in 0..self.batch_size {
+                        if let Some(next_val) = iter.next() {
+                            val.push(next_val.to_string().into_bytes());
                         } else {
                             has_more = false;
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::Set(Set {
                             key: &key,
                             members: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_LIST_QUICKLIST => {
-                let key = input.read_string()?;
                 let (count, _) = input.read_length()?;
                 let mut iter = QuickListIter {
                     len: -1,
@@ -530,9 +1415,42 @@ impl DefaultRDBParser {
                 };
 
                 let mut has_more = true;
+                let mut is_first = true;
+                while has_more {
+                    let mut val = Vec::new();
+                    for _ in 0..self.batch_size {
+                        if let Ok(next_val) = iter.next() {
+                            val.push(next_val);
+                        } else {
+                            has_more = false;
+                            break;
+                        }
+                    }
+                    if !val.is_empty() || !is_first {
+                        event_handler.handle(Event::RDB(Object::List(List {
+                            key: &key,
+                            values: &val,
+                            is_first,
+                            is_last: !has_more,
+                            meta,
+                        })));
+                        is_first = false;
+                    }
+                }
+            }
+            RDB_TYPE_LIST_QUICKLIST_2 => {
+                let (count, _) = input.read_length()?;
+                let mut iter = QuickList2Iter {
+                    count,
+                    input,
+                    cursor: Option::None,
+                };
+
+                let mut has_more = true;
+                let mut is_first = true;
                 while has_more {
                     let mut val = Vec::new();
-                    for _ in 0..BATCH_SIZE {
+                    for _ in 0..self.batch_size {
                         if let Ok(next_val) = iter.next() {
                             val.push(next_val);
                         } else {
@@ -540,58 +1458,85 @@ impl DefaultRDBParser {
                             break;
                         }
                     }
-                    if !val.is_empty() {
+                    if !val.is_empty() || !is_first {
                         event_handler.handle(Event::RDB(Object::List(List {
                             key: &key,
                             values: &val,
+                            is_first,
+                            is_last: !has_more,
                             meta,
                         })));
+                        is_first = false;
                     }
                 }
             }
             RDB_TYPE_MODULE | RDB_TYPE_MODULE_2 => {
-                let key = input.read_string()?;
                 let (module_id, _) = input.read_length()?;
-                let module_id = module_id as usize;
-                let mut array: [char; 9] = [' '; 9];
-                for i in 0..array.len() {
-                    let i1 = 10 + (array.len() - 1 - i) * 6;
-                    let i2 = (module_id >> i1 as usize) as usize;
-                    let i3 = i2 & 63;
-                    let chr = MODULE_SET.get(i3).unwrap();
-                    array[i] = *chr;
-                }
-                let module_name: String = String::from_iter(array.iter());
-                let module_version: usize = module_id & 1023;
-                if self.module_parser.is_none() && value_type == RDB_TYPE_MODULE {
-                    panic!("MODULE {}, version {} 无法解析", module_name, module_version);
+                let (module_name, module_version) = decode_module_id(module_id as usize);
+                let is_module2 = value_type == RDB_TYPE_MODULE_2;
+                let parser = match &mut self.module_parser {
+                    Some(parser) if parser.supports(&module_name, is_module2) => Some(parser),
+                    _ => None,
+                };
+                if parser.is_none() && !is_module2 {
+                    // 旧版本的Module类型的值不带opcode，没有parser时无法得知其长度，也就无法跳过
+                    let message = format!(
+                        "MODULE {}, version {} 无法解析: 没有ModuleParser",
+                        module_name, module_version
+                    );
+                    return Err(io::Error::new(ErrorKind::InvalidData, message));
                 }
-                if let Some(parser) = &mut self.module_parser {
+                if let Some(parser) = parser {
                     let module: Box<dyn Module>;
-                    if value_type == RDB_TYPE_MODULE_2 {
-                        module = parser.borrow_mut().parse(input, &module_name, 2);
-                        let (len, _) = input.read_length()?;
-                        if len != 0 {
-                            panic!(
+                    if is_module2 {
+                        module = parser.parse_module2(input, &module_name, module_version)?;
+                        let (op_code, _) = input.read_length()?;
+                        if op_code != RDB_MODULE_OPCODE_EOF {
+                            let message = format!(
                                 "module '{}' that is not terminated by EOF marker, but {}",
-                                &module_name, len
+                                &module_name, op_code
                             );
+                            irregular(self.parse_mode, message, ())?;
+                            // 跳过parser未读取的剩余数据
+                            skip_module_field(input, op_code)?;
+                            self.rdb_load_check_module_value(input)?;
                         }
                     } else {
-                        module = parser.borrow_mut().parse(input, &module_name, module_version);
+                        module = parser.parse(input, &module_name, module_version);
                     }
                     event_handler.handle(Event::RDB(Object::Module(key, module, meta)));
                 } else {
-                    // 没有parser，并且是Module 2类型的值，那就可以直接跳过了
-                    self.rdb_load_check_module_value(input)?;
+                    // 没有parser(或者parser不支持此Module)，并且是Module 2类型的值，按照opcode的结构读出原始数据，交给handler自行处理
+                    let mut recorder = RecordReader {
+                        input,
+                        data: Vec::new(),
+                    };
+                    self.rdb_load_check_module_value(&mut recorder)?;
+                    let module = RawModule {
+                        name: module_name,
+                        version: module_version,
+                        data: recorder.data,
+                    };
+                    event_handler.handle(Event::RDB(Object::Module(key, Box::new(module), meta)));
                 }
             }
-            RDB_TYPE_STREAM_LISTPACKS => {
-                let key = input.read_string()?;
-                let stream = self.read_stream_list_packs(meta, input)?;
+            RDB_TYPE_STREAM_LISTPACKS | RDB_TYPE_STREAM_LISTPACKS_2 | RDB_TYPE_STREAM_LISTPACKS_3 => {
+                let version = match value_type {
+                    RDB_TYPE_STREAM_LISTPACKS => 1,
+                    RDB_TYPE_STREAM_LISTPACKS_2 => 2,
+                    _ => 3,
+                };
+                let stream = self.read_stream_list_packs(meta, input, version)?;
                 event_handler.handle(Event::RDB(Object::Stream(key, stream)));
             }
-            _ => panic!("unknown data type: {}", value_type),
+            _ => {
+                // 无法得知值的长度，也就无法跳过此值继续解析，只能返回错误
+                let error = UnknownTypeError {
+                    type_id: value_type,
+                    key,
+                };
+                return Err(io::Error::new(ErrorKind::InvalidData, error));
+            }
         }
         Ok(())
     }
@@ -602,20 +1547,14 @@ impl DefaultRDBParser {
             if op_code == RDB_MODULE_OPCODE_EOF {
                 break;
             }
-            if op_code == RDB_MODULE_OPCODE_SINT || op_code == RDB_MODULE_OPCODE_UINT {
-                input.read_length()?;
-            } else if op_code == RDB_MODULE_OPCODE_STRING {
-                input.read_string()?;
-            } else if op_code == RDB_MODULE_OPCODE_FLOAT {
-                input.read_exact(&mut [0; 4])?;
-            } else if op_code == RDB_MODULE_OPCODE_DOUBLE {
-                input.read_exact(&mut [0; 8])?;
-            }
+            skip_module_field(input, op_code)?;
         }
         Ok(())
     }
 
-    fn read_stream_list_packs<'a>(&mut self, meta: &'a Meta, input: &mut dyn Read) -> Result<Stream<'a>> {
+    /// `version`: 1为RDB_TYPE_STREAM_LISTPACKS，2为Redis 7.0新增的RDB_TYPE_STREAM_LISTPACKS_2，
+    /// 3为Redis 7.2新增的RDB_TYPE_STREAM_LISTPACKS_3
+    fn read_stream_list_packs<'a>(&mut self, meta: &'a Meta, input: &mut dyn Read, version: u8) -> Result<Stream<'a>> {
         let mut entries: BTreeMap<ID, Entry> = BTreeMap::new();
         let (length, _) = input.read_length()?;
         for _ in 0..length {
@@ -627,21 +1566,21 @@ impl DefaultRDBParser {
             let raw_list_packs = input.read_string()?;
             let mut list_pack = Cursor::new(&raw_list_packs);
             list_pack.set_position(6);
-            let count = i64::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
-            let deleted = i64::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
-            let num_fields = i32::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
-            let mut tmp_fields = Vec::with_capacity(num_fields as usize);
+            let count: i64 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
+            let deleted: i64 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
+            let num_fields: i32 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
+            let mut tmp_fields = Vec::with_capacity(cmp::min(num_fields as usize, PREALLOC_LIMIT));
             for _ in 0..num_fields {
-                tmp_fields.push(read_list_pack_entry(&mut list_pack)?);
+                tmp_fields.push(listpack::read_entry(&mut list_pack)?);
             }
-            read_list_pack_entry(&mut list_pack)?;
+            listpack::read_entry(&mut list_pack)?;
 
             let total = count + deleted;
             for _ in 0..total {
                 let mut fields = BTreeMap::new();
-                let flag = i32::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
-                let ms = i64::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
-                let seq = i64::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
+                let flag: i32 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
+                let ms: i64 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
+                let seq: i64 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
                 let id = ID {
                     ms: ms + base_id.ms,
                     seq: seq + base_id.seq,
@@ -649,220 +1588,239 @@ impl DefaultRDBParser {
                 let deleted = (flag & 1) != 0;
                 if (flag & 2) != 0 {
                     for i in 0..num_fields {
-                        let value = read_list_pack_entry(&mut list_pack)?;
+                        let value = listpack::read_entry(&mut list_pack)?;
                         let field = tmp_fields.get(i as usize).unwrap().to_vec();
                         fields.insert(field, value);
                     }
                     entries.insert(id, Entry { id, deleted, fields });
                 } else {
-                    let num_fields = i32::from_str(&to_string(read_list_pack_entry(&mut list_pack)?)).unwrap();
+                    let num_fields: i32 = parse_number(self.parse_mode, &listpack::read_entry(&mut list_pack)?, 0)?;
                     for _ in 0..num_fields {
-                        let field = read_list_pack_entry(&mut list_pack)?;
-                        let value = read_list_pack_entry(&mut list_pack)?;
+                        let field = listpack::read_entry(&mut list_pack)?;
+                        let value = listpack::read_entry(&mut list_pack)?;
                         fields.insert(field, value);
                     }
                     entries.insert(id, Entry { id, deleted, fields });
                 }
-                read_list_pack_entry(&mut list_pack)?;
+                listpack::read_entry(&mut list_pack)?;
             }
             let end = list_pack.read_u8()?;
             if end != 255 {
-                panic!("listpack expect 255 but {}", end);
+                irregular(self.parse_mode, format!("listpack expect 255 but {}", end), ())?;
             }
         }
+        // stream中元素的数量
         input.read_length()?;
-        input.read_length()?;
-        input.read_length()?;
+        let last_id = read_stream_id(input)?;
+
+        let mut first_id = None;
+        let mut max_deleted_id = None;
+        let mut entries_added = None;
+        if version >= 2 {
+            first_id = Some(read_stream_id(input)?);
+            max_deleted_id = Some(read_stream_id(input)?);
+            let (added, _) = input.read_length()?;
+            entries_added = Some(added as u64);
+        }
 
         let mut groups: Vec<Group> = Vec::new();
         let (count, _) = input.read_length()?;
         for _ in 0..count {
             let name = input.read_string()?;
-            let (ms, _) = input.read_length()?;
-            let (seq, _) = input.read_length()?;
-            let group_last_id = ID {
-                ms: ms as i64,
-                seq: seq as i64,
-            };
-            groups.push(Group {
-                name,
-                last_id: group_last_id,
-            });
+            let group_last_id = read_stream_id(input)?;
+            let mut entries_read = None;
+            if version >= 2 {
+                let (read, _) = input.read_length()?;
+                entries_read = Some(read as i64);
+            }
 
             let (global_pel, _) = input.read_length()?;
+            let mut pending = Vec::with_capacity(cmp::min(global_pel as usize, PREALLOC_LIMIT));
             for _ in 0..global_pel {
-                read_long(input, 8, false)?;
-                read_long(input, 8, false)?;
-                input.read_integer(8, false)?;
-                input.read_length()?;
+                let id = read_raw_stream_id(input)?;
+                let delivery_time = input.read_integer(8, false)? as i64;
+                let (delivery_count, _) = input.read_length()?;
+                pending.push(PendingEntry {
+                    id,
+                    delivery_time,
+                    delivery_count: delivery_count as u64,
+                });
             }
 
             let (consumer_count, _) = input.read_length()?;
+            let mut consumers = Vec::with_capacity(cmp::min(consumer_count as usize, PREALLOC_LIMIT));
             for _ in 0..consumer_count {
-                input.read_string()?;
-                input.read_integer(8, false)?;
+                let consumer_name = input.read_string()?;
+                let seen_time = input.read_integer(8, false)? as i64;
+                let mut active_time = None;
+                if version >= 3 {
+                    active_time = Some(input.read_integer(8, false)? as i64);
+                }
 
                 let (pel, _) = input.read_length()?;
+                let mut consumer_pending = Vec::with_capacity(cmp::min(pel as usize, PREALLOC_LIMIT));
                 for _ in 0..pel {
-                    read_long(input, 8, false)?;
-                    read_long(input, 8, false)?;
+                    consumer_pending.push(read_raw_stream_id(input)?);
                 }
+                consumers.push(Consumer {
+                    name: consumer_name,
+                    seen_time,
+                    active_time,
+                    pending: consumer_pending,
+                });
             }
+            groups.push(Group {
+                name,
+                last_id: group_last_id,
+                entries_read,
+                pending,
+                consumers,
+            });
         }
-        Ok(Stream { entries, groups, meta })
+        Ok(Stream {
+            entries,
+            groups,
+            last_id,
+            first_id,
+            max_deleted_id,
+            entries_added,
+            meta,
+        })
     }
 }
 
-fn read_long(input: &mut dyn Read, length: i32, little_endian: bool) -> Result<i64> {
-    let mut r: i64 = 0;
-    for i in 0..length {
-        let v: i64 = input.read_u8()? as i64;
-        if little_endian {
-            r |= v << (i << 3) as i64;
-        } else {
-            r = (r << 8) | v;
+// 跳过一个字符串，压缩过的字符串也无需解压
+fn skip_string(input: &mut dyn Read) -> Result<()> {
+    let (length, is_encoded) = input.read_length()?;
+    let length = if is_encoded {
+        match length {
+            RDB_ENC_INT8 => 1,
+            RDB_ENC_INT16 => 2,
+            RDB_ENC_INT32 => 4,
+            RDB_ENC_LZF => {
+                let (compressed_len, _) = input.read_length()?;
+                // 解压之后的长度
+                input.read_length()?;
+                compressed_len
+            }
+            _ => {
+                let message = format!("Invalid string encoding: {}", length);
+                return Err(io::Error::new(ErrorKind::InvalidData, message));
+            }
         }
+    } else {
+        length
+    };
+    if length < 0 {
+        let message = format!("Invalid length: {}", length);
+        return Err(io::Error::new(ErrorKind::InvalidData, message));
     }
-    Ok(r)
+    skip_bytes(input, length as u64)
 }
 
-fn read_list_pack_entry(input: &mut dyn Read) -> Result<Vec<u8>> {
-    let special = input.read_u8()? as i32;
-    let skip: i32;
-    let mut bytes;
-    if (special & 0x80) == 0 {
-        skip = 1;
-        let value = special & 0x7F;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xC0) == 0x80 {
-        let len = special & 0x3F;
-        skip = 1 + len as i32;
-        bytes = vec![0; len as usize];
-        input.read_exact(&mut bytes)?;
-    } else if (special & 0xE0) == 0xC0 {
-        skip = 2;
-        let next = input.read_u8()?;
-        let value = (((special & 0x1F) << 8) | next as i32) << 19 >> 19;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xFF) == 0xF1 {
-        skip = 3;
-        let value = input.read_i16::<LittleEndian>()?;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xFF) == 0xF2 {
-        skip = 4;
-        let value = input.read_i24::<LittleEndian>()?;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xFF) == 0xF3 {
-        skip = 5;
-        let value = input.read_i32::<LittleEndian>()?;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xFF) == 0xF4 {
-        skip = 9;
-        let value = input.read_i64::<LittleEndian>()?;
-        let value = value.to_string();
-        bytes = value.into_bytes();
-    } else if (special & 0xF0) == 0xE0 {
-        let next = input.read_u8()?;
-        let len = ((special & 0x0F) << 8) | next as i32;
-        skip = 2 + len as i32;
-        bytes = vec![0; len as usize];
-        input.read_exact(&mut bytes)?;
-    } else if (special & 0xFF) == 0xF0 {
-        let len = input.read_u32::<BigEndian>()?;
-        skip = 5 + len as i32;
-        bytes = vec![0; len as usize];
-        input.read_exact(&mut bytes)?;
-    } else {
-        panic!("{}", special)
+fn skip_bytes(input: &mut dyn Read, length: u64) -> Result<()> {
+    let skipped = io::copy(&mut Read::take(input, length), &mut io::sink())?;
+    if skipped < length {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "failed to skip the whole value",
+        ));
     }
-    if skip <= 127 {
-        let mut buf = vec![0; 1];
-        input.read_exact(&mut buf)?;
-    } else if skip < 16383 {
-        let mut buf = vec![0; 2];
-        input.read_exact(&mut buf)?;
-    } else if skip < 2097151 {
-        let mut buf = vec![0; 3];
-        input.read_exact(&mut buf)?;
-    } else if skip < 268435455 {
-        let mut buf = vec![0; 4];
-        input.read_exact(&mut buf)?;
-    } else {
-        let mut buf = vec![0; 5];
-        input.read_exact(&mut buf)?;
-    }
-    Ok(bytes)
+    Ok(())
 }
 
-pub(crate) fn read_zm_len(cursor: &mut Cursor<&Vec<u8>>) -> Result<usize> {
-    let len = cursor.read_u8()?;
-    if len <= 253 {
-        return Ok(len as usize);
-    } else if len == 254 {
-        let value = cursor.read_u32::<BigEndian>()?;
-        return Ok(value as usize);
+/// 跳过module opcode所对应的值
+fn skip_module_field(input: &mut dyn Read, op_code: isize) -> Result<()> {
+    if op_code == RDB_MODULE_OPCODE_SINT || op_code == RDB_MODULE_OPCODE_UINT {
+        input.read_length()?;
+    } else if op_code == RDB_MODULE_OPCODE_STRING {
+        input.read_string()?;
+    } else if op_code == RDB_MODULE_OPCODE_FLOAT {
+        input.read_exact(&mut [0; 4])?;
+    } else if op_code == RDB_MODULE_OPCODE_DOUBLE {
+        input.read_exact(&mut [0; 8])?;
     }
-    Ok(len as usize)
+    Ok(())
 }
 
-pub(crate) fn read_zip_list_entry(cursor: &mut Cursor<Vec<u8>>) -> Result<Vec<u8>> {
-    if cursor.read_u8()? >= 254 {
-        cursor.read_u32::<LittleEndian>()?;
-    }
-    let flag = cursor.read_u8()?;
-    match flag >> 6 {
-        0 => {
-            let length = flag & 0x3F;
-            let mut buff = vec![0; length as usize];
-            cursor.read_exact(&mut buff)?;
-            return Ok(buff);
+/// 处理不符合规范的数据: 严格模式下返回`ParseError`，宽松模式下记录日志并返回`default`
+pub(crate) fn irregular<T>(mode: ParseMode, message: String, default: T) -> Result<T> {
+    match mode {
+        ParseMode::Strict => Err(io::Error::new(ErrorKind::InvalidData, ParseError { message })),
+        ParseMode::Lenient => {
+            warn!("{}, 忽略并继续解析", message);
+            Ok(default)
         }
-        1 => {
-            let next_byte = cursor.read_u8()?;
-            let length = (((flag as u16) & 0x3F) << 8) | (next_byte as u16);
-            let mut buff = vec![0; length as usize];
-            cursor.read_exact(&mut buff)?;
-            return Ok(buff);
-        }
-        2 => {
-            let length = cursor.read_u32::<BigEndian>()?;
-            let mut buff = vec![0; length as usize];
-            cursor.read_exact(&mut buff)?;
-            return Ok(buff);
-        }
-        _ => {}
     }
-    return match flag {
-        ZIP_INT_8BIT => {
-            let int = cursor.read_i8()?;
-            Ok(int.to_string().into_bytes())
-        }
-        ZIP_INT_16BIT => {
-            let int = cursor.read_i16::<LittleEndian>()?;
-            Ok(int.to_string().into_bytes())
-        }
-        ZIP_INT_24BIT => {
-            let int = cursor.read_i24::<LittleEndian>()?;
-            Ok(int.to_string().into_bytes())
-        }
-        ZIP_INT_32BIT => {
-            let int = cursor.read_i32::<LittleEndian>()?;
-            Ok(int.to_string().into_bytes())
-        }
-        ZIP_INT_64BIT => {
-            let int = cursor.read_i64::<LittleEndian>()?;
-            Ok(int.to_string().into_bytes())
-        }
+}
+
+/// 读取以字符串形式保存的double，无法解析时按照`mode`进行处理
+pub(crate) fn read_score<R: Read + ?Sized>(input: &mut R, mode: ParseMode) -> Result<f64> {
+    let len = input.read_u8()?;
+    match len {
+        255 => Ok(NEG_INFINITY),
+        254 => Ok(INFINITY),
+        253 => Ok(NAN),
         _ => {
-            let result = (flag - 0xF1) as isize;
-            Ok(result.to_string().into_bytes())
+            let mut buff = vec![0; len as usize];
+            input.read_exact(&mut buff)?;
+            parse_number(mode, &buff, 0.0)
         }
-    };
+    }
+}
+
+/// 将字符串形式的数字解析出来，无法解析时按照`mode`进行处理
+pub(crate) fn parse_number<T: FromStr>(mode: ParseMode, bytes: &[u8], default: T) -> Result<T> {
+    let text = String::from_utf8_lossy(bytes);
+    match text.parse::<T>() {
+        Ok(number) => Ok(number),
+        Err(_) => irregular(mode, format!("无法解析的数字: {}", text), default),
+    }
+}
+
+/// 从module id中解析出module的名字以及版本
+fn decode_module_id(module_id: usize) -> (String, usize) {
+    let mut array: [char; 9] = [' '; 9];
+    for i in 0..array.len() {
+        let i1 = 10 + (array.len() - 1 - i) * 6;
+        let i2 = (module_id >> i1 as usize) as usize;
+        let i3 = i2 & 63;
+        let chr = MODULE_SET.get(i3).unwrap();
+        array[i] = *chr;
+    }
+    let module_name: String = String::from_iter(array.iter());
+    let module_version: usize = module_id & 1023;
+    (module_name, module_version)
+}
+
+/// 读取以16个字节(大端序的ms及seq)表示的stream ID
+fn read_raw_stream_id(input: &mut dyn Read) -> Result<ID> {
+    let ms = read_long(input, 8, false)?;
+    let seq = read_long(input, 8, false)?;
+    Ok(ID { ms, seq })
+}
+
+/// 读取以两个length表示的stream ID
+fn read_stream_id(input: &mut dyn Read) -> Result<ID> {
+    let (ms, _) = input.read_length()?;
+    let (seq, _) = input.read_length()?;
+    Ok(ID {
+        ms: ms as i64,
+        seq: seq as i64,
+    })
+}
+
+fn read_long(input: &mut dyn Read, length: i32, little_endian: bool) -> Result<i64> {
+    let mut r: i64 = 0;
+    for i in 0..length {
+        let v: i64 = input.read_u8()? as i64;
+        if little_endian {
+            r |= v << (i << 3) as i64;
+        } else {
+            r = (r << 8) | v;
+        }
+    }
+    Ok(r)
 }
 
 /// 封装Redis中的各种数据类型，由`RdbHandler`统一处理
@@ -882,16 +1840,227 @@ pub enum Object<'a> {
     Module(Vec<u8>, Box<dyn Module>, &'a Meta),
     /// 代表Redis中的Stream类型数据
     Stream(Vec<u8>, Stream<'a>),
+    /// 代表Redis 7.0中通过FUNCTION LOAD加载的函数库，值为函数库的源码
+    Function(Vec<u8>),
+    /// 代表module的AUX数据，即module的全局数据，第一个值为module的名字，需要`ModuleParser`实现`parse_aux`
+    ModuleAux(String, Box<dyn Module>),
+    /// 代表String类型数据中的一块，仅在设置了`Config::string_chunk_size`时产生
+    StringChunk(StringChunk<'a>),
+    /// 代表因无法解析而被跳过的key，仅在`Config::is_skip_corrupt_keys`为true时产生
+    SkippedKey(SkippedKey<'a>),
+    /// 代表RDB中当前db的key数量，在该db的数据之前产生，可用于预先分配下游的空间以及统计解析进度
+    ResizeDB(ResizeDB),
     /// 代表rdb数据解析开始
     BOR,
+    /// 代表RDB中数据的统计信息，在`Object::EOR`之前产生，仅在`Config::is_collect_stats`为true时产生
+    Stats(&'a RDBStats),
     /// 代表rdb数据解析完毕
     EOR,
 }
 
+/// `Object`的拥有所有权的版本，不再借用解析时的缓冲区，可以保存在队列中，或者交给其他线程处理
+///
+/// 通过`Object::to_owned`得到，各个变体与`Object`一一对应；Module的值无法复制，只保留其key以及元信息，
+/// 需要的话应在转换之前通过`as_any`取出
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OwnedObject {
+    String {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        value: Vec<u8>,
+        meta: Meta,
+    },
+    List {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes_vec"))]
+        values: Vec<Vec<u8>>,
+        is_first: bool,
+        is_last: bool,
+        meta: Meta,
+    },
+    Set {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes_vec"))]
+        members: Vec<Vec<u8>>,
+        is_first: bool,
+        is_last: bool,
+        meta: Meta,
+    },
+    SortedSet {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        items: Vec<Item>,
+        is_first: bool,
+        is_last: bool,
+        meta: Meta,
+    },
+    Hash {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        fields: Vec<Field>,
+        is_first: bool,
+        is_last: bool,
+        meta: Meta,
+    },
+    Module {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        meta: Meta,
+    },
+    Stream {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        entries: BTreeMap<ID, Entry>,
+        groups: Vec<Group>,
+        last_id: ID,
+        first_id: Option<ID>,
+        max_deleted_id: Option<ID>,
+        entries_added: Option<u64>,
+        meta: Meta,
+    },
+    Function(#[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))] Vec<u8>),
+    /// module的名字
+    ModuleAux(String),
+    StringChunk {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        chunk: Vec<u8>,
+        offset: usize,
+        total_len: usize,
+        position: ChunkPosition,
+        meta: Meta,
+    },
+    /// `io::Error`无法复制，只保留其描述
+    SkippedKey {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        key: Vec<u8>,
+        value_type: u8,
+        error: String,
+        meta: Meta,
+    },
+    ResizeDB(ResizeDB),
+    BOR,
+    Stats(RDBStats),
+    EOR,
+}
+
+impl Object<'_> {
+    /// 复制为拥有所有权的`OwnedObject`
+    pub fn to_owned(&self) -> OwnedObject {
+        match self {
+            Object::String(kv) => OwnedObject::String {
+                key: kv.key.to_vec(),
+                value: kv.value.to_vec(),
+                meta: kv.meta.clone(),
+            },
+            Object::List(list) => OwnedObject::List {
+                key: list.key.to_vec(),
+                values: list.values.to_vec(),
+                is_first: list.is_first,
+                is_last: list.is_last,
+                meta: list.meta.clone(),
+            },
+            Object::Set(set) => OwnedObject::Set {
+                key: set.key.to_vec(),
+                members: set.members.to_vec(),
+                is_first: set.is_first,
+                is_last: set.is_last,
+                meta: set.meta.clone(),
+            },
+            Object::SortedSet(sorted_set) => OwnedObject::SortedSet {
+                key: sorted_set.key.to_vec(),
+                items: sorted_set.items.to_vec(),
+                is_first: sorted_set.is_first,
+                is_last: sorted_set.is_last,
+                meta: sorted_set.meta.clone(),
+            },
+            Object::Hash(hash) => OwnedObject::Hash {
+                key: hash.key.to_vec(),
+                fields: hash.fields.to_vec(),
+                is_first: hash.is_first,
+                is_last: hash.is_last,
+                meta: hash.meta.clone(),
+            },
+            Object::Module(key, _, meta) => OwnedObject::Module {
+                key: key.clone(),
+                meta: (*meta).clone(),
+            },
+            Object::Stream(key, stream) => OwnedObject::Stream {
+                key: key.clone(),
+                entries: stream.entries.clone(),
+                groups: stream.groups.clone(),
+                last_id: stream.last_id,
+                first_id: stream.first_id,
+                max_deleted_id: stream.max_deleted_id,
+                entries_added: stream.entries_added,
+                meta: stream.meta.clone(),
+            },
+            Object::Function(code) => OwnedObject::Function(code.clone()),
+            Object::ModuleAux(name, _) => OwnedObject::ModuleAux(name.clone()),
+            Object::StringChunk(chunk) => OwnedObject::StringChunk {
+                key: chunk.key.to_vec(),
+                chunk: chunk.chunk.to_vec(),
+                offset: chunk.offset,
+                total_len: chunk.total_len,
+                position: chunk.position,
+                meta: chunk.meta.clone(),
+            },
+            Object::SkippedKey(skipped) => OwnedObject::SkippedKey {
+                key: skipped.key.clone(),
+                value_type: skipped.value_type,
+                error: skipped.error.to_string(),
+                meta: skipped.meta.clone(),
+            },
+            Object::ResizeDB(resize_db) => OwnedObject::ResizeDB(*resize_db),
+            Object::BOR => OwnedObject::BOR,
+            Object::Stats(stats) => OwnedObject::Stats((*stats).clone()),
+            Object::EOR => OwnedObject::EOR,
+        }
+    }
+}
+
 pub trait Module {
     fn as_any(&self) -> &dyn Any;
 }
 
+/// 未注册`ModuleParser`时，MODULE_2类型的值以原始数据的形式交给handler
+///
+/// 可通过`module.as_any().downcast_ref::<RawModule>()`获取
+#[derive(Debug)]
+pub struct RawModule {
+    /// Module的名字
+    pub name: String,
+    /// Module的版本
+    pub version: usize,
+    /// 未解析的原始数据，由一系列module opcode及其对应的值组成，包括末尾的EOF
+    pub data: Vec<u8>,
+}
+
+impl Module for RawModule {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// 在读取的同时记录下所读取的数据
+struct RecordReader<'a> {
+    input: &'a mut dyn Read,
+    data: Vec<u8>,
+}
+
+impl Read for RecordReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.input.read(buf)?;
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+}
+
 impl Debug for dyn Module {
     fn fmt(&self, _: &mut Formatter) -> result::Result<(), Error> {
         unimplemented!()
@@ -899,7 +2068,8 @@ impl Debug for dyn Module {
 }
 
 /// 数据的元信息, 包括数据过期类型, 内存驱逐类型, 数据所属的db
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Meta {
     /// 数据所属的db
     pub db: isize,
@@ -909,8 +2079,46 @@ pub struct Meta {
     pub evict: Option<(EvictType, i64)>,
 }
 
-/// 过期类型
+impl Meta {
+    /// 以毫秒表示的过期时间(unix时间戳)，可直接用于`PEXPIREAT`或者`RESTORE ... ABSTTL`，不过期时为None
+    pub fn expire_millis(&self) -> Option<i64> {
+        match self.expire {
+            Some((ExpireType::Second, expire)) => Some(expire.saturating_mul(1000)),
+            Some((ExpireType::Millisecond, expire)) => Some(expire),
+            None => None,
+        }
+    }
+}
+
+/// 因无法解析而被跳过的key
+///
+/// 只有整体保存为一个字符串的编码(如ziplist、listpack、intset)才能确定值的边界并跳过；
+/// 出错之前已解析出的部分元素可能已经产生过事件
 #[derive(Debug)]
+pub struct SkippedKey<'a> {
+    pub key: Vec<u8>,
+    /// 值的类型，即RDB中的RDB_TYPE_XXX
+    pub value_type: u8,
+    /// 解析时出现的错误
+    pub error: io::Error,
+    pub meta: &'a Meta,
+}
+
+/// 数据库大小的提示信息，对应RDB中的RESIZEDB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResizeDB {
+    /// 所属的db
+    pub db: isize,
+    /// 此db中key的总数
+    pub size: usize,
+    /// 此db中设置了过期时间的key的数量
+    pub expires_size: usize,
+}
+
+/// 过期类型
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExpireType {
     /// 以秒计算过期时间
     Second,
@@ -919,7 +2127,8 @@ pub enum ExpireType {
 }
 
 /// 内存驱逐类型
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EvictType {
     /// Least Recently Used
     LRU,
@@ -928,7 +2137,7 @@ pub enum EvictType {
 }
 
 /// 代表Redis中的String类型数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct KeyValue<'a> {
     /// 数据的key
     pub key: &'a [u8],
@@ -938,72 +2147,135 @@ pub struct KeyValue<'a> {
     pub meta: &'a Meta,
 }
 
+/// 代表Redis中String类型数据的一块
+///
+/// 值的长度超出`Config::string_chunk_size`时，将按顺序分为多块产生，以免一次性将其读入内存
+#[derive(Debug, Clone)]
+pub struct StringChunk<'a> {
+    /// 数据的key
+    pub key: &'a [u8],
+    /// 此块的内容
+    pub chunk: &'a [u8],
+    /// 此块在整个值中的偏移量
+    pub offset: usize,
+    /// 整个值的长度
+    pub total_len: usize,
+    /// 此块在整个值中的位置
+    pub position: ChunkPosition,
+    /// 数据的元信息
+    pub meta: &'a Meta,
+}
+
+/// 分块在整个值中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ChunkPosition {
+    /// 第一块
+    Begin,
+    /// 中间的块
+    Continue,
+    /// 最后一块
+    End,
+}
+
 /// 代表Redis中的List类型数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct List<'a> {
     /// 数据的key
     pub key: &'a [u8],
     /// Set中所有的元素
     pub values: &'a [Vec<u8>],
+    /// 是否为此key的第一批元素，元素较多时将分为多个事件产生
+    pub is_first: bool,
+    /// 是否为此key的最后一批元素，元素个数恰好为整批时，最后一批可能不包含任何元素
+    pub is_last: bool,
     /// 数据的元信息
     pub meta: &'a Meta,
 }
 
 /// 代表Redis中的Set类型数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Set<'a> {
     /// 数据的key
     pub key: &'a [u8],
     /// Set中所有的元素
     pub members: &'a [Vec<u8>],
+    /// 是否为此key的第一批元素，元素较多时将分为多个事件产生
+    pub is_first: bool,
+    /// 是否为此key的最后一批元素，元素个数恰好为整批时，最后一批可能不包含任何元素
+    pub is_last: bool,
     /// 数据的元信息
     pub meta: &'a Meta,
 }
 
 /// 代表Redis中的SortedSet类型数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SortedSet<'a> {
     /// 数据的key
     pub key: &'a [u8],
     /// SortedSet中所有的元素
     pub items: &'a [Item],
+    /// 是否为此key的第一批元素，元素较多时将分为多个事件产生
+    pub is_first: bool,
+    /// 是否为此key的最后一批元素，元素个数恰好为整批时，最后一批可能不包含任何元素
+    pub is_last: bool,
     /// 数据的元信息
     pub meta: &'a Meta,
 }
 
 /// SortedSet中的一条元素
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Item {
     /// 元素值
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub member: Vec<u8>,
     /// 元素的排序分数
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::score"))]
     pub score: f64,
 }
 
 /// 代表Redis中的Hash类型数据
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Hash<'a> {
     /// 数据的key
     pub key: &'a [u8],
     /// 数据所有的字段
     pub fields: &'a [Field],
+    /// 是否为此key的第一批元素，元素较多时将分为多个事件产生
+    pub is_first: bool,
+    /// 是否为此key的最后一批元素，元素个数恰好为整批时，最后一批可能不包含任何元素
+    pub is_last: bool,
     /// 数据的元信息
     pub meta: &'a Meta,
 }
 
 /// Hash类型数据中的一个字段
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Field {
     /// 字段名
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub name: Vec<u8>,
     /// 字段值
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub value: Vec<u8>,
+    /// 字段的过期时间(unix时间戳，单位毫秒)，None为不过期，通过Redis 7.4的HEXPIRE等命令设置
+    pub expire: Option<i64>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Stream<'a> {
     pub entries: BTreeMap<ID, Entry>,
     pub groups: Vec<Group>,
+    /// 最后一个元素的ID
+    pub last_id: ID,
+    /// 第一个元素的ID，Redis 7.0之前的RDB中没有此信息
+    pub first_id: Option<ID>,
+    /// 被删除的元素中最大的ID，Redis 7.0之前的RDB中没有此信息
+    pub max_deleted_id: Option<ID>,
+    /// stream从创建以来添加过的元素总数，Redis 7.0之前的RDB中没有此信息
+    pub entries_added: Option<u64>,
     /// 数据的元信息
     pub meta: &'a Meta,
 }
@@ -1079,17 +2351,52 @@ impl Ord for ID {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Entry {
     pub id: ID,
     pub deleted: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes_map"))]
     pub fields: BTreeMap<Vec<u8>, Vec<u8>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Group {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub name: Vec<u8>,
     pub last_id: ID,
+    /// 此group已读取的元素数量，-1代表无法得知；Redis 7.0之前的RDB中没有此信息
+    pub entries_read: Option<i64>,
+    /// 此group中已投递但尚未ACK的元素(PEL)
+    pub pending: Vec<PendingEntry>,
+    /// 此group中的所有consumer
+    pub consumers: Vec<Consumer>,
+}
+
+/// consumer group的PEL中的一个元素
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PendingEntry {
+    pub id: ID,
+    /// 最近一次投递的时间，单位毫秒
+    pub delivery_time: i64,
+    /// 投递的次数
+    pub delivery_count: u64,
+}
+
+/// stream consumer group中的一个consumer
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Consumer {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+    pub name: Vec<u8>,
+    /// 最近一次尝试读取(如XREADGROUP)的时间，单位毫秒
+    pub seen_time: i64,
+    /// 最近一次成功读取到数据的时间，单位毫秒；Redis 7.2之前的RDB中没有此信息
+    pub active_time: Option<i64>,
+    /// 投递给此consumer但尚未ACK的元素ID，其投递信息可在`Group::pending`中找到
+    pub pending: Vec<ID>,
 }
 
 /// Map object types to RDB object types.
@@ -1115,10 +2422,20 @@ pub(crate) const RDB_TYPE_ZSET_ZIPLIST: u8 = 12;
 pub(crate) const RDB_TYPE_HASH_ZIPLIST: u8 = 13;
 pub(crate) const RDB_TYPE_LIST_QUICKLIST: u8 = 14;
 pub(crate) const RDB_TYPE_STREAM_LISTPACKS: u8 = 15;
+pub(crate) const RDB_TYPE_HASH_LISTPACK: u8 = 16;
+pub(crate) const RDB_TYPE_ZSET_LISTPACK: u8 = 17;
+pub(crate) const RDB_TYPE_LIST_QUICKLIST_2: u8 = 18;
+pub(crate) const RDB_TYPE_STREAM_LISTPACKS_2: u8 = 19;
+pub(crate) const RDB_TYPE_STREAM_LISTPACKS_3: u8 = 21;
+/// Redis 7.4中带有字段过期时间的Hash
+pub(crate) const RDB_TYPE_HASH_METADATA: u8 = 24;
+pub(crate) const RDB_TYPE_HASH_LISTPACK_EX: u8 = 25;
 
 /// Special RDB opcodes
 ///
 // Module auxiliary data.
+// Function library (Redis 7.0).
+pub(crate) const RDB_OPCODE_FUNCTION2: u8 = 245;
 pub(crate) const RDB_OPCODE_MODULE_AUX: u8 = 247;
 // LRU idle time.
 pub(crate) const RDB_OPCODE_IDLE: u8 = 248;
@@ -1145,6 +2462,11 @@ pub(crate) const RDB_MODULE_OPCODE_STRING: isize = 5;
 pub(crate) const RDB_MODULE_OPCODE_FLOAT: isize = 3;
 pub(crate) const RDB_MODULE_OPCODE_DOUBLE: isize = 4;
 
+/// quicklist中节点的类型，PLAIN为单个元素，PACKED为listpack
+pub(crate) const QUICKLIST_NODE_CONTAINER_PLAIN: isize = 1;
+/// listpack的结束标志
+pub(crate) const LP_EOF: u8 = 255;
+
 pub(crate) const ZIP_INT_8BIT: u8 = 254;
 pub(crate) const ZIP_INT_16BIT: u8 = 192;
 pub(crate) const ZIP_INT_24BIT: u8 = 240;
@@ -1184,6 +2506,10 @@ pub(crate) const RDB_ENC_INT32: isize = 2;
 /// string compressed with FASTLZ
 pub(crate) const RDB_ENC_LZF: isize = 3;
 pub(crate) const BATCH_SIZE: usize = 64;
+// lzf解压后与解压前长度的最大比例
+const LZF_MAX_RATIO: isize = 132;
+// 按照输入中的长度或者元素个数预先分配内存的上限，超出的部分随读取逐步分配
+const PREALLOC_LIMIT: usize = 1 << 16;
 
 pub(crate) const MODULE_SET: [char; 64] = [
     'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W',
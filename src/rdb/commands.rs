@@ -0,0 +1,194 @@
+// 将RDB中的数据转换为等价的写命令，与Redis重写AOF(rewriteAppendOnlyFile)时所生成的命令保持一致
+use crate::rdb::{ChunkPosition, Meta, Object, Stream, BATCH_SIZE, ID};
+
+impl Object<'_> {
+    /// 转换为等价的写命令，每条命令为其各个参数，可通过任意Redis客户端执行，或者以`redis-cli --pipe`的格式写出
+    ///
+    /// - String: `SET`，分块产生时为第一块的`SET`以及之后各块的`APPEND`
+    /// - List、Set、SortedSet、Hash: 分别为`RPUSH`、`SADD`、`ZADD`、`HSET`，与Redis一样每条命令最多包含64个元素；
+    ///   Hash字段的过期时间为`HPEXPIREAT`
+    /// - Stream: 各个元素的`XADD`，以及恢复ID、consumer group和PEL所需的`XSETID`、`XGROUP`、`XCLAIM`
+    /// - 函数库: `FUNCTION LOAD`
+    ///
+    /// 数据的过期时间为`PEXPIREAT`，在数据的最后一批(或最后一块)之后产生；命令中不包含`SELECT`，数据所属的db见`Meta::db`。
+    /// 其他类型的数据(如Module)无法转换，返回空的结果
+    pub fn to_commands(&self) -> Vec<Vec<Vec<u8>>> {
+        let mut commands = Vec::new();
+        match self {
+            Object::String(kv) => {
+                commands.push(vec![b"SET".to_vec(), kv.key.to_vec(), kv.value.to_vec()]);
+                push_expire(&mut commands, kv.key, kv.meta, true);
+            }
+            Object::StringChunk(chunk) => {
+                let name: &[u8] = if chunk.position == ChunkPosition::Begin {
+                    b"SET"
+                } else {
+                    b"APPEND"
+                };
+                commands.push(vec![name.to_vec(), chunk.key.to_vec(), chunk.chunk.to_vec()]);
+                let is_last = chunk.position == ChunkPosition::End;
+                push_expire(&mut commands, chunk.key, chunk.meta, is_last);
+            }
+            Object::List(list) => {
+                for values in list.values.chunks(BATCH_SIZE) {
+                    let mut command = vec![b"RPUSH".to_vec(), list.key.to_vec()];
+                    command.extend(values.iter().cloned());
+                    commands.push(command);
+                }
+                push_expire(&mut commands, list.key, list.meta, list.is_last);
+            }
+            Object::Set(set) => {
+                for members in set.members.chunks(BATCH_SIZE) {
+                    let mut command = vec![b"SADD".to_vec(), set.key.to_vec()];
+                    command.extend(members.iter().cloned());
+                    commands.push(command);
+                }
+                push_expire(&mut commands, set.key, set.meta, set.is_last);
+            }
+            Object::SortedSet(sorted_set) => {
+                for items in sorted_set.items.chunks(BATCH_SIZE) {
+                    let mut command = vec![b"ZADD".to_vec(), sorted_set.key.to_vec()];
+                    for item in items {
+                        command.push(item.score.to_string().into_bytes());
+                        command.push(item.member.clone());
+                    }
+                    commands.push(command);
+                }
+                push_expire(&mut commands, sorted_set.key, sorted_set.meta, sorted_set.is_last);
+            }
+            Object::Hash(hash) => {
+                for fields in hash.fields.chunks(BATCH_SIZE) {
+                    let mut command = vec![b"HSET".to_vec(), hash.key.to_vec()];
+                    for field in fields {
+                        command.push(field.name.clone());
+                        command.push(field.value.clone());
+                    }
+                    commands.push(command);
+                }
+                for field in hash.fields {
+                    if let Some(expire) = field.expire {
+                        commands.push(vec![
+                            b"HPEXPIREAT".to_vec(),
+                            hash.key.to_vec(),
+                            expire.to_string().into_bytes(),
+                            b"FIELDS".to_vec(),
+                            b"1".to_vec(),
+                            field.name.clone(),
+                        ]);
+                    }
+                }
+                push_expire(&mut commands, hash.key, hash.meta, hash.is_last);
+            }
+            Object::Stream(key, stream) => {
+                push_stream(&mut commands, key, stream);
+                push_expire(&mut commands, key, stream.meta, true);
+            }
+            Object::Function(code) => {
+                commands.push(vec![b"FUNCTION".to_vec(), b"LOAD".to_vec(), code.clone()]);
+            }
+            _ => {}
+        }
+        commands
+    }
+}
+
+fn push_expire(commands: &mut Vec<Vec<Vec<u8>>>, key: &[u8], meta: &Meta, is_last: bool) {
+    if !is_last {
+        return;
+    }
+    if let Some(expire) = meta.expire_millis() {
+        commands.push(vec![
+            b"PEXPIREAT".to_vec(),
+            key.to_vec(),
+            expire.to_string().into_bytes(),
+        ]);
+    }
+}
+
+fn push_stream(commands: &mut Vec<Vec<Vec<u8>>>, key: &[u8], stream: &Stream) {
+    let mut is_empty = true;
+    for entry in stream.entries.values().filter(|entry| !entry.deleted) {
+        is_empty = false;
+        let mut command = vec![b"XADD".to_vec(), key.to_vec(), id_bytes(&entry.id)];
+        for (field, value) in &entry.fields {
+            command.push(field.clone());
+            command.push(value.clone());
+        }
+        commands.push(command);
+    }
+    if is_empty {
+        // 与Redis一样，通过添加一个元素之后立即将其裁剪掉，来创建一个空的stream
+        commands.push(vec![
+            b"XADD".to_vec(),
+            key.to_vec(),
+            b"MAXLEN".to_vec(),
+            b"0".to_vec(),
+            id_bytes(&stream.last_id),
+            b"x".to_vec(),
+            b"y".to_vec(),
+        ]);
+    }
+
+    let mut command = vec![b"XSETID".to_vec(), key.to_vec(), id_bytes(&stream.last_id)];
+    if let (Some(entries_added), Some(max_deleted_id)) = (stream.entries_added, &stream.max_deleted_id) {
+        command.push(b"ENTRIESADDED".to_vec());
+        command.push(entries_added.to_string().into_bytes());
+        command.push(b"MAXDELETEDID".to_vec());
+        command.push(id_bytes(max_deleted_id));
+    }
+    commands.push(command);
+
+    for group in &stream.groups {
+        let mut command = vec![
+            b"XGROUP".to_vec(),
+            b"CREATE".to_vec(),
+            key.to_vec(),
+            group.name.clone(),
+            id_bytes(&group.last_id),
+        ];
+        if let Some(entries_read) = group.entries_read {
+            command.push(b"ENTRIESREAD".to_vec());
+            command.push(entries_read.to_string().into_bytes());
+        }
+        commands.push(command);
+
+        for consumer in &group.consumers {
+            if consumer.pending.is_empty() {
+                commands.push(vec![
+                    b"XGROUP".to_vec(),
+                    b"CREATECONSUMER".to_vec(),
+                    key.to_vec(),
+                    group.name.clone(),
+                    consumer.name.clone(),
+                ]);
+                continue;
+            }
+            // 通过XCLAIM ... FORCE将PEL中的元素重新分配给对应的consumer，同时恢复其投递时间以及次数
+            for id in &consumer.pending {
+                let pending = group.pending.iter().find(|pending| pending.id == *id);
+                let (delivery_time, delivery_count) = match pending {
+                    Some(pending) => (pending.delivery_time, pending.delivery_count),
+                    None => (0, 1),
+                };
+                commands.push(vec![
+                    b"XCLAIM".to_vec(),
+                    key.to_vec(),
+                    group.name.clone(),
+                    consumer.name.clone(),
+                    b"0".to_vec(),
+                    id_bytes(id),
+                    b"TIME".to_vec(),
+                    delivery_time.to_string().into_bytes(),
+                    b"RETRYCOUNT".to_vec(),
+                    delivery_count.to_string().into_bytes(),
+                    b"JUSTID".to_vec(),
+                    b"FORCE".to_vec(),
+                ]);
+            }
+        }
+    }
+}
+
+fn id_bytes(id: &ID) -> Vec<u8> {
+    id.to_string().into_bytes()
+}
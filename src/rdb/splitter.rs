@@ -0,0 +1,156 @@
+/*!
+按db或者key将一个RDB拆分为多个RDB文件
+
+[`RDBSplitter`]本身是一个`EventHandler`，每条数据按添加的顺序依次与各个输出的条件进行匹配，写入第一个匹配的输出中，
+都不匹配时写入默认的输出，未设置默认输出时丢弃。可用于将一个大的Redis实例拆分为多个较小的实例。
+
+```no_run
+use std::fs::File;
+use std::io::BufWriter;
+use redis_event::config::KeyFilter;
+use redis_event::rdb::{self, splitter::RDBSplitter, writer::RDBWriter};
+
+let create = |path: &str| RDBWriter::new(BufWriter::new(File::create(path).unwrap()));
+let mut splitter = RDBSplitter::new();
+splitter.with_output(None, Some(KeyFilter::Prefix(b"user:".to_vec())), create("user.rdb"));
+splitter.with_output(Some(vec![1]), None, create("db1.rdb"));
+splitter.with_default_output(create("others.rdb"));
+rdb::parse_file("dump.rdb", &mut splitter).unwrap();
+splitter.finish().unwrap();
+```
+
+[`RDBSplitter`]: struct.RDBSplitter.html
+*/
+use std::io::{Result, Write};
+
+use crate::config::KeyFilter;
+use crate::rdb::writer::RDBWriter;
+use crate::rdb::Object;
+use crate::{Event, EventHandler};
+
+/// 按db或者key将`Event::RDB`事件分别写入多个[`RDBWriter`]的`EventHandler`
+///
+/// `Object::BOR`、`Object::EOR`以及函数库将写入所有的输出；分批产生的集合类型数据，每一批都将写入同一个输出
+///
+/// [`RDBWriter`]: ../writer/struct.RDBWriter.html
+pub struct RDBSplitter<W: Write> {
+    routes: Vec<Route<W>>,
+    default: Option<RDBWriter<W>>,
+}
+
+struct Route<W: Write> {
+    dbs: Option<Vec<isize>>,
+    key_filter: Option<KeyFilter>,
+    writer: RDBWriter<W>,
+}
+
+impl<W: Write> RDBSplitter<W> {
+    pub fn new() -> RDBSplitter<W> {
+        RDBSplitter {
+            routes: Vec::new(),
+            default: None,
+        }
+    }
+
+    /// 添加一个输出，db在`dbs`之中且key满足`key_filter`的数据将写入此输出；条件为None时不作限制
+    pub fn with_output(&mut self, dbs: Option<Vec<isize>>, key_filter: Option<KeyFilter>, writer: RDBWriter<W>) {
+        self.routes.push(Route {
+            dbs,
+            key_filter,
+            writer,
+        });
+    }
+
+    /// 设置默认的输出，不匹配任何条件的数据将写入此输出
+    pub fn with_default_output(&mut self, writer: RDBWriter<W>) {
+        self.default = Some(writer);
+    }
+
+    /// 确认所有的输出都已完整写入，按添加的顺序返回各个输出，以及默认的输出
+    pub fn finish(self) -> Result<(Vec<W>, Option<W>)> {
+        let mut outputs = Vec::with_capacity(self.routes.len());
+        for route in self.routes {
+            outputs.push(route.writer.finish()?);
+        }
+        let default = match self.default {
+            Some(writer) => Some(writer.finish()?),
+            None => None,
+        };
+        Ok((outputs, default))
+    }
+
+    fn route(&mut self, db: isize, key: &[u8]) -> Option<&mut RDBWriter<W>> {
+        let route = self.routes.iter_mut().find(|route| {
+            let is_db_match = match &route.dbs {
+                Some(dbs) => dbs.contains(&db),
+                None => true,
+            };
+            let is_key_match = match &route.key_filter {
+                Some(key_filter) => key_filter.matches(key),
+                None => true,
+            };
+            is_db_match && is_key_match
+        });
+        match route {
+            Some(route) => Some(&mut route.writer),
+            None => self.default.as_mut(),
+        }
+    }
+
+    fn writers(&mut self) -> impl Iterator<Item = &mut RDBWriter<W>> {
+        let routes = self.routes.iter_mut().map(|route| &mut route.writer);
+        routes.chain(self.default.iter_mut())
+    }
+}
+
+impl<W: Write> Default for RDBSplitter<W> {
+    fn default() -> Self {
+        RDBSplitter::new()
+    }
+}
+
+impl<W: Write> EventHandler for RDBSplitter<W> {
+    fn handle(&mut self, event: Event) {
+        let object = match event {
+            Event::RDB(object) => object,
+            Event::AOF(_) => return,
+        };
+        let (db, key) = match &object {
+            Object::String(kv) => (kv.meta.db, kv.key),
+            Object::StringChunk(chunk) => (chunk.meta.db, chunk.key),
+            Object::List(list) => (list.meta.db, list.key),
+            Object::Set(set) => (set.meta.db, set.key),
+            Object::SortedSet(sorted_set) => (sorted_set.meta.db, sorted_set.key),
+            Object::Hash(hash) => (hash.meta.db, hash.key),
+            Object::Stream(key, stream) => (stream.meta.db, key.as_slice()),
+            Object::Module(key, _, meta) => (meta.db, key.as_slice()),
+            Object::SkippedKey(skipped) => (skipped.meta.db, skipped.key.as_slice()),
+            Object::BOR => {
+                self.writers().for_each(|writer| writer.handle(Event::RDB(Object::BOR)));
+                return;
+            }
+            Object::EOR => {
+                self.writers().for_each(|writer| writer.handle(Event::RDB(Object::EOR)));
+                return;
+            }
+            Object::Function(code) => {
+                self.writers()
+                    .for_each(|writer| writer.handle(Event::RDB(Object::Function(code.clone()))));
+                return;
+            }
+            // RDBWriter不会写入这些
+            Object::ResizeDB(_) | Object::ModuleAux(_, _) | Object::Stats(_) => return,
+        };
+        if let Some(writer) = self.route(db, key) {
+            writer.handle(Event::RDB(object));
+        }
+    }
+
+    /// 返回各个输出在写入过程中的第一个错误
+    fn flush(&mut self) -> Result<()> {
+        for writer in self.writers() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+/*!
+RDB快照的统计信息
+
+开启`Config::is_collect_stats`之后，解析RDB的同时统计各类型key的个数、元素个数、每种类型中最大的key、过期时间的分布以及各db的汇总，
+并在`Object::EOR`之前以`Object::Stats`事件交给handler，可用于容量评估以及排查大key，而无需再单独执行`redis-cli --bigkeys`。
+
+统计只包含产生了事件的key，被`db_filter`、`key_filter`过滤掉或者因过期而被丢弃的key不计入其中。
+*/
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::rdb::{ChunkPosition, Meta, Object};
+use crate::{Event, EventHandler};
+
+/// 统计时对key的值的分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ValueType {
+    String,
+    List,
+    Set,
+    SortedSet,
+    Hash,
+    Stream,
+    Module,
+}
+
+/// RDB快照的统计信息，见[模块文档](index.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RDBStats {
+    /// 各类型key的统计
+    pub types: BTreeMap<ValueType, TypeStats>,
+    /// 各db的统计，按db在RDB中出现的顺序
+    pub dbs: Vec<DBStats>,
+    /// 所有key过期时间的分布
+    pub expires: ExpireStats,
+}
+
+impl RDBStats {
+    /// 所有db中key的总数
+    pub fn key_count(&self) -> usize {
+        self.dbs.iter().map(|db| db.keys).sum()
+    }
+}
+
+/// 某一类型key的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TypeStats {
+    /// key的个数
+    pub keys: usize,
+    /// 元素的总数，String的元素个数为1，Stream为其中未删除的元素，Module为0
+    pub elements: usize,
+    /// 值的总字节数，即各元素的member、field、value的长度之和，不包括编码以及Redis内部的开销
+    pub bytes: usize,
+    /// 此类型中最大的key，String按字节数、其余类型按元素个数比较，与`redis-cli --bigkeys`一致
+    pub largest: Option<KeySize>,
+}
+
+/// 某个key的大小
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeySize {
+    pub db: isize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+    pub key: Vec<u8>,
+    pub elements: usize,
+    pub bytes: usize,
+}
+
+/// 某个db的统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DBStats {
+    pub db: isize,
+    /// key的个数
+    pub keys: usize,
+    /// 设置了过期时间的key的个数
+    pub expires: usize,
+    /// 值的总字节数
+    pub bytes: usize,
+}
+
+/// 以解析时的时间(或`Config::expire_reference_time`)为准，key的剩余过期时间的分布
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExpireStats {
+    /// 未设置过期时间
+    pub persistent: usize,
+    /// 已经过期
+    pub expired: usize,
+    /// 1小时之内过期
+    pub within_hour: usize,
+    /// 1小时至1天之内过期
+    pub within_day: usize,
+    /// 1天至7天之内过期
+    pub within_week: usize,
+    /// 7天之后过期
+    pub later: usize,
+}
+
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+const DAY_MILLIS: i64 = 24 * HOUR_MILLIS;
+const WEEK_MILLIS: i64 = 7 * DAY_MILLIS;
+
+impl ExpireStats {
+    fn count(&mut self, expire: Option<i64>, now: i64) {
+        let counter = match expire {
+            None => &mut self.persistent,
+            Some(expire) if expire < now => &mut self.expired,
+            Some(expire) if expire - now < HOUR_MILLIS => &mut self.within_hour,
+            Some(expire) if expire - now < DAY_MILLIS => &mut self.within_day,
+            Some(expire) if expire - now < WEEK_MILLIS => &mut self.within_week,
+            Some(_) => &mut self.later,
+        };
+        *counter += 1;
+    }
+}
+
+// 正在统计中的key，集合类型以及分块的String可能跨越多个事件
+struct Pending {
+    value_type: ValueType,
+    size: KeySize,
+    expire: Option<i64>,
+}
+
+/// 在将事件交给下游之前进行统计的`EventHandler`，并在`Object::EOR`之前产生`Object::Stats`
+pub(crate) struct StatsHandler<'a> {
+    inner: &'a mut dyn EventHandler,
+    stats: RDBStats,
+    pending: Option<Pending>,
+    now: i64,
+}
+
+impl<'a> StatsHandler<'a> {
+    pub(crate) fn new(inner: &'a mut dyn EventHandler, reference_time: Option<SystemTime>) -> StatsHandler<'a> {
+        let now = match reference_time
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+        {
+            Ok(duration) => duration.as_millis() as i64,
+            Err(_) => 0,
+        };
+        StatsHandler {
+            inner,
+            stats: RDBStats::default(),
+            pending: None,
+            now,
+        }
+    }
+
+    // 返回正在统计中的key的大小，`is_first`为true时开始统计一个新的key
+    fn begin(&mut self, value_type: ValueType, meta: &Meta, key: &[u8], is_first: bool) -> &mut KeySize {
+        if is_first || self.pending.is_none() {
+            self.pending = Some(Pending {
+                value_type,
+                size: KeySize {
+                    db: meta.db,
+                    key: key.to_vec(),
+                    elements: 0,
+                    bytes: 0,
+                },
+                expire: meta.expire_millis(),
+            });
+        }
+        &mut self.pending.as_mut().unwrap().size
+    }
+
+    // 此key的所有数据都已收到，计入统计之中
+    fn finish(&mut self) {
+        let Pending {
+            value_type,
+            size,
+            expire,
+        } = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        self.stats.expires.count(expire, self.now);
+
+        let is_new_db = match self.stats.dbs.last() {
+            Some(db) => db.db != size.db,
+            None => true,
+        };
+        if is_new_db {
+            self.stats.dbs.push(DBStats {
+                db: size.db,
+                ..DBStats::default()
+            });
+        }
+        let db = self.stats.dbs.last_mut().unwrap();
+        db.keys += 1;
+        db.bytes += size.bytes;
+        if expire.is_some() {
+            db.expires += 1;
+        }
+
+        let type_stats = self.stats.types.entry(value_type).or_default();
+        type_stats.keys += 1;
+        type_stats.elements += size.elements;
+        type_stats.bytes += size.bytes;
+        let measure = |size: &KeySize| match value_type {
+            ValueType::String => size.bytes,
+            _ => size.elements,
+        };
+        let is_largest = match &type_stats.largest {
+            Some(largest) => measure(&size) > measure(largest),
+            None => true,
+        };
+        if is_largest {
+            type_stats.largest = Some(size);
+        }
+    }
+}
+
+impl EventHandler for StatsHandler<'_> {
+    fn handle(&mut self, event: Event) {
+        if let Event::RDB(object) = &event {
+            match object {
+                Object::String(kv) => {
+                    let size = self.begin(ValueType::String, kv.meta, kv.key, true);
+                    size.elements = 1;
+                    size.bytes = kv.value.len();
+                    self.finish();
+                }
+                Object::StringChunk(chunk) => {
+                    let is_first = chunk.position == ChunkPosition::Begin;
+                    let size = self.begin(ValueType::String, chunk.meta, chunk.key, is_first);
+                    size.elements = 1;
+                    size.bytes += chunk.chunk.len();
+                    if chunk.position == ChunkPosition::End {
+                        self.finish();
+                    }
+                }
+                Object::List(list) => {
+                    let size = self.begin(ValueType::List, list.meta, list.key, list.is_first);
+                    size.elements += list.values.len();
+                    size.bytes += list.values.iter().map(|value| value.len()).sum::<usize>();
+                    if list.is_last {
+                        self.finish();
+                    }
+                }
+                Object::Set(set) => {
+                    let size = self.begin(ValueType::Set, set.meta, set.key, set.is_first);
+                    size.elements += set.members.len();
+                    size.bytes += set.members.iter().map(|member| member.len()).sum::<usize>();
+                    if set.is_last {
+                        self.finish();
+                    }
+                }
+                Object::SortedSet(sorted_set) => {
+                    let (meta, key) = (sorted_set.meta, sorted_set.key);
+                    let size = self.begin(ValueType::SortedSet, meta, key, sorted_set.is_first);
+                    size.elements += sorted_set.items.len();
+                    size.bytes += sorted_set.items.iter().map(|item| item.member.len()).sum::<usize>();
+                    if sorted_set.is_last {
+                        self.finish();
+                    }
+                }
+                Object::Hash(hash) => {
+                    let size = self.begin(ValueType::Hash, hash.meta, hash.key, hash.is_first);
+                    size.elements += hash.fields.len();
+                    for field in hash.fields {
+                        size.bytes += field.name.len() + field.value.len();
+                    }
+                    if hash.is_last {
+                        self.finish();
+                    }
+                }
+                Object::Stream(key, stream) => {
+                    let size = self.begin(ValueType::Stream, stream.meta, key, true);
+                    for entry in stream.entries.values().filter(|entry| !entry.deleted) {
+                        size.elements += 1;
+                        for (name, value) in &entry.fields {
+                            size.bytes += name.len() + value.len();
+                        }
+                    }
+                    self.finish();
+                }
+                Object::Module(key, _, meta) => {
+                    self.begin(ValueType::Module, meta, key, true);
+                    self.finish();
+                }
+                Object::EOR => {
+                    self.inner.handle(Event::RDB(Object::Stats(&self.stats)));
+                }
+                _ => {}
+            }
+        }
+        self.inner.handle(event);
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
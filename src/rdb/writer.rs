@@ -0,0 +1,343 @@
+/*!
+将`Event::RDB`事件重新写为RDB文件
+
+[`RDBWriter`]本身是一个`EventHandler`，配合`Config::db_filter`、`Config::key_filter`等过滤选项，
+可以从监听到的快照或者已有的RDB文件中过滤、改写出新的RDB文件。
+
+```no_run
+use std::fs::File;
+use std::io::BufWriter;
+use redis_event::rdb::{self, writer::RDBWriter};
+
+let output = BufWriter::new(File::create("filtered.rdb").unwrap());
+let mut writer = RDBWriter::new(output);
+rdb::parse_file("dump.rdb", &mut writer).unwrap();
+writer.finish().unwrap();
+```
+
+[`RDBWriter`]: struct.RDBWriter.html
+*/
+use std::io::{Error, ErrorKind, Result, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+#[cfg(not(feature = "tracing"))]
+use log::warn;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::crc64::crc64;
+use crate::dump::{write_length, write_object, write_string};
+use crate::rdb::{
+    ChunkPosition, EvictType, Field, Hash, Item, List, Meta, Object, Set, SortedSet, StringChunk, RDB_OPCODE_EOF,
+    RDB_OPCODE_EXPIRETIME_MS, RDB_OPCODE_FREQ, RDB_OPCODE_FUNCTION2, RDB_OPCODE_IDLE, RDB_OPCODE_SELECTDB,
+    RDB_TYPE_STRING,
+};
+use crate::{Event, EventHandler};
+
+// 默认的RDB版本(Redis 5.0)，所写入的数据类型均可被Redis 5.0及之后的版本加载
+const DEFAULT_RDB_VERSION: u16 = 9;
+// RDB_OPCODE_FUNCTION2最早出现的版本
+const RDB_VERSION_FUNCTION: u16 = 10;
+
+/// 将`Event::RDB`事件写为RDB文件的`EventHandler`
+///
+/// 在`Object::BOR`时写入RDB的头部，在`Object::EOR`时写入结束标志以及crc64校验和。
+/// 集合类型的数据若分批产生，将在最后一批到达之后整体写入；String类型的数据块则直接写入。
+/// Stream以及Module类型的数据无法写入，遇到时将出错。
+///
+/// `EventHandler::handle`无法返回错误，写入过程中的第一个错误将被记录下来，之后的事件将被忽略，
+/// 可通过[`flush`]或者[`finish`]得到此错误
+///
+/// [`flush`]: #method.flush
+/// [`finish`]: #method.finish
+pub struct RDBWriter<W: Write> {
+    output: W,
+    rdb_version: u16,
+    crc: u64,
+    db: Option<isize>,
+    pending: Option<Pending>,
+    is_finished: bool,
+    error: Option<Error>,
+}
+
+// 分批产生的集合类型数据，在最后一批到达之前暂存于此
+struct Pending {
+    key: Vec<u8>,
+    values: PendingValues,
+}
+
+enum PendingValues {
+    List(Vec<Vec<u8>>),
+    Set(Vec<Vec<u8>>),
+    SortedSet(Vec<Item>),
+    Hash(Vec<Field>),
+}
+
+impl<W: Write> RDBWriter<W> {
+    pub fn new(output: W) -> RDBWriter<W> {
+        RDBWriter {
+            output,
+            rdb_version: DEFAULT_RDB_VERSION,
+            crc: 0,
+            db: None,
+            pending: None,
+            is_finished: false,
+            error: None,
+        }
+    }
+
+    /// 指定写入的RDB版本，默认为9；需要写入Redis 7.0的函数库或者Redis 7.4带有字段过期时间的Hash时，需要指定更高的版本
+    pub fn with_rdb_version(&mut self, rdb_version: u16) {
+        self.rdb_version = rdb_version;
+    }
+
+    /// 确认已收到`Object::EOR`，返回写入过程中的错误或者底层的输出
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        if !self.is_finished {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "尚未收到Object::EOR，RDB不完整"));
+        }
+        Ok(self.output)
+    }
+
+    fn write_event(&mut self, object: &Object) -> Result<()> {
+        match object {
+            Object::BOR => {
+                let header = format!("REDIS{:04}", self.rdb_version);
+                self.write(header.as_bytes())?;
+            }
+            Object::EOR => {
+                if let Some(pending) = &self.pending {
+                    let message = format!("key {}的最后一批数据缺失", to_lossy(&pending.key));
+                    return Err(Error::new(ErrorKind::InvalidData, message));
+                }
+                self.write(&[RDB_OPCODE_EOF])?;
+                self.output.write_u64::<LittleEndian>(self.crc)?;
+                self.output.flush()?;
+                self.is_finished = true;
+            }
+            Object::String(kv) => self.write_key_value(kv.key, object, kv.meta)?,
+            Object::StringChunk(chunk) => self.write_chunk(chunk)?,
+            Object::List(list) => {
+                let batch = || PendingValues::List(list.values.to_vec());
+                self.write_batch(list.key, object, list.is_first, list.is_last, list.meta, batch)?;
+            }
+            Object::Set(set) => {
+                let batch = || PendingValues::Set(set.members.to_vec());
+                self.write_batch(set.key, object, set.is_first, set.is_last, set.meta, batch)?;
+            }
+            Object::SortedSet(sorted_set) => {
+                let batch = || PendingValues::SortedSet(sorted_set.items.iter().map(clone_item).collect());
+                let (is_first, is_last) = (sorted_set.is_first, sorted_set.is_last);
+                self.write_batch(sorted_set.key, object, is_first, is_last, sorted_set.meta, batch)?;
+            }
+            Object::Hash(hash) => {
+                let batch = || PendingValues::Hash(hash.fields.iter().map(clone_field).collect());
+                self.write_batch(hash.key, object, hash.is_first, hash.is_last, hash.meta, batch)?;
+            }
+            Object::Function(code) => {
+                if self.rdb_version < RDB_VERSION_FUNCTION {
+                    return Err(self.version_error(RDB_VERSION_FUNCTION));
+                }
+                let mut buf = vec![RDB_OPCODE_FUNCTION2];
+                write_string(&mut buf, code);
+                self.write(&buf)?;
+            }
+            Object::Stream(key, _) | Object::Module(key, _, _) => {
+                let message = format!("无法写入key {}: 不支持写入Stream以及Module类型的数据", to_lossy(key));
+                return Err(Error::new(ErrorKind::InvalidInput, message));
+            }
+            Object::SkippedKey(skipped) => {
+                warn!("key {}因无法解析而被跳过, 不会被写入RDB", to_lossy(&skipped.key));
+            }
+            // RESIZEDB只是加载时预先分配空间的提示，过滤之后其中的数量已不准确，因此不再写入
+            Object::ResizeDB(_) | Object::ModuleAux(_, _) | Object::Stats(_) => {}
+        }
+        Ok(())
+    }
+
+    // 只有一批时直接写入，否则暂存起来，直到最后一批到达
+    fn write_batch<F: FnOnce() -> PendingValues>(
+        &mut self, key: &[u8], object: &Object, is_first: bool, is_last: bool, meta: &Meta, batch: F,
+    ) -> Result<()> {
+        if is_first && is_last {
+            return self.write_key_value(key, object, meta);
+        }
+        if is_first {
+            if let Some(pending) = &self.pending {
+                let message = format!("key {}的最后一批数据缺失", to_lossy(&pending.key));
+                return Err(Error::new(ErrorKind::InvalidData, message));
+            }
+            self.pending = Some(Pending {
+                key: key.to_vec(),
+                values: batch(),
+            });
+        } else {
+            match &mut self.pending {
+                Some(pending) if pending.key == key => pending.values.append(batch())?,
+                _ => {
+                    let message = format!("key {}的第一批数据缺失", to_lossy(key));
+                    return Err(Error::new(ErrorKind::InvalidData, message));
+                }
+            }
+        }
+        if is_last {
+            if let Some(pending) = self.pending.take() {
+                let object = pending.values.to_object(&pending.key, meta);
+                self.write_key_value(&pending.key, &object, meta)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_key_value(&mut self, key: &[u8], object: &Object, meta: &Meta) -> Result<()> {
+        // write_object写入的是类型以及值，key需要插入到两者之间
+        let mut value = Vec::new();
+        let rdb_version = write_object(&mut value, object)?;
+        if rdb_version > self.rdb_version {
+            return Err(self.version_error(rdb_version));
+        }
+        let mut buf = self.meta_bytes(meta);
+        buf.push(value[0]);
+        write_string(&mut buf, key);
+        buf.extend_from_slice(&value[1..]);
+        self.write(&buf)
+    }
+
+    fn write_chunk(&mut self, chunk: &StringChunk) -> Result<()> {
+        if chunk.position == ChunkPosition::Begin {
+            let mut buf = self.meta_bytes(chunk.meta);
+            buf.push(RDB_TYPE_STRING);
+            write_string(&mut buf, chunk.key);
+            write_length(&mut buf, chunk.total_len as u64);
+            self.write(&buf)?;
+        }
+        self.write(chunk.chunk)
+    }
+
+    // 切换db，以及数据的过期时间、LRU/LFU信息
+    fn meta_bytes(&mut self, meta: &Meta) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if self.db != Some(meta.db) {
+            buf.push(RDB_OPCODE_SELECTDB);
+            write_length(&mut buf, meta.db as u64);
+            self.db = Some(meta.db);
+        }
+        if let Some(expire) = meta.expire_millis() {
+            buf.push(RDB_OPCODE_EXPIRETIME_MS);
+            buf.extend_from_slice(&expire.to_le_bytes());
+        }
+        // RDB版本9之前没有IDLE和FREQ
+        if self.rdb_version >= 9 {
+            match meta.evict {
+                Some((EvictType::LRU, idle)) => {
+                    buf.push(RDB_OPCODE_IDLE);
+                    write_length(&mut buf, idle as u64);
+                }
+                Some((EvictType::LFU, freq)) => {
+                    buf.push(RDB_OPCODE_FREQ);
+                    buf.push(freq as u8);
+                }
+                None => {}
+            }
+        }
+        buf
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.crc = crc64(self.crc, data);
+        self.output.write_all(data)
+    }
+
+    fn version_error(&self, rdb_version: u16) -> Error {
+        let message = format!("所写入的数据需要RDB版本{}, 当前为{}", rdb_version, self.rdb_version);
+        Error::new(ErrorKind::InvalidInput, message)
+    }
+}
+
+impl<W: Write> EventHandler for RDBWriter<W> {
+    fn handle(&mut self, event: Event) {
+        if self.error.is_some() {
+            return;
+        }
+        // SELECT等命令对于RDB没有意义，db的切换以Meta中的db为准
+        if let Event::RDB(object) = &event {
+            if let Err(err) = self.write_event(object) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    /// 返回写入过程中的第一个错误
+    fn flush(&mut self) -> Result<()> {
+        match &self.error {
+            Some(err) => Err(Error::new(err.kind(), err.to_string())),
+            None => self.output.flush(),
+        }
+    }
+}
+
+impl PendingValues {
+    fn append(&mut self, other: PendingValues) -> Result<()> {
+        match (self, other) {
+            (PendingValues::List(values), PendingValues::List(other)) => values.extend(other),
+            (PendingValues::Set(members), PendingValues::Set(other)) => members.extend(other),
+            (PendingValues::SortedSet(items), PendingValues::SortedSet(other)) => items.extend(other),
+            (PendingValues::Hash(fields), PendingValues::Hash(other)) => fields.extend(other),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "同一个key的各批数据类型不一致")),
+        }
+        Ok(())
+    }
+
+    fn to_object<'a>(&'a self, key: &'a [u8], meta: &'a Meta) -> Object<'a> {
+        match self {
+            PendingValues::List(values) => Object::List(List {
+                key,
+                values,
+                is_first: true,
+                is_last: true,
+                meta,
+            }),
+            PendingValues::Set(members) => Object::Set(Set {
+                key,
+                members,
+                is_first: true,
+                is_last: true,
+                meta,
+            }),
+            PendingValues::SortedSet(items) => Object::SortedSet(SortedSet {
+                key,
+                items,
+                is_first: true,
+                is_last: true,
+                meta,
+            }),
+            PendingValues::Hash(fields) => Object::Hash(Hash {
+                key,
+                fields,
+                is_first: true,
+                is_last: true,
+                meta,
+            }),
+        }
+    }
+}
+
+fn clone_item(item: &Item) -> Item {
+    Item {
+        member: item.member.clone(),
+        score: item.score,
+    }
+}
+
+fn clone_field(field: &Field) -> Field {
+    Field {
+        name: field.name.clone(),
+        value: field.value.clone(),
+        expire: field.expire,
+    }
+}
+
+fn to_lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).to_string()
+}
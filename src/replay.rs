@@ -0,0 +1,349 @@
+/*!
+将事件写入到另一个Redis，用于在线迁移或者实时同步
+
+```no_run
+use redis_event::config::Config;
+use redis_event::listener::Builder;
+use redis_event::normalize::Normalizer;
+use redis_event::replay::{ReplayConfig, Replayer};
+use redis_event::RedisListener;
+
+fn run(mut config: Config) -> std::io::Result<()> {
+    // RESTORE需要完整的值
+    config.is_aggregate_collections = true;
+    let mut target = ReplayConfig::new("10.0.0.2", 6379);
+    target.password = String::from("secret");
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    // 将EXPIRE等相对时间改写为绝对时间，延迟写入时过期时间保持不变
+    builder.with_event_handler(Box::new(Normalizer::new(Replayer::new(target))));
+    builder.build().start()
+}
+```
+
+RDB中的数据默认通过`RESTORE`写入，过期时间以`ABSTTL`的形式保留；分批产生的集合、分块产生的String以及Stream无法`RESTORE`，
+改为通过等价的写命令(见`Object::to_commands`)写入，过期时间为`PEXPIREAT`。Module的值无法写入，将被跳过。
+命令则原样写入，`SELECT`随之切换目标Redis的db。
+
+命令以pipeline的形式发送，每`ReplayConfig::pipeline_size`条命令(以及RDB结束和`flush`时)读取一次响应。
+连接断开时将重新连接，并重新发送尚未收到响应的命令，因此同一条命令可能被执行多次；
+重试`ReplayConfig::max_retries`次之后仍失败时，通过`HandlerError::Retry`交给监听器重新同步。
+Redis返回的错误(如`RESTORE`的key已存在)只记录日志，不会中断写入。暂不支持TLS。
+*/
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Result, Write};
+use std::net::TcpStream;
+use std::thread::sleep;
+use std::time::Duration;
+
+#[cfg(not(feature = "tracing"))]
+use log::{info, warn};
+#[cfg(feature = "tracing")]
+use tracing::{info, warn};
+
+use crate::dump;
+use crate::rdb::{Meta, Object};
+use crate::resp::{encode_command, Resp, RespDecode};
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// RDB中的数据的写入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// 通过`RESTORE`写入，无法`RESTORE`的数据改为写命令
+    Restore,
+    /// 全部通过等价的写命令写入，适用于不支持`RESTORE`或者RDB版本更低的目标
+    Commands,
+}
+
+/// 目标Redis的连接信息以及写入的方式
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    pub host: String,
+    pub port: u16,
+    /// 用户名，为空时只使用密码认证
+    pub username: String,
+    /// 密码，为空时不认证
+    pub password: String,
+    pub mode: ReplayMode,
+    /// 为true时覆盖目标中已存在的key，即`RESTORE ... REPLACE`，写命令时则先`DEL`
+    pub is_replace: bool,
+    /// 每读取一次响应之前最多发送的命令条数，至少为1
+    pub pipeline_size: usize,
+    /// 连接断开之后的重试次数
+    pub max_retries: u32,
+    /// 每次重试之前等待的时间
+    pub retry_interval: Duration,
+    pub read_timeout: Option<Duration>,
+    pub write_timeout: Option<Duration>,
+}
+
+impl ReplayConfig {
+    /// 以默认的设置写入`host:port`: 不认证，通过`RESTORE`写入并覆盖已存在的key，pipeline为256条命令，每隔1秒重试，最多3次
+    pub fn new(host: &str, port: u16) -> ReplayConfig {
+        ReplayConfig {
+            host: host.to_string(),
+            port,
+            username: String::new(),
+            password: String::new(),
+            mode: ReplayMode::Restore,
+            is_replace: true,
+            pipeline_size: 256,
+            max_retries: 3,
+            retry_interval: Duration::from_secs(1),
+            read_timeout: None,
+            write_timeout: None,
+        }
+    }
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+/// 将事件写入到目标Redis的处理器，见[模块文档](index.html)
+pub struct Replayer {
+    config: ReplayConfig,
+    conn: Option<Connection>,
+    // 已发送但尚未收到响应的命令，重新连接之后再次发送
+    pending: Vec<Vec<Vec<u8>>>,
+    // 所有命令执行之后目标所在的db
+    db: isize,
+    // pending中的命令执行之前目标所在的db
+    acked_db: isize,
+    error: Option<HandlerError>,
+}
+
+impl Replayer {
+    pub fn new(config: ReplayConfig) -> Replayer {
+        Replayer {
+            config,
+            conn: None,
+            pending: Vec::new(),
+            db: 0,
+            acked_db: 0,
+            error: None,
+        }
+    }
+
+    fn replay_object(&mut self, object: &Object) {
+        let (key, meta) = match object {
+            Object::String(kv) => (kv.key, kv.meta),
+            Object::StringChunk(chunk) => (chunk.key, chunk.meta),
+            Object::List(list) => (list.key, list.meta),
+            Object::Set(set) => (set.key, set.meta),
+            Object::SortedSet(sorted_set) => (sorted_set.key, sorted_set.meta),
+            Object::Hash(hash) => (hash.key, hash.meta),
+            Object::Stream(key, stream) => (key.as_slice(), stream.meta),
+            Object::Function(_) => return self.queue_all(object.to_commands()),
+            Object::Module(key, _, _) => {
+                warn!("无法写入Module的值, 已跳过key: {}", String::from_utf8_lossy(key));
+                return;
+            }
+            Object::EOR => return self.sync(),
+            Object::BOR | Object::ModuleAux(_, _) | Object::SkippedKey(_) | Object::ResizeDB(_) | Object::Stats(_) => {
+                return
+            }
+        };
+        if meta.db != self.db {
+            self.queue(vec![b"SELECT".to_vec(), meta.db.to_string().into_bytes()], false);
+        }
+        if self.config.mode == ReplayMode::Restore {
+            match dump::dump(object) {
+                Ok(payload) => return self.queue(self.restore_command(key, payload, meta), false),
+                Err(error) if error.kind() == ErrorKind::InvalidInput => {}
+                Err(error) => return self.fail(error),
+            }
+        }
+        if self.config.is_replace && is_first(object) {
+            self.queue(vec![b"DEL".to_vec(), key.to_vec()], false);
+        }
+        self.queue_all(object.to_commands());
+    }
+
+    fn restore_command(&self, key: &[u8], payload: Vec<u8>, meta: &Meta) -> Vec<Vec<u8>> {
+        let ttl = meta.expire_millis();
+        let mut command = vec![
+            b"RESTORE".to_vec(),
+            key.to_vec(),
+            ttl.unwrap_or(0).to_string().into_bytes(),
+            payload,
+        ];
+        if self.config.is_replace {
+            command.push(b"REPLACE".to_vec());
+        }
+        if ttl.is_some() {
+            command.push(b"ABSTTL".to_vec());
+        }
+        command
+    }
+
+    fn queue_all(&mut self, commands: Vec<Vec<Vec<u8>>>) {
+        for command in commands {
+            self.queue(command, false);
+        }
+    }
+
+    // 发送一条命令，`is_immediate`为true时立即写出，否则等待缓冲区写满或者读取响应时再写出
+    fn queue(&mut self, command: Vec<Vec<u8>>, is_immediate: bool) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(error) = self.try_queue(command, is_immediate) {
+            self.recover(error);
+        }
+    }
+
+    fn try_queue(&mut self, command: Vec<Vec<u8>>, is_immediate: bool) -> Result<()> {
+        if command[0].eq_ignore_ascii_case(b"SELECT") {
+            if let Some(db) = command.get(1).and_then(|db| String::from_utf8_lossy(db).parse().ok()) {
+                self.db = db;
+            }
+        }
+        let buf = encode_command(&command);
+        self.pending.push(command);
+        let conn = self.connection()?;
+        conn.writer.write_all(&buf)?;
+        if is_immediate {
+            conn.writer.flush()?;
+        }
+        if self.pending.len() >= self.config.pipeline_size.max(1) {
+            self.try_sync()?;
+        }
+        Ok(())
+    }
+
+    // 读取所有已发送命令的响应
+    fn sync(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(error) = self.try_sync() {
+            self.recover(error);
+        }
+    }
+
+    fn try_sync(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.connection()?;
+        let conn = self.conn.as_mut().unwrap();
+        conn.writer.flush()?;
+        for command in &self.pending {
+            if let Resp::Error(message) = conn.reader.decode_resp()? {
+                warn!("{}执行出错: {}", String::from_utf8_lossy(&command[0]), message);
+            }
+        }
+        self.pending.clear();
+        self.acked_db = self.db;
+        Ok(())
+    }
+
+    fn connection(&mut self) -> Result<&mut Connection> {
+        if self.conn.is_none() {
+            self.conn = Some(self.connect()?);
+        }
+        Ok(self.conn.as_mut().unwrap())
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        let addr = format!("{}:{}", self.config.host, self.config.port);
+        let stream = TcpStream::connect(&addr)?;
+        stream.set_read_timeout(self.config.read_timeout)?;
+        stream.set_write_timeout(self.config.write_timeout)?;
+        let mut conn = Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        };
+        if !self.config.password.is_empty() {
+            let mut command = vec![b"AUTH".to_vec()];
+            if !self.config.username.is_empty() {
+                command.push(self.config.username.as_bytes().to_vec());
+            }
+            command.push(self.config.password.as_bytes().to_vec());
+            conn.writer.write_all(&encode_command(&command))?;
+            conn.writer.flush()?;
+            if let Resp::Error(message) = conn.reader.decode_resp()? {
+                return Err(Error::new(ErrorKind::PermissionDenied, message));
+            }
+        }
+        info!("Connected to replay target {}", &addr);
+        Ok(conn)
+    }
+
+    // 重新连接，切换到pending中的命令执行之前的db之后重新发送这些命令，仍然失败时交给监听器重新同步
+    fn recover(&mut self, mut error: Error) {
+        for attempt in 1..=self.config.max_retries {
+            warn!(
+                "写入目标Redis出错: {}, {:?}之后第{}次重试",
+                error, self.config.retry_interval, attempt
+            );
+            self.conn = None;
+            sleep(self.config.retry_interval);
+            match self.resend() {
+                Ok(()) => return,
+                Err(e) => error = e,
+            }
+        }
+        self.fail(error);
+    }
+
+    fn resend(&mut self) -> Result<()> {
+        let mut commands = Vec::with_capacity(self.pending.len() + 1);
+        if self.acked_db != 0 {
+            commands.push(vec![b"SELECT".to_vec(), self.acked_db.to_string().into_bytes()]);
+        }
+        commands.append(&mut self.pending);
+        let conn = self.connection()?;
+        for command in &commands {
+            conn.writer.write_all(&encode_command(command))?;
+        }
+        self.pending = commands;
+        self.try_sync()
+    }
+
+    fn fail(&mut self, error: Error) {
+        self.conn = None;
+        self.pending.clear();
+        self.db = 0;
+        self.acked_db = 0;
+        self.error = Some(HandlerError::Retry(error));
+    }
+}
+
+fn is_first(object: &Object) -> bool {
+    match object {
+        Object::List(list) => list.is_first,
+        Object::Set(set) => set.is_first,
+        Object::SortedSet(sorted_set) => sorted_set.is_first,
+        Object::Hash(hash) => hash.is_first,
+        Object::Stream(_, _) => true,
+        _ => false,
+    }
+}
+
+impl EventHandler for Replayer {
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::RDB(object) => self.replay_object(&object),
+            Event::AOF(cmd) => self.queue(cmd.to_args(), true),
+        }
+    }
+
+    fn handle_aof_raw(&mut self, _event: Event, args: &[Vec<u8>], _position: AofPosition) {
+        self.queue(args.to_vec(), true);
+    }
+
+    /// 读取所有已发送命令的响应，重试之后仍然失败时返回错误
+    fn flush(&mut self) -> Result<()> {
+        self.sync();
+        match &self.error {
+            Some(error) => Err(Error::other(error.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.error.take()
+    }
+}
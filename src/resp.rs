@@ -1,8 +1,8 @@
 /*!
-Redis Serialization Protocol相关的解析代码
+Redis Serialization Protocol相关的解析以及编码代码
 */
 
-use std::io::{Read, Result};
+use std::io::{Error, ErrorKind, Read, Result};
 
 use byteorder::ReadBytesExt;
 
@@ -33,7 +33,12 @@ pub trait RespDecode: Read {
                     COLON => return Ok(Type::Int),
                     DOLLAR => return Ok(Type::BulkString),
                     STAR => return Ok(Type::Array),
-                    _ => panic!("Unexpected Data Type: {}", b),
+                    _ => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Unexpected Data Type: {}", b),
+                        ))
+                    }
                 }
             }
         }
@@ -52,15 +57,20 @@ pub trait RespDecode: Read {
         if self.read_u8()? == LF {
             Ok(to_string(buf))
         } else {
-            panic!("Expect LF after CR");
+            Err(Error::new(ErrorKind::InvalidData, "Expect LF after CR"))
         }
     }
 
     /// 解析Integer响应
     fn decode_int(&mut self) -> Result<Resp> {
         let s = self.decode_string()?;
-        let i = s.parse::<i64>().unwrap();
-        return Ok(Resp::Int(i));
+        match s.parse::<i64>() {
+            Ok(i) => Ok(Resp::Int(i)),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected Int Response: {}", s),
+            )),
+        }
     }
 
     /// 解析Bulk String响应
@@ -73,7 +83,7 @@ pub trait RespDecode: Read {
                 let mut end = vec![0; 2];
                 self.read_exact(&mut end)?;
                 if !end.eq(&[CR, LF]) {
-                    panic!("Expected CRLF");
+                    return Err(Error::new(ErrorKind::InvalidData, "Expected CRLF"));
                 } else {
                     return Ok(Resp::BulkBytes(buf));
                 }
@@ -82,7 +92,7 @@ pub trait RespDecode: Read {
                 return Ok(Resp::BulkBytes(vec![0; 0]));
             }
         } else {
-            panic!("Expected Int Response");
+            Err(Error::new(ErrorKind::InvalidData, "Expected Int Response"))
         }
     }
 
@@ -97,7 +107,7 @@ pub trait RespDecode: Read {
             }
             return Ok(Resp::Array(arr));
         } else {
-            panic!("Expected Int Response");
+            Err(Error::new(ErrorKind::InvalidData, "Expected Int Response"))
         }
     }
 }
@@ -135,9 +145,28 @@ pub(crate) const MINUS: u8 = b'-';
 // 代表integer响应
 pub(crate) const COLON: u8 = b':';
 
+/// 将命令的各个参数(第一个为命令名)编码为RESP array，即Redis客户端发送命令时所使用的格式
+///
+/// 编码的结果可以直接写入与Redis的连接，或者写入文件之后使用`redis-cli --pipe`导入
+pub fn encode_command(args: &[Vec<u8>]) -> Vec<u8> {
+    let len = args.iter().map(|arg| arg.len() + 16).sum::<usize>() + 16;
+    let mut buf = Vec::with_capacity(len);
+    buf.push(STAR);
+    buf.extend_from_slice(args.len().to_string().as_bytes());
+    buf.extend_from_slice(&[CR, LF]);
+    for arg in args {
+        buf.push(DOLLAR);
+        buf.extend_from_slice(arg.len().to_string().as_bytes());
+        buf.extend_from_slice(&[CR, LF]);
+        buf.extend_from_slice(arg);
+        buf.extend_from_slice(&[CR, LF]);
+    }
+    buf
+}
+
 #[cfg(test)]
 mod test {
-    use crate::resp::{Resp, RespDecode};
+    use crate::resp::{encode_command, Resp, RespDecode};
     use std::io::Cursor;
 
     #[test]
@@ -163,4 +192,27 @@ mod test {
             Err(err) => panic!(err),
         }
     }
+
+    #[test]
+    fn test_encode_command() {
+        let args = vec![b"SET".to_vec(), b"key".to_vec(), b"a\r\nb".to_vec(), Vec::new()];
+        let encoded = encode_command(&args);
+        assert_eq!(
+            b"*4\r\n$3\r\nSET\r\n$3\r\nkey\r\n$4\r\na\r\nb\r\n$0\r\n\r\n".to_vec(),
+            encoded
+        );
+        match Cursor::new(encoded).decode_resp().unwrap() {
+            Resp::Array(arr) => {
+                let decoded: Vec<Vec<u8>> = arr
+                    .into_iter()
+                    .map(|x| match x {
+                        Resp::BulkBytes(bytes) => bytes,
+                        _ => panic!("wrong type"),
+                    })
+                    .collect();
+                assert_eq!(args, decoded);
+            }
+            _ => panic!("wrong type"),
+        }
+    }
 }
@@ -0,0 +1,227 @@
+/*!
+开启`serde` feature之后，各类事件的序列化以及反序列化的实现
+
+- `Command`、`Object`以及`Event`借用了解析时的缓冲区，只实现了`Serialize`，分别与对应的`OwnedCommand`、`OwnedObject`、`OwnedEvent`序列化的结果相同，
+  反序列化时应使用`OwnedCommand`、`OwnedObject`、`OwnedEvent`
+- key、value等二进制数据在JSON等人类可读的格式中序列化为base64编码的字符串，开启`serde-lossy-string` feature之后则直接序列化为字符串，
+  其中不是合法UTF-8的字节将被替换为`U+FFFD`，无法还原；在CBOR等二进制的格式中，序列化为原始的字节
+- Stream的ID序列化为`1526919030474-55`形式的字符串
+- Sorted Set的score在人类可读的格式中，`inf`、`-inf`以及`nan`序列化为字符串，其余的序列化为数字
+*/
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::base64::{decode_base64, encode_base64};
+use crate::cmd::Command;
+use crate::rdb::{Object, ID};
+
+impl Serialize for Command<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_owned().serialize(serializer)
+    }
+}
+
+impl Serialize for Object<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_owned().serialize(serializer)
+    }
+}
+
+impl Serialize for ID {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ID {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(IDVisitor)
+    }
+}
+
+struct IDVisitor;
+
+impl<'de> Visitor<'de> for IDVisitor {
+    type Value = ID;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a stream id like 1526919030474-55")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<ID, E> {
+        let id = v.split_once('-').and_then(|(ms, seq)| {
+            let ms = ms.parse().ok()?;
+            let seq = seq.parse().ok()?;
+            Some(ID { ms, seq })
+        });
+        id.ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+// 序列化时借用的二进制数据
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            serializer.serialize_bytes(self.0)
+        } else if cfg!(feature = "serde-lossy-string") {
+            serializer.serialize_str(&String::from_utf8_lossy(self.0))
+        } else {
+            serializer.serialize_str(&encode_base64(self.0))
+        }
+    }
+}
+
+// 反序列化得到的二进制数据
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(ByteBufVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(ByteBufVisitor)
+        }
+    }
+}
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if cfg!(feature = "serde-lossy-string") {
+            formatter.write_str("a string or bytes")
+        } else {
+            formatter.write_str("a base64 encoded string or bytes")
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<ByteBuf, E> {
+        if cfg!(feature = "serde-lossy-string") {
+            Ok(ByteBuf(v.as_bytes().to_vec()))
+        } else {
+            match decode_base64(v) {
+                Some(bytes) => Ok(ByteBuf(bytes)),
+                None => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+        Ok(ByteBuf(v))
+    }
+
+    // 部分格式将字节序列化为数组
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(ByteBuf(bytes))
+    }
+}
+
+/// `Vec<u8>`字段，`#[serde(with = "crate::serialize::bytes")]`
+pub(crate) mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        ByteBuf::deserialize(deserializer).map(|bytes| bytes.0)
+    }
+}
+
+/// `Vec<Vec<u8>>`字段，`#[serde(with = "crate::serialize::bytes_vec")]`
+pub(crate) mod bytes_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| Bytes(value)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        let values: Vec<ByteBuf> = Deserialize::deserialize(deserializer)?;
+        Ok(values.into_iter().map(|value| value.0).collect())
+    }
+}
+
+/// `BTreeMap<Vec<u8>, Vec<u8>>`字段，`#[serde(with = "crate::serialize::bytes_map")]`
+pub(crate) mod bytes_map {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(map: &BTreeMap<Vec<u8>, Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (Bytes(key), Bytes(value))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, D::Error> {
+        let map: BTreeMap<ByteBuf, ByteBuf> = Deserialize::deserialize(deserializer)?;
+        Ok(map.into_iter().map(|(key, value)| (key.0, value.0)).collect())
+    }
+}
+
+/// Sorted Set的score，`#[serde(with = "crate::serialize::score")]`，JSON中无法表示`inf`等值
+pub(crate) mod score {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(score: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() || score.is_finite() {
+            serializer.serialize_f64(*score)
+        } else if score.is_nan() {
+            serializer.serialize_str("nan")
+        } else if score.is_sign_positive() {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        deserializer.deserialize_any(ScoreVisitor)
+    }
+
+    struct ScoreVisitor;
+
+    impl<'de> Visitor<'de> for ScoreVisitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number, inf, -inf or nan")
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<f64, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<f64, E> {
+            match v {
+                "inf" | "+inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                "nan" => Ok(f64::NAN),
+                _ => v.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+    }
+}
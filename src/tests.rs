@@ -1,19 +1,30 @@
 #[cfg(test)]
 mod rdb_tests {
     use std::any::Any;
-    use std::cell::RefCell;
     use std::collections::HashMap;
     use std::fs::File;
-    use std::io::Read;
-    use std::rc::Rc;
+    use std::io::{ErrorKind, Read};
     use std::sync::atomic::AtomicBool;
     use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     use num_bigint::Sign;
     use num_traits::ToPrimitive;
 
-    use crate::rdb::{DefaultRDBParser, EvictType, ExpireType, Module, Object, RDBDecode, ID};
-    use crate::{Event, EventHandler, ModuleParser, RDBParser};
+    use crate::cmd::Command;
+    use crate::config::{Config, KeyFilter, ParseMode};
+    use crate::crc64::crc64;
+    use crate::diff::{Difference, Snapshot};
+    use crate::export::csv::CsvWriter;
+    use crate::rdb::splitter::RDBSplitter;
+    use crate::rdb::stats::{RDBStats, ValueType};
+    use crate::rdb::writer::RDBWriter;
+    use crate::rdb::{
+        ChecksumStatus, ChunkPosition, DefaultRDBParser, EvictType, ExpireType, KeyValue, List, Meta, Module, Object,
+        OwnedObject, ParseError, RDBDecode, RDBError, RawModule, ResizeDB, UnknownTypeError, BATCH_SIZE, ID,
+    };
+    use crate::{diff, dump, rdb};
+    use crate::{Event, EventHandler, ModuleParser, NoOpEventHandler, RDBParser};
 
     #[test]
     fn test_zipmap_not_compress() {
@@ -47,10 +58,7 @@ mod rdb_tests {
         }
         let mut handler = TestRdbHandler { map: HashMap::new() };
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -88,10 +96,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler { map: HashMap::new() };
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -141,10 +146,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler { map: HashMap::new() };
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -185,10 +187,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler { list: Vec::new() };
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -237,10 +236,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler { map: HashMap::new() };
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -299,10 +295,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler {};
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -351,10 +344,7 @@ mod rdb_tests {
 
         let mut handler = TestRdbHandler {};
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
@@ -402,223 +392,1537 @@ mod rdb_tests {
         }
         let mut handler = TestRdbHandler {};
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+        let mut rdb_parser = parser(&rdb_config());
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
     }
 
-    struct HelloModuleParser {}
+    #[test]
+    fn test_parse_info() {
+        let mut rdb_parser = parser(&rdb_config());
+        let mut handler = NoOpEventHandler {};
 
-    #[derive(Debug)]
-    struct HelloModule {
-        pub values: Vec<i64>,
-    }
+        let mut file = File::open("tests/rdb/dump-lfu.rdb").expect("file not found");
+        let info = rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(9, info.rdb_version);
+        assert!(info.aux_fields.contains_key("redis-ver"));
+        assert_eq!(1, info.db_count);
+        assert_eq!(ChecksumStatus::Valid, info.checksum);
 
-    impl Module for HelloModule {
-        fn as_any(&self) -> &dyn Any {
-            self
+        let mut data = std::fs::read("tests/rdb/dump-lfu.rdb").unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        let info = rdb_parser.parse(&mut data.as_slice(), 0, &mut handler).unwrap();
+        match info.checksum {
+            ChecksumStatus::Mismatch { expected, actual } => assert_ne!(expected, actual),
+            status => panic!("unexpected checksum status: {:?}", status),
         }
+
+        let mut file = File::open("tests/rdb/function.rdb").expect("file not found");
+        let info = rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(10, info.rdb_version);
+        assert_eq!(ChecksumStatus::Disabled, info.checksum);
     }
 
-    impl HelloModuleParser {
-        fn load_unsigned(&self, input: &mut dyn Read, version: usize) -> num_bigint::BigInt {
-            let mut digits = [0; 8];
-            let value = self.load_signed(input, version);
-            for i in 0..8 {
-                digits[7 - i] = ((value as usize >> (i << 3)) & 0xFF) as u8;
-            }
-            num_bigint::BigInt::from_bytes_be(Sign::Plus, &digits)
+    #[test]
+    fn test_unknown_type() {
+        let mut rdb_parser = parser(&rdb_config());
+        let mut handler = NoOpEventHandler {};
+
+        let data = b"REDIS0009\xFE\x00\x63\x03key\x01\x02\x03";
+        let error = rdb_parser.parse(&mut &data[..], 0, &mut handler).err().unwrap();
+        let error = error.get_ref().unwrap().downcast_ref::<RDBError>().unwrap();
+        assert_eq!(Some((0x63, 11)), error.opcode);
+        assert_eq!(Some(b"key".to_vec()), error.key);
+        let cause = error.cause::<UnknownTypeError>().unwrap();
+        assert_eq!(0x63, cause.type_id);
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        struct TestRdbHandler {
+            items: Vec<(Vec<u8>, f64)>,
+            strings: usize,
         }
 
-        fn load_signed(&self, input: &mut dyn Read, version: usize) -> i64 {
-            if version == 2 {
-                let (opcode, _) = input.read_length().unwrap();
-                if opcode != 2 {
-                    panic!("opcode != 2");
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(Object::SortedSet(set)) => {
+                        for item in set.items {
+                            self.items.push((item.member.clone(), item.score));
+                        }
+                    }
+                    Event::RDB(Object::String(_)) => self.strings += 1,
+                    _ => {}
                 }
             }
-            let (len, _) = input.read_length().unwrap();
-            len as i64
         }
-    }
 
-    impl ModuleParser for HelloModuleParser {
-        fn parse(&mut self, input: &mut dyn Read, _module_name: &str, module_version: usize) -> Box<dyn Module> {
-            let elements = self.load_unsigned(input, module_version);
-            let elements = elements.to_u32().unwrap();
+        // 第二个元素的score无法解析
+        let data =
+            b"REDIS0009\xFE\x00\x03\x01z\x02\x01a\x031.5\x01b\x03abc\x00\x01k\x01v\xFF\x00\x00\x00\x00\x00\x00\x00\x00";
 
-            let mut array = Vec::new();
-            for _ in 0..elements {
-                let val = self.load_signed(input, module_version);
-                array.push(val);
-            }
+        let mut rdb_parser = parser(&rdb_config());
+        let mut handler = TestRdbHandler {
+            items: Vec::new(),
+            strings: 0,
+        };
+        let error = rdb_parser.parse(&mut &data[..], 0, &mut handler).err().unwrap();
+        let error = error.get_ref().unwrap().downcast_ref::<RDBError>().unwrap();
+        assert!(error.cause::<ParseError>().is_some());
+        assert_eq!(Some(b"z".to_vec()), error.key);
+        // 读取完第二个元素的score之后出错
+        assert_eq!(27, error.offset);
+        assert_eq!(0, handler.strings);
 
-            Box::new(HelloModule { values: array })
-        }
+        rdb_parser.parse_mode = ParseMode::Lenient;
+        let mut handler = TestRdbHandler {
+            items: Vec::new(),
+            strings: 0,
+        };
+        rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap();
+        assert_eq!(vec![(b"a".to_vec(), 1.5), (b"b".to_vec(), 0.0)], handler.items);
+        assert_eq!(1, handler.strings);
     }
 
     #[test]
-    fn test_module() {
-        let mut file = File::open("tests/rdb/module.rdb").expect("file not found");
+    fn test_stream_parse_mode() {
+        struct TestRdbHandler {
+            ids: Vec<ID>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Stream(_, stream)) = event {
+                    self.ids.extend(stream.entries.keys());
+                }
+            }
+        }
 
-        let parser = Rc::new(RefCell::new(HelloModuleParser {}));
+        // stream的listpack中，元素ID的ms无法解析
+        let data = b"REDIS0009\xFE\x00\x0F\x01s\x01\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x1E\x1E\x00\x00\x00\x00\x00\x01\x01\x00\x01\x01\x01\x81f\x02\x00\x01\x02\x01\x81x\x02\x00\x01\x81v\x02\x04\x01\xFF\x01\x00\x00\x00\xFF\x00\x00\x00\x00\x00\x00\x00\x00";
 
-        struct TestRdbHandler {}
+        let mut rdb_parser = parser(&rdb_config());
+        let mut handler = TestRdbHandler { ids: Vec::new() };
+        let error = rdb_parser.parse(&mut &data[..], 0, &mut handler).err().unwrap();
+        let error = error.get_ref().unwrap().downcast_ref::<RDBError>().unwrap();
+        assert!(error.cause::<ParseError>().is_some());
+        assert!(handler.ids.is_empty());
+
+        rdb_parser.parse_mode = ParseMode::Lenient;
+        rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap();
+        assert_eq!(vec![ID { ms: 0, seq: 0 }], handler.ids);
+    }
+
+    #[test]
+    fn test_skip_corrupt_keys() {
+        struct TestRdbHandler {
+            skipped: Vec<(Vec<u8>, u8)>,
+            strings: Vec<Vec<u8>>,
+        }
 
         impl EventHandler for TestRdbHandler {
             fn handle(&mut self, event: Event) {
                 match event {
-                    Event::RDB(rdb) => match rdb {
-                        Object::Module(_, module, _) => {
-                            let hello_module: &HelloModule = match module.as_any().downcast_ref::<HelloModule>() {
-                                Some(hello_module) => hello_module,
-                                None => panic!("not HelloModule"),
-                            };
-                            let values = &hello_module.values;
-                            assert_eq!(1, values.len());
-                            let val = values.get(0).unwrap();
-                            assert_eq!(&12123123112, val);
-                        }
-                        _ => {}
-                    },
+                    Event::RDB(Object::SkippedKey(skipped)) => {
+                        assert!(skipped.error.get_ref().unwrap().is::<ParseError>());
+                        self.skipped.push((skipped.key, skipped.value_type));
+                    }
+                    Event::RDB(Object::String(kv)) => self.strings.push(kv.key.to_vec()),
                     _ => {}
                 }
             }
         }
 
-        let mut handler = TestRdbHandler {};
+        // listpack编码的sorted set中第二个元素的score无法解析，其后是一个正常的string
+        let data = b"REDIS0010\xFE\x00\x11\x01z\x17\x17\x00\x00\x00\x04\x00\x81a\x02\x831.5\x04\x81b\x02\x83abc\x04\xFF\x00\x01k\x01v\xFF\x00\x00\x00\x00\x00\x00\x00\x00";
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: Some(parser),
+        let mut rdb_parser = parser(&rdb_config());
+        let mut handler = TestRdbHandler {
+            skipped: Vec::new(),
+            strings: Vec::new(),
         };
-        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert!(rdb_parser.parse(&mut &data[..], 0, &mut handler).is_err());
+        assert!(handler.skipped.is_empty());
+
+        rdb_parser.is_skip_corrupt_keys = true;
+        let mut handler = TestRdbHandler {
+            skipped: Vec::new(),
+            strings: Vec::new(),
+        };
+        rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap();
+        assert_eq!(vec![(b"z".to_vec(), 17)], handler.skipped);
+        assert_eq!(vec![b"k".to_vec()], handler.strings);
     }
 
     #[test]
-    fn test_module2() {
-        let mut file = File::open("tests/rdb/dump-module-2.rdb").expect("file not found");
+    fn test_incomplete_listpack() {
+        // listpack编码的hash以及sorted set中，最后一个字段缺少对应的值；带有过期时间的hash中缺少字段的过期时间
+        let values: [&[u8]; 3] = [
+            b"\x10\x01k\x0A\x0A\x00\x00\x00\x01\x00\x81a\x02\xFF",
+            b"\x11\x01k\x0A\x0A\x00\x00\x00\x01\x00\x81a\x02\xFF",
+            b"\x19\x01k\x00\x00\x00\x00\x00\x00\x00\x00\x0D\x0D\x00\x00\x00\x02\x00\x81a\x02\x81b\x02\xFF",
+        ];
+        for value in values.iter() {
+            let mut data = b"REDIS0012\xFE\x00".to_vec();
+            data.extend_from_slice(value);
+            data.extend_from_slice(b"\xFF\x00\x00\x00\x00\x00\x00\x00\x00");
 
-        let parser = Rc::new(RefCell::new(HelloModuleParser {}));
+            let mut rdb_parser = parser(&rdb_config());
+            let error = rdb_parser.parse(&mut data.as_slice(), 0, &mut NoOpEventHandler {});
+            assert!(error.is_err());
+        }
+    }
 
-        struct TestRdbHandler {}
+    #[test]
+    fn test_string_chunks() {
+        struct TestRdbHandler {
+            chunks: Vec<(Vec<u8>, usize, ChunkPosition)>,
+            strings: Vec<Vec<u8>>,
+        }
 
         impl EventHandler for TestRdbHandler {
             fn handle(&mut self, event: Event) {
                 match event {
-                    Event::RDB(rdb) => match rdb {
-                        Object::Module(_, module, _) => {
-                            let hello_module: &HelloModule = match module.as_any().downcast_ref::<HelloModule>() {
-                                Some(hello_module) => hello_module,
-                                None => panic!("not HelloModule"),
-                            };
-                            let values = &hello_module.values;
-                            assert_eq!(2, values.len());
-                            let val1 = values.get(0).unwrap();
-                            assert_eq!(-1025, *val1);
-                            let val2 = values.get(1).unwrap();
-                            assert_eq!(-1024, *val2);
-                        }
-                        _ => {}
-                    },
+                    Event::RDB(Object::StringChunk(chunk)) => {
+                        assert_eq!(b"s", chunk.key);
+                        assert_eq!(10, chunk.total_len);
+                        self.chunks.push((chunk.chunk.to_vec(), chunk.offset, chunk.position));
+                    }
+                    Event::RDB(Object::String(kv)) => self.strings.push(kv.key.to_vec()),
                     _ => {}
                 }
             }
         }
 
-        let mut handler = TestRdbHandler {};
+        // 一个长度为10的string，以及一个长度为1的string
+        let data = b"REDIS0010\xFE\x00\x00\x01s\x0A0123456789\x00\x01k\x01v\xFF\x00\x00\x00\x00\x00\x00\x00\x00";
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: Some(parser),
+        let mut config = rdb_config();
+        config.string_chunk_size = Some(4);
+        let mut rdb_parser = parser(&config);
+        let mut handler = TestRdbHandler {
+            chunks: Vec::new(),
+            strings: Vec::new(),
         };
+        rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap();
+        assert_eq!(
+            vec![
+                (b"0123".to_vec(), 0, ChunkPosition::Begin),
+                (b"4567".to_vec(), 4, ChunkPosition::Continue),
+                (b"89".to_vec(), 8, ChunkPosition::End),
+            ],
+            handler.chunks
+        );
+        assert_eq!(vec![b"k".to_vec()], handler.strings);
+    }
+
+    #[test]
+    fn test_batch_markers() {
+        struct TestRdbHandler {
+            batches: Vec<(usize, bool, bool)>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(Object::SortedSet(set)) => {
+                        self.batches.push((set.items.len(), set.is_first, set.is_last));
+                    }
+                    Event::RDB(Object::List(list)) => {
+                        self.batches.push((list.values.len(), list.is_first, list.is_last));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut rdb_parser = parser(&rdb_config());
+
+        // 500个元素，分为8批
+        let mut file = File::open("tests/rdb/regular_sorted_set_1.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { batches: Vec::new() };
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(8, handler.batches.len());
+        assert_eq!((64, true, false), handler.batches[0]);
+        assert_eq!((64, false, false), handler.batches[6]);
+        assert_eq!((52, false, true), handler.batches[7]);
+
+        // 元素个数恰好为一批时，最后一批为空
+        let mut data = b"REDIS0006\xFE\x00\x01\x01l\x40\x40".to_vec();
+        for _ in 0..64 {
+            data.extend_from_slice(b"\x01v");
+        }
+        data.extend_from_slice(b"\xFF\x00\x00\x00\x00\x00\x00\x00\x00");
+        let mut handler = TestRdbHandler { batches: Vec::new() };
+        rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap();
+        assert_eq!(vec![(64, true, false), (0, false, true)], handler.batches);
+
+        // 合并为一个事件
+        rdb_parser.batch_size = usize::MAX;
+        let mut file = File::open("tests/rdb/regular_sorted_set_1.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { batches: Vec::new() };
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(vec![(500, true, true)], handler.batches);
     }
 
     #[test]
-    fn test_module2_skip() {
-        let mut file = File::open("tests/rdb/dump-json-module.rdb").expect("file not found");
+    fn test_resize_db() {
+        let mut file = File::open("tests/rdb/dump-lfu.rdb").expect("file not found");
 
-        struct TestRdbHandler {}
+        struct TestRdbHandler {
+            resize_db: Option<ResizeDB>,
+            keys: usize,
+        }
 
         impl EventHandler for TestRdbHandler {
-            fn handle(&mut self, _: Event) {}
+            fn handle(&mut self, data: Event) {
+                match data {
+                    Event::RDB(Object::ResizeDB(resize_db)) => {
+                        // 先于该db中的key产生
+                        assert_eq!(0, self.keys);
+                        self.resize_db = Some(resize_db);
+                    }
+                    Event::RDB(Object::String(_)) => self.keys += 1,
+                    _ => {}
+                }
+            }
         }
+        let mut handler = TestRdbHandler {
+            resize_db: None,
+            keys: 0,
+        };
 
-        let mut handler = TestRdbHandler {};
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        let resize_db = handler.resize_db.expect("no RESIZEDB");
+        assert_eq!(0, resize_db.db);
+        assert_eq!(handler.keys, resize_db.size);
+        assert_eq!(1, resize_db.expires_size);
+    }
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
+    #[test]
+    fn test_discard_expired_keys() {
+        struct TestRdbHandler {
+            keys: usize,
+            expires: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, data: Event) {
+                if let Event::RDB(Object::String(kv)) = data {
+                    self.keys += 1;
+                    if kv.meta.expire.is_some() {
+                        self.expires += 1;
+                    }
+                }
+            }
+        }
+
+        let mut config = rdb_config();
+        config.is_discard_expired_keys = true;
+        config.expire_reference_time = Some(UNIX_EPOCH);
+        let mut rdb_parser = parser(&config);
+        let mut file = File::open("tests/rdb/dump-lfu.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { keys: 0, expires: 0 };
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        let keys = handler.keys;
+        assert_eq!(1, handler.expires);
+
+        // 在足够久之后，带有过期时间的key都已过期
+        rdb_parser.expire_reference_time = Some(UNIX_EPOCH + Duration::from_secs(u32::MAX as u64 * 1000));
+        let mut file = File::open("tests/rdb/dump-lfu.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { keys: 0, expires: 0 };
         rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(keys - 1, handler.keys);
+        assert_eq!(0, handler.expires);
     }
 
     #[test]
-    fn test_stream() {
-        let mut file = File::open("tests/rdb/dump-stream.rdb").expect("file not found");
+    fn test_db_filter() {
+        struct TestRdbHandler {
+            dbs: Vec<isize>,
+        }
 
-        struct TestRdbHandler {}
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, data: Event) {
+                match data {
+                    Event::RDB(Object::String(kv)) => self.dbs.push(kv.meta.db),
+                    Event::RDB(Object::List(list)) => self.dbs.push(list.meta.db),
+                    Event::RDB(Object::Set(set)) => self.dbs.push(set.meta.db),
+                    Event::RDB(Object::SortedSet(set)) => self.dbs.push(set.meta.db),
+                    Event::RDB(Object::Hash(hash)) => self.dbs.push(hash.meta.db),
+                    Event::RDB(Object::Module(_, _, meta)) => self.dbs.push(meta.db),
+                    Event::RDB(Object::Stream(_, stream)) => self.dbs.push(stream.meta.db),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut config = rdb_config();
+        config.db_filter = Some(vec![2]);
+        let mut rdb_parser = parser(&config);
+        let mut file = File::open("tests/rdb/multiple_databases.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { dbs: Vec::new() };
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(vec![2], handler.dbs);
+
+        // 离线解析时同样可以使用Config中的过滤选项
+        let mut config = rdb_config();
+        config.db_filter = Some(vec![2]);
+        let file = File::open("tests/rdb/multiple_databases.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { dbs: Vec::new() };
+        rdb::parse_reader_with_config(file, &config, &mut handler).unwrap();
+        assert_eq!(vec![2], handler.dbs);
+
+        // 跳过所有的key，各种编码的值都应被完整跳过，校验和与完整解析时一致
+        for name in &[
+            "dictionary.rdb",
+            "dump-lfu.rdb",
+            "dump-module-2.rdb",
+            "dump-stream.rdb",
+            "easily_compressible_string_key.rdb",
+            "hash_as_ziplist.rdb",
+            "hash_field_expire.rdb",
+            "hash_listpack.rdb",
+            "integer_keys.rdb",
+            "intset_64.rdb",
+            "keys_with_expiry.rdb",
+            "linkedlist.rdb",
+            "list_quicklist_2.rdb",
+            "regular_sorted_set.rdb",
+            "regular_sorted_set_1.rdb",
+            "stream_listpacks_3.rdb",
+            "timeseries.rdb",
+            "ziplist_with_integers.rdb",
+            "zipmap_with_big_values.rdb",
+            "zset_listpack.rdb",
+        ] {
+            rdb_parser.db_filter = None;
+            let mut file = File::open(format!("tests/rdb/{}", name)).expect("file not found");
+            let mut handler = TestRdbHandler { dbs: Vec::new() };
+            let expected = rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+            assert!(!handler.dbs.is_empty(), "{}", name);
+
+            rdb_parser.db_filter = Some(Vec::new());
+            let mut file = File::open(format!("tests/rdb/{}", name)).expect("file not found");
+            let mut handler = TestRdbHandler { dbs: Vec::new() };
+            let info = rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+            assert!(handler.dbs.is_empty(), "{}", name);
+            assert_eq!(expected.checksum, info.checksum, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_key_filter() {
+        struct TestRdbHandler {
+            keys: Vec<Vec<u8>>,
+        }
 
         impl EventHandler for TestRdbHandler {
-            fn handle(&mut self, event: Event) {
-                match event {
-                    Event::RDB(rdb) => match rdb {
-                        Object::Stream(key, stream) => {
-                            let key = String::from_utf8(key).unwrap();
-                            if &key == "listpack" {
-                                let mut i = 0;
-                                for (_, entry) in &stream.entries {
-                                    let __key = format!("field{}", i);
-                                    assert!(entry.fields.contains_key(__key.as_bytes()));
-                                    i += 1;
-                                }
-                                assert!(stream.groups.len() == 4);
-                            } else if &key == "trim" {
-                                let mut i = 0;
-                                for (_, entry) in &stream.entries {
-                                    if i < 20 {
-                                        assert!(entry.deleted)
-                                    } else {
-                                        if entry.id.eq(&ID {
-                                            ms: 1528512149341,
-                                            seq: 0,
-                                        }) {
-                                            assert!(entry.deleted)
-                                        } else if entry.id.eq(&ID {
-                                            ms: 1528512149742,
-                                            seq: 0,
-                                        }) {
-                                            assert!(entry.deleted)
-                                        } else {
-                                            assert!(entry.deleted == false)
-                                        }
-                                    }
-                                    i += 1;
-                                }
-                            } else if &key == "nums" {
-                                let mut i = 0;
-                                for (_, entry) in stream.entries {
-                                    match i {
-                                        0 => {
-                                            let __key = format!("{}", -2);
-                                            assert!(entry.fields.contains_key(__key.as_bytes()));
-                                        }
-                                        1 => {
-                                            let __key = format!("{}", -2000);
-                                            assert!(entry.fields.contains_key(__key.as_bytes()));
-                                        }
-                                        2 => {
-                                            let __key = format!("{}", -20000);
-                                            assert!(entry.fields.contains_key(__key.as_bytes()));
-                                        }
-                                        3 => {
-                                            let __key = format!("{}", -200000);
-                                            assert!(entry.fields.contains_key(__key.as_bytes()));
-                                        }
-                                        4 => {
+            fn handle(&mut self, data: Event) {
+                let key = match data {
+                    Event::RDB(Object::String(kv)) => kv.key.to_vec(),
+                    Event::RDB(Object::List(list)) => list.key.to_vec(),
+                    Event::RDB(Object::Set(set)) => set.key.to_vec(),
+                    Event::RDB(Object::SortedSet(set)) => set.key.to_vec(),
+                    Event::RDB(Object::Hash(hash)) => hash.key.to_vec(),
+                    _ => return,
+                };
+                if !self.keys.contains(&key) {
+                    self.keys.push(key);
+                }
+            }
+        }
+
+        let mut rdb_parser = parser(&rdb_config());
+        let mut file = File::open("tests/rdb/parser_filters.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { keys: Vec::new() };
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        let all_keys = handler.keys;
+        assert!(all_keys.len() > 1);
+
+        let prefix = all_keys[0][..1].to_vec();
+        let mut pattern = prefix.clone();
+        pattern.push(b'*');
+        let expected: Vec<Vec<u8>> = all_keys
+            .iter()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        assert!(!expected.is_empty() && expected.len() < all_keys.len());
+        for key_filter in vec![KeyFilter::Prefix(prefix), KeyFilter::Glob(pattern)] {
+            rdb_parser.key_filter = Some(key_filter);
+            let mut file = File::open("tests/rdb/parser_filters.rdb").expect("file not found");
+            let mut handler = TestRdbHandler { keys: Vec::new() };
+            rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+            assert_eq!(expected, handler.keys);
+        }
+    }
+
+    #[test]
+    fn test_parse_file() {
+        struct TestRdbHandler {
+            keys: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, data: Event) {
+                if let Event::RDB(Object::String(_)) = data {
+                    self.keys += 1;
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { keys: 0 };
+        let info = rdb::parse_file("tests/rdb/dump-lfu.rdb", &mut handler).unwrap();
+        assert_eq!(9, info.rdb_version);
+        assert_eq!(ChecksumStatus::Valid, info.checksum);
+        assert_eq!(2, handler.keys);
+
+        let data = std::fs::read("tests/rdb/dump-lfu.rdb").unwrap();
+        let mut handler = TestRdbHandler { keys: 0 };
+        rdb::parse_reader(&data[..], &mut handler).unwrap();
+        assert_eq!(2, handler.keys);
+
+        // 模拟管道等每次只返回少量数据的输入流
+        struct Trickle<'a>(&'a [u8]);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(self.0.len()).min(3);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let mut handler = TestRdbHandler { keys: 0 };
+        let info = rdb::parse_reader(Trickle(&data), &mut handler).unwrap();
+        assert_eq!(ChecksumStatus::Valid, info.checksum);
+        assert_eq!(2, handler.keys);
+        assert!(rdb::verify_reader(Trickle(&data)).is_valid());
+
+        // 压缩后的文件只有开启了对应的feature才能解析
+        for (path, is_enabled) in vec![
+            ("tests/rdb/dump-lfu.rdb.gz", cfg!(feature = "compression-gzip")),
+            ("tests/rdb/dump-lfu.rdb.zst", cfg!(feature = "compression-zstd")),
+        ] {
+            let mut handler = TestRdbHandler { keys: 0 };
+            match rdb::parse_file(path, &mut handler) {
+                Ok(info) => {
+                    assert!(is_enabled);
+                    assert_eq!(ChecksumStatus::Valid, info.checksum);
+                    assert_eq!(2, handler.keys);
+                }
+                Err(err) => {
+                    assert!(!is_enabled);
+                    assert_eq!(ErrorKind::InvalidInput, err.kind());
+                }
+            }
+        }
+
+        #[cfg(feature = "mmap")]
+        {
+            let mut handler = TestRdbHandler { keys: 0 };
+            let info = rdb::parse_mmap("tests/rdb/dump-lfu.rdb", &mut handler).unwrap();
+            assert_eq!(ChecksumStatus::Valid, info.checksum);
+            assert_eq!(2, handler.keys);
+
+            if cfg!(feature = "compression-gzip") {
+                let mut handler = TestRdbHandler { keys: 0 };
+                rdb::parse_mmap("tests/rdb/dump-lfu.rdb.gz", &mut handler).unwrap();
+                assert_eq!(2, handler.keys);
+            }
+
+            // 空文件无法映射，与parse_file返回相同的错误
+            let path = std::env::temp_dir().join(format!("redis-event-empty-{}.rdb", std::process::id()));
+            File::create(&path).unwrap();
+            let err = rdb::parse_mmap(&path, &mut NoOpEventHandler {}).unwrap_err();
+            let expected = rdb::parse_file(&path, &mut NoOpEventHandler {}).unwrap_err();
+            assert_eq!(expected.kind(), err.kind());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut differences = Vec::new();
+        diff::diff_files("tests/rdb/diff_old.rdb", "tests/rdb/diff_new.rdb", |difference| {
+            differences.push(difference)
+        })
+        .unwrap();
+        // set中元素的顺序不同不算作差异
+        let key = |key: &str| key.as_bytes().to_vec();
+        assert_eq!(
+            vec![
+                Difference::Added {
+                    db: 0,
+                    key: key("added")
+                },
+                Difference::ValueChanged {
+                    db: 0,
+                    key: key("hash")
+                },
+                Difference::Removed {
+                    db: 0,
+                    key: key("removed")
+                },
+                Difference::ExpireChanged {
+                    db: 0,
+                    key: key("ttl"),
+                    old: Some(1700000000000),
+                    new: Some(1800000000000),
+                },
+                Difference::ValueChanged {
+                    db: 1,
+                    key: key("list")
+                },
+            ],
+            differences
+        );
+
+        // 分批以及分块产生的数据与整体产生时的摘要相同
+        let snapshot = Snapshot::from_file("tests/rdb/dictionary.rdb").unwrap();
+        let mut config = rdb_config();
+        config.string_chunk_size = Some(3);
+        let mut rdb_parser = parser(&config);
+        rdb_parser.batch_size = 7;
+        let mut chunked = Snapshot::new();
+        let mut file = File::open("tests/rdb/dictionary.rdb").unwrap();
+        rdb_parser.parse(&mut file, 0, &mut chunked).unwrap();
+        assert!(!snapshot.is_empty());
+        assert_eq!(snapshot.len(), chunked.len());
+        snapshot.diff(&chunked, |difference| panic!("unexpected difference: {:?}", difference));
+    }
+
+    #[test]
+    fn test_verify() {
+        let report = rdb::verify_file("tests/rdb/multiple_databases.rdb").unwrap();
+        assert!(report.is_valid());
+        assert!(!report.is_truncated());
+        let info = rdb::parse_file("tests/rdb/multiple_databases.rdb", &mut NoOpEventHandler {}).unwrap();
+        assert_eq!(info.db_sizes, report.info.db_sizes);
+        assert_eq!(2, report.info.db_sizes.len());
+        assert_eq!(2, report.key_count());
+
+        let report = rdb::verify_file("tests/rdb/keys_with_expiry.rdb").unwrap();
+        assert_eq!(1, report.info.db_sizes[0].expires_size);
+
+        // 各种类型的值都能在不解码的情况下被正确跳过
+        for path in [
+            "tests/rdb/dictionary.rdb",
+            "tests/rdb/dump-stream.rdb",
+            "tests/rdb/dump-module-2.rdb",
+        ]
+        .iter()
+        {
+            let report = rdb::verify_file(path).unwrap();
+            assert!(report.is_valid(), "{}: {:?}", path, report.error);
+            assert!(report.key_count() > 0);
+        }
+
+        let data = std::fs::read("tests/rdb/dump-lfu.rdb").unwrap();
+        let report = rdb::verify_reader(&mut &data[..data.len() - 20]);
+        assert!(!report.is_valid());
+        assert!(report.is_truncated());
+        assert_eq!(9, report.info.rdb_version);
+
+        let mut corrupted = data.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        let report = rdb::verify_reader(&mut corrupted.as_slice());
+        assert!(report.error.is_none());
+        assert!(!report.is_valid());
+        assert!(matches!(report.info.checksum, ChecksumStatus::Mismatch { .. }));
+
+        // 不是RDB的输入，如误传入的RESP数据
+        for input in [&b"+OK\r\n$5\r\nhello\r\n"[..], &b"REDIS+OK\r\n"[..]].iter() {
+            let report = rdb::verify_reader(&mut &input[..]);
+            assert_eq!(ErrorKind::InvalidData, report.error.unwrap().kind());
+        }
+
+        // key的长度被破坏，数值远超剩余的输入或者为负数
+        let data = std::fs::read("tests/rdb/keys_with_expiry.rdb").unwrap();
+        for (length, kind) in [
+            (0x4841_0000_0000_0000u64, ErrorKind::UnexpectedEof),
+            (u64::MAX, ErrorKind::InvalidData),
+        ]
+        .iter()
+        {
+            let mut corrupted = data[..0x15].to_vec();
+            corrupted.push(0x81);
+            corrupted.extend_from_slice(&length.to_be_bytes());
+            corrupted.extend_from_slice(&data[0x16..]);
+            let report = rdb::verify_reader(&mut corrupted.as_slice());
+            assert_eq!(*kind, report.error.unwrap().kind());
+        }
+
+        // 跳过的值使用了未知的字符串编码
+        let mut corrupted = data.clone();
+        corrupted[0x2a] = 0xc5;
+        let report = rdb::verify_reader(&mut corrupted.as_slice());
+        assert_eq!(ErrorKind::InvalidData, report.error.unwrap().kind());
+    }
+
+    #[test]
+    fn test_stats() {
+        #[derive(Default)]
+        struct StatsRecorder {
+            stats: Option<RDBStats>,
+            is_stats_before_eor: bool,
+        }
+
+        impl EventHandler for StatsRecorder {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(Object::Stats(stats)) => self.stats = Some(stats.clone()),
+                    Event::RDB(Object::EOR) => self.is_stats_before_eor = self.stats.is_some(),
+                    _ => {}
+                }
+            }
+        }
+
+        let parse = |path: &str, batch_size: usize, string_chunk_size: Option<usize>, now: SystemTime| {
+            let mut config = rdb_config();
+            config.expire_reference_time = Some(now);
+            config.string_chunk_size = string_chunk_size;
+            config.is_collect_stats = true;
+            let mut rdb_parser = parser(&config);
+            rdb_parser.batch_size = batch_size;
+            let mut file = File::open(path).expect("file not found");
+            let mut handler = StatsRecorder::default();
+            rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+            assert!(handler.is_stats_before_eor);
+            handler.stats.unwrap()
+        };
+
+        let stats = parse("tests/rdb/parser_filters.rdb", BATCH_SIZE, None, UNIX_EPOCH);
+        assert_eq!(43, stats.key_count());
+        assert_eq!(1, stats.dbs.len());
+        assert_eq!(1981, stats.dbs[0].bytes);
+        assert_eq!(43, stats.expires.persistent);
+        let strings = &stats.types[&ValueType::String];
+        assert_eq!((18, 18, 638), (strings.keys, strings.elements, strings.bytes));
+        assert_eq!(b"s1".to_vec(), strings.largest.as_ref().unwrap().key);
+        let lists = &stats.types[&ValueType::List];
+        assert_eq!((12, 33), (lists.keys, lists.elements));
+        let largest = lists.largest.as_ref().unwrap();
+        assert_eq!((b"l8".to_vec(), 5), (largest.key.clone(), largest.elements));
+        assert_eq!(10, stats.types[&ValueType::Set].largest.as_ref().unwrap().elements);
+        assert_eq!(3, stats.types[&ValueType::Hash].keys);
+        // 分批以及分块产生事件时，统计结果不变
+        assert_eq!(stats, parse("tests/rdb/parser_filters.rdb", 2, Some(100), UNIX_EPOCH));
+
+        let stats = parse("tests/rdb/multiple_databases.rdb", BATCH_SIZE, None, UNIX_EPOCH);
+        let dbs: Vec<(isize, usize)> = stats.dbs.iter().map(|db| (db.db, db.keys)).collect();
+        assert_eq!(vec![(0, 1), (2, 1)], dbs);
+
+        let stats = parse("tests/rdb/dump-stream.rdb", BATCH_SIZE, None, UNIX_EPOCH);
+        let streams = &stats.types[&ValueType::Stream];
+        assert_eq!((5, 300), (streams.keys, streams.elements));
+        assert_eq!(150, streams.largest.as_ref().unwrap().elements);
+
+        let stats = parse("tests/rdb/keys_with_expiry.rdb", BATCH_SIZE, None, UNIX_EPOCH);
+        assert_eq!((1, 1), (stats.dbs[0].expires, stats.expires.later));
+        let now = UNIX_EPOCH + Duration::from_secs(u32::MAX as u64 * 10);
+        let stats = parse("tests/rdb/keys_with_expiry.rdb", BATCH_SIZE, None, now);
+        assert_eq!(1, stats.expires.expired);
+    }
+
+    #[test]
+    fn test_lua_scripts() {
+        struct ScriptHandler {
+            scripts: Vec<String>,
+            keys: usize,
+        }
+
+        impl EventHandler for ScriptHandler {
+            fn handle(&mut self, data: Event) {
+                match data {
+                    Event::AOF(Command::SCRIPTLOAD(script_load)) => {
+                        self.scripts
+                            .push(String::from_utf8_lossy(script_load.script).into_owned());
+                    }
+                    Event::RDB(Object::String(_)) => self.keys += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut handler = ScriptHandler {
+            scripts: Vec::new(),
+            keys: 0,
+        };
+        let info = rdb::parse_file("tests/rdb/lua_scripts.rdb", &mut handler).unwrap();
+        assert_eq!(
+            vec![
+                "return redis.call('incr', KEYS[1])",
+                "return redis.call('set', KEYS[1], ARGV[1])"
+            ],
+            handler.scripts
+        );
+        assert_eq!(1, handler.keys);
+        assert_eq!(Some(&"6.2.6".to_string()), info.aux_fields.get("redis-ver"));
+        assert!(!info.aux_fields.contains_key("lua"));
+    }
+
+    #[test]
+    fn test_owned_objects() {
+        struct OwnedHandler {
+            objects: Vec<OwnedObject>,
+        }
+
+        impl EventHandler for OwnedHandler {
+            fn handle(&mut self, data: Event) {
+                if let Event::RDB(object) = data {
+                    self.objects.push(object.to_owned());
+                }
+            }
+        }
+
+        let mut handler = OwnedHandler { objects: Vec::new() };
+        rdb::parse_file("tests/rdb/keys_with_expiry.rdb", &mut handler).unwrap();
+        rdb::parse_file("tests/rdb/dump-stream.rdb", &mut handler).unwrap();
+
+        // 拥有所有权之后可以交给其他线程处理
+        let objects = handler.objects.clone();
+        let (strings, streams, markers) = std::thread::spawn(move || {
+            let mut strings = Vec::new();
+            let mut streams = 0;
+            let mut markers = 0;
+            for object in objects {
+                match object {
+                    OwnedObject::String { key, meta, .. } => strings.push((key, meta.expire_millis())),
+                    OwnedObject::Stream { entries, .. } => {
+                        assert!(!entries.is_empty());
+                        streams += 1;
+                    }
+                    OwnedObject::BOR | OwnedObject::EOR => markers += 1,
+                    _ => {}
+                }
+            }
+            (strings, streams, markers)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(vec![(b"expires_ms_precision".to_vec(), Some(1671963072573))], strings);
+        assert!(streams > 0);
+        assert_eq!(4, markers);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_objects() {
+        struct JsonHandler {
+            objects: Vec<(String, OwnedObject)>,
+        }
+
+        impl EventHandler for JsonHandler {
+            fn handle(&mut self, data: Event) {
+                if let Event::RDB(object) = &data {
+                    let json = serde_json::to_string(&data).unwrap();
+                    // Event的序列化结果外层带有变体名
+                    assert!(json.starts_with("{\"RDB\":"));
+                    self.objects
+                        .push((serde_json::to_string(object).unwrap(), object.to_owned()));
+                }
+            }
+        }
+
+        let mut handler = JsonHandler { objects: Vec::new() };
+        rdb::parse_file("tests/rdb/keys_with_expiry.rdb", &mut handler).unwrap();
+        rdb::parse_file("tests/rdb/dump-stream.rdb", &mut handler).unwrap();
+        rdb::parse_file("tests/rdb/zset_listpack.rdb", &mut handler).unwrap();
+        for (json, object) in &handler.objects {
+            let decoded: OwnedObject = serde_json::from_str(json).unwrap();
+            assert_eq!(format!("{:?}", object), format!("{:?}", decoded));
+            assert_eq!(*json, serde_json::to_string(&decoded).unwrap());
+        }
+        assert!(handler.objects.iter().any(|(json, _)| json.contains("\"last_id\":\"")));
+    }
+
+    // 记录每个key的元信息及其DUMP格式的值
+    struct DumpHandler {
+        payloads: Vec<(Vec<u8>, Vec<u8>)>,
+        metas: Vec<(isize, Option<i64>)>,
+    }
+
+    impl EventHandler for DumpHandler {
+        fn handle(&mut self, data: Event) {
+            let (key, meta) = match &data {
+                Event::RDB(Object::String(kv)) => (kv.key, kv.meta),
+                Event::RDB(Object::List(list)) => (list.key, list.meta),
+                Event::RDB(Object::Set(set)) => (set.key, set.meta),
+                Event::RDB(Object::SortedSet(sorted_set)) => (sorted_set.key, sorted_set.meta),
+                Event::RDB(Object::Hash(hash)) => (hash.key, hash.meta),
+                _ => return,
+            };
+            if let Event::RDB(object) = &data {
+                self.payloads.push((key.to_vec(), dump::dump(object).unwrap()));
+                self.metas.push((meta.db, meta.expire_millis()));
+            }
+        }
+    }
+
+    fn dump_all(input: &mut dyn Read) -> DumpHandler {
+        let mut config = rdb_config();
+        config.is_aggregate_collections = true;
+        let mut rdb_parser = parser(&config);
+        let mut handler = DumpHandler {
+            payloads: Vec::new(),
+            metas: Vec::new(),
+        };
+        rdb_parser.parse(input, 0, &mut handler).unwrap();
+        handler
+    }
+
+    #[test]
+    fn test_dump() {
+        let files = [
+            "tests/rdb/easily_compressible_string_key.rdb",
+            "tests/rdb/list_quicklist_2.rdb",
+            "tests/rdb/intset_64.rdb",
+            "tests/rdb/regular_sorted_set.rdb",
+            "tests/rdb/hash_listpack.rdb",
+            "tests/rdb/hash_field_expire.rdb",
+        ];
+        let meta = Meta {
+            db: 0,
+            expire: None,
+            evict: None,
+        };
+        for path in files.iter() {
+            let mut file = File::open(path).expect("file not found");
+            let payloads = dump_all(&mut file).payloads;
+            assert!(!payloads.is_empty());
+
+            // 将DUMP的值重新组装成RDB，解析之后应得到同样的结果
+            let mut data = b"REDIS0012\xFE\x00".to_vec();
+            for (key, payload) in &payloads {
+                let (body, footer) = payload.split_at(payload.len() - 10);
+                let crc = crc64(0, &payload[..payload.len() - 8]);
+                assert_eq!(&crc.to_le_bytes(), &footer[2..]);
+                data.push(body[0]);
+                dump::write_string(&mut data, key);
+                data.extend_from_slice(&body[1..]);
+            }
+            data.push(0xFF);
+            data.extend_from_slice(&[0; 8]);
+            assert_eq!(payloads, dump_all(&mut &data[..]).payloads, "{}", path);
+
+            // 直接解析DUMP的值之后再次序列化，同样应得到相同的结果
+            let mut handler = DumpHandler {
+                payloads: Vec::new(),
+                metas: Vec::new(),
+            };
+            for (key, payload) in &payloads {
+                dump::parse(key, payload, &meta, &mut handler).unwrap();
+            }
+            assert_eq!(payloads, handler.payloads, "{}", path);
+        }
+
+        let (key, payload) = &dump_all(&mut File::open(files[0]).unwrap()).payloads[0];
+        let mut corrupted = payload.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        let mut handler = DumpHandler {
+            payloads: Vec::new(),
+            metas: Vec::new(),
+        };
+        let error = dump::parse(key, &corrupted, &meta, &mut handler).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        let error = dump::parse(key, &payload[..5], &meta, &mut handler).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        assert!(handler.payloads.is_empty());
+
+        let values = vec![b"a".to_vec()];
+        let list = Object::List(List {
+            key: b"list",
+            values: &values,
+            is_first: true,
+            is_last: false,
+            meta: &meta,
+        });
+        assert_eq!(ErrorKind::InvalidInput, dump::dump(&list).unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_rdb_writer() {
+        let files = [
+            "tests/rdb/keys_with_expiry.rdb",
+            "tests/rdb/multiple_databases.rdb",
+            "tests/rdb/dump-lfu.rdb",
+            "tests/rdb/easily_compressible_string_key.rdb",
+            "tests/rdb/regular_sorted_set_1.rdb",
+            "tests/rdb/list_quicklist_2.rdb",
+            "tests/rdb/regular_set.rdb",
+            "tests/rdb/hash_field_expire.rdb",
+            "tests/rdb/function.rdb",
+        ];
+        for path in files.iter() {
+            // 分批产生集合类型的数据，并将String分块，以覆盖RDBWriter中暂存以及分块写入的逻辑
+            let mut config = rdb_config();
+            config.string_chunk_size = Some(4);
+            let mut rdb_parser = parser(&config);
+            rdb_parser.batch_size = 16;
+            let mut writer = RDBWriter::new(Vec::new());
+            writer.with_rdb_version(12);
+            let mut file = File::open(path).expect("file not found");
+            rdb_parser.parse(&mut file, 0, &mut writer).unwrap();
+            let data = writer.finish().unwrap();
+
+            let info = rdb::parse_reader(&mut &data[..], &mut NoOpEventHandler {}).unwrap();
+            assert_eq!(ChecksumStatus::Valid, info.checksum, "{}", path);
+            let expected = dump_all(&mut File::open(path).unwrap());
+            let actual = dump_all(&mut &data[..]);
+            assert_eq!(expected.payloads, actual.payloads, "{}", path);
+            assert_eq!(expected.metas, actual.metas, "{}", path);
+        }
+
+        // 默认的RDB版本9无法写入带有字段过期时间的Hash
+        let mut writer = RDBWriter::new(Vec::new());
+        rdb::parse_file("tests/rdb/hash_field_expire.rdb", &mut writer).unwrap();
+        assert_eq!(ErrorKind::InvalidInput, writer.finish().unwrap_err().kind());
+
+        let mut writer = RDBWriter::new(Vec::new());
+        rdb::parse_file("tests/rdb/dump-stream.rdb", &mut writer).unwrap();
+        assert!(writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_rdb_splitter() {
+        let mut splitter = RDBSplitter::new();
+        splitter.with_output(None, Some(KeyFilter::Prefix(b"s".to_vec())), RDBWriter::new(Vec::new()));
+        splitter.with_output(Some(vec![1]), None, RDBWriter::new(Vec::new()));
+        rdb::parse_file("tests/rdb/diff_old.rdb", &mut splitter).unwrap();
+        let (outputs, default) = splitter.finish().unwrap();
+        assert!(default.is_none());
+
+        let keys = |output: &Vec<u8>| -> Vec<(isize, String)> {
+            let handler = dump_all(&mut output.as_slice());
+            let metas = handler.metas.iter();
+            let keys = handler
+                .payloads
+                .iter()
+                .map(|(key, _)| String::from_utf8_lossy(key).to_string());
+            metas.map(|(db, _)| *db).zip(keys).collect()
+        };
+        assert_eq!(vec![(0, "same".to_string()), (0, "set".to_string())], keys(&outputs[0]));
+        assert_eq!(vec![(1, "list".to_string())], keys(&outputs[1]));
+
+        // 不匹配任何条件的key写入默认的输出
+        let mut splitter = RDBSplitter::new();
+        splitter.with_output(Some(vec![1]), None, RDBWriter::new(Vec::new()));
+        splitter.with_default_output(RDBWriter::new(Vec::new()));
+        rdb::parse_file("tests/rdb/diff_old.rdb", &mut splitter).unwrap();
+        let (outputs, default) = splitter.finish().unwrap();
+        assert_eq!(vec![(1, "list".to_string())], keys(&outputs[0]));
+        let default = keys(&default.unwrap());
+        let default: Vec<&str> = default.iter().map(|(_, key)| key.as_str()).collect();
+        assert_eq!(vec!["same", "ttl", "set", "hash", "removed"], default);
+    }
+
+    #[test]
+    fn test_to_commands() {
+        struct CommandsHandler {
+            commands: Vec<Vec<Vec<u8>>>,
+        }
+
+        impl EventHandler for CommandsHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(object) = event {
+                    self.commands.extend(object.to_commands());
+                }
+            }
+        }
+
+        fn to_commands(path: &str) -> Vec<String> {
+            let mut config = rdb_config();
+            config.string_chunk_size = Some(4);
+            let mut rdb_parser = parser(&config);
+            let mut handler = CommandsHandler { commands: Vec::new() };
+            let mut file = File::open(path).expect("file not found");
+            rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+            handler
+                .commands
+                .iter()
+                .map(|command| {
+                    let args: Vec<String> = command
+                        .iter()
+                        .map(|arg| String::from_utf8_lossy(arg).to_string())
+                        .collect();
+                    args.join(" ")
+                })
+                .collect()
+        }
+
+        // 分块产生的String先SET再APPEND，过期时间在最后一块之后
+        let commands = to_commands("tests/rdb/keys_with_expiry.rdb");
+        assert_eq!("SET expires_ms_precision 2022", commands[0]);
+        assert_eq!("APPEND expires_ms_precision UTC", commands[commands.len() - 2]);
+        assert_eq!(
+            "PEXPIREAT expires_ms_precision 1671963072573",
+            commands[commands.len() - 1]
+        );
+
+        // 500个元素，每条ZADD最多64个元素
+        let commands = to_commands("tests/rdb/regular_sorted_set_1.rdb");
+        assert_eq!(8, commands.len());
+        assert!(commands
+            .iter()
+            .all(|command| command.starts_with("ZADD force_sorted_set ")));
+
+        // 被删除的元素不会产生XADD
+        let commands = to_commands("tests/rdb/stream_listpacks_3.rdb");
+        assert_eq!(
+            2,
+            commands.iter().filter(|command| command.starts_with("XADD ")).count()
+        );
+        assert!(commands.contains(
+            &"XCLAIM mystream g1 c1 0 1700000000000-0 TIME 1700000000500 RETRYCOUNT 2 JUSTID FORCE".to_string()
+        ));
+    }
+
+    struct HelloModuleParser {}
+
+    #[derive(Debug)]
+    struct HelloModule {
+        pub values: Vec<i64>,
+    }
+
+    impl Module for HelloModule {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl HelloModuleParser {
+        fn load_unsigned(&self, input: &mut dyn Read, version: usize) -> num_bigint::BigInt {
+            let mut digits = [0; 8];
+            let value = self.load_signed(input, version);
+            for i in 0..8 {
+                digits[7 - i] = ((value as usize >> (i << 3)) & 0xFF) as u8;
+            }
+            num_bigint::BigInt::from_bytes_be(Sign::Plus, &digits)
+        }
+
+        fn load_signed(&self, input: &mut dyn Read, version: usize) -> i64 {
+            if version == 2 {
+                let (opcode, _) = input.read_length().unwrap();
+                if opcode != 2 {
+                    panic!("opcode != 2");
+                }
+            }
+            let (len, _) = input.read_length().unwrap();
+            len as i64
+        }
+    }
+
+    impl ModuleParser for HelloModuleParser {
+        fn parse(&mut self, input: &mut dyn Read, _module_name: &str, module_version: usize) -> Box<dyn Module> {
+            let elements = self.load_unsigned(input, module_version);
+            let elements = elements.to_u32().unwrap();
+
+            let mut array = Vec::new();
+            for _ in 0..elements {
+                let val = self.load_signed(input, module_version);
+                array.push(val);
+            }
+
+            Box::new(HelloModule { values: array })
+        }
+    }
+
+    #[test]
+    fn test_module() {
+        let mut file = File::open("tests/rdb/module.rdb").expect("file not found");
+
+        let module_parser = Box::new(HelloModuleParser {});
+
+        struct TestRdbHandler {}
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(rdb) => match rdb {
+                        Object::Module(_, module, _) => {
+                            let hello_module: &HelloModule = match module.as_any().downcast_ref::<HelloModule>() {
+                                Some(hello_module) => hello_module,
+                                None => panic!("not HelloModule"),
+                            };
+                            let values = &hello_module.values;
+                            assert_eq!(1, values.len());
+                            let val = values.get(0).unwrap();
+                            assert_eq!(&12123123112, val);
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler {};
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(module_parser);
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+    }
+
+    #[test]
+    fn test_module2() {
+        let mut file = File::open("tests/rdb/dump-module-2.rdb").expect("file not found");
+
+        let module_parser = Box::new(HelloModuleParser {});
+
+        struct TestRdbHandler {}
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(rdb) => match rdb {
+                        Object::Module(_, module, _) => {
+                            let hello_module: &HelloModule = match module.as_any().downcast_ref::<HelloModule>() {
+                                Some(hello_module) => hello_module,
+                                None => panic!("not HelloModule"),
+                            };
+                            let values = &hello_module.values;
+                            assert_eq!(2, values.len());
+                            let val1 = values.get(0).unwrap();
+                            assert_eq!(-1025, *val1);
+                            let val2 = values.get(1).unwrap();
+                            assert_eq!(-1024, *val2);
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler {};
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(module_parser);
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+    }
+
+    #[test]
+    fn test_module_without_parser() {
+        // 旧版本的Module类型的值没有parser时无法跳过，返回错误而不是panic
+        let error = rdb::parse_file("tests/rdb/module.rdb", &mut NoOpEventHandler {}).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+        let error = error.get_ref().unwrap().downcast_ref::<RDBError>().unwrap();
+        assert_eq!(Some(6), error.opcode.map(|(opcode, _)| opcode));
+        assert!(error.key.is_some());
+    }
+
+    #[test]
+    fn test_module2_skip() {
+        let mut file = File::open("tests/rdb/dump-json-module.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            modules: Vec<String>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Module(_, module, _)) = event {
+                    let module = module.as_any().downcast_ref::<RawModule>().unwrap();
+                    // 原始数据以module opcode的EOF结尾
+                    assert_eq!(Some(&0), module.data.last());
+                    self.modules.push(module.name.clone());
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { modules: Vec::new() };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(vec!["ReJSON-RL"; 3], handler.modules);
+    }
+
+    #[test]
+    #[cfg(feature = "module-timeseries")]
+    fn test_timeseries() {
+        use crate::modules::timeseries::{Aggregation, Sample, TimeSeries, TimeSeriesModuleParser};
+        use crate::modules::ModuleValue;
+
+        let mut file = File::open("tests/rdb/timeseries.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            series: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Module(key, module, _)) = event {
+                    let series = module.as_any().downcast_ref::<TimeSeries>().unwrap();
+                    match key.as_slice() {
+                        b"temperature" => {
+                            assert!(series.is_compressed());
+                            assert_eq!(4096, series.chunk_size);
+                            assert_eq!(205000, series.last_timestamp);
+                            assert_eq!(None, series.src_key);
+                            let labels: Vec<_> = series.labels.iter().map(|l| (&l.name[..], &l.value[..])).collect();
+                            assert_eq!(vec![(&b"sensor"[..], &b"1"[..]), (b"room", b"kitchen")], labels);
+                            assert_eq!(1, series.rules.len());
+                            let rule = &series.rules[0];
+                            assert_eq!(b"temperature_avg", rule.dest_key.as_slice());
+                            assert_eq!(60000, rule.bucket_duration);
+                            assert_eq!(Aggregation::Avg, rule.aggregation);
+                            assert_eq!(vec![ModuleValue::Double(-3.0), ModuleValue::Double(1.0)], rule.context);
+                            let samples: Vec<_> = vec![
+                                (1000, 20.5),
+                                (1010, 20.5),
+                                (1020, 21.0),
+                                (1035, 19.75),
+                                (5000, 100.0),
+                                (205000, -3.0),
+                            ]
+                            .into_iter()
+                            .map(|(timestamp, value)| Sample { timestamp, value })
+                            .collect();
+                            assert_eq!(samples, series.samples);
+                        }
+                        b"temperature_avg" => {
+                            assert!(!series.is_compressed());
+                            assert_eq!(86400000, series.retention);
+                            assert_eq!(Some(b"temperature".to_vec()), series.src_key);
+                            assert!(series.rules.is_empty());
+                            assert_eq!(2, series.total_samples);
+                            assert_eq!(
+                                vec![
+                                    Sample {
+                                        timestamp: 0,
+                                        value: 20.6875
+                                    },
+                                    Sample {
+                                        timestamp: 60000,
+                                        value: 100.0
+                                    }
+                                ],
+                                series.samples
+                            );
+                        }
+                        _ => panic!("unexpected key"),
+                    }
+                    self.series += 1;
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { series: 0 };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(Box::new(TimeSeriesModuleParser {}));
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(2, handler.series);
+    }
+
+    #[test]
+    #[cfg(feature = "module-timeseries")]
+    fn test_timeseries_unsupported() {
+        use crate::modules::timeseries::TimeSeriesModuleParser;
+
+        struct TestRdbHandler {
+            modules: Vec<String>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Module(_, module, _)) = event {
+                    let module = module.as_any().downcast_ref::<RawModule>().unwrap();
+                    self.modules.push(module.name.clone());
+                }
+            }
+        }
+
+        // 其他Module的值以RawModule的形式产生
+        let mut handler = TestRdbHandler { modules: Vec::new() };
+        let mut file = File::open("tests/rdb/dump-json-module.rdb").expect("file not found");
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(Box::new(TimeSeriesModuleParser {}));
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(vec!["ReJSON-RL"; 3], handler.modules);
+
+        // 将temperature的第一个字段的opcode由string改为uint，解码失败时返回错误
+        let mut data = std::fs::read("tests/rdb/timeseries.rdb").unwrap();
+        assert_eq!(5, data[50]);
+        data[50] = 2;
+        let error = rdb_parser.parse(&mut &data[..], 0, &mut handler).unwrap_err();
+        let error = error.get_ref().unwrap().downcast_ref::<RDBError>().unwrap();
+        assert_eq!(Some(b"temperature".to_vec()), error.key);
+    }
+
+    #[test]
+    #[cfg(feature = "module-search")]
+    fn test_search_index() {
+        use crate::modules::search::{SearchIndexes, SearchModuleParser};
+
+        let mut file = File::open("tests/rdb/search_index.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            aux: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::ModuleAux(name, module)) = event {
+                    assert_eq!("ft_index0", name);
+                    let indexes = module.as_any().downcast_ref::<SearchIndexes>().unwrap();
+                    // 第三个索引使用了同义词，不支持解析
+                    assert!(!indexes.is_complete);
+                    assert_eq!(2, indexes.indexes.len());
+
+                    let user = &indexes.indexes[0];
+                    assert_eq!(b"idx:user", user.name.as_slice());
+                    assert_eq!(b"HASH", user.document_type.as_slice());
+                    assert_eq!(vec![b"user:".to_vec(), b"member:".to_vec()], user.prefixes);
+                    assert_eq!(Some(b"@age>18".to_vec()), user.filter);
+                    assert_eq!(None, user.stopwords);
+                    assert_eq!(3, user.fields.len());
+                    assert!(user.fields[0].is_text());
+                    assert_eq!(Some(2.0), user.fields[0].weight);
+                    assert_eq!(0, user.fields[0].sort_index);
+                    assert!(user.fields[1].is_numeric());
+                    assert_eq!(-1, user.fields[1].sort_index);
+                    assert!(user.fields[2].is_tag());
+                    assert_eq!(Some(b'|'), user.fields[2].tag_separator);
+
+                    let doc = &indexes.indexes[1];
+                    assert_eq!(b"JSON", doc.document_type.as_slice());
+                    assert_eq!(Some(b"$.title".to_vec()), doc.fields[0].path);
+                    assert_eq!(Some(vec![b"foo".to_vec(), b"bar".to_vec()]), doc.stopwords);
+                    assert_eq!(vec![b"docs".to_vec()], doc.aliases);
+                    self.aux += 1;
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { aux: 0 };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(Box::new(SearchModuleParser {}));
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(1, handler.aux);
+    }
+
+    #[test]
+    #[cfg(feature = "module-search")]
+    fn test_search_module_value() {
+        use crate::modules::search::SearchModuleParser;
+
+        struct TestRdbHandler {
+            modules: Vec<String>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Module(_, module, _)) = event {
+                    let module = module.as_any().downcast_ref::<RawModule>().unwrap();
+                    self.modules.push(module.name.clone());
+                }
+            }
+        }
+
+        // key对应的Module值不由SearchModuleParser解析，以RawModule的形式产生
+        let mut file = File::open("tests/rdb/dump-json-module.rdb").expect("file not found");
+        let mut handler = TestRdbHandler { modules: Vec::new() };
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.module_parser = Some(Box::new(SearchModuleParser {}));
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(vec!["ReJSON-RL"; 3], handler.modules);
+    }
+
+    #[test]
+    fn test_module_aux() {
+        let mut file = File::open("tests/rdb/module_aux.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            strings: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::String(kv)) = event {
+                    assert_eq!(b"k", kv.key);
+                    self.strings += 1;
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { strings: 0 };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert_eq!(1, handler.strings);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut file = File::open("tests/rdb/dump-stream.rdb").expect("file not found");
+
+        struct TestRdbHandler {}
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(rdb) => match rdb {
+                        Object::Stream(key, stream) => {
+                            let key = String::from_utf8(key).unwrap();
+                            if &key == "listpack" {
+                                let mut i = 0;
+                                for (_, entry) in &stream.entries {
+                                    let __key = format!("field{}", i);
+                                    assert!(entry.fields.contains_key(__key.as_bytes()));
+                                    i += 1;
+                                }
+                                assert!(stream.groups.len() == 4);
+                            } else if &key == "trim" {
+                                let mut i = 0;
+                                for (_, entry) in &stream.entries {
+                                    if i < 20 {
+                                        assert!(entry.deleted)
+                                    } else {
+                                        if entry.id.eq(&ID {
+                                            ms: 1528512149341,
+                                            seq: 0,
+                                        }) {
+                                            assert!(entry.deleted)
+                                        } else if entry.id.eq(&ID {
+                                            ms: 1528512149742,
+                                            seq: 0,
+                                        }) {
+                                            assert!(entry.deleted)
+                                        } else {
+                                            assert!(entry.deleted == false)
+                                        }
+                                    }
+                                    i += 1;
+                                }
+                            } else if &key == "nums" {
+                                let mut i = 0;
+                                for (_, entry) in stream.entries {
+                                    match i {
+                                        0 => {
+                                            let __key = format!("{}", -2);
+                                            assert!(entry.fields.contains_key(__key.as_bytes()));
+                                        }
+                                        1 => {
+                                            let __key = format!("{}", -2000);
+                                            assert!(entry.fields.contains_key(__key.as_bytes()));
+                                        }
+                                        2 => {
+                                            let __key = format!("{}", -20000);
+                                            assert!(entry.fields.contains_key(__key.as_bytes()));
+                                        }
+                                        3 => {
+                                            let __key = format!("{}", -200000);
+                                            assert!(entry.fields.contains_key(__key.as_bytes()));
+                                        }
+                                        4 => {
                                             let __key = format!("{}", -20000000);
                                             assert!(entry.fields.contains_key(__key.as_bytes()));
                                         }
@@ -676,354 +1980,3509 @@ mod rdb_tests {
                                         }
                                         _ => break,
                                     }
-                                    i += 1;
+                                    i += 1;
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler {};
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+    }
+
+    #[test]
+    fn test_stream1() {
+        let mut file = File::open("tests/rdb/dump-stream1.rdb").expect("file not found");
+
+        struct TestRdbHandler {}
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, _: Event) {}
+        }
+
+        let mut handler = TestRdbHandler {};
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+    }
+
+    #[test]
+    fn test_list_quicklist_2() {
+        let mut file = File::open("tests/rdb/list_quicklist_2.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            values: Vec<String>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::List(list)) = event {
+                    assert_eq!(b"mylist", list.key);
+                    for value in list.values {
+                        self.values.push(String::from_utf8_lossy(value).to_string());
+                    }
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { values: Vec::new() };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+
+        let big = "x".repeat(5000);
+        assert_eq!(
+            vec!["a", "1", "hello", "1000000", "-5", big.as_str(), "plain-element"],
+            handler.values
+        );
+    }
+
+    #[test]
+    fn test_hash_listpack() {
+        let mut file = File::open("tests/rdb/hash_listpack.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            map: HashMap<String, String>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Hash(hash)) = event {
+                    assert_eq!(b"myhash", hash.key);
+                    for field in hash.fields {
+                        let name = String::from_utf8_lossy(&field.name).to_string();
+                        let val = String::from_utf8_lossy(&field.value).to_string();
+                        self.map.insert(name, val);
+                    }
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { map: HashMap::new() };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+
+        assert_eq!(3, handler.map.len());
+        assert_eq!("redis", handler.map.get("name").unwrap());
+        assert_eq!("7", handler.map.get("version").unwrap());
+        assert_eq!("-300", handler.map.get("negative").unwrap());
+    }
+
+    #[test]
+    fn test_zset_listpack() {
+        let mut file = File::open("tests/rdb/zset_listpack.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            map: HashMap<String, f64>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::SortedSet(set)) = event {
+                    assert_eq!(b"myzset", set.key);
+                    for item in set.items {
+                        let member = String::from_utf8_lossy(&item.member).to_string();
+                        self.map.insert(member, item.score);
+                    }
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { map: HashMap::new() };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+
+        assert_eq!(4, handler.map.len());
+        assert_eq!(1.0, *handler.map.get("one").unwrap());
+        assert_eq!(0.5, *handler.map.get("half").unwrap());
+        assert_eq!(-20.0, *handler.map.get("neg").unwrap());
+        assert_eq!(3.25e10, *handler.map.get("big").unwrap());
+    }
+
+    #[test]
+    fn test_hash_field_expire() {
+        let mut file = File::open("tests/rdb/hash_field_expire.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            map: HashMap<String, (String, Option<i64>)>,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Hash(hash)) = event {
+                    let key = String::from_utf8_lossy(hash.key).to_string();
+                    for field in hash.fields {
+                        let name = String::from_utf8_lossy(&field.name).to_string();
+                        let val = String::from_utf8_lossy(&field.value).to_string();
+                        self.map.insert(format!("{}.{}", key, name), (val, field.expire));
+                    }
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { map: HashMap::new() };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+
+        assert_eq!(5, handler.map.len());
+        let expect = |val: &str, expire: Option<i64>| (val.to_string(), expire);
+        assert_eq!(&expect("v1", Some(1800000000000)), handler.map.get("hmeta.f1").unwrap());
+        assert_eq!(&expect("v2", None), handler.map.get("hmeta.f2").unwrap());
+        assert_eq!(&expect("v3", Some(1800000005000)), handler.map.get("hmeta.f3").unwrap());
+        assert_eq!(&expect("v1", Some(1800000000000)), handler.map.get("hlpex.f1").unwrap());
+        assert_eq!(&expect("v2", None), handler.map.get("hlpex.f2").unwrap());
+    }
+
+    #[test]
+    fn test_function() {
+        let mut file = File::open("tests/rdb/function.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            functions: Vec<String>,
+            strings: usize,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::RDB(Object::Function(code)) => {
+                        self.functions.push(String::from_utf8(code).unwrap());
+                    }
+                    Event::RDB(Object::String(_)) => self.strings += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler {
+            functions: Vec::new(),
+            strings: 0,
+        };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+
+        assert_eq!(1, handler.functions.len());
+        assert!(handler.functions[0].starts_with("#!lua name=mylib"));
+        assert_eq!(1, handler.strings);
+    }
+
+    #[test]
+    fn test_stream_list_packs_2() {
+        let mut file = File::open("tests/rdb/stream_listpacks_2.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            found: bool,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Stream(key, stream)) = event {
+                    assert_eq!(b"mystream", key.as_slice());
+                    self.found = true;
+
+                    assert_eq!(3, stream.entries.len());
+                    let deleted = ID {
+                        ms: 1700000000001,
+                        seq: 0,
+                    };
+                    assert!(stream.entries.get(&deleted).unwrap().deleted);
+                    let last = stream.entries.values().last().unwrap();
+                    assert_eq!(b"x", last.fields.get(&b"f1".to_vec()).unwrap().as_slice());
+
+                    assert_eq!("1700000000002-0", stream.last_id.to_string());
+                    assert_eq!("1700000000000-0", stream.first_id.unwrap().to_string());
+                    assert_eq!("1700000000001-0", stream.max_deleted_id.unwrap().to_string());
+                    assert_eq!(Some(3), stream.entries_added);
+
+                    assert_eq!(2, stream.groups.len());
+                    assert_eq!(b"g1", stream.groups[0].name.as_slice());
+                    assert_eq!(Some(1), stream.groups[0].entries_read);
+                    assert_eq!(Some(-1), stream.groups[1].entries_read);
+
+                    let consumer = &stream.groups[0].consumers[0];
+                    assert_eq!(b"c1", consumer.name.as_slice());
+                    assert_eq!(1700000000600, consumer.seen_time);
+                    assert_eq!(None, consumer.active_time);
+                    assert!(stream.groups[1].consumers.is_empty());
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { found: false };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert!(handler.found);
+    }
+
+    #[test]
+    fn test_stream_list_packs_3() {
+        let mut file = File::open("tests/rdb/stream_listpacks_3.rdb").expect("file not found");
+
+        struct TestRdbHandler {
+            found: bool,
+        }
+
+        impl EventHandler for TestRdbHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::RDB(Object::Stream(_, stream)) = event {
+                    self.found = true;
+                    assert_eq!(3, stream.entries.len());
+                    assert_eq!(Some(3), stream.entries_added);
+
+                    let consumer = &stream.groups[0].consumers[0];
+                    assert_eq!(b"c1", consumer.name.as_slice());
+                    assert_eq!(1700000000600, consumer.seen_time);
+                    assert_eq!(Some(1700000000550), consumer.active_time);
+
+                    let group = &stream.groups[0];
+                    assert_eq!(1, group.pending.len());
+                    let pending = &group.pending[0];
+                    assert_eq!("1700000000000-0", pending.id.to_string());
+                    assert_eq!(1700000000500, pending.delivery_time);
+                    assert_eq!(2, pending.delivery_count);
+                    assert_eq!(vec![pending.id], consumer.pending);
+                    assert!(stream.groups[1].pending.is_empty());
+                }
+            }
+        }
+
+        let mut handler = TestRdbHandler { found: false };
+
+        let mut rdb_parser = parser(&rdb_config());
+        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        assert!(handler.found);
+    }
+
+    #[test]
+    fn test_csv_writer() {
+        let parse = |path: &str, delimiter: u8| {
+            let mut handler = CsvWriter::new(Vec::new());
+            handler.with_delimiter(delimiter);
+            handler.with_reference_time(Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000)));
+            rdb::parse_file(path, &mut handler).unwrap();
+            String::from_utf8(handler.into_inner()).unwrap()
+        };
+
+        assert_eq!(
+            "db,key,type,field,value,ttl\n\
+             0,expires_ms_precision,string,,2022-12-25 10:11:12.573 UTC,71963072573\n",
+            parse("tests/rdb/keys_with_expiry.rdb", b',')
+        );
+        // 单独设置了过期时间的字段以字段的过期时间为准
+        assert_eq!(
+            "db\tkey\ttype\tfield\tvalue\tttl\n\
+             0\thmeta\thash\tf1\tv1\t200000000000\n\
+             0\thmeta\thash\tf2\tv2\t\n\
+             0\thmeta\thash\tf3\tv3\t200000005000\n\
+             0\thlpex\thash\tf1\tv1\t200000000000\n\
+             0\thlpex\thash\tf2\tv2\t\n",
+            parse("tests/rdb/hash_field_expire.rdb", b'\t')
+        );
+        let output = parse("tests/rdb/linkedlist.rdb", b',');
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(1001, lines.len());
+        assert_eq!("0,force_linkedlist,list,999,", &lines[1000][..28]);
+
+        let meta = Meta {
+            db: 1,
+            expire: None,
+            evict: None,
+        };
+        let mut handler = CsvWriter::new(Vec::new());
+        handler.with_header(false);
+        handler.handle(Event::RDB(Object::String(KeyValue {
+            key: b"a,b",
+            value: b"say \"hi\"\n",
+            meta: &meta,
+        })));
+        assert_eq!(
+            "1,\"a,b\",string,,\"say \"\"hi\"\"\n\",\n",
+            String::from_utf8(handler.into_inner()).unwrap()
+        );
+    }
+
+    // RDB解析相关的选项均为默认值: 数据不符合规范时返回错误，不过滤任何key
+    fn rdb_config() -> Config {
+        Config {
+            is_discard_rdb: false,
+            is_aof: false,
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            repl_id: String::new(),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        }
+    }
+
+    // 按照`config`中的选项创建DefaultRDBParser，没有Module解析器
+    fn parser(config: &Config) -> DefaultRDBParser {
+        DefaultRDBParser::from_config(config, Arc::new(AtomicBool::new(true)), None)
+    }
+}
+
+#[cfg(test)]
+mod aof_tests {
+    use std::any::Any;
+    use std::fs::{self, File};
+    use std::future::Future;
+    use std::io::Write;
+    use std::path::Path;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use std::{env, process, thread};
+
+    use crate::aof::writer::AOFWriter;
+    use crate::aof::{AofFileType, Manifest};
+    use crate::cmd::cluster::SlotState;
+    use crate::cmd::connection::SELECT;
+    use crate::cmd::geo::{GeoFrom, GeoShape};
+    use crate::cmd::hashes::ExpireCondition;
+    use crate::cmd::keys::{DEL, ORDER};
+    use crate::cmd::lists::DIRECTION;
+    use crate::cmd::scripting::RestorePolicy;
+    use crate::cmd::sorted_sets::{CompareType, MINMAX, RANGEBY};
+    use crate::cmd::streams::TrimStrategy;
+    use crate::cmd::strings::{ExistType, ExpireType, Op, Operation, Overflow, SET};
+    use crate::cmd::{Command, CommandParsers, DataType, OwnedCommand, RawCommand};
+    use crate::composite::{CompositeHandler, ErrorPolicy};
+    use crate::export::json::JsonLinesWriter;
+    use crate::export::{Encoding, RotatingFile};
+    use crate::fallible::Fallible;
+    use crate::filter::{Filter, Filtered, ObjectType};
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    use crate::listener::runtime::{AsyncListener, Runtime};
+    use crate::listener::Builder;
+    use crate::normalize::Normalizer;
+    use crate::rdb::{ChecksumStatus, KeyValue, Meta, Module, Object, OwnedObject};
+    use crate::replay::{ReplayConfig, Replayer};
+    use crate::resp::{Resp, RespDecode};
+    use crate::transaction::{Transaction, TransactionGrouper};
+    use crate::transform::{self, RedactValues, StripKeyPrefix, Transform, Transformer};
+    use crate::typed::{Typed, TypedEventHandler};
+    use crate::{
+        aof, broadcast, channel, cmd, dump, AofPosition, AsyncEventHandler, CommandParser, Event, EventHandler,
+        FallibleEventHandler, HandlerError, LifecycleHandler, NoOpEventHandler, OwnedEvent, RedisListener,
+    };
+    use std::io::{BufReader, Error, ErrorKind};
+    use std::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn test_aof1() {
+        let mut file = File::open("tests/aof/appendonly1.aof").expect("file not found");
+
+        struct TestCmdHandler {}
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, cmd: Event) {
+                match cmd {
+                    Event::RDB(_) => {}
+                    Event::AOF(cmd) => match cmd {
+                        Command::HMSET(hmset) => {
+                            let key = String::from_utf8_lossy(hmset.key);
+                            if "key".eq(&key) {
+                                for field in &hmset.fields {
+                                    let name = String::from_utf8_lossy(field.name);
+                                    if "field".eq(&name) {
+                                        let val = String::from_utf8_lossy(field.value);
+                                        assert_eq!("a", val);
+                                    } else if "field1".eq(&name) {
+                                        let val = String::from_utf8_lossy(field.value);
+                                        assert_eq!("b", val);
+                                    } else {
+                                        panic!("wrong name");
+                                    }
+                                }
+                            } else {
+                                panic!("wrong key");
+                            }
+                        }
+                        Command::SELECT(select) => {
+                            assert_eq!(0, select.db);
+                        }
+                        Command::SET(set) => {
+                            let key = String::from_utf8_lossy(set.key);
+                            let val = String::from_utf8_lossy(set.value);
+                            assert_eq!("a", key);
+                            assert_eq!("b", val);
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+
+        let mut cmd_handler = TestCmdHandler {};
+
+        loop {
+            match file.decode_resp() {
+                Ok(resp) => match resp {
+                    Resp::Array(arr) => {
+                        let mut data = Vec::new();
+                        for x in arr {
+                            if let Resp::BulkBytes(bytes) = x {
+                                data.push(bytes);
+                            } else {
+                                panic!("wrong data type");
+                            }
+                        }
+                        cmd::parse(&data, &CommandParsers::new(), &mut cmd_handler);
+                    }
+                    _ => panic!("wrong resp type "),
+                },
+                Err(ref e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        break;
+                    } else {
+                        panic!("err")
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_aof2() {
+        let mut file = File::open("tests/aof/appendonly2.aof").expect("file not found");
+
+        struct TestCmdHandler {
+            count: isize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, cmd: Event) {
+                match cmd {
+                    Event::RDB(_) => {}
+                    Event::AOF(cmd) => {
+                        if let Command::SET(set) = cmd {
+                            let key = String::from_utf8_lossy(set.key);
+                            if key.starts_with("test_") {
+                                self.count += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut cmd_handler = TestCmdHandler { count: 0 };
+
+        loop {
+            match file.decode_resp() {
+                Ok(resp) => match resp {
+                    Resp::Array(arr) => {
+                        let mut data = Vec::new();
+                        for x in arr {
+                            if let Resp::BulkBytes(bytes) = x {
+                                data.push(bytes);
+                            } else {
+                                panic!("wrong data type");
+                            }
+                        }
+                        cmd::parse(&data, &CommandParsers::new(), &mut cmd_handler);
+                    }
+                    _ => panic!("wrong resp type "),
+                },
+                Err(ref e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        break;
+                    } else {
+                        panic!("err")
+                    }
+                }
+            }
+        }
+
+        assert_eq!(48000, cmd_handler.count);
+    }
+
+    #[test]
+    fn test_aof3() {
+        let mut file = File::open("tests/aof/appendonly3.aof").expect("file not found");
+
+        struct TestCmdHandler {
+            count: isize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, _cmd: Event) {
+                self.count += 1;
+            }
+        }
+
+        let mut cmd_handler = TestCmdHandler { count: 0 };
+        loop {
+            match file.decode_resp() {
+                Ok(resp) => match resp {
+                    Resp::Array(arr) => {
+                        let mut data = Vec::new();
+                        for x in arr {
+                            if let Resp::BulkBytes(bytes) = x {
+                                data.push(bytes);
+                            } else {
+                                panic!("wrong data type");
+                            }
+                        }
+                        cmd::parse(&data, &CommandParsers::new(), &mut cmd_handler);
+                    }
+                    _ => panic!("wrong resp type "),
+                },
+                Err(ref e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        break;
+                    } else {
+                        panic!("err")
+                    }
+                }
+            }
+        }
+        assert_eq!(92539, cmd_handler.count);
+    }
+
+    #[test]
+    fn test_aof5() {
+        let mut file = File::open("tests/aof/appendonly5.aof").expect("file not found");
+
+        struct TestCmdHandler {
+            count: isize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, _cmd: Event) {
+                self.count += 1;
+            }
+        }
+
+        let mut cmd_handler = TestCmdHandler { count: 0 };
+
+        loop {
+            match file.decode_resp() {
+                Ok(resp) => match resp {
+                    Resp::Array(arr) => {
+                        let mut data = Vec::new();
+                        for x in arr {
+                            if let Resp::BulkBytes(bytes) = x {
+                                data.push(bytes);
+                            } else {
+                                panic!("wrong data type");
+                            }
+                        }
+                        cmd::parse(&data, &CommandParsers::new(), &mut cmd_handler);
+                    }
+                    _ => panic!("wrong resp type "),
+                },
+                Err(ref e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        break;
+                    } else {
+                        panic!("err")
+                    }
+                }
+            }
+        }
+
+        assert_eq!(71, cmd_handler.count);
+    }
+
+    #[test]
+    fn test_aof6() {
+        let mut file = File::open("tests/aof/appendonly.aof").expect("file not found");
+
+        struct TestCmdHandler {}
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, cmd: Event) {
+                match cmd {
+                    Event::RDB(_) => {}
+                    Event::AOF(cmd) => match cmd {
+                        Command::XADD(xadd) => {
+                            let key = String::from_utf8_lossy(xadd.key);
+                            assert_eq!(key, "stream");
+                            for field in &xadd.fields {
+                                let name = String::from_utf8_lossy(field.name);
+                                let value = String::from_utf8_lossy(field.value);
+                                if "name" == name {
+                                    assert_eq!("tomcat", value);
+                                } else if "age" == name {
+                                    assert_eq!("18", value);
                                 }
                             }
                         }
-                        _ => {}
-                    },
-                    _ => {}
+                        Command::XGROUP(xgroup) => {
+                            if let Some(create) = &xgroup.create {
+                                let key = String::from_utf8_lossy(create.key);
+                                assert_eq!("stream", key);
+                                let group = String::from_utf8_lossy(create.group_name);
+                                assert_eq!("group", group);
+                            }
+                        }
+                        Command::XTRIM(xtrim) => {
+                            assert_eq!(TrimStrategy::MAXLEN, xtrim.trim.strategy);
+                            assert_eq!(false, xtrim.trim.approximation);
+                            assert_eq!(b"1", xtrim.trim.threshold);
+                        }
+                        Command::XDEL(xdel) => {
+                            assert!(xdel.ids.len() == 1);
+                            let id = xdel.ids.get(0);
+                            let id = id.unwrap();
+                            let id = String::from_utf8_lossy(*id);
+                            assert_eq!("1588842699754-0", id);
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+
+        let mut cmd_handler = TestCmdHandler {};
+        loop {
+            match file.decode_resp() {
+                Ok(resp) => match resp {
+                    Resp::Array(arr) => {
+                        let mut data = Vec::new();
+                        for x in arr {
+                            if let Resp::BulkBytes(bytes) = x {
+                                data.push(bytes);
+                            } else {
+                                panic!("wrong data type");
+                            }
+                        }
+                        cmd::parse(&data, &CommandParsers::new(), &mut cmd_handler);
+                    }
+                    _ => panic!("wrong resp type "),
+                },
+                Err(ref e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        break;
+                    } else {
+                        panic!("err")
+                    }
+                }
+            }
+        }
+    }
+
+    // 记录String类型的key以及SELECT、SET和DEL命令
+    struct CommandRecorder {
+        keys: Vec<String>,
+        commands: Vec<String>,
+    }
+
+    impl EventHandler for CommandRecorder {
+        fn handle(&mut self, event: Event) {
+            match event {
+                Event::RDB(Object::String(kv)) => self.keys.push(String::from_utf8_lossy(kv.key).to_string()),
+                Event::RDB(_) => {}
+                Event::AOF(Command::SELECT(select)) => self.commands.push(format!("SELECT {}", select.db)),
+                Event::AOF(Command::SET(set)) => {
+                    self.commands.push(format!("SET {}", String::from_utf8_lossy(set.key)))
+                }
+                Event::AOF(Command::DEL(del)) => self
+                    .commands
+                    .push(format!("DEL {}", String::from_utf8_lossy(del.keys[0]))),
+                Event::AOF(_) => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_aof_with_rdb_preamble() {
+        let mut handler = CommandRecorder {
+            keys: Vec::new(),
+            commands: Vec::new(),
+        };
+        let info = aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        assert_eq!(ChecksumStatus::Disabled, info.unwrap().checksum);
+        assert_eq!(vec!["a", "b"], handler.keys);
+        // 第一个SELECT来自RDB preamble中的SELECTDB，末尾不完整的DEL命令被忽略
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], handler.commands);
+
+        handler.commands.clear();
+        let info = aof::parse_file("tests/aof/appendonly1.aof", &mut handler).unwrap();
+        assert!(info.is_none());
+        assert!(handler.commands.contains(&"SET a".to_string()));
+    }
+
+    #[test]
+    fn test_owned_events() {
+        struct Sender {
+            sender: mpsc::Sender<OwnedEvent>,
+        }
+
+        impl EventHandler for Sender {
+            fn handle(&mut self, event: Event) {
+                self.sender.send(event.to_owned()).unwrap();
+            }
+        }
+
+        // 拥有所有权的事件可以通过channel交给其他线程处理
+        let (sender, receiver) = mpsc::channel();
+        let consumer = thread::spawn(move || receiver.into_iter().collect::<Vec<OwnedEvent>>());
+        let mut handler = Sender { sender };
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        drop(handler);
+        let events = consumer.join().unwrap();
+
+        let mut keys = Vec::new();
+        let mut commands = Vec::new();
+        for event in &events {
+            match event {
+                OwnedEvent::RDB(OwnedObject::String { key, .. }) => keys.push(key.clone()),
+                OwnedEvent::RDB(_) => {}
+                OwnedEvent::AOF(cmd) => commands.push(cmd.name()),
+            }
+        }
+        assert_eq!(vec![b"a".to_vec(), b"b".to_vec()], keys);
+        assert_eq!(vec!["SELECT", "SELECT", "SET", "DEL"], commands);
+    }
+
+    #[test]
+    fn test_channel_handler() {
+        // 队列大小小于事件个数，解析线程会等待消费线程
+        let (mut handler, receiver) = channel::channel(1);
+        let consumer = thread::spawn(move || receiver.collect::<Vec<OwnedEvent>>());
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        drop(handler);
+        let commands: Vec<String> = consumer
+            .join()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                OwnedEvent::AOF(cmd) => Some(cmd.name()),
+                OwnedEvent::RDB(_) => None,
+            })
+            .collect();
+        assert_eq!(vec!["SELECT", "SELECT", "SET", "DEL"], commands);
+
+        // flush等待消费者处理完所有已放入队列的事件
+        let (mut handler, receiver) = channel::channel(16);
+        let processed = Arc::new(Mutex::new(0));
+        let consumer = {
+            let processed = Arc::clone(&processed);
+            thread::spawn(move || {
+                for _event in receiver {
+                    thread::sleep(Duration::from_millis(10));
+                    *processed.lock().unwrap() += 1;
+                }
+            })
+        };
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        handler.flush().unwrap();
+        let flushed = *processed.lock().unwrap();
+        drop(handler);
+        consumer.join().unwrap();
+        assert_eq!(*processed.lock().unwrap(), flushed);
+
+        // 接收端被drop之后丢弃事件，并将控制变量置为false，flush返回错误
+        let (mut handler, receiver) = channel::channel(16);
+        let running = Arc::new(AtomicBool::new(true));
+        handler.with_control_flag(Arc::clone(&running));
+        drop(receiver);
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        assert!(handler.is_disconnected());
+        assert!(!running.load(Ordering::SeqCst));
+        assert_eq!(ErrorKind::BrokenPipe, handler.flush().unwrap_err().kind());
+    }
+
+    // 前`failures`次收到SET时返回错误
+    struct FlakyHandler {
+        failures: u32,
+        is_retry: bool,
+        commands: Vec<String>,
+    }
+
+    impl FallibleEventHandler for FlakyHandler {
+        fn handle(&mut self, event: Event) -> Result<(), HandlerError> {
+            if let Event::AOF(cmd) = event {
+                let name = cmd.to_owned().name();
+                if name == "SET" && self.failures > 0 {
+                    self.failures -= 1;
+                    let error = Error::new(ErrorKind::ConnectionRefused, "downstream unavailable");
+                    return Err(if self.is_retry {
+                        HandlerError::Retry(error)
+                    } else {
+                        HandlerError::Fatal(error)
+                    });
+                }
+                self.commands.push(name);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let (mut handler, bus) = broadcast::bus(2);
+        let blocking = bus.subscribe_blocking();
+        let mut lossy = bus.subscribe();
+        // 缓冲区大小小于事件个数，阻塞式的消费者不会丢失事件
+        let consumer = thread::spawn(move || {
+            blocking
+                .filter_map(|event| match &*event {
+                    OwnedEvent::AOF(cmd) => Some(cmd.name()),
+                    OwnedEvent::RDB(_) => None,
+                })
+                .collect::<Vec<String>>()
+        });
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        drop(handler);
+        assert_eq!(vec!["SELECT", "SELECT", "SET", "DEL"], consumer.join().unwrap());
+
+        // 未消费的消费者落后，只能收到最后两个事件
+        let tail = bus.tail();
+        assert_eq!(Err(broadcast::RecvError::Lagged(tail - 2)), lossy.recv().map(|_| ()));
+        assert_eq!(tail - 2, lossy.position());
+        assert_eq!(2, lossy.by_ref().count());
+        assert_eq!(tail, lossy.position());
+        assert_eq!(Err(broadcast::RecvError::Closed), lossy.try_recv().map(|_| ()));
+
+        // flush等待阻塞式的消费者处理完所有事件，不等待Bus::subscribe订阅的消费者
+        let (mut handler, bus) = broadcast::bus(16);
+        let blocking = bus.subscribe_blocking();
+        let _lossy = bus.subscribe();
+        let processed = Arc::new(Mutex::new(0));
+        let consumer = {
+            let processed = Arc::clone(&processed);
+            thread::spawn(move || {
+                for _event in blocking {
+                    thread::sleep(Duration::from_millis(10));
+                    *processed.lock().unwrap() += 1;
+                }
+            })
+        };
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        handler.flush().unwrap();
+        assert_eq!(bus.tail(), *processed.lock().unwrap());
+        drop(handler);
+        consumer.join().unwrap();
+    }
+
+    #[test]
+    fn test_fallible_handler() {
+        for &is_retry in &[false, true] {
+            let mut handler = Fallible::new(FlakyHandler {
+                failures: 1,
+                is_retry,
+                commands: Vec::new(),
+            });
+            let error = aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap_err();
+            assert_eq!(ErrorKind::ConnectionRefused, error.kind());
+            // Fatal直接转换为其中的错误，Retry则保留在错误之中
+            let inner = error.get_ref().and_then(|inner| inner.downcast_ref::<HandlerError>());
+            assert_eq!(is_retry, inner.map_or(false, HandlerError::is_retry));
+            // 出错之后不再继续解析
+            assert_eq!(vec!["SELECT", "SELECT"], handler.get_ref().commands);
+            assert!(handler.take_error().is_none());
+        }
+    }
+
+    #[test]
+    fn test_handler_retry() {
+        use crate::resp::encode_command;
+
+        let select = encode_command(&[b"SELECT".to_vec(), b"0".to_vec()]);
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        for &failures in &[1, 2] {
+            let server = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = server.local_addr().unwrap().port();
+            let commands = [select.clone(), set.clone()].concat();
+            let resume_offset = (100 + select.len() + 1).to_string();
+            let stream = set.clone();
+            let master = thread::spawn(move || {
+                let (mut first, _) = accept_replica(&server);
+                first.write_all(&full_resync_reply(false, &[])).unwrap();
+                // RDB之后的数据需要在replica开始接收命令(即发送第一个REPLCONF ACK)之后再发送
+                first.decode_resp().unwrap();
+                first.write_all(&commands).unwrap();
+                // 处理SET出错之后重连，从SET开始继续同步
+                let (mut second, args) = accept_replica(&server);
+                assert_eq!(vec!["0".repeat(40).into_bytes(), resume_offset.into_bytes()], args);
+                second.write_all(b"+CONTINUE\r\n").unwrap();
+                second.write_all(&stream).unwrap();
+            });
+
+            let mut config = full_resync_config(port);
+            config.max_handler_retries = Some(1);
+            let handler = Arc::new(Mutex::new(Fallible::new(FlakyHandler {
+                failures,
+                is_retry: true,
+                commands: Vec::new(),
+            })));
+            let mut builder = Builder::new();
+            builder.with_config(config);
+            builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+            builder.with_event_handler(Box::new(Arc::clone(&handler)));
+            let error = builder.build().start().unwrap_err();
+            master.join().unwrap();
+
+            let handler = handler.lock().unwrap();
+            let commands = &handler.get_ref().commands;
+            if failures == 1 {
+                // 重试成功，master关闭连接之后返回错误
+                assert_ne!(ErrorKind::ConnectionRefused, error.kind());
+                assert_eq!(&vec!["SELECT", "SELECT", "SET"], commands);
+            } else {
+                // 超出重试次数，返回处理器的错误
+                assert_eq!(ErrorKind::ConnectionRefused, error.kind());
+                assert_eq!(&vec!["SELECT", "SELECT"], commands);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ack_open_transaction() {
+        use crate::resp::encode_command;
+
+        let select = encode_command(&[b"SELECT".to_vec(), b"0".to_vec()]);
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        let multi = encode_command(&[b"MULTI".to_vec()]);
+        let set_in_multi = encode_command(&[b"SET".to_vec(), b"b".to_vec(), b"2".to_vec()]);
+        let exec = encode_command(&[b"EXEC".to_vec()]);
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let commands = [select.clone(), set.clone(), multi.clone(), set_in_multi.clone()].concat();
+        let transaction = [multi, set_in_multi, exec].concat();
+        // 确认的offset停在MULTI之前
+        let acked = 100 + (select.len() + set.len()) as i64;
+        let master = thread::spawn(move || {
+            let (mut first, _) = accept_replica(&server);
+            first.write_all(&full_resync_reply(false, &[])).unwrap();
+            first.decode_resp().unwrap();
+            // 事务尚未结束时断开连接
+            first.write_all(&commands).unwrap();
+            drop(first);
+            let (mut second, args) = accept_replica(&server);
+            assert_eq!(
+                vec!["0".repeat(40).into_bytes(), (acked + 1).to_string().into_bytes()],
+                args
+            );
+            second.write_all(b"+CONTINUE\r\n").unwrap();
+            second.write_all(&transaction).unwrap();
+        });
+
+        let mut config = full_resync_config(port);
+        config.is_ack_after_handle = true;
+        let recorder = Arc::new(Mutex::new(CommandRecorder {
+            keys: Vec::new(),
+            commands: Vec::new(),
+        }));
+        let mut builder = Builder::new();
+        builder.with_config(config);
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(TransactionGrouper::new(Arc::clone(&recorder))));
+        let mut listener = builder.build();
+        listener.start().unwrap_err();
+        assert_eq!(acked, listener.config.repl_offset);
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET a"], recorder.lock().unwrap().commands);
+
+        // 从MULTI开始重新接收整个事务，之前暂存的部分被丢弃
+        listener.start().unwrap_err();
+        master.join().unwrap();
+        assert_eq!(
+            vec!["SELECT 0", "SELECT 0", "SET a", "SET b"],
+            recorder.lock().unwrap().commands
+        );
+    }
+
+    #[test]
+    fn test_protocol_error() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let master = thread::spawn(move || {
+            let (mut stream, _) = accept_replica(&server);
+            stream.write_all(&full_resync_reply(false, &[])).unwrap();
+            stream.decode_resp().unwrap();
+            // 命令应为RESP array
+            stream.write_all(b"+OK\r\n").unwrap();
+            (server, stream)
+        });
+
+        let mut config = full_resync_config(port);
+        config.max_full_resyncs = Some(1);
+        let mut builder = Builder::new();
+        builder.with_config(config);
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(NoOpEventHandler {}));
+        let error = builder.build().start().unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, error.kind());
+
+        // 不是RDB中的错误，不会重新进行全量同步
+        let (server, _stream) = master.join().unwrap();
+        server.set_nonblocking(true).unwrap();
+        assert_eq!(ErrorKind::WouldBlock, server.accept().unwrap_err().kind());
+    }
+
+    #[test]
+    fn test_master_timeout() {
+        struct TimeoutRecorder {
+            timeouts: Vec<Duration>,
+        }
+
+        impl LifecycleHandler for TimeoutRecorder {
+            fn on_master_timeout(&mut self, timeout: Duration) {
+                self.timeouts.push(timeout);
+            }
+        }
+
+        for &(read_timeout, master_timeout) in &[(Some(100), 10_000), (None, 100)] {
+            let server = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = server.local_addr().unwrap().port();
+            let (done, wait) = mpsc::channel::<()>();
+            let is_reconnect = read_timeout.is_none();
+            let master = thread::spawn(move || {
+                let (mut stream, _) = accept_replica(&server);
+                stream.write_all(&full_resync_reply(false, &[])).unwrap();
+                if is_reconnect {
+                    // 超时之后重新连接，master关闭新的连接以结束同步
+                    drop(accept_replica(&server));
+                }
+                // 在replica返回之前保持第一个连接，不发送任何数据
+                let _ = wait.recv();
+            });
+
+            let mut config = full_resync_config(port);
+            config.read_timeout = read_timeout.map(Duration::from_millis);
+            config.master_timeout = Some(Duration::from_millis(master_timeout));
+            let recorder = Arc::new(Mutex::new(TimeoutRecorder { timeouts: Vec::new() }));
+            let mut builder = Builder::new();
+            builder.with_config(config);
+            builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+            builder.with_event_handler(Box::new(NoOpEventHandler {}));
+            builder.with_lifecycle_handler(Box::new(Arc::clone(&recorder)));
+            let error = builder.build().start().unwrap_err();
+            done.send(()).unwrap();
+            master.join().unwrap();
+
+            let timeouts = &recorder.lock().unwrap().timeouts;
+            if is_reconnect {
+                assert_eq!(&vec![Duration::from_millis(master_timeout)], timeouts);
+            } else {
+                // read_timeout先于master_timeout到期，不属于master超时，不会重新连接
+                assert!(matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut));
+                assert!(timeouts.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_master_timeout_during_rdb() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let master = thread::spawn(move || {
+            let (mut stream, _) = accept_replica(&server);
+            // RDB只发送一部分，之后不再发送任何数据
+            let reply = full_resync_reply(false, &[]);
+            stream.write_all(&reply[..reply.len() - 10]).unwrap();
+            // 超时之后重新连接，master关闭新的连接以结束同步
+            let (_, args) = accept_replica(&server);
+            args
+        });
+
+        let mut config = full_resync_config(port);
+        config.master_timeout = Some(Duration::from_millis(100));
+        let mut builder = Builder::new();
+        builder.with_config(config);
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(NoOpEventHandler {}));
+        assert!(builder.build().start().is_err());
+        // RDB未接收完毕，重新连接时需要重新进行全量同步
+        assert_eq!(vec![b"?".to_vec(), b"-1".to_vec()], master.join().unwrap());
+    }
+
+    #[test]
+    fn test_restart_after_rdb_failure() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let master = thread::spawn(move || {
+            let (mut stream, _) = accept_replica(&server);
+            // RDB只发送一部分就关闭连接
+            let reply = full_resync_reply(false, &[]);
+            stream.write_all(&reply[..reply.len() - 10]).unwrap();
+            drop(stream);
+            let (_, args) = accept_replica(&server);
+            args
+        });
+
+        let mut builder = Builder::new();
+        builder.with_config(full_resync_config(port));
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(NoOpEventHandler {}));
+        let mut listener = builder.build();
+        assert!(listener.start().is_err());
+        // 与ListenerGroup重启时一样，复用同一个监听器再次同步
+        assert!(listener.start().is_err());
+        assert_eq!(vec![b"?".to_vec(), b"-1".to_vec()], master.join().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "metrics-prometheus")]
+    fn test_listener_collector() {
+        use std::collections::HashMap;
+
+        use prometheus::core::Collector;
+        use prometheus::proto::MetricType;
+
+        use crate::metrics::ListenerCollector;
+        use crate::resp::encode_command;
+
+        // 各指标的值，事件数量以`events_total{<type>}`为名
+        fn values(collector: &ListenerCollector) -> HashMap<String, f64> {
+            let mut values = HashMap::new();
+            for family in collector.collect() {
+                for metric in family.get_metric() {
+                    let mut name = family.get_name().to_string();
+                    for label in metric.get_label() {
+                        match label.get_name() {
+                            "source" => assert_eq!("redis-1", label.get_value()),
+                            _ => name = format!("{}{{{}}}", name, label.get_value()),
+                        }
+                    }
+                    let value = if family.get_field_type() == MetricType::COUNTER {
+                        metric.get_counter().get_value()
+                    } else {
+                        metric.get_gauge().get_value()
+                    };
+                    values.insert(name, value);
+                }
+            }
+            values
+        }
+
+        let select = encode_command(&[b"SELECT".to_vec(), b"0".to_vec()]);
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let commands = [select, set].concat();
+        let (next, wait) = mpsc::channel::<()>();
+        let master = thread::spawn(move || {
+            // 两次全量同步，每次之后发送两条命令并关闭连接
+            for _ in 0..2 {
+                wait.recv().unwrap();
+                let (mut stream, _) = accept_replica(&server);
+                stream.write_all(&full_resync_reply(false, &[])).unwrap();
+                stream.decode_resp().unwrap();
+                stream.write_all(&commands).unwrap();
+            }
+        });
+
+        let mut builder = Builder::new();
+        builder.with_config(full_resync_config(port));
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(NoOpEventHandler {}));
+        let mut listener = builder.build();
+        let collector = ListenerCollector::new(listener.health_check(), "redis-1").unwrap();
+        let rdb_size = fs::metadata("tests/rdb/keys_with_expiry.rdb").unwrap().len() as f64;
+
+        next.send(()).unwrap();
+        listener.start().unwrap_err();
+        let first = values(&collector);
+        let stats = listener.stats();
+        assert_eq!(Some(&0.0), first.get("redis_event_connected"));
+        assert_eq!(Some(&rdb_size), first.get("redis_event_rdb_bytes"));
+        assert_eq!(Some(&2.0), first.get("redis_event_commands_parsed_total"));
+        assert_eq!(
+            Some(&(stats.bytes_received as f64)),
+            first.get("redis_event_received_bytes_total")
+        );
+        assert_eq!(Some(&1.0), first.get("redis_event_events_total{string}"));
+        assert_eq!(
+            Some(&(stats.events.commands as f64)),
+            first.get("redis_event_events_total{command}")
+        );
+        assert_eq!(Some(&0.0), first.get("redis_event_events_total{stream}"));
+        // 重复抓取时counter不会重复累加
+        assert_eq!(first, values(&collector));
+
+        next.send(()).unwrap();
+        listener.start().unwrap_err();
+        master.join().unwrap();
+        let second = values(&collector);
+        let stats = listener.stats();
+        assert_eq!(Some(&4.0), second.get("redis_event_commands_parsed_total"));
+        assert_eq!(
+            Some(&(stats.bytes_received as f64)),
+            second.get("redis_event_received_bytes_total")
+        );
+        assert_eq!(Some(&2.0), second.get("redis_event_events_total{string}"));
+        // counter只增不减
+        for (name, value) in &first {
+            if name.contains("_total") {
+                assert!(second[name] >= *value, "{}", name);
+            }
+        }
+        assert_eq!(second, values(&collector));
+    }
+
+    #[test]
+    fn test_send_listener() {
+        use crate::resp::encode_command;
+
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        let master = thread::spawn(move || {
+            let (mut stream, _) = accept_replica(&server);
+            stream.write_all(&full_resync_reply(false, &[])).unwrap();
+            stream.decode_resp().unwrap();
+            stream.write_all(&set).unwrap();
+        });
+
+        let recorder = Arc::new(Mutex::new(CommandRecorder {
+            keys: Vec::new(),
+            commands: Vec::new(),
+        }));
+        let mut builder = Builder::new();
+        builder.with_config(full_resync_config(port));
+        builder.with_control_flag(Arc::new(AtomicBool::new(true)));
+        builder.with_event_handler(Box::new(Arc::clone(&recorder)));
+        let mut listener = builder.build();
+        // 监听器可以在创建之后交给其他线程运行
+        let replica = thread::spawn(move || listener.start());
+        master.join().unwrap();
+        // master关闭连接之后返回错误
+        assert!(replica.join().unwrap().is_err());
+
+        let recorder = recorder.lock().unwrap();
+        assert!(!recorder.keys.is_empty());
+        // SELECT来自RDB中的SELECTDB
+        assert_eq!(vec!["SELECT 0", "SET a"], recorder.commands);
+    }
+
+    #[test]
+    fn test_composite_handler() {
+        for &policy in &[ErrorPolicy::Propagate, ErrorPolicy::Ignore, ErrorPolicy::Detach] {
+            let flaky = Arc::new(Mutex::new(Fallible::new(FlakyHandler {
+                failures: 1,
+                is_retry: false,
+                commands: Vec::new(),
+            })));
+            let recorder = Arc::new(Mutex::new(CommandRecorder {
+                keys: Vec::new(),
+                commands: Vec::new(),
+            }));
+            let mut handler = CompositeHandler::new();
+            handler.add("flaky", Box::new(Arc::clone(&flaky)), policy);
+            handler.add("recorder", Box::new(Arc::clone(&recorder)), ErrorPolicy::Propagate);
+            let result = aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler);
+
+            let flaky = flaky.lock().unwrap();
+            let recorder = recorder.lock().unwrap();
+            // 每个处理器都收到了RDB preamble中的key
+            assert_eq!(vec!["a", "b"], recorder.keys);
+            match policy {
+                ErrorPolicy::Propagate => {
+                    // 出错的命令不再交给之后的处理器，并停止解析
+                    assert_eq!(ErrorKind::ConnectionRefused, result.unwrap_err().kind());
+                    assert_eq!(vec!["SELECT 0", "SELECT 0"], recorder.commands);
+                    assert!(handler.detached().is_empty());
+                }
+                ErrorPolicy::Ignore => {
+                    result.unwrap();
+                    assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], recorder.commands);
+                    assert_eq!(vec!["SELECT", "SELECT", "DEL"], flaky.get_ref().commands);
+                    assert!(handler.detached().is_empty());
+                }
+                ErrorPolicy::Detach => {
+                    result.unwrap();
+                    assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], recorder.commands);
+                    assert_eq!(vec!["SELECT", "SELECT"], flaky.get_ref().commands);
+                    assert_eq!(vec!["flaky"], handler.detached());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter_and_parse = |filter: Filter| {
+            let recorder = CommandRecorder {
+                keys: Vec::new(),
+                commands: Vec::new(),
+            };
+            let mut handler = Filtered::new(recorder, filter);
+            aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+            handler.into_inner()
+        };
+
+        let recorder = filter_and_parse(Filter::new());
+        assert_eq!(vec!["a", "b"], recorder.keys);
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], recorder.commands);
+
+        // 不涉及key的SELECT不受key的条件影响
+        let recorder = filter_and_parse(Filter::new().keys("a*").keys("c"));
+        assert_eq!(vec!["a"], recorder.keys);
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], recorder.commands);
+
+        let recorder = filter_and_parse(Filter::new().keys("[ab]").commands(&["del"]));
+        assert_eq!(vec!["a", "b"], recorder.keys);
+        assert_eq!(vec!["DEL a"], recorder.commands);
+
+        let recorder = filter_and_parse(Filter::new().dbs(&[1]));
+        assert!(recorder.keys.is_empty());
+        assert!(recorder.commands.is_empty());
+
+        // 类型的条件对命令没有影响
+        let recorder = filter_and_parse(Filter::new().types(&[ObjectType::Hash]));
+        assert!(recorder.keys.is_empty());
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET c", "DEL a"], recorder.commands);
+    }
+
+    #[test]
+    fn test_transformer() {
+        let recorder = CommandRecorder {
+            keys: Vec::new(),
+            commands: Vec::new(),
+        };
+        let mut handler = Transformer::new(recorder);
+        handler.add(Box::new(|mut event: OwnedEvent| {
+            transform::map_keys(&mut event, |key| *key = [b"app:", key.as_slice()].concat());
+            Some(event)
+        }));
+        // 之后的阶段收到的是改写之后的事件
+        handler.add(Box::new(|event: OwnedEvent| match &event {
+            OwnedEvent::AOF(cmd) if cmd.args[1] == b"app:a" => None,
+            _ => Some(event),
+        }));
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        let recorder = handler.into_inner();
+        assert_eq!(vec!["app:a", "app:b"], recorder.keys);
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET app:c"], recorder.commands);
+
+        let args = |args: &[&str]| args.iter().map(|arg| arg.as_bytes().to_vec()).collect::<Vec<_>>();
+        // 与key内容相同的值不会被当作key
+        let event = OwnedEvent::AOF(OwnedCommand::new(args(&["MSET", "p:a", "p:a", "p:b", "v"])));
+        match StripKeyPrefix::new(b"p:").transform(event) {
+            Some(OwnedEvent::AOF(cmd)) => assert_eq!(args(&["MSET", "a", "p:a", "b", "v"]), cmd.args),
+            _ => panic!("expected a command"),
+        }
+        let event = OwnedEvent::AOF(OwnedCommand::new(args(&["SET", "password", "password1", "EX", "10"])));
+        match RedactValues::new("pass*", b"***").transform(event) {
+            Some(OwnedEvent::AOF(cmd)) => assert_eq!(args(&["SET", "password", "***", "EX", "10"]), cmd.args),
+            _ => panic!("expected a command"),
+        }
+    }
+
+    #[test]
+    fn test_typed_event_handler() {
+        #[derive(Default)]
+        struct Recorder {
+            events: Vec<String>,
+        }
+
+        impl TypedEventHandler for Recorder {
+            fn on_rdb_end(&mut self) {
+                self.events.push("EOR".to_string());
+            }
+
+            fn on_string(&mut self, kv: &KeyValue) {
+                self.events.push(format!("string {}", String::from_utf8_lossy(kv.key)));
+            }
+
+            fn on_select(&mut self, cmd: &SELECT) {
+                self.events.push(format!("SELECT {}", cmd.db));
+            }
+
+            fn on_set_cmd(&mut self, cmd: &SET) {
+                self.events.push(format!("SET {}", String::from_utf8_lossy(cmd.key)));
+            }
+
+            fn on_del(&mut self, cmd: &DEL) {
+                self.events
+                    .push(format!("DEL {}", String::from_utf8_lossy(&cmd.keys[0])));
+            }
+
+            fn on_other_command(&mut self, cmd: &Command) {
+                self.events.push(format!("other {}", cmd.to_owned().name()));
+            }
+        }
+
+        let mut handler = Typed::new(Recorder::default());
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        assert_eq!(
+            vec!["SELECT 0", "string a", "string b", "EOR", "SELECT 0", "SET c", "DEL a"],
+            handler.get_ref().events
+        );
+    }
+
+    #[test]
+    fn test_replayer() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let target = thread::spawn(move || {
+            let mut connections = Vec::new();
+            for (index, stream) in server.incoming().take(2).enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut commands = Vec::new();
+                while let Ok(Resp::Array(args)) = reader.decode_resp() {
+                    let args: Vec<String> = args
+                        .into_iter()
+                        .take(2)
+                        .map(|arg| match arg {
+                            Resp::BulkBytes(arg) => String::from_utf8_lossy(&arg).to_string(),
+                            _ => panic!("wrong type"),
+                        })
+                        .collect();
+                    // 第一个连接在收到第三条命令之后断开
+                    if index == 0 && commands.len() == 2 {
+                        break;
+                    }
+                    commands.push(args.join(" "));
+                    stream.write_all(b"+OK\r\n").unwrap();
+                }
+                connections.push(commands);
+            }
+            connections
+        });
+
+        let mut config = ReplayConfig::new("127.0.0.1", port);
+        config.retry_interval = Duration::from_millis(10);
+        let mut handler = Replayer::new(config);
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        handler.flush().unwrap();
+        assert!(handler.take_error().is_none());
+        drop(handler);
+
+        let connections = target.join().unwrap();
+        assert_eq!(vec!["SELECT 0", "RESTORE a"], connections[0]);
+        // 重新连接之后重新发送尚未收到响应的命令
+        assert_eq!(
+            vec!["SELECT 0", "RESTORE a", "RESTORE b", "SELECT 0", "SET c", "DEL a"],
+            connections[1]
+        );
+    }
+
+    #[test]
+    fn test_json_lines_writer() {
+        let mut handler = JsonLinesWriter::new(Vec::new());
+        handler.with_key_encoding(Encoding::Hex);
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut handler).unwrap();
+        handler.flush().unwrap();
+        let output = String::from_utf8(handler.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            vec![
+                r#"{"type":"begin_rdb"}"#,
+                r#"{"type":"command","command":"SELECT","args":["0"]}"#,
+                r#"{"type":"string","db":0,"key":"61","value":"1"}"#,
+                r#"{"type":"string","db":0,"key":"62","value":"2"}"#,
+                r#"{"type":"end_rdb"}"#,
+                r#"{"type":"command","command":"SELECT","args":["0"]}"#,
+                r#"{"type":"command","command":"SET","args":["c","3"]}"#,
+                r#"{"type":"command","command":"DEL","args":["a"]}"#,
+            ],
+            lines
+        );
+        assert_eq!("\\xff\\xfe", Encoding::Utf8.encode(b"\xff\xfe"));
+        assert_eq!("aGk=", Encoding::Base64.encode(b"hi"));
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = env::temp_dir().join(format!("redis-event-rotating-file-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.jsonl");
+        let mut file = RotatingFile::new(&path).unwrap();
+        file.with_max_size(8);
+        // 只在一行写完之后切换
+        file.write_all(b"first").unwrap();
+        file.write_all(b" line\n").unwrap();
+        file.write_all(b"second line\n").unwrap();
+        file.write_all(b"third\n").unwrap();
+        file.flush().unwrap();
+        drop(file);
+
+        assert_eq!("first line\n", fs::read_to_string(dir.join("events.jsonl.1")).unwrap());
+        assert_eq!("second line\n", fs::read_to_string(dir.join("events.jsonl.2")).unwrap());
+        assert_eq!("third\n", fs::read_to_string(&path).unwrap());
+
+        // 重新打开时继续追加，切换时接着已有的序号
+        let mut file = RotatingFile::new(&path).unwrap();
+        file.with_max_size(4);
+        file.write_all(b"fourth\n").unwrap();
+        file.flush().unwrap();
+        drop(file);
+        assert_eq!("third\n", fs::read_to_string(dir.join("events.jsonl.3")).unwrap());
+        assert_eq!("fourth\n", fs::read_to_string(&path).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_async_event_handler() {
+        struct Recorder {
+            commands: Vec<String>,
+        }
+
+        impl AsyncEventHandler for Recorder {
+            async fn handle(&mut self, event: OwnedEvent) {
+                if let OwnedEvent::AOF(cmd) = event {
+                    self.commands.push(cmd.name());
+                }
+            }
+        }
+
+        // 不依赖运行时，直接轮询处理器返回的Future
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = pin!(future);
+            let mut context = Context::from_waker(Waker::noop());
+            loop {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                    return output;
+                }
+            }
+        }
+
+        let (mut sender, receiver) = channel::channel(64);
+        aof::parse_file("tests/aof/appendonly_preamble.aof", &mut sender).unwrap();
+        drop(sender);
+        let mut handler = Recorder { commands: Vec::new() };
+        for (offset, event) in receiver.enumerate() {
+            let position = AofPosition {
+                offset: offset as i64,
+                sequence: offset as u64 + 1,
+                timestamp: None,
+            };
+            block_on(handler.handle_aof(event, position));
+        }
+        block_on(handler.flush()).unwrap();
+        assert_eq!(vec!["SELECT", "SELECT", "SET", "DEL"], handler.commands);
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    struct AsyncRecorder {
+        keys: Vec<Vec<u8>>,
+        commands: Vec<(String, i64)>,
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    impl AsyncEventHandler for AsyncRecorder {
+        async fn handle(&mut self, event: OwnedEvent) {
+            if let OwnedEvent::RDB(OwnedObject::String { key, .. }) = event {
+                self.keys.push(key);
+            }
+        }
+
+        async fn handle_aof(&mut self, event: OwnedEvent, position: AofPosition) {
+            if let OwnedEvent::AOF(cmd) = event {
+                self.commands.push((cmd.name(), position.offset));
+            }
+        }
+    }
+
+    // 模拟master接受replica的连接: 回复握手阶段的命令，直到收到PSYNC，返回连接以及PSYNC的参数
+    fn accept_replica(server: &TcpListener) -> (TcpStream, Vec<Vec<u8>>) {
+        let (mut stream, _) = server.accept().unwrap();
+        loop {
+            let mut args: Vec<Vec<u8>> = match stream.decode_resp().unwrap() {
+                Resp::Array(args) => args
+                    .into_iter()
+                    .map(|arg| match arg {
+                        Resp::BulkBytes(arg) => arg,
+                        resp => panic!("unexpected {:?}", resp),
+                    })
+                    .collect(),
+                resp => panic!("unexpected {:?}", resp),
+            };
+            if args[0] == b"PSYNC" {
+                return (stream, args.split_off(1));
+            }
+            stream.write_all(b"+OK\r\n").unwrap();
+        }
+    }
+
+    // FULLRESYNC的响应，offset为100，之后依次是RDB以及命令
+    fn full_resync_reply(is_diskless: bool, commands: &[u8]) -> Vec<u8> {
+        let rdb = fs::read("tests/rdb/keys_with_expiry.rdb").unwrap();
+        let mut reply = format!("+FULLRESYNC {} 100\r\n\n\n", "0".repeat(40)).into_bytes();
+        if is_diskless {
+            let mark = "m".repeat(40);
+            reply.extend_from_slice(format!("$EOF:{}\r\n", mark).as_bytes());
+            reply.extend_from_slice(&rdb);
+            reply.extend_from_slice(mark.as_bytes());
+        } else {
+            reply.extend_from_slice(format!("${}\r\n", rdb.len()).as_bytes());
+            reply.extend_from_slice(&rdb);
+        }
+        reply.extend_from_slice(commands);
+        reply
+    }
+
+    // 模拟master: 全量同步之后关闭连接
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    fn serve_full_resync(server: TcpListener, is_diskless: bool, commands: &[u8]) {
+        let (mut stream, _) = accept_replica(&server);
+        stream.write_all(&full_resync_reply(is_diskless, commands)).unwrap();
+    }
+
+    fn full_resync_config(port: u16) -> crate::config::Config {
+        use crate::config::{Config, ParseMode};
+
+        Config {
+            is_discard_rdb: false,
+            is_aof: true,
+            host: String::from("127.0.0.1"),
+            port,
+            username: String::new(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        }
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    fn check_async_listener<R: Runtime>(run: impl Fn(&mut AsyncListener<R, AsyncRecorder>) -> std::io::Result<()>) {
+        use crate::resp::encode_command;
+
+        struct JsonSet {}
+
+        impl Module for JsonSet {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+
+        // 记录被调用的次数，确认自定义的命令解析器同样被异步监听器使用
+        struct JsonSetParser {
+            parsed: usize,
+        }
+
+        impl CommandParser for JsonSetParser {
+            fn parse(&mut self, _: &str, _: &[Vec<u8>]) -> Box<dyn Module> {
+                self.parsed += 1;
+                Box::new(JsonSet {})
+            }
+        }
+
+        let select = encode_command(&[b"SELECT".to_vec(), b"0".to_vec()]);
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        let json_set = encode_command(&[b"JSON.SET".to_vec(), b"j".to_vec(), b"$".to_vec(), b"1".to_vec()]);
+        let commands = [select.clone(), set.clone(), json_set.clone()].concat();
+        for &is_diskless in &[false, true] {
+            let server = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = server.local_addr().unwrap().port();
+            let stream = commands.clone();
+            let master = thread::spawn(move || serve_full_resync(server, is_diskless, &stream));
+
+            let config = full_resync_config(port);
+            let handler = AsyncRecorder {
+                keys: Vec::new(),
+                commands: Vec::new(),
+            };
+            let mut listener = AsyncListener::<R, _>::new(config, handler);
+            let parser = Arc::new(Mutex::new(JsonSetParser { parsed: 0 }));
+            listener.with_command_parser("json.set", parser.clone());
+            // master关闭连接之后返回错误
+            let error = run(&mut listener).unwrap_err();
+            assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+            master.join().unwrap();
+
+            let offset = 100 + select.len() as i64;
+            let expected = vec![
+                ("SELECT".to_string(), offset),
+                ("SET".to_string(), offset + set.len() as i64),
+                ("JSON.SET".to_string(), offset + (set.len() + json_set.len()) as i64),
+            ];
+            assert_eq!(vec![b"expires_ms_precision".to_vec()], listener.get_ref().keys);
+            assert_eq!(expected, listener.get_ref().commands);
+            assert_eq!(1, parser.lock().unwrap().parsed);
+            assert_eq!(100 + commands.len() as i64, listener.config.repl_offset);
+            let stats = listener.health_check().stats();
+            assert_eq!(3, stats.commands_parsed);
+            assert_eq!(
+                fs::metadata("tests/rdb/keys_with_expiry.rdb").unwrap().len(),
+                stats.rdb_size
+            );
+        }
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-std", feature = "smol"))]
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio_listener() {
+        use crate::listener::tokio::Tokio;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        check_async_listener::<Tokio>(|listener| {
+            let start = listener.start();
+            assert_send(&start);
+            runtime.block_on(start)
+        });
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn test_async_std_listener() {
+        use crate::listener::async_std::AsyncStd;
+
+        check_async_listener::<AsyncStd>(|listener| {
+            let start = listener.start();
+            assert_send(&start);
+            async_std::task::block_on(start)
+        });
+    }
+
+    #[cfg(feature = "smol")]
+    #[test]
+    fn test_smol_listener() {
+        use crate::listener::smol::Smol;
+
+        check_async_listener::<Smol>(|listener| {
+            let start = listener.start();
+            assert_send(&start);
+            smol::block_on(start)
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_event_stream() {
+        use crate::listener::runtime;
+        use crate::listener::tokio::Tokio;
+        use crate::resp::encode_command;
+        use futures_core::Stream;
+        use std::future::poll_fn;
+        use std::pin::Pin;
+
+        let select = encode_command(&[b"SELECT".to_vec(), b"0".to_vec()]);
+        let set = encode_command(&[b"SET".to_vec(), b"a".to_vec(), b"1".to_vec()]);
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let stream = [select, set].concat();
+        let master = thread::spawn(move || serve_full_resync(server, false, &stream));
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut events = runtime::events::<Tokio>(full_resync_config(port));
+        assert_send(&events);
+        let health_check = events.health_check();
+        let mut next = || runtime.block_on(poll_fn(|cx| Pin::new(&mut events).poll_next(cx)));
+        let mut keys = Vec::new();
+        let mut commands = Vec::new();
+        // master关闭连接之后产生一个错误，之后结束
+        let error = loop {
+            match next() {
+                Some(Ok(OwnedEvent::RDB(OwnedObject::String { key, .. }))) => keys.push(key),
+                Some(Ok(OwnedEvent::AOF(cmd))) => commands.push(cmd.name()),
+                Some(Ok(_)) => {}
+                Some(Err(error)) => break error,
+                None => panic!("stream ended without error"),
+            }
+        };
+        assert_eq!(ErrorKind::UnexpectedEof, error.kind());
+        assert!(next().is_none());
+        master.join().unwrap();
+        assert_eq!(vec![b"expires_ms_precision".to_vec()], keys);
+        // 第一个SELECT来自RDB中的切换数据库
+        assert_eq!(vec!["SELECT", "SELECT", "SET"], commands);
+        assert_eq!(2, health_check.stats().commands_parsed);
+    }
+
+    #[test]
+    fn test_aof_manifest() {
+        let mut handler = CommandRecorder {
+            keys: Vec::new(),
+            commands: Vec::new(),
+        };
+        let info = aof::parse_manifest("tests/aof/multi-part/appendonly.aof.manifest", &mut handler).unwrap();
+        assert_eq!(Some(&"1".to_string()), info.unwrap().aux_fields.get("aof-base"));
+        // history文件不会被加载，最后一个incr文件末尾不完整的命令被忽略
+        assert_eq!(vec!["a"], handler.keys);
+        assert_eq!(vec!["SELECT 0", "SELECT 0", "SET b", "DEL a"], handler.commands);
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let content = "# comment\n\
+                       file appendonly.aof.1.base.rdb seq 1 type h\n\
+                       file \"append only.aof.2.base.rdb\" seq 2 type b\n\
+                       file appendonly.aof.3.incr.aof seq 3 type i\n\
+                       file appendonly.aof.4.incr.aof type i seq 4\n";
+        let manifest = Manifest::parse(content).unwrap();
+        let base = manifest.base.unwrap();
+        assert_eq!("append only.aof.2.base.rdb", base.name);
+        assert_eq!(2, base.seq);
+        assert_eq!(1, manifest.history.len());
+        assert_eq!(AofFileType::History, manifest.history[0].file_type);
+        let incrs: Vec<u64> = manifest.incrs.iter().map(|file| file.seq).collect();
+        assert_eq!(vec![3, 4], incrs);
+
+        assert!(Manifest::parse("file appendonly.aof.1.base.rdb seq 1\n").is_err());
+        assert!(Manifest::parse("file appendonly.aof.1.base.rdb seq 1 type x\n").is_err());
+        assert!(Manifest::parse("file \"appendonly.aof seq 1 type b\n").is_err());
+        let two_bases = "file a seq 1 type b\nfile b seq 2 type b\n";
+        assert!(Manifest::parse(two_bases).is_err());
+    }
+
+    #[test]
+    fn test_follow_aof() {
+        struct StopOnDel {
+            recorder: CommandRecorder,
+            running: Arc<AtomicBool>,
+        }
+
+        impl EventHandler for StopOnDel {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(Command::DEL(_)) = event {
+                    self.running.store(false, Ordering::SeqCst);
+                }
+                self.recorder.handle(event);
+            }
+        }
+
+        let path = env::temp_dir().join(format!("redis-event-follow-{}.aof", process::id()));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n").unwrap();
+        let writer = thread::spawn(move || {
+            // 一条命令分两次写入，模拟Redis正在追加命令时被读取
+            thread::sleep(Duration::from_millis(50));
+            file.write_all(b"*3\r\n$3\r\nSET\r\n$1\r\nb").unwrap();
+            thread::sleep(Duration::from_millis(50));
+            file.write_all(b"\r\n$1\r\n2\r\n*2\r\n$3\r\nDEL\r\n$1\r\nb\r\n")
+                .unwrap();
+        });
+
+        let running = Arc::new(AtomicBool::new(true));
+        let mut handler = StopOnDel {
+            recorder: CommandRecorder {
+                keys: Vec::new(),
+                commands: Vec::new(),
+            },
+            running: Arc::clone(&running),
+        };
+        let info = aof::follow_file(&path, &mut handler, running, Duration::from_millis(10)).unwrap();
+        writer.join().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(info.is_none());
+        assert_eq!(vec!["SELECT 0", "SET b", "DEL b"], handler.recorder.commands);
+    }
+
+    struct PositionRecorder {
+        positions: Vec<AofPosition>,
+    }
+
+    impl EventHandler for PositionRecorder {
+        fn handle(&mut self, _: Event) {
+            panic!("AOF事件应该交给handle_aof");
+        }
+
+        fn handle_aof(&mut self, _: Event, position: AofPosition) {
+            self.positions.push(position);
+        }
+    }
+
+    #[test]
+    fn test_aof_timestamp() {
+        let mut handler = PositionRecorder { positions: Vec::new() };
+        aof::parse_file("tests/aof/appendonly_timestamp.aof", &mut handler).unwrap();
+        let sequences: Vec<u64> = handler.positions.iter().map(|position| position.sequence).collect();
+        assert_eq!(vec![1, 2, 3], sequences);
+        let first = UNIX_EPOCH + Duration::from_secs(1700000000);
+        let second = UNIX_EPOCH + Duration::from_secs(1700000005);
+        let timestamps: Vec<Option<SystemTime>> = handler.positions.iter().map(|position| position.timestamp).collect();
+        assert_eq!(vec![Some(first), Some(first), Some(second)], timestamps);
+    }
+
+    #[test]
+    fn test_aof_offset() {
+        let mut handler = PositionRecorder { positions: Vec::new() };
+        aof::parse_file("tests/aof/appendonly_offset.aof", &mut handler).unwrap();
+        // 每条命令的offset为注释中的offset加上此后各条命令的字节数，注释本身不计入；PING不产生事件，但同样占用offset
+        let offsets: Vec<i64> = handler.positions.iter().map(|position| position.offset).collect();
+        assert_eq!(vec![141, 220], offsets);
+
+        let mut handler = PositionRecorder { positions: Vec::new() };
+        aof::parse_file("tests/aof/appendonly_timestamp.aof", &mut handler).unwrap();
+        assert!(handler.positions.iter().all(|position| position.offset == -1));
+    }
+
+    // 记录每条命令还原之后的参数
+    struct ArgsRecorder {
+        commands: Vec<Vec<Vec<u8>>>,
+    }
+
+    impl EventHandler for ArgsRecorder {
+        fn handle(&mut self, event: Event) {
+            if let Event::AOF(command) = event {
+                self.commands.push(command.to_args());
+            }
+        }
+    }
+
+    fn parse_args<P: AsRef<Path>>(path: P) -> Vec<Vec<Vec<u8>>> {
+        let mut recorder = ArgsRecorder { commands: Vec::new() };
+        aof::parse_file(path, &mut recorder).unwrap();
+        recorder.commands
+    }
+
+    // 将以空格分隔的命令交给handler，并检查各个选项都被保留下来，可以原样还原
+    fn parse_commands(commands: &[&str], handler: &mut dyn EventHandler) {
+        let mut recorder = ArgsRecorder { commands: Vec::new() };
+        for command in commands {
+            let args: Vec<Vec<u8>> = command.split(' ').map(|arg| arg.as_bytes().to_vec()).collect();
+            cmd::parse(&args, &CommandParsers::new(), handler);
+            cmd::parse(&args, &CommandParsers::new(), &mut recorder);
+            assert_eq!(&args, recorder.commands.last().unwrap(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_command_to_args() {
+        for path in [
+            "tests/aof/appendonly.aof",
+            "tests/aof/appendonly3.aof",
+            "tests/aof/appendonly5.aof",
+            "tests/aof/appendonly_redis6.aof",
+        ]
+        .iter()
+        {
+            let mut file = File::open(path).unwrap();
+            let mut expected = Vec::new();
+            while let Ok(Resp::Array(arr)) = file.decode_resp() {
+                let mut args: Vec<Vec<u8>> = arr
+                    .into_iter()
+                    .map(|arg| match arg {
+                        Resp::BulkBytes(bytes) => bytes,
+                        _ => panic!("wrong data type"),
+                    })
+                    .collect();
+                if !args[0].eq_ignore_ascii_case(b"PING") {
+                    expected.push(args);
+                }
+            }
+            // 选项等关键字统一为大写，如`BITOP and`还原为`BITOP AND`
+            let actual = parse_args(path);
+            assert_eq!(expected.len(), actual.len(), "{}", path);
+            for (expected, actual) in expected.iter().zip(actual.iter()) {
+                let is_same = expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual.iter())
+                        .all(|(e, a)| e.eq_ignore_ascii_case(a));
+                assert!(is_same, "{:?} != {:?}", expected, actual);
+            }
+
+            // handle_aof_raw中的原始命令与AOF中的完全一致
+            let mut recorder = RawRecorder { commands: Vec::new() };
+            aof::parse_file(path, &mut recorder).unwrap();
+            assert_eq!(expected, recorder.commands, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_command_to_resp() {
+        struct RespRecorder {
+            output: Vec<u8>,
+        }
+
+        impl EventHandler for RespRecorder {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(cmd) = event {
+                    self.output.extend(cmd.to_resp());
+                }
+            }
+        }
+
+        // 编码的结果可以被重新解析为相同的命令
+        let data = fs::read("tests/aof/appendonly.aof").unwrap();
+        let mut recorder = RespRecorder { output: Vec::new() };
+        aof::parse_reader(&data[..], &mut recorder).unwrap();
+        let mut expected = RespRecorder { output: Vec::new() };
+        aof::parse_reader(&recorder.output[..], &mut expected).unwrap();
+        assert!(!recorder.output.is_empty());
+        assert_eq!(expected.output, recorder.output);
+
+        let args: Vec<Vec<u8>> = ["SET", "key", "value", "EX", "10"]
+            .iter()
+            .map(|arg| arg.as_bytes().to_vec())
+            .collect();
+        let mut recorder = RespRecorder { output: Vec::new() };
+        cmd::parse(&args, &CommandParsers::new(), &mut recorder);
+        assert_eq!(
+            b"*5\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n$2\r\nEX\r\n$2\r\n10\r\n".to_vec(),
+            recorder.output
+        );
+    }
+
+    #[test]
+    fn test_owned_commands() {
+        struct OwnedHandler {
+            commands: Vec<OwnedCommand>,
+        }
+
+        impl EventHandler for OwnedHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(cmd) = event {
+                    self.commands.push(cmd.to_owned());
+                }
+            }
+        }
+
+        let mut handler = OwnedHandler { commands: Vec::new() };
+        aof::parse_file("tests/aof/appendonly.aof", &mut handler).unwrap();
+        let commands = handler.commands;
+        assert!(!commands.is_empty());
+
+        // 拥有所有权之后可以交给其他线程处理，并重新解析为相同的命令
+        let replayed = thread::spawn(move || {
+            let mut handler = OwnedHandler { commands: Vec::new() };
+            for cmd in &commands {
+                cmd.replay(&CommandParsers::new(), &mut handler);
+            }
+            assert_eq!(commands, handler.commands);
+            handler.commands
+        })
+        .join()
+        .unwrap();
+        assert!(replayed.iter().any(|cmd| cmd.name() == "XADD"));
+
+        let cmd = OwnedCommand::new(vec![b"set".to_vec(), b"k".to_vec(), b"v".to_vec()]);
+        assert_eq!("SET", cmd.name());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_commands() {
+        struct JsonHandler {
+            commands: Vec<(String, OwnedCommand)>,
+        }
+
+        impl EventHandler for JsonHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(cmd) = event {
+                    self.commands
+                        .push((serde_json::to_string(&cmd).unwrap(), cmd.to_owned()));
+                }
+            }
+        }
+
+        let mut handler = JsonHandler { commands: Vec::new() };
+        aof::parse_file("tests/aof/appendonly.aof", &mut handler).unwrap();
+        assert!(!handler.commands.is_empty());
+        for (json, cmd) in &handler.commands {
+            let decoded: OwnedCommand = serde_json::from_str(json).unwrap();
+            assert_eq!(*cmd, decoded);
+        }
+
+        // 非UTF-8的参数
+        let cmd = OwnedCommand::new(vec![b"SET".to_vec(), b"\xffkey".to_vec(), b"v".to_vec()]);
+        let json = serde_json::to_string(&cmd).unwrap();
+        if cfg!(feature = "serde-lossy-string") {
+            assert_eq!("{\"args\":[\"SET\",\"\u{fffd}key\",\"v\"]}", json);
+        } else {
+            assert_eq!("{\"args\":[\"U0VU\",\"/2tleQ==\",\"dg==\"]}", json);
+            assert_eq!(cmd, serde_json::from_str(&json).unwrap());
+        }
+    }
+
+    // 记录handle_aof_raw收到的原始命令
+    struct RawRecorder {
+        commands: Vec<Vec<Vec<u8>>>,
+    }
+
+    impl EventHandler for RawRecorder {
+        fn handle(&mut self, _: Event) {
+            panic!("AOF事件应交给handle_aof_raw处理");
+        }
+
+        fn handle_aof_raw(&mut self, _: Event, args: &[Vec<u8>], _: AofPosition) {
+            self.commands.push(args.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_stream_commands() {
+        struct StreamHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for StreamHandler {
+            fn handle(&mut self, event: Event) {
+                let command = match event {
+                    Event::AOF(command) => command,
+                    Event::RDB(_) => return,
+                };
+                match &command {
+                    Command::XADD(xadd) => {
+                        assert_eq!(b"s", xadd.key);
+                        assert_eq!(Some(true), xadd.no_mkstream);
+                        let trim = xadd.trim.as_ref().unwrap();
+                        assert_eq!(TrimStrategy::MINID, trim.strategy);
+                        assert!(trim.approximation);
+                        assert_eq!(b"1700000000000-0", trim.threshold);
+                        assert_eq!(b"100", trim.limit.unwrap().as_slice());
+                        assert_eq!(b"1700000000001-0", xadd.id);
+                        assert_eq!(2, xadd.fields.len());
+                        assert_eq!(b"v2", xadd.fields[1].value);
+                    }
+                    Command::XTRIM(xtrim) => {
+                        assert_eq!(TrimStrategy::MAXLEN, xtrim.trim.strategy);
+                        assert!(!xtrim.trim.approximation);
+                        assert_eq!(b"10", xtrim.trim.threshold);
+                        assert!(xtrim.trim.limit.is_none());
+                    }
+                    Command::XCLAIM(xclaim) => {
+                        assert_eq!(2, xclaim.ids.len());
+                        assert_eq!(b"1700000000500", xclaim.time.unwrap().as_slice());
+                        assert_eq!(b"2", xclaim.retry_count.unwrap().as_slice());
+                        assert_eq!(Some(true), xclaim.force);
+                        assert_eq!(Some(true), xclaim.just_id);
+                        assert_eq!(b"1700000000001-0", xclaim.last_id.unwrap().as_slice());
+                    }
+                    Command::XAUTOCLAIM(xautoclaim) => {
+                        assert_eq!(b"0-0", xautoclaim.start);
+                        assert_eq!(b"25", xautoclaim.count.unwrap().as_slice());
+                        assert_eq!(Some(true), xautoclaim.just_id);
+                    }
+                    Command::XSETID(xsetid) => {
+                        assert_eq!(b"1700000000001-0", xsetid.last_id);
+                        assert_eq!(b"3", xsetid.entries_added.unwrap().as_slice());
+                        assert_eq!(b"1700000000000-5", xsetid.max_deleted_id.unwrap().as_slice());
+                    }
+                    Command::XGROUP(xgroup) => {
+                        if let Some(create) = &xgroup.create {
+                            assert_eq!(Some(true), create.mkstream);
+                            assert_eq!(b"2", create.entries_read.unwrap().as_slice());
+                        } else if let Some(set_id) = &xgroup.set_id {
+                            assert_eq!(b"$", set_id.id);
+                            assert_eq!(b"5", set_id.entries_read.unwrap().as_slice());
+                        } else {
+                            let create_consumer = xgroup.create_consumer.as_ref().unwrap();
+                            assert_eq!(b"c1", create_consumer.consumer_name);
+                        }
+                    }
+                    Command::XACK(xack) => assert_eq!(2, xack.ids.len()),
+                    Command::XDEL(xdel) => assert_eq!(1, xdel.ids.len()),
+                    _ => panic!("unexpected command"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = [
+            "XADD s NOMKSTREAM MINID ~ 1700000000000-0 LIMIT 100 1700000000001-0 f1 v1 f2 v2",
+            "XTRIM s MAXLEN 10",
+            "XCLAIM s g1 c1 0 1700000000000-1 1700000000000-2 TIME 1700000000500 RETRYCOUNT 2 FORCE JUSTID LASTID 1700000000001-0",
+            "XAUTOCLAIM s g1 c1 3600000 0-0 COUNT 25 JUSTID",
+            "XSETID s 1700000000001-0 ENTRIESADDED 3 MAXDELETEDID 1700000000000-5",
+            "XGROUP CREATE s g1 0 MKSTREAM ENTRIESREAD 2",
+            "XGROUP SETID s g1 $ ENTRIESREAD 5",
+            "XGROUP CREATECONSUMER s g1 c1",
+            "XACK s g1 1700000000000-1 1700000000000-2",
+            "XDEL s 1700000000000-1",
+        ];
+        let mut handler = StreamHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_set_options() {
+        struct TestCmdHandler {
+            sets: Vec<(Option<String>, bool, bool, bool)>,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(Command::SET(set)) = event {
+                    assert_eq!(b"k1", set.key);
+                    assert_eq!(b"v1", set.value);
+                    let expire = set.expire.as_ref().map(|(expire_type, expire)| {
+                        let expire = String::from_utf8_lossy(expire);
+                        match expire_type {
+                            ExpireType::EX => format!("EX {}", expire),
+                            ExpireType::PX => format!("PX {}", expire),
+                            ExpireType::EXAT => format!("EXAT {}", expire),
+                            ExpireType::PXAT => format!("PXAT {}", expire),
+                        }
+                    });
+                    let is_nx = matches!(set.exist_type, Some(ExistType::NX));
+                    self.sets
+                        .push((expire, is_nx, set.keep_ttl.is_some(), set.get.is_some()));
+                } else {
+                    panic!("unexpected event");
                 }
             }
         }
 
-        let mut handler = TestRdbHandler {};
+        let commands = [
+            "SET k1 v1 EX 10",
+            "SET k1 v1 PXAT 1700000000000 NX GET",
+            "SET k1 v1 EXAT 1700000000",
+            "SET k1 v1 XX KEEPTTL GET",
+        ];
+        let mut handler = TestCmdHandler { sets: Vec::new() };
+        parse_commands(&commands, &mut handler);
+        let expected = vec![
+            (Some("EX 10".to_string()), false, false, false),
+            (Some("PXAT 1700000000000".to_string()), true, false, true),
+            (Some("EXAT 1700000000".to_string()), false, false, false),
+            (None, false, true, true),
+        ];
+        assert_eq!(expected, handler.sets);
+    }
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
-        };
-        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+    #[test]
+    fn test_zadd_options() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::ZADD(zadd)) if zadd.incr.is_some() => {
+                        assert!(matches!(zadd.exist_type, Some(ExistType::XX)));
+                        assert!(matches!(zadd.compare_type, Some(CompareType::LT)));
+                        assert!(zadd.ch.is_none());
+                        assert_eq!(b"-1.5", zadd.items[0].score);
+                    }
+                    Event::AOF(Command::ZADD(zadd)) => {
+                        assert!(zadd.exist_type.is_none());
+                        assert!(matches!(zadd.compare_type, Some(CompareType::GT)));
+                        assert_eq!(Some(true), zadd.ch);
+                        assert_eq!(2, zadd.items.len());
+                        assert_eq!(b"m2", zadd.items[1].member);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = ["ZADD z GT CH 1 m1 2 m2", "ZADD z XX LT INCR -1.5 m1"];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
     }
 
     #[test]
-    fn test_stream1() {
-        let mut file = File::open("tests/rdb/dump-stream1.rdb").expect("file not found");
+    fn test_geo_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
 
-        struct TestRdbHandler {}
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::GEOADD(geoadd)) => {
+                        assert_eq!(b"g", geoadd.key);
+                        assert!(matches!(geoadd.exist_type, Some(ExistType::NX)));
+                        assert_eq!(Some(true), geoadd.ch);
+                        assert_eq!(2, geoadd.items.len());
+                        assert_eq!(b"13.361389", geoadd.items[0].longitude);
+                        assert_eq!(b"38.115556", geoadd.items[0].latitude);
+                        assert_eq!(b"Catania", geoadd.items[1].member);
+                    }
+                    Event::AOF(Command::GEOSEARCHSTORE(geosearchstore)) => {
+                        assert_eq!(b"dst", geosearchstore.destination);
+                        assert_eq!(b"g", geosearchstore.source);
+                        assert!(matches!(geosearchstore.from, GeoFrom::FROMLONLAT(b"15", b"37")));
+                        assert!(matches!(geosearchstore.shape, GeoShape::BYBOX(b"400", b"400", b"km")));
+                        assert!(matches!(geosearchstore.order, Some(ORDER::ASC)));
+                        assert_eq!(Some(&b"3"[..]), geosearchstore.count);
+                        assert_eq!(Some(true), geosearchstore.store_dist);
+                    }
+                    Event::AOF(Command::GEORADIUS(georadius)) => {
+                        assert_eq!(b"200", georadius.radius);
+                        assert_eq!(Some(&b"dst"[..]), georadius.options.store);
+                        assert!(georadius.options.store_dist.is_none());
+                    }
+                    Event::AOF(Command::GEORADIUSBYMEMBER(georadiusbymember)) => {
+                        assert_eq!(b"Palermo", georadiusbymember.member);
+                        assert!(matches!(georadiusbymember.options.order, Some(ORDER::DESC)));
+                        assert_eq!(Some(&b"dst"[..]), georadiusbymember.options.store_dist);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
 
-        impl EventHandler for TestRdbHandler {
-            fn handle(&mut self, _: Event) {}
+        let commands = [
+            "GEOADD g NX CH 13.361389 38.115556 Palermo 15.087269 37.502669 Catania",
+            "GEOSEARCHSTORE dst g FROMLONLAT 15 37 BYBOX 400 400 km ASC COUNT 3 STOREDIST",
+            "GEORADIUS g 15 37 200 km COUNT 10 ANY STORE dst",
+            "GEORADIUSBYMEMBER g Palermo 200 km DESC STOREDIST dst",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_hyperloglog_commands() {
+        struct TestCmdHandler {
+            checked: usize,
         }
 
-        let mut handler = TestRdbHandler {};
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::PFADD(pfadd)) => {
+                        assert_eq!(b"h1", pfadd.key);
+                        assert_eq!(vec![&b"a"[..], b"b", b"c"], pfadd.elements);
+                    }
+                    Event::AOF(Command::PFCOUNT(pfcount)) => assert_eq!(vec![&b"h1"[..], b"h2"], pfcount.keys),
+                    Event::AOF(Command::PFMERGE(pfmerge)) => {
+                        assert_eq!(b"h3", pfmerge.dest_key);
+                        assert_eq!(vec![&b"h1"[..], b"h2"], pfmerge.source_keys);
+                    }
+                    Event::AOF(Command::PFDEBUG(pfdebug)) => {
+                        assert_eq!(b"TODENSE", pfdebug.subcommand);
+                        assert_eq!(b"h1", pfdebug.key);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = [
+            "PFADD h1 a b c",
+            "PFCOUNT h1 h2",
+            "PFMERGE h3 h1 h2",
+            "PFDEBUG TODENSE h1",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_bitmap_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::BITFIELD(bitfield)) => {
+                        let statements = bitfield.statements.as_ref().unwrap();
+                        assert_eq!(4, statements.len());
+                        assert!(
+                            matches!(&statements[0], Operation::SET(set) if set._type == b"i8" && set.value == b"100")
+                        );
+                        assert!(matches!(statements[1], Operation::OVERFLOW(Overflow::SAT)));
+                        assert!(matches!(&statements[2], Operation::INCRBY(incr) if incr.offset == b"#1"));
+                        assert!(matches!(&statements[3], Operation::GET(get) if get._type == b"u4"));
+                        assert_eq!(1, bitfield.overflows.as_ref().unwrap().len());
+                    }
+                    Event::AOF(Command::BITFIELD_RO(bitfield_ro)) => {
+                        assert_eq!(2, bitfield_ro.statements.len());
+                        assert_eq!(b"100", bitfield_ro.statements[1].offset);
+                    }
+                    Event::AOF(Command::BITOP(bitop)) => {
+                        assert!(matches!(bitop.operation, Op::DIFF1));
+                        assert_eq!(b"dst", bitop.dest_key);
+                        assert_eq!(2, bitop.keys.len());
+                    }
+                    Event::AOF(Command::SETBIT(setbit)) => {
+                        assert_eq!(b"7", setbit.offset);
+                        assert_eq!(b"1", setbit.value);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = [
+            "BITFIELD b SET i8 0 100 OVERFLOW SAT INCRBY i8 #1 200 GET u4 0",
+            "BITFIELD_RO b GET u8 0 GET i4 100",
+            "BITOP DIFF1 dst b1 b2",
+            "SETBIT b 7 1",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_other_commands() {
+        struct TestCmdHandler {
+            commands: Vec<RawCommand>,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::Other(raw_cmd)) => self.commands.push(raw_cmd),
+                    _ => panic!("unexpected event"),
+                }
+            }
+        }
+
+        // 未知的命令以及未识别的子命令都不会被丢弃
+        let commands = ["JSON.SET doc $ {}", "SCRIPT KILL", "CLUSTER RESET HARD"];
+        let mut handler = TestCmdHandler { commands: Vec::new() };
+        parse_commands(&commands, &mut handler);
+        let actual: Vec<(&str, usize)> = handler
+            .commands
+            .iter()
+            .map(|raw_cmd| (raw_cmd.name.as_str(), raw_cmd.args.len()))
+            .collect();
+        assert_eq!(vec![("JSON.SET", 3), ("SCRIPT", 1), ("CLUSTER", 2)], actual);
+    }
+
+    #[test]
+    fn test_command_parsers() {
+        struct JsonSet {
+            key: Vec<u8>,
+            path: Vec<u8>,
+            value: Vec<u8>,
+        }
+
+        impl Module for JsonSet {
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+        }
+
+        struct JsonSetParser {}
+
+        impl CommandParser for JsonSetParser {
+            fn parse(&mut self, name: &str, args: &[Vec<u8>]) -> Box<dyn Module> {
+                assert_eq!("JSON.SET", name);
+                Box::new(JsonSet {
+                    key: args[0].clone(),
+                    path: args[1].clone(),
+                    value: args[2].clone(),
+                })
+            }
+        }
+
+        struct TestCmdHandler {
+            modules: usize,
+            others: Vec<String>,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::Module(cmd)) => {
+                        assert_eq!("JSON.SET", cmd.name);
+                        let json_set = cmd.value.as_any().downcast_ref::<JsonSet>().unwrap();
+                        assert_eq!(b"doc", json_set.key.as_slice());
+                        assert_eq!(b"$", json_set.path.as_slice());
+                        assert_eq!(b"{}", json_set.value.as_slice());
+                        self.modules += 1;
+                    }
+                    Event::AOF(Command::Other(raw_cmd)) => self.others.push(raw_cmd.name),
+                    Event::AOF(Command::SET(_)) => {}
+                    _ => panic!("unexpected event"),
+                }
+            }
+        }
 
-        let mut rdb_parser = DefaultRDBParser {
-            running: Arc::new(AtomicBool::new(true)),
-            module_parser: None,
+        let mut parsers = CommandParsers::new();
+        parsers.register("json.set", Arc::new(Mutex::new(JsonSetParser {})));
+        // 内置的命令不会交给自定义的解析器
+        parsers.register("SET", Arc::new(Mutex::new(JsonSetParser {})));
+
+        let mut handler = TestCmdHandler {
+            modules: 0,
+            others: Vec::new(),
         };
-        rdb_parser.parse(&mut file, 0, &mut handler).unwrap();
+        for command in ["JSON.SET doc $ {}", "json.set doc $ {}", "JSON.DEL doc $", "SET doc {}"].iter() {
+            let args: Vec<Vec<u8>> = command.split(' ').map(|arg| arg.as_bytes().to_vec()).collect();
+            cmd::parse(&args, &parsers, &mut handler);
+        }
+        assert_eq!(2, handler.modules);
+        assert_eq!(vec!["JSON.DEL"], handler.others);
+
+        let aof = b"*4\r\n$8\r\nJSON.SET\r\n$3\r\ndoc\r\n$1\r\n$\r\n$2\r\n{}\r\n";
+        handler.modules = 0;
+        aof::parse_reader_with_parsers(&aof[..], &parsers, &mut handler).unwrap();
+        assert_eq!(1, handler.modules);
     }
-}
 
-#[cfg(test)]
-mod aof_tests {
-    use std::fs::File;
+    #[test]
+    fn test_transaction_grouper() {
+        struct TestTxHandler {
+            commands: Vec<String>,
+            transactions: Vec<Vec<String>>,
+        }
 
-    use crate::cmd::Command;
-    use crate::resp::{Resp, RespDecode};
-    use crate::{cmd, Event, EventHandler};
-    use std::io::ErrorKind;
+        impl EventHandler for TestTxHandler {
+            fn handle(&mut self, event: Event) {
+                if let Event::AOF(cmd) = event {
+                    let name = String::from_utf8(cmd.to_args().remove(0)).unwrap();
+                    self.commands.push(name);
+                }
+            }
+
+            fn handle_transaction(&mut self, transaction: &Transaction) {
+                let names = transaction
+                    .commands
+                    .iter()
+                    .map(|(args, _)| String::from_utf8_lossy(&args[0]).into_owned())
+                    .collect();
+                self.transactions.push(names);
+            }
+        }
+
+        struct ReplayHandler {
+            commands: Vec<(String, u64)>,
+        }
+
+        impl EventHandler for ReplayHandler {
+            fn handle(&mut self, _: Event) {
+                panic!("unexpected event");
+            }
+
+            fn handle_aof_raw(&mut self, _: Event, args: &[Vec<u8>], position: AofPosition) {
+                let name = String::from_utf8_lossy(&args[0]).into_owned();
+                self.commands.push((name, position.sequence));
+            }
+        }
+
+        let commands = [
+            "SET a 1", "MULTI", "SET b 2", "INCR c", "EXEC", "SET d 4",
+            // 末尾没有EXEC的事务将被丢弃
+            "MULTI", "SET e 5",
+        ];
+        let mut aof = Vec::new();
+        for command in commands.iter() {
+            let args: Vec<&str> = command.split(' ').collect();
+            aof.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+            for arg in args {
+                aof.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+            }
+        }
+
+        let mut grouper = TransactionGrouper::new(TestTxHandler {
+            commands: Vec::new(),
+            transactions: Vec::new(),
+        });
+        aof::parse_reader(&aof[..], &mut grouper).unwrap();
+        let handler = grouper.into_inner();
+        assert_eq!(vec!["SET", "SET"], handler.commands);
+        assert_eq!(vec![vec!["MULTI", "SET", "INCR", "EXEC"]], handler.transactions);
+
+        // 默认将事务中的命令依次交给handle_aof_raw
+        let mut grouper = TransactionGrouper::new(ReplayHandler { commands: Vec::new() });
+        aof::parse_reader(&aof[..], &mut grouper).unwrap();
+        let expected: Vec<(String, u64)> = ["SET", "MULTI", "SET", "INCR", "EXEC", "SET"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), i as u64 + 1))
+            .collect();
+        assert_eq!(expected, grouper.get_ref().commands);
+    }
 
     #[test]
-    fn test_aof1() {
-        let mut file = File::open("tests/aof/appendonly1.aof").expect("file not found");
+    fn test_normalizer() {
+        struct RawRecorder {
+            commands: Vec<String>,
+        }
 
-        struct TestCmdHandler {}
+        impl EventHandler for RawRecorder {
+            fn handle(&mut self, _: Event) {
+                panic!("unexpected event");
+            }
+
+            fn handle_aof_raw(&mut self, _: Event, args: &[Vec<u8>], _: AofPosition) {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect();
+                self.commands.push(args.join(" "));
+            }
+        }
+
+        let commands = [
+            "EXPIRE a 10 NX",
+            "PEXPIRE a 1500",
+            "EXPIREAT a 1700000100",
+            "SETEX b 10 v",
+            "PSETEX b 100 v",
+            "SET c EX EX 10 GET",
+            "SET c v PXAT 1700000000001",
+            "GETEX d PX 20",
+            "GETEX d PERSIST",
+            "GETEX d",
+            "GETDEL d",
+            "HEXPIRE e 10 FIELDS 1 f",
+            "HGETEX e EXAT 1700000100 FIELDS 2 f g",
+            "SADD s m",
+        ];
+        let mut aof = b"#TS:1700000000\r\n".to_vec();
+        for command in commands.iter() {
+            let args: Vec<&str> = command.split(' ').collect();
+            aof.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+            for arg in args {
+                aof.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+            }
+        }
+
+        let mut normalizer = Normalizer::new(RawRecorder { commands: Vec::new() });
+        aof::parse_reader(&aof[..], &mut normalizer).unwrap();
+        let expected = vec![
+            "PEXPIREAT a 1700000010000 NX",
+            "PEXPIREAT a 1700000001500",
+            "PEXPIREAT a 1700000100000",
+            "SET b v",
+            "PEXPIREAT b 1700000010000",
+            "SET b v",
+            "PEXPIREAT b 1700000000100",
+            "SET c EX PXAT 1700000010000 GET",
+            "SET c v PXAT 1700000000001",
+            "PEXPIREAT d 1700000000020",
+            "PERSIST d",
+            "DEL d",
+            "HPEXPIREAT e 1700000010000 FIELDS 1 f",
+            "HPEXPIREAT e 1700000100000 FIELDS 2 f g",
+            "SADD s m",
+        ];
+        assert_eq!(expected, normalizer.into_inner().commands);
+    }
+
+    #[test]
+    fn test_command_classification() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
 
         impl EventHandler for TestCmdHandler {
-            fn handle(&mut self, cmd: Event) {
+            fn handle(&mut self, event: Event) {
+                let cmd = match event {
+                    Event::AOF(cmd) => cmd,
+                    Event::RDB(_) => panic!("unexpected event"),
+                };
+                let keys: Vec<&[u8]> = cmd.keys();
+                let classes = (cmd.data_type(), cmd.is_write(), cmd.is_delete(), cmd.is_expire());
                 match cmd {
-                    Event::RDB(_) => {}
-                    Event::AOF(cmd) => match cmd {
-                        Command::HMSET(hmset) => {
-                            let key = String::from_utf8_lossy(hmset.key);
-                            if "key".eq(&key) {
-                                for field in &hmset.fields {
-                                    let name = String::from_utf8_lossy(field.name);
-                                    if "field".eq(&name) {
-                                        let val = String::from_utf8_lossy(field.value);
-                                        assert_eq!("a", val);
-                                    } else if "field1".eq(&name) {
-                                        let val = String::from_utf8_lossy(field.value);
-                                        assert_eq!("b", val);
-                                    } else {
-                                        panic!("wrong name");
-                                    }
-                                }
-                            } else {
-                                panic!("wrong key");
-                            }
-                        }
-                        Command::SELECT(select) => {
-                            assert_eq!(0, select.db);
-                        }
-                        Command::SET(set) => {
-                            let key = String::from_utf8_lossy(set.key);
-                            let val = String::from_utf8_lossy(set.value);
-                            assert_eq!("a", key);
-                            assert_eq!("b", val);
-                        }
-                        _ => {}
-                    },
+                    Command::SET(_) => {
+                        assert_eq!(vec![b"k1".as_ref()], keys);
+                        assert_eq!((Some(DataType::String), true, false, true), classes);
+                    }
+                    Command::SUNIONSTORE(_) => {
+                        assert_eq!(vec![b"dst".as_ref(), b"s1", b"s2"], keys);
+                        assert_eq!((Some(DataType::Set), true, false, false), classes);
+                    }
+                    Command::DEL(_) => {
+                        assert_eq!(vec![b"k1".as_ref(), b"k2"], keys);
+                        assert_eq!((Some(DataType::Generic), true, true, false), classes);
+                    }
+                    Command::HPEXPIRE(_) => {
+                        assert_eq!(vec![b"h".as_ref()], keys);
+                        assert_eq!((Some(DataType::Hash), true, false, true), classes);
+                    }
+                    Command::GEORADIUS(_) => {
+                        assert_eq!(vec![b"geo".as_ref()], keys);
+                        assert_eq!((Some(DataType::SortedSet), false, false, false), classes);
+                    }
+                    Command::XGROUP(_) => {
+                        assert_eq!(vec![b"stream".as_ref()], keys);
+                        assert_eq!((Some(DataType::Stream), true, false, false), classes);
+                    }
+                    Command::EVAL(_) => {
+                        assert_eq!(vec![b"k1".as_ref(), b"k2"], keys);
+                        assert_eq!((None, true, false, false), classes);
+                    }
+                    Command::FLUSHDB(_) => {
+                        assert!(keys.is_empty());
+                        assert_eq!((None, true, true, false), classes);
+                    }
+                    Command::PUBLISH(_) | Command::Other(_) => {
+                        assert!(keys.is_empty());
+                        assert_eq!((None, false, false, false), classes);
+                    }
+                    _ => panic!("unexpected command"),
                 }
+                self.checked += 1;
             }
         }
 
-        let mut cmd_handler = TestCmdHandler {};
+        let commands = [
+            "SET k1 v EX 10",
+            "SUNIONSTORE dst s1 s2",
+            "DEL k1 k2",
+            "HPEXPIRE h 100 FIELDS 1 f",
+            "GEORADIUS geo 15 37 200 km WITHDIST",
+            "XGROUP CREATE stream group $",
+            "EVAL return 2 k1 k2 arg",
+            "FLUSHDB",
+            "PUBLISH channel message",
+            "JSON.SET doc $ {}",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
 
-        loop {
-            match file.decode_resp() {
-                Ok(resp) => match resp {
-                    Resp::Array(arr) => {
-                        let mut data = Vec::new();
-                        for x in arr {
-                            if let Resp::BulkBytes(bytes) = x {
-                                data.push(bytes);
-                            } else {
-                                panic!("wrong data type");
-                            }
+    #[test]
+    fn test_cluster_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::CLUSTERSETSLOT(setslot)) => {
+                        assert_eq!(b"100", setslot.slot);
+                        match setslot.state {
+                            SlotState::MIGRATING(node_id) => assert_eq!(b"node-b", node_id),
+                            SlotState::NODE(node_id) => assert_eq!(b"node-b", node_id),
+                            SlotState::STABLE => {}
+                            SlotState::IMPORTING(_) => panic!("unexpected slot state"),
                         }
-                        cmd::parse(data, &mut cmd_handler);
                     }
-                    _ => panic!("wrong resp type "),
-                },
-                Err(ref e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
-                    } else {
-                        panic!("err")
+                    Event::AOF(Command::CLUSTERBUMPEPOCH) | Event::AOF(Command::CLUSTERFLUSHSLOTS) => {}
+                    Event::AOF(Command::CLUSTERADDSLOTS(addslots)) => {
+                        assert_eq!(vec![&b"1"[..], b"2"], addslots.slots);
+                    }
+                    Event::AOF(Command::CLUSTERDELSLOTSRANGE(delslotsrange)) => {
+                        assert_eq!(vec![(&b"0"[..], &b"99"[..]), (b"200", b"299")], delslotsrange.ranges);
+                    }
+                    Event::AOF(Command::CLUSTERSETCONFIGEPOCH(set_config_epoch)) => {
+                        assert_eq!(b"7", set_config_epoch.config_epoch);
+                    }
+                    Event::AOF(Command::CLUSTERFORGET(forget)) => assert_eq!(b"node-c", forget.node_id),
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = [
+            "CLUSTER SETSLOT 100 MIGRATING node-b",
+            "CLUSTER SETSLOT 100 NODE node-b",
+            "CLUSTER SETSLOT 100 STABLE",
+            "CLUSTER BUMPEPOCH",
+            "CLUSTER ADDSLOTS 1 2",
+            "CLUSTER DELSLOTSRANGE 0 99 200 299",
+            "CLUSTER FLUSHSLOTS",
+            "CLUSTER SET-CONFIG-EPOCH 7",
+            "CLUSTER FORGET node-c",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_eval_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::EVAL(eval)) if eval.num_keys == 0 => {
+                        assert!(eval.keys.is_empty());
+                        assert_eq!(vec![&b"a1"[..], b"a2"], eval.args);
+                    }
+                    Event::AOF(Command::EVAL(eval)) => {
+                        assert_eq!(b"return", eval.script);
+                        assert_eq!(vec![&b"k1"[..], b"k2"], eval.keys);
+                        assert!(eval.args.is_empty());
+                    }
+                    Event::AOF(Command::EVALSHA(evalsha)) => {
+                        assert_eq!(b"e0e1f9fabfc9d4800c877a703b823ac0578ff8db", evalsha.sha1);
+                        assert_eq!(1, evalsha.num_keys);
+                        assert_eq!(vec![&b"k1"[..]], evalsha.keys);
+                        assert_eq!(vec![&b"a1"[..]], evalsha.args);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
+            }
+        }
+
+        let commands = [
+            "EVAL return 0 a1 a2",
+            "EVAL return 2 k1 k2",
+            "EVALSHA e0e1f9fabfc9d4800c877a703b823ac0578ff8db 1 k1 a1",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_function_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::FUNCTIONLOAD(function_load)) => {
+                        assert_eq!(Some(true), function_load.replace);
+                        assert!(function_load.code.starts_with(b"#!lua name=mylib\n"));
+                    }
+                    Event::AOF(Command::FUNCTIONDELETE(function_delete)) => {
+                        assert_eq!(b"mylib", function_delete.library_name);
+                    }
+                    Event::AOF(Command::FUNCTIONFLUSH(function_flush)) => {
+                        assert_eq!(Some(true), function_flush._async);
+                    }
+                    Event::AOF(Command::FUNCTIONRESTORE(function_restore)) => {
+                        assert_eq!(b"payload", function_restore.payload);
+                        assert!(matches!(function_restore.policy, Some(RestorePolicy::REPLACE)));
+                    }
+                    Event::AOF(Command::FCALL(fcall)) => {
+                        assert_eq!(b"myfunc", fcall.function);
+                        assert_eq!(2, fcall.num_keys);
+                        assert_eq!(vec![&b"k1"[..], b"k2"], fcall.keys);
+                        assert_eq!(vec![&b"a1"[..]], fcall.args);
+                    }
+                    Event::AOF(Command::Other(raw_cmd)) => {
+                        assert_eq!("FUNCTION", raw_cmd.name);
+                        assert_eq!(vec![b"KILL".to_vec()], raw_cmd.args);
                     }
+                    _ => panic!("unexpected event"),
                 }
+                self.checked += 1;
             }
         }
+
+        let mut handler = TestCmdHandler { checked: 0 };
+        let code = "#!lua name=mylib\nredis.register_function('myfunc', function(keys, args) return 1 end)";
+        let load = vec![
+            b"FUNCTION".to_vec(),
+            b"LOAD".to_vec(),
+            b"REPLACE".to_vec(),
+            code.as_bytes().to_vec(),
+        ];
+        cmd::parse(&load, &CommandParsers::new(), &mut handler);
+        let commands = [
+            "FUNCTION DELETE mylib",
+            "FUNCTION FLUSH ASYNC",
+            "FUNCTION RESTORE payload REPLACE",
+            "FCALL myfunc 2 k1 k2 a1",
+        ];
+        parse_commands(&commands, &mut handler);
+        cmd::parse(
+            &[b"FUNCTION".to_vec(), b"KILL".to_vec()],
+            &CommandParsers::new(),
+            &mut handler,
+        );
+        assert_eq!(commands.len() + 2, handler.checked);
     }
 
     #[test]
-    fn test_aof2() {
-        let mut file = File::open("tests/aof/appendonly2.aof").expect("file not found");
-
+    fn test_getex_getdel_copy() {
         struct TestCmdHandler {
-            count: isize,
+            checked: usize,
         }
 
         impl EventHandler for TestCmdHandler {
-            fn handle(&mut self, cmd: Event) {
-                match cmd {
-                    Event::RDB(_) => {}
-                    Event::AOF(cmd) => {
-                        if let Command::SET(set) = cmd {
-                            let key = String::from_utf8_lossy(set.key);
-                            if key.starts_with("test_") {
-                                self.count += 1;
-                            }
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::GETEX(getex)) => {
+                        assert_eq!(b"k1", getex.key);
+                        match &getex.expire {
+                            Some((ExpireType::PXAT, expire)) => assert_eq!(b"1700000000000", expire.as_slice()),
+                            Some(_) => panic!("wrong expire type"),
+                            None => assert_eq!(Some(true), getex.persist),
                         }
                     }
+                    Event::AOF(Command::GETDEL(getdel)) => assert_eq!(b"k1", getdel.key),
+                    Event::AOF(Command::COPY(copy)) => {
+                        assert_eq!(b"k1", copy.source);
+                        assert_eq!(b"k2", copy.destination);
+                        assert_eq!(Some(&b"3"[..]), copy.db);
+                        assert_eq!(Some(true), copy.replace);
+                    }
+                    _ => panic!("unexpected event"),
                 }
+                self.checked += 1;
             }
         }
 
-        let mut cmd_handler = TestCmdHandler { count: 0 };
+        let commands = [
+            "GETEX k1 PXAT 1700000000000",
+            "GETEX k1 PERSIST",
+            "GETDEL k1",
+            "COPY k1 k2 DB 3 REPLACE",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
 
-        loop {
-            match file.decode_resp() {
-                Ok(resp) => match resp {
-                    Resp::Array(arr) => {
-                        let mut data = Vec::new();
-                        for x in arr {
-                            if let Resp::BulkBytes(bytes) = x {
-                                data.push(bytes);
-                            } else {
-                                panic!("wrong data type");
-                            }
-                        }
-                        cmd::parse(data, &mut cmd_handler);
+    #[test]
+    fn test_multi_key_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::SINTERCARD(sintercard)) => {
+                        assert_eq!(2, sintercard.num_keys);
+                        assert_eq!(vec![&b"s1"[..], b"s2"], sintercard.keys);
+                        assert_eq!(Some(&b"10"[..]), sintercard.limit);
                     }
-                    _ => panic!("wrong resp type "),
-                },
-                Err(ref e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
-                    } else {
-                        panic!("err")
+                    Event::AOF(Command::LMPOP(lmpop)) => {
+                        assert_eq!(vec![&b"l1"[..], b"l2"], lmpop.keys);
+                        assert!(matches!(lmpop.direction, DIRECTION::LEFT));
+                        assert_eq!(Some(&b"3"[..]), lmpop.count);
+                    }
+                    Event::AOF(Command::BLMPOP(blmpop)) => {
+                        assert_eq!(b"0.5", blmpop.timeout);
+                        assert_eq!(vec![&b"l1"[..]], blmpop.keys);
+                        assert!(matches!(blmpop.direction, DIRECTION::RIGHT));
+                        assert!(blmpop.count.is_none());
+                    }
+                    Event::AOF(Command::ZMPOP(zmpop)) => {
+                        assert_eq!(1, zmpop.num_keys);
+                        assert!(matches!(zmpop.min_max, MINMAX::MAX));
+                        assert_eq!(Some(&b"2"[..]), zmpop.count);
+                    }
+                    Event::AOF(Command::ZRANGESTORE(zrangestore)) => {
+                        assert_eq!(b"dst", zrangestore.destination);
+                        assert_eq!(b"src", zrangestore.source);
+                        assert_eq!(b"(1", zrangestore.min);
+                        assert!(matches!(zrangestore.by, Some(RANGEBY::BYSCORE)));
+                        assert_eq!(Some(true), zrangestore.rev);
+                        assert_eq!(b"5", zrangestore.limit.as_ref().unwrap().count);
+                    }
+                    Event::AOF(Command::ZDIFFSTORE(zdiffstore)) => {
+                        assert_eq!(b"dst", zdiffstore.destination);
+                        assert_eq!(vec![&b"z1"[..], b"z2"], zdiffstore.keys);
                     }
+                    Event::AOF(Command::SMOVE(smove)) => {
+                        assert_eq!(b"s1", smove.source);
+                        assert_eq!(b"s2", smove.destination);
+                        assert_eq!(b"m", smove.member);
+                    }
+                    _ => panic!("unexpected event"),
                 }
+                self.checked += 1;
             }
         }
 
-        assert_eq!(48000, cmd_handler.count);
+        let commands = [
+            "SINTERCARD 2 s1 s2 LIMIT 10",
+            "LMPOP 2 l1 l2 LEFT COUNT 3",
+            "BLMPOP 0.5 1 l1 RIGHT",
+            "ZMPOP 1 z1 MAX COUNT 2",
+            "ZRANGESTORE dst src (1 +inf BYSCORE REV LIMIT 0 5",
+            "ZDIFFSTORE dst 2 z1 z2",
+            "SMOVE s1 s2 m",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
     }
 
     #[test]
-    fn test_aof3() {
-        let mut file = File::open("tests/aof/appendonly3.aof").expect("file not found");
-
+    fn test_redis6_commands() {
         struct TestCmdHandler {
-            count: isize,
+            checked: usize,
+            moves: Vec<String>,
         }
 
-        impl EventHandler for TestCmdHandler {
-            fn handle(&mut self, _cmd: Event) {
-                self.count += 1;
+        fn direction(direction: &DIRECTION) -> &'static str {
+            match direction {
+                DIRECTION::LEFT => "LEFT",
+                DIRECTION::RIGHT => "RIGHT",
             }
         }
 
-        let mut cmd_handler = TestCmdHandler { count: 0 };
-        loop {
-            match file.decode_resp() {
-                Ok(resp) => match resp {
-                    Resp::Array(arr) => {
-                        let mut data = Vec::new();
-                        for x in arr {
-                            if let Resp::BulkBytes(bytes) = x {
-                                data.push(bytes);
-                            } else {
-                                panic!("wrong data type");
-                            }
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::LMOVE(lmove)) => {
+                        assert_eq!(b"list", lmove.source);
+                        assert_eq!(b"list2", lmove.destination);
+                        let from = direction(&lmove.wherefrom);
+                        self.moves.push(format!("{} {}", from, direction(&lmove.whereto)));
+                    }
+                    Event::AOF(Command::BLMOVE(blmove)) => {
+                        assert_eq!(b"0.5", blmove.timeout);
+                        let from = direction(&blmove.wherefrom);
+                        self.moves.push(format!("{} {}", from, direction(&blmove.whereto)));
+                    }
+                    Event::AOF(Command::LPOP(lpop)) => {
+                        assert_eq!(b"list", lpop.key);
+                        assert_eq!(Some(&b"2"[..]), lpop.count);
+                    }
+                    Event::AOF(Command::RPOP(rpop)) => {
+                        if rpop.key == b"list2" {
+                            assert_eq!(Some(&b"1"[..]), rpop.count);
+                        } else {
+                            assert!(rpop.count.is_none());
                         }
-                        cmd::parse(data, &mut cmd_handler);
                     }
-                    _ => panic!("wrong resp type "),
-                },
-                Err(ref e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
-                    } else {
-                        panic!("err")
+                    Event::AOF(Command::SET(set)) => {
+                        assert_eq!(Some(true), set.keep_ttl);
+                        assert_eq!(Some(true), set.get);
+                    }
+                    Event::AOF(Command::SETRANGE(setrange)) => {
+                        assert_eq!(b"1", setrange.offset);
+                        assert_eq!(b"abc", setrange.value);
+                    }
+                    Event::AOF(Command::ZADD(zadd)) => {
+                        assert!(matches!(zadd.compare_type, Some(CompareType::GT)));
+                        assert_eq!(Some(true), zadd.ch);
+                    }
+                    Event::AOF(Command::COPY(copy)) => {
+                        assert_eq!(Some(&b"1"[..]), copy.db);
+                        assert_eq!(Some(true), copy.replace);
+                    }
+                    Event::AOF(Command::XADD(xadd)) => {
+                        assert!(matches!(xadd.trim.as_ref().unwrap().strategy, TrimStrategy::MINID));
+                    }
+                    Event::AOF(Command::XGROUP(xgroup)) => {
+                        if let Some(create_consumer) = &xgroup.create_consumer {
+                            assert_eq!(b"g", create_consumer.group_name);
+                            assert_eq!(b"c", create_consumer.consumer_name);
+                        } else {
+                            assert!(xgroup.create.is_some());
+                        }
                     }
+                    Event::AOF(Command::XTRIM(xtrim)) => {
+                        assert!(matches!(xtrim.trim.strategy, TrimStrategy::MINID));
+                        assert_eq!(b"1-1", xtrim.trim.threshold);
+                    }
+                    Event::AOF(Command::SELECT(_))
+                    | Event::AOF(Command::RPUSH(_))
+                    | Event::AOF(Command::ZRANGESTORE(_)) => {}
+                    _ => panic!("unexpected event"),
                 }
+                self.checked += 1;
             }
         }
-        assert_eq!(92539, cmd_handler.count);
+
+        let mut handler = TestCmdHandler {
+            checked: 0,
+            moves: Vec::new(),
+        };
+        aof::parse_file("tests/aof/appendonly_redis6.aof", &mut handler).unwrap();
+        assert_eq!(16, handler.checked);
+        assert_eq!(vec!["LEFT RIGHT", "RIGHT LEFT"], handler.moves);
+
+        let commands = [
+            "BLMOVE list list2 RIGHT RIGHT 0.5",
+            "LMOVE list list2 LEFT LEFT",
+            "RPOP list",
+        ];
+        let mut handler = TestCmdHandler {
+            checked: 0,
+            moves: Vec::new(),
+        };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+        assert_eq!(vec!["RIGHT RIGHT", "LEFT LEFT"], handler.moves);
     }
 
     #[test]
-    fn test_aof5() {
-        let mut file = File::open("tests/aof/appendonly5.aof").expect("file not found");
-
+    fn test_flush_swapdb() {
         struct TestCmdHandler {
-            count: isize,
+            checked: usize,
         }
 
         impl EventHandler for TestCmdHandler {
-            fn handle(&mut self, _cmd: Event) {
-                self.count += 1;
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::FLUSHDB(flushdb)) => {
+                        assert_eq!(Some(true), flushdb.sync);
+                        assert!(flushdb._async.is_none());
+                    }
+                    Event::AOF(Command::FLUSHALL(flushall)) => {
+                        assert_eq!(Some(true), flushall._async);
+                        assert!(flushall.sync.is_none());
+                    }
+                    Event::AOF(Command::SWAPDB(swapdb)) => {
+                        assert_eq!(b"0", swapdb.index1);
+                        assert_eq!(b"1", swapdb.index2);
+                    }
+                    _ => panic!("unexpected event"),
+                }
+                self.checked += 1;
             }
         }
 
-        let mut cmd_handler = TestCmdHandler { count: 0 };
+        let commands = ["FLUSHDB SYNC", "FLUSHALL ASYNC", "SWAPDB 0 1"];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
 
-        loop {
-            match file.decode_resp() {
-                Ok(resp) => match resp {
-                    Resp::Array(arr) => {
-                        let mut data = Vec::new();
-                        for x in arr {
-                            if let Resp::BulkBytes(bytes) = x {
-                                data.push(bytes);
-                            } else {
-                                panic!("wrong data type");
-                            }
-                        }
-                        cmd::parse(data, &mut cmd_handler);
+    #[test]
+    fn test_restore_migrate() {
+        struct TestCmdHandler {
+            restored: Vec<(Vec<u8>, isize, Option<i64>)>,
+            migrates: usize,
+        }
+
+        impl EventHandler for TestCmdHandler {
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::RESTORE(restore)) => {
+                        assert_eq!(b"k1", restore.key);
+                        assert_eq!(Some(true), restore.replace);
+                        assert_eq!(Some(&b"5"[..]), restore.idle_time);
+                        restore.parse_value(2, self).unwrap();
                     }
-                    _ => panic!("wrong resp type "),
-                },
-                Err(ref e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
-                    } else {
-                        panic!("err")
+                    Event::AOF(Command::MIGRATE(migrate)) => {
+                        assert_eq!(b"127.0.0.1", migrate.host);
+                        assert_eq!(b"", migrate.key);
+                        assert_eq!(b"3", migrate.destination_db);
+                        assert_eq!(Some(true), migrate.copy);
+                        assert_eq!(Some((&b"user"[..], &b"pass"[..])), migrate.auth2);
+                        assert_eq!(Some(vec![&b"k1"[..], b"k2"]), migrate.keys);
+                        self.migrates += 1;
                     }
+                    Event::RDB(Object::String(kv)) => {
+                        self.restored
+                            .push((kv.value.to_vec(), kv.meta.db, kv.meta.expire_millis()));
+                    }
+                    _ => panic!("unexpected event"),
                 }
             }
         }
 
-        assert_eq!(71, cmd_handler.count);
+        let meta = Meta {
+            db: 0,
+            expire: None,
+            evict: None,
+        };
+        let object = Object::String(KeyValue {
+            key: b"k1",
+            value: b"v1",
+            meta: &meta,
+        });
+        let payload = dump::dump(&object).unwrap();
+        let mut handler = TestCmdHandler {
+            restored: Vec::new(),
+            migrates: 0,
+        };
+        let restore = vec![
+            b"RESTORE".to_vec(),
+            b"k1".to_vec(),
+            b"1700000000000".to_vec(),
+            payload,
+            b"REPLACE".to_vec(),
+            b"ABSTTL".to_vec(),
+            b"IDLETIME".to_vec(),
+            b"5".to_vec(),
+        ];
+        cmd::parse(&restore, &CommandParsers::new(), &mut handler);
+        assert_eq!(vec![(b"v1".to_vec(), 2, Some(1700000000000))], handler.restored);
+        let mut recorder = ArgsRecorder { commands: Vec::new() };
+        cmd::parse(&restore, &CommandParsers::new(), &mut recorder);
+        assert_eq!(vec![restore], recorder.commands);
+
+        parse_commands(
+            &["MIGRATE 127.0.0.1 6379  3 1000 COPY AUTH2 user pass KEYS k1 k2"],
+            &mut handler,
+        );
+        assert_eq!(1, handler.migrates);
     }
 
     #[test]
-    fn test_aof6() {
-        let mut file = File::open("tests/aof/appendonly.aof").expect("file not found");
-
-        struct TestCmdHandler {}
+    fn test_hash_field_expire_commands() {
+        struct TestCmdHandler {
+            checked: usize,
+        }
 
         impl EventHandler for TestCmdHandler {
-            fn handle(&mut self, cmd: Event) {
-                match cmd {
-                    Event::RDB(_) => {}
-                    Event::AOF(cmd) => match cmd {
-                        Command::XADD(xadd) => {
-                            let key = String::from_utf8_lossy(xadd.key);
-                            assert_eq!(key, "stream");
-                            for field in &xadd.fields {
-                                let name = String::from_utf8_lossy(field.name);
-                                let value = String::from_utf8_lossy(field.value);
-                                if "name" == name {
-                                    assert_eq!("tomcat", value);
-                                } else if "age" == name {
-                                    assert_eq!("18", value);
-                                }
-                            }
-                        }
-                        Command::XGROUP(xgroup) => {
-                            if let Some(create) = &xgroup.create {
-                                let key = String::from_utf8_lossy(create.key);
-                                assert_eq!("stream", key);
-                                let group = String::from_utf8_lossy(create.group_name);
-                                assert_eq!("group", group);
-                            }
-                        }
-                        Command::XTRIM(xtrim) => {
-                            assert_eq!(false, xtrim.approximation);
-                            assert_eq!(1, xtrim.count);
-                        }
-                        Command::XDEL(xdel) => {
-                            assert!(xdel.ids.len() == 1);
-                            let id = xdel.ids.get(0);
-                            let id = id.unwrap();
-                            let id = String::from_utf8_lossy(*id);
-                            assert_eq!("1588842699754-0", id);
-                        }
-                        _ => {}
+            fn handle(&mut self, event: Event) {
+                match event {
+                    Event::AOF(Command::HEXPIRE(hexpire)) => {
+                        assert_eq!(b"h", hexpire.key);
+                        assert_eq!(b"100", hexpire.seconds);
+                        assert!(matches!(hexpire.condition, Some(ExpireCondition::NX)));
+                        assert_eq!(vec![&b"f1"[..], b"f2"], hexpire.fields);
+                    }
+                    Event::AOF(Command::HPEXPIRE(hpexpire)) => {
+                        assert_eq!(b"100000", hpexpire.milliseconds);
+                        assert!(hpexpire.condition.is_none());
+                        assert_eq!(vec![&b"f1"[..]], hpexpire.fields);
+                    }
+                    Event::AOF(Command::HEXPIREAT(hexpireat)) => {
+                        assert_eq!(b"1700000000", hexpireat.timestamp);
+                        assert!(matches!(hexpireat.condition, Some(ExpireCondition::GT)));
+                    }
+                    Event::AOF(Command::HPEXPIREAT(hpexpireat)) => {
+                        assert_eq!(b"1700000000000", hpexpireat.mill_timestamp);
+                        assert!(matches!(hpexpireat.condition, Some(ExpireCondition::LT)));
+                    }
+                    Event::AOF(Command::HPERSIST(hpersist)) => {
+                        assert_eq!(vec![&b"f1"[..], b"f2"], hpersist.fields);
+                    }
+                    Event::AOF(Command::HGETEX(hgetex)) => match &hgetex.expire {
+                        Some((ExpireType::PX, expire)) => assert_eq!(b"5000", expire.as_slice()),
+                        Some(_) => panic!("wrong expire type"),
+                        None => assert_eq!(Some(true), hgetex.persist),
                     },
+                    Event::AOF(Command::HGETDEL(hgetdel)) => {
+                        assert_eq!(vec![&b"f1"[..], b"f2"], hgetdel.fields);
+                    }
+                    _ => panic!("unexpected event"),
                 }
+                self.checked += 1;
             }
         }
 
-        let mut cmd_handler = TestCmdHandler {};
-        loop {
-            match file.decode_resp() {
-                Ok(resp) => match resp {
-                    Resp::Array(arr) => {
-                        let mut data = Vec::new();
-                        for x in arr {
-                            if let Resp::BulkBytes(bytes) = x {
-                                data.push(bytes);
-                            } else {
-                                panic!("wrong data type");
-                            }
-                        }
-                        cmd::parse(data, &mut cmd_handler);
-                    }
-                    _ => panic!("wrong resp type "),
-                },
-                Err(ref e) => {
-                    if e.kind() == ErrorKind::UnexpectedEof {
-                        break;
-                    } else {
-                        panic!("err")
-                    }
-                }
-            }
+        let commands = [
+            "HEXPIRE h 100 NX FIELDS 2 f1 f2",
+            "HPEXPIRE h 100000 FIELDS 1 f1",
+            "HEXPIREAT h 1700000000 GT FIELDS 1 f1",
+            "HPEXPIREAT h 1700000000000 LT FIELDS 1 f1",
+            "HPERSIST h FIELDS 2 f1 f2",
+            "HGETEX h PX 5000 FIELDS 1 f1",
+            "HGETEX h PERSIST FIELDS 1 f1",
+            "HGETDEL h FIELDS 2 f1 f2",
+        ];
+        let mut handler = TestCmdHandler { checked: 0 };
+        parse_commands(&commands, &mut handler);
+        assert_eq!(commands.len(), handler.checked);
+    }
+
+    #[test]
+    fn test_aof_writer() {
+        let dir = env::temp_dir().join(format!("redis-event-aof-writer-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("appendonly.aof");
+        let mut writer = AOFWriter::new(&path).unwrap();
+        aof::parse_file("tests/aof/appendonly3.aof", &mut writer).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(parse_args("tests/aof/appendonly3.aof"), parse_args(&path));
+        fs::remove_file(&path).unwrap();
+
+        // 保留原AOF中的时间
+        let mut writer = AOFWriter::new(&path).unwrap();
+        writer.with_timestamp_annotation(true);
+        aof::parse_file("tests/aof/appendonly_timestamp.aof", &mut writer).unwrap();
+        writer.finish().unwrap();
+        let mut expected = PositionRecorder { positions: Vec::new() };
+        aof::parse_file("tests/aof/appendonly_timestamp.aof", &mut expected).unwrap();
+        let mut actual = PositionRecorder { positions: Vec::new() };
+        aof::parse_file(&path, &mut actual).unwrap();
+        assert_eq!(expected.positions, actual.positions);
+        fs::remove_file(&path).unwrap();
+
+        // 切换文件时不会复用已被删除的较小的序号，每个新的文件都以SELECT开头
+        fs::write(dir.join("appendonly.aof.3"), b"").unwrap();
+        let mut writer = AOFWriter::new(&path).unwrap();
+        writer.with_max_size(512);
+        aof::parse_file("tests/aof/appendonly5.aof", &mut writer).unwrap();
+        writer.finish().unwrap();
+        let is_select = |args: &Vec<Vec<u8>>| args[0] == b"SELECT";
+        let mut commands = Vec::new();
+        let mut index = 4;
+        while dir.join(format!("appendonly.aof.{}", index)).exists() {
+            let rotated = parse_args(dir.join(format!("appendonly.aof.{}", index)));
+            assert!(is_select(&rotated[0]));
+            commands.extend(rotated);
+            index += 1;
         }
+        assert!(index > 5);
+        commands.extend(parse_args(&path));
+        let mut expected = parse_args("tests/aof/appendonly5.aof");
+        expected.retain(|args| !is_select(args));
+        commands.retain(|args| !is_select(args));
+        assert_eq!(expected, commands);
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
 
 #[cfg(test)]
 mod other_tests {
+    use crate::config::KeyFilter;
+    use crate::crc64::crc64;
+    use crate::encodings::{IntSet, ZipList, ZipMap};
+    use crate::io::{skip, TeeReader};
+    use crate::listener::ScriptReplication;
+    use crate::listpack::ListPack;
     use crate::rdb::ID;
 
+    #[test]
+    fn test_script_replication() {
+        assert_eq!(ScriptReplication::Effects, ScriptReplication::of_redis_version("7.2.4"));
+        assert_eq!(ScriptReplication::Unknown, ScriptReplication::of_redis_version("6.2.6"));
+        assert_eq!(ScriptReplication::Unknown, ScriptReplication::of_redis_version(""));
+
+        let command = |args: &[&str]| -> Vec<Vec<u8>> { args.iter().map(|arg| arg.as_bytes().to_vec()).collect() };
+        let evalsha = command(&["evalsha", "ffffffffffffffffffffffffffffffffffffffff", "0"]);
+        assert_eq!(ScriptReplication::Verbatim, ScriptReplication::of_command(&evalsha));
+        let eval = command(&["EVAL", "return 1", "0"]);
+        assert_eq!(ScriptReplication::Verbatim, ScriptReplication::of_command(&eval));
+        let multi = command(&["MULTI"]);
+        assert_eq!(ScriptReplication::Unknown, ScriptReplication::of_command(&multi));
+    }
+
+    #[test]
+    fn test_crc64() {
+        assert_eq!(0xe9c6d914c4b8d9ca, crc64(0, b"123456789"));
+    }
+
+    #[test]
+    fn test_listpack() {
+        // "abc", 100, -100, 5000, 100000, i64::MAX
+        let data = b"\x24\x00\x00\x00\x06\x00\x83abc\x04\x64\x01\xDF\x9C\x02\xF1\x88\x13\x03\xF2\xA0\x86\x01\x04\xF4\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F\x09\xFF";
+        let list_pack = ListPack::new(data).unwrap();
+        assert_eq!(Some(6), list_pack.num_elements());
+        let entries: Vec<String> = list_pack
+            .map(|entry| String::from_utf8(entry.unwrap()).unwrap())
+            .collect();
+        assert_eq!(
+            vec!["abc", "100", "-100", "5000", "100000", "9223372036854775807"],
+            entries
+        );
+
+        // 缺少结束标志
+        let mut list_pack = ListPack::new(b"\x07\x00\x00\x00\x01\x00\x01").unwrap();
+        assert!(list_pack.next().unwrap().is_err());
+        assert!(list_pack.next().is_none());
+
+        // 长度超出实际的数据
+        assert!(ListPack::new(b"\x0C\x00\x00\x00\x01\x00\xFF").is_err());
+    }
+
+    #[test]
+    fn test_ziplist() {
+        // "ab", 5, 300
+        let data = b"\x15\x00\x00\x00\x10\x00\x00\x00\x03\x00\x00\x02ab\x04\xF6\x02\xC0\x2C\x01\xFF";
+        let zip_list = ZipList::new(data).unwrap();
+        assert_eq!(Some(3), zip_list.num_elements());
+        let entries: Vec<Vec<u8>> = zip_list.map(|entry| entry.unwrap()).collect();
+        assert_eq!(vec![b"ab".to_vec(), b"5".to_vec(), b"300".to_vec()], entries);
+    }
+
+    #[test]
+    fn test_zipmap() {
+        // a => b, cd => xyz(末尾有1个空闲字节)
+        let data = b"\x02\x01a\x01\x00b\x02cd\x03\x01xyz\x00\xFF";
+        let zip_map = ZipMap::new(data).unwrap();
+        assert_eq!(Some(2), zip_map.num_fields());
+        let fields: Vec<(Vec<u8>, Vec<u8>)> = zip_map.map(|field| field.unwrap()).collect();
+        assert_eq!(
+            vec![(b"a".to_vec(), b"b".to_vec()), (b"cd".to_vec(), b"xyz".to_vec())],
+            fields
+        );
+    }
+
+    #[test]
+    fn test_intset() {
+        let data = b"\x04\x00\x00\x00\x02\x00\x00\x00\xFF\xFF\xFF\xFF\x00\x00\x01\x00";
+        let members: Vec<i64> = IntSet::new(data).unwrap().collect();
+        assert_eq!(vec![-1, 65536], members);
+
+        // 数据长度不足
+        assert!(IntSet::new(&data[..12]).is_err());
+        // 无效的编码
+        assert!(IntSet::new(b"\x03\x00\x00\x00\x00\x00\x00\x00").is_err());
+    }
+
+    #[test]
+    fn test_key_filter_matches() {
+        let glob = |pattern: &str, key: &str| KeyFilter::Glob(pattern.as_bytes().to_vec()).matches(key.as_bytes());
+        assert!(glob("*", "key"));
+        assert!(glob("user:*", "user:1"));
+        assert!(!glob("user:*", "order:1"));
+        assert!(glob("h?llo", "hello"));
+        assert!(!glob("h?llo", "hllo"));
+        assert!(glob("h*llo", "heeeello"));
+        assert!(glob("h[ae]llo", "hallo"));
+        assert!(!glob("h[ae]llo", "hillo"));
+        assert!(glob("h[^e]llo", "hallo"));
+        assert!(!glob("h[^e]llo", "hello"));
+        assert!(glob("h[a-b]llo", "hbllo"));
+        assert!(glob("a\\*b", "a*b"));
+        assert!(!glob("a\\*b", "axb"));
+        assert!(glob("*:*:end", "a:b:end"));
+
+        let filter = KeyFilter::Any(vec![
+            KeyFilter::Prefix(b"user:".to_vec()),
+            KeyFilter::Glob(b"*:tmp".to_vec()),
+        ]);
+        assert!(filter.matches(b"user:1"));
+        assert!(filter.matches(b"order:tmp"));
+        assert!(!filter.matches(b"order:1"));
+    }
+
+    #[test]
+    fn test_tee_reader() {
+        let data = b"REDIS0009 rest of the stream";
+        let mut input: &[u8] = data;
+        let mut output = Vec::new();
+        skip(&mut TeeReader::new(&mut input, Some(&mut output)), 9).unwrap();
+        assert_eq!(b"REDIS0009", &output[..]);
+        assert_eq!(b" rest of the stream", input);
+
+        skip(&mut TeeReader::new(&mut input, None::<&mut Vec<u8>>), 5).unwrap();
+        assert_eq!(b" of the stream", input);
+    }
+
     #[test]
     fn test_id_cmp() {
         let mut id1 = ID { ms: 0, seq: 0 };
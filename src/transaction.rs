@@ -0,0 +1,151 @@
+/*!
+将复制流中MULTI与EXEC之间的命令分组，作为一个完整的事务交给`EventHandler`
+
+Redis会将事务、Lua脚本以及Redis 7.0之后的Function所产生的写命令包装在MULTI与EXEC之间进行传播，
+复制流中的`Command::MULTI`与`Command::EXEC`事件即事务的边界。[`TransactionGrouper`]在此基础上将事务中的命令暂存起来，
+收到EXEC之后通过`EventHandler::handle_transaction`一次性交给下游，以便下游原子地写入。
+
+```no_run
+use redis_event::transaction::{Transaction, TransactionGrouper};
+use redis_event::{Event, EventHandler};
+
+struct Sink {}
+
+impl EventHandler for Sink {
+    fn handle(&mut self, _event: Event) {}
+
+    fn handle_transaction(&mut self, transaction: &Transaction) {
+        // 包括MULTI与EXEC
+        println!("{}条命令", transaction.commands.len());
+    }
+}
+
+let mut grouper = TransactionGrouper::new(Sink {});
+redis_event::aof::parse_file("appendonly.aof", &mut grouper).unwrap();
+```
+
+[`TransactionGrouper`]: struct.TransactionGrouper.html
+*/
+use std::cmp;
+use std::io::Result;
+
+use crate::cmd::{self, Command, CommandParsers};
+use crate::listener::PositionedHandler;
+use crate::resp::encode_command;
+use crate::{AofPosition, Event, EventHandler, HandlerError};
+
+/// MULTI与EXEC之间的一组命令
+pub struct Transaction {
+    /// 事务中的各个原始命令(第一个为命令名)及其在复制流中的位置，首尾分别为MULTI与EXEC
+    pub commands: Vec<(Vec<Vec<u8>>, AofPosition)>,
+    parsers: CommandParsers,
+}
+
+impl Transaction {
+    /// 依次解析事务中的命令，并交给`handler`的`handle_aof_raw`处理，与未分组时收到的事件相同
+    pub fn replay(&self, handler: &mut dyn EventHandler) {
+        for (args, position) in &self.commands {
+            let mut handler = PositionedHandler {
+                inner: handler,
+                args,
+                position: *position,
+            };
+            cmd::parse(args, &self.parsers, &mut handler);
+        }
+    }
+}
+
+/// 将MULTI与EXEC之间的命令分组之后交给内部的`EventHandler`，其余的事件直接转交
+///
+/// 只有通过`EventHandler::handle_aof_raw`收到的命令(即`Listener`以及`aof`模块中产生的所有命令)才会被分组。
+/// 与Redis加载AOF时一样，没有EXEC的事务将被丢弃，如AOF末尾不完整的事务。
+///
+/// 开启`Config::is_ack_after_handle`时，尚未结束的事务通过`EventHandler::pending_offset`返回MULTI之前的offset，
+/// 确认给master的offset不会越过MULTI，重新同步时将从MULTI开始再次接收整个事务，之前暂存的部分被丢弃
+pub struct TransactionGrouper<H: EventHandler> {
+    inner: H,
+    parsers: CommandParsers,
+    transaction: Option<Transaction>,
+}
+
+impl<H: EventHandler> TransactionGrouper<H> {
+    pub fn new(inner: H) -> TransactionGrouper<H> {
+        TransactionGrouper {
+            inner,
+            parsers: CommandParsers::new(),
+            transaction: None,
+        }
+    }
+
+    /// 重新解析事务中的命令时所使用的自定义命令解析器，应与`Listener`中注册的相同
+    pub fn with_command_parsers(&mut self, parsers: CommandParsers) {
+        self.parsers = parsers;
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+}
+
+impl<H: EventHandler> EventHandler for TransactionGrouper<H> {
+    fn handle(&mut self, event: Event) {
+        if let Event::RDB(_) = event {
+            // 重新进行了全量同步，之前的连接中未结束的事务不会再有EXEC
+            self.transaction = None;
+        }
+        self.inner.handle(event);
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        self.inner.handle_aof(event, position);
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        match (&event, self.transaction.as_mut()) {
+            (Event::AOF(Command::MULTI), _) => {
+                self.transaction = Some(Transaction {
+                    commands: vec![(args.to_vec(), position)],
+                    parsers: self.parsers.clone(),
+                });
+            }
+            (Event::AOF(Command::EXEC), Some(_)) => {
+                let mut transaction = self.transaction.take().unwrap();
+                transaction.commands.push((args.to_vec(), position));
+                self.inner.handle_transaction(&transaction);
+            }
+            (_, Some(transaction)) => transaction.commands.push((args.to_vec(), position)),
+            (_, None) => self.inner.handle_aof_raw(event, args, position),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// 有尚未结束的事务时为MULTI之前的offset
+    fn pending_offset(&self) -> Option<i64> {
+        let multi = match &self.transaction {
+            Some(transaction) => match &transaction.commands[0] {
+                (args, position) if position.offset >= 0 => Some(position.offset - encode_command(args).len() as i64),
+                _ => None,
+            },
+            None => None,
+        };
+        match (multi, self.inner.pending_offset()) {
+            (Some(multi), Some(pending)) => Some(cmp::min(multi, pending)),
+            (multi, pending) => multi.or(pending),
+        }
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
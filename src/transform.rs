@@ -0,0 +1,504 @@
+/*!
+在事件交给`EventHandler`之前按顺序执行的改写阶段，使去除key前缀、隐藏敏感值等与业务无关的改写不必放在业务的处理器中
+
+```no_run
+use redis_event::config::Config;
+use redis_event::listener::Builder;
+use redis_event::transform::{LowercaseKeys, RedactValues, StripKeyPrefix};
+use redis_event::{NoOpEventHandler, OwnedEvent, RedisListener};
+
+fn run(config: Config) -> std::io::Result<()> {
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(NoOpEventHandler {}));
+    builder.with_transform(Box::new(StripKeyPrefix::new(b"app1:")));
+    builder.with_transform(Box::new(LowercaseKeys));
+    builder.with_transform(Box::new(RedactValues::new("*password*", b"******")));
+    // 任意的闭包也可以作为改写阶段，返回None时丢弃此事件
+    builder.with_transform(Box::new(|event: OwnedEvent| match &event {
+        OwnedEvent::AOF(cmd) if cmd.name() == "PUBLISH" => None,
+        _ => Some(event),
+    }));
+    builder.build().start()
+}
+```
+
+每个阶段收到的是前一个阶段改写之后的[`OwnedEvent`]，所有阶段执行完毕之后，命令将重新解析为`Event::AOF`，
+RDB中的数据也将重新构造为`Event::RDB`，再交给处理器。事件需要复制一份才能改写，未设置任何阶段时不会有额外的开销。
+
+[`OwnedEvent`]: ../enum.OwnedEvent.html
+*/
+use std::io::{Error, ErrorKind, Result};
+
+#[cfg(not(feature = "tracing"))]
+use log::warn;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::cmd::{self, CommandParsers, OwnedCommand};
+use crate::config::KeyFilter;
+use crate::rdb::{Hash, KeyValue, List, Object, OwnedObject, Set, SkippedKey, SortedSet, Stream, StringChunk};
+use crate::{AofPosition, Event, EventHandler, HandlerError, OwnedEvent};
+
+/// 事件的改写阶段
+pub trait Transform {
+    /// 改写事件，返回None时丢弃此事件，之后的阶段以及处理器都不会收到
+    fn transform(&mut self, event: OwnedEvent) -> Option<OwnedEvent>;
+}
+
+impl<F> Transform for F
+where
+    F: FnMut(OwnedEvent) -> Option<OwnedEvent>,
+{
+    fn transform(&mut self, event: OwnedEvent) -> Option<OwnedEvent> {
+        self(event)
+    }
+}
+
+/// 改写事件中的所有key，包括RDB中的key以及命令中的key(即`Command::keys`所返回的参数)
+///
+/// `Command::Other`与`Command::Module`无法得知其中哪些参数是key，不会被改写
+pub fn map_keys(event: &mut OwnedEvent, mut f: impl FnMut(&mut Vec<u8>)) {
+    match event {
+        OwnedEvent::RDB(object) => match object {
+            OwnedObject::String { key, .. }
+            | OwnedObject::List { key, .. }
+            | OwnedObject::Set { key, .. }
+            | OwnedObject::SortedSet { key, .. }
+            | OwnedObject::Hash { key, .. }
+            | OwnedObject::Module { key, .. }
+            | OwnedObject::Stream { key, .. }
+            | OwnedObject::StringChunk { key, .. }
+            | OwnedObject::SkippedKey { key, .. } => f(key),
+            OwnedObject::Function(_)
+            | OwnedObject::ModuleAux(_)
+            | OwnedObject::ResizeDB(_)
+            | OwnedObject::BOR
+            | OwnedObject::Stats(_)
+            | OwnedObject::EOR => {}
+        },
+        OwnedEvent::AOF(cmd) => {
+            for index in key_indices(cmd) {
+                f(&mut cmd.args[index]);
+            }
+        }
+    }
+}
+
+/// 改写事件中的所有值，即String的值、List与Set的元素、SortedSet的成员、Hash中field的值以及Stream中field的值；
+/// 命令中除命令名以及key之外的参数都视为值，改写其中的选项(如`SET`中的`EX`)将使命令无法正确解析
+pub fn map_values(event: &mut OwnedEvent, mut f: impl FnMut(&mut Vec<u8>)) {
+    match event {
+        OwnedEvent::RDB(object) => match object {
+            OwnedObject::String { value, .. } => f(value),
+            OwnedObject::StringChunk { chunk, .. } => f(chunk),
+            OwnedObject::List { values, .. } => values.iter_mut().for_each(f),
+            OwnedObject::Set { members, .. } => members.iter_mut().for_each(f),
+            OwnedObject::SortedSet { items, .. } => items.iter_mut().for_each(|item| f(&mut item.member)),
+            OwnedObject::Hash { fields, .. } => fields.iter_mut().for_each(|field| f(&mut field.value)),
+            OwnedObject::Stream { entries, .. } => {
+                for entry in entries.values_mut() {
+                    entry.fields.values_mut().for_each(&mut f);
+                }
+            }
+            OwnedObject::Module { .. }
+            | OwnedObject::Function(_)
+            | OwnedObject::ModuleAux(_)
+            | OwnedObject::SkippedKey { .. }
+            | OwnedObject::ResizeDB(_)
+            | OwnedObject::BOR
+            | OwnedObject::Stats(_)
+            | OwnedObject::EOR => {}
+        },
+        OwnedEvent::AOF(cmd) => {
+            let keys = key_indices(cmd);
+            for (index, arg) in cmd.args.iter_mut().enumerate().skip(1) {
+                if !keys.contains(&index) {
+                    f(arg);
+                }
+            }
+        }
+    }
+}
+
+// 命令中作为key的参数的下标，`Command::keys`按照key在命令中出现的顺序排列，依次向后查找内容相同的参数即可
+fn key_indices(cmd: &OwnedCommand) -> Vec<usize> {
+    struct Collector<'a> {
+        args: &'a [Vec<u8>],
+        indices: Vec<usize>,
+    }
+
+    impl EventHandler for Collector<'_> {
+        fn handle(&mut self, event: Event) {
+            if let Event::AOF(cmd) = event {
+                let mut start = 1;
+                for key in cmd.keys() {
+                    if let Some(offset) = self.args[start..].iter().position(|arg| arg.as_slice() == key) {
+                        self.indices.push(start + offset);
+                        start += offset + 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut collector = Collector {
+        args: &cmd.args,
+        indices: Vec::new(),
+    };
+    cmd::parse(&cmd.args, &CommandParsers::new(), &mut collector);
+    collector.indices
+}
+
+/// 去除key的前缀，不以此前缀开头的key保持不变
+pub struct StripKeyPrefix {
+    prefix: Vec<u8>,
+}
+
+impl StripKeyPrefix {
+    pub fn new(prefix: &[u8]) -> StripKeyPrefix {
+        StripKeyPrefix {
+            prefix: prefix.to_vec(),
+        }
+    }
+}
+
+impl Transform for StripKeyPrefix {
+    fn transform(&mut self, mut event: OwnedEvent) -> Option<OwnedEvent> {
+        let prefix = &self.prefix;
+        map_keys(&mut event, |key| {
+            if key.starts_with(prefix) {
+                key.drain(..prefix.len());
+            }
+        });
+        Some(event)
+    }
+}
+
+/// 将key中的ASCII字母转为小写
+pub struct LowercaseKeys;
+
+impl Transform for LowercaseKeys {
+    fn transform(&mut self, mut event: OwnedEvent) -> Option<OwnedEvent> {
+        map_keys(&mut event, |key| key.make_ascii_lowercase());
+        Some(event)
+    }
+}
+
+/// 将与glob模式(与Redis中的KEYS命令相同)相匹配的值替换为`replacement`，哪些部分属于值见`map_values`
+pub struct RedactValues {
+    pattern: KeyFilter,
+    replacement: Vec<u8>,
+}
+
+impl RedactValues {
+    pub fn new(pattern: &str, replacement: &[u8]) -> RedactValues {
+        RedactValues {
+            pattern: KeyFilter::Glob(pattern.as_bytes().to_vec()),
+            replacement: replacement.to_vec(),
+        }
+    }
+}
+
+impl Transform for RedactValues {
+    fn transform(&mut self, mut event: OwnedEvent) -> Option<OwnedEvent> {
+        let (pattern, replacement) = (&self.pattern, &self.replacement);
+        map_values(&mut event, |value| {
+            if pattern.matches(value) {
+                *value = replacement.clone();
+            }
+        });
+        Some(event)
+    }
+}
+
+/// 将事件依次交给各个改写阶段，再将改写后的事件交给内部的`EventHandler`
+///
+/// 通过`Builder::with_transform`设置时由监听器自动创建，解析本地的RDB、AOF文件时可以直接使用。
+/// Module的值无法复制，改写之后仍使用原来的值；改写阶段将其他事件改为Module时，由于没有对应的值，此事件将被丢弃
+pub struct Transformer<H: EventHandler> {
+    inner: H,
+    stages: Vec<Box<dyn Transform + Send>>,
+    parsers: CommandParsers,
+}
+
+impl<H: EventHandler> Transformer<H> {
+    pub fn new(inner: H) -> Transformer<H> {
+        Transformer {
+            inner,
+            stages: Vec::new(),
+            parsers: CommandParsers::new(),
+        }
+    }
+
+    /// 在末尾添加一个改写阶段
+    pub fn add(&mut self, stage: Box<dyn Transform + Send>) {
+        self.stages.push(stage);
+    }
+
+    /// 设置重新解析改写后的命令时所使用的自定义解析器，见`Builder::with_command_parser`
+    pub fn set_command_parsers(&mut self, parsers: CommandParsers) {
+        self.parsers = parsers;
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    fn transform(&mut self, event: OwnedEvent) -> Option<OwnedEvent> {
+        self.stages
+            .iter_mut()
+            .try_fold(event, |event, stage| stage.transform(event))
+    }
+
+    fn deliver(&mut self, original: Event, event: OwnedEvent, delivery: Delivery) {
+        match event {
+            OwnedEvent::AOF(cmd) => {
+                let mut handler = Deliverer {
+                    inner: &mut self.inner,
+                    args: &cmd.args,
+                    delivery,
+                };
+                cmd::parse(&cmd.args, &self.parsers, &mut handler);
+            }
+            OwnedEvent::RDB(object) => {
+                let original = match original {
+                    Event::RDB(object) => Some(object),
+                    Event::AOF(_) => None,
+                };
+                deliver_object(&mut self.inner, object, original);
+            }
+        }
+    }
+}
+
+impl<H: EventHandler> EventHandler for Transformer<H> {
+    fn handle(&mut self, event: Event) {
+        if self.stages.is_empty() {
+            return self.inner.handle(event);
+        }
+        if let Some(transformed) = self.transform(event.to_owned()) {
+            self.deliver(event, transformed, Delivery::Handle);
+        }
+    }
+
+    fn handle_aof(&mut self, event: Event, position: AofPosition) {
+        if self.stages.is_empty() {
+            return self.inner.handle_aof(event, position);
+        }
+        if let Some(transformed) = self.transform(event.to_owned()) {
+            self.deliver(event, transformed, Delivery::Aof(position));
+        }
+    }
+
+    fn handle_aof_raw(&mut self, event: Event, args: &[Vec<u8>], position: AofPosition) {
+        if self.stages.is_empty() {
+            return self.inner.handle_aof_raw(event, args, position);
+        }
+        let cmd = OwnedEvent::AOF(OwnedCommand::new(args.to_vec()));
+        if let Some(transformed) = self.transform(cmd) {
+            self.deliver(event, transformed, Delivery::Raw(position));
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pending_offset(&self) -> Option<i64> {
+        self.inner.pending_offset()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
+
+/// 改写后的命令交给内部处理器的哪个方法，与收到原来的命令时一致
+#[derive(Clone, Copy)]
+enum Delivery {
+    Handle,
+    Aof(AofPosition),
+    Raw(AofPosition),
+}
+
+struct Deliverer<'a> {
+    inner: &'a mut dyn EventHandler,
+    args: &'a [Vec<u8>],
+    delivery: Delivery,
+}
+
+impl EventHandler for Deliverer<'_> {
+    fn handle(&mut self, event: Event) {
+        match self.delivery {
+            Delivery::Handle => self.inner.handle(event),
+            Delivery::Aof(position) => self.inner.handle_aof(event, position),
+            Delivery::Raw(position) => self.inner.handle_aof_raw(event, self.args, position),
+        }
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
+
+// 将改写后的数据重新构造为`Object`，Module的值以及跳过key时的错误类型取自原来的事件
+fn deliver_object(inner: &mut dyn EventHandler, object: OwnedObject, original: Option<Object>) {
+    let object = match object {
+        OwnedObject::String { key, value, meta } => {
+            return inner.handle(Event::RDB(Object::String(KeyValue {
+                key: &key,
+                value: &value,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::List {
+            key,
+            values,
+            is_first,
+            is_last,
+            meta,
+        } => {
+            return inner.handle(Event::RDB(Object::List(List {
+                key: &key,
+                values: &values,
+                is_first,
+                is_last,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::Set {
+            key,
+            members,
+            is_first,
+            is_last,
+            meta,
+        } => {
+            return inner.handle(Event::RDB(Object::Set(Set {
+                key: &key,
+                members: &members,
+                is_first,
+                is_last,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::SortedSet {
+            key,
+            items,
+            is_first,
+            is_last,
+            meta,
+        } => {
+            return inner.handle(Event::RDB(Object::SortedSet(SortedSet {
+                key: &key,
+                items: &items,
+                is_first,
+                is_last,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::Hash {
+            key,
+            fields,
+            is_first,
+            is_last,
+            meta,
+        } => {
+            return inner.handle(Event::RDB(Object::Hash(Hash {
+                key: &key,
+                fields: &fields,
+                is_first,
+                is_last,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::Stream {
+            key,
+            entries,
+            groups,
+            last_id,
+            first_id,
+            max_deleted_id,
+            entries_added,
+            meta,
+        } => {
+            let stream = Stream {
+                entries,
+                groups,
+                last_id,
+                first_id,
+                max_deleted_id,
+                entries_added,
+                meta: &meta,
+            };
+            return inner.handle(Event::RDB(Object::Stream(key, stream)));
+        }
+        OwnedObject::StringChunk {
+            key,
+            chunk,
+            offset,
+            total_len,
+            position,
+            meta,
+        } => {
+            return inner.handle(Event::RDB(Object::StringChunk(StringChunk {
+                key: &key,
+                chunk: &chunk,
+                offset,
+                total_len,
+                position,
+                meta: &meta,
+            })))
+        }
+        OwnedObject::SkippedKey {
+            key,
+            value_type,
+            error,
+            meta,
+        } => {
+            let kind = match &original {
+                Some(Object::SkippedKey(skipped)) => skipped.error.kind(),
+                _ => ErrorKind::Other,
+            };
+            return inner.handle(Event::RDB(Object::SkippedKey(SkippedKey {
+                key,
+                value_type,
+                error: Error::new(kind, error),
+                meta: &meta,
+            })));
+        }
+        OwnedObject::Stats(stats) => return inner.handle(Event::RDB(Object::Stats(&stats))),
+        OwnedObject::Function(code) => Object::Function(code),
+        OwnedObject::ResizeDB(resize_db) => Object::ResizeDB(resize_db),
+        OwnedObject::BOR => Object::BOR,
+        OwnedObject::EOR => Object::EOR,
+        OwnedObject::Module { key, meta } => match original {
+            Some(Object::Module(_, module, _)) => {
+                return inner.handle(Event::RDB(Object::Module(key, module, &meta)));
+            }
+            _ => {
+                warn!(
+                    "改写后的Module(key: {})没有对应的值，已丢弃",
+                    String::from_utf8_lossy(&key)
+                );
+                return;
+            }
+        },
+        OwnedObject::ModuleAux(name) => match original {
+            Some(Object::ModuleAux(_, module)) => Object::ModuleAux(name, module),
+            _ => {
+                warn!("改写后的Module AUX({})没有对应的值，已丢弃", name);
+                return;
+            }
+        },
+    };
+    inner.handle(Event::RDB(object));
+}
@@ -0,0 +1,289 @@
+/*!
+按照数据类型以及命令分别处理事件，无需在`EventHandler::handle`中自行匹配`Event`、`Object`以及`Command`
+
+[`TypedEventHandler`]中的每个方法默认都不做任何处理，只需实现关心的方法，再通过[`Typed`]适配为`EventHandler`:
+
+```no_run
+use redis_event::cmd::keys::DEL;
+use redis_event::cmd::strings::SET;
+use redis_event::config::Config;
+use redis_event::listener::Builder;
+use redis_event::rdb::{Hash, KeyValue};
+use redis_event::typed::{Typed, TypedEventHandler};
+use redis_event::RedisListener;
+
+struct Printer {}
+
+impl TypedEventHandler for Printer {
+    fn on_string(&mut self, kv: &KeyValue) {
+        println!("string {}", String::from_utf8_lossy(kv.key));
+    }
+
+    fn on_hash(&mut self, hash: &Hash) {
+        println!("hash {} with {} fields", String::from_utf8_lossy(hash.key), hash.fields.len());
+    }
+
+    fn on_set_cmd(&mut self, cmd: &SET) {
+        println!("SET {}", String::from_utf8_lossy(cmd.key));
+    }
+
+    fn on_del(&mut self, cmd: &DEL) {
+        println!("DEL {} keys", cmd.keys.len());
+    }
+}
+
+fn run(config: Config) -> std::io::Result<()> {
+    let mut builder = Builder::new();
+    builder.with_config(config);
+    builder.with_event_handler(Box::new(Typed::new(Printer {})));
+    builder.build().start()
+}
+```
+
+[`TypedEventHandler`]: trait.TypedEventHandler.html
+[`Typed`]: struct.Typed.html
+*/
+use std::io::Result;
+
+use crate::cmd::connection::SELECT;
+use crate::cmd::hashes::{HDEL, HMSET, HSET};
+use crate::cmd::keys::{DEL, EXPIRE, EXPIREAT, PERSIST, PEXPIRE, PEXPIREAT, RENAME, UNLINK};
+use crate::cmd::lists::{LPOP, LPUSH, LREM, LSET, LTRIM, RPOP, RPUSH};
+use crate::cmd::server::{FLUSHALL, FLUSHDB};
+use crate::cmd::sets::{SADD, SREM};
+use crate::cmd::sorted_sets::{ZADD, ZINCRBY, ZREM};
+use crate::cmd::streams::{XADD, XDEL};
+use crate::cmd::strings::{DECR, DECRBY, INCR, INCRBY, MSET, PSETEX, SET, SETEX, SETNX};
+use crate::cmd::Command;
+use crate::rdb::stats::RDBStats;
+use crate::rdb::{
+    Hash, KeyValue, List, Meta, Module, Object, ResizeDB, Set, SkippedKey, SortedSet, Stream, StringChunk,
+};
+use crate::{Event, EventHandler, HandlerError};
+
+/// 按照数据类型以及命令分别处理事件的处理器，所有方法默认都不做任何处理
+///
+/// RDB中的每种数据都有对应的方法；命令中只有常用的写入命令有对应的方法(命令的方法以命令名命名，`SET`命令为`on_set_cmd`，
+/// 以免与Set类型的`on_set`混淆)，其余的命令都交给`on_other_command`
+pub trait TypedEventHandler {
+    /// RDB开始，对应`Object::BOR`
+    fn on_rdb_begin(&mut self) {}
+
+    /// RDB结束，对应`Object::EOR`
+    fn on_rdb_end(&mut self) {}
+
+    fn on_resize_db(&mut self, _resize_db: &ResizeDB) {}
+
+    fn on_string(&mut self, _kv: &KeyValue) {}
+
+    fn on_string_chunk(&mut self, _chunk: &StringChunk) {}
+
+    fn on_list(&mut self, _list: &List) {}
+
+    fn on_set(&mut self, _set: &Set) {}
+
+    fn on_sorted_set(&mut self, _sorted_set: &SortedSet) {}
+
+    fn on_hash(&mut self, _hash: &Hash) {}
+
+    fn on_stream(&mut self, _key: &[u8], _stream: &Stream) {}
+
+    fn on_module(&mut self, _key: &[u8], _module: &dyn Module, _meta: &Meta) {}
+
+    fn on_module_aux(&mut self, _name: &str, _module: &dyn Module) {}
+
+    fn on_function(&mut self, _code: &[u8]) {}
+
+    fn on_skipped_key(&mut self, _skipped: &SkippedKey) {}
+
+    fn on_stats(&mut self, _stats: &RDBStats) {}
+
+    fn on_select(&mut self, _cmd: &SELECT) {}
+
+    fn on_set_cmd(&mut self, _cmd: &SET) {}
+
+    fn on_setex(&mut self, _cmd: &SETEX) {}
+
+    fn on_psetex(&mut self, _cmd: &PSETEX) {}
+
+    fn on_setnx(&mut self, _cmd: &SETNX) {}
+
+    fn on_mset(&mut self, _cmd: &MSET) {}
+
+    fn on_incr(&mut self, _cmd: &INCR) {}
+
+    fn on_incrby(&mut self, _cmd: &INCRBY) {}
+
+    fn on_decr(&mut self, _cmd: &DECR) {}
+
+    fn on_decrby(&mut self, _cmd: &DECRBY) {}
+
+    fn on_del(&mut self, _cmd: &DEL) {}
+
+    fn on_unlink(&mut self, _cmd: &UNLINK) {}
+
+    fn on_expire(&mut self, _cmd: &EXPIRE) {}
+
+    fn on_pexpire(&mut self, _cmd: &PEXPIRE) {}
+
+    fn on_expireat(&mut self, _cmd: &EXPIREAT) {}
+
+    fn on_pexpireat(&mut self, _cmd: &PEXPIREAT) {}
+
+    fn on_persist(&mut self, _cmd: &PERSIST) {}
+
+    fn on_rename(&mut self, _cmd: &RENAME) {}
+
+    fn on_hset(&mut self, _cmd: &HSET) {}
+
+    fn on_hmset(&mut self, _cmd: &HMSET) {}
+
+    fn on_hdel(&mut self, _cmd: &HDEL) {}
+
+    fn on_lpush(&mut self, _cmd: &LPUSH) {}
+
+    fn on_rpush(&mut self, _cmd: &RPUSH) {}
+
+    fn on_lpop(&mut self, _cmd: &LPOP) {}
+
+    fn on_rpop(&mut self, _cmd: &RPOP) {}
+
+    fn on_lrem(&mut self, _cmd: &LREM) {}
+
+    fn on_lset(&mut self, _cmd: &LSET) {}
+
+    fn on_ltrim(&mut self, _cmd: &LTRIM) {}
+
+    fn on_sadd(&mut self, _cmd: &SADD) {}
+
+    fn on_srem(&mut self, _cmd: &SREM) {}
+
+    fn on_zadd(&mut self, _cmd: &ZADD) {}
+
+    fn on_zincrby(&mut self, _cmd: &ZINCRBY) {}
+
+    fn on_zrem(&mut self, _cmd: &ZREM) {}
+
+    fn on_xadd(&mut self, _cmd: &XADD) {}
+
+    fn on_xdel(&mut self, _cmd: &XDEL) {}
+
+    fn on_flushdb(&mut self, _cmd: &FLUSHDB) {}
+
+    fn on_flushall(&mut self, _cmd: &FLUSHALL) {}
+
+    fn on_multi(&mut self) {}
+
+    fn on_exec(&mut self) {}
+
+    /// 没有对应方法的命令
+    fn on_other_command(&mut self, _cmd: &Command) {}
+
+    /// 见`EventHandler::flush`
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 见`EventHandler::take_error`
+    fn take_error(&mut self) -> Option<HandlerError> {
+        None
+    }
+}
+
+/// 将`TypedEventHandler`适配为`EventHandler`，按照事件的类型调用其对应的方法
+pub struct Typed<H: TypedEventHandler> {
+    inner: H,
+}
+
+impl<H: TypedEventHandler> Typed<H> {
+    pub fn new(inner: H) -> Typed<H> {
+        Typed { inner }
+    }
+
+    pub fn get_ref(&self) -> &H {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+}
+
+impl<H: TypedEventHandler> EventHandler for Typed<H> {
+    fn handle(&mut self, event: Event) {
+        let handler = &mut self.inner;
+        match event {
+            Event::RDB(object) => match object {
+                Object::BOR => handler.on_rdb_begin(),
+                Object::EOR => handler.on_rdb_end(),
+                Object::ResizeDB(resize_db) => handler.on_resize_db(&resize_db),
+                Object::String(kv) => handler.on_string(&kv),
+                Object::StringChunk(chunk) => handler.on_string_chunk(&chunk),
+                Object::List(list) => handler.on_list(&list),
+                Object::Set(set) => handler.on_set(&set),
+                Object::SortedSet(sorted_set) => handler.on_sorted_set(&sorted_set),
+                Object::Hash(hash) => handler.on_hash(&hash),
+                Object::Stream(key, stream) => handler.on_stream(&key, &stream),
+                Object::Module(key, module, meta) => handler.on_module(&key, module.as_ref(), meta),
+                Object::ModuleAux(name, module) => handler.on_module_aux(&name, module.as_ref()),
+                Object::Function(code) => handler.on_function(&code),
+                Object::SkippedKey(skipped) => handler.on_skipped_key(&skipped),
+                Object::Stats(stats) => handler.on_stats(stats),
+            },
+            Event::AOF(cmd) => match cmd {
+                Command::SELECT(cmd) => handler.on_select(cmd),
+                Command::SET(cmd) => handler.on_set_cmd(cmd),
+                Command::SETEX(cmd) => handler.on_setex(cmd),
+                Command::PSETEX(cmd) => handler.on_psetex(cmd),
+                Command::SETNX(cmd) => handler.on_setnx(cmd),
+                Command::MSET(cmd) => handler.on_mset(cmd),
+                Command::INCR(cmd) => handler.on_incr(cmd),
+                Command::INCRBY(cmd) => handler.on_incrby(cmd),
+                Command::DECR(cmd) => handler.on_decr(cmd),
+                Command::DECRBY(cmd) => handler.on_decrby(cmd),
+                Command::DEL(cmd) => handler.on_del(cmd),
+                Command::UNLINK(cmd) => handler.on_unlink(cmd),
+                Command::EXPIRE(cmd) => handler.on_expire(cmd),
+                Command::PEXPIRE(cmd) => handler.on_pexpire(cmd),
+                Command::EXPIREAT(cmd) => handler.on_expireat(cmd),
+                Command::PEXPIREAT(cmd) => handler.on_pexpireat(cmd),
+                Command::PERSIST(cmd) => handler.on_persist(cmd),
+                Command::RENAME(cmd) => handler.on_rename(cmd),
+                Command::HSET(cmd) => handler.on_hset(cmd),
+                Command::HMSET(cmd) => handler.on_hmset(cmd),
+                Command::HDEL(cmd) => handler.on_hdel(cmd),
+                Command::LPUSH(cmd) => handler.on_lpush(cmd),
+                Command::RPUSH(cmd) => handler.on_rpush(cmd),
+                Command::LPOP(cmd) => handler.on_lpop(cmd),
+                Command::RPOP(cmd) => handler.on_rpop(cmd),
+                Command::LREM(cmd) => handler.on_lrem(cmd),
+                Command::LSET(cmd) => handler.on_lset(cmd),
+                Command::LTRIM(cmd) => handler.on_ltrim(cmd),
+                Command::SADD(cmd) => handler.on_sadd(cmd),
+                Command::SREM(cmd) => handler.on_srem(cmd),
+                Command::ZADD(cmd) => handler.on_zadd(cmd),
+                Command::ZINCRBY(cmd) => handler.on_zincrby(cmd),
+                Command::ZREM(cmd) => handler.on_zrem(cmd),
+                Command::XADD(cmd) => handler.on_xadd(cmd),
+                Command::XDEL(cmd) => handler.on_xdel(cmd),
+                Command::FLUSHDB(cmd) => handler.on_flushdb(cmd),
+                Command::FLUSHALL(cmd) => handler.on_flushall(cmd),
+                Command::MULTI => handler.on_multi(),
+                Command::EXEC => handler.on_exec(),
+                cmd => handler.on_other_command(&cmd),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn take_error(&mut self) -> Option<HandlerError> {
+        self.inner.take_error()
+    }
+}
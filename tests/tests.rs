@@ -1,12 +1,10 @@
 use std::borrow::BorrowMut;
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::env;
 use std::ops::{Deref, DerefMut};
 use std::process::Command;
-use std::rc::Rc;
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::thread::sleep;
 use std::time::Duration;
@@ -17,9 +15,10 @@ use redis::ConnectionAddr;
 use serial_test::serial;
 
 use crate::support::*;
-use redis_event::config::Config;
-use redis_event::rdb::{ExpireType, Object};
-use redis_event::{cmd, Event, EventHandler, RedisListener};
+use redis_event::config::{Config, ParseMode};
+use redis_event::group::ListenerGroup;
+use redis_event::rdb::{self, ChecksumStatus, ExpireType, Object, OwnedObject};
+use redis_event::{cmd, AofPosition, Event, EventHandler, OwnedEvent, RedisListener, TaggedEventHandler};
 use redis_event::{listener, NoOpEventHandler};
 
 mod support;
@@ -48,7 +47,7 @@ fn test_hash() {
         }
     }
 
-    start_redis_test("dictionary.rdb", 10000, Rc::new(RefCell::new(TestRdbHandler {})));
+    start_redis_test("dictionary.rdb", 10000, Box::new(TestRdbHandler {}));
 }
 
 #[test]
@@ -80,7 +79,7 @@ fn test_hash_1() {
         }
     }
 
-    start_redis_test("hash_as_ziplist.rdb", 10001, Rc::new(RefCell::new(TestRdbHandler {})));
+    start_redis_test("hash_as_ziplist.rdb", 10001, Box::new(TestRdbHandler {}));
 }
 
 #[test]
@@ -107,11 +106,7 @@ fn test_string() {
         }
     }
 
-    start_redis_test(
-        "easily_compressible_string_key.rdb",
-        10002,
-        Rc::new(RefCell::new(TestRdbHandler {})),
-    );
+    start_redis_test("easily_compressible_string_key.rdb", 10002, Box::new(TestRdbHandler {}));
 }
 
 #[test]
@@ -149,7 +144,7 @@ fn test_integer() {
     start_redis_test(
         "integer_keys.rdb",
         10003,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -185,11 +180,7 @@ fn test_intset16() {
             }
         }
     }
-    start_redis_test(
-        "intset_16.rdb",
-        10004,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
-    );
+    start_redis_test("intset_16.rdb", 10004, Box::new(TestRdbHandler { map: HashMap::new() }));
 }
 
 #[test]
@@ -224,11 +215,7 @@ fn test_intset32() {
             }
         }
     }
-    start_redis_test(
-        "intset_32.rdb",
-        10005,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
-    );
+    start_redis_test("intset_32.rdb", 10005, Box::new(TestRdbHandler { map: HashMap::new() }));
 }
 
 #[test]
@@ -263,11 +250,7 @@ fn test_intset64() {
             }
         }
     }
-    start_redis_test(
-        "intset_64.rdb",
-        10006,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
-    );
+    start_redis_test("intset_64.rdb", 10006, Box::new(TestRdbHandler { map: HashMap::new() }));
 }
 
 #[test]
@@ -296,7 +279,7 @@ fn test_keys_with_expiry() {
             }
         }
     }
-    start_redis_test("keys_with_expiry.rdb", 10007, Rc::new(RefCell::new(TestRdbHandler {})));
+    start_redis_test("keys_with_expiry.rdb", 10007, Box::new(TestRdbHandler {}));
 }
 
 #[test]
@@ -334,11 +317,7 @@ fn test_linked_list() {
             }
         }
     }
-    start_redis_test(
-        "linkedlist.rdb",
-        10008,
-        Rc::new(RefCell::new(TestRdbHandler { list: vec![] })),
-    );
+    start_redis_test("linkedlist.rdb", 10008, Box::new(TestRdbHandler { list: vec![] }));
 }
 
 #[test]
@@ -368,11 +347,7 @@ fn test_multiple_database() {
             }
         }
     }
-    start_redis_test(
-        "multiple_databases.rdb",
-        10009,
-        Rc::new(RefCell::new(TestRdbHandler {})),
-    );
+    start_redis_test("multiple_databases.rdb", 10009, Box::new(TestRdbHandler {}));
 }
 
 #[test]
@@ -410,7 +385,7 @@ fn test_regular_set() {
     start_redis_test(
         "regular_set.rdb",
         11110,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -459,7 +434,7 @@ fn test_regular_sorted_set() {
     start_redis_test(
         "regular_sorted_set.rdb",
         10011,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -497,7 +472,7 @@ fn test_zipmap_big_values() {
     start_redis_test(
         "zipmap_with_big_values.rdb",
         10012,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -534,7 +509,7 @@ fn test_zipmap_compress() {
     start_redis_test(
         "zipmap_that_compresses_easily.rdb",
         10013,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -570,7 +545,7 @@ fn test_zipmap_not_compress() {
     start_redis_test(
         "zipmap_that_doesnt_compress.rdb",
         10014,
-        Rc::new(RefCell::new(TestRdbHandler { map: HashMap::new() })),
+        Box::new(TestRdbHandler { map: HashMap::new() }),
     );
 }
 
@@ -610,7 +585,7 @@ fn test_ziplist() {
     start_redis_test(
         "ziplist_that_compresses_easily.rdb",
         10015,
-        Rc::new(RefCell::new(TestRdbHandler { list: vec![] })),
+        Box::new(TestRdbHandler { list: vec![] }),
     );
 }
 
@@ -716,13 +691,29 @@ fn test_aof() {
             identity: None,
             username: "".to_string(),
             identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
         };
         let running = Arc::new(AtomicBool::new(true));
 
         let mut builder = listener::Builder::new();
         builder.with_config(conf);
         builder.with_control_flag(running);
-        builder.with_event_handler(Rc::new(RefCell::new(cmd_handler)));
+        builder.with_event_handler(Box::new(cmd_handler));
 
         let mut redis_listener = builder.build();
 
@@ -814,13 +805,29 @@ fn test_tls() {
         identity: None,
         username: "".to_string(),
         identity_passwd: None,
+        master_timeout: None,
+        aof_queue_size: None,
+        is_ack_after_handle: false,
+        max_full_resyncs: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        is_aggregate_collections: false,
+        db_filter: None,
+        key_filter: None,
+        rdb_backup_path: None,
+        aof_backup_path: None,
+        is_collect_stats: false,
+        max_handler_retries: None,
     };
     let running = Arc::new(AtomicBool::new(true));
 
     let mut builder = listener::Builder::new();
     builder.with_config(conf);
     builder.with_control_flag(running);
-    builder.with_event_handler(Rc::new(RefCell::new(NoOpEventHandler {})));
+    builder.with_event_handler(Box::new(NoOpEventHandler {}));
 
     let mut redis_listener = builder.build();
     println!("connect to redis-server");
@@ -832,7 +839,544 @@ fn test_tls() {
     context.stop_server();
 }
 
-fn start_redis_test(rdb: &str, port: u16, rdb_handler: Rc<RefCell<dyn EventHandler>>) {
+#[test]
+#[serial]
+fn test_stop() {
+    let port = 10017;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    let (sender, receiver) = mpsc::channel();
+    let t = thread::spawn(move || {
+        let conf = Config {
+            is_discard_rdb: true,
+            is_aof: true,
+            host: String::from("127.0.0.1"),
+            port,
+            username: "".to_string(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+
+        let mut builder = listener::Builder::new();
+        builder.with_config(conf);
+        builder.with_control_flag(running);
+
+        let mut redis_listener = builder.build();
+        sender.send(redis_listener.stop_handle()).unwrap();
+        redis_listener.start()
+    });
+
+    let stop_handle = receiver.recv().unwrap();
+    // 没有写入操作时，监听器会一直阻塞在读取上
+    sleep(Duration::from_secs(3));
+    stop_handle.stop();
+    let result = t.join().expect("thread error");
+    shutdown_redis(pid);
+    assert!(result.is_ok());
+}
+
+#[test]
+#[serial]
+fn test_aof_queue() {
+    let port = 10018;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    struct SlowCmdHandler {
+        pid: u32,
+        count: Arc<Mutex<i32>>,
+    }
+
+    impl EventHandler for SlowCmdHandler {
+        fn handle(&mut self, event: Event) {
+            if let Event::AOF(cmd) = event {
+                match cmd {
+                    cmd::Command::SET(_) => {
+                        // 模拟处理较慢的handler
+                        sleep(Duration::from_millis(10));
+                        *self.count.lock().unwrap() += 1;
+                    }
+                    cmd::Command::FLUSHALL(_) => shutdown_redis(self.pid),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let cmd_count = Arc::new(Mutex::new(0));
+    let rc = cmd_count.clone();
+    let t = thread::spawn(move || {
+        let conf = Config {
+            is_discard_rdb: true,
+            is_aof: true,
+            host: String::from("127.0.0.1"),
+            port,
+            username: "".to_string(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: Some(4),
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+
+        let mut builder = listener::Builder::new();
+        builder.with_config(conf);
+        builder.with_control_flag(running);
+        builder.with_event_handler(Box::new(SlowCmdHandler { pid, count: rc }));
+
+        let mut redis_listener = builder.build();
+        if let Err(_) = redis_listener.start() {
+            println!("redis-server closed");
+        }
+    });
+    // wait thread start
+    thread::sleep(Duration::from_secs(2));
+
+    let uri = format!("redis://127.0.0.1:{}", port);
+    let client = redis::Client::open(uri.as_str()).unwrap();
+    let mut conn = client.get_connection().unwrap();
+    for i in 0..100 {
+        let _: () = conn.set(format!("key{}", i), i).unwrap();
+    }
+    let _: () = redis::cmd("FLUSHALL").query(&mut conn).unwrap();
+    t.join().expect("thread error");
+
+    assert_eq!(100, *cmd_count.lock().unwrap().deref());
+}
+
+#[test]
+#[serial]
+fn test_listener_group() {
+    let pid1 = start_redis_server("easily_compressible_string_key.rdb", 10019);
+    let pid2 = start_redis_server("easily_compressible_string_key.rdb", 10020);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    struct TestTaggedHandler {
+        counts: Arc<Mutex<HashMap<String, i32>>>,
+    }
+
+    impl TaggedEventHandler for TestTaggedHandler {
+        fn handle(&mut self, source: &str, event: Event) {
+            if let Event::RDB(Object::String(_)) = event {
+                let mut counts = self.counts.lock().unwrap();
+                *counts.entry(source.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let counts = Arc::new(Mutex::new(HashMap::new()));
+    let handler = TestTaggedHandler { counts: counts.clone() };
+    let running = Arc::new(AtomicBool::new(true));
+
+    let mut group = ListenerGroup::new(Arc::new(Mutex::new(handler)), running);
+    for (source, port) in &[("redis-1", 10019), ("redis-2", 10020)] {
+        let conf = Config {
+            is_discard_rdb: false,
+            is_aof: false,
+            host: String::from("127.0.0.1"),
+            port: *port,
+            username: "".to_string(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        };
+        group.add(source, conf);
+    }
+    let result = group.start();
+    shutdown_redis(pid1);
+    shutdown_redis(pid2);
+    assert!(result.is_ok());
+
+    let counts = counts.lock().unwrap();
+    assert_eq!(Some(&1), counts.get("redis-1"));
+    assert_eq!(Some(&1), counts.get("redis-2"));
+}
+
+#[test]
+#[serial]
+fn test_ack_after_handle() {
+    let port = 10021;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    struct FailingSinkHandler {
+        should_fail: bool,
+    }
+
+    impl EventHandler for FailingSinkHandler {
+        fn handle(&mut self, event: Event) {
+            if let Event::AOF(cmd::Command::SET(set)) = event {
+                if set.key == b"boom" {
+                    self.should_fail = true;
+                }
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            if self.should_fail {
+                Err(std::io::Error::new(std::io::ErrorKind::Other, "flush failed"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    let t = thread::spawn(move || {
+        let conf = Config {
+            is_discard_rdb: true,
+            is_aof: true,
+            host: String::from("127.0.0.1"),
+            port,
+            username: "".to_string(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: true,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+
+        let mut builder = listener::Builder::new();
+        builder.with_config(conf);
+        builder.with_control_flag(running);
+        builder.with_event_handler(Box::new(FailingSinkHandler { should_fail: false }));
+
+        let mut redis_listener = builder.build();
+        redis_listener.start()
+    });
+    // wait thread start
+    thread::sleep(Duration::from_secs(2));
+
+    let uri = format!("redis://127.0.0.1:{}", port);
+    let client = redis::Client::open(uri.as_str()).unwrap();
+    let mut conn = client.get_connection().unwrap();
+    let _: () = conn.set("boom", "1").unwrap();
+    // flush每秒最多调用一次，等待之后再写入以触发flush
+    sleep(Duration::from_millis(1500));
+    let _: () = conn.set("aa", "bb").unwrap();
+
+    let result = t.join().expect("thread error");
+    shutdown_redis(pid);
+    assert_eq!("flush failed", result.unwrap_err().to_string());
+}
+
+#[test]
+#[serial]
+fn test_aof_position() {
+    let port = 10022;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    struct PositionHandler {
+        pid: u32,
+        positions: Arc<Mutex<Vec<AofPosition>>>,
+    }
+
+    impl EventHandler for PositionHandler {
+        fn handle(&mut self, _: Event) {
+            panic!("AOF events should be delivered through handle_aof");
+        }
+
+        fn handle_aof(&mut self, event: Event, position: AofPosition) {
+            match event {
+                Event::AOF(cmd::Command::SET(_)) => self.positions.lock().unwrap().push(position),
+                Event::AOF(cmd::Command::FLUSHALL(_)) => shutdown_redis(self.pid),
+                _ => {}
+            }
+        }
+    }
+
+    let positions = Arc::new(Mutex::new(Vec::new()));
+    let rc = positions.clone();
+    let t = thread::spawn(move || {
+        let conf = Config {
+            is_discard_rdb: true,
+            is_aof: true,
+            host: String::from("127.0.0.1"),
+            port,
+            username: "".to_string(),
+            password: String::new(),
+            repl_id: String::from("?"),
+            repl_offset: -1,
+            read_timeout: None,
+            write_timeout: None,
+            is_tls_enabled: false,
+            is_tls_insecure: false,
+            identity: None,
+            identity_passwd: None,
+            master_timeout: None,
+            aof_queue_size: None,
+            is_ack_after_handle: false,
+            max_full_resyncs: None,
+            parse_mode: ParseMode::Strict,
+            is_skip_corrupt_keys: false,
+            is_discard_expired_keys: false,
+            expire_reference_time: None,
+            string_chunk_size: None,
+            is_aggregate_collections: false,
+            db_filter: None,
+            key_filter: None,
+            rdb_backup_path: None,
+            aof_backup_path: None,
+            is_collect_stats: false,
+            max_handler_retries: None,
+        };
+        let running = Arc::new(AtomicBool::new(true));
+
+        let mut builder = listener::Builder::new();
+        builder.with_config(conf);
+        builder.with_control_flag(running);
+        builder.with_event_handler(Box::new(PositionHandler { pid, positions: rc }));
+
+        let mut redis_listener = builder.build();
+        let _ = redis_listener.start();
+        redis_listener.config.repl_offset
+    });
+    // wait thread start
+    thread::sleep(Duration::from_secs(2));
+
+    let uri = format!("redis://127.0.0.1:{}", port);
+    let client = redis::Client::open(uri.as_str()).unwrap();
+    let mut conn = client.get_connection().unwrap();
+    for i in 0..3 {
+        let _: () = conn.set("aa", i).unwrap();
+    }
+    let _: () = redis::cmd("FLUSHALL").query(&mut conn).unwrap();
+    let repl_offset = t.join().expect("thread error");
+
+    let positions = positions.lock().unwrap();
+    assert_eq!(3, positions.len());
+    for pair in positions.windows(2) {
+        assert!(pair[0].offset < pair[1].offset);
+        assert!(pair[0].sequence < pair[1].sequence);
+    }
+    assert!(positions[2].offset < repl_offset);
+}
+
+#[test]
+#[serial]
+fn test_rdb_backup() {
+    let port = 10023;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    let path = env::temp_dir().join(format!("redis-event-backup-{}.rdb", port));
+    let conf = Config {
+        is_discard_rdb: true,
+        is_aof: false,
+        host: String::from("127.0.0.1"),
+        port,
+        username: "".to_string(),
+        password: String::new(),
+        repl_id: String::from("?"),
+        repl_offset: -1,
+        read_timeout: None,
+        write_timeout: None,
+        is_tls_enabled: false,
+        is_tls_insecure: false,
+        identity: None,
+        identity_passwd: None,
+        master_timeout: None,
+        aof_queue_size: None,
+        is_ack_after_handle: false,
+        max_full_resyncs: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        is_aggregate_collections: false,
+        db_filter: None,
+        key_filter: None,
+        rdb_backup_path: Some(path.to_str().unwrap().to_string()),
+        aof_backup_path: None,
+        is_collect_stats: false,
+        max_handler_retries: None,
+    };
+    let running = Arc::new(AtomicBool::new(true));
+
+    let mut builder = listener::Builder::new();
+    builder.with_config(conf);
+    builder.with_control_flag(running);
+    let mut redis_listener = builder.build();
+    let result = redis_listener.start();
+    shutdown_redis(pid);
+    result.unwrap();
+
+    // 跳过了RDB的解析，但保存下来的文件是完整的
+    struct KeyCounter {
+        count: usize,
+    }
+
+    impl EventHandler for KeyCounter {
+        fn handle(&mut self, event: Event) {
+            if let Event::RDB(Object::Hash(_)) = event {
+                self.count += 1;
+            }
+        }
+    }
+
+    let mut handler = KeyCounter { count: 0 };
+    let info = rdb::parse_file(&path, &mut handler).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(ChecksumStatus::Valid, info.checksum);
+    assert!(handler.count > 0);
+}
+
+#[test]
+#[serial]
+fn test_events() {
+    let port = 10024;
+    let pid = start_redis_server("dictionary.rdb", port);
+    // wait redis to start
+    sleep(Duration::from_secs(2));
+
+    let conf = Config {
+        is_discard_rdb: false,
+        is_aof: false,
+        host: String::from("127.0.0.1"),
+        port,
+        username: "".to_string(),
+        password: String::new(),
+        repl_id: String::from("?"),
+        repl_offset: -1,
+        read_timeout: None,
+        write_timeout: None,
+        is_tls_enabled: false,
+        is_tls_insecure: false,
+        identity: None,
+        identity_passwd: None,
+        master_timeout: None,
+        aof_queue_size: None,
+        is_ack_after_handle: false,
+        max_full_resyncs: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        is_aggregate_collections: false,
+        db_filter: None,
+        key_filter: None,
+        rdb_backup_path: None,
+        aof_backup_path: None,
+        is_collect_stats: false,
+        max_handler_retries: None,
+    };
+    let events = listener::events(4, move |builder| builder.with_config(conf)).unwrap();
+    let mut keys = Vec::new();
+    let mut is_finished = false;
+    for event in events {
+        match event.unwrap() {
+            OwnedEvent::RDB(OwnedObject::Hash { key, fields, .. }) => {
+                assert!(fields.iter().all(|field| field.name.len() == 50));
+                keys.push(String::from_utf8(key).unwrap());
+            }
+            OwnedEvent::RDB(OwnedObject::EOR) => is_finished = true,
+            _ => {}
+        }
+    }
+    shutdown_redis(pid);
+    assert!(keys.iter().all(|key| key == "force_dictionary"));
+    assert!(!keys.is_empty());
+    assert!(is_finished);
+}
+
+fn start_redis_test(rdb: &str, port: u16, rdb_handler: Box<dyn EventHandler + Send>) {
     let pid = start_redis_server(rdb, port);
     // wait redis to start
     sleep(Duration::from_secs(2));
@@ -853,6 +1397,22 @@ fn start_redis_test(rdb: &str, port: u16, rdb_handler: Rc<RefCell<dyn EventHandl
         is_tls_insecure: false,
         identity: None,
         identity_passwd: None,
+        master_timeout: None,
+        aof_queue_size: None,
+        is_ack_after_handle: false,
+        max_full_resyncs: None,
+        parse_mode: ParseMode::Strict,
+        is_skip_corrupt_keys: false,
+        is_discard_expired_keys: false,
+        expire_reference_time: None,
+        string_chunk_size: None,
+        is_aggregate_collections: false,
+        db_filter: None,
+        key_filter: None,
+        rdb_backup_path: None,
+        aof_backup_path: None,
+        is_collect_stats: false,
+        max_handler_retries: None,
     };
     let running = Arc::new(AtomicBool::new(true));
 